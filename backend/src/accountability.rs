@@ -0,0 +1,261 @@
+use crate::activity::{ActivityEntry, ActivityFeed, ActivityKind};
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::incidents::IncidentLinkKind;
+use crate::members::MemberDirectory;
+use crate::proposal::{Proposal, ProposalManager};
+use crate::receipts::{ReceiptService, SignedReceipt, RECEIPT_ALGORITHM};
+use crate::rollback::RollbackEvent;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Domain separator for the signed report message; bump when the layout changes
+pub const INCIDENT_REPORT_VERSION: &str = "goquant-incident-report-v1";
+
+/// One member's approval of the rolled-back upgrade, with what they could
+/// have known when they gave it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalAccount {
+    pub member: String,
+    pub display_name: String,
+    pub approved_at: i64,
+    /// Seconds from the proposal to this approval
+    pub review_seconds: i64,
+    /// Audit and checklist entries recorded before the approval
+    pub evidence: Vec<ActivityEntry>,
+    /// The receipt the service signed when the member approved
+    pub receipt: Option<SignedReceipt>,
+}
+
+impl ApprovalAccount {
+    /// Evidence entries recording a failed check
+    pub fn failed_evidence(&self) -> impl Iterator<Item = &ActivityEntry> {
+        self.evidence
+            .iter()
+            .filter(|entry| entry.data["passed"] == serde_json::Value::Bool(false))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncidentTiming {
+    pub proposed_at: i64,
+    pub first_approval_at: Option<i64>,
+    pub last_approval_at: Option<i64>,
+    pub timelock_until: i64,
+    pub executed_at: Option<i64>,
+    pub rolled_back_at: i64,
+    /// How long the defective program was live
+    pub live_seconds: Option<i64>,
+}
+
+/// Who approved an upgrade that was later rolled back, on what evidence, and
+/// when, for governance review
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncidentReport {
+    pub version: String,
+    /// The incident, or the rollback event when it was requested directly
+    pub incident_id: String,
+    /// The `rollback_events` row
+    pub rollback_id: String,
+    pub proposal_id: String,
+    pub program: String,
+    pub buffer: String,
+    pub description: String,
+    pub rollback_reason: String,
+    pub approvals: Vec<ApprovalAccount>,
+    pub timing: IncidentTiming,
+    /// Approvals given without evidence or despite failed checks
+    pub findings: Vec<String>,
+    pub generated_at: i64,
+}
+
+impl IncidentReport {
+    /// Approvals come from the proposal's activity feed; receipts and
+    /// profiles are matched by member
+    pub fn build(
+        incident_id: &str,
+        rollback: &RollbackEvent,
+        proposal: &Proposal,
+        activity: &[ActivityEntry],
+        receipts: &[SignedReceipt],
+        directory: &MemberDirectory,
+        now: i64,
+    ) -> Self {
+        let evidence: Vec<&ActivityEntry> = activity
+            .iter()
+            .filter(|entry| matches!(entry.kind, ActivityKind::Audit | ActivityKind::Checklist))
+            .collect();
+
+        let approvals: Vec<ApprovalAccount> = activity
+            .iter()
+            .filter(|entry| entry.kind == ActivityKind::Approval)
+            .filter_map(|entry| {
+                let member = entry.actor.clone()?;
+                Some(ApprovalAccount {
+                    display_name: directory.display_name(&member),
+                    approved_at: entry.at,
+                    review_seconds: entry.at - proposal.proposed_at,
+                    evidence: evidence
+                        .iter()
+                        .filter(|evidence| evidence.at <= entry.at)
+                        .map(|evidence| (*evidence).clone())
+                        .collect(),
+                    receipt: receipts.iter().find(|r| r.receipt.member == member).cloned(),
+                    member,
+                })
+            })
+            .collect();
+
+        let mut findings = Vec::new();
+        for approval in &approvals {
+            if approval.evidence.is_empty() {
+                findings.push(format!(
+                    "{} approved after {}s with no audit or checklist evidence recorded",
+                    approval.display_name, approval.review_seconds
+                ));
+            }
+            for failed in approval.failed_evidence() {
+                findings.push(format!("{} approved despite: {}", approval.display_name, failed.summary));
+            }
+        }
+
+        let timing = IncidentTiming {
+            proposed_at: proposal.proposed_at,
+            first_approval_at: approvals.iter().map(|a| a.approved_at).min(),
+            last_approval_at: approvals.iter().map(|a| a.approved_at).max(),
+            timelock_until: proposal.timelock_until,
+            executed_at: proposal.executed_at,
+            rolled_back_at: rollback.rolled_back_at,
+            live_seconds: proposal.executed_at.map(|executed_at| rollback.rolled_back_at - executed_at),
+        };
+
+        Self {
+            version: INCIDENT_REPORT_VERSION.to_string(),
+            incident_id: incident_id.to_string(),
+            rollback_id: rollback.id.clone(),
+            proposal_id: proposal.id.clone(),
+            program: proposal.program.clone(),
+            buffer: proposal.new_buffer.clone(),
+            description: proposal.description.clone(),
+            rollback_reason: rollback.reason.clone(),
+            approvals,
+            timing,
+            findings,
+            generated_at: now,
+        }
+    }
+
+    /// Hex SHA-256 of the report's JSON
+    pub fn sha256(&self) -> String {
+        let json = serde_json::to_vec(self).expect("report serializes");
+        hex::encode(Sha256::digest(json))
+    }
+
+    /// Bytes covered by the signature: the version, incident and digest, one per line
+    pub fn message(&self) -> String {
+        [self.version.as_str(), self.incident_id.as_str(), &self.sha256()].join("\n")
+    }
+}
+
+/// An incident report with the service's signature over `message()`, using
+/// the approval receipt key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignedIncidentReport {
+    pub report: IncidentReport,
+    pub sha256: String,
+    pub algorithm: String,
+    /// Base58 signer pubkey
+    pub signer: String,
+    /// Base58 ed25519 signature
+    pub signature: String,
+}
+
+impl SignedIncidentReport {
+    pub fn new(report: IncidentReport, signer: &Pubkey, signature: &Signature) -> Self {
+        Self {
+            sha256: report.sha256(),
+            algorithm: RECEIPT_ALGORITHM.to_string(),
+            signer: signer.to_string(),
+            signature: signature.to_string(),
+            report,
+        }
+    }
+
+    /// Checks the signature against the report contents, without trusting `sha256`
+    pub fn verify(&self) -> bool {
+        let Ok(signer) = Pubkey::from_str(&self.signer) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_str(&self.signature) else {
+            return false;
+        };
+        self.report.sha256() == self.sha256 && signature.verify(signer.as_ref(), self.report.message().as_bytes())
+    }
+}
+
+/// Compiles signed incident packages for upgrades that were rolled back
+pub struct AccountabilityReporter {
+    database: Arc<Database>,
+    proposals: Arc<ProposalManager>,
+    activity: Arc<ActivityFeed>,
+    receipts: Arc<ReceiptService>,
+}
+
+impl AccountabilityReporter {
+    pub fn new(
+        database: Arc<Database>,
+        proposals: Arc<ProposalManager>,
+        activity: Arc<ActivityFeed>,
+        receipts: Arc<ReceiptService>,
+    ) -> Self {
+        Self {
+            database,
+            proposals,
+            activity,
+            receipts,
+        }
+    }
+
+    /// `incident_id` is an incident with a linked rollback, or a rollback
+    /// event id
+    pub async fn report(&self, incident_id: &str) -> Result<SignedIncidentReport, UpgradeError> {
+        let rollback_id = match self.database.get_incident(incident_id).await? {
+            Some(incident) => incident
+                .linked(IncidentLinkKind::Rollback)
+                .last()
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    UpgradeError::InvalidRequest(format!("Incident {} has no recorded rollback", incident_id))
+                })?,
+            None => incident_id.to_string(),
+        };
+        let rollback = self.database
+            .get_rollback_event(&rollback_id)
+            .await?
+            .ok_or_else(|| UpgradeError::IncidentNotFound(incident_id.to_string()))?;
+        let proposal = self.proposals.get_proposal(&rollback.proposal_id).await?;
+
+        let activity = self.activity.feed(&proposal, &[]).await?;
+        let receipts = self.database.list_approval_receipts(&proposal.id).await?;
+        let directory = MemberDirectory::new(self.database.list_member_profiles().await?);
+
+        let report = IncidentReport::build(
+            incident_id,
+            &rollback,
+            &proposal,
+            &activity,
+            &receipts,
+            &directory,
+            chrono::Utc::now().timestamp(),
+        );
+        let signature = self.receipts.sign_message(report.message().as_bytes());
+
+        tracing::info!("Compiled incident report {} for proposal {}", incident_id, proposal.id);
+
+        Ok(SignedIncidentReport::new(report, &self.receipts.signer_pubkey(), &signature))
+    }
+}
@@ -0,0 +1,188 @@
+use crate::database::Database;
+use crate::drafts::{ProposalDraft, ReviewVerdict};
+use crate::error::UpgradeError;
+use crate::multisig::MultisigCoordinator;
+use crate::proposal::Proposal;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+/// What an activity entry is about
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// Created or executed
+    Proposal,
+    /// Events the upgrade-manager program emitted for the proposal account
+    Onchain,
+    Approval,
+    /// A member withdrew their approval
+    Revocation,
+    /// Reviews of the draft the proposal was submitted from
+    Comment,
+    /// Invariant checks, fork tests, freezes and post-upgrade watch findings
+    Audit,
+    Checklist,
+    /// Execution requests, queue attempts and submitted transactions
+    Execution,
+    /// The upgrade was rolled back; `data.incident_id` identifies it
+    Rollback,
+    Cancellation,
+    /// WebSocket notifications and approval reminders sent
+    Notification,
+}
+
+impl ActivityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::Proposal => "proposal",
+            ActivityKind::Onchain => "onchain",
+            ActivityKind::Approval => "approval",
+            ActivityKind::Revocation => "revocation",
+            ActivityKind::Comment => "comment",
+            ActivityKind::Audit => "audit",
+            ActivityKind::Checklist => "checklist",
+            ActivityKind::Execution => "execution",
+            ActivityKind::Rollback => "rollback",
+            ActivityKind::Cancellation => "cancellation",
+            ActivityKind::Notification => "notification",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "proposal" => Some(ActivityKind::Proposal),
+            "onchain" => Some(ActivityKind::Onchain),
+            "approval" => Some(ActivityKind::Approval),
+            "revocation" => Some(ActivityKind::Revocation),
+            "comment" => Some(ActivityKind::Comment),
+            "audit" => Some(ActivityKind::Audit),
+            "checklist" => Some(ActivityKind::Checklist),
+            "execution" => Some(ActivityKind::Execution),
+            "rollback" => Some(ActivityKind::Rollback),
+            "cancellation" => Some(ActivityKind::Cancellation),
+            "notification" => Some(ActivityKind::Notification),
+            _ => None,
+        }
+    }
+
+    /// A comma-separated `kind` filter, e.g. `approval,audit`
+    pub fn parse_list(value: &str) -> Result<Vec<Self>, UpgradeError> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| {
+                Self::parse(kind).ok_or_else(|| UpgradeError::InvalidRequest(format!("Unknown activity kind '{}'", kind)))
+            })
+            .collect()
+    }
+}
+
+/// One thing that happened to a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActivityEntry {
+    pub at: i64,
+    pub kind: ActivityKind,
+    /// Who did it, when a person or member did
+    pub actor: Option<String>,
+    pub summary: String,
+    /// The source row's details
+    pub data: serde_json::Value,
+}
+
+/// Creation and execution, from the proposal itself
+pub fn proposal_entries(proposal: &Proposal) -> Vec<ActivityEntry> {
+    let mut entries = vec![ActivityEntry {
+        at: proposal.proposed_at,
+        kind: ActivityKind::Proposal,
+        actor: Some(proposal.proposer.clone()),
+        summary: format!("Proposed: {}", proposal.description),
+        data: serde_json::json!({
+            "program": proposal.program,
+            "buffer": proposal.new_buffer,
+            "timelock_until": proposal.timelock_until,
+        }),
+    }];
+    if let Some(executed_at) = proposal.executed_at {
+        entries.push(ActivityEntry {
+            at: executed_at,
+            kind: ActivityKind::Proposal,
+            actor: None,
+            summary: "Executed".to_string(),
+            data: serde_json::json!({}),
+        });
+    }
+    entries
+}
+
+/// Reviewer verdicts and comments on the draft the proposal came from
+pub fn draft_comments(draft: &ProposalDraft) -> Vec<ActivityEntry> {
+    draft
+        .reviews
+        .iter()
+        .map(|review| {
+            let verdict = match review.verdict {
+                ReviewVerdict::Approve => "Approved",
+                ReviewVerdict::RequestChanges => "Requested changes to",
+            };
+            let summary = match &review.comment {
+                Some(comment) => format!("{} revision {}: {}", verdict, review.revision, comment),
+                None => format!("{} revision {}", verdict, review.revision),
+            };
+            ActivityEntry {
+                at: review.reviewed_at,
+                kind: ActivityKind::Comment,
+                actor: Some(review.reviewer.clone()),
+                summary,
+                data: serde_json::json!({
+                    "draft_id": draft.id,
+                    "revision": review.revision,
+                    "verdict": review.verdict,
+                    "comment": review.comment,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Oldest first, keeping the relative order of entries at the same second.
+/// An empty `kinds` keeps every kind.
+pub fn merge(mut entries: Vec<ActivityEntry>, kinds: &[ActivityKind]) -> Vec<ActivityEntry> {
+    entries.retain(|entry| kinds.is_empty() || kinds.contains(&entry.kind));
+    entries.sort_by_key(|entry| entry.at);
+    entries
+}
+
+/// Assembles a proposal's timeline from the tables each part of the service
+/// records its work in
+pub struct ActivityFeed {
+    database: Arc<Database>,
+    multisig: Arc<MultisigCoordinator>,
+}
+
+impl ActivityFeed {
+    pub fn new(database: Arc<Database>, multisig: Arc<MultisigCoordinator>) -> Self {
+        Self { database, multisig }
+    }
+
+    pub async fn feed(&self, proposal: &Proposal, kinds: &[ActivityKind]) -> Result<Vec<ActivityEntry>, UpgradeError> {
+        // On-chain events are indexed by the proposal PDA
+        let onchain_proposal = match (proposal.program.parse::<Pubkey>(), proposal.new_buffer.parse::<Pubkey>()) {
+            (Ok(program), Ok(buffer)) => Some(self.multisig.proposal_address(&program, &buffer).to_string()),
+            _ => None,
+        };
+
+        let mut entries = proposal_entries(proposal);
+        entries.extend(
+            self.database
+                .list_proposal_activity(&proposal.id, onchain_proposal.as_deref())
+                .await?,
+        );
+        if let Some(draft) = self.database.get_draft_for_proposal(&proposal.id).await? {
+            entries.extend(draft_comments(&draft));
+        }
+
+        Ok(merge(entries, kinds))
+    }
+}
@@ -0,0 +1,209 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::proposal::{Proposal, RiskTier};
+use crate::templates::Channel;
+use crate::websocket::{Notification, NotificationService, NotificationType};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Public notice that an upgrade is scheduled, published once a proposal
+/// has its approvals and the timelock starts counting down
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Announcement {
+    pub proposal_id: String,
+    pub program: String,
+    pub title: String,
+    /// Earliest execution time, when the timelock expires
+    pub window_start: i64,
+    /// Operators commit to executing before this
+    pub window_end: i64,
+    pub affected_markets: Vec<String>,
+    /// Average duration of recent account migrations, when there are any
+    pub estimated_migration_seconds: Option<i64>,
+    pub risk_tier: Option<RiskTier>,
+    pub published_at: i64,
+}
+
+impl Announcement {
+    pub fn build(
+        proposal: &Proposal,
+        config: &AnnouncementConfig,
+        estimated_migration_seconds: Option<i64>,
+        now: i64,
+    ) -> Self {
+        let version = proposal
+            .source
+            .as_ref()
+            .map(|source| source.tag.clone())
+            .unwrap_or_else(|| proposal.id.clone());
+
+        Self {
+            proposal_id: proposal.id.clone(),
+            program: proposal.program.clone(),
+            title: format!("Scheduled upgrade {}", version),
+            window_start: proposal.timelock_until,
+            window_end: proposal.timelock_until + config.execution_window_seconds,
+            affected_markets: config.affected_markets.clone(),
+            estimated_migration_seconds,
+            risk_tier: proposal.risk_tier,
+            published_at: now,
+        }
+    }
+
+    /// Template variables for the `upgrade_announced` notification
+    pub fn template_data(&self) -> serde_json::Value {
+        serde_json::json!({
+            "title": self.title,
+            "program": self.program,
+            "window_start": self.window_start,
+            "window_end": self.window_end,
+            "window_start_utc": format_utc(self.window_start),
+            "window_end_utc": format_utc(self.window_end),
+            "affected_markets": if self.affected_markets.is_empty() {
+                "none listed".to_string()
+            } else {
+                self.affected_markets.join(", ")
+            },
+            "migration_estimate": match self.estimated_migration_seconds {
+                Some(seconds) => format_duration(seconds),
+                None => "no account migration expected".to_string(),
+            },
+            "risk_tier": self.risk_tier.map(|tier| tier.as_str()).unwrap_or("unassessed"),
+        })
+    }
+}
+
+pub(crate) fn format_utc(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// `1h 05m`, `12m`, `45s`
+pub fn format_duration(seconds: i64) -> String {
+    let (hours, minutes) = (seconds / 3600, (seconds % 3600) / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {:02}m", hours, minutes),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnnouncementConfig {
+    /// `ANNOUNCEMENT_AFFECTED_MARKETS`, comma separated
+    pub affected_markets: Vec<String>,
+    /// `ANNOUNCEMENT_EXECUTION_WINDOW_SECS` after the timelock (default 3600)
+    pub execution_window_seconds: i64,
+    /// `ANNOUNCEMENT_WEBHOOK_URLS`: Slack-compatible incoming webhooks, comma separated
+    pub webhook_urls: Vec<String>,
+}
+
+impl AnnouncementConfig {
+    pub fn from_env() -> Self {
+        let list = |key: &str| {
+            std::env::var(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|item| item.trim().to_string())
+                        .filter(|item| !item.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Self {
+            affected_markets: list("ANNOUNCEMENT_AFFECTED_MARKETS"),
+            execution_window_seconds: std::env::var("ANNOUNCEMENT_EXECUTION_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            webhook_urls: list("ANNOUNCEMENT_WEBHOOK_URLS"),
+        }
+    }
+}
+
+/// Publishes upgrade announcements to the notification stream, configured
+/// webhooks and `GET /public/announcements`. Each proposal is announced once.
+pub struct AnnouncementService {
+    config: AnnouncementConfig,
+    database: Arc<Database>,
+    notifications: Arc<NotificationService>,
+    http: reqwest::Client,
+}
+
+impl AnnouncementService {
+    pub fn from_env(database: Arc<Database>, notifications: Arc<NotificationService>) -> Self {
+        Self {
+            config: AnnouncementConfig::from_env(),
+            database,
+            notifications,
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Announce `proposal` unless it already was. Webhook failures are logged
+    /// and do not fail the announcement.
+    pub async fn announce(&self, proposal: &Proposal) -> Result<Option<Announcement>, UpgradeError> {
+        let estimate = self.database.average_migration_seconds().await?;
+        let announcement = Announcement::build(proposal, &self.config, estimate, chrono::Utc::now().timestamp());
+
+        if !self.database.insert_announcement(&announcement).await? {
+            return Ok(None);
+        }
+
+        let data = announcement.template_data();
+        self.notifications
+            .notify(Notification {
+                notification_type: NotificationType::UpgradeAnnounced,
+                proposal_id: Some(announcement.proposal_id.clone()),
+                message: announcement.title.clone(),
+                data: data.clone(),
+            })
+            .await;
+
+        let text = self
+            .notifications
+            .templates()
+            .render(
+                "upgrade_announced",
+                Channel::Slack,
+                None,
+                Some(&announcement.proposal_id),
+                &data,
+            )
+            .unwrap_or_else(|| announcement.title.clone());
+        for url in &self.config.webhook_urls {
+            let result = self
+                .http
+                .post(url)
+                .json(&serde_json::json!({ "text": text }))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                tracing::warn!("Failed to post announcement for {} to webhook: {}", announcement.proposal_id, e);
+            }
+        }
+
+        tracing::info!(
+            "Announced upgrade {} for {} to {}",
+            announcement.proposal_id,
+            announcement.window_start,
+            announcement.window_end
+        );
+
+        Ok(Some(announcement))
+    }
+
+    /// Announcements whose execution window has not closed, soonest first
+    pub async fn upcoming(&self) -> Result<Vec<Announcement>, UpgradeError> {
+        self.database
+            .list_announcements(chrono::Utc::now().timestamp())
+            .await
+    }
+}
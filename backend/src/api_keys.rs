@@ -0,0 +1,307 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::secrets::SecretStore;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Every generated key starts with this, so leaked keys are easy to grep for
+pub const KEY_PREFIX: &str = "gq_";
+
+/// Leading characters of a key stored in clear to tell keys apart
+const DISPLAY_PREFIX_LEN: usize = 11;
+
+/// What an API key may do. `Admin` implies every other scope.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    Propose,
+    Approve,
+    Execute,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Propose => "propose",
+            Scope::Approve => "approve",
+            Scope::Execute => "execute",
+            Scope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(Scope::Read),
+            "propose" => Some(Scope::Propose),
+            "approve" => Some(Scope::Approve),
+            "execute" => Some(Scope::Execute),
+            "admin" => Some(Scope::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// An API key as listed by `GET /api-keys`; the key itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key, e.g. `gq_3f9a0c1b`
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_by: Option<String>,
+    pub created_at: i64,
+    pub rotated_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+/// Request body for `POST /api-keys`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl NewApiKey {
+    /// Validate the request and build the key record for `key`
+    pub fn into_key(self, key: &str, created_by: Option<String>, now: i64) -> Result<ApiKey, UpgradeError> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(UpgradeError::InvalidRequest("name must not be empty".to_string()));
+        }
+        if self.scopes.is_empty() {
+            return Err(UpgradeError::InvalidRequest("at least one scope is required".to_string()));
+        }
+
+        let mut scopes = self.scopes;
+        scopes.sort();
+        scopes.dedup();
+
+        Ok(ApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            prefix: display_prefix(key),
+            scopes,
+            created_by,
+            created_at: now,
+            rotated_at: None,
+            last_used_at: None,
+            revoked_at: None,
+        })
+    }
+}
+
+/// The caller a request was authenticated as, available to handlers as an
+/// `Extension<ApiPrincipal>`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ApiPrincipal {
+    pub key_id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl ApiPrincipal {
+    fn bootstrap() -> Self {
+        Self {
+            key_id: "bootstrap".to_string(),
+            name: "bootstrap".to_string(),
+            scopes: vec![Scope::Admin],
+        }
+    }
+
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+/// A new random key: the prefix followed by 64 hex characters
+pub fn generate_key() -> String {
+    format!(
+        "{}{}{}",
+        KEY_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Hex SHA-256 of a key, the only form keys are stored in. Keys are random
+/// enough that a plain digest cannot be brute forced.
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+fn display_prefix(key: &str) -> String {
+    key.chars().take(DISPLAY_PREFIX_LEN).collect()
+}
+
+/// Key presented with a request, from `Authorization: Bearer` or `X-API-Key`
+pub fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Scope a request needs, or `None` for routes that stay open: the public
+/// explorer and the GitHub webhook, which is authenticated by its signature.
+/// `path` is relative to the API root, without the `/v1` prefix. Writes are
+/// mapped route by route; one missing here needs `admin` until it is added.
+pub fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        _ if method == Method::OPTIONS => None,
+        ["public", ..] => None,
+        ["integrations", "github", "release"] => None,
+        ["api-keys", ..] | ["history", "import"] => Some(Scope::Admin),
+        _ if method == Method::GET || method == Method::HEAD => Some(Scope::Read),
+        ["multisig", "members", ..] | ["security", "denylist", ..] => Some(Scope::Admin),
+        ["notifications", "templates", "preview"]
+        | ["upgrade", _, "sandbox", "run"]
+        | ["upgrade", _, "fork-test"] => Some(Scope::Read),
+        ["upgrade", "propose" | "propose-binary" | "deploy" | "authority"]
+        | ["upgrade", "draft", ..]
+        | ["upgrade", _, "submit"]
+        | ["programs", _, "flags", _, "propose"]
+        | ["buffers", _, "propose"]
+        | ["monitoring", "watch-expressions", ..] => Some(Scope::Propose),
+        ["upgrade", "approve-batch"]
+        | ["upgrade", _, "approve" | "revoke" | "cancel" | "freeze" | "unfreeze"]
+        | ["upgrade", _, "checklist", _]
+        | ["upgrade", _, "reminders", _]
+        | ["programs", _, "flags", _, "kill"]
+        | ["programs", _, "emergency-pause"]
+        | ["buffers", _, "dismiss"]
+        | ["incidents", ..] => Some(Scope::Approve),
+        ["upgrade", _, "execute", ..]
+        | ["upgrade", _, "execute-tx", ..]
+        | ["programs", _, "flag-proposals", _, "apply"]
+        | ["migration", ..]
+        | ["rollback", "drill"]
+        | ["snapshots"] => Some(Scope::Execute),
+        _ => Some(Scope::Admin),
+    }
+}
+
+/// Issues, rotates and checks per-user API keys. The `API_BOOTSTRAP_KEY`
+/// secret is accepted as an admin key so the first keys can be created.
+pub struct ApiKeyService {
+    database: Arc<Database>,
+    secrets: Arc<SecretStore>,
+    /// `API_KEY_ROTATION_GRACE_SECS`: how long a rotated-out key keeps working (default 3600)
+    rotation_grace_seconds: i64,
+}
+
+impl ApiKeyService {
+    pub fn from_env(database: Arc<Database>, secrets: Arc<SecretStore>) -> Self {
+        Self {
+            database,
+            secrets,
+            rotation_grace_seconds: std::env::var("API_KEY_ROTATION_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+        }
+    }
+
+    /// Resolve `key` and check it holds `scope`, recording when it was last used
+    pub async fn authenticate(&self, key: Option<&str>, scope: Scope) -> Result<ApiPrincipal, UpgradeError> {
+        let key = key.ok_or_else(|| UpgradeError::Unauthorized("API key required".to_string()))?;
+
+        let bootstrap = self.secrets.get("API_BOOTSTRAP_KEY").unwrap_or_default();
+        let principal = if !bootstrap.is_empty() && hash_key(&bootstrap) == hash_key(key) {
+            ApiPrincipal::bootstrap()
+        } else {
+            self.database
+                .touch_api_key(&hash_key(key))
+                .await?
+                .ok_or_else(|| UpgradeError::Unauthorized("Invalid or revoked API key".to_string()))?
+        };
+
+        if !principal.allows(scope) {
+            return Err(UpgradeError::Forbidden(format!(
+                "API key '{}' lacks the {} scope",
+                principal.name,
+                scope.as_str()
+            )));
+        }
+
+        Ok(principal)
+    }
+
+    /// Create a key. Returns the record and the key, which is not shown again.
+    pub async fn create(&self, request: NewApiKey, created_by: &ApiPrincipal) -> Result<(ApiKey, String), UpgradeError> {
+        let key = generate_key();
+        let record = request.into_key(&key, Some(created_by.name.clone()), chrono::Utc::now().timestamp())?;
+        self.database.insert_api_key(&record, &hash_key(&key)).await?;
+        tracing::info!(
+            "API key {} ({}) created by {} with scopes {:?}",
+            record.id,
+            record.name,
+            created_by.name,
+            record.scopes
+        );
+
+        Ok((record, key))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ApiKey>, UpgradeError> {
+        self.database.list_api_keys().await
+    }
+
+    /// Replace a key's secret. The old key keeps working for the grace period
+    /// so clients can be switched over; revoke instead if it leaked.
+    pub async fn rotate(&self, id: &str) -> Result<(ApiKey, String), UpgradeError> {
+        let key = generate_key();
+        let grace_until = chrono::Utc::now().timestamp() + self.rotation_grace_seconds;
+        let record = self
+            .database
+            .rotate_api_key(id, &hash_key(&key), &display_prefix(&key), grace_until)
+            .await?
+            .ok_or_else(|| UpgradeError::ApiKeyNotFound(id.to_string()))?;
+        tracing::info!("API key {} ({}) rotated", record.id, record.name);
+
+        Ok((record, key))
+    }
+
+    /// Revoke a key, and its rotated-out predecessor, immediately
+    pub async fn revoke(&self, id: &str) -> Result<(), UpgradeError> {
+        if !self.database.revoke_api_key(id).await? {
+            return Err(UpgradeError::ApiKeyNotFound(id.to_string()));
+        }
+        tracing::warn!("API key {} revoked", id);
+
+        Ok(())
+    }
+}
+
+/// Rejects requests without a key holding the scope their route needs and
+/// passes the authenticated [`ApiPrincipal`] on to the handler
+pub async fn require_api_key(
+    State(keys): State<Arc<ApiKeyService>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(scope) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    match keys.authenticate(presented_key(request.headers()), scope).await {
+        Ok(principal) => {
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(e) => e.into_response(),
+    }
+}
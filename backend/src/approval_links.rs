@@ -0,0 +1,288 @@
+use crate::error::UpgradeError;
+use crate::execute_tx::encode_transaction;
+use crate::multisig::MultisigCoordinator;
+use crate::proposal::{ProposalEvent, ProposalManager};
+use crate::secrets::SecretStore;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Characters left as they are when percent-encoding (RFC 3986 unreserved)
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| {
+            if is_unreserved(byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+/// HMAC binding a link to one proposal, member and expiry, so the public
+/// transaction-request endpoint only serves links this service issued
+pub fn link_token(secret: &str, proposal_id: &str, member: &str, expires_at: i64) -> String {
+    hex::encode(link_mac(secret, proposal_id, member, expires_at).finalize().into_bytes())
+}
+
+/// Check `token` in constant time
+pub fn verify_link_token(secret: &str, proposal_id: &str, member: &str, expires_at: i64, token: &str) -> bool {
+    hex::decode(token).is_ok_and(|token| {
+        link_mac(secret, proposal_id, member, expires_at)
+            .verify_slice(&token)
+            .is_ok()
+    })
+}
+
+fn link_mac(secret: &str, proposal_id: &str, member: &str, expires_at: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}:{}:{}", proposal_id, member, expires_at).as_bytes());
+    mac
+}
+
+/// Solana Pay transaction request: `solana:` followed by the URL-encoded
+/// HTTPS endpoint the wallet fetches the transaction from
+pub fn solana_pay_url(base_url: &str, proposal_id: &str, member: &str, expires_at: i64, token: &str) -> String {
+    let endpoint = format!(
+        "{}/v1/public/approve-link/{}?member={}&expires={}&token={}",
+        base_url.trim_end_matches('/'),
+        proposal_id,
+        member,
+        expires_at,
+        token
+    );
+    format!("solana:{}", percent_encode(&endpoint))
+}
+
+/// Query string of the transaction-request URL
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinkQuery {
+    pub member: String,
+    pub expires: i64,
+    pub token: String,
+}
+
+/// Body a wallet posts to a transaction request
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionRequest {
+    pub account: String,
+}
+
+/// Returned by `GET /upgrade/:id/approve-link`
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalLink {
+    pub proposal_id: String,
+    pub member: String,
+    /// `solana:` transaction request to render as a QR code; `None` unless
+    /// `APPROVAL_LINK_BASE_URL` is set
+    pub link: Option<String>,
+    pub expires_at: i64,
+    /// The approval transaction as of now, for wallets that import one directly
+    pub transaction: ApprovalTransaction,
+}
+
+/// Unsigned approval transaction with the member as fee payer and only signer
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalTransaction {
+    /// Base64 wire-format transaction
+    pub transaction: String,
+    /// Shown by the wallet next to the signing prompt
+    pub message: String,
+    pub last_valid_block_height: u64,
+}
+
+/// Label and icon a wallet shows before fetching the transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkLabel {
+    pub label: String,
+    pub icon: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalLinkConfig {
+    /// `APPROVAL_LINK_BASE_URL`: public HTTPS origin of this service
+    pub base_url: Option<String>,
+    /// `APPROVAL_LINK_TTL_SECS` a link stays valid (default 900)
+    pub ttl_seconds: i64,
+    /// `APPROVAL_LINK_ICON_URL` shown by the wallet
+    pub icon_url: String,
+}
+
+impl ApprovalLinkConfig {
+    pub fn from_env() -> Self {
+        let base_url = std::env::var("APPROVAL_LINK_BASE_URL")
+            .ok()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+
+        Self {
+            icon_url: std::env::var("APPROVAL_LINK_ICON_URL").unwrap_or_else(|_| {
+                format!("{}/favicon.ico", base_url.as_deref().unwrap_or_default())
+            }),
+            base_url,
+            ttl_seconds: std::env::var("APPROVAL_LINK_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+        }
+    }
+}
+
+/// Builds approval transactions members can sign from a mobile wallet. The
+/// link is a Solana Pay transaction request, so the wallet fetches a fresh
+/// transaction when it is scanned rather than one whose blockhash expired.
+/// Links are signed with the `APPROVAL_LINK_SECRET` secret.
+pub struct ApprovalLinkService {
+    config: ApprovalLinkConfig,
+    multisig: Arc<MultisigCoordinator>,
+    proposal_manager: Arc<ProposalManager>,
+    secrets: Arc<SecretStore>,
+    rpc_client: Arc<AsyncRpcClient>,
+}
+
+impl ApprovalLinkService {
+    pub fn from_env(
+        multisig: Arc<MultisigCoordinator>,
+        proposal_manager: Arc<ProposalManager>,
+        secrets: Arc<SecretStore>,
+    ) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+
+        Self {
+            config: ApprovalLinkConfig::from_env(),
+            multisig,
+            proposal_manager,
+            secrets,
+            rpc_client: Arc::new(AsyncRpcClient::new(rpc_url)),
+        }
+    }
+
+    fn secret(&self) -> Option<String> {
+        self.secrets.get("APPROVAL_LINK_SECRET").filter(|secret| !secret.is_empty())
+    }
+
+    /// Link and current transaction for `member` to approve `proposal_id`
+    pub async fn link(&self, proposal_id: &str, member: &str) -> Result<ApprovalLink, UpgradeError> {
+        let member = Pubkey::from_str(member).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let transaction = self.transaction(proposal_id, &member).await?;
+
+        let expires_at = chrono::Utc::now().timestamp() + self.config.ttl_seconds;
+        let link = match (&self.config.base_url, self.secret()) {
+            (Some(base_url), Some(secret)) => {
+                let token = link_token(&secret, proposal_id, &member.to_string(), expires_at);
+                Some(solana_pay_url(base_url, proposal_id, &member.to_string(), expires_at, &token))
+            }
+            _ => None,
+        };
+
+        Ok(ApprovalLink {
+            proposal_id: proposal_id.to_string(),
+            member: member.to_string(),
+            link,
+            expires_at,
+            transaction,
+        })
+    }
+
+    pub fn label(&self, proposal_id: &str) -> LinkLabel {
+        LinkLabel {
+            label: format!("Approve upgrade {}", proposal_id),
+            icon: self.config.icon_url.clone(),
+        }
+    }
+
+    /// Answer a wallet's transaction request, after checking the link is
+    /// genuine, unexpired and being used by the member it was issued to
+    pub async fn request(
+        &self,
+        proposal_id: &str,
+        query: &LinkQuery,
+        request: &TransactionRequest,
+    ) -> Result<ApprovalTransaction, UpgradeError> {
+        let secret = self
+            .secret()
+            .ok_or_else(|| UpgradeError::Forbidden("Approval links are disabled".to_string()))?;
+        if !verify_link_token(&secret, proposal_id, &query.member, query.expires, &query.token) {
+            return Err(UpgradeError::Unauthorized("Invalid approval link".to_string()));
+        }
+        if chrono::Utc::now().timestamp() > query.expires {
+            return Err(UpgradeError::Unauthorized("Approval link expired".to_string()));
+        }
+        if request.account != query.member {
+            return Err(UpgradeError::Forbidden(format!(
+                "This link is for {}, not {}",
+                query.member, request.account
+            )));
+        }
+
+        let member = Pubkey::from_str(&query.member).map_err(|_| UpgradeError::InvalidPubkey)?;
+        self.transaction(proposal_id, &member).await
+    }
+
+    /// The upgrade-manager approval of `proposal_id` by `member`, committing
+    /// to the digest stored on chain once it has been checked
+    async fn transaction(&self, proposal_id: &str, member: &Pubkey) -> Result<ApprovalTransaction, UpgradeError> {
+        let proposal = self.proposal_manager.get_proposal(proposal_id).await?;
+        proposal.status.transition(ProposalEvent::Approve)?;
+
+        let program = Pubkey::from_str(&proposal.program).map_err(|_| {
+            UpgradeError::InvalidRequest(format!("Proposal program '{}' is not an address", proposal.program))
+        })?;
+        let buffer = Pubkey::from_str(&proposal.new_buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        let config = self.multisig.fetch_onchain_config().await?;
+        if !config.members.contains(member) {
+            return Err(UpgradeError::NotMultisigMember);
+        }
+
+        let commitment = self.multisig.fetch_proposal_commitment(&program, &buffer).await?;
+        if commitment.approvals.contains(&member.to_string()) {
+            return Err(UpgradeError::InvalidRequest(format!("{} already approved", member)));
+        }
+        let timelock_duration = self.multisig.fetch_timelock_duration().await?;
+        if !commitment.verify(timelock_duration) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "On-chain approval digest for {} does not match the proposal",
+                proposal_id
+            )));
+        }
+        let digest: [u8; 32] = hex::decode(&commitment.approval_digest)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| UpgradeError::MultisigError("Invalid approval digest".to_string()))?;
+
+        let instruction = self.multisig.build_approve_instruction(member, &program, &buffer, &digest);
+        let (blockhash, last_valid_block_height) = self
+            .rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get blockhash: {}", e)))?;
+        let mut transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(member)));
+        transaction.message.recent_blockhash = blockhash;
+
+        Ok(ApprovalTransaction {
+            transaction: encode_transaction(&transaction)?,
+            message: format!(
+                "Approve upgrade of {} to buffer {} (digest {})",
+                program,
+                buffer,
+                &commitment.approval_digest[..16]
+            ),
+            last_valid_block_height,
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::security::SecurityAuditor;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+/// Metadata for a stored program binary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub program_hash: String,
+    pub program_name: String,
+    pub size_bytes: i64,
+    pub source_commit: Option<String>,
+    pub toolchain: serde_json::Value,
+    pub storage_key: String,
+    pub created_at: i64,
+}
+
+/// Blob storage for artifacts (local disk by default; S3/GCS in production)
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), UpgradeError>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, UpgradeError>;
+}
+
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    pub fn new(root: PathBuf) -> Result<Self, UpgradeError> {
+        std::fs::create_dir_all(&root)
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to create artifact dir: {}", e)))?;
+        Ok(Self { root })
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<(), UpgradeError> {
+        tokio::fs::write(self.root.join(key), data)
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to store artifact: {}", e)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, UpgradeError> {
+        tokio::fs::read(self.root.join(key))
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to read artifact: {}", e)))
+    }
+}
+
+pub struct ArtifactRegistry {
+    database: Arc<Database>,
+    store: Arc<dyn ObjectStore>,
+}
+
+impl ArtifactRegistry {
+    pub fn new(database: Arc<Database>) -> Result<Self, UpgradeError> {
+        let root = std::env::var("ARTIFACT_STORE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir().join("goquant_artifacts"));
+
+        Ok(Self {
+            database,
+            store: Arc::new(LocalObjectStore::new(root)?),
+        })
+    }
+
+    /// Store a built binary and its provenance. Registering the same binary twice is a no-op.
+    pub async fn register(
+        &self,
+        binary: &[u8],
+        program_name: &str,
+        source_commit: Option<&str>,
+    ) -> Result<Artifact, UpgradeError> {
+        let program_hash = hex::encode(SecurityAuditor::calculate_program_hash(binary));
+        if let Some(existing) = self.database.get_artifact(&program_hash).await? {
+            return Ok(existing);
+        }
+
+        let storage_key = format!("{}.so", program_hash);
+        self.store.put(&storage_key, binary).await?;
+
+        let toolchain = Self::detect_toolchain();
+        self.database
+            .save_artifact(
+                &program_hash,
+                program_name,
+                binary.len() as i64,
+                source_commit,
+                &toolchain,
+                &storage_key,
+            )
+            .await?;
+
+        tracing::info!("Registered artifact {} ({} bytes)", program_hash, binary.len());
+
+        self.database
+            .get_artifact(&program_hash)
+            .await?
+            .ok_or_else(|| UpgradeError::InternalError("Artifact missing after save".to_string()))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Artifact>, UpgradeError> {
+        self.database.list_artifacts().await
+    }
+
+    /// Fetch the binary, re-verifying it still matches its recorded hash
+    pub async fn download(&self, program_hash: &str) -> Result<(Artifact, Vec<u8>), UpgradeError> {
+        let artifact = self.database
+            .get_artifact(program_hash)
+            .await?
+            .ok_or_else(|| UpgradeError::ArtifactNotFound(program_hash.to_string()))?;
+
+        let binary = self.store.get(&artifact.storage_key).await?;
+        let actual = hex::encode(SecurityAuditor::calculate_program_hash(&binary));
+        if actual != artifact.program_hash {
+            return Err(UpgradeError::InternalError(format!(
+                "Stored artifact {} is corrupted (hash {})",
+                program_hash, actual
+            )));
+        }
+
+        Ok((artifact, binary))
+    }
+
+    fn detect_toolchain() -> serde_json::Value {
+        let version = |cmd: &str| {
+            Command::new(cmd)
+                .arg("--version")
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        };
+
+        serde_json::json!({
+            "rustc": version("rustc"),
+            "solana": version("solana"),
+            "anchor": version("anchor"),
+        })
+    }
+}
@@ -0,0 +1,299 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::loader_upgradeable_instruction::UpgradeableLoaderInstruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiInstruction, UiLoadedAddresses, UiTransactionEncoding,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Most signatures one import scans or fetches
+pub const MAX_BACKFILL_SIGNATURES: usize = 5_000;
+
+/// Page size of `getSignaturesForAddress`
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// A BPF upgradeable loader `Upgrade` instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoaderUpgrade {
+    pub program: Pubkey,
+    pub buffer: Pubkey,
+    pub authority: Pubkey,
+}
+
+/// Find loader `Upgrade` instructions, whether called directly or through a
+/// multisig's CPI. Accounts follow the loader's layout: programdata, program,
+/// buffer, spill, rent, clock, authority.
+pub fn find_upgrades(account_keys: &[Pubkey], instructions: &[CompiledInstruction]) -> Vec<LoaderUpgrade> {
+    let key = |instruction: &CompiledInstruction, position: usize| {
+        instruction
+            .accounts
+            .get(position)
+            .and_then(|index| account_keys.get(*index as usize))
+            .copied()
+    };
+
+    instructions
+        .iter()
+        .filter(|instruction| {
+            account_keys.get(instruction.program_id_index as usize) == Some(&bpf_loader_upgradeable::id())
+        })
+        .filter(|instruction| {
+            matches!(
+                bincode::deserialize(&instruction.data),
+                Ok(UpgradeableLoaderInstruction::Upgrade)
+            )
+        })
+        .filter_map(|instruction| {
+            Some(LoaderUpgrade {
+                program: key(instruction, 1)?,
+                buffer: key(instruction, 2)?,
+                authority: key(instruction, 6)?,
+            })
+        })
+        .collect()
+}
+
+/// An upgrade found on chain, as imported into `upgrade_history`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoricalUpgrade {
+    pub signature: String,
+    pub slot: u64,
+    pub executed_at: i64,
+    pub program: String,
+    pub buffer: String,
+    pub authority: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Request body for `POST /history/import`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackfillRequest {
+    pub program: String,
+    /// Transactions to import; when empty, the program's history is scanned
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    /// Stop scanning after this many signatures (default and cap 5000)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BackfillReport {
+    pub program: String,
+    /// Transactions fetched and decoded
+    pub transactions_checked: usize,
+    pub upgrades_found: usize,
+    pub imported: usize,
+    /// Upgrades already in the history, imported earlier or executed by this service
+    pub already_recorded: usize,
+    /// Oldest first
+    pub upgrades: Vec<HistoricalUpgrade>,
+}
+
+/// Imports upgrades performed before this service was adopted (or around it)
+/// into `upgrade_history`, so the version timeline is complete. Scanning
+/// reads the history of the program's programdata account, which only
+/// deploys, upgrades and authority changes touch.
+pub struct HistoryBackfill {
+    database: Arc<Database>,
+    rpc_url: String,
+}
+
+impl HistoryBackfill {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            rpc_url: std::env::var("SOLANA_RPC_URL")
+                .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string()),
+        }
+    }
+
+    pub async fn import(&self, request: BackfillRequest) -> Result<BackfillReport, UpgradeError> {
+        let program = Pubkey::from_str(&request.program).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let limit = request.limit.unwrap_or(MAX_BACKFILL_SIGNATURES).clamp(1, MAX_BACKFILL_SIGNATURES);
+        if request.signatures.len() > limit {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "at most {} signatures per import",
+                limit
+            )));
+        }
+        let signatures = request
+            .signatures
+            .iter()
+            .map(|signature| {
+                Signature::from_str(signature)
+                    .map_err(|_| UpgradeError::InvalidRequest(format!("Invalid signature: {}", signature)))
+            })
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+        let rpc_url = self.rpc_url.clone();
+        let (transactions_checked, mut upgrades) = tokio::task::spawn_blocking(move || {
+            let client = RpcClient::new(rpc_url);
+            let signatures = if signatures.is_empty() {
+                scan_signatures(&client, &program, limit)?
+            } else {
+                signatures
+            };
+
+            let mut upgrades = Vec::new();
+            for signature in &signatures {
+                upgrades.extend(
+                    fetch_upgrades(&client, signature)?
+                        .into_iter()
+                        .filter(|upgrade| upgrade.program == program.to_string()),
+                );
+            }
+            Ok::<_, UpgradeError>((signatures.len(), upgrades))
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))??;
+        upgrades.sort_by_key(|upgrade| upgrade.slot);
+
+        let mut imported = 0;
+        for upgrade in &upgrades {
+            if self.database.import_upgrade_history(upgrade).await? {
+                imported += 1;
+            }
+        }
+
+        tracing::info!(
+            "History backfill for {}: {} upgrades in {} transactions, {} imported",
+            program,
+            upgrades.len(),
+            transactions_checked,
+            imported
+        );
+
+        Ok(BackfillReport {
+            program: program.to_string(),
+            transactions_checked,
+            upgrades_found: upgrades.len(),
+            imported,
+            already_recorded: upgrades.len() - imported,
+            upgrades,
+        })
+    }
+}
+
+/// Signatures touching the program's programdata account, newest first
+fn scan_signatures(client: &RpcClient, program: &Pubkey, limit: usize) -> Result<Vec<Signature>, UpgradeError> {
+    let (programdata, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    let mut signatures = Vec::new();
+    let mut before = None;
+
+    while signatures.len() < limit {
+        let page = client
+            .get_signatures_for_address_with_config(
+                &programdata,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    limit: Some(SIGNATURE_PAGE.min(limit - signatures.len())),
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch signatures: {}", e)))?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(
+            Signature::from_str(&last.signature)
+                .map_err(|e| UpgradeError::SolanaError(format!("Invalid signature: {}", e)))?,
+        );
+
+        let full = page.len() == SIGNATURE_PAGE;
+        for status in page {
+            signatures.push(
+                Signature::from_str(&status.signature)
+                    .map_err(|e| UpgradeError::SolanaError(format!("Invalid signature: {}", e)))?,
+            );
+        }
+        if !full {
+            break;
+        }
+    }
+
+    Ok(signatures)
+}
+
+fn fetch_upgrades(client: &RpcClient, signature: &Signature) -> Result<Vec<HistoricalUpgrade>, UpgradeError> {
+    let transaction = client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch transaction {}: {}", signature, e)))?;
+
+    Ok(decode_upgrades(signature, transaction))
+}
+
+fn decode_upgrades(
+    signature: &Signature,
+    transaction: EncodedConfirmedTransactionWithStatusMeta,
+) -> Vec<HistoricalUpgrade> {
+    let Some(decoded) = transaction.transaction.transaction.decode() else {
+        return Vec::new();
+    };
+    let Some(executed_at) = transaction.block_time else {
+        tracing::warn!("Skipping {}: no block time", signature);
+        return Vec::new();
+    };
+    let meta = transaction.transaction.meta;
+
+    let mut account_keys = decoded.message.static_account_keys().to_vec();
+    let mut instructions = decoded.message.instructions().to_vec();
+    let mut error = None;
+    if let Some(meta) = meta {
+        // Address lookup table entries follow the static keys, writable first
+        if let Some(UiLoadedAddresses { writable, readonly }) = Option::from(meta.loaded_addresses) {
+            account_keys.extend(
+                writable
+                    .iter()
+                    .chain(&readonly)
+                    .filter_map(|key| Pubkey::from_str(key).ok()),
+            );
+        }
+        let inner: Option<Vec<_>> = meta.inner_instructions.into();
+        instructions.extend(
+            inner
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|inner| inner.instructions)
+                .filter_map(|instruction| match instruction {
+                    UiInstruction::Compiled(compiled) => Some(CompiledInstruction {
+                        program_id_index: compiled.program_id_index,
+                        accounts: compiled.accounts,
+                        data: bs58::decode(compiled.data).into_vec().ok()?,
+                    }),
+                    UiInstruction::Parsed(_) => None,
+                }),
+        );
+        error = meta.err.map(|e| e.to_string());
+    }
+
+    find_upgrades(&account_keys, &instructions)
+        .into_iter()
+        .map(|upgrade| HistoricalUpgrade {
+            signature: signature.to_string(),
+            slot: transaction.slot,
+            executed_at,
+            program: upgrade.program.to_string(),
+            buffer: upgrade.buffer.to_string(),
+            authority: upgrade.authority.to_string(),
+            success: error.is_none(),
+            error: error.clone(),
+        })
+        .collect()
+}
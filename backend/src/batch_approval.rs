@@ -0,0 +1,118 @@
+use crate::error::UpgradeError;
+use crate::proposal::{Proposal, ProposalStatus};
+use crate::receipts::SignedReceipt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Domain separator for the batch approval message; bump when the layout changes
+pub const BATCH_APPROVAL_VERSION: &str = "goquant-batch-approval-v1";
+
+/// Most proposals one signature may approve
+pub const MAX_BATCH_SIZE: usize = 25;
+
+/// What one member signs to approve several proposals at once
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchApprovalRequest {
+    /// Base58 pubkey of the approving member
+    pub member: String,
+    pub proposal_ids: Vec<String>,
+    /// Base58 ed25519 signature over `batch_message`
+    pub signature: String,
+}
+
+/// The outcome for one proposal of a batch
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BatchApprovalResult {
+    pub proposal_id: String,
+    pub approved: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposal_status: Option<ProposalStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<SignedReceipt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchApprovalResult {
+    pub fn approved(proposal_id: &str, proposal_status: ProposalStatus, receipt: SignedReceipt) -> Self {
+        Self {
+            proposal_id: proposal_id.to_string(),
+            approved: true,
+            proposal_status: Some(proposal_status),
+            receipt: Some(receipt),
+            error: None,
+        }
+    }
+
+    pub fn failed(proposal_id: &str, error: &UpgradeError) -> Self {
+        Self {
+            proposal_id: proposal_id.to_string(),
+            approved: false,
+            proposal_status: None,
+            receipt: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// SHA-256 over what an approval commits to: the proposal, the program, the
+/// buffer and, for proposals built from source, the artifact hash. Hex encoded.
+pub fn proposal_digest(proposal: &Proposal) -> String {
+    let artifact_hash = proposal
+        .source
+        .as_ref()
+        .map(|source| source.artifact_hash.as_str())
+        .unwrap_or("");
+    let fields = [
+        proposal.id.as_str(),
+        proposal.program.as_str(),
+        proposal.new_buffer.as_str(),
+        artifact_hash,
+    ]
+    .join("\n");
+    hex::encode(Sha256::digest(fields.as_bytes()))
+}
+
+/// Bytes the member signs: the version, the member, then one
+/// `<proposal_id>:<digest>` line per proposal in request order
+pub fn batch_message(member: &str, digests: &[(String, String)]) -> String {
+    let mut lines = vec![BATCH_APPROVAL_VERSION.to_string(), member.to_string()];
+    lines.extend(digests.iter().map(|(id, digest)| format!("{}:{}", id, digest)));
+    lines.join("\n")
+}
+
+/// Rejects empty, oversized and repeated batches before anything is loaded
+pub fn validate_batch(proposal_ids: &[String]) -> Result<(), UpgradeError> {
+    if proposal_ids.is_empty() {
+        return Err(UpgradeError::InvalidRequest("No proposals to approve".to_string()));
+    }
+    if proposal_ids.len() > MAX_BATCH_SIZE {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "At most {} proposals can be approved in one batch",
+            MAX_BATCH_SIZE
+        )));
+    }
+    let mut seen = HashSet::new();
+    if let Some(repeated) = proposal_ids.iter().find(|id| !seen.insert(id.as_str())) {
+        return Err(UpgradeError::InvalidRequest(format!("{} appears more than once", repeated)));
+    }
+    Ok(())
+}
+
+/// Checks `signature` is `member`'s over `message`
+pub fn verify_batch_signature(member: &str, message: &str, signature: &str) -> Result<(), UpgradeError> {
+    let member = Pubkey::from_str(member).map_err(|_| UpgradeError::InvalidPubkey)?;
+    let signature = Signature::from_str(signature)
+        .map_err(|_| UpgradeError::InvalidRequest("Signature is not base58 ed25519".to_string()))?;
+
+    if !signature.verify(member.as_ref(), message.as_bytes()) {
+        return Err(UpgradeError::Unauthorized(
+            "Signature does not cover these proposals".to_string(),
+        ));
+    }
+    Ok(())
+}
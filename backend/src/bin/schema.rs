@@ -0,0 +1,19 @@
+//! Writes `api.schema.json` (see `schema::api_schema`) to the directory given
+//! as the first argument, `target/schema` by default.
+//!
+//! ```sh
+//! cargo run --features schema --bin schema -- target/schema
+//! ```
+
+use goquant_upgrade_service::schema::api_schema;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = PathBuf::from(std::env::args().nth(1).unwrap_or_else(|| "target/schema".to_string()));
+    std::fs::create_dir_all(&out_dir)?;
+
+    let path = out_dir.join("api.schema.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&api_schema())?)?;
+    println!("{}", path.display());
+    Ok(())
+}
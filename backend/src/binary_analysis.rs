@@ -0,0 +1,313 @@
+use crate::error::UpgradeError;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// Size of an SBF stack frame; accesses deeper than this fault at runtime
+pub const STACK_FRAME_SIZE: u32 = 4096;
+
+/// Stack use past this many bytes of a frame is flagged to approvers
+const STACK_WARN_THRESHOLD: u32 = STACK_FRAME_SIZE * 7 / 8;
+
+/// Syscalls that perform a cross-program invocation
+const INVOKE_SYSCALLS: [&str; 2] = ["sol_invoke_signed_rust", "sol_invoke_signed_c"];
+
+/// Programs recognised by address in `.rodata`. The system program is left
+/// out: its all-zero address matches any zeroed constant.
+const KNOWN_PROGRAMS: [(&str, &str); 10] = [
+    ("SPL Token", "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+    ("SPL Token-2022", "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"),
+    ("Associated Token Account", "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
+    ("SPL Memo", "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+    ("BPF Upgradeable Loader", "BPFLoaderUpgradeab1e11111111111111111111111"),
+    ("Compute Budget", "ComputeBudget111111111111111111111111111111"),
+    ("Address Lookup Table", "AddressLookupTab1e1111111111111111111111111"),
+    ("Stake", "Stake11111111111111111111111111111111111111"),
+    ("Vote", "Vote111111111111111111111111111111111111111"),
+    ("Metaplex Token Metadata", "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
+];
+
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+const SHF_EXECINSTR: u64 = 0x4;
+
+/// What a program binary can do, as far as can be told without running it
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct BinaryProfile {
+    /// Imported syscalls (undefined dynamic symbols)
+    pub syscalls: BTreeSet<String>,
+    /// Known program addresses embedded in read-only data
+    pub known_programs: BTreeSet<String>,
+    /// Other 8-byte aligned 32-byte constants that look like ed25519 public
+    /// keys; possible program IDs, but also hashes or other key material
+    pub unrecognized_keys: BTreeSet<String>,
+    pub text_size: u64,
+    pub rodata_size: u64,
+    /// Deepest stack-pointer (`r10`) relative access in any function
+    pub max_stack_offset: u32,
+}
+
+struct Section<'a> {
+    name: String,
+    kind: u32,
+    flags: u64,
+    link: u32,
+    data: &'a [u8],
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], UpgradeError> {
+    offset
+        .checked_add(N)
+        .and_then(|end| data.get(offset..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| UpgradeError::InvalidRequest(format!("Truncated ELF at offset {}", offset)))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, UpgradeError> {
+    read(data, offset).map(u16::from_le_bytes)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, UpgradeError> {
+    read(data, offset).map(u32::from_le_bytes)
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Result<u64, UpgradeError> {
+    read(data, offset).map(u64::from_le_bytes)
+}
+
+fn slice(data: &[u8], offset: u64, len: u64) -> Result<&[u8], UpgradeError> {
+    usize::try_from(offset)
+        .ok()
+        .zip(usize::try_from(len).ok())
+        .and_then(|(offset, len)| data.get(offset..offset.checked_add(len)?))
+        .ok_or_else(|| UpgradeError::InvalidRequest("ELF section out of bounds".to_string()))
+}
+
+fn c_string(table: &[u8], offset: usize) -> String {
+    let bytes = table.get(offset..).unwrap_or_default();
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn sections(elf: &[u8]) -> Result<Vec<Section<'_>>, UpgradeError> {
+    if elf.get(..4) != Some(b"\x7fELF".as_slice()) || elf.get(4) != Some(&2) || elf.get(5) != Some(&1) {
+        return Err(UpgradeError::InvalidRequest("Not a 64-bit little-endian ELF".to_string()));
+    }
+
+    let shoff = u64_at(elf, 0x28)? as usize;
+    let shentsize = u16_at(elf, 0x3a)? as usize;
+    let shnum = u16_at(elf, 0x3c)? as usize;
+    let shstrndx = u16_at(elf, 0x3e)? as usize;
+
+    let mut headers = Vec::with_capacity(shnum);
+    for index in 0..shnum {
+        let at = shoff + index * shentsize;
+        let kind = u32_at(elf, at + 4)?;
+        // .bss occupies no file bytes
+        let data = if kind == SHT_NOBITS {
+            &[][..]
+        } else {
+            slice(elf, u64_at(elf, at + 24)?, u64_at(elf, at + 32)?)?
+        };
+        headers.push((u32_at(elf, at)?, kind, u64_at(elf, at + 8)?, u32_at(elf, at + 40)?, data));
+    }
+
+    let names = headers.get(shstrndx).map(|h| h.4).unwrap_or_default();
+    Ok(headers
+        .into_iter()
+        .map(|(name, kind, flags, link, data)| Section {
+            name: c_string(names, name as usize),
+            kind,
+            flags,
+            link,
+            data,
+        })
+        .collect())
+}
+
+fn imported_syscalls(sections: &[Section]) -> Result<BTreeSet<String>, UpgradeError> {
+    let mut syscalls = BTreeSet::new();
+    for dynsym in sections.iter().filter(|s| s.kind == SHT_DYNSYM) {
+        let strings = sections.get(dynsym.link as usize).map(|s| s.data).unwrap_or_default();
+        for symbol in dynsym.data.chunks_exact(24) {
+            let name = u32_at(symbol, 0)? as usize;
+            let shndx = u16_at(symbol, 6)?;
+            if shndx == 0 && name != 0 {
+                syscalls.insert(c_string(strings, name));
+            }
+        }
+    }
+    Ok(syscalls)
+}
+
+/// Largest negative offset from the frame pointer used by a load or store
+fn max_stack_offset(text: &[u8]) -> u32 {
+    text.chunks_exact(8)
+        .filter_map(|ix| {
+            let opcode = ix[0];
+            let (dst, src) = (ix[1] & 0x0f, ix[1] >> 4);
+            let offset = i16::from_le_bytes([ix[2], ix[3]]);
+            let memory = opcode & 0xe0 == 0x60;
+            // ldx reads through its source register, st/stx write through the destination
+            let frame_access = match opcode & 0x07 {
+                0x01 => src == 10,
+                0x02 | 0x03 => dst == 10,
+                _ => false,
+            };
+            (memory && frame_access && offset < 0).then(|| offset.unsigned_abs() as u32)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn looks_like_key(window: &[u8]) -> bool {
+    let distinct = window.iter().collect::<BTreeSet<_>>().len();
+    let high = window.iter().filter(|&&b| b >= 0x80).count();
+    distinct >= 28 && high >= 8 && Pubkey::try_from(window).is_ok_and(|key| key.is_on_curve())
+}
+
+impl BinaryProfile {
+    pub fn analyze(elf: &[u8]) -> Result<Self, UpgradeError> {
+        let sections = sections(elf)?;
+        let mut profile = Self {
+            syscalls: imported_syscalls(&sections)?,
+            ..Self::default()
+        };
+
+        let known: Vec<Pubkey> = KNOWN_PROGRAMS
+            .iter()
+            .map(|(_, address)| Pubkey::from_str(address).expect("valid program address"))
+            .collect();
+
+        for section in &sections {
+            if section.flags & SHF_EXECINSTR != 0 {
+                profile.text_size += section.data.len() as u64;
+                profile.max_stack_offset = profile.max_stack_offset.max(max_stack_offset(section.data));
+            }
+            if !(section.name.starts_with(".rodata") || section.name.starts_with(".data.rel.ro")) {
+                continue;
+            }
+
+            profile.rodata_size += section.data.len() as u64;
+            for program in &known {
+                if section.data.windows(32).any(|w| w == program.as_ref()) {
+                    profile.known_programs.insert(program.to_string());
+                }
+            }
+            let aligned = (0..section.data.len()).step_by(8).filter_map(|at| section.data.get(at..at + 32));
+            for window in aligned {
+                if looks_like_key(window) && !known.iter().any(|k| k.as_ref() == window) {
+                    profile.unrecognized_keys.insert(Pubkey::try_from(window).unwrap().to_string());
+                }
+            }
+        }
+
+        Ok(profile)
+    }
+
+    pub fn performs_cpi(&self) -> bool {
+        INVOKE_SYSCALLS.iter().any(|s| self.syscalls.contains(*s))
+    }
+}
+
+/// A known program referenced by the binary
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProgramReference {
+    pub name: String,
+    pub address: String,
+}
+
+fn reference(address: &str) -> ProgramReference {
+    let name = KNOWN_PROGRAMS
+        .iter()
+        .find(|(_, known)| *known == address)
+        .map_or("unknown", |(name, _)| name);
+    ProgramReference {
+        name: name.to_string(),
+        address: address.to_string(),
+    }
+}
+
+/// How a proposed binary differs from the deployed one, for approvers
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryImpact {
+    /// False when there was no deployed binary to compare against
+    pub compared_to_deployed: bool,
+    pub proposed: BinaryProfile,
+    pub added_syscalls: Vec<String>,
+    pub removed_syscalls: Vec<String>,
+    pub added_programs: Vec<ProgramReference>,
+    pub removed_programs: Vec<ProgramReference>,
+    pub added_unrecognized_keys: Vec<String>,
+    pub max_stack_offset_before: Option<u32>,
+    pub max_stack_offset_after: u32,
+    pub warnings: Vec<String>,
+}
+
+impl BinaryImpact {
+    pub fn compare(deployed: Option<&BinaryProfile>, proposed: BinaryProfile) -> Self {
+        let empty = BinaryProfile::default();
+        let before = deployed.unwrap_or(&empty);
+
+        let added_syscalls: Vec<String> = proposed.syscalls.difference(&before.syscalls).cloned().collect();
+        let removed_syscalls: Vec<String> = before.syscalls.difference(&proposed.syscalls).cloned().collect();
+        let added_programs: Vec<ProgramReference> = proposed
+            .known_programs
+            .difference(&before.known_programs)
+            .map(|a| reference(a))
+            .collect();
+        let removed_programs: Vec<ProgramReference> = before
+            .known_programs
+            .difference(&proposed.known_programs)
+            .map(|a| reference(a))
+            .collect();
+        let added_unrecognized_keys: Vec<String> = proposed
+            .unrecognized_keys
+            .difference(&before.unrecognized_keys)
+            .cloned()
+            .collect();
+
+        let mut warnings = Vec::new();
+        if proposed.performs_cpi() && !before.performs_cpi() {
+            warnings.push("Program now makes cross-program invocations".to_string());
+        }
+        if !added_programs.is_empty() {
+            let names: Vec<String> = added_programs.iter().map(|p| format!("{} ({})", p.name, p.address)).collect();
+            warnings.push(format!("New external program calls detected: {}", names.join(", ")));
+        }
+        if proposed.performs_cpi() && !added_unrecognized_keys.is_empty() {
+            warnings.push(format!(
+                "{} new 32-byte constants that may be program IDs; check for new CPI targets",
+                added_unrecognized_keys.len()
+            ));
+        }
+        let other_syscalls: Vec<&str> = added_syscalls
+            .iter()
+            .map(String::as_str)
+            .filter(|s| !INVOKE_SYSCALLS.contains(s))
+            .collect();
+        if !other_syscalls.is_empty() {
+            warnings.push(format!("New syscalls: {}", other_syscalls.join(", ")));
+        }
+        if proposed.max_stack_offset >= STACK_WARN_THRESHOLD && proposed.max_stack_offset > before.max_stack_offset {
+            warnings.push(format!(
+                "Stack use reaches {} of {} bytes per frame (was {})",
+                proposed.max_stack_offset, STACK_FRAME_SIZE, before.max_stack_offset
+            ));
+        }
+
+        Self {
+            compared_to_deployed: deployed.is_some(),
+            added_syscalls,
+            removed_syscalls,
+            added_programs,
+            removed_programs,
+            added_unrecognized_keys,
+            max_stack_offset_before: deployed.map(|p| p.max_stack_offset),
+            max_stack_offset_after: proposed.max_stack_offset,
+            proposed,
+            warnings,
+        }
+    }
+}
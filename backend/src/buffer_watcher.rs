@@ -0,0 +1,319 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::proposal::ProposalManager;
+use crate::security::SecurityAuditor;
+use crate::websocket::NotificationService;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Bincode size of `UpgradeableLoaderState::Buffer`: enum tag, option tag, authority
+pub const BUFFER_METADATA_SIZE: usize = 4 + 1 + 32;
+
+/// `UpgradeableLoaderState::Buffer` enum tag
+const BUFFER_TAG: [u8; 4] = [1, 0, 0, 0];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectedBufferStatus {
+    /// Offered to operators, no proposal yet
+    Pending,
+    Proposed,
+    Dismissed,
+    /// Closed on-chain before anyone proposed it
+    Closed,
+}
+
+impl DetectedBufferStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DetectedBufferStatus::Pending => "pending",
+            DetectedBufferStatus::Proposed => "proposed",
+            DetectedBufferStatus::Dismissed => "dismissed",
+            DetectedBufferStatus::Closed => "closed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(DetectedBufferStatus::Pending),
+            "proposed" => Some(DetectedBufferStatus::Proposed),
+            "dismissed" => Some(DetectedBufferStatus::Dismissed),
+            "closed" => Some(DetectedBufferStatus::Closed),
+            _ => None,
+        }
+    }
+}
+
+/// A buffer owned by the multisig vault that was found on-chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedBuffer {
+    pub buffer: String,
+    pub authority: String,
+    /// Size of the program bytes, excluding the buffer header
+    pub program_len: u64,
+    /// Hex SHA-256 of the program bytes
+    pub program_hash: String,
+    pub lamports: u64,
+    pub status: DetectedBufferStatus,
+    pub proposal_id: Option<String>,
+    pub detected_at: i64,
+}
+
+/// What to do with a newly detected buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferWatchMode {
+    /// Record it and notify; an operator turns it into a proposal
+    Offer,
+    /// Create the proposal immediately
+    Auto,
+}
+
+/// Split a loader buffer account into its authority and program bytes.
+/// Returns `None` for anything that is not a buffer.
+pub fn parse_buffer(data: &[u8]) -> Option<(Option<Pubkey>, &[u8])> {
+    if data.len() < BUFFER_METADATA_SIZE || data[..4] != BUFFER_TAG {
+        return None;
+    }
+
+    let authority = match data[4] {
+        0 => None,
+        1 => Some(Pubkey::new_from_array(data[5..BUFFER_METADATA_SIZE].try_into().ok()?)),
+        _ => return None,
+    };
+
+    Some((authority, &data[BUFFER_METADATA_SIZE..]))
+}
+
+/// Require a buffer to be held by `expected` (the multisig vault or upgrade
+/// authority), so whoever uploaded it can no longer rewrite or reclaim it
+pub fn ensure_buffer_handed_off(owner: &Pubkey, data: &[u8], expected: &Pubkey) -> Result<(), UpgradeError> {
+    if *owner != bpf_loader_upgradeable::id() {
+        return Err(UpgradeError::BufferNotHandedOff("account is not a loader buffer".to_string()));
+    }
+    match parse_buffer(data) {
+        Some((Some(authority), _)) if authority == *expected => Ok(()),
+        Some((Some(authority), _)) => Err(UpgradeError::BufferNotHandedOff(format!(
+            "buffer authority is {}, expected {}",
+            authority, expected
+        ))),
+        // An immutable buffer can never be used for an upgrade
+        Some((None, _)) => Err(UpgradeError::BufferNotHandedOff("buffer has no authority".to_string())),
+        None => Err(UpgradeError::BufferNotHandedOff("account is not a loader buffer".to_string())),
+    }
+}
+
+/// Finds buffers whose authority is the multisig vault so uploads made outside
+/// the release pipeline still end up in front of the multisig
+pub struct BufferWatcher {
+    database: Arc<Database>,
+    proposal_manager: Arc<ProposalManager>,
+    notifications: Option<Arc<NotificationService>>,
+    rpc_url: String,
+    vault: Option<Pubkey>,
+    mode: BufferWatchMode,
+}
+
+impl BufferWatcher {
+    pub fn new(database: Arc<Database>, proposal_manager: Arc<ProposalManager>) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let vault = std::env::var("MULTISIG_VAULT")
+            .ok()
+            .and_then(|v| Pubkey::from_str(&v).ok());
+        let mode = match std::env::var("BUFFER_WATCH_MODE").as_deref() {
+            Ok("auto") => BufferWatchMode::Auto,
+            _ => BufferWatchMode::Offer,
+        };
+
+        Self {
+            database,
+            proposal_manager,
+            notifications: None,
+            rpc_url,
+            vault,
+            mode,
+        }
+    }
+
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Scan every `BUFFER_WATCH_INTERVAL_SECS` (default 60). Does nothing
+    /// without `MULTISIG_VAULT`, since there is no authority to match.
+    pub fn spawn(self: Arc<Self>) {
+        if self.vault.is_none() {
+            tracing::info!("Buffer watcher disabled: MULTISIG_VAULT not set");
+            return;
+        }
+
+        let interval_secs = std::env::var("BUFFER_WATCH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                match self.scan().await {
+                    Ok(detected) if !detected.is_empty() => {
+                        tracing::info!("Detected {} new vault-owned buffers", detected.len())
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Buffer scan failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// One scan: record buffers not seen before and not already proposed, and
+    /// settle pending offers that were proposed elsewhere or closed.
+    /// Returns the newly detected buffers.
+    pub async fn scan(&self) -> Result<Vec<DetectedBuffer>, UpgradeError> {
+        let vault = self.vault.ok_or_else(|| {
+            UpgradeError::InvalidRequest("MULTISIG_VAULT is required to watch buffers".to_string())
+        })?;
+
+        let rpc_url = self.rpc_url.clone();
+        let accounts = tokio::task::spawn_blocking(move || {
+            let mut authority_filter = vec![1u8];
+            authority_filter.extend_from_slice(vault.as_ref());
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, BUFFER_TAG.to_vec())),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(4, authority_filter)),
+                ]),
+                ..Default::default()
+            };
+            RpcClient::new(rpc_url).get_program_accounts_with_config(&bpf_loader_upgradeable::id(), config)
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+        .map_err(|e| UpgradeError::SolanaError(format!("Buffer scan failed: {}", e)))?;
+
+        let proposed: HashMap<String, String> = self.proposal_manager
+            .list_proposals()
+            .await?
+            .into_iter()
+            .map(|p| (p.new_buffer, p.id))
+            .collect();
+        let on_chain: Vec<String> = accounts.iter().map(|(pubkey, _)| pubkey.to_string()).collect();
+
+        for pending in self.database.list_detected_buffers(Some(DetectedBufferStatus::Pending)).await? {
+            if let Some(proposal_id) = proposed.get(&pending.buffer) {
+                self.database
+                    .update_detected_buffer_status(&pending.buffer, DetectedBufferStatus::Proposed, Some(proposal_id))
+                    .await?;
+            } else if !on_chain.contains(&pending.buffer) {
+                self.database
+                    .update_detected_buffer_status(&pending.buffer, DetectedBufferStatus::Closed, None)
+                    .await?;
+            }
+        }
+
+        let mut detected = Vec::new();
+        for (pubkey, account) in accounts {
+            let buffer = pubkey.to_string();
+            if proposed.contains_key(&buffer) {
+                continue;
+            }
+            let Some((Some(authority), program)) = parse_buffer(&account.data) else {
+                continue;
+            };
+
+            let candidate = DetectedBuffer {
+                buffer,
+                authority: authority.to_string(),
+                program_len: program.len() as u64,
+                program_hash: hex::encode(SecurityAuditor::calculate_program_hash(program)),
+                lamports: account.lamports,
+                status: DetectedBufferStatus::Pending,
+                proposal_id: None,
+                detected_at: chrono::Utc::now().timestamp(),
+            };
+            if !self.database.insert_detected_buffer(&candidate).await? {
+                continue;
+            }
+
+            tracing::info!("Detected buffer {} ({} bytes) owned by the vault", candidate.buffer, candidate.program_len);
+            if let Some(notifications) = &self.notifications {
+                notifications.notify_buffer_detected(&candidate).await;
+            }
+
+            if self.mode == BufferWatchMode::Auto {
+                match self.propose(&candidate.buffer, None).await {
+                    Ok(proposal_id) => tracing::info!("Buffer {} proposed as {}", candidate.buffer, proposal_id),
+                    Err(e) => tracing::error!("Failed to propose detected buffer {}: {}", candidate.buffer, e),
+                }
+            }
+
+            detected.push(candidate);
+        }
+
+        Ok(detected)
+    }
+
+    /// Turn a pending detected buffer into a proposal
+    pub async fn propose(&self, buffer: &str, description: Option<String>) -> Result<String, UpgradeError> {
+        let detected = self.get(buffer).await?;
+        if detected.status != DetectedBufferStatus::Pending {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Buffer {} is already {}",
+                buffer,
+                detected.status.as_str()
+            )));
+        }
+
+        let buffer_pubkey = Pubkey::from_str(buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let description = description.unwrap_or_else(|| {
+            format!(
+                "Upgrade from buffer {} detected on-chain ({} bytes, sha256 {})",
+                buffer, detected.program_len, detected.program_hash
+            )
+        });
+
+        let proposal_id = self.proposal_manager
+            .propose_upgrade(buffer_pubkey, description)
+            .await?;
+        self.database
+            .update_detected_buffer_status(buffer, DetectedBufferStatus::Proposed, Some(&proposal_id))
+            .await?;
+
+        Ok(proposal_id)
+    }
+
+    pub async fn dismiss(&self, buffer: &str) -> Result<(), UpgradeError> {
+        let detected = self.get(buffer).await?;
+        if detected.status != DetectedBufferStatus::Pending {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Buffer {} is already {}",
+                buffer,
+                detected.status.as_str()
+            )));
+        }
+
+        self.database
+            .update_detected_buffer_status(buffer, DetectedBufferStatus::Dismissed, None)
+            .await
+    }
+
+    pub async fn list(&self, status: Option<DetectedBufferStatus>) -> Result<Vec<DetectedBuffer>, UpgradeError> {
+        self.database.list_detected_buffers(status).await
+    }
+
+    async fn get(&self, buffer: &str) -> Result<DetectedBuffer, UpgradeError> {
+        self.database
+            .get_detected_buffer(buffer)
+            .await?
+            .ok_or_else(|| UpgradeError::BufferNotFound(buffer.to_string()))
+    }
+}
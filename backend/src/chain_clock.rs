@@ -0,0 +1,147 @@
+use crate::error::UpgradeError;
+use crate::rpc_quorum::QuorumRpc;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::sysvar;
+use std::sync::{Arc, RwLock};
+
+/// Size of the bincode-encoded `Clock` sysvar
+const CLOCK_SYSVAR_LEN: usize = 40;
+
+/// One comparison of the backend's clock against the chain's
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ClockSample {
+    pub slot: u64,
+    /// `Clock::unix_timestamp`, the time programs check timelocks against
+    pub chain_time: i64,
+    pub backend_time: i64,
+    /// `backend_time - chain_time`; positive when the backend runs ahead
+    pub drift_seconds: i64,
+}
+
+/// `(slot, unix_timestamp)` from the `Clock` sysvar account data
+pub fn decode_clock_sysvar(data: &[u8]) -> Result<(u64, i64), UpgradeError> {
+    if data.len() < CLOCK_SYSVAR_LEN {
+        return Err(UpgradeError::SolanaError(format!(
+            "Clock sysvar is {} bytes, expected {}",
+            data.len(),
+            CLOCK_SYSVAR_LEN
+        )));
+    }
+
+    // slot, epoch_start_timestamp, epoch, leader_schedule_epoch, unix_timestamp
+    let slot = u64::from_le_bytes(data[0..8].try_into().unwrap());
+    let unix_timestamp = i64::from_le_bytes(data[32..40].try_into().unwrap());
+    Ok((slot, unix_timestamp))
+}
+
+/// Chain time as seen by the upgrade program.
+///
+/// Timelocks are enforced on chain against the `Clock` sysvar, which can run
+/// seconds to minutes away from the backend's system clock. The clock is
+/// sampled periodically and `now()` corrects system time by the last measured
+/// drift, so countdowns shown to users match what the program will accept.
+pub struct ChainClock {
+    rpc_client: Option<Arc<AsyncRpcClient>>,
+    quorum: Option<Arc<QuorumRpc>>,
+    last_sample: RwLock<Option<ClockSample>>,
+    alert_threshold_seconds: i64,
+}
+
+impl ChainClock {
+    pub fn new(rpc_url: String) -> Self {
+        Self {
+            rpc_client: Some(Arc::new(AsyncRpcClient::new_with_commitment(
+                rpc_url,
+                CommitmentConfig::confirmed(),
+            ))),
+            ..Self::system()
+        }
+    }
+
+    /// Sampled from `SOLANA_RPC_URL`; alerts past `CLOCK_DRIFT_ALERT_SECONDS` (default 30)
+    pub fn from_env() -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let alert_threshold_seconds = std::env::var("CLOCK_DRIFT_ALERT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+
+        Self {
+            alert_threshold_seconds,
+            ..Self::new(rpc_url)
+        }
+    }
+
+    /// A clock that never samples the chain and reports system time
+    pub fn system() -> Self {
+        Self {
+            rpc_client: None,
+            quorum: None,
+            last_sample: RwLock::new(None),
+            alert_threshold_seconds: 30,
+        }
+    }
+
+    /// Sample the clock from redundant providers instead of `SOLANA_RPC_URL` alone
+    pub fn with_quorum(mut self, quorum: Arc<QuorumRpc>) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    pub fn alert_threshold_seconds(&self) -> i64 {
+        self.alert_threshold_seconds
+    }
+
+    /// Best estimate of the current chain time
+    pub fn now(&self) -> i64 {
+        system_now() - self.drift_seconds().unwrap_or(0)
+    }
+
+    /// Drift measured by the last sample, if any
+    pub fn drift_seconds(&self) -> Option<i64> {
+        self.last_sample().map(|sample| sample.drift_seconds)
+    }
+
+    pub fn last_sample(&self) -> Option<ClockSample> {
+        *self.last_sample.read().unwrap()
+    }
+
+    /// Store a measurement and use it for `now()` from here on
+    pub fn record(&self, slot: u64, chain_time: i64, backend_time: i64) -> ClockSample {
+        let sample = ClockSample {
+            slot,
+            chain_time,
+            backend_time,
+            drift_seconds: backend_time - chain_time,
+        };
+        *self.last_sample.write().unwrap() = Some(sample);
+        sample
+    }
+
+    /// Read the `Clock` sysvar and record the drift against system time
+    pub async fn sample(&self) -> Result<ClockSample, UpgradeError> {
+        if let Some(quorum) = &self.quorum {
+            let (slot, chain_time) = quorum.clock().await?;
+            return Ok(self.record(slot, chain_time, system_now()));
+        }
+
+        let rpc_client = self.rpc_client.as_ref().ok_or_else(|| {
+            UpgradeError::InternalError("Chain clock has no RPC endpoint".to_string())
+        })?;
+
+        let data = rpc_client
+            .get_account_data(&sysvar::clock::id())
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to read the Clock sysvar: {}", e)))?;
+        let (slot, chain_time) = decode_clock_sysvar(&data)?;
+
+        Ok(self.record(slot, chain_time, system_now()))
+    }
+}
+
+fn system_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
@@ -0,0 +1,189 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::secrets::SecretStore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// One pre-flight step and the roles allowed to sign it off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistItemSpec {
+    pub key: String,
+    pub description: String,
+    /// Optional items are tracked but never block execution
+    #[serde(default = "default_required")]
+    pub required: bool,
+    pub roles: Vec<String>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl ChecklistItemSpec {
+    fn new(key: &str, description: &str, roles: &[&str]) -> Self {
+        Self {
+            key: key.to_string(),
+            description: description.to_string(),
+            required: true,
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+}
+
+/// Checklist used when `CHECKLIST_CONFIG` is not set
+pub fn default_items() -> Vec<ChecklistItemSpec> {
+    vec![
+        ChecklistItemSpec::new("audit_uploaded", "Audit report uploaded", &["auditor"]),
+        ChecklistItemSpec::new(
+            "devnet_canary_passed",
+            "Devnet canary deployment passed",
+            &["release_manager", "ci"],
+        ),
+        ChecklistItemSpec::new("changelog_present", "Changelog present", &["release_manager"]),
+        ChecklistItemSpec::new("idl_diff_reviewed", "IDL diff reviewed", &["reviewer", "release_manager"]),
+    ]
+}
+
+/// A sign-off recorded against a proposal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChecklistCompletion {
+    pub proposal_id: String,
+    pub item: String,
+    pub role: String,
+    pub completed_by: Option<String>,
+    pub evidence: Option<String>,
+    pub completed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChecklistItemState {
+    #[serde(flatten)]
+    pub spec: ChecklistItemSpec,
+    pub completed: bool,
+    pub completion: Option<ChecklistCompletion>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChecklistStatus {
+    pub items: Vec<ChecklistItemState>,
+    /// Every required item is signed off
+    pub complete: bool,
+    /// Required items still open
+    pub missing: Vec<String>,
+}
+
+/// Match recorded sign-offs against the configured items. Sign-offs for items
+/// that are no longer configured are ignored.
+pub fn evaluate(items: &[ChecklistItemSpec], completions: &[ChecklistCompletion]) -> ChecklistStatus {
+    let items: Vec<ChecklistItemState> = items
+        .iter()
+        .map(|spec| {
+            let completion = completions.iter().find(|c| c.item == spec.key).cloned();
+            ChecklistItemState {
+                spec: spec.clone(),
+                completed: completion.is_some(),
+                completion,
+            }
+        })
+        .collect();
+
+    let missing: Vec<String> = items
+        .iter()
+        .filter(|i| i.spec.required && !i.completed)
+        .map(|i| i.spec.key.clone())
+        .collect();
+
+    ChecklistStatus {
+        complete: missing.is_empty(),
+        items,
+        missing,
+    }
+}
+
+/// Resolve a checklist token to its role. Tokens come from the
+/// `CHECKLIST_ROLE_TOKENS` secret as comma separated `role:token` pairs.
+pub fn role_for_token(token: &str, secrets: &SecretStore) -> Option<String> {
+    let configured = secrets.get("CHECKLIST_ROLE_TOKENS")?;
+    configured.split(',').find_map(|pair| {
+        let (role, expected) = pair.trim().split_once(':')?;
+        (!expected.is_empty() && expected == token).then(|| role.trim().to_string())
+    })
+}
+
+/// Per-proposal pre-flight checklist that gates execution
+pub struct ChecklistService {
+    database: Arc<Database>,
+    items: Vec<ChecklistItemSpec>,
+}
+
+impl ChecklistService {
+    pub fn new(database: Arc<Database>, items: Vec<ChecklistItemSpec>) -> Self {
+        Self { database, items }
+    }
+
+    /// Items from the JSON file at `CHECKLIST_CONFIG`, or the default checklist
+    pub fn from_env(database: Arc<Database>) -> Result<Self, UpgradeError> {
+        let items = match std::env::var("CHECKLIST_CONFIG") {
+            Ok(path) => {
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|e| UpgradeError::InternalError(format!("Failed to read {}: {}", path, e)))?;
+                serde_json::from_str(&contents)
+                    .map_err(|e| UpgradeError::InternalError(format!("Invalid checklist config: {}", e)))?
+            }
+            Err(_) => default_items(),
+        };
+
+        Ok(Self::new(database, items))
+    }
+
+    pub fn items(&self) -> &[ChecklistItemSpec] {
+        &self.items
+    }
+
+    pub async fn status(&self, proposal_id: &str) -> Result<ChecklistStatus, UpgradeError> {
+        let completions = self.database.list_checklist_completions(proposal_id).await?;
+        Ok(evaluate(&self.items, &completions))
+    }
+
+    /// Sign off `item` for a proposal. The caller's token must map to one of
+    /// the roles configured for that item.
+    pub async fn complete(
+        &self,
+        proposal_id: &str,
+        item: &str,
+        token: Option<&str>,
+        secrets: &SecretStore,
+        completed_by: Option<String>,
+        evidence: Option<String>,
+    ) -> Result<ChecklistStatus, UpgradeError> {
+        let spec = self
+            .items
+            .iter()
+            .find(|s| s.key == item)
+            .ok_or_else(|| UpgradeError::InvalidRequest(format!("Unknown checklist item '{}'", item)))?;
+
+        let token = token.ok_or_else(|| UpgradeError::Forbidden("Checklist token required".to_string()))?;
+        let role = role_for_token(token, secrets)
+            .filter(|role| spec.roles.contains(role))
+            .ok_or_else(|| {
+                UpgradeError::Forbidden(format!("'{}' requires one of the roles: {}", item, spec.roles.join(", ")))
+            })?;
+
+        self.database
+            .upsert_checklist_completion(proposal_id, item, &role, completed_by.as_deref(), evidence.as_deref())
+            .await?;
+        tracing::info!("Checklist item {} completed for {} by {}", item, proposal_id, role);
+
+        self.status(proposal_id).await
+    }
+
+    /// Refuse execution while a required item is open
+    pub async fn ensure_complete(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+        let status = self.status(proposal_id).await?;
+        if status.complete {
+            Ok(())
+        } else {
+            Err(UpgradeError::ChecklistIncomplete(status.missing.join(", ")))
+        }
+    }
+}
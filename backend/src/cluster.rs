@@ -0,0 +1,129 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::websocket::NotificationService;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Postgres channel instances announce persisted events on
+pub const EVENTS_CHANNEL: &str = "goquant_events";
+
+/// Where state that every replica must agree on lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedState {
+    /// Single instance: the notification bus and timelock cache stay in process
+    Memory,
+    /// Replicas share events through LISTEN/NOTIFY and read timelocks from the database
+    Postgres,
+}
+
+impl SharedState {
+    pub fn parse(value: &str) -> Result<Self, UpgradeError> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "memory" => Ok(SharedState::Memory),
+            "postgres" => Ok(SharedState::Postgres),
+            other => Err(UpgradeError::InvalidRequest(format!(
+                "SHARED_STATE must be 'memory' or 'postgres', got '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// `NOTIFY` payload announcing that `instance` persisted event `seq`. Events
+/// themselves stay in the `events` table, since payloads are capped at 8000 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventAnnouncement {
+    pub instance: String,
+    pub seq: i64,
+}
+
+impl EventAnnouncement {
+    pub fn to_payload(&self) -> String {
+        format!("{}:{}", self.instance, self.seq)
+    }
+
+    pub fn parse(payload: &str) -> Option<Self> {
+        let (instance, seq) = payload.rsplit_once(':')?;
+        if instance.is_empty() {
+            return None;
+        }
+        Some(Self {
+            instance: instance.to_string(),
+            seq: seq.parse().ok()?,
+        })
+    }
+}
+
+/// Fans notifications out across replicas behind a load balancer. Each
+/// instance persists its own events as before, then announces their `seq` on
+/// `EVENTS_CHANNEL`; every other instance loads the event and rebroadcasts it
+/// to its WebSocket and SSE clients, so all clients see the same stream in the
+/// same `seq` numbering.
+pub struct ClusterBus {
+    database: Arc<Database>,
+    instance_id: String,
+}
+
+impl ClusterBus {
+    /// `INSTANCE_ID` names this replica in logs (default: a random id)
+    pub fn from_env(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            instance_id: std::env::var("INSTANCE_ID")
+                .ok()
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        }
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Tell the other instances about an event this one persisted
+    pub async fn announce(&self, seq: i64) -> Result<(), UpgradeError> {
+        let announcement = EventAnnouncement {
+            instance: self.instance_id.clone(),
+            seq,
+        };
+        self.database
+            .notify_channel(EVENTS_CHANNEL, &announcement.to_payload())
+            .await
+    }
+
+    /// Relay other instances' events to local subscribers, reconnecting if
+    /// the listening connection drops
+    pub async fn run(&self, notifications: Arc<NotificationService>) {
+        loop {
+            if let Err(e) = self.listen(&notifications).await {
+                tracing::warn!("Cluster event listener stopped: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
+
+    async fn listen(&self, notifications: &NotificationService) -> Result<(), UpgradeError> {
+        let mut listener = self.database.listen(EVENTS_CHANNEL).await?;
+        tracing::info!("Instance {} listening for cluster events", self.instance_id);
+
+        loop {
+            let notification = listener.recv().await?;
+            let Some(announcement) = EventAnnouncement::parse(notification.payload()) else {
+                tracing::warn!("Ignoring malformed cluster event '{}'", notification.payload());
+                continue;
+            };
+            if announcement.instance == self.instance_id {
+                continue;
+            }
+
+            match self.database.get_event(announcement.seq).await? {
+                Some(event) => notifications.relay(event),
+                None => tracing::warn!(
+                    "Event {} announced by {} is not in the database",
+                    announcement.seq,
+                    announcement.instance
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,522 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::faucet::Cluster;
+use crate::program_errors::ErrorDecoder;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, TransactionConfirmationStatus, TransactionStatus,
+    UiTransactionEncoding,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Commitment an execution must reach before the proposal counts as executed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationLevel {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl ConfirmationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfirmationLevel::Processed => "processed",
+            ConfirmationLevel::Confirmed => "confirmed",
+            ConfirmationLevel::Finalized => "finalized",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "processed" => Some(ConfirmationLevel::Processed),
+            "confirmed" => Some(ConfirmationLevel::Confirmed),
+            "finalized" => Some(ConfirmationLevel::Finalized),
+            _ => None,
+        }
+    }
+}
+
+/// Where a submitted execution transaction stands
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    /// Sent, not yet seen by the RPC node
+    Submitted,
+    Processed,
+    Confirmed,
+    Finalized,
+    /// Landed with an error
+    Failed,
+    /// Never landed before its blockhash expired
+    Dropped,
+}
+
+impl ConfirmationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfirmationStatus::Submitted => "submitted",
+            ConfirmationStatus::Processed => "processed",
+            ConfirmationStatus::Confirmed => "confirmed",
+            ConfirmationStatus::Finalized => "finalized",
+            ConfirmationStatus::Failed => "failed",
+            ConfirmationStatus::Dropped => "dropped",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "submitted" => Some(ConfirmationStatus::Submitted),
+            "processed" => Some(ConfirmationStatus::Processed),
+            "confirmed" => Some(ConfirmationStatus::Confirmed),
+            "finalized" => Some(ConfirmationStatus::Finalized),
+            "failed" => Some(ConfirmationStatus::Failed),
+            "dropped" => Some(ConfirmationStatus::Dropped),
+            _ => None,
+        }
+    }
+
+    /// Map an RPC signature status. Nodes omit `confirmation_status` for
+    /// rooted transactions from old ledger ranges, so a missing level is
+    /// treated as finalized.
+    pub fn from_transaction_status(status: &TransactionStatus) -> Self {
+        if status.err.is_some() {
+            return ConfirmationStatus::Failed;
+        }
+        match status.confirmation_status {
+            Some(TransactionConfirmationStatus::Processed) => ConfirmationStatus::Processed,
+            Some(TransactionConfirmationStatus::Confirmed) => ConfirmationStatus::Confirmed,
+            Some(TransactionConfirmationStatus::Finalized) | None => ConfirmationStatus::Finalized,
+        }
+    }
+
+    pub fn level(&self) -> Option<ConfirmationLevel> {
+        match self {
+            ConfirmationStatus::Processed => Some(ConfirmationLevel::Processed),
+            ConfirmationStatus::Confirmed => Some(ConfirmationLevel::Confirmed),
+            ConfirmationStatus::Finalized => Some(ConfirmationLevel::Finalized),
+            _ => None,
+        }
+    }
+
+    pub fn reaches(&self, target: ConfirmationLevel) -> bool {
+        self.level().is_some_and(|level| level >= target)
+    }
+}
+
+/// Latest known state of an execution transaction (see `execution_confirmations` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionConfirmation {
+    pub proposal_id: String,
+    pub signature: String,
+    pub status: ConfirmationStatus,
+    pub target_commitment: ConfirmationLevel,
+    pub rebroadcasts: u32,
+    /// Block height after which the transaction can no longer land
+    pub last_valid_block_height: Option<u64>,
+    pub error: Option<String>,
+    pub submitted_at: i64,
+    pub updated_at: i64,
+}
+
+/// What an execution transaction cost and where it landed, read back from
+/// the chain once it reached the target commitment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionDetails {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub fee_lamports: u64,
+    pub compute_units: Option<u64>,
+}
+
+impl TransactionDetails {
+    pub fn from_transaction(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Option<Self> {
+        let meta = transaction.transaction.meta.as_ref()?;
+        Some(Self {
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            fee_lamports: meta.fee,
+            compute_units: Option::from(meta.compute_units_consumed.clone()),
+        })
+    }
+}
+
+/// Links to the transaction on public explorers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExplorerLinks {
+    pub solana_explorer: String,
+    pub solscan: String,
+}
+
+impl ExplorerLinks {
+    /// `None` for local clusters, which public explorers cannot see
+    pub fn new(signature: &str, cluster: Cluster) -> Option<Self> {
+        let query = match cluster {
+            Cluster::Mainnet => "",
+            Cluster::Devnet => "?cluster=devnet",
+            Cluster::Testnet => "?cluster=testnet",
+            Cluster::Local => return None,
+        };
+        Some(Self {
+            solana_explorer: format!("https://explorer.solana.com/tx/{}{}", signature, query),
+            solscan: format!("https://solscan.io/tx/{}{}", signature, query),
+        })
+    }
+}
+
+/// Receipt of an executed upgrade (see `execution_confirmations` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionReceipt {
+    pub proposal_id: String,
+    pub signature: String,
+    pub status: ConfirmationStatus,
+    #[serde(flatten)]
+    pub details: TransactionDetails,
+    /// `mainnet-beta`, `devnet`, `testnet` or `local`
+    pub cluster: String,
+    pub explorer: Option<ExplorerLinks>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfirmationConfig {
+    pub target: ConfirmationLevel,
+    pub poll_interval: Duration,
+    /// How often an unseen transaction is resent while its blockhash is valid
+    pub rebroadcast_interval: Duration,
+    /// Give up on a signature with no known expiry after this long
+    pub timeout: Duration,
+}
+
+impl ConfirmationConfig {
+    pub fn from_env() -> Self {
+        let seconds = |key: &str, default: u64| {
+            Duration::from_secs(
+                std::env::var(key)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+
+        Self {
+            target: std::env::var("EXECUTION_COMMITMENT")
+                .ok()
+                .and_then(|v| ConfirmationLevel::parse(&v))
+                .unwrap_or(ConfirmationLevel::Finalized),
+            poll_interval: Duration::from_millis(500),
+            rebroadcast_interval: seconds("EXECUTION_REBROADCAST_SECONDS", 2),
+            timeout: seconds("EXECUTION_CONFIRMATION_TIMEOUT_SECONDS", 120),
+        }
+    }
+}
+
+/// Follows an execution transaction through processed, confirmed and
+/// finalized, returning once it reaches the configured commitment. Every
+/// change is recorded so the status endpoint can show progress while the
+/// execution job is still waiting.
+pub struct ConfirmationTracker {
+    rpc_client: Arc<AsyncRpcClient>,
+    database: Option<Arc<Database>>,
+    config: ConfirmationConfig,
+    error_decoder: Arc<ErrorDecoder>,
+    cluster: OnceCell<Cluster>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc_client: Arc<AsyncRpcClient>, config: ConfirmationConfig) -> Self {
+        Self {
+            rpc_client,
+            database: None,
+            config,
+            error_decoder: Arc::new(ErrorDecoder::new()),
+            cluster: OnceCell::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        Self::new(Arc::new(AsyncRpcClient::new(rpc_url)), ConfirmationConfig::from_env())
+    }
+
+    /// Persist confirmation progress into `execution_confirmations`
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Name program errors in failed executions using the programs' IDLs
+    pub fn with_error_decoder(mut self, error_decoder: Arc<ErrorDecoder>) -> Self {
+        self.error_decoder = error_decoder;
+        self
+    }
+
+    pub fn target(&self) -> ConfirmationLevel {
+        self.config.target
+    }
+
+    /// Send a signed transaction and follow it, resending it until it is seen
+    /// or `last_valid_block_height` passes
+    pub async fn submit(
+        &self,
+        proposal_id: &str,
+        transaction: &Transaction,
+        last_valid_block_height: u64,
+    ) -> Result<ExecutionConfirmation, UpgradeError> {
+        let record = self.send(proposal_id, transaction, last_valid_block_height).await?;
+        self.follow_sent(record, transaction).await
+    }
+
+    /// First half of `submit`: send and record the transaction, so preflight
+    /// errors reach the caller before following moves to the background
+    pub async fn send(
+        &self,
+        proposal_id: &str,
+        transaction: &Transaction,
+        last_valid_block_height: u64,
+    ) -> Result<ExecutionConfirmation, UpgradeError> {
+        let signature = self.broadcast(transaction).await?;
+        let record = self.new_record(proposal_id, &signature, Some(last_valid_block_height));
+        self.persist(&record).await;
+        Ok(record)
+    }
+
+    /// Second half of `submit`
+    pub async fn follow_sent(
+        &self,
+        record: ExecutionConfirmation,
+        transaction: &Transaction,
+    ) -> Result<ExecutionConfirmation, UpgradeError> {
+        self.follow(record, Some(transaction)).await
+    }
+
+    /// Follow a transaction someone else sent. Without the transaction it
+    /// cannot be resent, so it is reported dropped after the configured timeout.
+    pub async fn track(
+        &self,
+        proposal_id: &str,
+        signature: &Signature,
+    ) -> Result<ExecutionConfirmation, UpgradeError> {
+        let record = self.new_record(proposal_id, signature, None);
+        self.follow(record, None).await
+    }
+
+    /// Latest execution attempt for a proposal
+    pub async fn status(&self, proposal_id: &str) -> Result<Option<ExecutionConfirmation>, UpgradeError> {
+        match &self.database {
+            Some(database) => database.latest_execution_confirmation(proposal_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Receipt of the execution that landed for a proposal, if any
+    pub async fn receipt(&self, proposal_id: &str) -> Result<Option<ExecutionReceipt>, UpgradeError> {
+        match &self.database {
+            Some(database) => database.execution_receipt(proposal_id).await,
+            None => Ok(None),
+        }
+    }
+
+    fn new_record(
+        &self,
+        proposal_id: &str,
+        signature: &Signature,
+        last_valid_block_height: Option<u64>,
+    ) -> ExecutionConfirmation {
+        let now = chrono::Utc::now().timestamp();
+        ExecutionConfirmation {
+            proposal_id: proposal_id.to_string(),
+            signature: signature.to_string(),
+            status: ConfirmationStatus::Submitted,
+            target_commitment: self.config.target,
+            rebroadcasts: 0,
+            last_valid_block_height,
+            error: None,
+            submitted_at: now,
+            updated_at: now,
+        }
+    }
+
+    async fn follow(
+        &self,
+        mut record: ExecutionConfirmation,
+        transaction: Option<&Transaction>,
+    ) -> Result<ExecutionConfirmation, UpgradeError> {
+        let signature: Signature = record.signature
+            .parse()
+            .map_err(|_| UpgradeError::InternalError(format!("Invalid signature {}", record.signature)))?;
+        self.persist(&record).await;
+
+        let started = Instant::now();
+        let mut last_broadcast = Instant::now();
+
+        loop {
+            tokio::time::sleep(self.config.poll_interval).await;
+
+            let observed = self.rpc_client
+                .get_signature_statuses(&[signature])
+                .await
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to get signature status: {}", e)))?
+                .value
+                .into_iter()
+                .next()
+                .flatten();
+
+            if let Some(status) = observed {
+                let next = ConfirmationStatus::from_transaction_status(&status);
+                if next != record.status {
+                    tracing::info!(
+                        "Execution {} for proposal {} is {}",
+                        record.signature,
+                        record.proposal_id,
+                        next.as_str()
+                    );
+                    record.status = next;
+                    record.error = status.err.as_ref().map(|e| {
+                        self.error_decoder.describe(&e.to_string(), &instruction_programs(transaction))
+                    });
+                    self.persist(&record).await;
+                }
+
+                if next == ConfirmationStatus::Failed {
+                    return Err(UpgradeError::SolanaError(format!(
+                        "Execution transaction {} failed: {}",
+                        record.signature,
+                        record.error.as_deref().unwrap_or("unknown error")
+                    )));
+                }
+                if next.reaches(self.config.target) {
+                    self.record_details(&record, &signature).await;
+                    return Ok(record);
+                }
+                continue;
+            }
+
+            // Not seen (or dropped off a minority fork): resend while it can still land
+            let expired = match record.last_valid_block_height {
+                Some(last_valid) => self.block_height().await? > last_valid,
+                None => started.elapsed() > self.config.timeout,
+            };
+            if expired {
+                record.status = ConfirmationStatus::Dropped;
+                record.error = Some("Blockhash expired before the transaction landed".to_string());
+                self.persist(&record).await;
+                return Err(UpgradeError::SolanaError(format!(
+                    "Execution transaction {} was dropped",
+                    record.signature
+                )));
+            }
+
+            if let Some(transaction) = transaction {
+                if last_broadcast.elapsed() >= self.config.rebroadcast_interval {
+                    // A send failure here is not fatal; the next poll decides
+                    if let Err(e) = self.broadcast(transaction).await {
+                        tracing::warn!("Rebroadcast of {} failed: {}", record.signature, e);
+                    }
+                    last_broadcast = Instant::now();
+                    record.rebroadcasts += 1;
+                    self.persist(&record).await;
+                }
+            }
+        }
+    }
+
+    async fn broadcast(&self, transaction: &Transaction) -> Result<Signature, UpgradeError> {
+        self.rpc_client
+            .send_transaction_with_config(
+                transaction,
+                RpcSendTransactionConfig {
+                    // Rebroadcasting is handled here rather than by the RPC node
+                    max_retries: Some(0),
+                    ..RpcSendTransactionConfig::default()
+                },
+            )
+            .await
+            .map_err(|e| {
+                let error = self.error_decoder.describe(&e.to_string(), &instruction_programs(Some(transaction)));
+                UpgradeError::SolanaError(format!("Failed to send execution transaction: {}", error))
+            })
+    }
+
+    /// Store slot, block time, fee and compute units for the receipt. The
+    /// execution already landed, so failures here are logged, not returned.
+    async fn record_details(&self, record: &ExecutionConfirmation, signature: &Signature) {
+        let Some(database) = &self.database else {
+            return;
+        };
+        let details = match self.fetch_details(signature).await {
+            Ok(details) => details,
+            Err(e) => {
+                tracing::warn!("Failed to read execution details for {}: {}", record.signature, e);
+                return;
+            }
+        };
+        let cluster = match self.cluster.get_or_try_init(|| Cluster::detect(&self.rpc_client)).await {
+            Ok(cluster) => *cluster,
+            Err(e) => {
+                tracing::warn!("Failed to detect cluster for {}: {}", record.signature, e);
+                Cluster::Local
+            }
+        };
+        if let Err(e) = database.save_execution_details(&record.signature, &details, cluster).await {
+            tracing::warn!("Failed to record execution details for {}: {}", record.signature, e);
+        }
+    }
+
+    async fn fetch_details(&self, signature: &Signature) -> Result<TransactionDetails, UpgradeError> {
+        let transaction = self.rpc_client
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    // getTransaction does not serve processed transactions
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch transaction {}: {}", signature, e)))?;
+
+        TransactionDetails::from_transaction(&transaction)
+            .ok_or_else(|| UpgradeError::SolanaError(format!("Transaction {} has no status meta", signature)))
+    }
+
+    async fn block_height(&self) -> Result<u64, UpgradeError> {
+        self.rpc_client
+            .get_block_height()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get block height: {}", e)))
+    }
+
+    async fn persist(&self, record: &ExecutionConfirmation) {
+        if let Some(database) = &self.database {
+            let mut record = record.clone();
+            record.updated_at = chrono::Utc::now().timestamp();
+            if let Err(e) = database.upsert_execution_confirmation(&record).await {
+                tracing::warn!("Failed to record confirmation for {}: {}", record.signature, e);
+            }
+        }
+    }
+}
+
+/// Program invoked by each instruction, for decoding `Instruction N` errors
+fn instruction_programs(transaction: Option<&Transaction>) -> Vec<Pubkey> {
+    transaction
+        .map(|transaction| {
+            transaction
+                .message
+                .instructions
+                .iter()
+                .map(|instruction| *instruction.program_id(&transaction.message.account_keys))
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,125 @@
+use crate::proposal::{Proposal, ProposalManager, ProposalStatus};
+use crate::timelock::TimelockManager;
+use crate::websocket::{Notification, NotificationService, NotificationType};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Seconds between ticks until the final minute
+pub const TICK_INTERVAL_SECS: i64 = 60;
+
+/// Final stretch of a timelock that is ticked every second
+pub const FINAL_TICKS_SECS: i64 = 60;
+
+/// Seconds until the next tick: every minute, landing on the start of the
+/// final minute, then every second
+pub fn next_tick_delay(remaining_seconds: i64) -> i64 {
+    if remaining_seconds > FINAL_TICKS_SECS {
+        (remaining_seconds - FINAL_TICKS_SECS).min(TICK_INTERVAL_SECS)
+    } else {
+        1
+    }
+}
+
+/// Time left on a proposal's timelock, in chain time, so clients can count
+/// down without trusting their own clocks
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Countdown {
+    pub proposal_id: String,
+    pub status: ProposalStatus,
+    pub timelock_until: i64,
+    /// Chain time the countdown was computed at
+    pub chain_time: i64,
+    pub remaining_seconds: i64,
+    pub expired: bool,
+    /// Seconds until the next `timelock_tick`; `None` once expired
+    pub next_tick_seconds: Option<i64>,
+    /// System time minus chain time at the last clock sample
+    pub clock_drift_seconds: Option<i64>,
+}
+
+impl Countdown {
+    pub fn new(proposal: &Proposal, chain_time: i64, clock_drift_seconds: Option<i64>) -> Self {
+        let remaining_seconds = (proposal.timelock_until - chain_time).max(0);
+        let ticking = remaining_seconds > 0 && !proposal.status.is_terminal();
+        Self {
+            proposal_id: proposal.id.clone(),
+            status: proposal.status.clone(),
+            timelock_until: proposal.timelock_until,
+            chain_time,
+            remaining_seconds,
+            expired: remaining_seconds == 0,
+            next_tick_seconds: ticking.then(|| next_tick_delay(remaining_seconds)),
+            clock_drift_seconds,
+        }
+    }
+}
+
+/// Broadcasts `timelock_tick` events for open proposals: once a minute, every
+/// second in the final minute and once more at expiry. Ticks go to connected
+/// clients only and are not persisted, so each replica sends its own.
+pub struct CountdownTicker {
+    proposals: Arc<ProposalManager>,
+    timelocks: Arc<TimelockManager>,
+    notifications: Arc<NotificationService>,
+}
+
+impl CountdownTicker {
+    pub fn new(
+        proposals: Arc<ProposalManager>,
+        timelocks: Arc<TimelockManager>,
+        notifications: Arc<NotificationService>,
+    ) -> Self {
+        Self {
+            proposals,
+            timelocks,
+            notifications,
+        }
+    }
+
+    pub async fn run(&self) {
+        // Chain time of each ticking proposal's next tick
+        let mut next_ticks: HashMap<String, i64> = HashMap::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+            let proposals = match self.proposals.list_proposals().await {
+                Ok(proposals) => proposals,
+                Err(e) => {
+                    tracing::warn!("Countdown ticker could not list proposals: {}", e);
+                    continue;
+                }
+            };
+            let now = self.timelocks.now();
+            let drift = self.timelocks.drift_seconds();
+
+            let open: Vec<&Proposal> = proposals.iter().filter(|p| !p.status.is_terminal()).collect();
+            next_ticks.retain(|id, _| open.iter().any(|p| &p.id == id));
+
+            for proposal in open {
+                let due = match next_ticks.get(&proposal.id) {
+                    Some(next_tick) => now >= *next_tick,
+                    // Timelocks that had already run out when first seen are not ticked
+                    None => proposal.timelock_until > now,
+                };
+                if !due {
+                    continue;
+                }
+
+                let countdown = Countdown::new(proposal, now, drift);
+                match countdown.next_tick_seconds {
+                    Some(delay) => next_ticks.insert(proposal.id.clone(), now + delay),
+                    None => next_ticks.remove(&proposal.id),
+                };
+                self.notifications.notify_transient(Notification {
+                    notification_type: NotificationType::TimelockTick,
+                    proposal_id: Some(proposal.id.clone()),
+                    message: format!("Timelock ends in {}s", countdown.remaining_seconds),
+                    data: serde_json::json!(countdown),
+                });
+            }
+        }
+    }
+}
@@ -1,6 +1,37 @@
+use crate::activity::{ActivityEntry, ActivityKind};
+use crate::announcements::Announcement;
+use crate::api_keys::{ApiKey, ApiPrincipal, Scope};
+use crate::artifacts::Artifact;
+use crate::backfill::HistoricalUpgrade;
+use crate::buffer_watcher::{DetectedBuffer, DetectedBufferStatus};
+use crate::checklist::ChecklistCompletion;
+use crate::confirmation::{
+    ConfirmationLevel, ConfirmationStatus, ExecutionConfirmation, ExecutionReceipt, ExplorerLinks, TransactionDetails,
+};
+use crate::denylist::{DenylistEntry, DenylistKind};
+use crate::drafts::{DraftStatus, ProposalDraft};
 use crate::error::UpgradeError;
+use crate::execution_queue::{ExecutionAttempt, ExecutionJob};
+use crate::faucet::Cluster;
+use crate::fork_replay::ForkTestReport;
+use crate::incidents::Incident;
+use crate::indexer::{EventCursor, OnchainEvent};
+use crate::invariants::{InvariantPhase, InvariantResult};
+use crate::members::MemberProfile;
+use crate::metrics_history::{MetricPoint, WindowStats};
+use crate::proposal::{AuthorityChange, ProgramDeployment, ProposalEvent, ProposalFreeze, ProposalSearchHit, ProposalStatus};
+use crate::receipts::{ApprovalReceipt, SignedReceipt};
+use crate::rollback::{DrillReport, RollbackEvent};
+use crate::sampling::AccountBackup;
+use crate::snapshots::{AccountSetSnapshot, SnapshotDiff, SnapshotLabel};
+use crate::templates::Channel;
+use crate::two_person::ExecutionRequest;
+use crate::watch::{WatchExpression, WatchFinding, WatchKind};
+use crate::websocket::Event;
+use sqlx::postgres::PgListener;
 use sqlx::{PgPool, Row};
 use serde_json::Value;
+use std::collections::HashSet;
 
 pub struct Database {
     pool: PgPool,
@@ -21,12 +52,13 @@ impl Database {
         description: &str,
         timelock_until: i64,
         approval_threshold: i32,
+        version_tag: Option<&str>,
     ) -> Result<(), UpgradeError> {
         sqlx::query!(
             r#"
             INSERT INTO upgrade_proposals 
-            (proposal_id, proposer, program, new_buffer, description, timelock_until, approval_threshold, status)
-            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), $7, 'proposed')
+            (proposal_id, proposer, program, new_buffer, description, timelock_until, approval_threshold, status, version_tag)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), $7, 'proposed', $8)
             "#,
             proposal_id,
             proposer,
@@ -34,7 +66,8 @@ impl Database {
             new_buffer,
             description,
             timelock_until,
-            approval_threshold
+            approval_threshold,
+            version_tag
         )
         .execute(&self.pool)
         .await?;
@@ -42,61 +75,269 @@ impl Database {
         Ok(())
     }
 
-    pub async fn add_approval(
+    /// Mark a proposal as the first deployment of its program
+    pub async fn save_proposal_deployment(
         &self,
         proposal_id: &str,
-        approver: &str,
-        signature: Option<&str>,
+        deployment: &ProgramDeployment,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            "UPDATE upgrade_proposals SET kind = 'deploy', max_data_len = $1 WHERE proposal_id = $2",
+            deployment.max_data_len as i64,
+            proposal_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark a proposal as a change of its program's upgrade authority
+    pub async fn save_proposal_authority_change(
+        &self,
+        proposal_id: &str,
+        change: &AuthorityChange,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            "UPDATE upgrade_proposals SET kind = 'set_authority', new_authority = $1 WHERE proposal_id = $2",
+            change.new_authority,
+            proposal_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the dependency and supersession links a proposal declared
+    pub async fn save_proposal_links(
+        &self,
+        proposal_id: &str,
+        depends_on: &[String],
+        supersedes: &[String],
     ) -> Result<(), UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+        for (kind, linked) in [("depends_on", depends_on), ("supersedes", supersedes)] {
+            for linked_proposal_id in linked {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO proposal_links (proposal_id, linked_proposal_id, kind)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                    proposal_id,
+                    linked_proposal_id,
+                    kind
+                )
+                .execute(&mut tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Record an approval and the status it moves the proposal to
+    pub async fn approve_proposal_txn(
+        &self,
+        proposal_id: &str,
+        approver: &str,
+        event: ProposalEvent,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        let next = Self::apply_transition(&mut tx, proposal_id, event, None).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO approval_history (proposal_id, approver)
+            VALUES ($1, $2)
+            "#,
+            proposal_id,
+            approver
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(next)
+    }
+
+    /// Drop a withdrawn approval, keep the revocation for the record and
+    /// apply the status it leaves the proposal in
+    pub async fn revoke_approval_txn(
+        &self,
+        proposal_id: &str,
+        approver: &str,
+        reason: &str,
+        event: ProposalEvent,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        let next = Self::apply_transition(&mut tx, proposal_id, event, None).await?;
+        sqlx::query!(
+            "DELETE FROM approval_history WHERE proposal_id = $1 AND approver = $2",
+            proposal_id,
+            approver
+        )
+        .execute(&mut tx)
+        .await?;
+
         sqlx::query!(
             r#"
-            INSERT INTO approval_history (proposal_id, approver, signature)
+            INSERT INTO approval_revocations (proposal_id, approver, reason)
             VALUES ($1, $2, $3)
             "#,
             proposal_id,
             approver,
-            signature
+            reason
         )
-        .execute(&self.pool)
+        .execute(&mut tx)
         .await?;
 
-        Ok(())
+        tx.commit().await?;
+
+        Ok(next)
     }
 
-    pub async fn update_proposal_status(
+    /// Mark the proposal cancelled together with why
+    pub async fn cancel_proposal_txn(
         &self,
         proposal_id: &str,
-        status: &str,
+        reason: &str,
+        details: &str,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        let next = Self::apply_transition(&mut tx, proposal_id, ProposalEvent::Cancel, None).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO proposal_cancellations (proposal_id, reason, details)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (proposal_id) DO NOTHING
+            "#,
+            proposal_id,
+            reason,
+            details
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(next)
+    }
+
+    /// Mark the proposal frozen together with the finding holding it
+    pub async fn freeze_proposal_txn(
+        &self,
+        proposal_id: &str,
+        freeze: &ProposalFreeze,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        let next = Self::apply_transition(&mut tx, proposal_id, ProposalEvent::Freeze, None).await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO proposal_freezes (proposal_id, finding, source, frozen_by, frozen_from, frozen_at)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6))
+            "#,
+            proposal_id,
+            freeze.finding,
+            freeze.source.as_str(),
+            freeze.frozen_by,
+            freeze.frozen_from.as_str(),
+            freeze.frozen_at as f64
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(next)
+    }
+
+    /// Mark the proposal executed and write its successful `upgrade_history`
+    /// row, so neither exists without the other. The IDL hash and account
+    /// diff are attached to the row afterwards.
+    pub async fn execute_proposal_txn(
+        &self,
+        proposal_id: &str,
+        executed_at: i64,
+        program: &str,
+        new_program_hash: &str,
+        kind: &str,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        let next = Self::apply_transition(&mut tx, proposal_id, ProposalEvent::Execute, Some(executed_at)).await?;
+        // A deployment is version 1; upgrades count on from the program's last
+        // versioned row, and stay unversioned for programs deployed elsewhere.
+        // Authority changes leave the code, and so the version, alone.
+        sqlx::query!(
+            r#"
+            INSERT INTO upgrade_history
+            (proposal_id, program, new_program_hash, executed_at, success, deployment, kind, version)
+            SELECT $1, $2::varchar, $3, to_timestamp($4), TRUE, $5::varchar = 'deploy', $5::varchar,
+                   CASE $5::varchar
+                       WHEN 'deploy' THEN 1
+                       WHEN 'upgrade' THEN (SELECT MAX(version) + 1 FROM upgrade_history WHERE program = $2::varchar AND success)
+                   END
+            "#,
+            proposal_id,
+            program,
+            new_program_hash,
+            executed_at as f64,
+            kind
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(next)
+    }
+
+    /// Apply `event` to the stored proposal status inside `tx`, rejecting
+    /// illegal transitions. The row stays locked until `tx` ends.
+    async fn apply_transition(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        proposal_id: &str,
+        event: ProposalEvent,
         executed_at: Option<i64>,
-    ) -> Result<(), UpgradeError> {
-        if let Some(executed_at) = executed_at {
-            sqlx::query!(
-                r#"
-                UPDATE upgrade_proposals 
-                SET status = $1, executed_at = to_timestamp($2)
-                WHERE proposal_id = $3
-                "#,
-                status,
-                executed_at,
-                proposal_id
-            )
-            .execute(&self.pool)
-            .await?;
-        } else {
-            sqlx::query!(
-                r#"
-                UPDATE upgrade_proposals 
-                SET status = $1
-                WHERE proposal_id = $2
-                "#,
-                status,
-                proposal_id
-            )
-            .execute(&self.pool)
-            .await?;
-        }
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let current = sqlx::query!(
+            r#"
+            SELECT status FROM upgrade_proposals
+            WHERE proposal_id = $1
+            FOR UPDATE
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
 
-        Ok(())
+        let current = ProposalStatus::parse(&current.status).ok_or_else(|| {
+            UpgradeError::InternalError(format!("Unknown proposal status: {}", current.status))
+        })?;
+        let next = current.transition(event)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE upgrade_proposals
+            SET status = $1, executed_at = COALESCE(to_timestamp($2), executed_at)
+            WHERE proposal_id = $3
+            "#,
+            next.as_str(),
+            executed_at,
+            proposal_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(next)
     }
 
     pub async fn get_proposal(&self, proposal_id: &str) -> Result<Value, UpgradeError> {
@@ -236,19 +477,34 @@ impl Database {
         new_program_hash: &str,
         success: bool,
         error_message: Option<&str>,
+        idl_hash: Option<&str>,
     ) -> Result<(), UpgradeError> {
         sqlx::query!(
             r#"
             INSERT INTO upgrade_history 
-            (proposal_id, program, old_program_hash, new_program_hash, executed_at, success, error_message)
-            VALUES ($1, $2, $3, $4, NOW(), $5, $6)
+            (proposal_id, program, old_program_hash, new_program_hash, executed_at, success, error_message, idl_hash)
+            VALUES ($1, $2, $3, $4, NOW(), $5, $6, $7)
             "#,
             proposal_id,
             program,
             old_program_hash,
             new_program_hash,
             success,
-            error_message
+            error_message,
+            idl_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Attach the hash of the IDL published after the upgrade to its history row
+    pub async fn save_upgrade_idl_hash(&self, proposal_id: &str, idl_hash: &str) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            "UPDATE upgrade_history SET idl_hash = $1 WHERE proposal_id = $2 AND success",
+            idl_hash,
+            proposal_id
         )
         .execute(&self.pool)
         .await?;
@@ -256,6 +512,42 @@ impl Database {
         Ok(())
     }
 
+    /// Close the proposal's open freeze and put back the status it was frozen from
+    pub async fn clear_freeze(
+        &self,
+        proposal_id: &str,
+        cleared_by: &str,
+        resolution: &str,
+        restored: &ProposalStatus,
+    ) -> Result<(), UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE proposal_freezes
+            SET cleared_by = $1, resolution = $2, cleared_at = NOW()
+            WHERE proposal_id = $3 AND cleared_at IS NULL
+            "#,
+            cleared_by,
+            resolution,
+            proposal_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE upgrade_proposals SET status = $1 WHERE proposal_id = $2 AND status = 'frozen'",
+            restored.as_str(),
+            proposal_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     pub async fn record_rollback_event(
         &self,
         proposal_id: &str,
@@ -263,12 +555,13 @@ impl Database {
         rollback_reason: &str,
         positions_closed: i32,
         funds_returned: bool,
-    ) -> Result<(), UpgradeError> {
-        sqlx::query!(
+    ) -> Result<String, UpgradeError> {
+        let row = sqlx::query!(
             r#"
             INSERT INTO rollback_events 
             (proposal_id, old_program_id, rollback_reason, positions_closed, funds_returned)
             VALUES ($1, $2, $3, $4, $5)
+            RETURNING id::TEXT as "id!"
             "#,
             proposal_id,
             old_program_id,
@@ -276,9 +569,2487 @@ impl Database {
             positions_closed,
             funds_returned
         )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.id)
+    }
+
+    pub async fn get_rollback_event(&self, id: &str) -> Result<Option<RollbackEvent>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id::TEXT as "id!", proposal_id, old_program_id, rollback_reason,
+                   EXTRACT(epoch FROM rollback_at)::BIGINT as "rollback_at!"
+            FROM rollback_events
+            WHERE id::TEXT = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| RollbackEvent {
+            id: row.id,
+            proposal_id: row.proposal_id,
+            old_program_id: row.old_program_id,
+            reason: row.rollback_reason,
+            rolled_back_at: row.rollback_at,
+        }))
+    }
+
+    pub async fn list_upgrade_history(&self, limit: i64) -> Result<Vec<Value>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT proposal_id, program, old_program_hash, new_program_hash,
+                   EXTRACT(epoch FROM executed_at) as executed_at,
+                   success, rollback_required, idl_hash, account_diff,
+                   signature, buffer, imported, deployment, kind, version
+            FROM upgrade_history
+            ORDER BY executed_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                serde_json::json!({
+                    "proposal_id": row.proposal_id,
+                    "program": row.program,
+                    "old_program_hash": row.old_program_hash,
+                    "new_program_hash": row.new_program_hash,
+                    "executed_at": row.executed_at,
+                    "success": row.success,
+                    "rollback_required": row.rollback_required,
+                    "idl_hash": row.idl_hash,
+                    "account_diff": row.account_diff,
+                    "signature": row.signature,
+                    "buffer": row.buffer,
+                    "imported": row.imported,
+                    "deployment": row.deployment,
+                    "kind": row.kind,
+                    "version": row.version,
+                })
+            })
+            .collect())
+    }
+
+    /// Add an upgrade found on chain to the history. Returns false when its
+    /// transaction is already recorded, or was sent by this service's executor.
+    pub async fn import_upgrade_history(&self, upgrade: &HistoricalUpgrade) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO upgrade_history
+            (program, executed_at, success, error_message, signature, slot, buffer, authority, imported)
+            SELECT $1, to_timestamp($2), $3, $4, $5, $6, $7, $8, TRUE
+            WHERE NOT EXISTS (SELECT 1 FROM execution_confirmations WHERE signature = $5::varchar)
+            ON CONFLICT (signature) DO NOTHING
+            "#,
+            upgrade.program,
+            upgrade.executed_at as f64,
+            upgrade.success,
+            upgrade.error,
+            upgrade.signature,
+            upgrade.slot as i64,
+            upgrade.buffer,
+            upgrade.authority
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queue an execution job, returning the existing live job if one is already queued,
+    /// running or done for this proposal. A forced enqueue marks the existing job forced.
+    pub async fn enqueue_execution_job(
+        &self,
+        job_id: &str,
+        proposal_id: &str,
+        max_attempts: i32,
+        forced: bool,
+    ) -> Result<ExecutionJob, UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO execution_jobs (job_id, proposal_id, status, max_attempts, forced)
+            VALUES ($1, $2, 'queued', $3, $4)
+            ON CONFLICT (proposal_id) WHERE status IN ('queued', 'running', 'done')
+            DO UPDATE SET forced = execution_jobs.forced OR EXCLUDED.forced
+            "#,
+            job_id,
+            proposal_id,
+            max_attempts,
+            forced
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_execution_job_for_proposal(proposal_id)
+            .await?
+            .ok_or_else(|| UpgradeError::InternalError("Execution job missing after enqueue".to_string()))
+    }
+
+    /// Atomically claim the oldest queued job, marking it running
+    pub async fn claim_next_execution_job(&self) -> Result<Option<ExecutionJob>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE execution_jobs
+            SET status = 'running', attempts = attempts + 1, locked_at = NOW()
+            WHERE job_id = (
+                SELECT job_id FROM execution_jobs
+                WHERE status = 'queued' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at, created_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING job_id, proposal_id, status, attempts, max_attempts, last_error, forced,
+                      EXTRACT(epoch FROM created_at)::BIGINT as created_at,
+                      EXTRACT(epoch FROM completed_at)::BIGINT as completed_at,
+                      EXTRACT(epoch FROM next_attempt_at)::BIGINT as next_attempt_at
+            "#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ExecutionJob {
+            job_id: row.job_id,
+            proposal_id: row.proposal_id,
+            status: row.status,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            last_error: row.last_error,
+            forced: row.forced,
+            created_at: row.created_at.unwrap_or_default(),
+            completed_at: row.completed_at,
+            next_attempt_at: row.next_attempt_at.unwrap_or_default(),
+        }))
+    }
+
+    pub async fn complete_execution_job(&self, job_id: &str) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            UPDATE execution_jobs
+            SET status = 'done', last_error = NULL, locked_at = NULL, completed_at = NOW()
+            WHERE job_id = $1
+            "#,
+            job_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt. The job is requeued to run again in
+    /// `retry_after_seconds` unless `terminal` is set.
+    pub async fn fail_execution_job(
+        &self,
+        job_id: &str,
+        error: &str,
+        terminal: bool,
+        retry_after_seconds: i64,
+    ) -> Result<(), UpgradeError> {
+        let status = if terminal { "failed" } else { "queued" };
+        sqlx::query!(
+            r#"
+            UPDATE execution_jobs
+            SET status = $1, last_error = $2, locked_at = NULL,
+                completed_at = CASE WHEN $1::varchar = 'failed' THEN NOW() ELSE completed_at END,
+                next_attempt_at = NOW() + make_interval(secs => $4)
+            WHERE job_id = $3
+            "#,
+            status,
+            error,
+            job_id,
+            retry_after_seconds as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Keep the outcome of one execution attempt
+    pub async fn record_execution_attempt(
+        &self,
+        job_id: &str,
+        attempt: &ExecutionAttempt,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO execution_job_attempts (job_id, attempt, outcome, error, duration_ms, retry_at)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6))
+            "#,
+            job_id,
+            attempt.attempt,
+            attempt.outcome,
+            attempt.error,
+            attempt.duration_ms,
+            attempt.retry_at
+        )
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub async fn list_execution_attempts(&self, job_id: &str) -> Result<Vec<ExecutionAttempt>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT attempt, outcome, error, duration_ms,
+                   EXTRACT(epoch FROM retry_at)::BIGINT as retry_at,
+                   EXTRACT(epoch FROM finished_at)::BIGINT as finished_at
+            FROM execution_job_attempts
+            WHERE job_id = $1
+            ORDER BY finished_at
+            "#,
+            job_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExecutionAttempt {
+                attempt: row.attempt,
+                outcome: row.outcome,
+                error: row.error,
+                duration_ms: row.duration_ms,
+                retry_at: row.retry_at,
+                finished_at: row.finished_at.unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Return jobs left running by a crashed worker to the queue
+    pub async fn requeue_stale_execution_jobs(&self, stale_after_seconds: i64) -> Result<u64, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE execution_jobs
+            SET status = 'queued', locked_at = NULL
+            WHERE status = 'running' AND locked_at < NOW() - make_interval(secs => $1)
+            "#,
+            stale_after_seconds as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_execution_job_for_proposal(
+        &self,
+        proposal_id: &str,
+    ) -> Result<Option<ExecutionJob>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT job_id, proposal_id, status, attempts, max_attempts, last_error, forced,
+                   EXTRACT(epoch FROM created_at)::BIGINT as created_at,
+                   EXTRACT(epoch FROM completed_at)::BIGINT as completed_at,
+                   EXTRACT(epoch FROM next_attempt_at)::BIGINT as next_attempt_at
+            FROM execution_jobs
+            WHERE proposal_id = $1
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ExecutionJob {
+            job_id: row.job_id,
+            proposal_id: row.proposal_id,
+            status: row.status,
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            last_error: row.last_error,
+            forced: row.forced,
+            created_at: row.created_at.unwrap_or_default(),
+            completed_at: row.completed_at,
+            next_attempt_at: row.next_attempt_at.unwrap_or_default(),
+        }))
+    }
+
+    pub async fn record_migration_transaction_cost(
+        &self,
+        migration_id: &str,
+        account_pubkey: &str,
+        signature: &str,
+        compute_units: i64,
+        fee_lamports: i64,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO migration_transaction_costs
+            (migration_id, account_pubkey, signature, compute_units, fee_lamports)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            migration_id,
+            account_pubkey,
+            signature,
+            compute_units,
+            fee_lamports
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_migration_cost_breakdown(&self, migration_id: &str) -> Result<Value, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "transactions!",
+                   COUNT(DISTINCT account_pubkey) as "accounts!",
+                   COALESCE(SUM(compute_units), 0)::BIGINT as "total_compute_units!",
+                   COALESCE(SUM(fee_lamports), 0)::BIGINT as "total_fee_lamports!",
+                   COALESCE(MAX(compute_units), 0)::BIGINT as "max_compute_units!"
+            FROM migration_transaction_costs
+            WHERE migration_id = $1
+            "#,
+            migration_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let per_account = |total: i64| {
+            if row.accounts > 0 {
+                total as f64 / row.accounts as f64
+            } else {
+                0.0
+            }
+        };
+
+        Ok(serde_json::json!({
+            "migration_id": migration_id,
+            "transactions": row.transactions,
+            "accounts": row.accounts,
+            "total_compute_units": row.total_compute_units,
+            "total_fee_lamports": row.total_fee_lamports,
+            "max_compute_units_per_transaction": row.max_compute_units,
+            "avg_compute_units_per_account": per_account(row.total_compute_units),
+            "avg_fee_lamports_per_account": per_account(row.total_fee_lamports),
+        }))
+    }
+
+    pub async fn save_artifact(
+        &self,
+        program_hash: &str,
+        program_name: &str,
+        size_bytes: i64,
+        source_commit: Option<&str>,
+        toolchain: &Value,
+        storage_key: &str,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO program_artifacts
+            (program_hash, program_name, size_bytes, source_commit, toolchain, storage_key)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (program_hash) DO NOTHING
+            "#,
+            program_hash,
+            program_name,
+            size_bytes,
+            source_commit,
+            toolchain,
+            storage_key
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_artifact(&self, program_hash: &str) -> Result<Option<Artifact>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT program_hash, program_name, size_bytes, source_commit, toolchain, storage_key,
+                   EXTRACT(epoch FROM created_at)::BIGINT as created_at
+            FROM program_artifacts
+            WHERE program_hash = $1
+            "#,
+            program_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Artifact {
+            program_hash: row.program_hash,
+            program_name: row.program_name,
+            size_bytes: row.size_bytes,
+            source_commit: row.source_commit,
+            toolchain: row.toolchain,
+            storage_key: row.storage_key,
+            created_at: row.created_at.unwrap_or_default(),
+        }))
+    }
+
+    pub async fn list_artifacts(&self) -> Result<Vec<Artifact>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT program_hash, program_name, size_bytes, source_commit, toolchain, storage_key,
+                   EXTRACT(epoch FROM created_at)::BIGINT as created_at
+            FROM program_artifacts
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Artifact {
+                program_hash: row.program_hash,
+                program_name: row.program_name,
+                size_bytes: row.size_bytes,
+                source_commit: row.source_commit,
+                toolchain: row.toolchain,
+                storage_key: row.storage_key,
+                created_at: row.created_at.unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Cheap round trip used by the dependency health probe
+    pub async fn ping(&self) -> Result<(), UpgradeError> {
+        sqlx::query!("SELECT 1 as one")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Tables in the connected schema, checked by `--preflight`
+    pub async fn list_tables(&self) -> Result<Vec<String>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT table_name::TEXT as "table_name!"
+            FROM information_schema.tables
+            WHERE table_schema = current_schema()
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.table_name).collect())
+    }
+
+    pub async fn insert_event(&self, event: &Event) -> Result<i64, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO events (event_type, proposal_id, message, data, created_at)
+            VALUES ($1, $2, $3, $4, to_timestamp($5))
+            RETURNING seq
+            "#,
+            event.event_type,
+            event.proposal_id,
+            event.message,
+            event.data,
+            event.timestamp
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.seq)
+    }
+
+    pub async fn list_events_since(&self, since_seq: i64, limit: i64) -> Result<Vec<Event>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT seq, event_type, proposal_id, message, data,
+                   EXTRACT(epoch FROM created_at)::BIGINT as "created_at!"
+            FROM events
+            WHERE seq > $1
+            ORDER BY seq ASC
+            LIMIT $2
+            "#,
+            since_seq,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Event {
+                seq: Some(row.seq),
+                event_type: row.event_type,
+                proposal_id: row.proposal_id,
+                message: row.message,
+                data: row.data,
+                timestamp: row.created_at,
+            })
+            .collect())
+    }
+
+    pub async fn get_event(&self, seq: i64) -> Result<Option<Event>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT seq, event_type, proposal_id, message, data,
+                   EXTRACT(epoch FROM created_at)::BIGINT as "created_at!"
+            FROM events
+            WHERE seq = $1
+            "#,
+            seq
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Event {
+            seq: Some(row.seq),
+            event_type: row.event_type,
+            proposal_id: row.proposal_id,
+            message: row.message,
+            data: row.data,
+            timestamp: row.created_at,
+        }))
+    }
+
+    /// `NOTIFY channel, payload`, delivered to every listening connection
+    pub async fn notify_channel(&self, channel: &str, payload: &str) -> Result<(), UpgradeError> {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(channel)
+            .bind(payload)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// A dedicated connection subscribed to `channel`
+    pub async fn listen(&self, channel: &str) -> Result<PgListener, UpgradeError> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(channel).await?;
+        Ok(listener)
+    }
+
+    /// Chain timestamp a stored proposal's timelock ends at
+    pub async fn get_timelock_until(&self, proposal_id: &str) -> Result<Option<i64>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT EXTRACT(epoch FROM timelock_until)::BIGINT as "timelock_until!"
+            FROM upgrade_proposals
+            WHERE proposal_id = $1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| row.timelock_until))
+    }
+
+    /// Store events not stored yet, returning how many were new
+    pub async fn insert_onchain_events(&self, events: &[OnchainEvent]) -> Result<u64, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for event in events {
+            let result = sqlx::query!(
+                r#"
+                INSERT INTO onchain_events
+                (signature, slot, event_index, event_name, proposal, data, block_time)
+                VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7))
+                ON CONFLICT (signature, event_index) DO NOTHING
+                "#,
+                event.signature,
+                event.slot as i64,
+                event.event_index,
+                event.name,
+                event.proposal,
+                event.data,
+                event.block_time.map(|time| time as f64)
+            )
+            .execute(&mut tx)
+            .await?;
+            inserted += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(inserted)
+    }
+
+    /// Stored events with `id > after`, oldest first
+    pub async fn list_onchain_events(
+        &self,
+        proposal: Option<&str>,
+        event_name: Option<&str>,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<OnchainEvent>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, signature, slot, event_index, event_name, proposal, data,
+                   EXTRACT(epoch FROM block_time)::BIGINT as block_time
+            FROM onchain_events
+            WHERE id > $1
+              AND ($2::TEXT IS NULL OR proposal = $2)
+              AND ($3::TEXT IS NULL OR event_name = $3)
+            ORDER BY id ASC
+            LIMIT $4
+            "#,
+            after,
+            proposal,
+            event_name,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| OnchainEvent {
+                id: Some(row.id),
+                signature: row.signature,
+                slot: row.slot as u64,
+                event_index: row.event_index,
+                name: row.event_name,
+                proposal: row.proposal,
+                data: row.data,
+                block_time: row.block_time,
+            })
+            .collect())
+    }
+
+    pub async fn get_event_cursor(&self, program: &str) -> Result<Option<EventCursor>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT program, last_signature, last_slot,
+                   EXTRACT(epoch FROM updated_at)::BIGINT as "updated_at!"
+            FROM onchain_event_cursors
+            WHERE program = $1
+            "#,
+            program
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| EventCursor {
+            program: row.program,
+            last_signature: row.last_signature,
+            last_slot: row.last_slot as u64,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    pub async fn save_event_cursor(&self, program: &str, last_signature: &str, last_slot: u64) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO onchain_event_cursors (program, last_signature, last_slot, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (program) DO UPDATE
+            SET last_signature = EXCLUDED.last_signature,
+                last_slot = EXCLUDED.last_slot,
+                updated_at = NOW()
+            "#,
+            program,
+            last_signature,
+            last_slot as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_invariant_result(&self, result: &InvariantResult) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO invariant_results (subject_id, check_name, phase, passed, detail, checked_at)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6))
+            "#,
+            result.subject_id,
+            result.name,
+            result.phase.as_str(),
+            result.passed,
+            result.detail,
+            result.checked_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_invariant_results(&self, subject_id: &str) -> Result<Vec<InvariantResult>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT subject_id, check_name, phase, passed, detail,
+                   EXTRACT(epoch FROM checked_at)::BIGINT as "checked_at!"
+            FROM invariant_results
+            WHERE subject_id = $1
+            ORDER BY checked_at ASC
+            "#,
+            subject_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(InvariantResult {
+                    subject_id: row.subject_id,
+                    name: row.check_name,
+                    phase: InvariantPhase::parse(&row.phase)?,
+                    passed: row.passed,
+                    detail: row.detail,
+                    checked_at: row.checked_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn insert_approval_receipt(&self, signed: &SignedReceipt) -> Result<(), UpgradeError> {
+        let receipt = &signed.receipt;
+        sqlx::query!(
+            r#"
+            INSERT INTO approval_receipts
+                (proposal_id, member, proposal_pda, buffer, buffer_hash, approved_at, signer, signature)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6), $7, $8)
+            "#,
+            receipt.proposal_id,
+            receipt.member,
+            receipt.proposal_pda,
+            receipt.buffer,
+            receipt.buffer_hash,
+            receipt.approved_at,
+            signed.signer,
+            signed.signature
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_approval_receipts(&self, proposal_id: &str) -> Result<Vec<SignedReceipt>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT proposal_id, member, proposal_pda, buffer, buffer_hash,
+                   EXTRACT(epoch FROM approved_at)::BIGINT as "approved_at!",
+                   signer, signature
+            FROM approval_receipts
+            WHERE proposal_id = $1
+            ORDER BY approved_at ASC
+            "#,
+            proposal_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let receipt = ApprovalReceipt {
+                    version: crate::receipts::RECEIPT_VERSION.to_string(),
+                    member: row.member,
+                    proposal_id: row.proposal_id,
+                    proposal_pda: row.proposal_pda,
+                    buffer: row.buffer,
+                    buffer_hash: row.buffer_hash,
+                    approved_at: row.approved_at,
+                };
+                SignedReceipt {
+                    message: receipt.message(),
+                    receipt,
+                    signer: row.signer,
+                    signature: row.signature,
+                }
+            })
+            .collect())
+    }
+
+    /// Ranked full-text search over description, version tag, program and proposer
+    pub async fn search_proposals(&self, query: &str, limit: i64) -> Result<Vec<ProposalSearchHit>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            WITH q AS (
+                SELECT websearch_to_tsquery('english', $1) || websearch_to_tsquery('simple', $1) AS query
+            )
+            SELECT proposal_id, program, proposer, version_tag, status, description,
+                   EXTRACT(epoch FROM proposed_at)::BIGINT as "proposed_at!",
+                   ts_rank(search_vector, q.query) as "rank!",
+                   ts_headline('english', description, q.query,
+                               'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') as "highlight!"
+            FROM upgrade_proposals, q
+            WHERE search_vector @@ q.query
+            ORDER BY 8 DESC, proposed_at DESC
+            LIMIT $2
+            "#,
+            query,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ProposalSearchHit {
+                proposal_id: row.proposal_id,
+                program: row.program,
+                proposer: row.proposer,
+                version_tag: row.version_tag,
+                status: row.status,
+                description: row.description,
+                proposed_at: row.proposed_at,
+                rank: row.rank,
+                highlight: row.highlight,
+            })
+            .collect())
+    }
+
+    pub async fn insert_account_snapshot(&self, snapshot: &AccountSetSnapshot) -> Result<(), UpgradeError> {
+        let size_buckets = serde_json::to_value(&snapshot.size_buckets)
+            .map_err(|e| UpgradeError::InternalError(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO account_snapshots
+                (snapshot_id, program, proposal_id, label, account_count, total_lamports, size_buckets, taken_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+            "#,
+            snapshot.snapshot_id,
+            snapshot.program,
+            snapshot.proposal_id,
+            snapshot.label.as_str(),
+            snapshot.account_count as i64,
+            snapshot.total_lamports as i64,
+            size_buckets,
+            snapshot.taken_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_account_snapshot(&self, snapshot_id: &str) -> Result<Option<AccountSetSnapshot>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT snapshot_id, program, proposal_id, label, account_count, total_lamports, size_buckets,
+                   EXTRACT(epoch FROM taken_at)::BIGINT as "taken_at!"
+            FROM account_snapshots
+            WHERE snapshot_id = $1
+            "#,
+            snapshot_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            Some(AccountSetSnapshot {
+                snapshot_id: row.snapshot_id,
+                program: row.program,
+                proposal_id: row.proposal_id,
+                label: SnapshotLabel::parse(&row.label)?,
+                account_count: row.account_count as u64,
+                total_lamports: row.total_lamports as u64,
+                size_buckets: serde_json::from_value(row.size_buckets).ok()?,
+                taken_at: row.taken_at,
+            })
+        }))
+    }
+
+    /// Most recent snapshot of `label` taken for a proposal
+    pub async fn latest_account_snapshot(
+        &self,
+        proposal_id: &str,
+        label: SnapshotLabel,
+    ) -> Result<Option<AccountSetSnapshot>, UpgradeError> {
+        let snapshot_id = sqlx::query_scalar!(
+            r#"
+            SELECT snapshot_id FROM account_snapshots
+            WHERE proposal_id = $1 AND label = $2
+            ORDER BY taken_at DESC
+            LIMIT 1
+            "#,
+            proposal_id,
+            label.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match snapshot_id {
+            Some(snapshot_id) => self.get_account_snapshot(&snapshot_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Attach a pre/post account-set comparison to the proposal's upgrade record
+    pub async fn save_upgrade_account_diff(&self, proposal_id: &str, diff: &SnapshotDiff) -> Result<(), UpgradeError> {
+        let diff = serde_json::to_value(diff).map_err(|e| UpgradeError::InternalError(e.to_string()))?;
+
+        sqlx::query!(
+            "UPDATE upgrade_history SET account_diff = $1 WHERE proposal_id = $2",
+            diff,
+            proposal_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_upgrade_account_diff(&self, proposal_id: &str) -> Result<Option<SnapshotDiff>, UpgradeError> {
+        let diff = sqlx::query_scalar!(
+            r#"
+            SELECT account_diff FROM upgrade_history
+            WHERE proposal_id = $1 AND account_diff IS NOT NULL
+            ORDER BY executed_at DESC
+            LIMIT 1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(diff.and_then(|diff| serde_json::from_value(diff).ok()))
+    }
+
+    /// Record a checklist sign-off; signing off an item again replaces the earlier record
+    pub async fn upsert_checklist_completion(
+        &self,
+        proposal_id: &str,
+        item: &str,
+        role: &str,
+        completed_by: Option<&str>,
+        evidence: Option<&str>,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO proposal_checklist (proposal_id, item, role, completed_by, evidence)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (proposal_id, item) DO UPDATE
+            SET role = EXCLUDED.role,
+                completed_by = EXCLUDED.completed_by,
+                evidence = EXCLUDED.evidence,
+                completed_at = NOW()
+            "#,
+            proposal_id,
+            item,
+            role,
+            completed_by,
+            evidence
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_checklist_completions(&self, proposal_id: &str) -> Result<Vec<ChecklistCompletion>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT proposal_id, item, role, completed_by, evidence,
+                   EXTRACT(epoch FROM completed_at)::BIGINT as "completed_at!"
+            FROM proposal_checklist
+            WHERE proposal_id = $1
+            ORDER BY completed_at ASC
+            "#,
+            proposal_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ChecklistCompletion {
+                proposal_id: row.proposal_id,
+                item: row.item,
+                role: row.role,
+                completed_by: row.completed_by,
+                evidence: row.evidence,
+                completed_at: row.completed_at,
+            })
+            .collect())
+    }
+
+    /// Returns false if the buffer was already recorded
+    pub async fn insert_detected_buffer(&self, detected: &DetectedBuffer) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO detected_buffers
+                (buffer, authority, program_len, program_hash, lamports, status, detected_at)
+            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7))
+            ON CONFLICT (buffer) DO NOTHING
+            "#,
+            detected.buffer,
+            detected.authority,
+            detected.program_len as i64,
+            detected.program_hash,
+            detected.lamports as i64,
+            detected.status.as_str(),
+            detected.detected_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_detected_buffer(&self, buffer: &str) -> Result<Option<DetectedBuffer>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT buffer, authority, program_len, program_hash, lamports, status, proposal_id,
+                   EXTRACT(epoch FROM detected_at)::BIGINT as "detected_at!"
+            FROM detected_buffers
+            WHERE buffer = $1
+            "#,
+            buffer
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            Some(DetectedBuffer {
+                buffer: row.buffer,
+                authority: row.authority,
+                program_len: row.program_len as u64,
+                program_hash: row.program_hash,
+                lamports: row.lamports as u64,
+                status: DetectedBufferStatus::parse(&row.status)?,
+                proposal_id: row.proposal_id,
+                detected_at: row.detected_at,
+            })
+        }))
+    }
+
+    pub async fn list_detected_buffers(
+        &self,
+        status: Option<DetectedBufferStatus>,
+    ) -> Result<Vec<DetectedBuffer>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT buffer, authority, program_len, program_hash, lamports, status, proposal_id,
+                   EXTRACT(epoch FROM detected_at)::BIGINT as "detected_at!"
+            FROM detected_buffers
+            WHERE $1::VARCHAR IS NULL OR status = $1
+            ORDER BY detected_at DESC
+            "#,
+            status.map(|s| s.as_str())
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(DetectedBuffer {
+                    buffer: row.buffer,
+                    authority: row.authority,
+                    program_len: row.program_len as u64,
+                    program_hash: row.program_hash,
+                    lamports: row.lamports as u64,
+                    status: DetectedBufferStatus::parse(&row.status)?,
+                    proposal_id: row.proposal_id,
+                    detected_at: row.detected_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn update_detected_buffer_status(
+        &self,
+        buffer: &str,
+        status: DetectedBufferStatus,
+        proposal_id: Option<&str>,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            UPDATE detected_buffers
+            SET status = $2, proposal_id = COALESCE($3, proposal_id)
+            WHERE buffer = $1
+            "#,
+            buffer,
+            status.as_str(),
+            proposal_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn upsert_execution_confirmation(&self, record: &ExecutionConfirmation) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO execution_confirmations
+                (signature, proposal_id, status, target_commitment, rebroadcasts,
+                 last_valid_block_height, error, submitted_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8), to_timestamp($9))
+            ON CONFLICT (signature) DO UPDATE
+            SET status = EXCLUDED.status,
+                rebroadcasts = EXCLUDED.rebroadcasts,
+                error = EXCLUDED.error,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            record.signature,
+            record.proposal_id,
+            record.status.as_str(),
+            record.target_commitment.as_str(),
+            record.rebroadcasts as i32,
+            record.last_valid_block_height.map(|h| h as i64),
+            record.error,
+            record.submitted_at,
+            record.updated_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn latest_execution_confirmation(
+        &self,
+        proposal_id: &str,
+    ) -> Result<Option<ExecutionConfirmation>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT signature, proposal_id, status, target_commitment, rebroadcasts,
+                   last_valid_block_height, error,
+                   EXTRACT(epoch FROM submitted_at)::BIGINT as "submitted_at!",
+                   EXTRACT(epoch FROM updated_at)::BIGINT as "updated_at!"
+            FROM execution_confirmations
+            WHERE proposal_id = $1
+            ORDER BY submitted_at DESC
+            LIMIT 1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            Some(ExecutionConfirmation {
+                proposal_id: row.proposal_id,
+                signature: row.signature,
+                status: ConfirmationStatus::parse(&row.status)?,
+                target_commitment: ConfirmationLevel::parse(&row.target_commitment)?,
+                rebroadcasts: row.rebroadcasts as u32,
+                last_valid_block_height: row.last_valid_block_height.map(|h| h as u64),
+                error: row.error,
+                submitted_at: row.submitted_at,
+                updated_at: row.updated_at,
+            })
+        }))
+    }
+
+    pub async fn save_execution_details(
+        &self,
+        signature: &str,
+        details: &TransactionDetails,
+        cluster: Cluster,
+    ) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            UPDATE execution_confirmations
+            SET slot = $1, block_time = to_timestamp($2), fee_lamports = $3, compute_units = $4, cluster = $5
+            WHERE signature = $6
+            "#,
+            details.slot as i64,
+            details.block_time,
+            details.fee_lamports as i64,
+            details.compute_units.map(|units| units as i64),
+            cluster.as_str(),
+            signature
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Latest execution of a proposal whose details were recorded
+    pub async fn execution_receipt(&self, proposal_id: &str) -> Result<Option<ExecutionReceipt>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT proposal_id, signature, status, slot as "slot!", fee_lamports as "fee_lamports!",
+                   compute_units, cluster as "cluster!",
+                   EXTRACT(epoch FROM block_time)::BIGINT as block_time
+            FROM execution_confirmations
+            WHERE proposal_id = $1 AND slot IS NOT NULL
+            ORDER BY submitted_at DESC
+            LIMIT 1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            let cluster = Cluster::parse(&row.cluster)?;
+            Some(ExecutionReceipt {
+                explorer: ExplorerLinks::new(&row.signature, cluster),
+                proposal_id: row.proposal_id,
+                signature: row.signature,
+                status: ConfirmationStatus::parse(&row.status)?,
+                details: TransactionDetails {
+                    slot: row.slot as u64,
+                    block_time: row.block_time,
+                    fee_lamports: row.fee_lamports as u64,
+                    compute_units: row.compute_units.map(|units| units as u64),
+                },
+                cluster: row.cluster,
+            })
+        }))
+    }
+
+    pub async fn insert_draft(&self, draft: &ProposalDraft) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO proposal_drafts (draft_id, author, status, draft, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, to_timestamp($5), to_timestamp($6))
+            "#,
+            draft.id,
+            draft.author,
+            draft.status.as_str(),
+            serde_json::json!(draft),
+            draft.created_at,
+            draft.updated_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_draft(&self, draft_id: &str) -> Result<Option<ProposalDraft>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT draft FROM proposal_drafts
+            WHERE draft_id = $1
+            "#,
+            draft_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| decode_draft(row.draft)).transpose()
+    }
+
+    /// The draft `proposal_id` was submitted from, if it came from one
+    pub async fn get_draft_for_proposal(&self, proposal_id: &str) -> Result<Option<ProposalDraft>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT draft FROM proposal_drafts
+            WHERE status = 'submitted' AND draft->>'proposal_id' = $1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| decode_draft(row.draft)).transpose()
+    }
+
+    pub async fn list_drafts(&self, status: Option<DraftStatus>) -> Result<Vec<ProposalDraft>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT draft FROM proposal_drafts
+            WHERE $1::VARCHAR IS NULL OR status = $1
+            ORDER BY updated_at DESC
+            "#,
+            status.map(|s| s.as_str())
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|row| decode_draft(row.draft)).collect()
+    }
+
+    /// Apply `change` to a draft under a row lock, so concurrent edits,
+    /// reviews and submits are serialized. Nothing is written if it fails.
+    pub async fn modify_draft<F>(&self, draft_id: &str, change: F) -> Result<ProposalDraft, UpgradeError>
+    where
+        F: FnOnce(&mut ProposalDraft) -> Result<(), UpgradeError>,
+    {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT draft FROM proposal_drafts
+            WHERE draft_id = $1
+            FOR UPDATE
+            "#,
+            draft_id
+        )
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or_else(|| UpgradeError::DraftNotFound(draft_id.to_string()))?;
+
+        let mut draft = decode_draft(row.draft)?;
+        change(&mut draft)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE proposal_drafts
+            SET status = $1, draft = $2, updated_at = to_timestamp($3)
+            WHERE draft_id = $4
+            "#,
+            draft.status.as_str(),
+            serde_json::json!(draft),
+            draft.updated_at,
+            draft_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(draft)
+    }
+
+    pub async fn insert_incident(&self, incident: &Incident) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO incidents (id, status, program, incident, opened_at, updated_at)
+            VALUES ($1, $2, $3, $4, to_timestamp($5), to_timestamp($6))
+            "#,
+            incident.id,
+            incident.status.as_str(),
+            incident.program,
+            serde_json::json!(incident),
+            incident.opened_at,
+            incident.updated_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_incident(&self, id: &str) -> Result<Option<Incident>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT incident FROM incidents
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| decode_incident(row.incident)).transpose()
+    }
+
+    /// The newest unresolved incident on `program`
+    pub async fn find_active_incident(&self, program: &str) -> Result<Option<Incident>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT incident FROM incidents
+            WHERE program = $1 AND status <> 'resolved'
+            ORDER BY opened_at DESC
+            LIMIT 1
+            "#,
+            program
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| decode_incident(row.incident)).transpose()
+    }
+
+    /// Newest first
+    pub async fn list_incidents(&self, active_only: bool, limit: i64) -> Result<Vec<Incident>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT incident FROM incidents
+            WHERE NOT $1 OR status <> 'resolved'
+            ORDER BY opened_at DESC
+            LIMIT $2
+            "#,
+            active_only,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|row| decode_incident(row.incident)).collect()
+    }
+
+    /// Unresolved incidents, and those resolved after `since`
+    pub async fn list_incidents_since(&self, since: i64) -> Result<Vec<Incident>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT incident FROM incidents
+            WHERE status <> 'resolved' OR resolved_at >= to_timestamp($1)
+            ORDER BY opened_at DESC
+            "#,
+            since as f64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|row| decode_incident(row.incident)).collect()
+    }
+
+    /// Apply `change` to an incident under a row lock, like `modify_draft`
+    pub async fn modify_incident<F>(&self, id: &str, change: F) -> Result<Incident, UpgradeError>
+    where
+        F: FnOnce(&mut Incident) -> Result<(), UpgradeError>,
+    {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT incident FROM incidents
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or_else(|| UpgradeError::IncidentNotFound(id.to_string()))?;
+
+        let mut incident = decode_incident(row.incident)?;
+        change(&mut incident)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE incidents
+            SET status = $1, incident = $2, updated_at = to_timestamp($3), resolved_at = to_timestamp($4)
+            WHERE id = $5
+            "#,
+            incident.status.as_str(),
+            serde_json::json!(incident),
+            incident.updated_at,
+            incident.resolved_at,
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(incident)
+    }
+
+    /// Returns false if the same hash or pattern is already listed
+    pub async fn insert_denylist_entry(&self, entry: &DenylistEntry) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO binary_denylist (id, kind, value, reason, added_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, to_timestamp($6))
+            ON CONFLICT (kind, value) DO NOTHING
+            "#,
+            entry.id,
+            entry.kind.as_str(),
+            entry.value,
+            entry.reason,
+            entry.added_by,
+            entry.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn list_denylist(&self) -> Result<Vec<DenylistEntry>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, kind, value, reason, added_by,
+                   EXTRACT(epoch FROM created_at)::BIGINT as "created_at!"
+            FROM binary_denylist
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(DenylistEntry {
+                    id: row.id,
+                    kind: DenylistKind::parse(&row.kind)?,
+                    value: row.value,
+                    reason: row.reason,
+                    added_by: row.added_by,
+                    created_at: row.created_at,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns false if no entry has this id
+    pub async fn remove_denylist_entry(&self, id: &str) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM binary_denylist WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn upsert_member_profile(&self, profile: &MemberProfile) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO member_profiles (member, name, organization, contact, notification_channel, reminders_enabled, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+            ON CONFLICT (member) DO UPDATE
+            SET name = $2, organization = $3, contact = $4, notification_channel = $5,
+                reminders_enabled = $6, updated_by = $7, updated_at = to_timestamp($8)
+            "#,
+            profile.member,
+            profile.name,
+            profile.organization,
+            profile.contact,
+            profile.notification_channel,
+            profile.reminders_enabled,
+            profile.updated_by,
+            profile.updated_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_member_profiles(&self) -> Result<Vec<MemberProfile>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT member, name, organization, contact, notification_channel, reminders_enabled, updated_by,
+                   EXTRACT(epoch FROM updated_at)::BIGINT as "updated_at!"
+            FROM member_profiles
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MemberProfile {
+                member: row.member,
+                name: row.name,
+                organization: row.organization,
+                contact: row.contact,
+                notification_channel: row.notification_channel,
+                reminders_enabled: row.reminders_enabled,
+                updated_by: row.updated_by,
+                updated_at: row.updated_at,
+            })
+            .collect())
+    }
+
+    /// Returns false if the member has no profile
+    pub async fn delete_member_profile(&self, member: &str) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM member_profiles WHERE member = $1
+            "#,
+            member
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn insert_watch_expression(&self, expression: &WatchExpression) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO watch_expressions (id, name, matcher, kind, min_matches, window_seconds, rollback, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, to_timestamp($9))
+            "#,
+            expression.id,
+            expression.name,
+            serde_json::json!(expression.matcher),
+            expression.kind.as_str(),
+            expression.min_matches as i32,
+            expression.window_seconds,
+            expression.rollback,
+            expression.created_by,
+            expression.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_watch_expressions(&self) -> Result<Vec<WatchExpression>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, matcher, kind, min_matches, window_seconds, rollback, created_by,
+                   EXTRACT(epoch FROM created_at)::BIGINT as "created_at!"
+            FROM watch_expressions
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(WatchExpression {
+                    id: row.id,
+                    name: row.name,
+                    matcher: serde_json::from_value(row.matcher).ok()?,
+                    kind: WatchKind::parse(&row.kind)?,
+                    min_matches: row.min_matches as u32,
+                    window_seconds: row.window_seconds,
+                    rollback: row.rollback,
+                    created_by: row.created_by,
+                    created_at: row.created_at,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns false if no expression has this id
+    pub async fn remove_watch_expression(&self, id: &str) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM watch_expressions WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn record_watch_finding(&self, finding: &WatchFinding) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO watch_findings (proposal_id, expression_id, name, matches, anomaly, detail, rolled_back, evaluated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, to_timestamp($8))
+            ON CONFLICT (proposal_id, expression_id) DO UPDATE
+            SET matches = $4, anomaly = $5, detail = $6, rolled_back = $7, evaluated_at = to_timestamp($8)
+            "#,
+            finding.proposal_id,
+            finding.expression_id,
+            finding.name,
+            finding.matches as i32,
+            finding.anomaly,
+            finding.detail,
+            finding.rolled_back,
+            finding.evaluated_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Everything recorded about `proposal_id` across the service's tables,
+    /// oldest first. `onchain_proposal` is its upgrade-manager proposal PDA.
+    pub async fn list_proposal_activity(
+        &self,
+        proposal_id: &str,
+        onchain_proposal: Option<&str>,
+    ) -> Result<Vec<ActivityEntry>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT kind as "kind!", actor, summary as "summary!", data as "data!", at as "at!"
+            FROM (
+                SELECT 'approval'::TEXT as kind, approver::TEXT as actor, 'Approved'::TEXT as summary,
+                       jsonb_build_object('signature', signature) as data,
+                       EXTRACT(epoch FROM approved_at)::BIGINT as at
+                FROM approval_history WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'revocation', approver, 'Withdrew approval',
+                       jsonb_build_object('reason', reason),
+                       EXTRACT(epoch FROM revoked_at)::BIGINT
+                FROM approval_revocations WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'checklist', completed_by, 'Completed ' || item,
+                       jsonb_build_object('item', item, 'role', role, 'evidence', evidence),
+                       EXTRACT(epoch FROM completed_at)::BIGINT
+                FROM proposal_checklist WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'audit', NULL,
+                       CASE WHEN passed THEN 'Invariant passed: ' ELSE 'Invariant failed: ' END || check_name,
+                       jsonb_build_object('phase', phase, 'passed', passed, 'detail', detail),
+                       EXTRACT(epoch FROM checked_at)::BIGINT
+                FROM invariant_results WHERE subject_id = $1
+                UNION ALL
+                SELECT 'audit', NULL, CASE WHEN passed THEN 'Fork test passed' ELSE 'Fork test failed' END,
+                       jsonb_build_object('fork_test_id', id, 'passed', passed),
+                       EXTRACT(epoch FROM ran_at)::BIGINT
+                FROM fork_tests WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'audit', frozen_by, 'Frozen: ' || finding,
+                       jsonb_build_object('source', source, 'frozen_from', frozen_from),
+                       EXTRACT(epoch FROM frozen_at)::BIGINT
+                FROM proposal_freezes WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'audit', cleared_by, 'Unfrozen: ' || COALESCE(resolution, ''),
+                       jsonb_build_object('finding', finding),
+                       EXTRACT(epoch FROM cleared_at)::BIGINT
+                FROM proposal_freezes WHERE proposal_id = $1 AND cleared_at IS NOT NULL
+                UNION ALL
+                SELECT 'audit', NULL, 'Watch ' || name || ': ' || detail,
+                       jsonb_build_object('expression_id', expression_id, 'matches', matches,
+                                          'anomaly', anomaly, 'rolled_back', rolled_back),
+                       EXTRACT(epoch FROM evaluated_at)::BIGINT
+                FROM watch_findings WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'execution', initiated_by, 'Requested execution',
+                       jsonb_build_object('forced', forced, 'expires_at', EXTRACT(epoch FROM expires_at)::BIGINT),
+                       EXTRACT(epoch FROM initiated_at)::BIGINT
+                FROM execution_requests WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'execution', NULL, 'Execution attempt ' || a.attempt || ' ' || a.outcome,
+                       jsonb_build_object('job_id', a.job_id, 'attempt', a.attempt, 'outcome', a.outcome,
+                                          'error', a.error, 'duration_ms', a.duration_ms),
+                       EXTRACT(epoch FROM a.finished_at)::BIGINT
+                FROM execution_job_attempts a
+                JOIN execution_jobs j ON j.job_id = a.job_id
+                WHERE j.proposal_id = $1
+                UNION ALL
+                SELECT 'execution', NULL, 'Transaction ' || status,
+                       jsonb_build_object('signature', signature, 'status', status,
+                                          'rebroadcasts', rebroadcasts, 'error', error),
+                       EXTRACT(epoch FROM submitted_at)::BIGINT
+                FROM execution_confirmations WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'rollback', NULL, 'Rolled back: ' || rollback_reason,
+                       jsonb_build_object('incident_id', id::TEXT, 'old_program_id', old_program_id),
+                       EXTRACT(epoch FROM rollback_at)::BIGINT
+                FROM rollback_events WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'cancellation', NULL, 'Cancelled: ' || reason,
+                       jsonb_build_object('reason', reason, 'details', details),
+                       EXTRACT(epoch FROM cancelled_at)::BIGINT
+                FROM proposal_cancellations WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'notification', NULL, message,
+                       jsonb_build_object('seq', seq, 'type', event_type, 'data', data),
+                       EXTRACT(epoch FROM created_at)::BIGINT
+                FROM events WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'notification', member, 'Approval reminder sent by ' || channel,
+                       jsonb_build_object('channel', channel),
+                       EXTRACT(epoch FROM sent_at)::BIGINT
+                FROM approval_reminders WHERE proposal_id = $1
+                UNION ALL
+                SELECT 'onchain', NULL, event_name,
+                       jsonb_build_object('signature', signature, 'slot', slot, 'event', data),
+                       EXTRACT(epoch FROM COALESCE(block_time, indexed_at))::BIGINT
+                FROM onchain_events WHERE $2::TEXT IS NOT NULL AND proposal = $2
+            ) activity
+            ORDER BY at
+            "#,
+            proposal_id,
+            onchain_proposal
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(ActivityEntry {
+                    at: row.at,
+                    kind: ActivityKind::parse(&row.kind)?,
+                    actor: row.actor,
+                    summary: row.summary,
+                    data: row.data,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn list_watch_findings(&self, proposal_id: &str) -> Result<Vec<WatchFinding>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT proposal_id, expression_id, name, matches, anomaly, detail, rolled_back,
+                   EXTRACT(epoch FROM evaluated_at)::BIGINT as "evaluated_at!"
+            FROM watch_findings
+            WHERE proposal_id = $1
+            ORDER BY evaluated_at
+            "#,
+            proposal_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| WatchFinding {
+                proposal_id: row.proposal_id,
+                expression_id: row.expression_id,
+                name: row.name,
+                matches: row.matches as u32,
+                anomaly: row.anomaly,
+                detail: row.detail,
+                rolled_back: row.rolled_back,
+                evaluated_at: row.evaluated_at,
+            })
+            .collect())
+    }
+
+    /// Store a new execution request, replacing any earlier one for the proposal
+    pub async fn save_execution_request(&self, request: &ExecutionRequest) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO execution_requests (proposal_id, initiated_by, initiated_at, expires_at, forced)
+            VALUES ($1, $2, to_timestamp($3), to_timestamp($4), $5)
+            ON CONFLICT (proposal_id) DO UPDATE
+            SET initiated_by = $2, initiated_at = to_timestamp($3), expires_at = to_timestamp($4), forced = $5
+            "#,
+            request.proposal_id,
+            request.initiated_by,
+            request.initiated_at,
+            request.expires_at,
+            request.forced
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_execution_request(&self, proposal_id: &str) -> Result<Option<ExecutionRequest>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT proposal_id, initiated_by, forced,
+                   EXTRACT(epoch FROM initiated_at)::BIGINT as "initiated_at!",
+                   EXTRACT(epoch FROM expires_at)::BIGINT as "expires_at!"
+            FROM execution_requests
+            WHERE proposal_id = $1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ExecutionRequest {
+            proposal_id: row.proposal_id,
+            initiated_by: row.initiated_by,
+            initiated_at: row.initiated_at,
+            expires_at: row.expires_at,
+            forced: row.forced,
+        }))
+    }
+
+    /// Delete and return the proposal's execution request if `check` accepts
+    /// it. The row is locked meanwhile, so two confirmations cannot both win.
+    pub async fn take_execution_request<F>(&self, proposal_id: &str, check: F) -> Result<ExecutionRequest, UpgradeError>
+    where
+        F: FnOnce(&ExecutionRequest) -> Result<(), UpgradeError>,
+    {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT proposal_id, initiated_by, forced,
+                   EXTRACT(epoch FROM initiated_at)::BIGINT as "initiated_at!",
+                   EXTRACT(epoch FROM expires_at)::BIGINT as "expires_at!"
+            FROM execution_requests
+            WHERE proposal_id = $1
+            FOR UPDATE
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or_else(|| {
+            UpgradeError::InvalidRequest(format!("No execution of {} awaiting confirmation", proposal_id))
+        })?;
+
+        let request = ExecutionRequest {
+            proposal_id: row.proposal_id,
+            initiated_by: row.initiated_by,
+            initiated_at: row.initiated_at,
+            expires_at: row.expires_at,
+            forced: row.forced,
+        };
+        check(&request)?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM execution_requests WHERE proposal_id = $1
+            "#,
+            proposal_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(request)
+    }
+
+    /// Proposals created and executed, and approvals cast, in `(from, to]`
+    pub async fn metrics_window_stats(&self, from: i64, to: i64) -> Result<WindowStats, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM upgrade_proposals
+                 WHERE proposed_at > to_timestamp($1) AND proposed_at <= to_timestamp($2)) as "proposals_created!",
+                (SELECT COUNT(*) FROM upgrade_proposals
+                 WHERE executed_at > to_timestamp($1) AND executed_at <= to_timestamp($2)) as "proposals_executed!",
+                (SELECT COUNT(*) FROM approval_history
+                 WHERE approved_at > to_timestamp($1) AND approved_at <= to_timestamp($2)) as "approvals!",
+                (SELECT AVG(EXTRACT(epoch FROM a.approved_at - p.proposed_at))::FLOAT8
+                 FROM approval_history a
+                 JOIN upgrade_proposals p ON p.proposal_id = a.proposal_id
+                 WHERE a.approved_at > to_timestamp($1) AND a.approved_at <= to_timestamp($2)) as average_approval_latency
+            "#,
+            from as f64,
+            to as f64
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(WindowStats {
+            proposals_created: row.proposals_created,
+            proposals_executed: row.proposals_executed,
+            approvals: row.approvals,
+            average_approval_latency_seconds: row.average_approval_latency,
+        })
+    }
+
+    pub async fn insert_metric_points(&self, points: &[MetricPoint]) -> Result<(), UpgradeError> {
+        let metrics: Vec<String> = points.iter().map(|p| p.metric.clone()).collect();
+        let values: Vec<f64> = points.iter().map(|p| p.value).collect();
+        let recorded_at: Vec<f64> = points.iter().map(|p| p.recorded_at as f64).collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO metrics_timeseries (metric, value, recorded_at)
+            SELECT metric, value, to_timestamp(recorded_at)
+            FROM UNNEST($1::TEXT[], $2::FLOAT8[], $3::FLOAT8[]) AS t(metric, value, recorded_at)
+            "#,
+            &metrics,
+            &values,
+            &recorded_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Points of `metric` in `[from, to)`, averaged into `step`-second buckets
+    pub async fn metric_history(
+        &self,
+        metric: &str,
+        from: i64,
+        to: i64,
+        step: i64,
+    ) -> Result<Vec<MetricPoint>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT (FLOOR(EXTRACT(epoch FROM recorded_at) / $4) * $4)::BIGINT as "bucket!",
+                   AVG(value) as "value!"
+            FROM metrics_timeseries
+            WHERE metric = $1 AND recorded_at >= to_timestamp($2) AND recorded_at < to_timestamp($3)
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            metric,
+            from as f64,
+            to as f64,
+            step as f64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| MetricPoint {
+                metric: metric.to_string(),
+                value: row.value,
+                recorded_at: row.bucket,
+            })
+            .collect())
+    }
+
+    pub async fn prune_metric_points(&self, before: i64) -> Result<u64, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM metrics_timeseries WHERE recorded_at < to_timestamp($1)
+            "#,
+            before as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns false if the proposal was already announced
+    pub async fn insert_announcement(&self, announcement: &Announcement) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO upgrade_announcements (proposal_id, announcement, window_end, published_at)
+            VALUES ($1, $2, to_timestamp($3), to_timestamp($4))
+            ON CONFLICT (proposal_id) DO NOTHING
+            "#,
+            announcement.proposal_id,
+            serde_json::json!(announcement),
+            announcement.window_end as f64,
+            announcement.published_at as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Claim the reminder due at `due_at` for `member`; false if it was already sent
+    pub async fn claim_approval_reminder(
+        &self,
+        proposal_id: &str,
+        member: &str,
+        due_at: i64,
+        channel: Channel,
+    ) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO approval_reminders (proposal_id, member, due_at, channel)
+            VALUES ($1, $2, to_timestamp($3), $4)
+            ON CONFLICT (proposal_id, member, due_at) DO NOTHING
+            "#,
+            proposal_id,
+            member,
+            due_at as f64,
+            channel.as_str()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn insert_reminder_opt_out(&self, proposal_id: &str, member: &str) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO approval_reminder_opt_outs (proposal_id, member)
+            VALUES ($1, $2)
+            ON CONFLICT (proposal_id, member) DO NOTHING
+            "#,
+            proposal_id,
+            member
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_reminder_opt_out(&self, proposal_id: &str, member: &str) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            "DELETE FROM approval_reminder_opt_outs WHERE proposal_id = $1 AND member = $2",
+            proposal_id,
+            member
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Members who muted reminders for `proposal_id`
+    pub async fn list_reminder_opt_outs(&self, proposal_id: &str) -> Result<HashSet<String>, UpgradeError> {
+        let members = sqlx::query_scalar!(
+            "SELECT member FROM approval_reminder_opt_outs WHERE proposal_id = $1",
+            proposal_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members.into_iter().collect())
+    }
+
+    /// Announcements whose window ends at or after `now`, soonest first
+    pub async fn list_announcements(&self, now: i64) -> Result<Vec<Announcement>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT announcement FROM upgrade_announcements
+            WHERE window_end >= to_timestamp($1)
+            ORDER BY window_end ASC
+            "#,
+            now as f64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                serde_json::from_value(row.announcement)
+                    .map_err(|e| UpgradeError::InternalError(format!("Corrupt announcement: {}", e)))
+            })
+            .collect()
+    }
+
+    /// Average duration of the last 10 completed account migrations
+    pub async fn average_migration_seconds(&self) -> Result<Option<i64>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT AVG(EXTRACT(epoch FROM completed_at - started_at))::BIGINT as average_seconds
+            FROM (
+                SELECT started_at, completed_at FROM migration_progress
+                WHERE status = 'completed' AND completed_at IS NOT NULL
+                ORDER BY completed_at DESC
+                LIMIT 10
+            ) recent
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.average_seconds)
+    }
+
+    pub async fn insert_api_key(&self, key: &ApiKey, key_hash: &str) -> Result<(), UpgradeError> {
+        let scopes: Vec<String> = key.scopes.iter().map(|scope| scope.as_str().to_string()).collect();
+        sqlx::query!(
+            r#"
+            INSERT INTO api_keys (id, name, prefix, key_hash, scopes, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7))
+            "#,
+            key.id,
+            key.name,
+            key.prefix,
+            key_hash,
+            &scopes,
+            key.created_by,
+            key.created_at as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Look up an unrevoked key by its hash, or by the hash it had before its
+    /// last rotation while the grace period lasts, and mark it used
+    pub async fn touch_api_key(&self, key_hash: &str) -> Result<Option<ApiPrincipal>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE api_keys SET last_used_at = NOW()
+            WHERE revoked_at IS NULL
+              AND (key_hash = $1 OR (previous_key_hash = $1 AND previous_expires_at > NOW()))
+            RETURNING id, name, scopes
+            "#,
+            key_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ApiPrincipal {
+            key_id: row.id,
+            name: row.name,
+            scopes: row.scopes.iter().filter_map(|scope| Scope::parse(scope)).collect(),
+        }))
+    }
+
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, prefix, scopes, created_by,
+                   EXTRACT(epoch FROM created_at)::BIGINT as "created_at!",
+                   EXTRACT(epoch FROM rotated_at)::BIGINT as rotated_at,
+                   EXTRACT(epoch FROM last_used_at)::BIGINT as last_used_at,
+                   EXTRACT(epoch FROM revoked_at)::BIGINT as revoked_at
+            FROM api_keys
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ApiKey {
+                id: row.id,
+                name: row.name,
+                prefix: row.prefix,
+                scopes: row.scopes.iter().filter_map(|scope| Scope::parse(scope)).collect(),
+                created_by: row.created_by,
+                created_at: row.created_at,
+                rotated_at: row.rotated_at,
+                last_used_at: row.last_used_at,
+                revoked_at: row.revoked_at,
+            })
+            .collect())
+    }
+
+    /// Swap in a new key hash, keeping the old one valid until `grace_until`.
+    /// Returns `None` if there is no unrevoked key with this id.
+    pub async fn rotate_api_key(
+        &self,
+        id: &str,
+        key_hash: &str,
+        prefix: &str,
+        grace_until: i64,
+    ) -> Result<Option<ApiKey>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET previous_key_hash = key_hash,
+                previous_expires_at = to_timestamp($4),
+                key_hash = $2,
+                prefix = $3,
+                rotated_at = NOW()
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING id, name, prefix, scopes, created_by,
+                      EXTRACT(epoch FROM created_at)::BIGINT as "created_at!",
+                      EXTRACT(epoch FROM rotated_at)::BIGINT as rotated_at,
+                      EXTRACT(epoch FROM last_used_at)::BIGINT as last_used_at
+            "#,
+            id,
+            key_hash,
+            prefix,
+            grace_until as f64
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ApiKey {
+            id: row.id,
+            name: row.name,
+            prefix: row.prefix,
+            scopes: row.scopes.iter().filter_map(|scope| Scope::parse(scope)).collect(),
+            created_by: row.created_by,
+            created_at: row.created_at,
+            rotated_at: row.rotated_at,
+            last_used_at: row.last_used_at,
+            revoked_at: None,
+        }))
+    }
+
+    /// Returns false if no unrevoked key has this id
+    pub async fn revoke_api_key(&self, id: &str) -> Result<bool, UpgradeError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE api_keys SET revoked_at = NOW()
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Give `program`'s migration lock to `migration_id` and return the other
+    /// migration holding it, if any. A held lock is only replaced when `force`
+    /// is set. Concurrent acquirers are serialized by a transaction-scoped
+    /// advisory lock on the program.
+    pub async fn acquire_migration_lock(
+        &self,
+        program: &str,
+        migration_id: &str,
+        force: bool,
+    ) -> Result<Option<String>, UpgradeError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("migration_lock:{}", program))
+            .execute(&mut tx)
+            .await?;
+
+        let holder = sqlx::query!(
+            r#"
+            SELECT migration_id FROM migration_locks WHERE program = $1
+            "#,
+            program
+        )
+        .fetch_optional(&mut tx)
+        .await?
+        .map(|row| row.migration_id)
+        .filter(|holder| holder != migration_id);
+
+        if holder.is_none() || force {
+            sqlx::query!(
+                r#"
+                INSERT INTO migration_locks (program, migration_id, acquired_at)
+                VALUES ($1, $2, NOW())
+                ON CONFLICT (program) DO UPDATE
+                SET migration_id = EXCLUDED.migration_id, acquired_at = EXCLUDED.acquired_at
+                "#,
+                program,
+                migration_id
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(holder)
+    }
+
+    /// Keep an account's pre-migration data. The first backup of an account
+    /// wins, so a retried migration does not overwrite the original.
+    pub async fn save_account_backup(&self, migration_id: &str, backup: &AccountBackup) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO account_backups (migration_id, account_pubkey, account_type, data)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (migration_id, account_pubkey) DO NOTHING
+            "#,
+            migration_id,
+            backup.account,
+            backup.account_type,
+            backup.data
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_account_backups(&self, migration_id: &str) -> Result<i64, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM account_backups WHERE migration_id = $1
+            "#,
+            migration_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    /// `limit` backups of the migration, picked at random
+    pub async fn sample_account_backups(&self, migration_id: &str, limit: i64) -> Result<Vec<AccountBackup>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT account_pubkey, account_type, data FROM account_backups
+            WHERE migration_id = $1
+            ORDER BY random()
+            LIMIT $2
+            "#,
+            migration_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AccountBackup {
+                account: row.account_pubkey,
+                account_type: row.account_type,
+                data: row.data,
+            })
+            .collect())
+    }
+
+    /// Release `program`'s migration lock if `migration_id` still holds it
+    pub async fn release_migration_lock(&self, program: &str, migration_id: &str) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM migration_locks WHERE program = $1 AND migration_id = $2
+            "#,
+            program,
+            migration_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn insert_rollback_drill(&self, report: &DrillReport) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO rollback_drills (id, program, target, passed, duration_ms, report, started_at)
+            VALUES ($1, $2, $3, $4, $5, $6, to_timestamp($7))
+            "#,
+            report.id,
+            report.program,
+            report.target,
+            report.passed,
+            report.duration_ms as i64,
+            serde_json::json!(report),
+            report.started_at as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent drills first
+    pub async fn list_rollback_drills(&self, limit: i64) -> Result<Vec<DrillReport>, UpgradeError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT report FROM rollback_drills
+            ORDER BY started_at DESC
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                serde_json::from_value(row.report)
+                    .map_err(|e| UpgradeError::InternalError(format!("Corrupt drill report: {}", e)))
+            })
+            .collect()
+    }
+
+    pub async fn insert_fork_test(&self, report: &ForkTestReport) -> Result<(), UpgradeError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO fork_tests (id, proposal_id, passed, report, ran_at)
+            VALUES ($1, $2, $3, $4, to_timestamp($5))
+            "#,
+            report.id,
+            report.proposal_id,
+            report.passed,
+            serde_json::json!(report),
+            report.ran_at as f64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn latest_fork_test(&self, proposal_id: &str) -> Result<Option<ForkTestReport>, UpgradeError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT report FROM fork_tests
+            WHERE proposal_id = $1
+            ORDER BY ran_at DESC
+            LIMIT 1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            serde_json::from_value(row.report)
+                .map_err(|e| UpgradeError::InternalError(format!("Corrupt fork test report: {}", e)))
+        })
+        .transpose()
+    }
+}
+
+fn decode_draft(value: Value) -> Result<ProposalDraft, UpgradeError> {
+    serde_json::from_value(value)
+        .map_err(|e| UpgradeError::InternalError(format!("Corrupt proposal draft: {}", e)))
+}
+
+fn decode_incident(value: Value) -> Result<Incident, UpgradeError> {
+    serde_json::from_value(value)
+        .map_err(|e| UpgradeError::InternalError(format!("Corrupt incident: {}", e)))
+}
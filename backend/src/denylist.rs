@@ -0,0 +1,173 @@
+use crate::error::UpgradeError;
+use crate::security::SecurityAuditor;
+use serde::{Deserialize, Serialize};
+
+/// Shortest byte pattern accepted; anything shorter matches too much by chance
+pub const MIN_PATTERN_BYTES: usize = 4;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DenylistKind {
+    /// Hex SHA-256 of the whole program binary
+    Hash,
+    /// Hex byte sequence found anywhere in the binary; `??` matches any byte
+    Pattern,
+}
+
+impl DenylistKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DenylistKind::Hash => "hash",
+            DenylistKind::Pattern => "pattern",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hash" => Some(DenylistKind::Hash),
+            "pattern" => Some(DenylistKind::Pattern),
+            _ => None,
+        }
+    }
+}
+
+/// A banned binary or byte pattern, with why it was banned
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DenylistEntry {
+    pub id: String,
+    pub kind: DenylistKind,
+    /// Lowercase hex without separators
+    pub value: String,
+    pub reason: String,
+    pub added_by: Option<String>,
+    pub created_at: i64,
+}
+
+/// Request body for `POST /security/denylist`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewDenylistEntry {
+    pub kind: DenylistKind,
+    pub value: String,
+    pub reason: String,
+    pub added_by: Option<String>,
+}
+
+impl NewDenylistEntry {
+    /// Validate and normalize the value: whitespace and a `0x` prefix are
+    /// dropped and hex is lowercased
+    pub fn into_entry(self, now: i64) -> Result<DenylistEntry, UpgradeError> {
+        if self.reason.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest("reason must not be empty".to_string()));
+        }
+
+        let value: String = self.value.split_whitespace().collect::<String>().to_lowercase();
+        let value = value.strip_prefix("0x").unwrap_or(&value).to_string();
+
+        match self.kind {
+            DenylistKind::Hash => {
+                if value.len() != 64 || hex::decode(&value).is_err() {
+                    return Err(UpgradeError::InvalidRequest(
+                        "hash must be a hex SHA-256 digest".to_string(),
+                    ));
+                }
+            }
+            DenylistKind::Pattern => {
+                let pattern = parse_pattern(&value)?;
+                if pattern.iter().flatten().count() < MIN_PATTERN_BYTES {
+                    return Err(UpgradeError::InvalidRequest(format!(
+                        "pattern needs at least {} fixed bytes",
+                        MIN_PATTERN_BYTES
+                    )));
+                }
+            }
+        }
+
+        Ok(DenylistEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: self.kind,
+            value,
+            reason: self.reason.trim().to_string(),
+            added_by: self.added_by,
+            created_at: now,
+        })
+    }
+}
+
+/// Decode a hex pattern into bytes, `None` standing for a `??` wildcard
+pub fn parse_pattern(value: &str) -> Result<Vec<Option<u8>>, UpgradeError> {
+    if !value.len().is_multiple_of(2) {
+        return Err(UpgradeError::InvalidRequest("pattern has an odd number of hex digits".to_string()));
+    }
+
+    value
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            b"??" => Ok(None),
+            _ => std::str::from_utf8(pair)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .map(Some)
+                .ok_or_else(|| UpgradeError::InvalidRequest(format!("invalid pattern byte {:?}", pair))),
+        })
+        .collect()
+}
+
+fn contains_pattern(binary: &[u8], pattern: &[Option<u8>]) -> bool {
+    !pattern.is_empty()
+        && binary.windows(pattern.len()).any(|window| {
+            window
+                .iter()
+                .zip(pattern)
+                .all(|(byte, expected)| expected.is_none_or(|expected| *byte == expected))
+        })
+}
+
+/// The ELF file at the start of `binary`, without the zero padding the loader
+/// leaves after it in buffer and program data accounts. The section header
+/// table is the last thing in an SBF shared object, so it marks the end.
+pub fn elf_file(binary: &[u8]) -> &[u8] {
+    let end = || -> Option<usize> {
+        if binary.get(..4)? != b"\x7fELF" {
+            return None;
+        }
+        let shoff = u64::from_le_bytes(binary.get(0x28..0x30)?.try_into().ok()?);
+        let shentsize = u16::from_le_bytes(binary.get(0x3a..0x3c)?.try_into().ok()?) as u64;
+        let shnum = u16::from_le_bytes(binary.get(0x3c..0x3e)?.try_into().ok()?) as u64;
+        usize::try_from(shoff.checked_add(shentsize * shnum)?).ok()
+    };
+
+    match end() {
+        Some(end) if end <= binary.len() => &binary[..end],
+        _ => binary,
+    }
+}
+
+/// Entries matching `binary`. Hashes are compared against both the bytes as
+/// given and the ELF file inside them, so padded on-chain data matches the
+/// hash of the release artifact.
+pub fn find_matches<'a>(entries: &'a [DenylistEntry], binary: &[u8]) -> Vec<&'a DenylistEntry> {
+    let hashes = [
+        hex::encode(SecurityAuditor::calculate_program_hash(binary)),
+        hex::encode(SecurityAuditor::calculate_program_hash(elf_file(binary))),
+    ];
+
+    entries
+        .iter()
+        .filter(|entry| match entry.kind {
+            DenylistKind::Hash => hashes.contains(&entry.value),
+            DenylistKind::Pattern => parse_pattern(&entry.value)
+                .map(|pattern| contains_pattern(binary, &pattern))
+                .unwrap_or(false),
+        })
+        .collect()
+}
+
+/// One line per match for alerts and error messages
+pub fn describe(matches: &[DenylistEntry]) -> String {
+    matches
+        .iter()
+        .map(|entry| format!("{} ({} {})", entry.reason, entry.kind.as_str(), entry.id))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
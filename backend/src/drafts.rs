@@ -0,0 +1,357 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::proposal::{ProposalAttachment, ProposalManager, ProposalOptions, RiskTier};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DraftStatus {
+    /// Editable and open for review
+    Draft,
+    /// Turned into an on-chain proposal; `proposal_id` is set
+    Submitted,
+    Discarded,
+}
+
+impl DraftStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DraftStatus::Draft => "draft",
+            DraftStatus::Submitted => "submitted",
+            DraftStatus::Discarded => "discarded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(DraftStatus::Draft),
+            "submitted" => Some(DraftStatus::Submitted),
+            "discarded" => Some(DraftStatus::Discarded),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewVerdict {
+    Approve,
+    RequestChanges,
+}
+
+/// A reviewer's verdict on one revision of a draft
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftReview {
+    pub reviewer: String,
+    pub revision: u32,
+    pub verdict: ReviewVerdict,
+    pub comment: Option<String>,
+    pub reviewed_at: i64,
+}
+
+/// Fields set when creating or editing a draft; omitted fields are left as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DraftChanges {
+    #[serde(alias = "new_program_buffer")]
+    pub new_buffer: Option<String>,
+    pub description: Option<String>,
+    pub risk_tier: Option<RiskTier>,
+    pub attachments: Option<Vec<ProposalAttachment>>,
+    pub publish_idl: Option<bool>,
+}
+
+/// An upgrade proposal that has not been put on chain yet. Every edit bumps
+/// `revision`, and only reviews of the current revision count towards
+/// submission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposalDraft {
+    pub id: String,
+    pub author: String,
+    pub new_buffer: Option<String>,
+    pub description: String,
+    pub risk_tier: RiskTier,
+    pub attachments: Vec<ProposalAttachment>,
+    pub publish_idl: bool,
+    pub revision: u32,
+    pub reviews: Vec<DraftReview>,
+    pub status: DraftStatus,
+    pub proposal_id: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl ProposalDraft {
+    pub fn new(author: &str, changes: DraftChanges, now: i64) -> Result<Self, UpgradeError> {
+        if author.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest("author must not be empty".to_string()));
+        }
+
+        let mut draft = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            author: author.to_string(),
+            new_buffer: None,
+            description: String::new(),
+            risk_tier: RiskTier::Medium,
+            attachments: Vec::new(),
+            publish_idl: false,
+            revision: 0,
+            reviews: Vec::new(),
+            status: DraftStatus::Draft,
+            proposal_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        draft.edit(changes, now)?;
+
+        Ok(draft)
+    }
+
+    fn ensure_open(&self) -> Result<(), UpgradeError> {
+        if self.status != DraftStatus::Draft {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Draft {} is already {}",
+                self.id,
+                self.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply `changes` as a new revision. Earlier reviews are kept for the
+    /// record but no longer count.
+    pub fn edit(&mut self, changes: DraftChanges, now: i64) -> Result<(), UpgradeError> {
+        self.ensure_open()?;
+
+        if let Some(buffer) = changes.new_buffer {
+            Pubkey::from_str(&buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+            self.new_buffer = Some(buffer);
+        }
+        if let Some(description) = changes.description {
+            self.description = description;
+        }
+        if let Some(risk_tier) = changes.risk_tier {
+            self.risk_tier = risk_tier;
+        }
+        if let Some(attachments) = changes.attachments {
+            self.attachments = attachments;
+        }
+        if let Some(publish_idl) = changes.publish_idl {
+            self.publish_idl = publish_idl;
+        }
+
+        self.revision += 1;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Record `reviewer`'s verdict on the current revision, replacing any
+    /// verdict they already gave on it. Authors cannot review their own draft.
+    pub fn review(
+        &mut self,
+        reviewer: &str,
+        verdict: ReviewVerdict,
+        comment: Option<String>,
+        now: i64,
+    ) -> Result<(), UpgradeError> {
+        self.ensure_open()?;
+        if reviewer.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest("reviewer must not be empty".to_string()));
+        }
+        if reviewer == self.author {
+            return Err(UpgradeError::Forbidden("Authors cannot review their own draft".to_string()));
+        }
+
+        let revision = self.revision;
+        self.reviews.retain(|r| !(r.reviewer == reviewer && r.revision == revision));
+        self.reviews.push(DraftReview {
+            reviewer: reviewer.to_string(),
+            revision,
+            verdict,
+            comment,
+            reviewed_at: now,
+        });
+        Ok(())
+    }
+
+    /// Reviewers who approved the current revision
+    pub fn approvals(&self) -> Vec<&str> {
+        self.current_reviews(ReviewVerdict::Approve)
+    }
+
+    fn current_reviews(&self, verdict: ReviewVerdict) -> Vec<&str> {
+        self.reviews
+            .iter()
+            .filter(|r| r.revision == self.revision && r.verdict == verdict)
+            .map(|r| r.reviewer.as_str())
+            .collect()
+    }
+
+    /// The buffer to propose, once the draft is complete, has `required_reviews`
+    /// approvals of its current revision and no outstanding change requests
+    pub fn check_submittable(&self, required_reviews: usize) -> Result<Pubkey, UpgradeError> {
+        self.ensure_open()?;
+
+        let buffer = self
+            .new_buffer
+            .as_deref()
+            .ok_or_else(|| UpgradeError::InvalidRequest("Draft has no new_buffer".to_string()))?;
+        if self.description.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest("Draft has no description".to_string()));
+        }
+
+        let changes_requested = self.current_reviews(ReviewVerdict::RequestChanges);
+        if !changes_requested.is_empty() {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Changes requested on revision {} by {}",
+                self.revision,
+                changes_requested.join(", ")
+            )));
+        }
+
+        let approvals = self.approvals().len();
+        if approvals < required_reviews {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Revision {} has {}/{} approving reviews",
+                self.revision, approvals, required_reviews
+            )));
+        }
+
+        Pubkey::from_str(buffer).map_err(|_| UpgradeError::InvalidPubkey)
+    }
+}
+
+/// Drafts that are edited and reviewed before becoming on-chain proposals
+pub struct DraftService {
+    database: Arc<Database>,
+    proposal_manager: Arc<ProposalManager>,
+    required_reviews: usize,
+}
+
+impl DraftService {
+    /// `DRAFT_REQUIRED_REVIEWS` approving reviews are needed to submit (default 0)
+    pub fn new(database: Arc<Database>, proposal_manager: Arc<ProposalManager>) -> Self {
+        let required_reviews = std::env::var("DRAFT_REQUIRED_REVIEWS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+
+        Self {
+            database,
+            proposal_manager,
+            required_reviews,
+        }
+    }
+
+    pub fn required_reviews(&self) -> usize {
+        self.required_reviews
+    }
+
+    pub async fn create(&self, author: &str, changes: DraftChanges) -> Result<ProposalDraft, UpgradeError> {
+        let draft = ProposalDraft::new(author, changes, now())?;
+        self.database.insert_draft(&draft).await?;
+        tracing::info!("Draft {} created by {}", draft.id, draft.author);
+        Ok(draft)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<ProposalDraft, UpgradeError> {
+        self.database
+            .get_draft(id)
+            .await?
+            .ok_or_else(|| UpgradeError::DraftNotFound(id.to_string()))
+    }
+
+    pub async fn list(&self, status: Option<DraftStatus>) -> Result<Vec<ProposalDraft>, UpgradeError> {
+        self.database.list_drafts(status).await
+    }
+
+    pub async fn edit(&self, id: &str, changes: DraftChanges) -> Result<ProposalDraft, UpgradeError> {
+        self.database
+            .modify_draft(id, |draft| draft.edit(changes, now()))
+            .await
+    }
+
+    pub async fn review(
+        &self,
+        id: &str,
+        reviewer: &str,
+        verdict: ReviewVerdict,
+        comment: Option<String>,
+    ) -> Result<ProposalDraft, UpgradeError> {
+        self.database
+            .modify_draft(id, |draft| draft.review(reviewer, verdict, comment, now()))
+            .await
+    }
+
+    pub async fn discard(&self, id: &str) -> Result<ProposalDraft, UpgradeError> {
+        self.database
+            .modify_draft(id, |draft| {
+                draft.ensure_open()?;
+                draft.status = DraftStatus::Discarded;
+                draft.updated_at = now();
+                Ok(())
+            })
+            .await
+    }
+
+    /// Create the on-chain proposal from a reviewed draft. The draft is
+    /// claimed first so a concurrent edit or second submit cannot race the
+    /// proposal, and reopened if proposing fails.
+    pub async fn submit(&self, id: &str) -> Result<ProposalDraft, UpgradeError> {
+        let required_reviews = self.required_reviews;
+        let mut buffer = Pubkey::default();
+        let draft = self
+            .database
+            .modify_draft(id, |draft| {
+                buffer = draft.check_submittable(required_reviews)?;
+                draft.status = DraftStatus::Submitted;
+                draft.updated_at = now();
+                Ok(())
+            })
+            .await?;
+
+        let proposed = self
+            .proposal_manager
+            .propose_upgrade_with_options(
+                buffer,
+                draft.description.clone(),
+                ProposalOptions {
+                    publish_idl: draft.publish_idl,
+                    risk_tier: Some(draft.risk_tier),
+                    attachments: draft.attachments.clone(),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        let proposal_id = match proposed {
+            Ok(proposal_id) => proposal_id,
+            Err(e) => {
+                self.database
+                    .modify_draft(id, |draft| {
+                        draft.status = DraftStatus::Draft;
+                        Ok(())
+                    })
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        tracing::info!("Draft {} revision {} submitted as proposal {}", id, draft.revision, proposal_id);
+        self.database
+            .modify_draft(id, |draft| {
+                draft.proposal_id = Some(proposal_id);
+                Ok(())
+            })
+            .await
+    }
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
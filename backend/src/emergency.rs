@@ -0,0 +1,300 @@
+use crate::error::UpgradeError;
+use crate::incidents::{Detection, IncidentManager, IncidentSeverity, IncidentSource};
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_EMERGENCY};
+use crate::multisig::{AccountReader, MultisigCoordinator};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+/// Longest reason the upgrade-manager program accepts with a pause vote
+pub const MAX_PAUSE_REASON_LEN: usize = 200;
+
+/// How long pause votes stay open on chain before they lapse
+pub const EMERGENCY_VOTE_WINDOW: i64 = 60 * 60;
+
+/// Borsh `Option<i64>`
+fn read_timestamp(reader: &mut AccountReader) -> Result<Option<i64>, UpgradeError> {
+    match reader.u8()? {
+        0 => Ok(None),
+        _ => Ok(Some(reader.u64()? as i64)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PausableProgram {
+    pub program: String,
+    /// Hex instruction data of the program's pause instruction
+    pub pause_data: String,
+    /// Writable accounts the pause instruction takes after the pause authority
+    pub pause_accounts: Vec<String>,
+    pub registered_at: i64,
+    /// Last time the multisig paused the program
+    pub paused_at: Option<i64>,
+}
+
+impl PausableProgram {
+    pub fn pause_account_keys(&self) -> Result<Vec<Pubkey>, UpgradeError> {
+        self.pause_accounts
+            .iter()
+            .map(|account| account.parse().map_err(|_| UpgradeError::InvalidPubkey))
+            .collect()
+    }
+}
+
+/// Programs the multisig can pause, from the upgrade-manager `pause_registry` PDA
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnchainPauseRegistry {
+    pub account: String,
+    /// Member votes that pause a program
+    pub emergency_quorum: u8,
+    pub programs: Vec<PausableProgram>,
+}
+
+impl OnchainPauseRegistry {
+    /// Registry before any program was registered
+    pub fn empty(account: &Pubkey) -> Self {
+        Self {
+            account: account.to_string(),
+            emergency_quorum: 0,
+            programs: Vec::new(),
+        }
+    }
+
+    /// Decode the Anchor `PauseRegistry` account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8], account: &Pubkey) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        let programs = (0..reader.u32()?)
+            .map(|_| {
+                Ok(PausableProgram {
+                    program: reader.pubkey()?.to_string(),
+                    pause_data: hex::encode(reader.bytes()?),
+                    pause_accounts: reader.pubkeys()?.iter().map(|key| key.to_string()).collect(),
+                    registered_at: reader.u64()? as i64,
+                    paused_at: read_timestamp(&mut reader)?,
+                })
+            })
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+        Ok(Self {
+            account: account.to_string(),
+            emergency_quorum: reader.u8()?,
+            programs,
+        })
+    }
+
+    pub fn get(&self, program: &str) -> Option<&PausableProgram> {
+        self.programs.iter().find(|entry| entry.program == program)
+    }
+}
+
+/// Open pause votes for one program, from its upgrade-manager `pause_votes` PDA
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnchainPauseVotes {
+    pub program: String,
+    pub account: String,
+    pub votes: Vec<String>,
+    /// Reason given with the first vote
+    pub reason: String,
+    pub opened_at: i64,
+    pub last_paused_at: Option<i64>,
+}
+
+impl OnchainPauseVotes {
+    /// Votes of a program nobody voted to pause yet
+    pub fn empty(program: &Pubkey, account: &Pubkey) -> Self {
+        Self {
+            program: program.to_string(),
+            account: account.to_string(),
+            votes: Vec::new(),
+            reason: String::new(),
+            opened_at: 0,
+            last_paused_at: None,
+        }
+    }
+
+    /// Decode the Anchor `PauseVotes` account data
+    pub fn try_from_account_data(data: &[u8], account: &Pubkey) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        Ok(Self {
+            program: reader.pubkey()?.to_string(),
+            account: account.to_string(),
+            votes: reader.pubkeys()?.iter().map(|key| key.to_string()).collect(),
+            reason: String::from_utf8_lossy(reader.bytes()?).to_string(),
+            opened_at: reader.u64()? as i64,
+            last_paused_at: read_timestamp(&mut reader)?,
+        })
+    }
+
+    /// Votes still counting at `now`; the program drops lapsed votes on the next vote
+    pub fn live_votes(&self, now: i64) -> usize {
+        if now > self.opened_at + EMERGENCY_VOTE_WINDOW {
+            0
+        } else {
+            self.votes.len()
+        }
+    }
+}
+
+/// Whether a vote ran the pause, from the entry's `paused_at` before and after it
+pub fn pause_executed(before: Option<i64>, after: Option<i64>) -> bool {
+    after.is_some() && after != before
+}
+
+/// Body of `POST /programs/:program/emergency-pause`
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmergencyPauseRequest {
+    pub reason: String,
+}
+
+impl EmergencyPauseRequest {
+    pub fn validate(&self) -> Result<(), UpgradeError> {
+        if self.reason.trim().is_empty() || self.reason.len() > MAX_PAUSE_REASON_LEN {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "An emergency pause needs a reason of at most {} bytes",
+                MAX_PAUSE_REASON_LEN
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A program's pause registration together with its open votes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmergencyPauseStatus {
+    pub registration: PausableProgram,
+    pub emergency_quorum: u8,
+    pub votes: OnchainPauseVotes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmergencyPauseOutcome {
+    pub program: String,
+    pub voter: String,
+    pub signature: String,
+    /// Votes counted, including this one
+    pub votes: usize,
+    pub required: u8,
+    /// The vote reached quorum and the program was paused
+    pub paused: bool,
+    pub paused_at: Option<i64>,
+}
+
+/// Votes for emergency pauses with the configured member key and raises a
+/// critical alert and incident for every vote and pause
+pub struct EmergencyPauseService {
+    multisig: Arc<MultisigCoordinator>,
+    monitoring: Arc<MonitoringService>,
+    incidents: Arc<IncidentManager>,
+}
+
+impl EmergencyPauseService {
+    pub fn new(
+        multisig: Arc<MultisigCoordinator>,
+        monitoring: Arc<MonitoringService>,
+        incidents: Arc<IncidentManager>,
+    ) -> Self {
+        Self {
+            multisig,
+            monitoring,
+            incidents,
+        }
+    }
+
+    pub async fn pausable_programs(&self) -> Result<OnchainPauseRegistry, UpgradeError> {
+        self.multisig.fetch_pause_registry().await
+    }
+
+    pub async fn status(&self, program: &Pubkey) -> Result<EmergencyPauseStatus, UpgradeError> {
+        let registry = self.multisig.fetch_pause_registry().await?;
+        let registration = registry
+            .get(&program.to_string())
+            .cloned()
+            .ok_or_else(|| not_pausable(program))?;
+
+        Ok(EmergencyPauseStatus {
+            registration,
+            emergency_quorum: registry.emergency_quorum,
+            votes: self.multisig.fetch_pause_votes(program).await?,
+        })
+    }
+
+    /// Cast the configured member's pause vote for `program`. The vote that
+    /// reaches the emergency quorum pauses the program in the same transaction.
+    pub async fn vote(
+        &self,
+        program: &Pubkey,
+        request: &EmergencyPauseRequest,
+    ) -> Result<EmergencyPauseOutcome, UpgradeError> {
+        request.validate()?;
+        let registry = self.multisig.fetch_pause_registry().await?;
+        let entry = registry.get(&program.to_string()).ok_or_else(|| not_pausable(program))?;
+        let pause_accounts = entry.pause_account_keys()?;
+
+        self.alert(format!("EMERGENCY PAUSE vote for {}: {}", program, request.reason)).await;
+
+        let (voter, signature) = match self.multisig.emergency_pause(program, &request.reason, &pause_accounts).await {
+            Ok(sent) => sent,
+            Err(e) => {
+                self.alert(format!("Emergency pause vote for {} failed: {}", program, e)).await;
+                return Err(e);
+            }
+        };
+
+        let paused_at = self
+            .multisig
+            .fetch_pause_registry()
+            .await?
+            .get(&program.to_string())
+            .and_then(|entry| entry.paused_at);
+        let paused = pause_executed(entry.paused_at, paused_at);
+        let votes = if paused {
+            registry.emergency_quorum as usize
+        } else {
+            let now = chrono::Utc::now().timestamp();
+            self.multisig.fetch_pause_votes(program).await?.live_votes(now)
+        };
+
+        if paused {
+            self.paused(program, &request.reason, &signature.to_string()).await;
+        }
+
+        Ok(EmergencyPauseOutcome {
+            program: program.to_string(),
+            voter: voter.to_string(),
+            signature: signature.to_string(),
+            votes,
+            required: registry.emergency_quorum,
+            paused,
+            paused_at,
+        })
+    }
+
+    async fn alert(&self, message: String) {
+        tracing::error!("{}", message);
+        self.monitoring
+            .send_alert(AlertLevel::Critical, message, COMPONENT_EMERGENCY.to_string())
+            .await;
+    }
+
+    async fn paused(&self, program: &Pubkey, reason: &str, signature: &str) {
+        let message = format!("EMERGENCY PAUSE executed: {} paused ({}), transaction {}", program, reason, signature);
+        self.alert(message.clone()).await;
+
+        let detection = Detection {
+            source: IncidentSource::EmergencyPause,
+            severity: IncidentSeverity::Critical,
+            program: program.to_string(),
+            proposal_id: None,
+            title: format!("Emergency pause of {}", program),
+            detail: format!("Paused by the multisig emergency quorum: {}", reason),
+            alert: Some((COMPONENT_EMERGENCY.to_string(), message)),
+        };
+        if let Err(e) = self.incidents.detect(detection).await {
+            tracing::error!("Failed to open an incident for the emergency pause of {}: {}", program, e);
+        }
+    }
+}
+
+fn not_pausable(program: &Pubkey) -> UpgradeError {
+    UpgradeError::InvalidRequest(format!("Program {} is not registered for emergency pause", program))
+}
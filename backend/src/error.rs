@@ -14,6 +14,9 @@ pub enum UpgradeError {
     #[error("Insufficient approvals: {current}/{required}")]
     InsufficientApprovals { current: usize, required: usize },
 
+    #[error("Approvals span {current} of {required} required organizations")]
+    InsufficientOrganizations { current: usize, required: usize },
+
     #[error("Not a multisig member")]
     NotMultisigMember,
 
@@ -23,6 +26,9 @@ pub enum UpgradeError {
     #[error("Proposal already cancelled")]
     AlreadyCancelled,
 
+    #[error("Illegal proposal transition: {event} from {from}")]
+    InvalidTransition { from: String, event: String },
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 
@@ -35,6 +41,75 @@ pub enum UpgradeError {
     #[error("Migration error: {0}")]
     MigrationError(String),
 
+    #[error("Migration {migration_id} is already running for {program}")]
+    MigrationLocked { program: String, migration_id: String },
+
+    #[error("Artifact not found: {0}")]
+    ArtifactNotFound(String),
+
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    #[error("Detected buffer not found: {0}")]
+    BufferNotFound(String),
+
+    #[error("Draft not found: {0}")]
+    DraftNotFound(String),
+
+    #[error("No fork test for proposal: {0}")]
+    ForkTestNotFound(String),
+
+    #[error("API key not found: {0}")]
+    ApiKeyNotFound(String),
+
+    #[error("Incident not found: {0}")]
+    IncidentNotFound(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Invalid webhook signature")]
+    InvalidWebhookSignature,
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Execution blocked, dependencies not healthy: {0}")]
+    DependenciesUnhealthy(String),
+
+    #[error("Execution waiting on dependencies: {0}")]
+    DependencyPending(String),
+
+    #[error("Invariant check failed: {0}")]
+    InvariantViolation(String),
+
+    #[error("Pre-flight checklist incomplete: {0}")]
+    ChecklistIncomplete(String),
+
+    #[error("Binary is denylisted: {0}")]
+    KnownVulnerability(String),
+
+    #[error("Insufficient funding: {0}")]
+    InsufficientFunding(String),
+
+    #[error("Buffer authority not handed off: {0}")]
+    BufferNotHandedOff(String),
+
+    #[error("Proposal is frozen until its critical finding is cleared")]
+    ProposalFrozen,
+
+    #[error("RPC quorum not reached: {0}")]
+    RpcQuorumNotReached(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    #[error("Request timed out: {0}")]
+    RequestTimeout(String),
+
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -45,9 +120,35 @@ impl axum::response::IntoResponse for UpgradeError {
             UpgradeError::ProposalNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
             UpgradeError::TimelockActive { .. } => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
             UpgradeError::InsufficientApprovals { .. } => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
+            UpgradeError::InsufficientOrganizations { .. } => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
             UpgradeError::NotMultisigMember => (axum::http::StatusCode::FORBIDDEN, self.to_string()),
             UpgradeError::AlreadyExecuted => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
             UpgradeError::AlreadyCancelled => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
+            UpgradeError::InvalidTransition { .. } => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::MigrationLocked { .. } => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::ArtifactNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::SnapshotNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::BufferNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::DraftNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::ForkTestNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::ApiKeyNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::IncidentNotFound(_) => (axum::http::StatusCode::NOT_FOUND, self.to_string()),
+            UpgradeError::InvalidPubkey => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
+            UpgradeError::InvalidRequest(_) => (axum::http::StatusCode::BAD_REQUEST, self.to_string()),
+            UpgradeError::InvalidWebhookSignature => (axum::http::StatusCode::UNAUTHORIZED, self.to_string()),
+            UpgradeError::Unauthorized(_) => (axum::http::StatusCode::UNAUTHORIZED, self.to_string()),
+            UpgradeError::Forbidden(_) => (axum::http::StatusCode::FORBIDDEN, self.to_string()),
+            UpgradeError::DependenciesUnhealthy(_) => (axum::http::StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            UpgradeError::DependencyPending(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::InvariantViolation(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::ChecklistIncomplete(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::KnownVulnerability(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::InsufficientFunding(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::BufferNotHandedOff(_) => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::ProposalFrozen => (axum::http::StatusCode::CONFLICT, self.to_string()),
+            UpgradeError::RpcQuorumNotReached(_) => (axum::http::StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            UpgradeError::PayloadTooLarge(_) => (axum::http::StatusCode::PAYLOAD_TOO_LARGE, self.to_string()),
+            UpgradeError::RequestTimeout(_) => (axum::http::StatusCode::GATEWAY_TIMEOUT, self.to_string()),
             _ => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
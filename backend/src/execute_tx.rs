@@ -0,0 +1,353 @@
+use crate::confirmation::{ConfirmationStatus, ConfirmationTracker};
+use crate::error::UpgradeError;
+use crate::multisig::MultisigCoordinator;
+use crate::program_builder::ExtensionCost;
+use crate::proposal::{ProposalEvent, ProposalManager};
+use crate::signer::SharedSigner;
+use base64::Engine;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// SPL Memo v2, which fails unless every account passed to it signed
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+
+/// Memo attesting that `signers` authorised the execution of `proposal_id`.
+/// The memo program checks every listed account signed, so the transaction
+/// only lands with all of their signatures.
+pub fn attestation_instruction(proposal_id: &str, signers: &[Pubkey]) -> Instruction {
+    Instruction {
+        program_id: Pubkey::from_str(MEMO_PROGRAM_ID).unwrap(),
+        accounts: signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)).collect(),
+        data: format!("goquant-execute:{}", proposal_id).into_bytes(),
+    }
+}
+
+/// Place a member's signature over `transaction`'s message, checking it is one
+/// of the required signers and that the signature verifies
+pub fn apply_signature(transaction: &mut Transaction, signer: &Pubkey, signature: Signature) -> Result<(), UpgradeError> {
+    let position = transaction
+        .get_signing_keypair_positions(&[*signer])
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+        .first()
+        .copied()
+        .flatten()
+        .ok_or_else(|| UpgradeError::InvalidRequest(format!("{} is not a signer of this transaction", signer)))?;
+
+    if !signature.verify(signer.as_ref(), &transaction.message_data()) {
+        return Err(UpgradeError::InvalidRequest(format!("Signature does not verify for {}", signer)));
+    }
+
+    transaction.signatures[position] = signature;
+    Ok(())
+}
+
+/// Wire-format transaction, base64 encoded, as wallets expect it
+pub fn encode_transaction(transaction: &Transaction) -> Result<String, UpgradeError> {
+    let bytes = bincode::serialize(transaction)
+        .map_err(|e| UpgradeError::InternalError(format!("Failed to serialize transaction: {}", e)))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignerSlot {
+    pub pubkey: String,
+    pub signed: bool,
+}
+
+/// What members are shown and sign
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecuteTransactionView {
+    pub proposal_id: String,
+    /// Base64 wire-format transaction with the signatures collected so far
+    pub transaction: String,
+    /// Base64 message bytes each member signs
+    pub message: String,
+    pub fee_payer: String,
+    pub signers: Vec<SignerSlot>,
+    pub signatures_collected: usize,
+    pub signatures_required: usize,
+    /// Signatures must all arrive before the chain passes this block height
+    pub last_valid_block_height: u64,
+    /// Set once the assembled transaction has been sent
+    pub broadcast_signature: Option<String>,
+    /// Set when the transaction extends the program data account before
+    /// upgrading; the fee payer pays its rent
+    pub extension: Option<ExtensionCost>,
+}
+
+struct ExecuteSession {
+    transaction: Transaction,
+    /// Members whose signatures are collected; the first one executes
+    signers: Vec<Pubkey>,
+    last_valid_block_height: u64,
+    broadcast: Option<Signature>,
+    extension: Option<ExtensionCost>,
+}
+
+impl ExecuteSession {
+    fn signed(&self, signer: &Pubkey) -> bool {
+        self.transaction
+            .get_signing_keypair_positions(&[*signer])
+            .ok()
+            .and_then(|positions| positions[0])
+            .is_some_and(|position| self.transaction.signatures[position] != Signature::default())
+    }
+
+    fn collected(&self) -> usize {
+        self.signers.iter().filter(|signer| self.signed(signer)).count()
+    }
+
+    fn view(&self, proposal_id: &str) -> Result<ExecuteTransactionView, UpgradeError> {
+        Ok(ExecuteTransactionView {
+            proposal_id: proposal_id.to_string(),
+            transaction: encode_transaction(&self.transaction)?,
+            message: base64::engine::general_purpose::STANDARD.encode(self.transaction.message_data()),
+            fee_payer: self.transaction.message.account_keys[0].to_string(),
+            signers: self.signers
+                .iter()
+                .map(|signer| SignerSlot {
+                    pubkey: signer.to_string(),
+                    signed: self.signed(signer),
+                })
+                .collect(),
+            signatures_collected: self.collected(),
+            signatures_required: self.signers.len(),
+            last_valid_block_height: self.last_valid_block_height,
+            broadcast_signature: self.broadcast.map(|signature| signature.to_string()),
+            extension: self.extension,
+        })
+    }
+}
+
+/// Builds the execute transaction for members to sign offline (hardware
+/// wallets, a signing UI) and broadcasts it once every required member
+/// signature is in. Sessions only live as long as their blockhash, so they
+/// are kept in memory; an expired one is rebuilt with empty signatures.
+pub struct ExecuteTransactionService {
+    multisig: Arc<MultisigCoordinator>,
+    proposal_manager: Arc<ProposalManager>,
+    confirmation: Arc<ConfirmationTracker>,
+    rpc_client: Arc<AsyncRpcClient>,
+    fee_payer: Option<SharedSigner>,
+    sessions: Mutex<HashMap<String, ExecuteSession>>,
+}
+
+impl ExecuteTransactionService {
+    pub fn new(
+        multisig: Arc<MultisigCoordinator>,
+        proposal_manager: Arc<ProposalManager>,
+        confirmation: Arc<ConfirmationTracker>,
+        rpc_client: Arc<AsyncRpcClient>,
+    ) -> Self {
+        Self {
+            multisig,
+            proposal_manager,
+            confirmation,
+            rpc_client,
+            fee_payer: None,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env(
+        multisig: Arc<MultisigCoordinator>,
+        proposal_manager: Arc<ProposalManager>,
+        confirmation: Arc<ConfirmationTracker>,
+    ) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        Self::new(multisig, proposal_manager, confirmation, Arc::new(AsyncRpcClient::new(rpc_url)))
+    }
+
+    /// Pay fees from the service's key instead of the executing member's
+    pub fn with_fee_payer(mut self, fee_payer: Option<SharedSigner>) -> Self {
+        self.fee_payer = fee_payer;
+        self
+    }
+
+    /// The current execute transaction for a proposal. `signers` picks which
+    /// members sign (at least the on-chain threshold); by default the first
+    /// `threshold` members do. Asking for a different signer set starts over.
+    pub async fn transaction(
+        &self,
+        proposal_id: &str,
+        signers: Option<Vec<Pubkey>>,
+    ) -> Result<ExecuteTransactionView, UpgradeError> {
+        let mut sessions = self.sessions.lock().await;
+        let block_height = self.block_height().await?;
+
+        if let Some(session) = sessions.get(proposal_id) {
+            let reusable = match session.broadcast {
+                // Keep showing a sent transaction unless it failed or was dropped
+                Some(signature) => !self.abandoned(proposal_id, &signature).await?,
+                None => {
+                    signers.as_ref().is_none_or(|s| *s == session.signers)
+                        && block_height <= session.last_valid_block_height
+                }
+            };
+            if reusable {
+                return session.view(proposal_id);
+            }
+        }
+
+        let session = self.build(proposal_id, signers).await?;
+        let view = session.view(proposal_id);
+        sessions.insert(proposal_id.to_string(), session);
+        view
+    }
+
+    /// Add a member's signature; the last one triggers the broadcast
+    pub async fn add_signature(
+        &self,
+        proposal_id: &str,
+        signer: &Pubkey,
+        signature: Signature,
+    ) -> Result<ExecuteTransactionView, UpgradeError> {
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.get_mut(proposal_id).ok_or_else(|| {
+            UpgradeError::InvalidRequest("No execute transaction; fetch it from /execute-tx first".to_string())
+        })?;
+
+        if session.broadcast.is_some() {
+            return Err(UpgradeError::AlreadyExecuted);
+        }
+        if !session.signers.contains(signer) {
+            return Err(UpgradeError::NotMultisigMember);
+        }
+        if self.block_height().await? > session.last_valid_block_height {
+            return Err(UpgradeError::InvalidRequest(
+                "Execute transaction expired; fetch a fresh one from /execute-tx".to_string(),
+            ));
+        }
+
+        apply_signature(&mut session.transaction, signer, signature)?;
+        tracing::info!(
+            "Execute transaction for {} signed by {} ({}/{})",
+            proposal_id,
+            signer,
+            session.collected(),
+            session.signers.len()
+        );
+
+        if session.collected() == session.signers.len() {
+            self.broadcast(proposal_id, session).await?;
+        }
+
+        session.view(proposal_id)
+    }
+
+    async fn build(&self, proposal_id: &str, signers: Option<Vec<Pubkey>>) -> Result<ExecuteSession, UpgradeError> {
+        let proposal = self.proposal_manager.get_proposal(proposal_id).await?;
+        proposal.status.transition(ProposalEvent::Execute)?;
+
+        let program = Pubkey::from_str(&proposal.program).map_err(|_| {
+            UpgradeError::InvalidRequest(format!("Proposal program '{}' is not an address", proposal.program))
+        })?;
+        let buffer = Pubkey::from_str(&proposal.new_buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        let config = self.multisig.fetch_onchain_config().await?;
+        let signers = match signers {
+            Some(signers) => signers,
+            None => config.members.iter().take(config.threshold as usize).copied().collect(),
+        };
+        if signers.len() < config.threshold as usize {
+            return Err(UpgradeError::InsufficientApprovals {
+                current: signers.len(),
+                required: config.threshold as usize,
+            });
+        }
+        if let Some(outsider) = signers.iter().find(|signer| !config.members.contains(signer)) {
+            return Err(UpgradeError::InvalidRequest(format!("{} is not a multisig member", outsider)));
+        }
+
+        let executor = signers[0];
+        let fee_payer = self.fee_payer.as_ref().map_or(executor, |payer| payer.pubkey());
+        // Grow the program data first when the new program does not fit it
+        let extension = match (&proposal.deployment, &proposal.authority_change) {
+            (None, None) => self.multisig.extension_cost(&program, &buffer).await?,
+            _ => None,
+        };
+        let mut instructions = vec![attestation_instruction(proposal_id, &signers)];
+        instructions.extend(extension.map(|extension| extension.instruction(&program, &fee_payer)));
+        instructions.push(self.multisig.build_execute_instruction(&executor, &program, &buffer));
+
+        let (blockhash, last_valid_block_height) = self.rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get blockhash: {}", e)))?;
+        let mut transaction = Transaction::new_unsigned(Message::new(&instructions, Some(&fee_payer)));
+        transaction.message.recent_blockhash = blockhash;
+
+        Ok(ExecuteSession {
+            transaction,
+            signers,
+            last_valid_block_height,
+            broadcast: None,
+            extension,
+        })
+    }
+
+    /// Add the fee payer's signature and send. Confirmation is followed in the
+    /// background and the proposal is marked executed once it is reached.
+    async fn broadcast(&self, proposal_id: &str, session: &mut ExecuteSession) -> Result<(), UpgradeError> {
+        if let Some(fee_payer) = &self.fee_payer {
+            if !session.signers.contains(&fee_payer.pubkey()) {
+                let signature = fee_payer.sign_message(&session.transaction.message_data()).await?;
+                session.transaction.signatures[0] = signature;
+            }
+        }
+
+        let transaction = session.transaction.clone();
+        transaction
+            .verify()
+            .map_err(|e| UpgradeError::InvalidRequest(format!("Assembled transaction does not verify: {}", e)))?;
+
+        // Preflight failures (e.g. the timelock has not passed) are returned to
+        // the last signer; the collected signatures stay valid for a retry
+        let record = self.confirmation
+            .send(proposal_id, &transaction, session.last_valid_block_height)
+            .await?;
+        session.broadcast = transaction.signatures.first().copied();
+
+        let confirmation = self.confirmation.clone();
+        let proposal_manager = self.proposal_manager.clone();
+        let proposal_id = proposal_id.to_string();
+        tokio::spawn(async move {
+            match confirmation.follow_sent(record, &transaction).await {
+                Ok(_) => {
+                    if let Err(e) = proposal_manager.record_execution(&proposal_id).await {
+                        tracing::error!("Failed to mark {} executed: {}", proposal_id, e);
+                    }
+                }
+                Err(e) => tracing::error!("Execute transaction for {} did not land: {}", proposal_id, e),
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn abandoned(&self, proposal_id: &str, signature: &Signature) -> Result<bool, UpgradeError> {
+        let latest = self.confirmation.status(proposal_id).await?;
+        Ok(latest.is_some_and(|record| {
+            record.signature == signature.to_string()
+                && matches!(record.status, ConfirmationStatus::Failed | ConfirmationStatus::Dropped)
+        }))
+    }
+
+    async fn block_height(&self) -> Result<u64, UpgradeError> {
+        self.rpc_client
+            .get_block_height()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get block height: {}", e)))
+    }
+}
@@ -0,0 +1,421 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::idl::IdlPublisher;
+use crate::invariants::{InvariantPhase, InvariantRegistry};
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_EXECUTION};
+use crate::proposal::ProposalManager;
+use crate::security::SecurityAuditor;
+use crate::snapshots::{SnapshotLabel, SnapshotService};
+use crate::watch::PostUpgradeWatch;
+use crate::websocket::NotificationService;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{interval, Duration, Instant};
+
+/// Persisted execution job (see `execution_jobs` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionJob {
+    pub job_id: String,
+    pub proposal_id: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    /// Skip the dependency health gate (requires the Executor role)
+    pub forced: bool,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    /// Earliest time a queued job is claimed again
+    pub next_attempt_at: i64,
+}
+
+/// One run of an execution job (see `execution_job_attempts` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionAttempt {
+    pub attempt: i32,
+    /// `succeeded`, `held`, `retrying` or `failed`
+    pub outcome: String,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    /// When the requeued job may run again
+    pub retry_at: Option<i64>,
+    pub finished_at: i64,
+}
+
+/// Delay before the retry that follows failed attempt `attempt`: `base`
+/// doubled for every earlier attempt, capped at `max`
+pub fn retry_delay(attempt: i32, base: Duration, max: Duration) -> Duration {
+    let doublings = attempt.saturating_sub(1).clamp(0, 31) as u32;
+    base.saturating_mul(1u32 << doublings).min(max)
+}
+
+/// Background worker that drains the execution queue.
+///
+/// HTTP handlers only enqueue; the worker claims one job at a time with
+/// `FOR UPDATE SKIP LOCKED`, so multiple workers never run the same job and
+/// every attempt leaves a record even if the caller disconnects.
+pub struct ExecutionWorker {
+    database: Arc<Database>,
+    proposal_manager: Arc<ProposalManager>,
+    idl_publisher: IdlPublisher,
+    monitoring: Option<Arc<MonitoringService>>,
+    invariants: Option<Arc<InvariantRegistry>>,
+    snapshots: Option<Arc<SnapshotService>>,
+    auditor: Option<Arc<SecurityAuditor>>,
+    notifications: Option<Arc<NotificationService>>,
+    watch: Option<Arc<PostUpgradeWatch>>,
+    poll_interval: Duration,
+    max_attempts: i32,
+    retry_base: Duration,
+    retry_max: Duration,
+    stale_after_seconds: i64,
+}
+
+impl ExecutionWorker {
+    pub fn new(database: Arc<Database>, proposal_manager: Arc<ProposalManager>) -> Self {
+        let max_attempts = std::env::var("EXECUTION_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        let seconds = |key: &str, default: u64| {
+            Duration::from_secs(
+                std::env::var(key)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+
+        Self {
+            database,
+            proposal_manager,
+            idl_publisher: IdlPublisher::new(),
+            monitoring: None,
+            invariants: None,
+            snapshots: None,
+            auditor: None,
+            notifications: None,
+            watch: None,
+            poll_interval: Duration::from_secs(5),
+            max_attempts,
+            retry_base: seconds("EXECUTION_RETRY_BASE_SECS", 15),
+            retry_max: seconds("EXECUTION_RETRY_MAX_SECS", 600),
+            stale_after_seconds: 600,
+        }
+    }
+
+    /// Refuse to execute while dependencies are degraded, unless the job is forced
+    pub fn with_health_gate(mut self, monitoring: Arc<MonitoringService>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Run invariant checks before execution and after the upgrade lands
+    pub fn with_invariants(mut self, invariants: Arc<InvariantRegistry>) -> Self {
+        self.invariants = Some(invariants);
+        self
+    }
+
+    /// Refuse to execute buffers that match the binary denylist
+    pub fn with_denylist(mut self, auditor: Arc<SecurityAuditor>) -> Self {
+        self.auditor = Some(auditor);
+        self
+    }
+
+    /// Snapshot the managed program's accounts around the upgrade and store the diff
+    pub fn with_snapshots(mut self, snapshots: Arc<SnapshotService>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Announce executions that failed for good
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Watch the managed program's logs for anomalies after each upgrade
+    pub fn with_watch(mut self, watch: Arc<PostUpgradeWatch>) -> Self {
+        self.watch = Some(watch);
+        self
+    }
+
+    /// Queue a proposal for execution. Enqueuing twice returns the existing job.
+    pub async fn enqueue(&self, proposal_id: &str, forced: bool) -> Result<ExecutionJob, UpgradeError> {
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let job = self.database
+            .enqueue_execution_job(&job_id, proposal_id, self.max_attempts, forced)
+            .await?;
+
+        tracing::info!("Execution job {} queued for proposal {}", job.job_id, proposal_id);
+
+        Ok(job)
+    }
+
+    pub async fn run(&self) {
+        match self.database.requeue_stale_execution_jobs(self.stale_after_seconds).await {
+            Ok(0) => {}
+            Ok(n) => tracing::warn!("Requeued {} stale execution jobs", n),
+            Err(e) => tracing::error!("Failed to requeue stale execution jobs: {}", e),
+        }
+
+        let mut interval = interval(self.poll_interval);
+
+        loop {
+            interval.tick().await;
+
+            loop {
+                match self.database.claim_next_execution_job().await {
+                    Ok(Some(job)) => self.process(job).await,
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!("Failed to claim execution job: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn process(&self, job: ExecutionJob) {
+        tracing::info!(
+            "Running execution job {} for proposal {} (attempt {}/{})",
+            job.job_id,
+            job.proposal_id,
+            job.attempts,
+            job.max_attempts
+        );
+
+        let started = Instant::now();
+        let result = match self.check_preconditions(&job).await {
+            Ok(()) => match self.proposal_manager.execute_upgrade(&job.proposal_id).await {
+                // A previous attempt already landed; treat as done rather than failing
+                Err(UpgradeError::AlreadyExecuted) => Ok(()),
+                other => other,
+            },
+            Err(e) => Err(e),
+        };
+        let mut attempt = ExecutionAttempt {
+            attempt: job.attempts,
+            outcome: "succeeded".to_string(),
+            error: None,
+            duration_ms: started.elapsed().as_millis() as i64,
+            retry_at: None,
+            finished_at: chrono::Utc::now().timestamp(),
+        };
+
+        let outcome = match result {
+            Ok(()) => {
+                self.record_slo_outcome(true).await;
+                self.record_history(&job.proposal_id).await;
+                self.database.complete_execution_job(&job.job_id).await
+            }
+            // Wait for dependencies to recover, or for the proposals this one
+            // depends on to execute, without burning through attempts
+            Err(e @ (UpgradeError::DependenciesUnhealthy(_) | UpgradeError::DependencyPending(_))) => {
+                tracing::warn!("Execution job {} held back: {}", job.job_id, e);
+                let delay = self.poll_interval;
+                attempt.outcome = "held".to_string();
+                attempt.error = Some(e.to_string());
+                attempt.retry_at = Some(attempt.finished_at + delay.as_secs() as i64);
+                self.database
+                    .fail_execution_job(&job.job_id, &e.to_string(), false, delay.as_secs() as i64)
+                    .await
+            }
+            Err(e) => {
+                let retryable = Self::is_retryable(&e);
+                let terminal = !retryable || job.attempts >= job.max_attempts;
+                let delay = retry_delay(job.attempts, self.retry_base, self.retry_max);
+                attempt.error = Some(e.to_string());
+                if terminal {
+                    tracing::error!("Execution job {} failed permanently: {}", job.job_id, e);
+                    attempt.outcome = "failed".to_string();
+                    self.record_slo_outcome(false).await;
+                    self.record_failure(&job.proposal_id, &e).await;
+                    if retryable {
+                        self.alert_exhausted(&job, &e).await;
+                    }
+                } else {
+                    tracing::warn!(
+                        "Execution job {} failed, retrying in {}s: {}",
+                        job.job_id,
+                        delay.as_secs(),
+                        e
+                    );
+                    attempt.outcome = "retrying".to_string();
+                    attempt.retry_at = Some(attempt.finished_at + delay.as_secs() as i64);
+                }
+                self.database
+                    .fail_execution_job(&job.job_id, &e.to_string(), terminal, delay.as_secs() as i64)
+                    .await
+            }
+        };
+
+        if let Err(e) = outcome {
+            tracing::error!("Failed to record execution job {} outcome: {}", job.job_id, e);
+        }
+        if let Err(e) = self.database.record_execution_attempt(&job.job_id, &attempt).await {
+            tracing::error!("Failed to record attempt {} of execution job {}: {}", attempt.attempt, job.job_id, e);
+        }
+    }
+
+    /// Page when transient failures used up every attempt; the proposal needs
+    /// someone to look at the cluster before it is executed again
+    async fn alert_exhausted(&self, job: &ExecutionJob, error: &UpgradeError) {
+        if let Some(monitoring) = &self.monitoring {
+            monitoring
+                .send_alert(
+                    AlertLevel::Critical,
+                    format!(
+                        "Execution of proposal {} failed after {} attempts: {}",
+                        job.proposal_id, job.attempts, error
+                    ),
+                    COMPONENT_EXECUTION.to_string(),
+                )
+                .await;
+        }
+    }
+
+    /// Jobs count toward the execution SLO once they finish, not per attempt
+    async fn record_slo_outcome(&self, success: bool) {
+        if let Some(monitoring) = &self.monitoring {
+            monitoring.record_outcome("execution", success).await;
+        }
+    }
+
+    async fn check_preconditions(&self, job: &ExecutionJob) -> Result<(), UpgradeError> {
+        if !job.forced {
+            if let Some(monitoring) = &self.monitoring {
+                monitoring.ensure_execution_dependencies_healthy().await?;
+            }
+        }
+        if let Some(auditor) = &self.auditor {
+            let proposal = self.proposal_manager.get_proposal(&job.proposal_id).await?;
+            let buffer = proposal.new_buffer.parse().map_err(|_| UpgradeError::InvalidPubkey)?;
+            auditor.ensure_not_denylisted(&job.proposal_id, buffer).await?;
+        }
+        if let Some(invariants) = &self.invariants {
+            invariants.ensure_pre_execution(&job.proposal_id).await?;
+        }
+        if let Some(snapshots) = &self.snapshots {
+            if let Err(e) = snapshots.capture(SnapshotLabel::PreUpgrade, Some(&job.proposal_id)).await {
+                tracing::warn!("Pre-upgrade snapshot failed for {}: {}", job.proposal_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep the cause of a failed execution, with program errors already
+    /// named by the error decoder, in `upgrade_history` and the notification stream
+    async fn record_failure(&self, proposal_id: &str, error: &UpgradeError) {
+        // Rejected before reaching the chain; the job's last_error is enough
+        if !matches!(error, UpgradeError::SolanaError(_) | UpgradeError::MultisigError(_)) {
+            return;
+        }
+
+        let proposal = match self.proposal_manager.get_proposal(proposal_id).await {
+            Ok(proposal) => proposal,
+            Err(e) => {
+                tracing::error!("Failed to load failed proposal {}: {}", proposal_id, e);
+                return;
+            }
+        };
+        let new_program_hash = proposal.source
+            .as_ref()
+            .map(|s| s.artifact_hash.clone())
+            .unwrap_or_default();
+        let message = error.to_string();
+
+        if let Err(e) = self.database
+            .record_upgrade_history(
+                proposal_id,
+                &proposal.program,
+                None,
+                &new_program_hash,
+                false,
+                Some(&message),
+                None,
+            )
+            .await
+        {
+            tracing::error!("Failed to record upgrade failure for {}: {}", proposal_id, e);
+        }
+
+        if let Some(notifications) = &self.notifications {
+            notifications
+                .notify_upgrade_failed(proposal_id.to_string(), proposal.program, message)
+                .await;
+        }
+    }
+
+    /// Post-execution bookkeeping: optional IDL publish, its hash on the
+    /// upgrade_history row, the pre/post account-set diff and the
+    /// post-upgrade watch.
+    /// The upgrade has already landed, so failures here are logged, not retried.
+    async fn record_history(&self, proposal_id: &str) {
+        let proposal = match self.proposal_manager.get_proposal(proposal_id).await {
+            Ok(proposal) => proposal,
+            Err(e) => {
+                tracing::error!("Failed to load executed proposal {}: {}", proposal_id, e);
+                return;
+            }
+        };
+
+        let idl_hash = if proposal.publish_idl {
+            match self.idl_publisher.publish(&proposal.program).await {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    tracing::error!("IDL publish failed for proposal {}: {}", proposal_id, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(invariants) = &self.invariants {
+            invariants
+                .verify_or_rollback(proposal_id, InvariantPhase::PostUpgrade)
+                .await;
+        }
+
+        // Authority changes leave the program's code untouched
+        if let (Some(watch), None) = (&self.watch, &proposal.authority_change) {
+            let watch = watch.clone();
+            let proposal_id = proposal_id.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = watch.watch(&proposal_id).await {
+                    tracing::error!("Post-upgrade watch failed for {}: {}", proposal_id, e);
+                }
+            });
+        }
+
+        // The history row itself was written with the executed status
+        if let Some(idl_hash) = &idl_hash {
+            if let Err(e) = self.database.save_upgrade_idl_hash(proposal_id, idl_hash).await {
+                tracing::error!("Failed to record IDL hash for {}: {}", proposal_id, e);
+            }
+        }
+
+        if let Some(snapshots) = &self.snapshots {
+            let compared = match snapshots.capture(SnapshotLabel::PostUpgrade, Some(proposal_id)).await {
+                Ok(_) => snapshots.compare_upgrade(proposal_id).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = compared {
+                tracing::warn!("Account set comparison failed for {}: {}", proposal_id, e);
+            }
+        }
+    }
+
+    /// Only infrastructure failures are worth retrying; governance rejections are final
+    fn is_retryable(err: &UpgradeError) -> bool {
+        matches!(
+            err,
+            UpgradeError::SolanaError(_)
+                | UpgradeError::MultisigError(_)
+                | UpgradeError::DatabaseError(_)
+                | UpgradeError::InternalError(_)
+        )
+    }
+}
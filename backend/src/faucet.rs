@@ -0,0 +1,217 @@
+use crate::error::UpgradeError;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Base fee per signature, used to estimate transaction costs
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+
+/// Cluster the RPC endpoint serves, identified by genesis hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    /// Anything else, e.g. solana-test-validator
+    Local,
+}
+
+impl Cluster {
+    pub fn from_genesis_hash(hash: &str) -> Self {
+        match hash {
+            MAINNET_GENESIS_HASH => Cluster::Mainnet,
+            DEVNET_GENESIS_HASH => Cluster::Devnet,
+            TESTNET_GENESIS_HASH => Cluster::Testnet,
+            _ => Cluster::Local,
+        }
+    }
+
+    /// Ask the RPC endpoint for its genesis hash
+    pub async fn detect(rpc_client: &AsyncRpcClient) -> Result<Self, UpgradeError> {
+        let hash = rpc_client
+            .get_genesis_hash()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get genesis hash: {}", e)))?;
+        Ok(Cluster::from_genesis_hash(&hash.to_string()))
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "mainnet-beta" => Some(Cluster::Mainnet),
+            "devnet" => Some(Cluster::Devnet),
+            "testnet" => Some(Cluster::Testnet),
+            "local" => Some(Cluster::Local),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Local => "local",
+        }
+    }
+
+    /// Whether the cluster runs a faucet
+    pub fn has_faucet(&self) -> bool {
+        *self != Cluster::Mainnet
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AirdropConfig {
+    pub enabled: bool,
+    /// Balance kept on top of what the operation needs
+    pub reserve_lamports: u64,
+    /// Lamports per airdrop request; public faucets cap this
+    pub request_lamports: u64,
+    pub max_requests: u32,
+}
+
+impl AirdropConfig {
+    pub fn from_env() -> Self {
+        let var = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            enabled: std::env::var("AUTO_AIRDROP").map(|v| v != "false").unwrap_or(true),
+            reserve_lamports: var("AIRDROP_RESERVE_LAMPORTS", LAMPORTS_PER_SOL / 2),
+            request_lamports: var("AIRDROP_REQUEST_LAMPORTS", LAMPORTS_PER_SOL),
+            max_requests: var("AIRDROP_MAX_REQUESTS", 5) as u32,
+        }
+    }
+
+    /// Airdrops needed to lift `balance` to `target`, or `None` if more than `max_requests`
+    pub fn requests_needed(&self, balance: u64, target: u64) -> Option<u32> {
+        let shortfall = target.saturating_sub(balance);
+        let requests = shortfall.div_ceil(self.request_lamports.max(1));
+        u32::try_from(requests).ok().filter(|n| *n <= self.max_requests)
+    }
+}
+
+/// Tops up a fee payer from the cluster faucet before operations that spend
+/// lamports, so devnet/testnet runs don't fail on an empty wallet. Never
+/// airdrops on mainnet; there it only reports the shortfall.
+pub struct AirdropFunder {
+    rpc_client: Arc<AsyncRpcClient>,
+    config: AirdropConfig,
+    cluster: OnceCell<Cluster>,
+}
+
+impl AirdropFunder {
+    pub fn new(rpc_client: Arc<AsyncRpcClient>, config: AirdropConfig) -> Self {
+        Self {
+            rpc_client,
+            config,
+            cluster: OnceCell::new(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        Self::new(Arc::new(AsyncRpcClient::new(rpc_url)), AirdropConfig::from_env())
+    }
+
+    pub async fn cluster(&self) -> Result<Cluster, UpgradeError> {
+        self.cluster
+            .get_or_try_init(|| Cluster::detect(&self.rpc_client))
+            .await
+            .copied()
+    }
+
+    /// Make sure `payer` holds `required_lamports` plus the configured reserve,
+    /// airdropping the difference where the cluster allows it.
+    /// Returns the balance afterwards.
+    pub async fn ensure_funded(&self, payer: &Pubkey, required_lamports: u64) -> Result<u64, UpgradeError> {
+        let target = required_lamports.saturating_add(self.config.reserve_lamports);
+        let mut balance = self.balance(payer).await?;
+        if balance >= target {
+            return Ok(balance);
+        }
+
+        let cluster = self.cluster().await?;
+        if !self.config.enabled || !cluster.has_faucet() {
+            // Only the operation's own cost is a hard requirement
+            if balance < required_lamports {
+                return Err(UpgradeError::SolanaError(format!(
+                    "Fee payer {} has {} lamports, needs {}",
+                    payer, balance, required_lamports
+                )));
+            }
+            tracing::warn!("Fee payer {} is below its reserve: {} < {} lamports", payer, balance, target);
+            return Ok(balance);
+        }
+
+        let requests = self.config.requests_needed(balance, target).ok_or_else(|| {
+            UpgradeError::SolanaError(format!(
+                "Fee payer {} needs {} lamports, more than {} airdrops of {} can cover",
+                payer,
+                target - balance,
+                self.config.max_requests,
+                self.config.request_lamports
+            ))
+        })?;
+
+        tracing::info!(
+            "Fee payer {} has {} lamports on {:?}, requesting {} airdrop(s)",
+            payer, balance, cluster, requests
+        );
+        for attempt in 1..=requests {
+            let signature = self.rpc_client
+                .request_airdrop(payer, self.config.request_lamports)
+                .await
+                .map_err(|e| UpgradeError::SolanaError(format!("Airdrop request failed: {}", e)))?;
+            self.confirm(&signature).await?;
+            balance = self.balance(payer).await?;
+            tracing::info!("Airdrop {}/{} confirmed, balance {} lamports", attempt, requests, balance);
+            if balance >= target {
+                break;
+            }
+        }
+
+        if balance < required_lamports {
+            return Err(UpgradeError::SolanaError(format!(
+                "Fee payer {} still has {} lamports after airdrops, needs {}",
+                payer, balance, required_lamports
+            )));
+        }
+
+        Ok(balance)
+    }
+
+    async fn balance(&self, payer: &Pubkey) -> Result<u64, UpgradeError> {
+        self.rpc_client
+            .get_balance(payer)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get balance: {}", e)))
+    }
+
+    async fn confirm(&self, signature: &solana_sdk::signature::Signature) -> Result<(), UpgradeError> {
+        for _ in 0..30 {
+            let confirmed = self.rpc_client
+                .confirm_transaction(signature)
+                .await
+                .map_err(|e| UpgradeError::SolanaError(e.to_string()))?;
+            if confirmed {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        Err(UpgradeError::SolanaError(format!("Airdrop {} was not confirmed", signature)))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::UpgradeError;
+use crate::multisig::AccountReader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+/// Bytes of a flag name; the program stores names zero-padded to this length
+pub const MAX_FLAG_NAME_LEN: usize = 32;
+
+/// Domain separator of the upgrade-manager flag change digest
+pub const FLAG_CHANGE_DIGEST_DOMAIN: &[u8] = b"goquant-flag-change-v1";
+
+/// Zero-padded on-chain name. Names are 1 to 32 bytes of ASCII letters,
+/// digits, `_`, `-` and `.`, so the managed program can match them byte for byte.
+pub fn encode_flag_name(name: &str) -> Result<[u8; MAX_FLAG_NAME_LEN], UpgradeError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_FLAG_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'));
+    if !valid {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "Invalid flag name '{}': use 1 to {} ASCII letters, digits, '_', '-' or '.'",
+            name, MAX_FLAG_NAME_LEN
+        )));
+    }
+
+    let mut bytes = [0; MAX_FLAG_NAME_LEN];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(bytes)
+}
+
+/// Name of an on-chain flag, without its padding
+pub fn decode_flag_name(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+/// Digest approvers sign over for a flag change, as the upgrade-manager
+/// program computes it
+pub fn flag_change_digest(
+    program: &Pubkey,
+    name: &[u8; MAX_FLAG_NAME_LEN],
+    enabled: bool,
+    kill_switch: bool,
+    revision: u32,
+    timelock_duration: i64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(FLAG_CHANGE_DIGEST_DOMAIN);
+    hasher.update(program.as_ref());
+    hasher.update(name);
+    hasher.update([enabled as u8, kill_switch as u8]);
+    hasher.update(revision.to_le_bytes());
+    hasher.update(timelock_duration.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Key in the buffer slot of a flag change proposal's address
+pub fn flag_change_target(name: &[u8; MAX_FLAG_NAME_LEN], enabled: bool, kill_switch: bool, revision: u32) -> Pubkey {
+    let mut hasher = Sha256::new();
+    hasher.update(FLAG_CHANGE_DIGEST_DOMAIN);
+    hasher.update(name);
+    hasher.update([enabled as u8, kill_switch as u8]);
+    hasher.update(revision.to_le_bytes());
+    Pubkey::new_from_array(hasher.finalize().into())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub enabled: bool,
+    /// Any member may turn the flag off without approvals
+    pub kill_switch: bool,
+    /// Changes made to the flag so far
+    pub revision: u32,
+    pub updated_at: i64,
+    pub updated_by: String,
+}
+
+/// A managed program's flags, from the upgrade-manager `feature_flags` PDA
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnchainFeatureFlags {
+    pub program: String,
+    pub account: String,
+    pub flags: Vec<FeatureFlag>,
+}
+
+impl OnchainFeatureFlags {
+    /// Flags of a program that never had one set
+    pub fn empty(program: &Pubkey, account: &Pubkey) -> Self {
+        Self {
+            program: program.to_string(),
+            account: account.to_string(),
+            flags: Vec::new(),
+        }
+    }
+
+    /// Decode the Anchor `FeatureFlags` account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8], account: &Pubkey) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        let program = reader.pubkey()?;
+        let flags = (0..reader.u32()?)
+            .map(|_| {
+                Ok(FeatureFlag {
+                    name: decode_flag_name(reader.take(MAX_FLAG_NAME_LEN)?),
+                    enabled: reader.u8()? == 1,
+                    kill_switch: reader.u8()? == 1,
+                    revision: reader.u32()?,
+                    updated_at: reader.u64()? as i64,
+                    updated_by: reader.pubkey()?.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+        Ok(Self {
+            program: program.to_string(),
+            account: account.to_string(),
+            flags,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FeatureFlag> {
+        self.flags.iter().find(|flag| flag.name == name)
+    }
+
+    /// Current revision of `name`; 0 for a flag that was never set
+    pub fn revision(&self, name: &str) -> u32 {
+        self.get(name).map_or(0, |flag| flag.revision)
+    }
+}
+
+/// Body of `POST /programs/:program/flags/:name/propose`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlagChangeRequest {
+    pub enabled: bool,
+    /// Let any member turn the flag off instantly once it is set
+    #[serde(default)]
+    pub kill_switch: bool,
+    pub description: String,
+}
+
+/// A flag change put up for approval on chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FlagChangeProposal {
+    /// Upgrade-manager proposal account; approve it with `approve_upgrade`
+    pub proposal_account: String,
+    pub program: String,
+    pub name: String,
+    pub enabled: bool,
+    pub kill_switch: bool,
+    /// Flag revision the change applies to
+    pub revision: u32,
+    /// Hex digest approvers commit to
+    pub approval_digest: String,
+    pub signature: String,
+}
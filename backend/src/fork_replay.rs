@@ -0,0 +1,388 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::proposal::Proposal;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcSimulateTransactionConfig, RpcTransactionConfig};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// Most recent transactions one fork test replays
+pub const MAX_FORK_REPLAYS: usize = 200;
+
+/// Log lines kept from the upgraded run of a diverging transaction
+pub const MAX_DIVERGENCE_LOGS: usize = 20;
+
+/// Bincode size of `UpgradeableLoaderState::ProgramData`: enum tag, slot,
+/// option tag, upgrade authority
+pub const PROGRAMDATA_METADATA_SIZE: usize = 4 + 8 + 1 + 32;
+
+/// `UpgradeableLoaderState::ProgramData` enum tag
+const PROGRAMDATA_TAG: [u8; 4] = [3, 0, 0, 0];
+
+/// Swap the ELF in a programdata account, keeping its slot and upgrade
+/// authority. Returns `None` for anything that is not programdata.
+pub fn programdata_with_elf(current: &[u8], elf: &[u8]) -> Option<Vec<u8>> {
+    if current.len() < PROGRAMDATA_METADATA_SIZE || current[..4] != PROGRAMDATA_TAG {
+        return None;
+    }
+
+    let mut data = current[..PROGRAMDATA_METADATA_SIZE].to_vec();
+    data.extend_from_slice(elf);
+    Some(data)
+}
+
+/// Result of simulating one replayed transaction on the fork
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplayOutcome {
+    pub success: bool,
+    pub error: Option<String>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+impl ReplayOutcome {
+    fn from_simulation(result: &RpcSimulateTransactionResult) -> Self {
+        Self {
+            success: result.err.is_none(),
+            error: result.err.as_ref().map(|e| e.to_string()),
+            compute_units_consumed: result.units_consumed,
+        }
+    }
+
+    /// Whether the upgrade changed how the transaction ends. Compute usage
+    /// alone is not a divergence.
+    pub fn diverges_from(&self, baseline: &ReplayOutcome) -> bool {
+        self.success != baseline.success || self.error != baseline.error
+    }
+}
+
+/// A transaction that ended differently on the upgraded fork
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplayDivergence {
+    pub signature: String,
+    pub baseline: ReplayOutcome,
+    pub upgraded: ReplayOutcome,
+    /// Last log lines of the upgraded run
+    pub logs: Vec<String>,
+}
+
+/// Percentage of `outcomes` that succeeded; 0 when nothing was replayed
+pub fn success_rate(outcomes: &[&ReplayOutcome]) -> f64 {
+    if outcomes.is_empty() {
+        return 0.0;
+    }
+    outcomes.iter().filter(|o| o.success).count() as f64 / outcomes.len() as f64 * 100.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForkTestReport {
+    pub id: String,
+    pub proposal_id: String,
+    pub program: String,
+    pub ran_at: i64,
+    pub duration_ms: u64,
+    /// Transactions simulated both before and after the upgrade
+    pub replayed: usize,
+    /// Transactions that could not be fetched, decoded or simulated
+    pub skipped: usize,
+    /// Success rate of the replays on the fork as it is
+    pub baseline_success_rate: f64,
+    /// Success rate of the same replays with the proposed binary
+    pub success_rate: f64,
+    pub divergences: Vec<ReplayDivergence>,
+    /// No replayed transaction ended differently
+    pub passed: bool,
+}
+
+/// Dry-runs a proposal against forked mainnet state: the program's most
+/// recent transactions on the source cluster are simulated on the fork
+/// (`FORK_TEST_RPC_URL`, e.g. surfpool) as it is, then again after the
+/// proposed binary is written into the program's programdata account.
+/// The fork must accept `surfnet_setAccount`; the original account is
+/// restored after the run. Nothing is sent to the source cluster.
+pub struct ForkTester {
+    fork_rpc_url: Option<String>,
+    source_rpc_url: String,
+    replay_count: usize,
+    http: reqwest::Client,
+    database: Option<Arc<Database>>,
+    /// Runs share the fork's programdata account, so only one runs at a time
+    running: Mutex<()>,
+}
+
+impl Default for ForkTester {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForkTester {
+    pub fn new() -> Self {
+        Self {
+            fork_rpc_url: std::env::var("FORK_TEST_RPC_URL").ok().filter(|v| !v.is_empty()),
+            source_rpc_url: std::env::var("FORK_SOURCE_RPC_URL")
+                .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
+            replay_count: std::env::var("FORK_TEST_REPLAY_COUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(50)
+                .clamp(1, MAX_FORK_REPLAYS),
+            http: reqwest::Client::new(),
+            database: None,
+            running: Mutex::new(()),
+        }
+    }
+
+    /// Keep reports, so `GET /upgrade/:id/fork-test` can show the latest one
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Replay the program's recent transactions against `elf`, the binary
+    /// `proposal` would deploy
+    pub async fn run(&self, proposal: &Proposal, elf: Vec<u8>) -> Result<ForkTestReport, UpgradeError> {
+        let fork_rpc_url = self.fork_rpc_url.as_ref().ok_or_else(|| {
+            UpgradeError::InvalidRequest("FORK_TEST_RPC_URL is required for fork tests".to_string())
+        })?;
+        if elf.is_empty() {
+            return Err(UpgradeError::InvalidRequest(format!("Proposal {} has no program binary", proposal.id)));
+        }
+        let program = proposal.program.parse::<Pubkey>().map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        let _running = self.running.lock().await;
+        let id = uuid::Uuid::new_v4().to_string();
+        let ran_at = chrono::Utc::now().timestamp();
+        let started = Instant::now();
+        tracing::info!("Fork test {} for proposal {}", id, proposal.id);
+
+        let source = AsyncRpcClient::new(self.source_rpc_url.clone());
+        let fork = AsyncRpcClient::new(fork_rpc_url.clone());
+        let (transactions, mut skipped) = self.recent_transactions(&source, &program).await?;
+
+        let mut baseline = Vec::with_capacity(transactions.len());
+        for (signature, transaction) in &transactions {
+            baseline.push(simulate(&fork, signature, transaction).await);
+        }
+
+        // Programs are loaded from their programdata; rewriting the program
+        // account afterwards makes the fork load the new ELF
+        let (programdata, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+        let program_account = fetch_account(&fork, &program).await?;
+        let original = fetch_account(&fork, &programdata).await?;
+        let data = programdata_with_elf(&original.data, &elf).ok_or_else(|| {
+            UpgradeError::InvalidRequest(format!("{} is not an upgradeable program on the fork", program))
+        })?;
+        let upgraded_programdata = Account {
+            lamports: original.lamports.max(fork.get_minimum_balance_for_rent_exemption(data.len()).await.unwrap_or(0)),
+            data,
+            ..original.clone()
+        };
+        self.set_account(fork_rpc_url, &programdata, &upgraded_programdata).await?;
+        let upgraded = match self.set_account(fork_rpc_url, &program, &program_account).await {
+            Ok(()) => {
+                let mut upgraded = Vec::with_capacity(transactions.len());
+                for (signature, transaction) in &transactions {
+                    upgraded.push(simulate(&fork, signature, transaction).await);
+                }
+                Ok(upgraded)
+            }
+            Err(e) => Err(e),
+        };
+
+        // Leave the fork as it was for the next run
+        let restored = match self.set_account(fork_rpc_url, &programdata, &original).await {
+            Ok(()) => self.set_account(fork_rpc_url, &program, &program_account).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = restored {
+            tracing::error!("Fork test {} could not restore {}: {}", id, program, e);
+        }
+        let upgraded = upgraded?;
+
+        let mut pairs = Vec::new();
+        for ((signature, _), (baseline, upgraded)) in transactions.iter().zip(baseline.into_iter().zip(upgraded)) {
+            match (baseline, upgraded) {
+                (Ok(baseline), Ok(upgraded)) => pairs.push((signature, baseline, upgraded)),
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::warn!("Fork test {} skipped {}: {}", id, signature, e);
+                    skipped += 1;
+                }
+            }
+        }
+
+        let divergences: Vec<ReplayDivergence> = pairs
+            .iter()
+            .filter(|(_, (baseline, _), (upgraded, _))| upgraded.diverges_from(baseline))
+            .map(|(signature, (baseline, _), (upgraded, logs))| ReplayDivergence {
+                signature: signature.to_string(),
+                baseline: baseline.clone(),
+                upgraded: upgraded.clone(),
+                logs: logs[logs.len().saturating_sub(MAX_DIVERGENCE_LOGS)..].to_vec(),
+            })
+            .collect();
+
+        let report = ForkTestReport {
+            id,
+            proposal_id: proposal.id.clone(),
+            program: program.to_string(),
+            ran_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            replayed: pairs.len(),
+            skipped,
+            baseline_success_rate: success_rate(&pairs.iter().map(|(_, (b, _), _)| b).collect::<Vec<_>>()),
+            success_rate: success_rate(&pairs.iter().map(|(_, _, (u, _))| u).collect::<Vec<_>>()),
+            passed: divergences.is_empty(),
+            divergences,
+        };
+
+        if let Some(database) = &self.database {
+            database.insert_fork_test(&report).await?;
+        }
+        tracing::info!(
+            "Fork test {} replayed {} transactions: {:.1}% succeeded ({:.1}% before), {} diverged",
+            report.id,
+            report.replayed,
+            report.success_rate,
+            report.baseline_success_rate,
+            report.divergences.len()
+        );
+
+        Ok(report)
+    }
+
+    /// Latest report for a proposal
+    pub async fn latest(&self, proposal_id: &str) -> Result<ForkTestReport, UpgradeError> {
+        let report = match &self.database {
+            Some(database) => database.latest_fork_test(proposal_id).await?,
+            None => None,
+        };
+        report.ok_or_else(|| UpgradeError::ForkTestNotFound(proposal_id.to_string()))
+    }
+
+    /// The program's most recent transactions on the source cluster, newest
+    /// first, and how many of them could not be fetched or decoded
+    async fn recent_transactions(
+        &self,
+        source: &AsyncRpcClient,
+        program: &Pubkey,
+    ) -> Result<(Vec<(Signature, VersionedTransaction)>, usize), UpgradeError> {
+        let statuses = source
+            .get_signatures_for_address_with_config(
+                program,
+                GetConfirmedSignaturesForAddress2Config {
+                    limit: Some(self.replay_count),
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch signatures: {}", e)))?;
+
+        let mut transactions = Vec::with_capacity(statuses.len());
+        let mut skipped = 0;
+        for status in statuses {
+            let Ok(signature) = Signature::from_str(&status.signature) else {
+                skipped += 1;
+                continue;
+            };
+            let fetched = source
+                .get_transaction_with_config(
+                    &signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await;
+            match fetched.ok().and_then(|t| t.transaction.transaction.decode()) {
+                Some(transaction) => transactions.push((signature, transaction)),
+                None => {
+                    tracing::warn!("Fork test could not load transaction {}", signature);
+                    skipped += 1;
+                }
+            }
+        }
+
+        Ok((transactions, skipped))
+    }
+
+    /// Overwrite an account on the fork with surfpool's `surfnet_setAccount`
+    async fn set_account(&self, fork_rpc_url: &str, address: &Pubkey, account: &Account) -> Result<(), UpgradeError> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "surfnet_setAccount",
+            "params": [
+                address.to_string(),
+                {
+                    "lamports": account.lamports,
+                    "data": hex::encode(&account.data),
+                    "owner": account.owner.to_string(),
+                    "executable": account.executable,
+                },
+            ],
+        });
+
+        let response: serde_json::Value = self.http
+            .post(fork_rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to write {} on the fork: {}", address, e)))?
+            .json()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Invalid response from the fork: {}", e)))?;
+
+        match response.get("error") {
+            Some(error) => Err(UpgradeError::SolanaError(format!(
+                "Fork rejected the write to {}: {}",
+                address, error
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+async fn fetch_account(fork: &AsyncRpcClient, address: &Pubkey) -> Result<Account, UpgradeError> {
+    fork.get_account(address)
+        .await
+        .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch {} from the fork: {}", address, e)))
+}
+
+/// Simulate a recorded transaction on the fork. Its signatures are not
+/// checked and its blockhash is swapped for a recent one, since both are
+/// long stale by the time it is replayed.
+async fn simulate(
+    fork: &AsyncRpcClient,
+    signature: &Signature,
+    transaction: &VersionedTransaction,
+) -> Result<(ReplayOutcome, Vec<String>), UpgradeError> {
+    let result = fork
+        .simulate_transaction_with_config(
+            transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                encoding: Some(UiTransactionEncoding::Base64),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| UpgradeError::SolanaError(format!("Failed to simulate {}: {}", signature, e)))?
+        .value;
+
+    Ok((ReplayOutcome::from_simulation(&result), result.logs.unwrap_or_default()))
+}
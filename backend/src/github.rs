@@ -0,0 +1,337 @@
+use crate::announcements::format_utc;
+use crate::artifacts::ArtifactRegistry;
+use crate::error::UpgradeError;
+use crate::program_builder::ProgramBuilder;
+use crate::proposal::{Proposal, ProposalManager, ProposalOptions, ProposalSource, ProposalStatus, RiskTier};
+use crate::secrets::SecretStore;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+
+/// Subset of GitHub's `release` webhook payload
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseEvent {
+    pub action: String,
+    pub release: Release,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub target_commitish: String,
+    pub html_url: Option<String>,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+}
+
+/// Which releases are allowed to create proposals
+#[derive(Debug, Clone)]
+pub struct ReleaseRules {
+    pub tag_prefix: String,
+    pub allow_prerelease: bool,
+}
+
+impl ReleaseRules {
+    pub fn from_env() -> Self {
+        Self {
+            tag_prefix: std::env::var("GITHUB_RELEASE_TAG_PREFIX")
+                .unwrap_or_else(|_| "v".to_string()),
+            allow_prerelease: std::env::var("GITHUB_ALLOW_PRERELEASE")
+                .map(|v| v == "true")
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn matches(&self, event: &ReleaseEvent) -> bool {
+        event.action == "published"
+            && !event.release.draft
+            && (self.allow_prerelease || !event.release.prerelease)
+            && event.release.tag_name.starts_with(&self.tag_prefix)
+    }
+}
+
+/// Risk tier declared by the release author on a `Risk: <tier>` line of the notes
+pub fn release_risk_tier(body: Option<&str>) -> Option<RiskTier> {
+    body?.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("risk") {
+            return None;
+        }
+        RiskTier::parse(&value.trim().to_lowercase())
+    })
+}
+
+/// Turns tagged GitHub releases into upgrade proposals
+pub struct GitHubReleaseHandler {
+    /// Holds `GITHUB_WEBHOOK_SECRET`; read per request so rotations apply immediately
+    secrets: Arc<SecretStore>,
+    rules: ReleaseRules,
+    source_path: String,
+    program_builder: Arc<ProgramBuilder>,
+    proposal_manager: Arc<ProposalManager>,
+    artifacts: Arc<ArtifactRegistry>,
+}
+
+impl GitHubReleaseHandler {
+    pub fn new(
+        program_builder: Arc<ProgramBuilder>,
+        proposal_manager: Arc<ProposalManager>,
+        artifacts: Arc<ArtifactRegistry>,
+        secrets: Arc<SecretStore>,
+    ) -> Self {
+        Self {
+            secrets,
+            rules: ReleaseRules::from_env(),
+            source_path: std::env::var("PROGRAM_SOURCE_PATH")
+                .unwrap_or_else(|_| ".".to_string()),
+            program_builder,
+            proposal_manager,
+            artifacts,
+        }
+    }
+
+    /// Verify the `X-Hub-Signature-256` header against the raw request body
+    pub fn verify_signature(&self, body: &[u8], signature_header: Option<&str>) -> Result<(), UpgradeError> {
+        let secret = self.secrets.get("GITHUB_WEBHOOK_SECRET").ok_or_else(|| {
+            UpgradeError::InternalError("GITHUB_WEBHOOK_SECRET not configured".to_string())
+        })?;
+
+        let signature = signature_header
+            .and_then(|h| h.strip_prefix("sha256="))
+            .and_then(|h| hex::decode(h).ok())
+            .ok_or(UpgradeError::InvalidWebhookSignature)?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| UpgradeError::InternalError(format!("Invalid webhook secret: {}", e)))?;
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| UpgradeError::InvalidWebhookSignature)
+    }
+
+    /// Returns the event if it should produce a proposal
+    pub fn accept(&self, body: &[u8]) -> Result<Option<ReleaseEvent>, UpgradeError> {
+        let event: ReleaseEvent = serde_json::from_slice(body)
+            .map_err(|e| UpgradeError::InvalidRequest(format!("Invalid release payload: {}", e)))?;
+
+        if !self.rules.matches(&event) {
+            tracing::info!("Ignoring release {} ({})", event.release.tag_name, event.action);
+            return Ok(None);
+        }
+
+        Ok(Some(event))
+    }
+
+    /// Build the tagged source, upload it to a buffer and open a proposal
+    pub async fn process_release(&self, event: ReleaseEvent) -> Result<String, UpgradeError> {
+        let release = event.release;
+        tracing::info!("Processing release {} at {}", release.tag_name, release.target_commitish);
+
+        let binary = self.program_builder.build_program(&self.source_path).await?;
+        let artifact = self.artifacts
+            .register(&binary, "upgrade_manager", Some(&release.target_commitish))
+            .await?;
+        let artifact_hash = artifact.program_hash;
+        let buffer = self.program_builder.create_buffer(&binary).await?;
+
+        let description = format!(
+            "{}\n\nRelease: {}\nCommit: {}\nArtifact SHA-256: {}\n\n{}",
+            release.name.as_deref().unwrap_or(&release.tag_name),
+            release.tag_name,
+            release.target_commitish,
+            artifact_hash,
+            release.body.as_deref().unwrap_or_default(),
+        );
+
+        let proposal_id = self.proposal_manager
+            .propose_upgrade_with_options(
+                buffer,
+                description,
+                ProposalOptions {
+                    source: Some(ProposalSource {
+                        tag: release.tag_name.clone(),
+                        commit: release.target_commitish.clone(),
+                        artifact_hash,
+                        release_url: release.html_url.clone(),
+                    }),
+                    publish_idl: true,
+                    risk_tier: release_risk_tier(release.body.as_deref()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        tracing::info!("Release {} proposed as {}", release.tag_name, proposal_id);
+
+        Ok(proposal_id)
+    }
+}
+
+/// Body of GitHub's "create a commit status" request
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CommitStatus {
+    /// `pending`, `success`, `failure` or `error`
+    pub state: &'static str,
+    pub description: String,
+    pub context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_url: Option<String>,
+}
+
+/// The status shown on a proposal's source commit. GitHub truncates
+/// descriptions past 140 characters.
+pub fn commit_status(proposal: &Proposal, context: &str, target_url: Option<String>) -> CommitStatus {
+    let (state, description) = match proposal.status {
+        ProposalStatus::Proposed => (
+            "pending",
+            format!("Upgrade proposed, 0/{} approvals", proposal.approval_threshold),
+        ),
+        ProposalStatus::Approved => (
+            "pending",
+            format!(
+                "Upgrade approved by {}/{} members",
+                proposal.approvals.len(),
+                proposal.approval_threshold
+            ),
+        ),
+        ProposalStatus::TimelockActive => (
+            "pending",
+            format!("Approved, executable after {}", format_utc(proposal.timelock_until)),
+        ),
+        ProposalStatus::Frozen => (
+            "failure",
+            "Upgrade frozen by a critical audit finding".to_string(),
+        ),
+        ProposalStatus::Executed => ("success", "Upgrade executed on chain".to_string()),
+        ProposalStatus::Cancelled => (
+            "failure",
+            match &proposal.cancellation {
+                Some(cancellation) => format!("Upgrade cancelled ({})", cancellation.reason.as_str()),
+                None => "Upgrade cancelled".to_string(),
+            },
+        ),
+    };
+
+    CommitStatus {
+        state,
+        description: description.chars().take(140).collect(),
+        context: context.to_string(),
+        target_url,
+    }
+}
+
+/// Whether `reference` is a full commit SHA rather than a branch or tag
+pub fn is_commit_sha(reference: &str) -> bool {
+    reference.len() == 40 && reference.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[derive(Debug, Clone)]
+pub struct CommitStatusConfig {
+    /// `GITHUB_REPOSITORY` (`owner/repo`) releases are published from; unset disables statuses
+    pub repository: Option<String>,
+    /// `GITHUB_API_URL`, for GitHub Enterprise (default https://api.github.com)
+    pub api_url: String,
+    /// `GITHUB_STATUS_CONTEXT` the statuses are grouped under
+    pub context: String,
+    /// `GITHUB_STATUS_TARGET_URL`: dashboard link, with `{id}` replaced by the proposal id
+    pub target_url: Option<String>,
+}
+
+impl CommitStatusConfig {
+    pub fn from_env() -> Self {
+        Self {
+            repository: std::env::var("GITHUB_REPOSITORY").ok().filter(|repo| !repo.is_empty()),
+            api_url: std::env::var("GITHUB_API_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|_| "https://api.github.com".to_string()),
+            context: std::env::var("GITHUB_STATUS_CONTEXT")
+                .unwrap_or_else(|_| "goquant/upgrade".to_string()),
+            target_url: std::env::var("GITHUB_STATUS_TARGET_URL").ok().filter(|url| !url.is_empty()),
+        }
+    }
+}
+
+/// Posts a proposal's status to the commit its binary was built from, so the
+/// pull request shows where the deployment stands. Authenticates with the
+/// `GITHUB_TOKEN` secret, which needs the `repo:status` scope.
+pub struct CommitStatusReporter {
+    config: CommitStatusConfig,
+    secrets: Arc<SecretStore>,
+    http: reqwest::Client,
+}
+
+impl CommitStatusReporter {
+    pub fn from_env(secrets: Arc<SecretStore>) -> Self {
+        Self {
+            config: CommitStatusConfig::from_env(),
+            secrets,
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .user_agent("goquant-upgrade-service")
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Post the status of `proposal` on its source commit. Proposals without
+    /// a source, or a reporter without a repository or token, are skipped.
+    pub async fn report(&self, proposal: &Proposal) -> Result<(), UpgradeError> {
+        let (Some(source), Some(repository), Some(token)) = (
+            &proposal.source,
+            &self.config.repository,
+            self.secrets.get("GITHUB_TOKEN").filter(|token| !token.is_empty()),
+        ) else {
+            return Ok(());
+        };
+
+        let sha = if is_commit_sha(&source.commit) {
+            source.commit.clone()
+        } else {
+            self.resolve_commit(repository, &source.commit, &token).await?
+        };
+        let target_url = self
+            .config
+            .target_url
+            .as_ref()
+            .map(|url| url.replace("{id}", &proposal.id));
+        let status = commit_status(proposal, &self.config.context, target_url);
+
+        self.http
+            .post(format!("{}/repos/{}/statuses/{}", self.config.api_url, repository, sha))
+            .bearer_auth(&token)
+            .header("Accept", "application/vnd.github+json")
+            .json(&status)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to post commit status: {}", e)))?;
+
+        tracing::info!("Commit status {} posted on {} for proposal {}", status.state, sha, proposal.id);
+
+        Ok(())
+    }
+
+    /// SHA a release's `target_commitish` (usually a branch) pointed at
+    async fn resolve_commit(&self, repository: &str, reference: &str, token: &str) -> Result<String, UpgradeError> {
+        let sha = self
+            .http
+            .get(format!("{}/repos/{}/commits/{}", self.config.api_url, repository, reference))
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github.sha")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to resolve {}: {}", reference, e)))?
+            .text()
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to resolve {}: {}", reference, e)))?;
+
+        let sha = sha.trim().to_string();
+        if !is_commit_sha(&sha) {
+            return Err(UpgradeError::InternalError(format!("{} did not resolve to a commit", reference)));
+        }
+        Ok(sha)
+    }
+}
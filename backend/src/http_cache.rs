@@ -0,0 +1,123 @@
+use axum::body::{Body, HttpBody};
+use axum::extract::{MatchedPath, Request};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+/// Responses larger than this are sent without an ETag rather than buffered
+pub const MAX_ETAG_BODY_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Content-addressed downloads never change
+pub const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// Unauthenticated explorer data, fine to serve slightly stale from a CDN
+pub const CACHE_PUBLIC: &str = "public, max-age=15";
+/// Live state: clients must revalidate every time, which the ETag makes cheap
+pub const CACHE_REVALIDATE: &str = "private, no-cache";
+/// Health and metrics must never come from a cache
+pub const CACHE_NONE: &str = "no-store";
+
+/// `Cache-Control` for a route template, with or without the `/v1` prefix
+pub fn cache_control_for(route: &str) -> &'static str {
+    let route = route.strip_prefix("/v1").unwrap_or(route);
+
+    if route == "/artifacts/:hash/download" {
+        CACHE_IMMUTABLE
+    } else if route.starts_with("/public/") {
+        CACHE_PUBLIC
+    } else if route.starts_with("/monitoring/") || route == "/metrics" {
+        CACHE_NONE
+    } else {
+        CACHE_REVALIDATE
+    }
+}
+
+/// Weak validator over the uncompressed body. Weak because the same entity is
+/// also served gzip or brotli encoded.
+pub fn etag_for(body: &[u8]) -> String {
+    format!("W/\"{}\"", &hex::encode(Sha256::digest(body))[..32])
+}
+
+/// `If-None-Match` semantics with weak comparison: `*` or any listed tag equal
+/// to `etag` once `W/` prefixes are ignored
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let expected = opaque(etag);
+
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == expected)
+}
+
+/// Adds `Cache-Control` and an `ETag` to successful GET responses and answers
+/// `304 Not Modified` when the client already holds the current version.
+/// Streaming responses (SSE, WebSocket upgrades) pass through untouched.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+
+    let cache_control = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| cache_control_for(route.as_str()))
+        .unwrap_or(CACHE_REVALIDATE);
+    let request_headers = request.headers().clone();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    parts
+        .headers
+        .entry(header::CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static(cache_control));
+
+    // Handlers that know their version (content-addressed downloads) set the ETag themselves
+    let existing = parts
+        .headers
+        .get(header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let (etag, body) = match existing {
+        Some(etag) => (etag, body),
+        None => {
+            let buffered = body
+                .size_hint()
+                .exact()
+                .is_some_and(|len| len <= MAX_ETAG_BODY_BYTES);
+            if !buffered {
+                return Response::from_parts(parts, body);
+            }
+
+            let bytes = match axum::body::to_bytes(body, MAX_ETAG_BODY_BYTES as usize).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::error!("Failed to buffer response for ETag: {}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            };
+
+            let etag = etag_for(&bytes);
+            if let Ok(value) = HeaderValue::from_str(&etag) {
+                parts.headers.insert(header::ETAG, value);
+            }
+            (etag, Body::from(bytes))
+        }
+    };
+
+    if if_none_match(&request_headers, &etag) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_DISPOSITION);
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    Response::from_parts(parts, body)
+}
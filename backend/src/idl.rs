@@ -0,0 +1,60 @@
+use crate::error::UpgradeError;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Publishes a program's Anchor IDL on chain after an upgrade.
+///
+/// Delegates to `anchor idl upgrade`, which writes the IDL into a buffer
+/// (`idl_write`) and swaps it into the program's IDL account (`set_buffer`).
+pub struct IdlPublisher {
+    idl_path: PathBuf,
+    rpc_url: String,
+    wallet_path: Option<String>,
+}
+
+impl IdlPublisher {
+    pub fn new() -> Self {
+        Self {
+            idl_path: std::env::var("IDL_PATH")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| PathBuf::from("target/idl/upgrade_manager.json")),
+            rpc_url: std::env::var("SOLANA_RPC_URL")
+                .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string()),
+            wallet_path: std::env::var("FEE_PAYER_KEYPAIR").ok(),
+        }
+    }
+
+    /// SHA-256 of the IDL file that would be published
+    pub fn idl_hash(&self) -> Result<String, UpgradeError> {
+        let idl = std::fs::read(&self.idl_path)
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to read IDL: {}", e)))?;
+        Ok(hex::encode(Sha256::digest(&idl)))
+    }
+
+    /// Publish the IDL for `program_id`, returning its hash
+    pub async fn publish(&self, program_id: &str) -> Result<String, UpgradeError> {
+        let idl_hash = self.idl_hash()?;
+        tracing::info!("Publishing IDL {} for program {}", idl_hash, program_id);
+
+        let mut cmd = Command::new("anchor");
+        cmd.args(["idl", "upgrade", program_id, "--filepath"])
+            .arg(&self.idl_path)
+            .args(["--provider.cluster", &self.rpc_url]);
+        if let Some(wallet) = &self.wallet_path {
+            cmd.args(["--provider.wallet", wallet]);
+        }
+
+        let output = tokio::task::spawn_blocking(move || cmd.output())
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("IDL publish task failed: {}", e)))?
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to run anchor: {}", e)))?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(UpgradeError::InternalError(format!("IDL publish error: {}", error)));
+        }
+
+        Ok(idl_hash)
+    }
+}
@@ -0,0 +1,501 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const MAX_TITLE_LEN: usize = 255;
+
+/// Resolved incidents stay on the public status page this long
+pub const RECENTLY_RESOLVED_SECONDS: i64 = 7 * 24 * 3600;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentStatus {
+    Investigating,
+    /// The cause is known and a fix or rollback is under way
+    Identified,
+    /// Fixed, and being watched before closing
+    Monitoring,
+    Resolved,
+}
+
+impl IncidentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IncidentStatus::Investigating => "investigating",
+            IncidentStatus::Identified => "identified",
+            IncidentStatus::Monitoring => "monitoring",
+            IncidentStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "investigating" => Some(IncidentStatus::Investigating),
+            "identified" => Some(IncidentStatus::Identified),
+            "monitoring" => Some(IncidentStatus::Monitoring),
+            "resolved" => Some(IncidentStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered from least to most severe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentSeverity {
+    Minor,
+    Major,
+    Critical,
+}
+
+/// What opened an incident
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentSource {
+    /// Opened by an operator through the API
+    Manual,
+    /// A rollback of the managed program started
+    Rollback,
+    /// A post-upgrade watch expression flagged an anomaly
+    Watch,
+    /// Post-upgrade or post-migration invariants failed
+    Invariants,
+    /// The multisig paused a managed program through the emergency path
+    EmergencyPause,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentLinkKind {
+    /// `reference` is a proposal id
+    Proposal,
+    /// `reference` is the alerting component, `detail` the alert message
+    Alert,
+    /// `reference` is a `rollback_events` id
+    Rollback,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncidentLink {
+    pub kind: IncidentLinkKind,
+    pub reference: String,
+    pub detail: Option<String>,
+    pub linked_at: i64,
+}
+
+impl IncidentLink {
+    pub fn new(kind: IncidentLinkKind, reference: &str, detail: Option<String>, now: i64) -> Self {
+        Self {
+            kind,
+            reference: reference.to_string(),
+            detail,
+            linked_at: now,
+        }
+    }
+}
+
+/// A status update posted to an incident; shown on the public status page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IncidentUpdate {
+    pub status: IncidentStatus,
+    pub message: String,
+    /// `None` for updates posted by the service itself
+    pub author: Option<String>,
+    pub posted_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Incident {
+    pub id: String,
+    pub title: String,
+    pub status: IncidentStatus,
+    pub severity: IncidentSeverity,
+    pub source: IncidentSource,
+    /// The program affected, when known
+    pub program: Option<String>,
+    pub opened_by: Option<String>,
+    pub opened_at: i64,
+    pub updated_at: i64,
+    pub resolved_at: Option<i64>,
+    /// Proposals, alerts and rollbacks, in the order they were linked
+    pub links: Vec<IncidentLink>,
+    /// Oldest first
+    pub updates: Vec<IncidentUpdate>,
+}
+
+impl Incident {
+    pub fn is_active(&self) -> bool {
+        self.status != IncidentStatus::Resolved
+    }
+
+    /// References of the links of `kind`, oldest first
+    pub fn linked(&self, kind: IncidentLinkKind) -> impl Iterator<Item = &str> {
+        self.links
+            .iter()
+            .filter(move |link| link.kind == kind)
+            .map(|link| link.reference.as_str())
+    }
+
+    /// Add `link` unless the same reference is already linked
+    pub fn link(&mut self, link: IncidentLink) {
+        if !self.links.iter().any(|l| l.kind == link.kind && l.reference == link.reference) {
+            self.updated_at = self.updated_at.max(link.linked_at);
+            self.links.push(link);
+        }
+    }
+
+    fn ensure_active(&self) -> Result<(), UpgradeError> {
+        if !self.is_active() {
+            return Err(UpgradeError::InvalidRequest(format!("Incident {} is already resolved", self.id)));
+        }
+        Ok(())
+    }
+
+    /// Post a status update; severity only ever changes when one is given
+    pub fn post(&mut self, change: IncidentChange, now: i64) -> Result<(), UpgradeError> {
+        self.ensure_active()?;
+        let message = change.message.trim().to_string();
+        if message.is_empty() {
+            return Err(UpgradeError::InvalidRequest("message must not be empty".to_string()));
+        }
+
+        if let Some(status) = change.status {
+            self.status = status;
+        }
+        if let Some(severity) = change.severity {
+            self.severity = severity;
+        }
+        if self.status == IncidentStatus::Resolved {
+            self.resolved_at = Some(now);
+        }
+        self.updates.push(IncidentUpdate {
+            status: self.status,
+            message,
+            author: change.author,
+            posted_at: now,
+        });
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Fold another failure into this incident, raising its severity if the
+    /// detection is worse
+    pub fn absorb(&mut self, detection: &Detection, now: i64) -> Result<(), UpgradeError> {
+        self.post(
+            IncidentChange {
+                status: None,
+                severity: Some(self.severity.max(detection.severity)),
+                message: detection.detail.clone(),
+                author: None,
+            },
+            now,
+        )?;
+        for link in detection.links(now) {
+            self.link(link);
+        }
+        Ok(())
+    }
+
+    pub fn public(&self) -> PublicIncident {
+        PublicIncident {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            status: self.status,
+            severity: self.severity,
+            opened_at: self.opened_at,
+            updated_at: self.updated_at,
+            resolved_at: self.resolved_at,
+            updates: self
+                .updates
+                .iter()
+                .rev()
+                .map(|update| PublicIncidentUpdate {
+                    status: update.status,
+                    message: update.message.clone(),
+                    posted_at: update.posted_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Request body for `POST /incidents`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenIncidentRequest {
+    pub title: String,
+    #[serde(default = "severity_default")]
+    pub severity: IncidentSeverity,
+    /// The first status update, shown publicly
+    pub message: String,
+    pub program: Option<String>,
+    pub proposal_id: Option<String>,
+    pub opened_by: Option<String>,
+}
+
+fn severity_default() -> IncidentSeverity {
+    IncidentSeverity::Major
+}
+
+impl OpenIncidentRequest {
+    pub fn into_incident(self, source: IncidentSource, now: i64) -> Result<Incident, UpgradeError> {
+        let title = self.title.trim().to_string();
+        if title.is_empty() || title.chars().count() > MAX_TITLE_LEN {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "title must be 1 to {} characters",
+                MAX_TITLE_LEN
+            )));
+        }
+
+        let mut incident = Incident {
+            id: uuid::Uuid::new_v4().to_string(),
+            title,
+            status: IncidentStatus::Investigating,
+            severity: self.severity,
+            source,
+            program: self.program,
+            opened_by: self.opened_by.clone(),
+            opened_at: now,
+            updated_at: now,
+            resolved_at: None,
+            links: Vec::new(),
+            updates: Vec::new(),
+        };
+        incident.post(
+            IncidentChange {
+                status: None,
+                severity: None,
+                message: self.message,
+                author: self.opened_by,
+            },
+            now,
+        )?;
+        if let Some(proposal_id) = &self.proposal_id {
+            incident.link(IncidentLink::new(IncidentLinkKind::Proposal, proposal_id, None, now));
+        }
+
+        Ok(incident)
+    }
+}
+
+/// Request body for `PATCH /incidents/:id`; omitted fields are left as they are
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncidentChange {
+    pub status: Option<IncidentStatus>,
+    pub severity: Option<IncidentSeverity>,
+    pub message: String,
+    pub author: Option<String>,
+}
+
+/// Request body for `POST /incidents/:id/close`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloseIncidentRequest {
+    pub message: Option<String>,
+    pub author: Option<String>,
+}
+
+impl CloseIncidentRequest {
+    pub fn into_change(self) -> IncidentChange {
+        IncidentChange {
+            status: Some(IncidentStatus::Resolved),
+            severity: None,
+            message: self.message.unwrap_or_else(|| "This incident has been resolved.".to_string()),
+            author: self.author,
+        }
+    }
+}
+
+/// A failure the service noticed itself. It opens an incident for the
+/// program, or is added to the one already open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub source: IncidentSource,
+    pub severity: IncidentSeverity,
+    pub program: String,
+    pub proposal_id: Option<String>,
+    pub title: String,
+    pub detail: String,
+    /// `(component, message)` of the alert raised for it
+    pub alert: Option<(String, String)>,
+}
+
+impl Detection {
+    fn links(&self, now: i64) -> Vec<IncidentLink> {
+        let mut links = Vec::new();
+        if let Some(proposal_id) = &self.proposal_id {
+            links.push(IncidentLink::new(IncidentLinkKind::Proposal, proposal_id, None, now));
+        }
+        if let Some((component, message)) = &self.alert {
+            links.push(IncidentLink::new(IncidentLinkKind::Alert, component, Some(message.clone()), now));
+        }
+        links
+    }
+
+    pub fn into_incident(self, now: i64) -> Result<Incident, UpgradeError> {
+        let links = self.links(now);
+        let mut incident = OpenIncidentRequest {
+            title: self.title,
+            severity: self.severity,
+            message: self.detail,
+            program: Some(self.program),
+            proposal_id: None,
+            opened_by: None,
+        }
+        .into_incident(self.source, now)?;
+        for link in links {
+            incident.link(link);
+        }
+        Ok(incident)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicIncidentUpdate {
+    pub status: IncidentStatus,
+    pub message: String,
+    pub posted_at: i64,
+}
+
+/// What users see of an incident: no links, sources or authors
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicIncident {
+    pub id: String,
+    pub title: String,
+    pub status: IncidentStatus,
+    pub severity: IncidentSeverity,
+    pub opened_at: i64,
+    pub updated_at: i64,
+    pub resolved_at: Option<i64>,
+    /// Newest first
+    pub updates: Vec<PublicIncidentUpdate>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceStatus {
+    Operational,
+    Degraded,
+    PartialOutage,
+    MajorOutage,
+}
+
+impl ServiceStatus {
+    /// Worst active severity: minor degrades, major is a partial outage,
+    /// critical a major outage
+    pub fn from_severity(severity: Option<IncidentSeverity>) -> Self {
+        match severity {
+            None => ServiceStatus::Operational,
+            Some(IncidentSeverity::Minor) => ServiceStatus::Degraded,
+            Some(IncidentSeverity::Major) => ServiceStatus::PartialOutage,
+            Some(IncidentSeverity::Critical) => ServiceStatus::MajorOutage,
+        }
+    }
+}
+
+/// Response of `GET /public/status`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusSummary {
+    pub status: ServiceStatus,
+    /// Newest first
+    pub active: Vec<PublicIncident>,
+    /// Resolved within `RECENTLY_RESOLVED_SECONDS`, newest first
+    pub recently_resolved: Vec<PublicIncident>,
+    pub generated_at: i64,
+}
+
+impl StatusSummary {
+    pub fn build(incidents: &[Incident], now: i64) -> Self {
+        let mut incidents: Vec<&Incident> = incidents.iter().collect();
+        incidents.sort_by_key(|incident| std::cmp::Reverse(incident.opened_at));
+
+        let (active, resolved): (Vec<&Incident>, Vec<&Incident>) =
+            incidents.into_iter().partition(|incident| incident.is_active());
+
+        Self {
+            status: ServiceStatus::from_severity(active.iter().map(|incident| incident.severity).max()),
+            active: active.iter().map(|incident| incident.public()).collect(),
+            recently_resolved: resolved
+                .iter()
+                .filter(|incident| incident.resolved_at.is_some_and(|at| now - at <= RECENTLY_RESOLVED_SECONDS))
+                .map(|incident| incident.public())
+                .collect(),
+            generated_at: now,
+        }
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Opens, updates and closes incidents, by hand or when rollbacks and
+/// failure detection fire (see `incidents` table)
+pub struct IncidentManager {
+    database: Arc<Database>,
+}
+
+impl IncidentManager {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn open(&self, request: OpenIncidentRequest) -> Result<Incident, UpgradeError> {
+        let incident = request.into_incident(IncidentSource::Manual, now())?;
+        self.database.insert_incident(&incident).await?;
+        tracing::warn!("Incident {} opened: {}", incident.id, incident.title);
+        Ok(incident)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Incident, UpgradeError> {
+        self.database
+            .get_incident(id)
+            .await?
+            .ok_or_else(|| UpgradeError::IncidentNotFound(id.to_string()))
+    }
+
+    pub async fn list(&self, active_only: bool, limit: i64) -> Result<Vec<Incident>, UpgradeError> {
+        self.database.list_incidents(active_only, limit).await
+    }
+
+    pub async fn update(&self, id: &str, change: IncidentChange) -> Result<Incident, UpgradeError> {
+        self.database
+            .modify_incident(id, |incident| incident.post(change, now()))
+            .await
+    }
+
+    pub async fn close(&self, id: &str, request: CloseIncidentRequest) -> Result<Incident, UpgradeError> {
+        let incident = self.update(id, request.into_change()).await?;
+        tracing::info!("Incident {} resolved", incident.id);
+        Ok(incident)
+    }
+
+    pub async fn link(&self, id: &str, link: IncidentLink) -> Result<Incident, UpgradeError> {
+        self.database
+            .modify_incident(id, |incident| {
+                incident.link(link);
+                Ok(())
+            })
+            .await
+    }
+
+    /// Record a detected failure on the program's open incident, opening one
+    /// if there is none. Returns the incident id.
+    pub async fn detect(&self, detection: Detection) -> Result<String, UpgradeError> {
+        if let Some(open) = self.database.find_active_incident(&detection.program).await? {
+            let incident = self.database
+                .modify_incident(&open.id, |incident| incident.absorb(&detection, now()))
+                .await?;
+            return Ok(incident.id);
+        }
+
+        let incident = detection.into_incident(now())?;
+        self.database.insert_incident(&incident).await?;
+        tracing::warn!("Incident {} opened automatically: {}", incident.id, incident.title);
+        Ok(incident.id)
+    }
+
+    pub async fn status_summary(&self) -> Result<StatusSummary, UpgradeError> {
+        let incidents = self.database.list_incidents_since(now() - RECENTLY_RESOLVED_SECONDS).await?;
+        Ok(StatusSummary::build(&incidents, now()))
+    }
+}
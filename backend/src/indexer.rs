@@ -0,0 +1,436 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::feature_flags::{decode_flag_name, MAX_FLAG_NAME_LEN};
+use crate::multisig::AccountReader;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Page size of `getSignaturesForAddress`
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// Events the upgrade-manager program emits, in declaration order
+pub const UPGRADE_MANAGER_EVENTS: &[&str] = &[
+    "InitializedEvent",
+    "ProposalCreatedEvent",
+    "ProposalApprovedEvent",
+    "ApprovalRevokedEvent",
+    "UpgradeExecutedEvent",
+    "ProposalCancelledEvent",
+    "CancelVoteCastEvent",
+    "ExecutionBotUpdatedEvent",
+    "OrganizationsUpdatedEvent",
+    "MigrationProgressEvent",
+    "AccountMigratedEvent",
+    "FlagChangeProposedEvent",
+    "FeatureFlagSetEvent",
+    "PausableProgramRegisteredEvent",
+    "EmergencyPauseVoteEvent",
+    "EmergencyPauseExecutedEvent",
+];
+
+/// On-chain `CancellationReason` variants, by borsh index
+const CANCELLATION_REASONS: &[&str] = &[
+    "security_issue",
+    "build_mismatch",
+    "superseded",
+    "proposer_withdrawn",
+    "other",
+];
+
+/// Anchor event discriminator: the first 8 bytes of `sha256("event:<Name>")`
+pub fn event_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("event:{}", name).as_bytes());
+    hash[..8].try_into().unwrap()
+}
+
+/// An upgrade-manager event decoded from a `Program data: <base64>` log line
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DecodedEvent {
+    pub name: String,
+    /// On-chain proposal account the event is about, if any
+    pub proposal: Option<String>,
+    pub data: Value,
+}
+
+/// Decode an upgrade-manager event. Other log lines, other programs' events
+/// and events that fail to decode return `None`.
+pub fn decode_event(log: &str) -> Option<DecodedEvent> {
+    let encoded = log.strip_prefix("Program data: ")?;
+    let data = base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    let name = UPGRADE_MANAGER_EVENTS
+        .iter()
+        .find(|name| data.get(..8) == Some(&event_discriminator(name)[..]))?;
+
+    decode_fields(name, &mut AccountReader::anchor(&data))
+        .ok()
+        .map(|(proposal, data)| DecodedEvent {
+            name: name.to_string(),
+            proposal: proposal.map(|proposal| proposal.to_string()),
+            data,
+        })
+}
+
+fn decode_fields(name: &str, r: &mut AccountReader) -> Result<(Option<Pubkey>, Value), UpgradeError> {
+    let reason = |index: u8| CANCELLATION_REASONS.get(index as usize).copied().unwrap_or("unknown");
+
+    Ok(match name {
+        "InitializedEvent" => (None, json!({
+            "authority": r.pubkey()?.to_string(),
+            "members": r.pubkeys()?.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+            "threshold": r.u8()?,
+            "timelock_duration": r.u64()? as i64,
+        })),
+        "ProposalCreatedEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "proposer": r.pubkey()?.to_string(),
+                "new_buffer": r.pubkey()?.to_string(),
+                "timelock_until": r.u64()? as i64,
+                "target_version": r.u32()?,
+                "approval_digest": hex::encode(r.take(32)?),
+            }))
+        }
+        "ProposalApprovedEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "approver": r.pubkey()?.to_string(),
+                "approvals": r.u64()?,
+                "threshold": r.u8()?,
+                "organizations": r.u8()?,
+                "organization_quorum": r.u8()?,
+            }))
+        }
+        "ApprovalRevokedEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "approver": r.pubkey()?.to_string(),
+                "approvals": r.u64()?,
+                "threshold": r.u8()?,
+            }))
+        }
+        "UpgradeExecutedEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "program": r.pubkey()?.to_string(),
+                "executor": r.pubkey()?.to_string(),
+                "executed_at": r.u64()? as i64,
+                "version": r.u32()?,
+                "code_hash": hex::encode(r.take(32)?),
+            }))
+        }
+        "ProposalCancelledEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "canceller": r.pubkey()?.to_string(),
+                "reason": reason(r.u8()?),
+                "details": String::from_utf8_lossy(r.bytes()?),
+                "votes": r.u8()?,
+            }))
+        }
+        "CancelVoteCastEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "voter": r.pubkey()?.to_string(),
+                "votes": r.u8()?,
+                "required": r.u8()?,
+                "reason": reason(r.u8()?),
+            }))
+        }
+        "ExecutionBotUpdatedEvent" => {
+            let authority = r.pubkey()?;
+            let execution_bot = match r.u8()? {
+                0 => None,
+                _ => Some(r.pubkey()?.to_string()),
+            };
+            (None, json!({
+                "authority": authority.to_string(),
+                "execution_bot": execution_bot,
+            }))
+        }
+        "OrganizationsUpdatedEvent" => (None, json!({
+            "authority": r.pubkey()?.to_string(),
+            "member_organizations": r.bytes()?,
+            "organization_quorum": r.u8()?,
+        })),
+        "MigrationProgressEvent" => {
+            let migration_id = uuid::Uuid::from_slice(r.take(16)?)
+                .map_err(|e| UpgradeError::InternalError(e.to_string()))?;
+            (None, json!({
+                "migration_id": migration_id.to_string(),
+                "total": r.u64()?,
+                "migrated": r.u64()?,
+                "failed": r.u64()?,
+            }))
+        }
+        "AccountMigratedEvent" => (None, json!({
+            "account": r.pubkey()?.to_string(),
+            "new_version": r.u32()?,
+            "migrated_at": r.u64()? as i64,
+        })),
+        "FlagChangeProposedEvent" => {
+            let proposal = r.pubkey()?;
+            (Some(proposal), json!({
+                "proposal_id": proposal.to_string(),
+                "proposer": r.pubkey()?.to_string(),
+                "program": r.pubkey()?.to_string(),
+                "name": decode_flag_name(r.take(MAX_FLAG_NAME_LEN)?),
+                "enabled": r.u8()? == 1,
+                "kill_switch": r.u8()? == 1,
+                "revision": r.u32()?,
+                "timelock_until": r.u64()? as i64,
+                "approval_digest": hex::encode(r.take(32)?),
+            }))
+        }
+        "FeatureFlagSetEvent" => {
+            let program = r.pubkey()?;
+            let name = decode_flag_name(r.take(MAX_FLAG_NAME_LEN)?);
+            let enabled = r.u8()? == 1;
+            let kill_switch = r.u8()? == 1;
+            let revision = r.u32()?;
+            // No proposal when a kill switch was tripped
+            let proposal = match r.u8()? {
+                0 => None,
+                _ => Some(r.pubkey()?),
+            };
+            (proposal, json!({
+                "program": program.to_string(),
+                "name": name,
+                "enabled": enabled,
+                "kill_switch": kill_switch,
+                "revision": revision,
+                "proposal_id": proposal.map(|proposal| proposal.to_string()),
+                "updated_by": r.pubkey()?.to_string(),
+                "updated_at": r.u64()? as i64,
+            }))
+        }
+        "PausableProgramRegisteredEvent" => (None, json!({
+            "authority": r.pubkey()?.to_string(),
+            "program": r.pubkey()?.to_string(),
+            "registered": r.u8()? == 1,
+        })),
+        "EmergencyPauseVoteEvent" => (None, json!({
+            "program": r.pubkey()?.to_string(),
+            "voter": r.pubkey()?.to_string(),
+            "votes": r.u8()?,
+            "required": r.u8()?,
+            "reason": String::from_utf8_lossy(r.bytes()?),
+        })),
+        "EmergencyPauseExecutedEvent" => (None, json!({
+            "program": r.pubkey()?.to_string(),
+            "voters": r.pubkeys()?.iter().map(|voter| voter.to_string()).collect::<Vec<_>>(),
+            "reason": String::from_utf8_lossy(r.bytes()?),
+            "paused_at": r.u64()? as i64,
+        })),
+        other => {
+            return Err(UpgradeError::InternalError(format!("Unknown event {}", other)));
+        }
+    })
+}
+
+/// A decoded event as stored in `onchain_events`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnchainEvent {
+    /// Assigned when stored; the `after` cursor of `GET /onchain/events`
+    pub id: Option<i64>,
+    pub signature: String,
+    pub slot: u64,
+    /// Position among the transaction's events
+    pub event_index: i32,
+    pub name: String,
+    pub proposal: Option<String>,
+    pub data: Value,
+    pub block_time: Option<i64>,
+}
+
+/// Query string of `GET /onchain/events`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OnchainEventQuery {
+    /// On-chain proposal account, or a backend proposal id
+    pub proposal: Option<String>,
+    /// Event name, e.g. `ProposalApprovedEvent`
+    pub event: Option<String>,
+    /// Only events with a larger `id`
+    pub after: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// How far the projection has read the program's history
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EventCursor {
+    pub program: String,
+    pub last_signature: String,
+    pub last_slot: u64,
+    pub updated_at: i64,
+}
+
+/// Polls upgrade-manager transactions, decodes their Anchor events and, with
+/// a database, projects them into `onchain_events`. The newest processed
+/// signature is kept as a cursor in `onchain_event_cursors`, so a restart
+/// resumes where the last poll stopped; events are stored once per
+/// signature and index, so re-reading a transaction is harmless.
+pub struct ProgramIndexer {
+    rpc_client: RpcClient,
+    program_id: Pubkey,
+    last_signature: Mutex<Option<Signature>>,
+    database: Option<Arc<Database>>,
+}
+
+impl ProgramIndexer {
+    pub fn new(rpc_url: String, program_id: Pubkey) -> Self {
+        Self {
+            rpc_client: RpcClient::new(rpc_url),
+            program_id,
+            last_signature: Mutex::new(None),
+            database: None,
+        }
+    }
+
+    /// Store decoded events and the replay cursor
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Projected events, for `GET /onchain/events`
+    pub async fn stored_events(
+        &self,
+        proposal: Option<&str>,
+        event_name: Option<&str>,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<OnchainEvent>, UpgradeError> {
+        self.database()?
+            .list_onchain_events(proposal, event_name, after, limit)
+            .await
+    }
+
+    /// Where the projection has read up to; `None` before the first poll
+    pub async fn cursor(&self) -> Result<Option<EventCursor>, UpgradeError> {
+        self.database()?
+            .get_event_cursor(&self.program_id.to_string())
+            .await
+    }
+
+    fn database(&self) -> Result<&Arc<Database>, UpgradeError> {
+        self.database.as_ref().ok_or_else(|| {
+            UpgradeError::InternalError("On-chain event projection requires a database".to_string())
+        })
+    }
+
+    /// Accounts reported by `AccountMigratedEvent` since the previous poll
+    pub async fn poll_migrated_accounts(&self) -> Result<Vec<Pubkey>, UpgradeError> {
+        Ok(self
+            .poll()
+            .await?
+            .iter()
+            .filter(|event| event.name == "AccountMigratedEvent")
+            .filter_map(|event| event.data["account"].as_str())
+            .filter_map(|account| Pubkey::from_str(account).ok())
+            .collect())
+    }
+
+    /// Events of the program's transactions since the cursor, oldest first
+    pub async fn poll(&self) -> Result<Vec<OnchainEvent>, UpgradeError> {
+        let mut last_signature = self.last_signature.lock().await;
+        if last_signature.is_none() {
+            if let Some(database) = &self.database {
+                *last_signature = database
+                    .get_event_cursor(&self.program_id.to_string())
+                    .await?
+                    .and_then(|cursor| Signature::from_str(&cursor.last_signature).ok());
+            }
+        }
+
+        // Newest first; page back until the cursor
+        let mut signatures = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.rpc_client
+                .get_signatures_for_address_with_config(
+                    &self.program_id,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: *last_signature,
+                        limit: Some(SIGNATURE_PAGE),
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch signatures: {}", e)))?;
+            let full = page.len() == SIGNATURE_PAGE;
+            before = match page.last() {
+                Some(status) => Some(
+                    Signature::from_str(&status.signature)
+                        .map_err(|e| UpgradeError::SolanaError(format!("Invalid signature: {}", e)))?,
+                ),
+                None => None,
+            };
+            signatures.extend(page);
+            // The first poll without a cursor starts from the latest page
+            if !full || last_signature.is_none() {
+                break;
+            }
+        }
+
+        let mut events = Vec::new();
+
+        // Process oldest first
+        for status in signatures.iter().rev() {
+            if status.err.is_some() {
+                continue;
+            }
+            let signature = Signature::from_str(&status.signature)
+                .map_err(|e| UpgradeError::SolanaError(format!("Invalid signature: {}", e)))?;
+
+            let tx = self.rpc_client
+                .get_transaction(&signature, UiTransactionEncoding::Json)
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch transaction: {}", e)))?;
+
+            let logs: Option<Vec<String>> = tx.transaction.meta
+                .and_then(|meta| meta.log_messages.into());
+
+            let decoded = logs.unwrap_or_default().iter().filter_map(|log| decode_event(log)).collect::<Vec<_>>();
+            for (index, event) in decoded.into_iter().enumerate() {
+                events.push(OnchainEvent {
+                    id: None,
+                    signature: status.signature.clone(),
+                    slot: tx.slot,
+                    event_index: index as i32,
+                    name: event.name,
+                    proposal: event.proposal,
+                    data: event.data,
+                    block_time: tx.block_time,
+                });
+            }
+        }
+
+        if let Some(newest) = signatures.first() {
+            if let Some(database) = &self.database {
+                let stored = database.insert_onchain_events(&events).await?;
+                database
+                    .save_event_cursor(&self.program_id.to_string(), &newest.signature, newest.slot)
+                    .await?;
+                if stored > 0 {
+                    tracing::info!("Projected {} on-chain events up to slot {}", stored, newest.slot);
+                }
+            }
+            *last_signature = Signature::from_str(&newest.signature).ok();
+        }
+
+        Ok(events)
+    }
+}
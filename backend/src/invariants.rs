@@ -0,0 +1,416 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::incidents::{Detection, IncidentManager, IncidentSeverity, IncidentSource};
+use crate::rollback::RollbackHandler;
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// When a check runs relative to the upgrade
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvariantPhase {
+    /// Before execution; a failure blocks the upgrade
+    PreExecution,
+    /// After the upgrade lands; a failure triggers rollback
+    PostUpgrade,
+    /// After a batch migration completes; a failure triggers rollback
+    PostMigration,
+}
+
+impl InvariantPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvariantPhase::PreExecution => "pre_execution",
+            InvariantPhase::PostUpgrade => "post_upgrade",
+            InvariantPhase::PostMigration => "post_migration",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pre_execution" => Some(InvariantPhase::PreExecution),
+            "post_upgrade" => Some(InvariantPhase::PostUpgrade),
+            "post_migration" => Some(InvariantPhase::PostMigration),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvariantOutcome {
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Result of one check, stored per proposal (or migration)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvariantResult {
+    pub subject_id: String,
+    pub name: String,
+    pub phase: InvariantPhase,
+    pub passed: bool,
+    pub detail: String,
+    pub checked_at: i64,
+}
+
+#[async_trait]
+pub trait InvariantCheck: Send + Sync {
+    fn name(&self) -> &str;
+    async fn check(&self) -> Result<InvariantOutcome, UpgradeError>;
+}
+
+/// Check backed by an async Rust closure
+pub struct FnInvariant<F>
+where
+    F: Fn() -> BoxFuture<'static, Result<InvariantOutcome, UpgradeError>> + Send + Sync,
+{
+    name: String,
+    check: F,
+}
+
+impl<F> FnInvariant<F>
+where
+    F: Fn() -> BoxFuture<'static, Result<InvariantOutcome, UpgradeError>> + Send + Sync,
+{
+    pub fn new(name: impl Into<String>, check: F) -> Self {
+        Self { name: name.into(), check }
+    }
+}
+
+#[async_trait]
+impl<F> InvariantCheck for FnInvariant<F>
+where
+    F: Fn() -> BoxFuture<'static, Result<InvariantOutcome, UpgradeError>> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> Result<InvariantOutcome, UpgradeError> {
+        (self.check)().await
+    }
+}
+
+/// A value read from chain for a declarative check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcQuery {
+    Constant(u64),
+    Lamports { account: String },
+    TokenBalance { account: String },
+    /// Sum of a little-endian u64 at `offset` across all accounts owned by `program`
+    ProgramAccountFieldSum {
+        program: String,
+        offset: usize,
+        data_size: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Comparison {
+    fn holds(&self, lhs: u64, rhs: u64) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Ne => lhs != rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Lte => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Gte => lhs >= rhs,
+        }
+    }
+}
+
+/// Declarative check loaded from `INVARIANTS_CONFIG`, e.g.
+/// "vault token balance == sum of user deposits"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclarativeInvariant {
+    pub name: String,
+    pub phases: Vec<InvariantPhase>,
+    pub lhs: RpcQuery,
+    pub op: Comparison,
+    pub rhs: RpcQuery,
+}
+
+struct DeclarativeCheck {
+    spec: DeclarativeInvariant,
+    rpc_client: Arc<RpcClient>,
+}
+
+impl DeclarativeCheck {
+    fn evaluate(&self, query: &RpcQuery) -> Result<u64, UpgradeError> {
+        let parse = |key: &str| Pubkey::from_str(key).map_err(|_| UpgradeError::InvalidPubkey);
+        let rpc_error = |e: solana_client::client_error::ClientError| {
+            UpgradeError::SolanaError(format!("Invariant query failed: {}", e))
+        };
+
+        match query {
+            RpcQuery::Constant(value) => Ok(*value),
+            RpcQuery::Lamports { account } => {
+                self.rpc_client.get_balance(&parse(account)?).map_err(rpc_error)
+            }
+            RpcQuery::TokenBalance { account } => {
+                let balance = self.rpc_client
+                    .get_token_account_balance(&parse(account)?)
+                    .map_err(rpc_error)?;
+                balance.amount.parse().map_err(|_| {
+                    UpgradeError::SolanaError(format!("Invalid token amount: {}", balance.amount))
+                })
+            }
+            RpcQuery::ProgramAccountFieldSum { program, offset, data_size } => {
+                let config = RpcProgramAccountsConfig {
+                    filters: data_size.map(|size| vec![RpcFilterType::DataSize(size)]),
+                    ..Default::default()
+                };
+                let accounts = self.rpc_client
+                    .get_program_accounts_with_config(&parse(program)?, config)
+                    .map_err(rpc_error)?;
+
+                let mut sum: u64 = 0;
+                for (_, account) in accounts {
+                    let bytes = account.data.get(*offset..*offset + 8).ok_or_else(|| {
+                        UpgradeError::SolanaError(format!("Account data shorter than offset {}", offset))
+                    })?;
+                    sum = sum.saturating_add(u64::from_le_bytes(bytes.try_into().unwrap()));
+                }
+                Ok(sum)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl InvariantCheck for DeclarativeCheck {
+    fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    async fn check(&self) -> Result<InvariantOutcome, UpgradeError> {
+        let lhs = self.evaluate(&self.spec.lhs)?;
+        let rhs = self.evaluate(&self.spec.rhs)?;
+
+        Ok(InvariantOutcome {
+            passed: self.spec.op.holds(lhs, rhs),
+            detail: format!("lhs={} {:?} rhs={}", lhs, self.spec.op, rhs),
+        })
+    }
+}
+
+struct RegisteredCheck {
+    check: Arc<dyn InvariantCheck>,
+    phases: Vec<InvariantPhase>,
+}
+
+/// Operator-registered checks run around execution and migration
+pub struct InvariantRegistry {
+    checks: RwLock<Vec<RegisteredCheck>>,
+    rpc_client: Arc<RpcClient>,
+    database: Option<Arc<Database>>,
+    rollback_handler: Option<Arc<RollbackHandler>>,
+    incidents: Option<Arc<IncidentManager>>,
+    /// Program rolled back when a post-upgrade or post-migration check fails
+    managed_program: String,
+}
+
+impl InvariantRegistry {
+    pub fn new() -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+
+        Self {
+            checks: RwLock::new(Vec::new()),
+            rpc_client: Arc::new(RpcClient::new(rpc_url)),
+            database: None,
+            rollback_handler: None,
+            incidents: None,
+            managed_program: std::env::var("MANAGED_PROGRAM_ID")
+                .unwrap_or_else(|_| "program_id".to_string()),
+        }
+    }
+
+    /// Store every result so it can be reviewed per proposal
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Roll back when a post-upgrade or post-migration check fails
+    pub fn with_rollback(mut self, rollback_handler: Arc<RollbackHandler>) -> Self {
+        self.rollback_handler = Some(rollback_handler);
+        self
+    }
+
+    /// Open an incident when a post-upgrade or post-migration check fails
+    pub fn with_incidents(mut self, incidents: Arc<IncidentManager>) -> Self {
+        self.incidents = Some(incidents);
+        self
+    }
+
+    pub async fn register(&self, check: Arc<dyn InvariantCheck>, phases: Vec<InvariantPhase>) {
+        tracing::info!("Registered invariant '{}' for {:?}", check.name(), phases);
+        self.checks.write().await.push(RegisteredCheck { check, phases });
+    }
+
+    /// Register the declarative checks listed in the JSON file at `INVARIANTS_CONFIG`
+    pub async fn load_from_env(&self) -> Result<usize, UpgradeError> {
+        let path = match std::env::var("INVARIANTS_CONFIG") {
+            Ok(path) => path,
+            Err(_) => return Ok(0),
+        };
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to read {}: {}", path, e)))?;
+        let specs: Vec<DeclarativeInvariant> = serde_json::from_str(&contents)
+            .map_err(|e| UpgradeError::InternalError(format!("Invalid invariants config: {}", e)))?;
+
+        let count = specs.len();
+        for spec in specs {
+            let phases = spec.phases.clone();
+            let check = DeclarativeCheck {
+                spec,
+                rpc_client: self.rpc_client.clone(),
+            };
+            self.register(Arc::new(check), phases).await;
+        }
+
+        Ok(count)
+    }
+
+    /// Run every check registered for `phase`. A check that errors counts as failed.
+    pub async fn run(&self, subject_id: &str, phase: InvariantPhase) -> Vec<InvariantResult> {
+        let checks: Vec<Arc<dyn InvariantCheck>> = self.checks
+            .read()
+            .await
+            .iter()
+            .filter(|c| c.phases.contains(&phase))
+            .map(|c| c.check.clone())
+            .collect();
+
+        let mut results = Vec::with_capacity(checks.len());
+        for check in checks {
+            let outcome = check.check().await.unwrap_or_else(|e| InvariantOutcome {
+                passed: false,
+                detail: format!("check errored: {}", e),
+            });
+
+            let result = InvariantResult {
+                subject_id: subject_id.to_string(),
+                name: check.name().to_string(),
+                phase,
+                passed: outcome.passed,
+                detail: outcome.detail,
+                checked_at: chrono::Utc::now().timestamp(),
+            };
+
+            if !result.passed {
+                tracing::error!("Invariant '{}' failed for {}: {}", result.name, subject_id, result.detail);
+            }
+
+            if let Some(database) = &self.database {
+                if let Err(e) = database.record_invariant_result(&result).await {
+                    tracing::warn!("Failed to record invariant result: {}", e);
+                }
+            }
+
+            results.push(result);
+        }
+
+        results
+    }
+
+    /// Pre-execution gate: fails with `InvariantViolation` if any check fails
+    pub async fn ensure_pre_execution(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+        let failed = Self::failed_names(&self.run(proposal_id, InvariantPhase::PreExecution).await);
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(UpgradeError::InvariantViolation(failed.join(", ")))
+        }
+    }
+
+    /// Post-upgrade/post-migration verification. Triggers a rollback of the
+    /// managed program when a check fails; returns whether all checks passed.
+    pub async fn verify_or_rollback(&self, subject_id: &str, phase: InvariantPhase) -> bool {
+        let failed = Self::failed_names(&self.run(subject_id, phase).await);
+        if failed.is_empty() {
+            return true;
+        }
+
+        tracing::error!(
+            "{} invariants failed for {} ({}), rolling back {}",
+            phase.as_str(),
+            subject_id,
+            failed.join(", "),
+            self.managed_program
+        );
+
+        if let Some(incidents) = &self.incidents {
+            let detection = Detection {
+                source: IncidentSource::Invariants,
+                severity: IncidentSeverity::Critical,
+                program: self.managed_program.clone(),
+                proposal_id: (phase == InvariantPhase::PostUpgrade).then(|| subject_id.to_string()),
+                title: match phase {
+                    InvariantPhase::PostMigration => "Checks failed after an account migration".to_string(),
+                    _ => "Checks failed after a program upgrade".to_string(),
+                },
+                detail: format!("{} invariants failed for {}: {}", phase.as_str(), subject_id, failed.join(", ")),
+                alert: None,
+            };
+            if let Err(e) = incidents.detect(detection).await {
+                tracing::error!("Failed to open an incident for failed invariants: {}", e);
+            }
+        }
+
+        match &self.rollback_handler {
+            Some(rollback) => {
+                // Post-upgrade checks run against the proposal that made the upgrade
+                let result = match phase {
+                    InvariantPhase::PostUpgrade => {
+                        let reason = format!("Invariants failed: {}", failed.join(", "));
+                        rollback.rollback_upgrade(subject_id, &self.managed_program, &reason).await
+                    }
+                    _ => rollback.rollback_program(&self.managed_program).await,
+                };
+                if let Err(e) = result {
+                    tracing::error!("Rollback after invariant failure failed: {}", e);
+                }
+            }
+            None => tracing::error!("No rollback handler configured; manual rollback required"),
+        }
+
+        false
+    }
+
+    pub async fn results_for(&self, subject_id: &str) -> Result<Vec<InvariantResult>, UpgradeError> {
+        match &self.database {
+            Some(database) => database.list_invariant_results(subject_id).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn failed_names(results: &[InvariantResult]) -> Vec<String> {
+        results
+            .iter()
+            .filter(|r| !r.passed)
+            .map(|r| r.name.clone())
+            .collect()
+    }
+}
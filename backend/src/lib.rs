@@ -1,15 +1,74 @@
+pub mod accountability;
+pub mod activity;
+pub mod announcements;
+pub mod api_keys;
+pub mod approval_links;
+pub mod artifacts;
+pub mod backfill;
+pub mod batch_approval;
+pub mod binary_analysis;
+pub mod buffer_watcher;
+pub mod chain_clock;
+pub mod checklist;
+pub mod cluster;
+pub mod confirmation;
+pub mod countdown;
 pub mod database;
+pub mod denylist;
+pub mod drafts;
+pub mod emergency;
 pub mod error;
+pub mod execute_tx;
+pub mod execution_queue;
+pub mod faucet;
+pub mod feature_flags;
+pub mod fork_replay;
+pub mod github;
+pub mod http_cache;
+pub mod idl;
+pub mod incidents;
+pub mod indexer;
+pub mod invariants;
+pub mod members;
+pub mod metrics_history;
 pub mod migration;
 pub mod multisig;
+pub mod multisig_backend;
+pub mod policy;
+pub mod preflight;
 pub mod proposal;
 pub mod program_builder;
+pub mod program_errors;
+pub mod realms;
+pub mod receipts;
+pub mod reminders;
+pub mod request_limits;
+pub mod request_metrics;
 pub mod rollback;
+pub mod rpc_quorum;
+pub mod sampling;
+pub mod sandbox;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod secrets;
 pub mod squads;
+pub mod sse;
+pub mod templates;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod throughput;
 pub mod timelock;
+pub mod tss;
+pub mod two_person;
+pub mod versioning;
+pub mod watch;
 pub mod websocket;
 pub mod monitoring;
 pub mod security;
+pub mod server;
+pub mod signer;
+pub mod slo;
+pub mod snapshots;
 
 pub use error::UpgradeError;
 
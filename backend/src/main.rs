@@ -1,49 +1,176 @@
 use axum::{
-    extract::{Path, State, WebSocketUpgrade},
-    http::StatusCode,
-    response::{Json, Response},
-    routing::{get, post},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State, WebSocketUpgrade},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{sse::{KeepAlive, Sse}, Json, Response},
+    routing::{delete, get, post},
     Router,
 };
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use tower_http::compression::CompressionLayer;
 use tracing::{info, Level};
 use tracing_subscriber;
 
+mod accountability;
+mod activity;
+mod announcements;
+mod api_keys;
+mod approval_links;
+mod artifacts;
+mod backfill;
+mod batch_approval;
+mod binary_analysis;
+mod buffer_watcher;
+mod chain_clock;
+mod checklist;
+mod cluster;
+mod confirmation;
+mod countdown;
 mod database;
+mod denylist;
+mod drafts;
+mod emergency;
 mod error;
+mod execute_tx;
+mod execution_queue;
+mod faucet;
+mod feature_flags;
+mod fork_replay;
+mod github;
+mod http_cache;
+mod idl;
+mod incidents;
+mod indexer;
+mod invariants;
+mod members;
+mod metrics_history;
 mod migration;
 mod monitoring;
 mod multisig;
+mod multisig_backend;
+mod policy;
+mod preflight;
 mod proposal;
 mod program_builder;
+mod program_errors;
+mod realms;
+mod receipts;
+mod reminders;
+mod request_limits;
+mod request_metrics;
 mod rollback;
+mod rpc_quorum;
+mod sampling;
+mod sandbox;
+mod secrets;
 mod security;
+mod server;
+mod signer;
+mod slo;
+mod snapshots;
 mod squads;
+mod sse;
+mod templates;
+mod throughput;
 mod timelock;
+mod tss;
+mod two_person;
+mod versioning;
+mod watch;
 mod websocket;
 
 use error::UpgradeError;
+use accountability::AccountabilityReporter;
+use activity::{ActivityFeed, ActivityKind};
+use announcements::AnnouncementService;
+use api_keys::{ApiKeyService, ApiPrincipal};
+use approval_links::{ApprovalLinkService, LinkQuery, TransactionRequest};
+use artifacts::ArtifactRegistry;
+use backfill::{BackfillRequest, HistoryBackfill};
+use batch_approval::{BatchApprovalRequest, BatchApprovalResult};
+use buffer_watcher::{BufferWatcher, DetectedBufferStatus};
+use chain_clock::ChainClock;
+use checklist::ChecklistService;
+use confirmation::ConfirmationTracker;
 use database::Database;
-use proposal::ProposalManager;
+use drafts::{DraftChanges, DraftService, DraftStatus, ReviewVerdict};
+use emergency::{EmergencyPauseRequest, EmergencyPauseService};
+use execute_tx::ExecuteTransactionService;
+use execution_queue::ExecutionWorker;
+use faucet::AirdropFunder;
+use fork_replay::ForkTester;
+use github::GitHubReleaseHandler;
+use incidents::{CloseIncidentRequest, IncidentChange, IncidentManager, OpenIncidentRequest};
+use indexer::ProgramIndexer;
+use invariants::InvariantRegistry;
+use metrics_history::{HistoryQuery, MetricsRecorder};
+use proposal::{AuthorityChange, CancellationReason, FreezeSource, ProgramDeployment, ProposalManager, ProposalOptions};
 use multisig::MultisigCoordinator;
+use policy::PolicyEngine;
 use timelock::TimelockManager;
-use program_builder::ProgramBuilder;
-use migration::MigrationManager;
+use two_person::TwoPersonRule;
+use program_builder::{ProgramBuilder, UpgradeCost};
+use program_errors::ErrorDecoder;
+use migration::{Migration, MigrationManager, MigrationStartOptions, MigrationStrategy};
+use receipts::ReceiptService;
+use reminders::ApprovalReminderService;
+use request_limits::RequestLimits;
 use rollback::RollbackHandler;
+use sandbox::{Sandbox, SandboxRunRequest};
+use secrets::SecretStore;
 use monitoring::MonitoringService;
 use security::SecurityAuditor;
+use server::ServerConfig;
+use snapshots::{SnapshotLabel, SnapshotService};
+use templates::{Channel, NotificationTemplates};
+use members::{MemberDirectory, MemberProfile, MemberProfileUpdate};
+use versioning::LegacyRoutes;
+use watch::PostUpgradeWatch;
 
 #[derive(Clone)]
 pub struct AppState {
+    pub database: Arc<Database>,
     pub proposal_manager: Arc<ProposalManager>,
     pub multisig_coordinator: Arc<MultisigCoordinator>,
     pub timelock_manager: Arc<TimelockManager>,
+    pub chain_clock: Arc<ChainClock>,
     pub program_builder: Arc<ProgramBuilder>,
-    pub migration_manager: Arc<MigrationManager>,
+    pub migration_manager: Arc<dyn Migration>,
     pub rollback_handler: Arc<RollbackHandler>,
+    pub monitoring_service: Arc<MonitoringService>,
+    pub execution_worker: Arc<ExecutionWorker>,
+    pub github_release_handler: Arc<GitHubReleaseHandler>,
+    pub artifact_registry: Arc<ArtifactRegistry>,
+    pub notification_service: Arc<websocket::NotificationService>,
+    pub invariant_registry: Arc<InvariantRegistry>,
+    pub receipt_service: Arc<ReceiptService>,
+    pub snapshot_service: Arc<SnapshotService>,
+    pub secrets: Arc<SecretStore>,
+    pub checklist_service: Arc<ChecklistService>,
+    pub buffer_watcher: Arc<BufferWatcher>,
+    pub draft_service: Arc<DraftService>,
+    pub confirmation_tracker: Arc<ConfirmationTracker>,
+    pub execute_tx_service: Arc<ExecuteTransactionService>,
+    pub approval_link_service: Arc<ApprovalLinkService>,
+    pub security_auditor: Arc<SecurityAuditor>,
+    pub two_person_rule: Arc<TwoPersonRule>,
+    pub policy_engine: Arc<PolicyEngine>,
+    pub metrics_recorder: Arc<MetricsRecorder>,
+    pub announcement_service: Arc<AnnouncementService>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub sandbox: Arc<Sandbox>,
+    pub fork_tester: Arc<ForkTester>,
+    pub history_backfill: Arc<HistoryBackfill>,
+    pub program_indexer: Arc<ProgramIndexer>,
+    pub approval_reminders: Arc<ApprovalReminderService>,
+    pub post_upgrade_watch: Arc<PostUpgradeWatch>,
+    pub activity_feed: Arc<ActivityFeed>,
+    pub accountability_reporter: Arc<AccountabilityReporter>,
+    pub incident_manager: Arc<IncidentManager>,
+    pub emergency_pause: Arc<EmergencyPauseService>,
 }
 
 #[tokio::main]
@@ -55,17 +182,152 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting GoQuant Upgrade Service...");
 
+    let server_config = ServerConfig::from_env()?;
+
+    // Secrets are validated before anything uses them, then refreshed in the background
+    let secrets = Arc::new(SecretStore::from_env()?);
+
+    // `--preflight` validates the deployment's dependencies and exits, for deploy pipelines
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--preflight") {
+        let report = preflight::Preflight::from_env().run(&secrets).await;
+        if args.iter().any(|arg| arg == "--json") {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print!("{}", report.render());
+        }
+        std::process::exit(if report.passed { 0 } else { 1 });
+    }
+
+    secrets.load().await?;
+    secrets.clone().spawn_rotation();
+    // RPC clients are built from SOLANA_RPC_URL, which may embed a provider API key
+    if let Some(rpc_url) = secrets.get("SOLANA_RPC_URL") {
+        std::env::set_var("SOLANA_RPC_URL", rpc_url);
+    }
+    if let Some(rpc_urls) = secrets.get("RPC_QUORUM_URLS") {
+        std::env::set_var("RPC_QUORUM_URLS", rpc_urls);
+    }
+
     // Initialize database
-    let database_url = std::env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "postgresql://localhost/goquant_upgrades".to_string());
+    let database_url = secrets
+        .get("DATABASE_URL")
+        .unwrap_or_else(|| "postgresql://localhost/goquant_upgrades".to_string());
     let database = Arc::new(Database::new(&database_url).await?);
 
+    // Replicas behind a load balancer share events and timelocks through Postgres
+    let shared_state = cluster::SharedState::parse(&std::env::var("SHARED_STATE").unwrap_or_default())?;
+    let cluster_bus = match shared_state {
+        cluster::SharedState::Postgres => Some(Arc::new(cluster::ClusterBus::from_env(database.clone()))),
+        cluster::SharedState::Memory => None,
+    };
+
+    // Initialize notification service
+    let mut notification_service = websocket::NotificationService::new()
+        .with_database(database.clone())
+        .with_templates(NotificationTemplates::from_env()?);
+    if let Some(cluster_bus) = &cluster_bus {
+        notification_service = notification_service.with_cluster(cluster_bus.clone());
+    }
+    let notification_service = Arc::new(notification_service);
+    if let Some(cluster_bus) = cluster_bus {
+        tracing::info!("Shared state in Postgres as instance {}", cluster_bus.instance_id());
+        let notifications = notification_service.clone();
+        tokio::spawn(async move {
+            cluster_bus.run(notifications).await;
+        });
+    }
+
+    // Initialize monitoring service
+    let monitoring_service = Arc::new(MonitoringService::new());
+
+    // Critical reads are cross-checked across RPC_QUORUM_URLS when configured
+    let rpc_quorum = rpc_quorum::QuorumRpc::from_env()?
+        .map(|quorum| Arc::new(quorum.with_alerts(monitoring_service.clone())));
+    if let Some(quorum) = &rpc_quorum {
+        info!("RPC quorum: {} of {} providers", quorum.required(), quorum.provider_count());
+    }
+
     // Initialize services
-    let multisig_coordinator = Arc::new(MultisigCoordinator::new().await?);
-    let timelock_manager = Arc::new(TimelockManager::new().await?);
-    let program_builder = Arc::new(ProgramBuilder::new().await?);
-    let migration_manager = Arc::new(MigrationManager::new().await?);
-    let rollback_handler = Arc::new(RollbackHandler::new().await?);
+    // Devnet/testnet fee payers are topped up from the faucet before they spend
+    let fee_payer = signer::fee_payer(&secrets)?;
+    // The fee payer doubles as the member key that proposes and executes on chain
+    // Failed transactions report program errors by name from the programs' IDLs
+    let error_decoder = Arc::new(ErrorDecoder::from_env());
+    let mut multisig_coordinator = MultisigCoordinator::new().await?
+        .with_executor(fee_payer.clone())
+        .with_error_decoder(error_decoder.clone());
+    // Timelocks are counted down in chain time, not system time
+    let mut chain_clock = ChainClock::from_env();
+    if let Some(quorum) = &rpc_quorum {
+        multisig_coordinator = multisig_coordinator.with_rpc_quorum(quorum.clone());
+        chain_clock = chain_clock.with_quorum(quorum.clone());
+    }
+    let multisig_coordinator = Arc::new(multisig_coordinator);
+    let chain_clock = Arc::new(chain_clock);
+    let mut timelock_manager = TimelockManager::new().await?.with_clock(chain_clock.clone());
+    if shared_state == cluster::SharedState::Postgres {
+        timelock_manager = timelock_manager.with_database(database.clone());
+    }
+    let timelock_manager = Arc::new(timelock_manager);
+    let faucet = Arc::new(AirdropFunder::from_env());
+    let program_builder = Arc::new(
+        ProgramBuilder::new().await?
+            .with_fee_payer(fee_payer.clone())
+            .with_faucet(faucet.clone())
+            .with_notifications(notification_service.clone()),
+    );
+    // Rollbacks and failure detection open incidents, shown on /public/status
+    let incident_manager = Arc::new(IncidentManager::new(database.clone()));
+
+    // Live rollbacks and drills announce themselves; drills are kept for review
+    let rollback_handler = Arc::new(
+        RollbackHandler::new().await?
+            .with_database(database.clone())
+            .with_notifications(notification_service.clone())
+            .with_incidents(incident_manager.clone()),
+    );
+
+    // Operator invariants gate execution and verify upgrades/migrations
+    let invariant_registry = Arc::new(
+        InvariantRegistry::new()
+            .with_database(database.clone())
+            .with_rollback(rollback_handler.clone())
+            .with_incidents(incident_manager.clone()),
+    );
+    let loaded = invariant_registry.load_from_env().await?;
+    info!("Loaded {} declarative invariants", loaded);
+
+    let mut migration_manager = MigrationManager::new().await?
+        .with_database(database.clone())
+        .with_invariants(invariant_registry.clone())
+        .with_notifications(notification_service.clone())
+        .with_monitoring(monitoring_service.clone())
+        .with_rollback(rollback_handler.clone());
+    if let Some(payer) = &fee_payer {
+        migration_manager = migration_manager.with_faucet(faucet.clone(), payer.pubkey());
+    }
+    let migration_manager = Arc::new(migration_manager);
+
+    // Execution only counts once its transaction reaches EXECUTION_COMMITMENT
+    let confirmation_tracker = Arc::new(
+        ConfirmationTracker::from_env()
+            .with_database(database.clone())
+            .with_error_decoder(error_decoder.clone()),
+    );
+    info!("Execution commitment: {}", confirmation_tracker.target().as_str());
+
+    // Execution windows are announced publicly once a proposal enters its timelock
+    let announcement_service = Arc::new(AnnouncementService::from_env(
+        database.clone(),
+        notification_service.clone(),
+    ));
+
+    // Must match the program's timelock; refused outside the on-chain bounds
+    let timelock_duration = std::env::var("TIMELOCK_DURATION_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(proposal::DEFAULT_TIMELOCK_DURATION);
 
     let proposal_manager = Arc::new(
         ProposalManager::new(
@@ -73,63 +335,493 @@ async fn main() -> anyhow::Result<()> {
             timelock_manager.clone(),
             program_builder.clone(),
         )
-        .await?,
+        .await?
+        .with_timelock_duration(timelock_duration)?
+        .with_database(database.clone())
+        .with_confirmation(confirmation_tracker.clone())
+        .with_announcements(announcement_service.clone())
+        .with_commit_statuses(Arc::new(github::CommitStatusReporter::from_env(secrets.clone())))
+        .with_freeze_alerts(monitoring_service.clone(), notification_service.clone()),
+    );
+
+    // Timelock countdown ticks for WebSocket clients
+    {
+        let ticker = countdown::CountdownTicker::new(
+            proposal_manager.clone(),
+            timelock_manager.clone(),
+            notification_service.clone(),
+        );
+        tokio::spawn(async move {
+            ticker.run().await;
+        });
+    }
+
+    // Remind members who have not approved as the approval deadline nears
+    let approval_reminders = Arc::new(ApprovalReminderService::from_env(
+        database.clone(),
+        proposal_manager.clone(),
+        multisig_coordinator.clone(),
+        notification_service.clone(),
+    )?);
+    {
+        let reminders = approval_reminders.clone();
+        tokio::spawn(async move {
+            reminders.run().await;
+        });
+    }
+
+    // Periodically flag multisig members that stopped signing
+    {
+        let multisig = multisig_coordinator.clone();
+        let monitoring = monitoring_service.clone();
+        tokio::spawn(async move {
+            let mut flagged = std::collections::HashSet::new();
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+            loop {
+                interval.tick().await;
+                let activity = multisig.get_member_activity().await;
+                monitoring.check_member_inactivity(&activity, &mut flagged).await;
+            }
+        });
+    }
+
+    // Persist metric snapshots so trends can be graphed over months
+    let metrics_recorder = Arc::new(MetricsRecorder::from_env(database.clone(), monitoring_service.clone()));
+    {
+        let recorder = metrics_recorder.clone();
+        tokio::spawn(async move {
+            let mut since = chrono::Utc::now().timestamp();
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+                recorder.interval_seconds().max(1) as u64,
+            ));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let now = chrono::Utc::now().timestamp();
+                match recorder.snapshot(since, now).await {
+                    Ok(_) => since = now,
+                    Err(e) => tracing::warn!("Failed to record metrics snapshot: {}", e),
+                }
+            }
+        });
+    }
+
+    // Measure drift between system time and the Clock sysvar
+    {
+        let clock = chain_clock.clone();
+        let monitoring = monitoring_service.clone();
+        let interval_secs = std::env::var("CLOCK_DRIFT_CHECK_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        tokio::spawn(async move {
+            let mut alerted = false;
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                match clock.sample().await {
+                    Ok(sample) => {
+                        monitoring
+                            .check_clock_drift(&sample, clock.alert_threshold_seconds(), &mut alerted)
+                            .await;
+                    }
+                    Err(e) => tracing::warn!("Failed to sample chain clock: {}", e),
+                }
+            }
+        });
+    }
+
+    // Raise burn-rate alerts for SLOs spending their error budget too fast
+    {
+        let monitoring = monitoring_service.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                monitoring.check_slo_burn_rates().await;
+            }
+        });
+    }
+
+    // Keep execution dependency health current for the execute gate
+    {
+        let database = database.clone();
+        let multisig = multisig_coordinator.clone();
+        let monitoring = monitoring_service.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                monitoring.probe_dependencies(&database, &multisig).await;
+            }
+        });
+    }
+
+    // Account-set snapshots bracket each upgrade to catch mass closures or growth
+    let snapshot_service = Arc::new(SnapshotService::new(database.clone()));
+
+    // Security auditor, consulting the binary denylist at execution time and
+    // freezing proposals that match it
+    let mut security_auditor = SecurityAuditor::default()
+        .with_denylist(database.clone())
+        .with_alerts(monitoring_service.clone())
+        .with_freeze(proposal_manager.clone());
+    if let Some(quorum) = &rpc_quorum {
+        security_auditor = security_auditor.with_rpc_quorum(quorum.clone());
+    }
+    let security_auditor = Arc::new(security_auditor);
+
+    // Operator watch expressions over the managed program's logs after each upgrade
+    let post_upgrade_watch = Arc::new(
+        PostUpgradeWatch::new(database.clone())
+            .with_alerts(monitoring_service.clone())
+            .with_rollback(rollback_handler.clone())
+            .with_incidents(incident_manager.clone()),
+    );
+
+    // Execution runs in a background worker fed by the persisted job queue
+    let execution_worker = Arc::new(
+        ExecutionWorker::new(database.clone(), proposal_manager.clone())
+            .with_health_gate(monitoring_service.clone())
+            .with_denylist(security_auditor.clone())
+            .with_invariants(invariant_registry.clone())
+            .with_snapshots(snapshot_service.clone())
+            .with_notifications(notification_service.clone())
+            .with_watch(post_upgrade_watch.clone()),
+    );
+    {
+        let worker = execution_worker.clone();
+        tokio::spawn(async move {
+            worker.run().await;
+        });
+    }
+
+    // Buffers uploaded straight to the vault (outside the release pipeline) are offered as proposals
+    let buffer_watcher = Arc::new(
+        BufferWatcher::new(database.clone(), proposal_manager.clone())
+            .with_notifications(notification_service.clone()),
+    );
+    buffer_watcher.clone().spawn();
+
+    // Proposals are drafted and peer-reviewed before they go on chain
+    let draft_service = Arc::new(DraftService::new(database.clone(), proposal_manager.clone()));
+
+    // Upgrade-manager events are projected into `onchain_events`; lazy
+    // migrations are tracked from the AccountMigratedEvents among them
+    let program_indexer = {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let program_id = std::env::var("UPGRADE_MANAGER_PROGRAM_ID")
+            .unwrap_or_else(|_| "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string())
+            .parse()
+            .map_err(|_| UpgradeError::InvalidPubkey)?;
+        Arc::new(ProgramIndexer::new(rpc_url, program_id).with_database(database.clone()))
+    };
+    {
+        let indexer = program_indexer.clone();
+        let migrations = migration_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+            loop {
+                interval.tick().await;
+                match indexer.poll_migrated_accounts().await {
+                    Ok(accounts) => migrations.record_lazy_migrations(&accounts).await,
+                    Err(e) => tracing::warn!("Indexer poll failed: {}", e),
+                }
+            }
+        });
+    }
+
+    let artifact_registry = Arc::new(ArtifactRegistry::new(database.clone())?);
+
+    // Approvals get receipts signed by the service key
+    let receipt_service = Arc::new(ReceiptService::new(database.clone(), &secrets)?);
+    info!("Signing approval receipts as {}", receipt_service.signer_pubkey());
+
+    // Pre-flight checklist that must be signed off before execution
+    let checklist_service = Arc::new(ChecklistService::from_env(database.clone())?);
+
+    let github_release_handler = Arc::new(GitHubReleaseHandler::new(
+        program_builder.clone(),
+        proposal_manager.clone(),
+        artifact_registry.clone(),
+        secrets.clone(),
+    ));
+
+    // Execute transactions assembled from member signatures collected offline
+    let execute_tx_service = Arc::new(
+        ExecuteTransactionService::from_env(
+            multisig_coordinator.clone(),
+            proposal_manager.clone(),
+            confirmation_tracker.clone(),
+        )
+        .with_fee_payer(fee_payer.clone()),
+    );
+
+    // Approval transactions members sign from a mobile wallet
+    let approval_link_service = Arc::new(ApprovalLinkService::from_env(
+        multisig_coordinator.clone(),
+        proposal_manager.clone(),
+        secrets.clone(),
+    ));
+
+    // Second executor confirmation before queued executions
+    let two_person_rule = Arc::new(TwoPersonRule::from_env(database.clone()));
+    if two_person_rule.enabled() {
+        info!(
+            "Two-person rule enabled: executions need a second executor within {}s",
+            two_person_rule.window_seconds()
+        );
+    }
+
+    // Bot approval for proposals that match operator policy rules
+    let policy_engine = Arc::new(
+        PolicyEngine::new(
+            proposal_manager.clone(),
+            multisig_coordinator.clone(),
+            security_auditor.clone(),
+        )
+        .with_receipts(receipt_service.clone())
+        .load_from_env(&secrets)?,
     );
+    info!("Loaded {} auto-approval policy rules", policy_engine.rules().len());
+
+    // Every route outside /public needs an API key with the right scope
+    let api_key_service = Arc::new(ApiKeyService::from_env(database.clone(), secrets.clone()));
+
+    // Imports upgrades made before this service managed the program
+    let history_backfill = Arc::new(HistoryBackfill::new(database.clone()));
+
+    // Replays recent mainnet transactions against proposed binaries on a fork
+    let fork_tester = Arc::new(ForkTester::new().with_database(database.clone()));
+
+    // One timeline per proposal, merged from the tables the services above write
+    let activity_feed = Arc::new(ActivityFeed::new(database.clone(), multisig_coordinator.clone()));
+
+    // Signed accountability packages for upgrades that were rolled back
+    let accountability_reporter = Arc::new(AccountabilityReporter::new(
+        database.clone(),
+        proposal_manager.clone(),
+        activity_feed.clone(),
+        receipt_service.clone(),
+    ));
+
+    let emergency_pause_service = Arc::new(EmergencyPauseService::new(
+        multisig_coordinator.clone(),
+        monitoring_service.clone(),
+        incident_manager.clone(),
+    ));
 
     let app_state = AppState {
+        database,
         proposal_manager,
         multisig_coordinator,
         timelock_manager,
+        chain_clock,
         program_builder,
         migration_manager,
         rollback_handler,
+        monitoring_service,
+        execution_worker,
+        github_release_handler,
+        artifact_registry,
+        notification_service,
+        invariant_registry,
+        receipt_service,
+        snapshot_service,
+        secrets,
+        checklist_service,
+        buffer_watcher,
+        draft_service,
+        confirmation_tracker,
+        execute_tx_service,
+        approval_link_service,
+        security_auditor,
+        two_person_rule,
+        policy_engine,
+        metrics_recorder,
+        announcement_service,
+        api_key_service: api_key_service.clone(),
+        sandbox: Arc::new(Sandbox::new()),
+        fork_tester,
+        history_backfill,
+        program_indexer,
+        approval_reminders,
+        post_upgrade_watch,
+        activity_feed,
+        accountability_reporter,
+        incident_manager,
+        emergency_pause: emergency_pause_service,
     };
 
-    // Initialize notification service
-    let notification_service = websocket::NotificationService::new();
-    let notification_sender = notification_service.get_sender();
-    
-    // Initialize monitoring service
-    let monitoring_service = Arc::new(MonitoringService::new());
-    
-    // Initialize security auditor
-    let security_auditor = Arc::new(SecurityAuditor);
+    // Read-only explorer routes, safe to expose without credentials
+    let public_routes = Router::new()
+        .route("/proposals", get(public_list_proposals))
+        .route("/proposals/:id", get(public_get_proposal))
+        .route("/history", get(public_upgrade_history))
+        .route("/announcements", get(public_announcements))
+        .route("/status", get(public_status))
+        .route("/approve-link/:id", get(approval_link_label).post(approval_link_transaction));
 
     // Build router
-    let app = Router::new()
+    let api = Router::new()
         .route("/upgrade/propose", post(propose_upgrade))
+        .route("/upgrade/propose-binary", post(propose_binary))
+        .route("/upgrade/deploy", post(propose_deploy))
+        .route("/upgrade/authority", post(propose_authority_change))
+        .route("/upgrade/draft", post(create_draft))
+        .route("/upgrade/drafts", get(list_drafts))
+        .route("/upgrade/draft/:id", get(get_draft).patch(edit_draft))
+        .route("/upgrade/draft/:id/review", post(review_draft))
+        .route("/upgrade/draft/:id/discard", post(discard_draft))
+        .route("/upgrade/approve-batch", post(approve_batch))
+        .route("/upgrade/:id/submit", post(submit_draft))
         .route("/upgrade/:id/approve", post(approve_upgrade))
+        .route("/upgrade/:id/revoke", post(revoke_approval))
+        .route("/upgrade/:id/freeze", post(freeze_proposal))
+        .route("/upgrade/:id/unfreeze", post(unfreeze_proposal))
+        .route("/upgrade/:id/approve-link", get(get_approval_link))
+        .route("/upgrade/:id/reminders", get(get_approval_reminders))
+        .route("/upgrade/:id/reminders/opt-out", post(opt_out_of_reminders))
+        .route("/upgrade/:id/reminders/opt-in", post(opt_in_to_reminders))
         .route("/upgrade/:id/execute", post(execute_upgrade))
+        .route("/upgrade/:id/execute/confirm", post(confirm_execution))
+        .route("/upgrade/:id/job", get(get_execution_job))
+        .route("/upgrade/:id/execution", get(get_execution_receipt))
+        .route("/upgrade/:id/watch", get(get_watch_findings))
+        .route("/upgrade/:id/activity", get(get_proposal_activity))
+        .route("/upgrade/:id/execute-tx", get(get_execute_transaction))
+        .route("/upgrade/:id/execute-tx/signatures", post(submit_execute_signature))
         .route("/upgrade/:id/cancel", post(cancel_upgrade))
+        .route("/upgrade/:id/invariants", get(get_invariant_results))
+        .route("/upgrade/:id/receipts", get(get_approval_receipts))
+        .route("/upgrade/:id/snapshot-diff", get(get_upgrade_snapshot_diff))
+        .route("/upgrade/:id/impact", get(get_upgrade_impact))
+        .route("/upgrade/:id/cost", get(get_upgrade_cost))
+        .route("/upgrade/:id/sandbox/run", post(run_sandbox))
+        .route("/upgrade/:id/fork-test", get(get_fork_test).post(run_fork_test))
+        .route("/upgrade/:id/links", get(get_proposal_links))
+        .route("/onchain/events", get(list_onchain_events))
+        .route("/upgrade/:id/policy", get(get_upgrade_policy))
+        .route("/upgrade/:id/checklist", get(get_checklist))
+        .route("/upgrade/:id/checklist/:item", post(complete_checklist_item))
         .route("/upgrade/proposals", get(list_proposals))
+        .route("/upgrade/search", get(search_proposals))
         .route("/upgrade/:id/status", get(get_proposal_status))
+        .route("/upgrade/:id/countdown", get(get_proposal_countdown))
+        .route("/multisig/members", get(get_multisig_members))
+        .route("/multisig/members/profiles", get(list_member_profiles))
+        .route(
+            "/multisig/members/:member/profile",
+            get(get_member_profile).put(put_member_profile).delete(delete_member_profile),
+        )
+        .route("/multisig/config", get(get_multisig_config))
+        .route("/programs/:program/meta", get(get_program_meta))
+        .route("/programs/:program/proposals/:buffer/digest", get(get_approval_digest))
+        .route("/programs/:program/flags", get(get_feature_flags))
+        .route("/programs/:program/flags/:name/propose", post(propose_flag_change))
+        .route("/programs/:program/flags/:name/kill", post(trip_kill_switch))
+        .route("/programs/:program/flag-proposals/:proposal/apply", post(apply_flag_change))
+        .route("/programs/:program/emergency-pause", get(get_emergency_pause).post(emergency_pause))
+        .route("/emergency/pausable", get(list_pausable_programs))
         .route("/migration/start", post(start_migration))
         .route("/migration/progress", get(get_migration_progress))
+        .route("/migration/rent-budget", get(get_migration_rent_budget))
+        .route("/migration/:id/progress/stream", get(stream_migration_progress))
+        .route("/migration/:id/retry", post(retry_migration))
+        .route("/migration/:id/rollback", post(rollback_migration))
+        .route("/migration/:id/costs", get(get_migration_costs))
+        .route("/migration/:id/coverage", get(get_migration_coverage))
+        .route("/migration/:id/throughput", get(get_migration_throughput))
         .route("/monitoring/metrics", get(get_metrics))
+        .route("/monitoring/metrics/history", get(get_metrics_history))
         .route("/monitoring/alerts", get(get_alerts))
+        .route("/monitoring/slos", get(get_slos))
         .route("/monitoring/health", get(get_health))
+        .route("/monitoring/watch-expressions", get(list_watch_expressions).post(add_watch_expression))
+        .route("/monitoring/watch-expressions/:id", delete(remove_watch_expression))
+        .route("/snapshots", post(take_snapshot))
+        .route("/snapshots/diff", get(diff_snapshots))
+        .route("/security/denylist", get(list_denylist).post(add_denylist_entry))
+        .route("/security/denylist/:id", delete(remove_denylist_entry))
+        .route("/buffers/detected", get(list_detected_buffers))
+        .route("/buffers/:buffer/propose", post(propose_detected_buffer))
+        .route("/buffers/:buffer/dismiss", post(dismiss_detected_buffer))
+        .route("/history/import", post(import_upgrade_history))
+        .route("/rollback/drill", post(run_rollback_drill))
+        .route("/rollback/drills", get(list_rollback_drills))
+        .route("/incidents", get(list_incidents).post(open_incident))
+        .route("/incidents/:id", get(get_incident).patch(update_incident))
+        .route("/incidents/:id/close", post(close_incident))
+        .route("/incidents/:id/report", get(get_incident_report))
+        .route("/artifacts", get(list_artifacts))
+        .route("/artifacts/:hash/download", get(download_artifact))
+        .route("/integrations/github/release", post(github_release_webhook))
+        .route("/events", get(list_events))
         .route("/ws", get(websocket_handler))
-        .layer(CorsLayer::permissive())
-        .with_state(app_state);
+        .route("/notifications/templates/preview", post(preview_notification_template))
+        .route("/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/:id/rotate", post(rotate_api_key))
+        .route("/api-keys/:id", delete(revoke_api_key))
+        .nest("/public", public_routes)
+        .layer(middleware::from_fn_with_state(api_key_service, api_keys::require_api_key))
+        // Bodies are capped per route by `request_limits` instead of axum's flat 2 MiB
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn_with_state(
+            Arc::new(RequestLimits::from_env()),
+            request_limits::enforce,
+        ));
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    info!("Server listening on http://0.0.0.0:3000");
+    // Routes live under /v1; the unversioned paths remain as deprecated aliases
+    let legacy_routes = Arc::new(LegacyRoutes::from_env());
+    let app = Router::new()
+        .nest("/v1", api.clone())
+        .merge(api.layer(middleware::from_fn_with_state(
+            legacy_routes,
+            versioning::deprecate_legacy_route,
+        )))
+        .route("/metrics", get(prometheus_metrics))
+        // ETags are computed on the uncompressed body, so compression goes outside
+        .layer(middleware::from_fn(http_cache::conditional_get))
+        .layer(CompressionLayer::new())
+        .layer(middleware::from_fn_with_state(
+            app_state.monitoring_service.clone(),
+            request_metrics::track_requests,
+        ))
+        .layer(middleware::from_fn(versioning::negotiate_version))
+        .layer(server_config.cors_layer()?)
+        .with_state(app_state);
 
-    axum::serve(listener, app).await?;
+    server::serve(&server_config, app).await?;
 
     Ok(())
 }
 
+#[derive(Deserialize)]
+struct CancelUpgradeRequest {
+    reason: CancellationReason,
+    #[serde(default)]
+    details: String,
+}
+
 #[derive(Deserialize)]
 struct ProposeUpgradeRequest {
     new_program_buffer: String,
     description: String,
+    #[serde(default)]
+    publish_idl: bool,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    #[serde(default)]
+    supersedes: Vec<String>,
 }
 
 #[derive(Serialize)]
 struct ProposeUpgradeResponse {
     proposal_id: String,
     timelock_until: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
 async fn propose_upgrade(
@@ -140,8 +832,19 @@ async fn propose_upgrade(
         .map_err(|_| UpgradeError::InvalidPubkey)?;
 
     let proposal_id = state.proposal_manager
-        .propose_upgrade(buffer_pubkey, req.description)
+        .propose_upgrade_with_options(
+            buffer_pubkey,
+            req.description,
+            ProposalOptions {
+                publish_idl: req.publish_idl,
+                depends_on: req.depends_on,
+                supersedes: req.supersedes,
+                ..Default::default()
+            },
+        )
         .await?;
+    spawn_policy_review(&state, &proposal_id);
+    let warnings = capacity_warnings(&state, &proposal_id).await;
 
     let timelock_until = state.timelock_manager
         .get_timelock_end(&proposal_id)
@@ -150,121 +853,2311 @@ async fn propose_upgrade(
     Ok(Json(ProposeUpgradeResponse {
         proposal_id,
         timelock_until,
+        warnings,
     }))
 }
 
-async fn approve_upgrade(
+/// Warn when the proposed program outgrew the deployed program data. Reading
+/// the accounts is best effort; execution checks again and extends the
+/// account when it has to.
+async fn capacity_warnings(state: &AppState, proposal_id: &str) -> Vec<String> {
+    let Ok(proposal) = state.proposal_manager.get_proposal(proposal_id).await else {
+        return Vec::new();
+    };
+    let (Ok(program), Ok(buffer)) = (proposal.program.parse(), proposal.new_buffer.parse()) else {
+        return Vec::new();
+    };
+
+    match state.multisig_coordinator.program_capacity(&program, &buffer).await {
+        Ok(capacity) => capacity.and_then(|capacity| capacity.warning()).into_iter().collect(),
+        Err(e) => {
+            tracing::warn!("Capacity check for proposal {} failed: {}", proposal_id, e);
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProposeDeployRequest {
+    new_program_buffer: String,
+    description: String,
+    /// Program data room for this and every later upgrade
+    max_data_len: usize,
+    #[serde(default)]
+    publish_idl: bool,
+}
+
+#[derive(Serialize)]
+struct ProposeDeployResponse {
+    proposal_id: String,
+    program_id: String,
+    timelock_until: i64,
+}
+
+/// Propose the first deployment of a new program from a buffer. The program
+/// account is created now; the multisig deploys into it and becomes its
+/// upgrade authority once the proposal executes.
+async fn propose_deploy(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Path(proposal_id): Path<String>,
+    Json(req): Json<ProposeDeployRequest>,
+) -> Result<Json<ProposeDeployResponse>, UpgradeError> {
+    let buffer_pubkey = req.new_program_buffer.parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    if req.max_data_len == 0 {
+        return Err(UpgradeError::InvalidRequest("max_data_len must be positive".to_string()));
+    }
+
+    let (proposal_id, program_id) =
+        open_deployment(&state, buffer_pubkey, req.description, req.max_data_len, req.publish_idl).await?;
+
+    let timelock_until = state.timelock_manager
+        .get_timelock_end(&proposal_id)
+        .await?;
+
+    Ok(Json(ProposeDeployResponse {
+        proposal_id,
+        program_id: program_id.to_string(),
+        timelock_until,
+    }))
+}
+
+/// Create the program account and the deployment proposal for `buffer`
+async fn open_deployment(
+    state: &AppState,
+    buffer: solana_sdk::pubkey::Pubkey,
+    description: String,
+    max_data_len: usize,
+    publish_idl: bool,
+) -> Result<(String, solana_sdk::pubkey::Pubkey), UpgradeError> {
+    // Check before paying rent for a program account nothing could deploy into
+    let backend = state.multisig_coordinator.backend();
+    if !backend.wraps_loader_instructions() {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "The {} backend cannot propose initial deployments",
+            backend.kind().as_str()
+        )));
+    }
+
+    let program_id = state.program_builder
+        .create_program_account()
+        .await?;
+
+    let proposal_id = state.proposal_manager
+        .propose_upgrade_with_options(
+            buffer,
+            description,
+            ProposalOptions {
+                publish_idl,
+                deployment: Some(ProgramDeployment {
+                    program_id: program_id.to_string(),
+                    max_data_len,
+                }),
+                ..Default::default()
+            },
+        )
+        .await?;
+    spawn_policy_review(state, &proposal_id);
+
+    Ok((proposal_id, program_id))
+}
+
+#[derive(Deserialize)]
+struct ProposeAuthorityChangeRequest {
+    /// Omit or `null` to make the program immutable
+    new_authority: Option<String>,
+    description: String,
+    #[serde(default)]
+    confirm_immutable: bool,
+}
+
+#[derive(Serialize)]
+struct ProposeAuthorityChangeResponse {
+    proposal_id: String,
+    new_authority: Option<String>,
+    irreversible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<&'static str>,
+    timelock_until: i64,
+}
+
+/// Propose handing the managed program's upgrade authority to a new key, or
+/// removing it, through the usual approval and timelock flow
+async fn propose_authority_change(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(req): Json<ProposeAuthorityChangeRequest>,
+) -> Result<Json<ProposeAuthorityChangeResponse>, UpgradeError> {
+    let new_authority = req.new_authority
+        .as_deref()
+        .map(|authority| authority.parse().map_err(|_| UpgradeError::InvalidPubkey))
+        .transpose()?;
+    let change = AuthorityChange::new(new_authority, req.confirm_immutable)?;
+    let irreversible = change.is_irreversible();
+
+    let proposal_id = state.proposal_manager
+        .propose_upgrade_with_options(
+            change.target()?,
+            change.describe(&req.description),
+            ProposalOptions {
+                authority_change: Some(change.clone()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    spawn_policy_review(&state, &proposal_id);
+    if irreversible {
+        tracing::warn!("Proposal {} would make the program immutable", proposal_id);
+    }
+
+    let timelock_until = state.timelock_manager
+        .get_timelock_end(&proposal_id)
+        .await?;
+
+    Ok(Json(ProposeAuthorityChangeResponse {
+        proposal_id,
+        new_authority: change.new_authority,
+        irreversible,
+        warning: irreversible.then_some(proposal::IMMUTABILITY_WARNING),
+        timelock_until,
+    }))
+}
+
+/// Upload a compiled program and propose it in one call. Multipart fields:
+/// `program` (the .so), `description`, and optionally `program_name`,
+/// `publish_idl`, and `deploy` with `max_data_len` for a first deployment.
+/// Chunk uploads are reported as `buffer_upload_progress`.
+async fn propose_binary(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    mut multipart: Multipart,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    state.multisig_coordinator
-        .approve_proposal(&proposal_id)
+    let mut binary = None;
+    let mut description = None;
+    let mut program_name = "upgrade_manager".to_string();
+    let mut publish_idl = false;
+    let mut deploy = false;
+    let mut max_data_len = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| UpgradeError::InvalidRequest(format!("Invalid multipart body: {}", e)))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let read_error = |e: axum::extract::multipart::MultipartError| {
+            UpgradeError::InvalidRequest(format!("Failed to read field '{}': {}", name, e))
+        };
+        match name.as_str() {
+            "program" => binary = Some(field.bytes().await.map_err(read_error)?.to_vec()),
+            "description" => description = Some(field.text().await.map_err(read_error)?),
+            "program_name" => program_name = field.text().await.map_err(read_error)?,
+            "publish_idl" => publish_idl = field.text().await.map_err(read_error)?.trim() == "true",
+            "deploy" => deploy = field.text().await.map_err(read_error)?.trim() == "true",
+            "max_data_len" => {
+                let value = field.text().await.map_err(read_error)?;
+                max_data_len = Some(value.trim().parse::<usize>().map_err(|_| {
+                    UpgradeError::InvalidRequest(format!("Invalid max_data_len: {}", value))
+                })?);
+            }
+            _ => {}
+        }
+    }
+
+    let binary = binary
+        .ok_or_else(|| UpgradeError::InvalidRequest("Missing 'program' file field".to_string()))?;
+    let description = description
+        .filter(|d| !d.trim().is_empty())
+        .ok_or_else(|| UpgradeError::InvalidRequest("Missing 'description' field".to_string()))?;
+    // Rejects anything that is not a well-formed 64-bit SBF ELF before it
+    // costs rent for a buffer
+    binary_analysis::BinaryProfile::analyze(&binary)?;
+    let max_data_len = max_data_len.unwrap_or_else(|| program_builder::default_max_data_len(binary.len()));
+    if deploy && max_data_len < binary.len() {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "max_data_len {} is smaller than the {} byte program",
+            max_data_len,
+            binary.len()
+        )));
+    }
+
+    let artifact = state.artifact_registry
+        .register(&binary, &program_name, None)
+        .await?;
+    let buffer = state.program_builder
+        .create_buffer(&binary)
+        .await?;
+
+    let description = format!("{}\n\nArtifact SHA-256: {}", description, artifact.program_hash);
+    let (proposal_id, program_id) = if deploy {
+        let (proposal_id, program_id) =
+            open_deployment(&state, buffer, description, max_data_len, publish_idl).await?;
+        (proposal_id, Some(program_id.to_string()))
+    } else {
+        let proposal_id = state.proposal_manager
+            .propose_upgrade_with_options(
+                buffer,
+                description,
+                ProposalOptions {
+                    publish_idl,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        spawn_policy_review(&state, &proposal_id);
+        (proposal_id, None)
+    };
+    let warnings = match program_id {
+        Some(_) => Vec::new(),
+        None => capacity_warnings(&state, &proposal_id).await,
+    };
+
+    let timelock_until = state.timelock_manager
+        .get_timelock_end(&proposal_id)
         .await?;
 
     Ok(Json(serde_json::json!({
-        "status": "approved",
-        "proposal_id": proposal_id
+        "proposal_id": proposal_id,
+        "program_id": program_id,
+        "buffer": buffer.to_string(),
+        "program_hash": artifact.program_hash,
+        "size_bytes": binary.len(),
+        "timelock_until": timelock_until,
+        "warnings": warnings
     })))
 }
 
-async fn execute_upgrade(
+#[derive(Deserialize)]
+struct CreateDraftRequest {
+    author: String,
+    #[serde(flatten)]
+    changes: DraftChanges,
+}
+
+async fn create_draft(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Path(proposal_id): Path<String>,
+    Json(req): Json<CreateDraftRequest>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    state.proposal_manager
-        .execute_upgrade(&proposal_id)
+    let draft = state.draft_service
+        .create(&req.author, req.changes)
         .await?;
 
-    Ok(Json(serde_json::json!({
-        "status": "executed",
-        "proposal_id": proposal_id
-    })))
+    Ok(Json(serde_json::json!(draft)))
 }
 
-async fn cancel_upgrade(
+#[derive(Deserialize)]
+struct DraftsQuery {
+    status: Option<String>,
+}
+
+async fn list_drafts(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Path(proposal_id): Path<String>,
+    Query(query): Query<DraftsQuery>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    state.proposal_manager
-        .cancel_upgrade(&proposal_id)
+    let status = query.status
+        .map(|s| {
+            DraftStatus::parse(&s)
+                .ok_or_else(|| UpgradeError::InvalidRequest(format!("Unknown draft status '{}'", s)))
+        })
+        .transpose()?;
+
+    let drafts = state.draft_service
+        .list(status)
+        .await?;
+
+    Ok(Json(serde_json::json!(drafts)))
+}
+
+async fn get_draft(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(draft_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let draft = state.draft_service
+        .get(&draft_id)
         .await?;
 
     Ok(Json(serde_json::json!({
-        "status": "cancelled",
-        "proposal_id": proposal_id
+        "draft": draft,
+        "approvals": draft.approvals(),
+        "required_reviews": state.draft_service.required_reviews(),
     })))
 }
 
-async fn list_proposals(
+async fn edit_draft(
     axum::extract::State(state): axum::extract::State<AppState>,
+    Path(draft_id): Path<String>,
+    Json(changes): Json<DraftChanges>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    let proposals = state.proposal_manager
-        .list_proposals()
+    let draft = state.draft_service
+        .edit(&draft_id, changes)
         .await?;
 
-    Ok(Json(serde_json::json!(proposals)))
+    Ok(Json(serde_json::json!(draft)))
 }
 
-async fn get_proposal_status(
+#[derive(Deserialize)]
+struct ReviewDraftRequest {
+    reviewer: String,
+    verdict: ReviewVerdict,
+    comment: Option<String>,
+}
+
+async fn review_draft(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Path(proposal_id): Path<String>,
+    Path(draft_id): Path<String>,
+    Json(req): Json<ReviewDraftRequest>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    let status = state.proposal_manager
-        .get_proposal_status(&proposal_id)
+    let draft = state.draft_service
+        .review(&draft_id, &req.reviewer, req.verdict, req.comment)
         .await?;
 
-    Ok(Json(status))
+    Ok(Json(serde_json::json!(draft)))
 }
 
-async fn start_migration(
+async fn discard_draft(
     axum::extract::State(state): axum::extract::State<AppState>,
+    Path(draft_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    let migration_id = state.migration_manager
-        .start_migration()
+    let draft = state.draft_service
+        .discard(&draft_id)
+        .await?;
+
+    Ok(Json(serde_json::json!(draft)))
+}
+
+async fn submit_draft(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(draft_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let draft = state.draft_service
+        .submit(&draft_id)
+        .await?;
+    let proposal_id = draft.proposal_id.clone().unwrap_or_default();
+    spawn_policy_review(&state, &proposal_id);
+
+    let timelock_until = state.timelock_manager
+        .get_timelock_end(&proposal_id)
         .await?;
 
     Ok(Json(serde_json::json!({
-        "migration_id": migration_id,
-        "status": "started"
+        "proposal_id": proposal_id,
+        "timelock_until": timelock_until,
+        "draft": draft,
     })))
 }
 
-async fn get_migration_progress(
+async fn approve_upgrade(
     axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, UpgradeError> {
-    let progress = state.migration_manager
-        .get_progress()
+    // Checked before the vote is recorded with the multisig
+    state.proposal_manager
+        .ensure_not_frozen(&proposal_id)
         .await?;
 
-    Ok(Json(progress))
+    let approver = state.multisig_coordinator
+        .approve_proposal(&proposal_id)
+        .await?;
+
+    let status = state.proposal_manager
+        .record_approval(&proposal_id, &approver)
+        .await?;
+
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let receipt = state.receipt_service
+        .issue(&proposal, &approver)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "approved",
+        "proposal_id": proposal_id,
+        "proposal_status": status,
+        "receipt": receipt
+    })))
 }
 
-async fn websocket_handler(
-    ws: WebSocketUpgrade,
-) -> Response {
-    // In real implementation, get notification sender from state
-    let notification_service = websocket::NotificationService::new();
-    let notification_sender = notification_service.get_sender();
-    let receiver = notification_sender.subscribe();
+async fn approve_batch(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(request): Json<BatchApprovalRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    batch_approval::validate_batch(&request.proposal_ids)?;
+
+    // The signature covers every proposal's current digest, so all of them
+    // must exist before any approval is recorded
+    let mut proposals = Vec::with_capacity(request.proposal_ids.len());
+    for proposal_id in &request.proposal_ids {
+        proposals.push(state.proposal_manager.get_proposal(proposal_id).await?);
+    }
+    let digests: Vec<(String, String)> = proposals
+        .iter()
+        .map(|proposal| (proposal.id.clone(), batch_approval::proposal_digest(proposal)))
+        .collect();
+    let message = batch_approval::batch_message(&request.member, &digests);
+    batch_approval::verify_batch_signature(&request.member, &message, &request.signature)?;
 
-    ws.on_upgrade(|socket| websocket::handle_websocket(socket, receiver))
+    let mut results = Vec::with_capacity(proposals.len());
+    for proposal in &proposals {
+        let result = match approve_one(&state, proposal, &request.member).await {
+            Ok((status, receipt)) => BatchApprovalResult::approved(&proposal.id, status, receipt),
+            Err(e) => {
+                tracing::warn!("Batch approval of {} by {} failed: {}", proposal.id, request.member, e);
+                BatchApprovalResult::failed(&proposal.id, &e)
+            }
+        };
+        results.push(result);
+    }
+
+    let approved = results.iter().filter(|r| r.approved).count();
+    Ok(Json(serde_json::json!({
+        "member": request.member,
+        "approved": approved,
+        "failed": results.len() - approved,
+        "results": results
+    })))
 }
 
-async fn get_metrics() -> Json<serde_json::Value> {
-    let monitoring = MonitoringService::new();
-    let dashboard = monitoring.get_dashboard_data().await;
-    Json(dashboard)
+/// One proposal of a batch: everything that can reject it is checked before
+/// the multisig records the vote, so a failure leaves no partial approval
+async fn approve_one(
+    state: &AppState,
+    proposal: &proposal::Proposal,
+    member: &str,
+) -> Result<(proposal::ProposalStatus, receipts::SignedReceipt), UpgradeError> {
+    state.proposal_manager
+        .ensure_not_frozen(&proposal.id)
+        .await?;
+    proposal.status.transition(proposal::ProposalEvent::Approve)?;
+    if proposal.approvals.iter().any(|a| a == member) {
+        return Err(UpgradeError::InvalidRequest(format!("{} already approved", member)));
+    }
+
+    state.multisig_coordinator
+        .approve_signed(&proposal.id, member)
+        .await?;
+
+    let status = state.proposal_manager
+        .record_approval(&proposal.id, member)
+        .await?;
+
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal.id)
+        .await?;
+    let receipt = state.receipt_service
+        .issue(&proposal, member)
+        .await?;
+
+    Ok((status, receipt))
 }
 
-async fn get_alerts() -> Json<serde_json::Value> {
-    let monitoring = MonitoringService::new();
-    let alerts = monitoring.get_alerts(50).await;
-    Json(serde_json::json!(alerts))
+#[derive(Deserialize, Default)]
+struct RevokeApprovalRequest {
+    /// Why the member changed their stance, e.g. a new audit finding
+    #[serde(default)]
+    reason: String,
+}
+
+async fn revoke_approval(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    body: Option<Json<RevokeApprovalRequest>>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+
+    let approver = state.multisig_coordinator
+        .revoke_proposal_approval(&proposal_id)
+        .await?;
+
+    let status = state.proposal_manager
+        .revoke_approval(&proposal_id, &approver, &req.reason)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "revoked",
+        "proposal_id": proposal_id,
+        "approver": approver,
+        "proposal_status": status,
+        "reason": req.reason,
+    })))
 }
 
-async fn get_health() -> Json<serde_json::Value> {
-    let monitoring = MonitoringService::new();
-    let health = monitoring.check_health("system").await;
+async fn get_approval_reminders(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let schedule = state.approval_reminders.schedule();
+    let opted_out = state.database
+        .list_reminder_opt_outs(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "deadline": schedule.deadline(proposal.proposed_at),
+        "reminder_times": schedule.reminder_times(proposal.proposed_at),
+        "opted_out": opted_out,
+    })))
+}
+
+#[derive(Deserialize)]
+struct ReminderPreferenceRequest {
+    member: String,
+}
+
+async fn opt_out_of_reminders(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Json(req): Json<ReminderPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.approval_reminders
+        .opt_out(&proposal_id, &req.member)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "member": req.member,
+        "reminders": "off"
+    })))
+}
+
+async fn opt_in_to_reminders(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Json(req): Json<ReminderPreferenceRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.approval_reminders
+        .opt_in(&proposal_id, &req.member)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "member": req.member,
+        "reminders": "on"
+    })))
+}
+
+#[derive(Deserialize)]
+struct FreezeProposalRequest {
+    finding: String,
+}
+
+async fn freeze_proposal(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<FreezeProposalRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    let admin = security::security_admin_identity(token, &state.secrets)?;
+    if req.finding.trim().is_empty() {
+        return Err(UpgradeError::InvalidRequest("finding is required".to_string()));
+    }
+
+    let freeze = state.proposal_manager
+        .freeze(&proposal_id, req.finding, FreezeSource::Admin, &admin)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "frozen",
+        "proposal_id": proposal_id,
+        "freeze": freeze,
+    })))
+}
+
+#[derive(Deserialize)]
+struct UnfreezeProposalRequest {
+    /// How the finding was resolved
+    resolution: String,
+}
+
+async fn unfreeze_proposal(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<UnfreezeProposalRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    let admin = security::security_admin_identity(token, &state.secrets)?;
+    if req.resolution.trim().is_empty() {
+        return Err(UpgradeError::InvalidRequest("resolution is required".to_string()));
+    }
+
+    let status = state.proposal_manager
+        .unfreeze(&proposal_id, &admin, &req.resolution)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "unfrozen",
+        "proposal_id": proposal_id,
+        "proposal_status": status,
+    })))
+}
+
+#[derive(Deserialize)]
+struct ExecuteQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+async fn execute_upgrade(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Query(query): Query<ExecuteQuery>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    // Fail fast on unknown proposals instead of queueing a job that can never run
+    state.proposal_manager
+        .get_proposal_status(&proposal_id)
+        .await?;
+
+    // Forcing only skips the health gate; the checklist always applies
+    state.checklist_service
+        .ensure_complete(&proposal_id)
+        .await?;
+
+    let token = headers
+        .get("x-executor-token")
+        .and_then(|v| v.to_str().ok());
+    if query.force {
+        security::verify_executor_token(token, &state.secrets)?;
+        tracing::warn!("Execution of {} forced past the dependency health gate", proposal_id);
+    } else {
+        state.monitoring_service
+            .ensure_execution_dependencies_healthy()
+            .await?;
+    }
+
+    // Under the two-person rule this only opens the window for a second executor
+    if state.two_person_rule.enabled() {
+        let initiator = security::executor_identity(token, &state.secrets)?;
+        let request = state.two_person_rule
+            .initiate(&proposal_id, &initiator, query.force)
+            .await?;
+
+        return Ok((StatusCode::ACCEPTED, Json(serde_json::json!({
+            "status": "awaiting_confirmation",
+            "proposal_id": proposal_id,
+            "initiated_by": request.initiated_by,
+            "expires_at": request.expires_at,
+        }))));
+    }
+
+    let job = state.execution_worker
+        .enqueue(&proposal_id, query.force)
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({
+        "status": job.status,
+        "proposal_id": proposal_id,
+        "job_id": job.job_id,
+    }))))
+}
+
+async fn confirm_execution(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    let token = headers
+        .get("x-executor-token")
+        .and_then(|v| v.to_str().ok());
+    let confirmer = security::executor_identity(token, &state.secrets)?;
+
+    // Gates are checked again; things may have changed since initiation
+    let pending = state.two_person_rule
+        .pending(&proposal_id)
+        .await?;
+    state.checklist_service
+        .ensure_complete(&proposal_id)
+        .await?;
+    if !pending.forced {
+        state.monitoring_service
+            .ensure_execution_dependencies_healthy()
+            .await?;
+    }
+
+    let request = state.two_person_rule
+        .confirm(&proposal_id, &confirmer)
+        .await?;
+    let job = state.execution_worker
+        .enqueue(&proposal_id, request.forced)
+        .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({
+        "status": job.status,
+        "proposal_id": proposal_id,
+        "job_id": job.job_id,
+        "initiated_by": request.initiated_by,
+        "confirmed_by": confirmer,
+    }))))
+}
+
+async fn get_execution_job(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let job = state.database
+        .get_execution_job_for_proposal(&proposal_id)
+        .await?
+        .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.clone()))?;
+    let attempt_history = state.database
+        .list_execution_attempts(&job.job_id)
+        .await?;
+
+    let mut body = serde_json::json!(job);
+    body["attempt_history"] = serde_json::json!(attempt_history);
+    Ok(Json(body))
+}
+
+async fn get_execution_receipt(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<confirmation::ExecutionReceipt>, UpgradeError> {
+    let receipt = state.confirmation_tracker
+        .receipt(&proposal_id)
+        .await?
+        .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.clone()))?;
+
+    Ok(Json(receipt))
+}
+
+async fn get_watch_findings(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<Vec<watch::WatchFinding>>, UpgradeError> {
+    let findings = state.post_upgrade_watch
+        .findings(&proposal_id)
+        .await?;
+
+    Ok(Json(findings))
+}
+
+#[derive(Deserialize)]
+struct ActivityQuery {
+    /// Comma-separated kinds to keep, e.g. `approval,audit`
+    kind: Option<String>,
+}
+
+async fn get_proposal_activity(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let kinds = query.kind
+        .as_deref()
+        .map(ActivityKind::parse_list)
+        .transpose()?
+        .unwrap_or_default();
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+
+    let entries = state.activity_feed
+        .feed(&proposal, &kinds)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "entries": entries,
+    })))
+}
+
+#[derive(Deserialize)]
+struct IncidentsQuery {
+    /// `active` (default) or `all`
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+async fn list_incidents(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<IncidentsQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let active_only = match query.status.as_deref() {
+        None | Some("active") => true,
+        Some("all") => false,
+        Some(other) => {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Unknown incident status filter '{}', expected active or all",
+                other
+            )))
+        }
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
+    let incidents = state.incident_manager
+        .list(active_only, limit)
+        .await?;
+
+    Ok(Json(serde_json::json!({ "incidents": incidents })))
+}
+
+async fn open_incident(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(req): Json<OpenIncidentRequest>,
+) -> Result<Json<incidents::Incident>, UpgradeError> {
+    let incident = state.incident_manager
+        .open(req)
+        .await?;
+
+    Ok(Json(incident))
+}
+
+async fn get_incident(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(incident_id): Path<String>,
+) -> Result<Json<incidents::Incident>, UpgradeError> {
+    let incident = state.incident_manager
+        .get(&incident_id)
+        .await?;
+
+    Ok(Json(incident))
+}
+
+async fn update_incident(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(incident_id): Path<String>,
+    Json(change): Json<IncidentChange>,
+) -> Result<Json<incidents::Incident>, UpgradeError> {
+    let incident = state.incident_manager
+        .update(&incident_id, change)
+        .await?;
+
+    Ok(Json(incident))
+}
+
+async fn close_incident(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(incident_id): Path<String>,
+    Json(req): Json<CloseIncidentRequest>,
+) -> Result<Json<incidents::Incident>, UpgradeError> {
+    let incident = state.incident_manager
+        .close(&incident_id, req)
+        .await?;
+
+    Ok(Json(incident))
+}
+
+async fn get_incident_report(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(incident_id): Path<String>,
+) -> Result<Json<accountability::SignedIncidentReport>, UpgradeError> {
+    let report = state.accountability_reporter
+        .report(&incident_id)
+        .await?;
+
+    Ok(Json(report))
+}
+
+async fn cancel_upgrade(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Json(req): Json<CancelUpgradeRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.proposal_manager
+        .cancel_upgrade(&proposal_id, req.reason, req.details.clone())
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "cancelled",
+        "proposal_id": proposal_id,
+        "reason": req.reason,
+        "details": req.details,
+    })))
+}
+
+async fn get_invariant_results(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let results = state.invariant_registry
+        .results_for(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!(results)))
+}
+
+#[derive(Deserialize)]
+struct ApprovalLinkQuery {
+    member: String,
+}
+
+async fn get_approval_link(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Query(query): Query<ApprovalLinkQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let link = state.approval_link_service
+        .link(&proposal_id, &query.member)
+        .await?;
+
+    Ok(Json(serde_json::json!(link)))
+}
+
+async fn get_approval_receipts(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let bundle = state.receipt_service
+        .bundle(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!(bundle)))
+}
+
+async fn get_upgrade_snapshot_diff(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let diff = match state.database.get_upgrade_account_diff(&proposal_id).await? {
+        Some(diff) => diff,
+        None => state.snapshot_service.compare_upgrade(&proposal_id).await?,
+    };
+
+    Ok(Json(serde_json::json!(diff)))
+}
+
+#[derive(Deserialize)]
+struct TakeSnapshotRequest {
+    proposal_id: Option<String>,
+}
+
+async fn take_snapshot(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(req): Json<TakeSnapshotRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let snapshot = state.snapshot_service
+        .capture(SnapshotLabel::Manual, req.proposal_id.as_deref())
+        .await?;
+
+    Ok(Json(serde_json::json!(snapshot)))
+}
+
+#[derive(Deserialize)]
+struct SnapshotDiffQuery {
+    from: String,
+    to: String,
+}
+
+async fn diff_snapshots(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<SnapshotDiffQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let diff = state.snapshot_service
+        .compare(&query.from, &query.to)
+        .await?;
+
+    Ok(Json(serde_json::json!(diff)))
+}
+
+#[derive(Deserialize)]
+struct DetectedBuffersQuery {
+    status: Option<String>,
+}
+
+async fn list_detected_buffers(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<DetectedBuffersQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let status = query.status
+        .map(|s| {
+            DetectedBufferStatus::parse(&s)
+                .ok_or_else(|| UpgradeError::InvalidRequest(format!("Unknown buffer status '{}'", s)))
+        })
+        .transpose()?;
+
+    let buffers = state.buffer_watcher
+        .list(status)
+        .await?;
+
+    Ok(Json(serde_json::json!(buffers)))
+}
+
+#[derive(Deserialize, Default)]
+struct ProposeDetectedBufferRequest {
+    description: Option<String>,
+}
+
+async fn propose_detected_buffer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(buffer): Path<String>,
+    body: Option<Json<ProposeDetectedBufferRequest>>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let proposal_id = state.buffer_watcher
+        .propose(&buffer, req.description)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "buffer": buffer,
+        "status": "proposed"
+    })))
+}
+
+async fn dismiss_detected_buffer(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(buffer): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.buffer_watcher
+        .dismiss(&buffer)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "buffer": buffer,
+        "status": "dismissed"
+    })))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<i64>,
+}
+
+async fn search_proposals(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    if query.q.trim().is_empty() {
+        return Err(UpgradeError::InvalidRequest("q must not be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    let hits = state.proposal_manager
+        .search(&query.q, limit)
+        .await?;
+
+    Ok(Json(serde_json::json!(hits)))
+}
+
+async fn list_proposals(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let proposals = state.proposal_manager
+        .list_proposals()
+        .await?;
+
+    Ok(Json(serde_json::json!(proposals)))
+}
+
+async fn get_proposal_countdown(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<countdown::Countdown>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+
+    Ok(Json(countdown::Countdown::new(
+        &proposal,
+        state.timelock_manager.now(),
+        state.timelock_manager.drift_seconds(),
+    )))
+}
+
+async fn get_proposal_status(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let started = std::time::Instant::now();
+    let mut status = state.proposal_manager
+        .get_proposal_status(&proposal_id)
+        .await?;
+    let checklist = state.checklist_service
+        .status(&proposal_id)
+        .await?;
+    status["checklist"] = serde_json::json!(checklist);
+    let execution = state.confirmation_tracker
+        .status(&proposal_id)
+        .await?;
+    status["execution"] = serde_json::json!(execution);
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let directory = MemberDirectory::new(state.database.list_member_profiles().await?);
+    status["approvers"] = serde_json::json!(proposal.approvals
+        .iter()
+        .map(|member| serde_json::json!({
+            "member": member,
+            "display_name": directory.display_name(member),
+        }))
+        .collect::<Vec<_>>());
+
+    state.monitoring_service
+        .record_latency("status_read", started.elapsed())
+        .await;
+
+    Ok(Json(status))
+}
+
+#[derive(Deserialize)]
+struct ExecuteTransactionQuery {
+    /// Comma-separated member pubkeys; the first one executes
+    signers: Option<String>,
+}
+
+async fn get_execute_transaction(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Query(query): Query<ExecuteTransactionQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let signers = query
+        .signers
+        .map(|signers| {
+            signers
+                .split(',')
+                .map(|s| s.trim().parse().map_err(|_| UpgradeError::InvalidPubkey))
+                .collect::<Result<Vec<solana_sdk::pubkey::Pubkey>, _>>()
+        })
+        .transpose()?;
+
+    // Same gates as queued execution, before members spend time signing
+    state.checklist_service
+        .ensure_complete(&proposal_id)
+        .await?;
+    state.monitoring_service
+        .ensure_execution_dependencies_healthy()
+        .await?;
+
+    let view = state.execute_tx_service
+        .transaction(&proposal_id, signers)
+        .await?;
+
+    Ok(Json(serde_json::json!(view)))
+}
+
+#[derive(Deserialize)]
+struct ExecuteSignatureRequest {
+    signer: String,
+    /// Base58 ed25519 signature over the transaction message
+    signature: String,
+}
+
+async fn submit_execute_signature(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Json(req): Json<ExecuteSignatureRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let signer: solana_sdk::pubkey::Pubkey = req.signer
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    let signature: solana_sdk::signature::Signature = req.signature
+        .parse()
+        .map_err(|_| UpgradeError::InvalidRequest("Invalid signature encoding".to_string()))?;
+
+    // The last signature broadcasts, so a checklist item reopened meanwhile still blocks
+    state.checklist_service
+        .ensure_complete(&proposal_id)
+        .await?;
+
+    // Transactions assembled here bypass the execution worker's denylist check
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let buffer = proposal.new_buffer.parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    state.security_auditor
+        .ensure_not_denylisted(&proposal_id, buffer)
+        .await?;
+
+    let view = state.execute_tx_service
+        .add_signature(&proposal_id, &signer, signature)
+        .await?;
+
+    Ok(Json(serde_json::json!(view)))
+}
+
+async fn get_upgrade_impact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+
+    let buffer = proposal.new_buffer.parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    // Not yet deployed (or not a real address) means there is nothing to compare against
+    let program = proposal.program.parse().ok();
+    let (buffer_binary, deployed) = state.security_auditor
+        .fetch_upgrade_binaries(buffer, program)
+        .await?;
+
+    // Release proposals are analyzed from the verified artifact rather than the buffer
+    let proposed = match &proposal.source {
+        Some(source) => state.artifact_registry.download(&source.artifact_hash).await?.1,
+        None => buffer_binary,
+    };
+
+    let impact = state.security_auditor
+        .analyze_binary_impact(&proposed, deployed.as_deref())?;
+    let capacity = match program {
+        Some(program) => state.multisig_coordinator.program_capacity(&program, &buffer).await?,
+        None => None,
+    };
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "impact": impact,
+        "capacity": capacity,
+        "extension_bytes": capacity.map(|capacity| capacity.extension_needed()),
+    })))
+}
+
+async fn get_upgrade_cost(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+
+    let program = proposal.program.parse::<solana_sdk::pubkey::Pubkey>();
+    let extension = match (&proposal.deployment, &proposal.authority_change, program) {
+        (None, None, Ok(program)) => {
+            let buffer = proposal.new_buffer.parse()
+                .map_err(|_| UpgradeError::InvalidPubkey)?;
+            state.multisig_coordinator
+                .extension_cost(&program, &buffer)
+                .await?
+        }
+        _ => None,
+    };
+    // Priced as the member-signed execute transaction with its own fee payer,
+    // the path with the most signatures
+    let signatures = state.multisig_coordinator.get_threshold() as usize + 1;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "cost": UpgradeCost::new(extension, signatures),
+    })))
+}
+
+/// Run the auto-approval policy on a new proposal. Reading the buffer and
+/// voting on chain is slow, so the caller does not wait for it.
+fn spawn_policy_review(state: &AppState, proposal_id: &str) {
+    let policy_engine = state.policy_engine.clone();
+    let proposal_id = proposal_id.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = policy_engine.review(&proposal_id).await {
+            tracing::error!("Policy review of {} failed: {}", proposal_id, e);
+        }
+    });
+}
+
+async fn get_proposal_links(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let links = state.proposal_manager
+        .get_links(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!(links)))
+}
+
+async fn list_onchain_events(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<indexer::OnchainEventQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let proposal = match query.proposal.as_deref() {
+        Some(proposal) if proposal.parse::<solana_sdk::pubkey::Pubkey>().is_ok() => Some(proposal.to_string()),
+        // A backend proposal id: its events are about the proposal account for its buffer
+        Some(proposal_id) => {
+            let proposal = state.proposal_manager
+                .get_proposal(proposal_id)
+                .await?;
+            let program: solana_sdk::pubkey::Pubkey = proposal.program.parse().map_err(|_| {
+                UpgradeError::InvalidRequest(format!("Proposal program '{}' is not an address", proposal.program))
+            })?;
+            let buffer: solana_sdk::pubkey::Pubkey = proposal.new_buffer
+                .parse()
+                .map_err(|_| UpgradeError::InvalidPubkey)?;
+            Some(state.multisig_coordinator.proposal_address(&program, &buffer).to_string())
+        }
+        None => None,
+    };
+
+    let limit = query.limit.unwrap_or(500).clamp(1, 1000);
+    let events = state.program_indexer
+        .stored_events(proposal.as_deref(), query.event.as_deref(), query.after.unwrap_or(0), limit)
+        .await?;
+    let cursor = state.program_indexer
+        .cursor()
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal": proposal,
+        "next_after": events.last().and_then(|event| event.id),
+        "events": events,
+        "cursor": cursor,
+    })))
+}
+
+async fn run_sandbox(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Json(request): Json<SandboxRunRequest>,
+) -> Result<Json<sandbox::SandboxReport>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let elf = proposed_binary(&state, &proposal).await?;
+
+    let report = state.sandbox
+        .run(&proposal, elf, request)
+        .await?;
+
+    Ok(Json(report))
+}
+
+/// Binary a proposal would deploy. Release proposals use the verified
+/// artifact, like the impact analysis; others read the buffer.
+async fn proposed_binary(state: &AppState, proposal: &proposal::Proposal) -> Result<Vec<u8>, UpgradeError> {
+    match &proposal.source {
+        Some(source) => Ok(state.artifact_registry.download(&source.artifact_hash).await?.1),
+        None => {
+            let buffer = proposal.new_buffer.parse()
+                .map_err(|_| UpgradeError::InvalidPubkey)?;
+            Ok(state.security_auditor
+                .fetch_upgrade_binaries(buffer, None)
+                .await?
+                .0)
+        }
+    }
+}
+
+async fn run_fork_test(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<fork_replay::ForkTestReport>, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_proposal(&proposal_id)
+        .await?;
+    let elf = proposed_binary(&state, &proposal).await?;
+
+    let report = state.fork_tester
+        .run(&proposal, elf)
+        .await?;
+
+    Ok(Json(report))
+}
+
+async fn get_fork_test(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<fork_replay::ForkTestReport>, UpgradeError> {
+    let report = state.fork_tester
+        .latest(&proposal_id)
+        .await?;
+
+    Ok(Json(report))
+}
+
+async fn get_upgrade_policy(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let decision = state.policy_engine
+        .evaluate(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "proposal_id": proposal_id,
+        "policy": decision,
+    })))
+}
+
+async fn list_api_keys(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let keys = state.api_key_service
+        .list()
+        .await?;
+
+    Ok(Json(serde_json::json!(keys)))
+}
+
+async fn create_api_key(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(principal): axum::Extension<ApiPrincipal>,
+    Json(req): Json<api_keys::NewApiKey>,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    let (record, key) = state.api_key_service
+        .create(req, &principal)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!({
+        "key": key,
+        "api_key": record,
+    }))))
+}
+
+async fn rotate_api_key(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let (record, key) = state.api_key_service
+        .rotate(&id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "key": key,
+        "api_key": record,
+    })))
+}
+
+async fn revoke_api_key(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.api_key_service
+        .revoke(&id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "status": "revoked",
+        "id": id,
+    })))
+}
+
+async fn list_watch_expressions(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let expressions = state.database
+        .list_watch_expressions()
+        .await?;
+
+    Ok(Json(serde_json::json!(expressions)))
+}
+
+/// Expressions can roll the program back, so they need the security admin token
+async fn add_watch_expression(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<watch::NewWatchExpression>,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    security::verify_security_admin_token(token, &state.secrets)?;
+
+    let expression = req.into_expression(chrono::Utc::now().timestamp())?;
+    state.database.insert_watch_expression(&expression).await?;
+    tracing::info!("Watch expression '{}' registered ({})", expression.name, expression.id);
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!(expression))))
+}
+
+async fn remove_watch_expression(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    security::verify_security_admin_token(token, &state.secrets)?;
+
+    if !state.database.remove_watch_expression(&id).await? {
+        return Err(UpgradeError::InvalidRequest(format!("No watch expression {}", id)));
+    }
+    tracing::info!("Watch expression {} removed", id);
+
+    Ok(Json(serde_json::json!({
+        "status": "removed",
+        "id": id,
+    })))
+}
+
+async fn list_denylist(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let entries = state.database
+        .list_denylist()
+        .await?;
+
+    Ok(Json(serde_json::json!(entries)))
+}
+
+async fn add_denylist_entry(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<denylist::NewDenylistEntry>,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    security::verify_security_admin_token(token, &state.secrets)?;
+
+    let entry = req.into_entry(chrono::Utc::now().timestamp())?;
+    if !state.database.insert_denylist_entry(&entry).await? {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "{} {} is already denylisted",
+            entry.kind.as_str(),
+            entry.value
+        )));
+    }
+    tracing::warn!("Denylisted {} {}: {}", entry.kind.as_str(), entry.value, entry.reason);
+
+    Ok((StatusCode::CREATED, Json(serde_json::json!(entry))))
+}
+
+async fn remove_denylist_entry(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    security::verify_security_admin_token(token, &state.secrets)?;
+
+    if !state.database.remove_denylist_entry(&id).await? {
+        return Err(UpgradeError::InvalidRequest(format!("No denylist entry {}", id)));
+    }
+    tracing::warn!("Denylist entry {} removed", id);
+
+    Ok(Json(serde_json::json!({
+        "status": "removed",
+        "id": id,
+    })))
+}
+
+async fn get_checklist(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.proposal_manager
+        .get_proposal_status(&proposal_id)
+        .await?;
+
+    let checklist = state.checklist_service
+        .status(&proposal_id)
+        .await?;
+
+    Ok(Json(serde_json::json!(checklist)))
+}
+
+#[derive(Deserialize, Default)]
+struct CompleteChecklistItemRequest {
+    completed_by: Option<String>,
+    evidence: Option<String>,
+}
+
+async fn complete_checklist_item(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path((proposal_id, item)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Option<Json<CompleteChecklistItemRequest>>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.proposal_manager
+        .get_proposal_status(&proposal_id)
+        .await?;
+
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let token = headers
+        .get("x-checklist-token")
+        .and_then(|v| v.to_str().ok());
+    let checklist = state.checklist_service
+        .complete(&proposal_id, &item, token, &state.secrets, req.completed_by, req.evidence)
+        .await?;
+
+    Ok(Json(serde_json::json!(checklist)))
+}
+
+/// Cache-Control value for public explorer responses
+const PUBLIC_CACHE_CONTROL: &str = "public, max-age=15";
+
+async fn public_list_proposals(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let proposals = state.proposal_manager
+        .list_public_proposals()
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)], Json(proposals)))
+}
+
+async fn public_get_proposal(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let proposal = state.proposal_manager
+        .get_public_proposal(&proposal_id)
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)], Json(proposal)))
+}
+
+async fn public_upgrade_history(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let history = state.database
+        .list_upgrade_history(100)
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)], Json(history)))
+}
+
+async fn public_status(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let summary = state.incident_manager
+        .status_summary()
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)], Json(summary)))
+}
+
+async fn public_announcements(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let announcements = state.announcement_service
+        .upcoming()
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, PUBLIC_CACHE_CONTROL)], Json(announcements)))
+}
+
+/// Solana Pay transaction request, first step: what the wallet shows
+async fn approval_link_label(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+) -> Json<approval_links::LinkLabel> {
+    Json(state.approval_link_service.label(&proposal_id))
+}
+
+/// Solana Pay transaction request, second step: the transaction to sign
+async fn approval_link_transaction(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(proposal_id): Path<String>,
+    Query(query): Query<LinkQuery>,
+    Json(request): Json<TransactionRequest>,
+) -> Result<Json<approval_links::ApprovalTransaction>, UpgradeError> {
+    let transaction = state.approval_link_service
+        .request(&proposal_id, &query, &request)
+        .await?;
+
+    Ok(Json(transaction))
+}
+
+async fn import_upgrade_history(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(request): Json<BackfillRequest>,
+) -> Result<Json<backfill::BackfillReport>, UpgradeError> {
+    let report = state.history_backfill
+        .import(request)
+        .await?;
+
+    Ok(Json(report))
+}
+
+async fn run_rollback_drill(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    body: Option<Json<rollback::DrillRequest>>,
+) -> Result<Json<rollback::DrillReport>, UpgradeError> {
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let report = state.rollback_handler
+        .drill(request)
+        .await?;
+
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct RollbackDrillsQuery {
+    limit: Option<i64>,
+}
+
+async fn list_rollback_drills(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<RollbackDrillsQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let drills = state.rollback_handler
+        .list_drills(query.limit.unwrap_or(20).clamp(1, 100))
+        .await?;
+
+    Ok(Json(serde_json::json!({ "drills": drills })))
+}
+
+async fn get_multisig_members(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let activity = state.multisig_coordinator
+        .get_member_activity()
+        .await;
+    let directory = MemberDirectory::new(state.database.list_member_profiles().await?);
+    let members: Vec<_> = activity
+        .into_iter()
+        .map(|activity| directory.annotate(&activity.member.clone(), activity))
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "threshold": state.multisig_coordinator.get_threshold(),
+        "members": members,
+    })))
+}
+
+async fn list_member_profiles(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<Vec<MemberProfile>>, UpgradeError> {
+    let profiles = state.database
+        .list_member_profiles()
+        .await?;
+
+    Ok(Json(profiles))
+}
+
+async fn get_member_profile(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(member): Path<String>,
+) -> Result<Json<MemberProfile>, UpgradeError> {
+    let profile = MemberDirectory::new(state.database.list_member_profiles().await?)
+        .get(&member)
+        .cloned()
+        .ok_or_else(|| UpgradeError::InvalidRequest(format!("No profile for member {}", member)))?;
+
+    Ok(Json(profile))
+}
+
+async fn put_member_profile(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(member): Path<String>,
+    Json(req): Json<MemberProfileUpdate>,
+) -> Result<Json<MemberProfile>, UpgradeError> {
+    let profile = req.into_profile(&member, chrono::Utc::now().timestamp())?;
+    if !state.multisig_coordinator.get_members().await.contains(&member) {
+        return Err(UpgradeError::InvalidRequest(format!("{} is not a multisig member", member)));
+    }
+    state.database.upsert_member_profile(&profile).await?;
+    info!("Profile of member {} set to '{}'", member, profile.display_name());
+
+    Ok(Json(profile))
+}
+
+/// Profiles of former members can be removed too
+async fn delete_member_profile(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(member): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    if !state.database.delete_member_profile(&member).await? {
+        return Err(UpgradeError::InvalidRequest(format!("No profile for member {}", member)));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "removed",
+        "member": member,
+    })))
+}
+
+async fn get_multisig_config(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Json<serde_json::Value> {
+    let config = state.multisig_coordinator
+        .get_config_view()
+        .await;
+
+    Json(serde_json::json!(config))
+}
+
+async fn get_program_meta(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(program): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let meta = state.multisig_coordinator
+        .fetch_program_meta(&program)
+        .await?;
+
+    Ok(Json(serde_json::json!(meta)))
+}
+
+/// The digest an approver commits to, recomputed here so it can be checked
+/// against the proposal's fields before signing
+async fn get_approval_digest(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path((program, buffer)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    let buffer: solana_sdk::pubkey::Pubkey = buffer
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let commitment = state.multisig_coordinator
+        .fetch_proposal_commitment(&program, &buffer)
+        .await?;
+    let timelock_duration = state.multisig_coordinator
+        .fetch_timelock_duration()
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "verified": commitment.verify(timelock_duration),
+        "timelock_duration": timelock_duration,
+        "commitment": commitment,
+    })))
+}
+
+/// Feature flags the managed program reads from its upgrade-manager PDA
+async fn get_feature_flags(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(program): Path<String>,
+) -> Result<Json<feature_flags::OnchainFeatureFlags>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let flags = state.multisig_coordinator
+        .fetch_feature_flags(&program)
+        .await?;
+
+    Ok(Json(flags))
+}
+
+/// Put a flag change up for approval on chain; it applies after the
+/// threshold and timelock like an upgrade
+async fn propose_flag_change(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path((program, name)): Path<(String, String)>,
+    Json(req): Json<feature_flags::FlagChangeRequest>,
+) -> Result<Json<feature_flags::FlagChangeProposal>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let proposal = state.multisig_coordinator
+        .propose_flag_change(&program, &name, &req)
+        .await?;
+
+    Ok(Json(proposal))
+}
+
+async fn apply_flag_change(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path((program, proposal)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+    let proposal: solana_sdk::pubkey::Pubkey = proposal
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let signature = state.multisig_coordinator
+        .set_flag(&program, &proposal)
+        .await?;
+    let flags = state.multisig_coordinator
+        .fetch_feature_flags(&program)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "signature": signature.to_string(),
+        "flags": flags,
+    })))
+}
+
+/// Turn a kill-switch flag off immediately, without approvals or a timelock
+async fn trip_kill_switch(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path((program, name)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let signature = state.multisig_coordinator
+        .trip_kill_switch(&program, &name)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "program": program.to_string(),
+        "name": name,
+        "enabled": false,
+        "signature": signature.to_string(),
+    })))
+}
+
+/// Programs the multisig can pause through the emergency path
+async fn list_pausable_programs(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<emergency::OnchainPauseRegistry>, UpgradeError> {
+    let registry = state.emergency_pause.pausable_programs().await?;
+    Ok(Json(registry))
+}
+
+async fn get_emergency_pause(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(program): Path<String>,
+) -> Result<Json<emergency::EmergencyPauseStatus>, UpgradeError> {
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let status = state.emergency_pause.status(&program).await?;
+    Ok(Json(status))
+}
+
+/// Vote to pause a registered program; the vote that reaches the emergency
+/// quorum pauses it at once, without a timelock. A pause vote halts a live
+/// program, so it needs the security admin token.
+async fn emergency_pause(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(program): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<EmergencyPauseRequest>,
+) -> Result<Json<emergency::EmergencyPauseOutcome>, UpgradeError> {
+    let token = headers
+        .get("x-security-token")
+        .and_then(|v| v.to_str().ok());
+    security::verify_security_admin_token(token, &state.secrets)?;
+
+    let program: solana_sdk::pubkey::Pubkey = program
+        .parse()
+        .map_err(|_| UpgradeError::InvalidPubkey)?;
+
+    let outcome = state.emergency_pause.vote(&program, &req).await?;
+    Ok(Json(outcome))
+}
+
+#[derive(Deserialize, Default)]
+struct StartMigrationRequest {
+    #[serde(default)]
+    strategy: MigrationStrategy,
+    #[serde(flatten)]
+    options: MigrationStartOptions,
+}
+
+async fn start_migration(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    axum::Extension(principal): axum::Extension<ApiPrincipal>,
+    req: Option<Json<StartMigrationRequest>>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+    if req.options.force_takeover && !principal.allows(api_keys::Scope::Admin) {
+        return Err(UpgradeError::Forbidden(
+            "force_takeover requires the admin scope".to_string(),
+        ));
+    }
+    let migration_id = state.migration_manager
+        .start_with_options(req.strategy, req.options)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "migration_id": migration_id,
+        "strategy": req.strategy,
+        "status": "started"
+    })))
+}
+
+async fn get_migration_progress(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let progress = state.migration_manager
+        .progress()
+        .await?;
+
+    Ok(Json(progress))
+}
+
+async fn get_migration_rent_budget(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let budget = state.migration_manager
+        .rent_budget()
+        .await?;
+
+    Ok(Json(serde_json::json!(budget)))
+}
+
+async fn stream_migration_progress(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    // Subscribe first so no update falls between the snapshot and the live stream
+    let receiver = state.notification_service.subscribe();
+    let progress = state.migration_manager
+        .get_migration(&migration_id)
+        .await?;
+
+    let stream = sse::migration_progress_stream(receiver, state.migration_manager.clone(), progress);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn retry_migration(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.migration_manager
+        .retry(&migration_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "migration_id": migration_id,
+        "status": "retrying"
+    })))
+}
+
+async fn rollback_migration(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    state.migration_manager
+        .rollback(&migration_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "migration_id": migration_id,
+        "status": "rolled_back"
+    })))
+}
+
+async fn get_migration_costs(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let costs = state.migration_manager
+        .cost_breakdown(&migration_id)
+        .await?;
+
+    Ok(Json(costs))
+}
+
+/// Concurrency the migration settled on and its throughput per batch
+async fn get_migration_throughput(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let migration = state.migration_manager
+        .get_migration(&migration_id)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "migration_id": migration.migration_id,
+        "status": format!("{:?}", migration.status),
+        "concurrency": migration.concurrency,
+        "points": migration.throughput,
+    })))
+}
+
+async fn get_migration_coverage(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(migration_id): Path<String>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let coverage = state.migration_manager
+        .coverage(&migration_id)
+        .await?;
+
+    Ok(Json(coverage))
+}
+
+async fn list_artifacts(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let artifacts = state.artifact_registry
+        .list()
+        .await?;
+
+    Ok(Json(serde_json::json!(artifacts)))
+}
+
+async fn download_artifact(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Path(program_hash): Path<String>,
+) -> Result<impl IntoResponse, UpgradeError> {
+    let (artifact, binary) = state.artifact_registry
+        .download(&program_hash)
+        .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}-{}.so\"", artifact.program_name, artifact.program_hash),
+            ),
+            (header::ETAG, format!("W/\"{}\"", artifact.program_hash)),
+        ],
+        binary,
+    ))
+}
+
+async fn github_release_webhook(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<serde_json::Value>), UpgradeError> {
+    let handler = state.github_release_handler.clone();
+    handler.verify_signature(
+        &body,
+        headers.get("x-hub-signature-256").and_then(|v| v.to_str().ok()),
+    )?;
+
+    let event_type = headers.get("x-github-event").and_then(|v| v.to_str().ok());
+    if event_type != Some("release") {
+        return Ok((StatusCode::OK, Json(serde_json::json!({ "status": "ignored" }))));
+    }
+
+    let Some(event) = handler.accept(&body)? else {
+        return Ok((StatusCode::OK, Json(serde_json::json!({ "status": "ignored" }))));
+    };
+
+    // Building takes far longer than GitHub's webhook timeout
+    let tag = event.release.tag_name.clone();
+    tokio::spawn(async move {
+        match handler.process_release(event).await {
+            Ok(proposal_id) => spawn_policy_review(&state, &proposal_id),
+            Err(e) => tracing::error!("Failed to create proposal from release: {}", e),
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({
+        "status": "accepted",
+        "tag": tag,
+    }))))
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+    since_seq: Option<i64>,
+    limit: Option<i64>,
+    locale: Option<String>,
+}
+
+async fn list_events(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let limit = query.limit.unwrap_or(500).clamp(1, 1000);
+    let events = state.notification_service
+        .events_since(query.since_seq.unwrap_or(0), limit)
+        .await?;
+
+    let events: Vec<websocket::Event> = match query.locale.as_deref() {
+        Some(locale) => events
+            .into_iter()
+            .map(|mut event| {
+                event.message = state.notification_service.render(&event, Channel::Websocket, Some(locale));
+                event
+            })
+            .collect(),
+        None => events,
+    };
+
+    Ok(Json(serde_json::json!(events)))
+}
+
+async fn websocket_handler(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<EventsQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let notification_service = state.notification_service.clone();
+
+    ws.on_upgrade(move |socket| {
+        websocket::handle_websocket(socket, notification_service, query.since_seq, query.locale)
+    })
+}
+
+#[derive(Deserialize)]
+struct TemplatePreviewRequest {
+    #[serde(rename = "type")]
+    event_type: String,
+    channel: Option<Channel>,
+    locale: Option<String>,
+    proposal_id: Option<String>,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+async fn preview_notification_template(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Json(req): Json<TemplatePreviewRequest>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let templates = state.notification_service.templates();
+    let channel = req.channel.unwrap_or(Channel::Websocket);
+    let message = templates
+        .render(&req.event_type, channel, req.locale.as_deref(), req.proposal_id.as_deref(), &req.data)
+        .ok_or_else(|| {
+            UpgradeError::InvalidRequest(format!("No template for notification type {}", req.event_type))
+        })?;
+
+    Ok(Json(serde_json::json!({
+        "type": req.event_type,
+        "channel": channel,
+        "locale": req.locale.as_deref().unwrap_or(templates.default_locale()),
+        "message": message,
+    })))
+}
+
+async fn get_metrics(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Json<serde_json::Value> {
+    let mut dashboard = state.monitoring_service.get_dashboard_data().await;
+    dashboard["notifications"] = serde_json::json!(state.notification_service.stats());
+    dashboard["chain_clock"] = serde_json::json!({
+        "last_sample": state.chain_clock.last_sample(),
+        "alert_threshold_seconds": state.chain_clock.alert_threshold_seconds(),
+        "chain_time": state.chain_clock.now(),
+    });
+    Json(dashboard)
+}
+
+async fn get_metrics_history(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<serde_json::Value>, UpgradeError> {
+    let (range, points) = state.metrics_recorder
+        .history(&query)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "metric": range.metric,
+        "from": range.from,
+        "to": range.to,
+        "step": range.step,
+        "points": points
+            .iter()
+            .map(|point| serde_json::json!({ "timestamp": point.recorded_at, "value": point.value }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+/// Prometheus scrape endpoint, served outside `/v1` so scrape configs never change
+async fn prometheus_metrics(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> impl IntoResponse {
+    let body = state.monitoring_service.render_prometheus().await;
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+async fn get_alerts(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Json<serde_json::Value> {
+    let alerts = state.monitoring_service.get_alerts(50).await;
+    Json(serde_json::json!(alerts))
+}
+
+async fn get_slos(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Json<serde_json::Value> {
+    let slos = state.monitoring_service.get_slo_status().await;
+    Json(serde_json::json!(slos))
+}
+
+async fn get_health(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Json<serde_json::Value> {
+    let health = state.monitoring_service.check_health("system").await;
     Json(serde_json::json!({
         "status": format!("{:?}", health),
         "timestamp": std::time::SystemTime::now()
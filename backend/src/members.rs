@@ -0,0 +1,146 @@
+use crate::error::UpgradeError;
+use crate::reminders::ReminderContact;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+const MAX_NAME_LEN: usize = 64;
+const MAX_CONTACT_LEN: usize = 255;
+
+/// Human-friendly metadata for a multisig member (see `member_profiles` table)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemberProfile {
+    pub member: String,
+    pub name: String,
+    pub organization: Option<String>,
+    /// Shown to other members, e.g. an email address or Slack handle
+    pub contact: Option<String>,
+    /// Where approval reminders go: `websocket`, `slack:<webhook url>` or
+    /// `email:<address>`. Overrides `MEMBER_NOTIFICATION_CHANNELS`.
+    pub notification_channel: Option<String>,
+    pub reminders_enabled: bool,
+    pub updated_by: Option<String>,
+    pub updated_at: i64,
+}
+
+impl MemberProfile {
+    /// `"Alice (Ops)"`, or just the name without an organization
+    pub fn display_name(&self) -> String {
+        match &self.organization {
+            Some(organization) => format!("{} ({})", self.name, organization),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn reminder_contact(&self) -> Option<ReminderContact> {
+        self.notification_channel.as_deref().and_then(ReminderContact::parse)
+    }
+}
+
+/// Request body for `PUT /multisig/members/:member/profile`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemberProfileUpdate {
+    pub name: String,
+    pub organization: Option<String>,
+    pub contact: Option<String>,
+    pub notification_channel: Option<String>,
+    #[serde(default = "reminders_enabled_default")]
+    pub reminders_enabled: bool,
+    pub updated_by: Option<String>,
+}
+
+fn reminders_enabled_default() -> bool {
+    true
+}
+
+impl MemberProfileUpdate {
+    pub fn into_profile(self, member: &str, now: i64) -> Result<MemberProfile, UpgradeError> {
+        member.parse::<Pubkey>().map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        let trimmed = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let name = self.name.trim().to_string();
+        let organization = trimmed(self.organization);
+        let contact = trimmed(self.contact);
+        let notification_channel = trimmed(self.notification_channel);
+
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "name must be 1 to {} characters",
+                MAX_NAME_LEN
+            )));
+        }
+        if organization.as_ref().is_some_and(|o| o.chars().count() > MAX_NAME_LEN) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "organization must be at most {} characters",
+                MAX_NAME_LEN
+            )));
+        }
+        if contact.as_ref().is_some_and(|c| c.chars().count() > MAX_CONTACT_LEN) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "contact must be at most {} characters",
+                MAX_CONTACT_LEN
+            )));
+        }
+        if let Some(channel) = &notification_channel {
+            if ReminderContact::parse(channel).is_none() {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Invalid notification_channel '{}', expected websocket, slack:<url> or email:<address>",
+                    channel
+                )));
+            }
+        }
+
+        Ok(MemberProfile {
+            member: member.to_string(),
+            name,
+            organization,
+            contact,
+            notification_channel,
+            reminders_enabled: self.reminders_enabled,
+            updated_by: self.updated_by,
+            updated_at: now,
+        })
+    }
+}
+
+/// `T` with the member's display name and profile alongside it
+#[derive(Debug, Clone, Serialize)]
+pub struct WithProfile<T> {
+    #[serde(flatten)]
+    pub inner: T,
+    pub display_name: String,
+    pub profile: Option<MemberProfile>,
+}
+
+/// Member profiles by pubkey, for labelling member and approval views
+#[derive(Debug, Clone, Default)]
+pub struct MemberDirectory {
+    profiles: HashMap<String, MemberProfile>,
+}
+
+impl MemberDirectory {
+    pub fn new(profiles: Vec<MemberProfile>) -> Self {
+        Self {
+            profiles: profiles.into_iter().map(|p| (p.member.clone(), p)).collect(),
+        }
+    }
+
+    pub fn get(&self, member: &str) -> Option<&MemberProfile> {
+        self.profiles.get(member)
+    }
+
+    /// The profile's display name, or the pubkey for members without one
+    pub fn display_name(&self, member: &str) -> String {
+        self.get(member)
+            .map(MemberProfile::display_name)
+            .unwrap_or_else(|| member.to_string())
+    }
+
+    pub fn annotate<T>(&self, member: &str, inner: T) -> WithProfile<T> {
+        WithProfile {
+            inner,
+            display_name: self.display_name(member),
+            profile: self.get(member).cloned(),
+        }
+    }
+}
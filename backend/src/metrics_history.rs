@@ -0,0 +1,193 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::monitoring::{Metrics, MonitoringService};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Series written on every snapshot. The first four count activity since the
+/// previous snapshot; the `_total` series are cumulative since startup.
+pub const METRIC_NAMES: &[&str] = &[
+    "proposals_created",
+    "proposals_executed",
+    "approvals",
+    "approval_latency_seconds",
+    "proposals_created_total",
+    "proposals_executed_total",
+    "proposals_cancelled_total",
+    "migrations_completed_total",
+    "rollbacks_initiated_total",
+    "http_requests_total",
+    "http_errors_total",
+];
+
+/// Most points one history query returns; longer ranges are averaged into wider buckets
+pub const MAX_HISTORY_POINTS: i64 = 1000;
+
+/// Default range of a history query without `from`
+pub const DEFAULT_HISTORY_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricPoint {
+    pub metric: String,
+    pub value: f64,
+    pub recorded_at: i64,
+}
+
+/// Proposal activity between two snapshots, read from the proposal tables
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowStats {
+    pub proposals_created: i64,
+    pub proposals_executed: i64,
+    pub approvals: i64,
+    /// Average time from proposal to each approval cast in the window
+    pub average_approval_latency_seconds: Option<f64>,
+}
+
+/// Points for one snapshot. A window without approvals has no latency point,
+/// so graphs show a gap rather than a drop to zero.
+pub fn snapshot_points(
+    metrics: &Metrics,
+    requests: u64,
+    errors: u64,
+    window: &WindowStats,
+    now: i64,
+) -> Vec<MetricPoint> {
+    let mut values = vec![
+        ("proposals_created", window.proposals_created as f64),
+        ("proposals_executed", window.proposals_executed as f64),
+        ("approvals", window.approvals as f64),
+        ("proposals_created_total", metrics.proposals_created as f64),
+        ("proposals_executed_total", metrics.proposals_executed as f64),
+        ("proposals_cancelled_total", metrics.proposals_cancelled as f64),
+        ("migrations_completed_total", metrics.migrations_completed as f64),
+        ("rollbacks_initiated_total", metrics.rollbacks_initiated as f64),
+        ("http_requests_total", requests as f64),
+        ("http_errors_total", errors as f64),
+    ];
+    if let Some(latency) = window.average_approval_latency_seconds {
+        values.push(("approval_latency_seconds", latency));
+    }
+
+    values
+        .into_iter()
+        .map(|(metric, value)| MetricPoint {
+            metric: metric.to_string(),
+            value,
+            recorded_at: now,
+        })
+        .collect()
+}
+
+/// Query string of `GET /monitoring/metrics/history`; times are unix seconds
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryQuery {
+    pub metric: String,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Bucket width in seconds; widened as needed to stay under `MAX_HISTORY_POINTS`
+    pub step: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HistoryRange {
+    pub metric: String,
+    pub from: i64,
+    pub to: i64,
+    pub step: i64,
+}
+
+impl HistoryQuery {
+    /// Fill in defaults and pick a bucket width no finer than the snapshot interval
+    pub fn resolve(&self, now: i64, snapshot_interval: i64) -> Result<HistoryRange, UpgradeError> {
+        if !METRIC_NAMES.contains(&self.metric.as_str()) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Unknown metric '{}', expected one of {}",
+                self.metric,
+                METRIC_NAMES.join(", ")
+            )));
+        }
+
+        let to = self.to.unwrap_or(now);
+        let from = self.from.unwrap_or(to - DEFAULT_HISTORY_SECONDS);
+        if from >= to {
+            return Err(UpgradeError::InvalidRequest("from must be before to".to_string()));
+        }
+
+        let widest = (to - from + MAX_HISTORY_POINTS - 1) / MAX_HISTORY_POINTS;
+        let step = self.step.unwrap_or(0).max(snapshot_interval).max(widest).max(1);
+
+        Ok(HistoryRange {
+            metric: self.metric.clone(),
+            from,
+            to,
+            step,
+        })
+    }
+}
+
+/// Persists a snapshot of the service metrics every
+/// `METRICS_SNAPSHOT_INTERVAL_SECS` (default 300) to `metrics_timeseries`,
+/// keeping `METRICS_RETENTION_DAYS` (default 400) of history
+pub struct MetricsRecorder {
+    database: Arc<Database>,
+    monitoring: Arc<MonitoringService>,
+    interval_seconds: i64,
+    retention_days: i64,
+}
+
+impl MetricsRecorder {
+    pub fn from_env(database: Arc<Database>, monitoring: Arc<MonitoringService>) -> Self {
+        let interval_seconds = std::env::var("METRICS_SNAPSHOT_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let retention_days = std::env::var("METRICS_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(400);
+
+        Self {
+            database,
+            monitoring,
+            interval_seconds,
+            retention_days,
+        }
+    }
+
+    pub fn interval_seconds(&self) -> i64 {
+        self.interval_seconds
+    }
+
+    /// Record activity in `(since, now]` and the current counters, then drop
+    /// points past the retention period. Returns the number of points written.
+    pub async fn snapshot(&self, since: i64, now: i64) -> Result<usize, UpgradeError> {
+        let metrics = self.monitoring.get_metrics().await;
+        let routes = self.monitoring.request_metrics().snapshot();
+        let requests = routes.values().map(|route| route.requests).sum();
+        let errors = routes.values().map(|route| route.errors()).sum();
+        let window = self.database.metrics_window_stats(since, now).await?;
+
+        let points = snapshot_points(&metrics, requests, errors, &window, now);
+        self.database.insert_metric_points(&points).await?;
+
+        let pruned = self
+            .database
+            .prune_metric_points(now - self.retention_days * 24 * 60 * 60)
+            .await?;
+        if pruned > 0 {
+            tracing::debug!("Pruned {} metric points past retention", pruned);
+        }
+
+        Ok(points.len())
+    }
+
+    pub async fn history(&self, query: &HistoryQuery) -> Result<(HistoryRange, Vec<MetricPoint>), UpgradeError> {
+        let range = query.resolve(chrono::Utc::now().timestamp(), self.interval_seconds)?;
+        let points = self
+            .database
+            .metric_history(&range.metric, range.from, range.to, range.step)
+            .await?;
+
+        Ok((range, points))
+    }
+}
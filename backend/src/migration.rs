@@ -1,33 +1,304 @@
+use crate::database::Database;
 use crate::error::UpgradeError;
+use crate::faucet::{AirdropFunder, LAMPORTS_PER_SIGNATURE};
+use crate::invariants::{InvariantPhase, InvariantRegistry};
+use crate::monitoring::{AlertLevel, MonitoringService};
+use crate::rollback::RollbackHandler;
+use crate::sampling::{AccountBackup, SampleVerification, SampleVerifier, SamplingConfig};
+use crate::throughput::{
+    is_rate_limit_error, BatchOutcome, ConcurrencyController, ThroughputConfig, ThroughputPoint,
+    MAX_THROUGHPUT_POINTS,
+};
+use crate::websocket::NotificationService;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
+use tokio::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MigrationProgress {
     pub migration_id: String,
+    pub strategy: MigrationStrategy,
     pub total_accounts: usize,
     pub migrated_accounts: usize,
     pub failed_accounts: usize,
     pub status: MigrationStatus,
     pub started_at: i64,
     pub completed_at: Option<i64>,
+    pub transactions_sent: u64,
+    pub compute_units_consumed: u64,
+    pub fees_paid_lamports: u64,
+    /// Progress of each account type, in dependency order
+    pub account_types: Vec<AccountTypeProgress>,
+    /// Read-back check of a sample of migrated accounts, once a batch migration finishes
+    #[serde(default)]
+    pub sample_verification: Option<SampleVerification>,
+    /// Accounts currently migrated in parallel, tuned from RPC errors and confirmation times
+    #[serde(default)]
+    pub concurrency: usize,
+    /// One point per parallel batch, the most recent `MAX_THROUGHPUT_POINTS`
+    #[serde(default)]
+    pub throughput: Vec<ThroughputPoint>,
+}
+
+impl MigrationProgress {
+    /// Progress as served by `/migration/progress` and sent in notifications
+    pub fn snapshot(&self) -> serde_json::Value {
+        let progress_percent = if self.total_accounts > 0 {
+            (self.migrated_accounts as f64 / self.total_accounts as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        serde_json::json!({
+            "migration_id": self.migration_id,
+            "strategy": self.strategy,
+            "status": format!("{:?}", self.status),
+            "progress_percent": progress_percent,
+            "migrated_accounts": self.migrated_accounts,
+            "total_accounts": self.total_accounts,
+            "failed_accounts": self.failed_accounts,
+            "started_at": self.started_at,
+            "completed_at": self.completed_at,
+            "compute_units_consumed": self.compute_units_consumed,
+            "fees_paid_lamports": self.fees_paid_lamports,
+            "account_types": self.account_types,
+            "sample_verification": self.sample_verification,
+            "concurrency": self.concurrency,
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.status,
+            MigrationStatus::Completed | MigrationStatus::Failed | MigrationStatus::RolledBack
+        )
+    }
+}
+
+/// Progress of one account type within a batch migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AccountTypeProgress {
+    pub account_type: String,
+    /// Account types that must finish migrating before this one starts
+    pub depends_on: Vec<String>,
+    pub total_accounts: usize,
+    pub migrated_accounts: usize,
+    pub failed_accounts: usize,
+    pub status: MigrationStatus,
+    pub started_at: Option<i64>,
+    pub completed_at: Option<i64>,
+}
+
+/// Counters of a batch migration, from the upgrade-manager `migration_state` PDA
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OnchainMigrationState {
+    pub authority: String,
+    pub total: u64,
+    /// Counted by `migrate_account` itself
+    pub migrated: u64,
+    /// Reported by the migration's authority
+    pub failed: u64,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+impl OnchainMigrationState {
+    /// Decode the Anchor account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let invalid = || UpgradeError::SolanaError("Invalid migration_state account data".to_string());
+        // migration_id, authority, total, migrated, failed, started_at, updated_at
+        let body = data.get(8..8 + 16 + 32 + 8 * 5).ok_or_else(invalid)?;
+        let u64_at = |offset: usize| u64::from_le_bytes(body[offset..offset + 8].try_into().unwrap());
+
+        Ok(Self {
+            authority: Pubkey::new_from_array(body[16..48].try_into().unwrap()).to_string(),
+            total: u64_at(48),
+            migrated: u64_at(56),
+            failed: u64_at(64),
+            started_at: i64::from_le_bytes(body[72..80].try_into().unwrap()),
+            updated_at: i64::from_le_bytes(body[80..88].try_into().unwrap()),
+        })
+    }
+}
+
+/// Address of the `migration_state` PDA of a migration, seeded with the 16
+/// bytes of its UUID. `None` for IDs that are not UUIDs.
+pub fn migration_state_address(upgrade_manager: &Pubkey, migration_id: &str) -> Option<Pubkey> {
+    let id = uuid::Uuid::parse_str(migration_id).ok()?;
+    Some(Pubkey::find_program_address(&[b"migration_state", id.as_bytes()], upgrade_manager).0)
+}
+
+/// Counters where the backend's progress and the chain disagree
+pub fn reconcile_progress(progress: &MigrationProgress, onchain: &OnchainMigrationState) -> Vec<String> {
+    [
+        ("total", progress.total_accounts as u64, onchain.total),
+        ("migrated", progress.migrated_accounts as u64, onchain.migrated),
+        ("failed", progress.failed_accounts as u64, onchain.failed),
+    ]
+    .into_iter()
+    .filter(|(_, backend, onchain)| backend != onchain)
+    .map(|(counter, backend, onchain)| format!("{}: backend={} onchain={}", counter, backend, onchain))
+    .collect()
+}
+
+/// Compute units and fee charged for a single migration transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionCost {
+    pub signature: String,
+    pub compute_units: u64,
+    pub fee_lamports: u64,
+}
+
+/// Rent needed for one account type to grow into its migrated layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeRentBudget {
+    pub account_type: String,
+    pub accounts: usize,
+    pub current_bytes: u64,
+    pub migrated_bytes: u64,
+    /// Lamports to lift every account to rent exemption at its migrated size
+    pub additional_rent_lamports: u64,
+}
+
+impl TypeRentBudget {
+    /// `accounts` are the `(data_len, lamports)` of each existing account
+    pub fn estimate(
+        account_type: &str,
+        migrator: &(dyn AccountMigrator + Send + Sync),
+        accounts: &[(usize, u64)],
+        rent: &Rent,
+    ) -> Self {
+        let mut budget = Self {
+            account_type: account_type.to_string(),
+            accounts: accounts.len(),
+            current_bytes: 0,
+            migrated_bytes: 0,
+            additional_rent_lamports: 0,
+        };
+
+        for &(data_len, lamports) in accounts {
+            let migrated_len = migrator.migrated_size(data_len);
+            budget.current_bytes += data_len as u64;
+            budget.migrated_bytes += migrated_len as u64;
+            budget.additional_rent_lamports += rent.minimum_balance(migrated_len).saturating_sub(lamports);
+        }
+
+        budget
+    }
+}
+
+/// What a batch migration will cost its funding source, checked before it starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RentBudget {
+    pub account_types: Vec<TypeRentBudget>,
+    pub additional_rent_lamports: u64,
+    /// One signature per migrated account
+    pub fee_lamports: u64,
+    pub required_lamports: u64,
+    /// Fee payer that funds reallocations; `None` when none is configured
+    pub funding_source: Option<String>,
+    pub funding_balance: Option<u64>,
+    pub sufficient: bool,
+}
+
+impl RentBudget {
+    pub fn new(account_types: Vec<TypeRentBudget>, funding_source: Option<Pubkey>, funding_balance: Option<u64>) -> Self {
+        let additional_rent_lamports = account_types.iter().map(|t| t.additional_rent_lamports).sum();
+        let accounts: usize = account_types.iter().map(|t| t.accounts).sum();
+        let fee_lamports = accounts as u64 * LAMPORTS_PER_SIGNATURE;
+
+        let mut budget = Self {
+            account_types,
+            additional_rent_lamports,
+            fee_lamports,
+            required_lamports: additional_rent_lamports + fee_lamports,
+            funding_source: funding_source.map(|source| source.to_string()),
+            funding_balance: None,
+            sufficient: false,
+        };
+        budget.set_funding_balance(funding_balance);
+        budget
+    }
+
+    /// Record the funding source's balance, e.g. after a faucet top-up
+    pub fn set_funding_balance(&mut self, balance: Option<u64>) {
+        self.funding_balance = balance;
+        self.sufficient = self.required_lamports == 0
+            || balance.is_some_and(|balance| balance >= self.required_lamports);
+    }
+}
+
+/// Options for starting a migration
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MigrationStartOptions {
+    /// Start a batch migration even if its rent budget is not covered
+    #[serde(default)]
+    pub allow_underfunded: bool,
+    /// Take the migration lock from a batch migration that is still running
+    /// (or left the lock behind), marking that migration failed
+    #[serde(default)]
+    pub force_takeover: bool,
+}
+
+/// How accounts get migrated after an upgrade
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationStrategy {
+    /// Backend migrates every account in one background job
+    #[default]
+    Batch,
+    /// The program calls `migrate_if_needed` on first access; the backend only tracks coverage
+    Lazy,
+}
+
+/// Percent of accounts migrated at a point in time (lazy migrations)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveragePoint {
+    pub timestamp: i64,
+    pub migrated_accounts: usize,
+    pub percent_migrated: f64,
+}
+
+#[derive(Default)]
+struct LazyCoverage {
+    pending: HashSet<Pubkey>,
+    history: Vec<CoveragePoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum MigrationStatus {
     NotStarted,
     InProgress,
     Completed,
     Failed,
+    /// Not run because an account type it depends on failed
+    Skipped,
+    /// Reverted with `rollback`
+    RolledBack,
 }
 
 /// Account data transformation for migration
 pub trait AccountMigrator {
     fn migrate(&self, old_data: &[u8]) -> Result<Vec<u8>, MigrationError>;
     fn verify(&self, old_data: &[u8], new_data: &[u8]) -> Result<bool, MigrationError>;
+
+    /// Data length after migrating an account of `old_len` bytes, for rent budgeting
+    fn migrated_size(&self, old_len: usize) -> usize {
+        old_len
+    }
 }
 
 #[derive(Debug)]
@@ -36,6 +307,19 @@ pub enum MigrationError {
     TransformationFailed,
     VerificationFailed,
     AccountNotFound,
+    RpcFailed,
+    /// The RPC provider throttled the request (HTTP 429)
+    RateLimited,
+}
+
+impl MigrationError {
+    fn from_rpc(error: &solana_client::client_error::ClientError) -> Self {
+        if is_rate_limit_error(&error.to_string()) {
+            MigrationError::RateLimited
+        } else {
+            MigrationError::RpcFailed
+        }
+    }
 }
 
 impl From<MigrationError> for UpgradeError {
@@ -44,6 +328,41 @@ impl From<MigrationError> for UpgradeError {
     }
 }
 
+/// Operations on account migrations, implemented by `MigrationManager` and by
+/// `MockMigration` for tests that should not need RPC or real accounts
+#[async_trait]
+pub trait Migration: Send + Sync {
+    async fn start(&self, strategy: MigrationStrategy) -> Result<String, UpgradeError> {
+        self.start_with_options(strategy, MigrationStartOptions::default()).await
+    }
+
+    /// Batch migrations are refused when the rent budget is not covered,
+    /// unless `allow_underfunded` is set
+    async fn start_with_options(
+        &self,
+        strategy: MigrationStrategy,
+        options: MigrationStartOptions,
+    ) -> Result<String, UpgradeError>;
+
+    /// Rent and fees the next batch migration needs, against the funding source's balance
+    async fn rent_budget(&self) -> Result<RentBudget, UpgradeError>;
+
+    /// Progress of the latest migration
+    async fn progress(&self) -> Result<serde_json::Value, UpgradeError>;
+
+    async fn get_migration(&self, migration_id: &str) -> Result<MigrationProgress, UpgradeError>;
+
+    async fn retry(&self, migration_id: &str) -> Result<(), UpgradeError>;
+
+    async fn rollback(&self, migration_id: &str) -> Result<(), UpgradeError>;
+
+    async fn cost_breakdown(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError>;
+
+    async fn coverage(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError>;
+
+    async fn record_lazy_migrations(&self, accounts: &[Pubkey]);
+}
+
 /// Example: Migrate user account from v1 to v2
 pub struct UserAccountMigrator {
     old_version: u32,
@@ -101,110 +420,618 @@ impl AccountMigrator for UserAccountMigrator {
         
         Ok(true)
     }
+
+    fn migrated_size(&self, old_len: usize) -> usize {
+        // last_active (i64) and the version marker (u32)
+        old_len + 8 + 4
+    }
+}
+
+/// Migrator for an account type, shared with the per-type migration tasks
+pub type SharedMigrator = Arc<dyn AccountMigrator + Send + Sync>;
+
+/// Lets only one batch migration per program run at a time. Backed by the
+/// `migration_locks` table when there is a database, so it holds across
+/// service instances, and by process memory otherwise.
+#[derive(Clone)]
+pub struct MigrationLock {
+    program: String,
+    database: Option<Arc<Database>>,
+    local: Arc<Mutex<Option<String>>>,
+}
+
+impl MigrationLock {
+    pub fn new(program: impl Into<String>, database: Option<Arc<Database>>) -> Self {
+        Self {
+            program: program.into(),
+            database,
+            local: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Claim the lock for `migration_id`. Fails with the running migration's
+    /// id unless `force_takeover` is set; returns the migration taken over from.
+    pub async fn acquire(&self, migration_id: &str, force_takeover: bool) -> Result<Option<String>, UpgradeError> {
+        let holder = match &self.database {
+            Some(database) => {
+                database
+                    .acquire_migration_lock(&self.program, migration_id, force_takeover)
+                    .await?
+            }
+            None => {
+                let mut local = self.local.lock().await;
+                let holder = local.clone().filter(|holder| holder != migration_id);
+                if holder.is_none() || force_takeover {
+                    *local = Some(migration_id.to_string());
+                }
+                holder
+            }
+        };
+
+        match holder {
+            Some(holder) if !force_takeover => Err(UpgradeError::MigrationLocked {
+                program: self.program.clone(),
+                migration_id: holder,
+            }),
+            holder => Ok(holder),
+        }
+    }
+
+    /// Release the lock if `migration_id` still holds it
+    pub async fn release(&self, migration_id: &str) {
+        match &self.database {
+            Some(database) => {
+                if let Err(e) = database.release_migration_lock(&self.program, migration_id).await {
+                    tracing::warn!("Failed to release migration lock held by {}: {}", migration_id, e);
+                }
+            }
+            None => {
+                let mut local = self.local.lock().await;
+                if local.as_deref() == Some(migration_id) {
+                    *local = None;
+                }
+            }
+        }
+    }
 }
 
 pub struct MigrationManager {
     migrations: Arc<Mutex<Vec<MigrationProgress>>>,
-    rpc_client: Option<RpcClient>,
-    migrators: Vec<Box<dyn AccountMigrator + Send + Sync>>,
+    rpc_client: Option<Arc<RpcClient>>,
+    migrators: HashMap<String, SharedMigrator>,
+    /// account type -> account types that must be migrated first
+    dependencies: HashMap<String, Vec<String>>,
+    database: Option<Arc<Database>>,
+    lazy_coverage: Arc<Mutex<HashMap<String, LazyCoverage>>>,
+    invariants: Option<Arc<InvariantRegistry>>,
+    notifications: Option<Arc<NotificationService>>,
+    rollback_handler: Option<Arc<RollbackHandler>>,
+    managed_program: String,
+    upgrade_manager_program: Pubkey,
+    /// Faucet and the fee payer it keeps funded for batch migrations
+    funding: Option<(Arc<AirdropFunder>, Pubkey)>,
+    lock: MigrationLock,
+    monitoring: Option<Arc<MonitoringService>>,
+    sampling: SamplingConfig,
+    throughput: ThroughputConfig,
 }
 
+/// A progress notification is sent every this many accounts per account type
+const PROGRESS_NOTIFY_INTERVAL: usize = 100;
+
+/// Times an account is retried after being rate limited before it counts as failed
+const MAX_RATE_LIMITED_ATTEMPTS: u32 = 5;
+
+/// Pause after a batch the RPC provider throttled
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
 impl MigrationManager {
     pub async fn new() -> Result<Self, UpgradeError> {
         let rpc_url = std::env::var("SOLANA_RPC_URL")
             .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
-        let rpc_client = Some(RpcClient::new(rpc_url));
+        let rpc_client = Some(Arc::new(RpcClient::new(rpc_url)));
+
+        let mut migrators: HashMap<String, SharedMigrator> = HashMap::new();
+        migrators.insert("user_account".to_string(), Arc::new(UserAccountMigrator::new()));
+
+        // "vault>positions,vault>orders" migrates vault before positions and orders
+        let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
+        if let Ok(spec) = std::env::var("MIGRATION_DEPENDENCIES") {
+            for edge in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (before, after) = edge.split_once('>').ok_or_else(|| {
+                    UpgradeError::MigrationError(format!("Invalid migration dependency: {}", edge))
+                })?;
+                dependencies
+                    .entry(after.trim().to_string())
+                    .or_default()
+                    .push(before.trim().to_string());
+            }
+        }
 
-        let mut migrators: Vec<Box<dyn AccountMigrator + Send + Sync>> = Vec::new();
-        migrators.push(Box::new(UserAccountMigrator::new()));
+        let managed_program = std::env::var("MANAGED_PROGRAM_ID")
+            .unwrap_or_else(|_| "program_id".to_string());
+
+        // Batch migrations keep their counters in upgrade-manager migration_state PDAs
+        let upgrade_manager_program = std::env::var("UPGRADE_MANAGER_PROGRAM_ID")
+            .ok()
+            .and_then(|s| Pubkey::from_str(&s).ok())
+            .unwrap_or_else(|| {
+                Pubkey::from_str("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS").unwrap()
+            });
 
         Ok(Self {
             migrations: Arc::new(Mutex::new(Vec::new())),
             rpc_client,
             migrators,
+            dependencies,
+            database: None,
+            lazy_coverage: Arc::new(Mutex::new(HashMap::new())),
+            invariants: None,
+            notifications: None,
+            rollback_handler: None,
+            lock: MigrationLock::new(managed_program.clone(), None),
+            managed_program,
+            upgrade_manager_program,
+            funding: None,
+            monitoring: None,
+            sampling: SamplingConfig::from_env(),
+            throughput: ThroughputConfig::from_env(),
         })
     }
 
+    /// Publish progress snapshots as `migration_progress` notifications
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Make sure `payer` can cover a batch migration's fees before it starts,
+    /// airdropping on devnet/testnet
+    pub fn with_faucet(mut self, faucet: Arc<AirdropFunder>, payer: Pubkey) -> Self {
+        self.funding = Some((faucet, payer));
+        self
+    }
+
+    /// Alert when sampled verification fails a migration
+    pub fn with_monitoring(mut self, monitoring: Arc<MonitoringService>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Roll back the managed program when a migration is rolled back
+    pub fn with_rollback(mut self, rollback_handler: Arc<RollbackHandler>) -> Self {
+        self.rollback_handler = Some(rollback_handler);
+        self
+    }
+
+    /// Register (or replace) the migrator for an account type
+    pub fn with_migrator(mut self, account_type: &str, migrator: SharedMigrator) -> Self {
+        self.migrators.insert(account_type.to_string(), migrator);
+        self
+    }
+
+    /// Require `before` to finish migrating before `after` starts
+    pub fn with_dependency(mut self, before: &str, after: &str) -> Self {
+        self.dependencies
+            .entry(after.to_string())
+            .or_default()
+            .push(before.to_string());
+        self
+    }
+
+    /// Account types in an order where every type comes after its dependencies
+    fn migration_order(&self) -> Result<Vec<String>, UpgradeError> {
+        let mut remaining: HashMap<&str, usize> = HashMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for account_type in self.migrators.keys() {
+            let deps = self.dependencies.get(account_type).map(Vec::as_slice).unwrap_or_default();
+            for dep in deps {
+                if !self.migrators.contains_key(dep) {
+                    return Err(UpgradeError::MigrationError(format!(
+                        "{} depends on unknown account type {}",
+                        account_type, dep
+                    )));
+                }
+                dependents.entry(dep.as_str()).or_default().push(account_type);
+            }
+            remaining.insert(account_type, deps.len());
+        }
+
+        let mut ready: Vec<&str> = remaining
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(name, _)| *name)
+            .collect();
+        ready.sort_unstable();
+        let mut queue: VecDeque<&str> = ready.into();
+
+        let mut order = Vec::with_capacity(self.migrators.len());
+        while let Some(account_type) = queue.pop_front() {
+            order.push(account_type.to_string());
+            for dependent in dependents.get(account_type).into_iter().flatten() {
+                let count = remaining.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    queue.push_back(*dependent);
+                }
+            }
+        }
+
+        if order.len() != self.migrators.len() {
+            return Err(UpgradeError::MigrationError(
+                "Migration dependencies contain a cycle".to_string(),
+            ));
+        }
+
+        Ok(order)
+    }
+
+    /// Persist per-transaction migration costs to the database
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.lock = MigrationLock::new(self.managed_program.clone(), Some(database.clone()));
+        self.database = Some(database);
+        self
+    }
+
+    /// Run post-migration invariant checks once a batch migration finishes
+    pub fn with_invariants(mut self, invariants: Arc<InvariantRegistry>) -> Self {
+        self.invariants = Some(invariants);
+        self
+    }
+
     pub async fn start_migration(&self) -> Result<String, UpgradeError> {
+        self.start_migration_with_strategy(MigrationStrategy::Batch).await
+    }
+
+    pub async fn start_migration_with_strategy(
+        &self,
+        strategy: MigrationStrategy,
+    ) -> Result<String, UpgradeError> {
+        self.start_migration_with_options(strategy, MigrationStartOptions::default()).await
+    }
+
+    pub async fn start_migration_with_options(
+        &self,
+        strategy: MigrationStrategy,
+        options: MigrationStartOptions,
+    ) -> Result<String, UpgradeError> {
         let migration_id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().timestamp();
 
+        let order = self.migration_order()?;
+
         // Identify accounts to migrate
-        let accounts_to_migrate = self.identify_accounts_to_migrate().await?;
+        let accounts_by_type = self.identify_accounts_to_migrate().await?;
+
+        // Lazy migrations reallocate on access, paid by whoever touches the account
+        if strategy != MigrationStrategy::Lazy {
+            self.check_rent_budget(&accounts_by_type, options).await?;
+            // Lazy migrations send nothing themselves, so only batch runs can collide
+            if let Some(previous) = self.lock.acquire(&migration_id, options.force_takeover).await? {
+                self.take_over(&previous).await;
+            }
+        }
+
+        let account_types: Vec<AccountTypeProgress> = order
+            .iter()
+            .map(|account_type| AccountTypeProgress {
+                account_type: account_type.clone(),
+                depends_on: self.dependencies.get(account_type).cloned().unwrap_or_default(),
+                total_accounts: accounts_by_type.get(account_type).map_or(0, Vec::len),
+                migrated_accounts: 0,
+                failed_accounts: 0,
+                status: MigrationStatus::NotStarted,
+                started_at: None,
+                completed_at: None,
+            })
+            .collect();
 
         let migration = MigrationProgress {
             migration_id: migration_id.clone(),
-            total_accounts: accounts_to_migrate.len(),
+            strategy,
+            total_accounts: account_types.iter().map(|t| t.total_accounts).sum(),
             migrated_accounts: 0,
             failed_accounts: 0,
             status: MigrationStatus::InProgress,
             started_at: now,
             completed_at: None,
+            transactions_sent: 0,
+            compute_units_consumed: 0,
+            fees_paid_lamports: 0,
+            account_types,
+            sample_verification: None,
+            concurrency: self.throughput.initial_concurrency,
+            throughput: Vec::new(),
         };
 
         let mut migrations = self.migrations.lock().await;
         migrations.push(migration);
+        drop(migrations);
 
-        // Start background migration task
-        let migrations_clone = self.migrations.clone();
-        let accounts_clone = accounts_to_migrate.clone();
-        let migrators_clone = self.migrators.clone();
-        
-        tokio::spawn(async move {
-            Self::migrate_accounts_batch(
-                &migration_id,
-                accounts_clone,
-                migrations_clone,
-                migrators_clone,
-            ).await;
-        });
+        if strategy == MigrationStrategy::Lazy {
+            // Nothing to run: accounts migrate on access and the indexer reports them
+            let coverage = LazyCoverage {
+                pending: accounts_by_type.into_values().flatten().collect(),
+                history: Vec::new(),
+            };
+            self.lazy_coverage.lock().await.insert(migration_id.clone(), coverage);
+            return Ok(migration_id);
+        }
+
+        self.spawn_batch(&migration_id, &order, accounts_by_type);
 
         Ok(migration_id)
     }
 
-    async fn migrate_accounts_batch(
+    /// Run the given account types (in dependency order) for a batch migration,
+    /// then settle the migration's final status
+    fn spawn_batch(
+        &self,
         migration_id: &str,
-        accounts: Vec<Pubkey>,
-        migrations: Arc<Mutex<Vec<MigrationProgress>>>,
-        migrators: Vec<Box<dyn AccountMigrator + Send + Sync>>,
+        order: &[String],
+        mut accounts_by_type: HashMap<String, Vec<Pubkey>>,
     ) {
-        for account in accounts {
-            match Self::migrate_single_account(&account, &migrators).await {
-                Ok(_) => {
-                    let mut migrations_guard = migrations.lock().await;
-                    if let Some(migration) = migrations_guard.iter_mut()
-                        .find(|m| m.migration_id == migration_id) {
-                        migration.migrated_accounts += 1;
+        // One task per account type; each waits only on its own dependencies,
+        // so independent types migrate in parallel. They share one controller,
+        // since they share the RPC provider's limits.
+        let controller = Arc::new(Mutex::new(ConcurrencyController::new(self.throughput)));
+        let mut finished: HashMap<String, watch::Receiver<Option<bool>>> = HashMap::new();
+        let mut tasks = Vec::with_capacity(order.len());
+        for account_type in order {
+            let (done_tx, done_rx) = watch::channel(None);
+            // Dependencies outside this run already completed
+            let dependencies: Vec<_> = self
+                .dependencies
+                .get(account_type)
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| finished.get(dep).map(|done| (dep.clone(), done.clone())))
+                .collect();
+            finished.insert(account_type.clone(), done_rx);
+
+            let job = TypeMigration {
+                migration_id: migration_id.to_string(),
+                account_type: account_type.clone(),
+                accounts: accounts_by_type.remove(account_type).unwrap_or_default(),
+                migrator: self.migrators[account_type].clone(),
+                migrations: self.migrations.clone(),
+                rpc_client: self.rpc_client.clone(),
+                database: self.database.clone(),
+                notifications: self.notifications.clone(),
+                controller: controller.clone(),
+            };
+
+            tasks.push(tokio::spawn(async move {
+                for (dep, mut dep_done) in dependencies {
+                    let succeeded = dep_done
+                        .wait_for(Option::is_some)
+                        .await
+                        .map(|done| *done == Some(true))
+                        .unwrap_or(false);
+                    if !succeeded {
+                        tracing::warn!("Skipping {}: dependency {} did not migrate", job.account_type, dep);
+                        job.set_status(MigrationStatus::Skipped).await;
+                        let _ = done_tx.send(Some(false));
+                        return;
                     }
                 }
-                Err(_) => {
-                    let mut migrations_guard = migrations.lock().await;
-                    if let Some(migration) = migrations_guard.iter_mut()
-                        .find(|m| m.migration_id == migration_id) {
-                        migration.failed_accounts += 1;
+
+                let succeeded = job.run().await;
+                let _ = done_tx.send(Some(succeeded));
+            }));
+        }
+
+        let migrations_clone = self.migrations.clone();
+        let lock = self.lock.clone();
+        let invariants = self.invariants.clone();
+        let notifications = self.notifications.clone();
+        let monitoring = self.monitoring.clone();
+        let sampler = self.sample_verifier();
+        let migrators = self.migrators.clone();
+        let task_migration_id = migration_id.to_string();
+
+        tokio::spawn(async move {
+            let migration_id = task_migration_id;
+            futures_util::future::join_all(tasks).await;
+
+            // Read a sample of the migrated accounts back from the chain
+            let all_migrated = migrations_clone.lock().await
+                .iter()
+                .find(|m| m.migration_id == migration_id)
+                .is_some_and(|m| m.account_types.iter().all(|t| t.status == MigrationStatus::Completed));
+            let sample = match sampler {
+                Some(sampler) if all_migrated => match sampler.verify(&migration_id, &migrators).await {
+                    Ok(sample) => sample,
+                    Err(e) => {
+                        tracing::error!("Sampled verification of migration {} failed to run: {}", migration_id, e);
+                        None
                     }
+                },
+                _ => None,
+            };
+            let sample_passed = sample.as_ref().is_none_or(|sample| sample.passed);
+            if let (Some(sample), Some(monitoring)) = (sample.as_ref().filter(|s| !s.passed), &monitoring) {
+                monitoring.send_alert(
+                    AlertLevel::Critical,
+                    format!(
+                        "Migration {}: {} of {} sampled accounts failed verification ({:.2}% > {:.2}% allowed)",
+                        migration_id, sample.failed, sample.sampled, sample.error_rate_percent, sample.max_error_percent
+                    ),
+                    "migration".to_string(),
+                ).await;
+            }
+
+            let invariants_passed = match invariants {
+                Some(invariants) => {
+                    invariants.verify_or_rollback(&migration_id, InvariantPhase::PostMigration).await
+                }
+                None => true,
+            };
+
+            // Mark migration as completed, or failed if any account type did not
+            // finish or an invariant broke
+            let snapshot = {
+                let mut migrations_guard = migrations_clone.lock().await;
+                migrations_guard.iter_mut()
+                    .find(|m| m.migration_id == migration_id)
+                    .map(|migration| {
+                        let all_completed = migration.account_types
+                            .iter()
+                            .all(|t| t.status == MigrationStatus::Completed);
+                        migration.sample_verification = sample;
+                        migration.status = if all_completed && sample_passed && invariants_passed {
+                            MigrationStatus::Completed
+                        } else {
+                            MigrationStatus::Failed
+                        };
+                        migration.completed_at = Some(chrono::Utc::now().timestamp());
+                        migration.snapshot()
+                    })
+            };
+
+            lock.release(&migration_id).await;
+
+            if let (Some(notifications), Some(snapshot)) = (notifications, snapshot) {
+                notifications.notify_migration_progress(migration_id, snapshot).await;
+            }
+        });
+
+    }
+
+    /// Mark a migration whose lock was taken over as failed. Its tasks may
+    /// still be running; they no longer hold the lock.
+    async fn take_over(&self, previous: &str) {
+        tracing::warn!(
+            "Migration lock for {} taken over from migration {}",
+            self.managed_program,
+            previous
+        );
+
+        let mut migrations = self.migrations.lock().await;
+        if let Some(migration) = migrations
+            .iter_mut()
+            .find(|m| m.migration_id == previous && m.status == MigrationStatus::InProgress)
+        {
+            migration.status = MigrationStatus::Failed;
+            migration.completed_at = Some(chrono::Utc::now().timestamp());
+        }
+    }
+
+    /// Re-run the account types of a failed batch migration that did not
+    /// complete; completed types are kept and satisfy their dependents.
+    pub async fn retry_migration(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        let mut accounts_by_type = self.identify_accounts_to_migrate().await?;
+
+        let order: Vec<String> = {
+            let mut migrations = self.migrations.lock().await;
+            let migration = migrations
+                .iter_mut()
+                .find(|m| m.migration_id == migration_id)
+                .ok_or_else(|| UpgradeError::MigrationError(format!("Migration not found: {}", migration_id)))?;
+
+            if migration.strategy != MigrationStrategy::Batch {
+                return Err(UpgradeError::InvalidRequest(
+                    "Only batch migrations can be retried".to_string(),
+                ));
+            }
+            if migration.status != MigrationStatus::Failed {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Migration {} is {:?}, only failed migrations can be retried",
+                    migration_id, migration.status
+                )));
+            }
+            self.lock.acquire(migration_id, false).await?;
+
+            let mut order = Vec::new();
+            for progress in migration.account_types.iter_mut() {
+                if progress.status == MigrationStatus::Completed {
+                    continue;
                 }
+                migration.migrated_accounts -= progress.migrated_accounts;
+                migration.failed_accounts -= progress.failed_accounts;
+                migration.total_accounts -= progress.total_accounts;
+
+                progress.total_accounts = accounts_by_type.get(&progress.account_type).map_or(0, Vec::len);
+                progress.migrated_accounts = 0;
+                progress.failed_accounts = 0;
+                progress.status = MigrationStatus::NotStarted;
+                progress.started_at = None;
+                progress.completed_at = None;
+
+                migration.total_accounts += progress.total_accounts;
+                order.push(progress.account_type.clone());
+            }
+
+            migration.status = MigrationStatus::InProgress;
+            migration.completed_at = None;
+            migration.sample_verification = None;
+            order
+        };
+
+        accounts_by_type.retain(|account_type, _| order.contains(account_type));
+        tracing::info!("Retrying migration {} for {:?}", migration_id, order);
+        self.spawn_batch(migration_id, &order, accounts_by_type);
+
+        Ok(())
+    }
+
+    /// Revert a finished migration by rolling back the managed program
+    pub async fn rollback_migration(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        {
+            let migrations = self.migrations.lock().await;
+            let migration = migrations
+                .iter()
+                .find(|m| m.migration_id == migration_id)
+                .ok_or_else(|| UpgradeError::MigrationError(format!("Migration not found: {}", migration_id)))?;
+
+            if matches!(migration.status, MigrationStatus::InProgress | MigrationStatus::RolledBack) {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Migration {} is {:?} and cannot be rolled back",
+                    migration_id, migration.status
+                )));
             }
         }
 
-        // Mark migration as completed
-        let mut migrations_guard = migrations.lock().await;
-        if let Some(migration) = migrations_guard.iter_mut()
-            .find(|m| m.migration_id == migration_id) {
-            migration.status = MigrationStatus::Completed;
-            migration.completed_at = Some(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as i64
-            );
+        if let Some(rollback_handler) = &self.rollback_handler {
+            rollback_handler.rollback_program(&self.managed_program).await?;
+        }
+
+        let snapshot = {
+            let mut migrations = self.migrations.lock().await;
+            migrations
+                .iter_mut()
+                .find(|m| m.migration_id == migration_id)
+                .map(|migration| {
+                    migration.status = MigrationStatus::RolledBack;
+                    migration.completed_at = Some(chrono::Utc::now().timestamp());
+                    migration.snapshot()
+                })
+        };
+
+        if let (Some(notifications), Some(snapshot)) = (&self.notifications, snapshot) {
+            notifications.notify_migration_progress(migration_id.to_string(), snapshot).await;
         }
+
+        tracing::warn!("Migration {} rolled back", migration_id);
+        Ok(())
     }
 
+    /// Sampled verification needs the backups in the database and RPC to read accounts
+    fn sample_verifier(&self) -> Option<SampleVerifier> {
+        Some(SampleVerifier::new(
+            self.sampling,
+            self.database.clone()?,
+            self.rpc_client.clone()?,
+        ))
+    }
+
+    /// Migrate one account, returning the signature of the transaction sent
+    /// (if any) and the account's data before the migration
     async fn migrate_single_account(
         account: &Pubkey,
-        migrators: &[Box<dyn AccountMigrator + Send + Sync>],
-    ) -> Result<(), MigrationError> {
+        migrator: &(dyn AccountMigrator + Send + Sync),
+    ) -> Result<(Option<String>, Vec<u8>), MigrationError> {
         // In production, this would:
         // 1. Fetch account data from Solana
         // 2. Determine which migrator to use
@@ -217,16 +1044,146 @@ impl MigrationManager {
         // Placeholder: In real implementation, fetch and transform
         let old_data = vec![0u8; 40]; // Placeholder
         
-        if let Some(migrator) = migrators.first() {
-            let new_data = migrator.migrate(&old_data)?;
-            let verified = migrator.verify(&old_data, &new_data)?;
-            
-            if !verified {
-                return Err(MigrationError::VerificationFailed);
+        let new_data = migrator.migrate(&old_data)?;
+        let verified = migrator.verify(&old_data, &new_data)?;
+
+        if !verified {
+            return Err(MigrationError::VerificationFailed);
+        }
+
+        // Placeholder: no transaction is submitted yet
+        Ok((None, old_data))
+    }
+
+    /// Rent and fees for migrating every account currently due for migration
+    pub async fn get_rent_budget(&self) -> Result<RentBudget, UpgradeError> {
+        let accounts_by_type = self.identify_accounts_to_migrate().await?;
+        self.rent_budget_for(&accounts_by_type).await
+    }
+
+    async fn rent_budget_for(
+        &self,
+        accounts_by_type: &HashMap<String, Vec<Pubkey>>,
+    ) -> Result<RentBudget, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+        let rent = Rent::default();
+
+        let mut account_types: Vec<&String> = accounts_by_type.keys().collect();
+        account_types.sort();
+
+        let mut budgets = Vec::with_capacity(account_types.len());
+        for account_type in account_types {
+            let Some(migrator) = self.migrators.get(account_type) else {
+                continue;
+            };
+
+            // Accounts that no longer exist are skipped; migrating them fails anyway
+            let mut sizes = Vec::new();
+            for chunk in accounts_by_type[account_type].chunks(100) {
+                let accounts = client.get_multiple_accounts(chunk)
+                    .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch accounts: {}", e)))?;
+                sizes.extend(accounts.into_iter().flatten().map(|a| (a.data.len(), a.lamports)));
             }
+            budgets.push(TypeRentBudget::estimate(account_type, migrator.as_ref(), &sizes, &rent));
         }
 
-        Ok(())
+        let funding_source = self.funding.as_ref().map(|(_, payer)| *payer);
+        let funding_balance = match funding_source {
+            Some(payer) => Some(
+                client.get_balance(&payer)
+                    .map_err(|e| UpgradeError::SolanaError(format!("Failed to get balance: {}", e)))?,
+            ),
+            None => None,
+        };
+
+        Ok(RentBudget::new(budgets, funding_source, funding_balance))
+    }
+
+    /// Top the fee payer up where a faucet allows, then refuse to start unless
+    /// the budget is covered or `allow_underfunded` is set
+    async fn check_rent_budget(
+        &self,
+        accounts_by_type: &HashMap<String, Vec<Pubkey>>,
+        options: MigrationStartOptions,
+    ) -> Result<(), UpgradeError> {
+        let mut budget = self.rent_budget_for(accounts_by_type).await?;
+
+        if let Some((faucet, payer)) = &self.funding {
+            match faucet.ensure_funded(payer, budget.required_lamports).await {
+                Ok(balance) => budget.set_funding_balance(Some(balance)),
+                Err(e) if options.allow_underfunded => tracing::warn!("Migration funding: {}", e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if budget.sufficient {
+            return Ok(());
+        }
+
+        let message = format!(
+            "migration needs {} lamports ({} rent, {} fees), funding source {} has {}",
+            budget.required_lamports,
+            budget.additional_rent_lamports,
+            budget.fee_lamports,
+            budget.funding_source.as_deref().unwrap_or("(none configured)"),
+            budget.funding_balance.unwrap_or(0)
+        );
+        if options.allow_underfunded {
+            tracing::warn!("Starting underfunded migration: {}", message);
+            return Ok(());
+        }
+
+        Err(UpgradeError::InsufficientFunding(message))
+    }
+
+    /// Read compute units consumed and fee paid from a confirmed transaction
+    fn fetch_transaction_cost(
+        client: &RpcClient,
+        signature: &str,
+    ) -> Result<TransactionCost, MigrationError> {
+        let sig = Signature::from_str(signature).map_err(|_| MigrationError::InvalidData)?;
+        let tx = client
+            .get_transaction(&sig, UiTransactionEncoding::Json)
+            .map_err(|e| MigrationError::from_rpc(&e))?;
+        let meta = tx.transaction.meta.ok_or(MigrationError::InvalidData)?;
+
+        Ok(TransactionCost {
+            signature: signature.to_string(),
+            compute_units: Option::<u64>::from(meta.compute_units_consumed).unwrap_or(0),
+            fee_lamports: meta.fee,
+        })
+    }
+
+    /// Cost breakdown for a migration, from the database when available
+    pub async fn get_cost_breakdown(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        if let Some(db) = &self.database {
+            return db.get_migration_cost_breakdown(migration_id).await;
+        }
+
+        let migrations = self.migrations.lock().await;
+        let migration = migrations
+            .iter()
+            .find(|m| m.migration_id == migration_id)
+            .ok_or_else(|| UpgradeError::MigrationError(format!("Migration not found: {}", migration_id)))?;
+
+        let per_account = |total: u64| {
+            if migration.migrated_accounts > 0 {
+                total as f64 / migration.migrated_accounts as f64
+            } else {
+                0.0
+            }
+        };
+
+        Ok(serde_json::json!({
+            "migration_id": migration.migration_id,
+            "transactions": migration.transactions_sent,
+            "accounts": migration.migrated_accounts,
+            "total_compute_units": migration.compute_units_consumed,
+            "total_fee_lamports": migration.fees_paid_lamports,
+            "avg_compute_units_per_account": per_account(migration.compute_units_consumed),
+            "avg_fee_lamports_per_account": per_account(migration.fees_paid_lamports),
+        }))
     }
 
     pub async fn get_progress(&self) -> Result<serde_json::Value, UpgradeError> {
@@ -238,28 +1195,575 @@ impl MigrationManager {
             }));
         }
 
-        let latest = migrations.last().unwrap();
-        let progress_percent = if latest.total_accounts > 0 {
-            (latest.migrated_accounts as f64 / latest.total_accounts as f64) * 100.0
-        } else {
-            0.0
+        let migration = migrations.last().unwrap().clone();
+        drop(migrations);
+
+        let mut snapshot = migration.snapshot();
+        // Lazy migrations are counted by the indexer, not a migration_state account
+        if migration.strategy != MigrationStrategy::Lazy {
+            snapshot["onchain"] = self.onchain_progress(&migration);
+        }
+        Ok(snapshot)
+    }
+
+    /// The migration's `migration_state` account and any drift from the
+    /// backend's counters, or null when it has none or it cannot be read
+    fn onchain_progress(&self, migration: &MigrationProgress) -> serde_json::Value {
+        let (Some(client), Some(address)) = (
+            self.rpc_client.as_ref(),
+            migration_state_address(&self.upgrade_manager_program, &migration.migration_id),
+        ) else {
+            return serde_json::Value::Null;
+        };
+
+        let data = match client.get_account_with_commitment(&address, client.commitment()) {
+            Ok(response) => match response.value {
+                Some(account) => account.data,
+                None => return serde_json::Value::Null,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to fetch migration state {}: {}", address, e);
+                return serde_json::Value::Null;
+            }
         };
 
+        match OnchainMigrationState::try_from_account_data(&data) {
+            Ok(state) => serde_json::json!({
+                "address": address.to_string(),
+                "drift": reconcile_progress(migration, &state),
+                "state": state,
+            }),
+            Err(e) => {
+                tracing::warn!("Migration state {}: {}", address, e);
+                serde_json::Value::Null
+            }
+        }
+    }
+
+    /// Current progress of one migration
+    pub async fn get_migration(&self, migration_id: &str) -> Result<MigrationProgress, UpgradeError> {
+        let migrations = self.migrations.lock().await;
+        migrations
+            .iter()
+            .find(|m| m.migration_id == migration_id)
+            .cloned()
+            .ok_or_else(|| UpgradeError::MigrationError(format!("Migration not found: {}", migration_id)))
+    }
+
+    /// Feed accounts seen in `AccountMigratedEvent`s into running lazy migrations
+    pub async fn record_lazy_migrations(&self, accounts: &[Pubkey]) {
+        let now = chrono::Utc::now().timestamp();
+        let mut coverage_map = self.lazy_coverage.lock().await;
+        let mut migrations = self.migrations.lock().await;
+        let mut updated = Vec::new();
+
+        for (migration_id, coverage) in coverage_map.iter_mut() {
+            let Some(migration) = migrations.iter_mut()
+                .find(|m| &m.migration_id == migration_id && m.status == MigrationStatus::InProgress)
+            else {
+                continue;
+            };
+
+            let before = migration.migrated_accounts;
+            for account in accounts {
+                if coverage.pending.remove(account) {
+                    migration.migrated_accounts += 1;
+                }
+            }
+
+            let percent_migrated = if migration.total_accounts > 0 {
+                (migration.migrated_accounts as f64 / migration.total_accounts as f64) * 100.0
+            } else {
+                100.0
+            };
+            coverage.history.push(CoveragePoint {
+                timestamp: now,
+                migrated_accounts: migration.migrated_accounts,
+                percent_migrated,
+            });
+
+            if coverage.pending.is_empty() {
+                migration.status = MigrationStatus::Completed;
+                migration.completed_at = Some(now);
+            }
+
+            if migration.migrated_accounts != before || migration.is_finished() {
+                updated.push((migration_id.clone(), migration.snapshot()));
+            }
+        }
+        drop(migrations);
+        drop(coverage_map);
+
+        if let Some(notifications) = &self.notifications {
+            for (migration_id, snapshot) in updated {
+                notifications.notify_migration_progress(migration_id, snapshot).await;
+            }
+        }
+    }
+
+    /// Coverage over time for a lazy migration
+    pub async fn get_coverage(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        let coverage_map = self.lazy_coverage.lock().await;
+        let coverage = coverage_map
+            .get(migration_id)
+            .ok_or_else(|| UpgradeError::MigrationError(format!("Not a lazy migration: {}", migration_id)))?;
+
         Ok(serde_json::json!({
-            "migration_id": latest.migration_id,
-            "status": format!("{:?}", latest.status),
-            "progress_percent": progress_percent,
-            "migrated_accounts": latest.migrated_accounts,
-            "total_accounts": latest.total_accounts,
-            "failed_accounts": latest.failed_accounts,
-            "started_at": latest.started_at,
-            "completed_at": latest.completed_at,
+            "migration_id": migration_id,
+            "pending_accounts": coverage.pending.len(),
+            "history": coverage.history,
         }))
     }
 
-    async fn identify_accounts_to_migrate(&self) -> Result<Vec<Pubkey>, UpgradeError> {
+    async fn identify_accounts_to_migrate(&self) -> Result<HashMap<String, Vec<Pubkey>>, UpgradeError> {
         // In production, query Solana for accounts owned by old program
-        // that need migration based on version
-        Ok(vec![])
+        // that need migration based on version, grouped by account type
+        Ok(self
+            .migrators
+            .keys()
+            .map(|account_type| (account_type.clone(), vec![]))
+            .collect())
+    }
+}
+
+/// Batch migration of a single account type
+struct TypeMigration {
+    migration_id: String,
+    account_type: String,
+    accounts: Vec<Pubkey>,
+    migrator: SharedMigrator,
+    migrations: Arc<Mutex<Vec<MigrationProgress>>>,
+    rpc_client: Option<Arc<RpcClient>>,
+    database: Option<Arc<Database>>,
+    notifications: Option<Arc<NotificationService>>,
+    controller: Arc<Mutex<ConcurrencyController>>,
+}
+
+impl TypeMigration {
+    /// Migrates every account of this type in parallel batches sized by the
+    /// shared concurrency controller; returns false if any account failed
+    async fn run(&self) -> bool {
+        self.set_status(MigrationStatus::InProgress).await;
+
+        let mut queue: VecDeque<(Pubkey, u32)> = self.accounts.iter().map(|account| (*account, 0)).collect();
+        let mut processed = 0;
+        let mut next_publish = PROGRESS_NOTIFY_INTERVAL;
+        let mut failed = false;
+
+        while !queue.is_empty() {
+            let concurrency = self.controller.lock().await.current();
+            let batch: Vec<(Pubkey, u32)> = queue.drain(..concurrency.min(queue.len())).collect();
+
+            let started = Instant::now();
+            let results = futures_util::future::join_all(batch.iter().map(|(account, _)| async move {
+                let sent = Instant::now();
+                let result = MigrationManager::migrate_single_account(account, self.migrator.as_ref()).await;
+                (result, sent.elapsed())
+            }))
+            .await;
+
+            let mut outcome = BatchOutcome {
+                accounts: batch.len(),
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                ..Default::default()
+            };
+            for ((account, attempts), (result, confirmation)) in batch.into_iter().zip(results) {
+                outcome.total_confirmation_ms += confirmation.as_millis() as u64;
+                match result {
+                    Ok((signature, old_data)) => {
+                        if self.record_migrated(&account, signature, old_data).await {
+                            outcome.rate_limited += 1;
+                        }
+                        processed += 1;
+                    }
+                    // Throttled accounts go back in the queue for a smaller batch
+                    Err(MigrationError::RateLimited) if attempts + 1 < MAX_RATE_LIMITED_ATTEMPTS => {
+                        outcome.errors += 1;
+                        outcome.rate_limited += 1;
+                        queue.push_back((account, attempts + 1));
+                    }
+                    Err(e) => {
+                        if matches!(e, MigrationError::RateLimited) {
+                            outcome.rate_limited += 1;
+                        }
+                        outcome.errors += 1;
+                        failed = true;
+                        processed += 1;
+                        self.update(|migration, progress| {
+                            migration.failed_accounts += 1;
+                            progress.failed_accounts += 1;
+                        }).await;
+                    }
+                }
+            }
+
+            let next = self.controller.lock().await.observe(&outcome);
+            let point = ThroughputPoint {
+                at: chrono::Utc::now().timestamp(),
+                concurrency,
+                accounts: outcome.accounts,
+                accounts_per_second: outcome.accounts_per_second(),
+                error_percent: outcome.error_percent(),
+                rate_limited: outcome.rate_limited,
+                avg_confirmation_ms: outcome.avg_confirmation_ms(),
+            };
+            self.update(|migration, _| {
+                migration.concurrency = next;
+                if migration.throughput.len() >= MAX_THROUGHPUT_POINTS {
+                    migration.throughput.remove(0);
+                }
+                migration.throughput.push(point);
+            }).await;
+            if next != concurrency {
+                tracing::info!(
+                    "Migration {} ({}): concurrency {} -> {}",
+                    self.migration_id,
+                    self.account_type,
+                    concurrency,
+                    next
+                );
+            }
+
+            if processed >= next_publish {
+                self.publish().await;
+                next_publish = processed - processed % PROGRESS_NOTIFY_INTERVAL + PROGRESS_NOTIFY_INTERVAL;
+            }
+            if outcome.rate_limited > 0 {
+                tokio::time::sleep(RATE_LIMIT_BACKOFF).await;
+            }
+        }
+
+        self.set_status(if failed {
+            MigrationStatus::Failed
+        } else {
+            MigrationStatus::Completed
+        }).await;
+
+        !failed
     }
+
+    /// Back up, profile and count a migrated account. Returns whether the
+    /// RPC provider throttled the cost lookup.
+    async fn record_migrated(&self, account: &Pubkey, signature: Option<String>, old_data: Vec<u8>) -> bool {
+        if let Some(db) = self.database.as_ref() {
+            let backup = AccountBackup {
+                account: account.to_string(),
+                account_type: self.account_type.clone(),
+                data: old_data,
+            };
+            if let Err(e) = db.save_account_backup(&self.migration_id, &backup).await {
+                tracing::warn!("Failed to back up {}: {}", account, e);
+            }
+        }
+
+        // Profile the transaction so devnet dry runs can predict mainnet cost
+        let mut rate_limited = false;
+        let cost = match (signature, self.rpc_client.as_ref()) {
+            (Some(signature), Some(client)) => {
+                match MigrationManager::fetch_transaction_cost(client, &signature) {
+                    Ok(cost) => Some(cost),
+                    Err(e) => {
+                        rate_limited = matches!(e, MigrationError::RateLimited);
+                        tracing::warn!("Failed to fetch cost for {}: {:?}", signature, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        if let (Some(cost), Some(db)) = (cost.as_ref(), self.database.as_ref()) {
+            if let Err(e) = db.record_migration_transaction_cost(
+                &self.migration_id,
+                &account.to_string(),
+                &cost.signature,
+                cost.compute_units as i64,
+                cost.fee_lamports as i64,
+            ).await {
+                tracing::warn!("Failed to record migration cost: {}", e);
+            }
+        }
+
+        self.update(|migration, progress| {
+            migration.migrated_accounts += 1;
+            progress.migrated_accounts += 1;
+            if let Some(cost) = cost {
+                migration.transactions_sent += 1;
+                migration.compute_units_consumed += cost.compute_units;
+                migration.fees_paid_lamports += cost.fee_lamports;
+            }
+        }).await;
+
+        rate_limited
+    }
+
+    async fn set_status(&self, status: MigrationStatus) {
+        let now = chrono::Utc::now().timestamp();
+        self.update(|_, progress| {
+            match status {
+                MigrationStatus::InProgress => progress.started_at = Some(now),
+                MigrationStatus::Completed | MigrationStatus::Failed | MigrationStatus::Skipped => {
+                    progress.completed_at = Some(now)
+                }
+                MigrationStatus::NotStarted | MigrationStatus::RolledBack => {}
+            }
+            progress.status = status;
+        }).await;
+        self.publish().await;
+    }
+
+    async fn publish(&self) {
+        let Some(notifications) = &self.notifications else {
+            return;
+        };
+        let snapshot = {
+            let migrations = self.migrations.lock().await;
+            migrations
+                .iter()
+                .find(|m| m.migration_id == self.migration_id)
+                .map(MigrationProgress::snapshot)
+        };
+        if let Some(snapshot) = snapshot {
+            notifications
+                .notify_migration_progress(self.migration_id.clone(), snapshot)
+                .await;
+        }
+    }
+
+    async fn update(&self, f: impl FnOnce(&mut MigrationProgress, &mut AccountTypeProgress)) {
+        let mut migrations = self.migrations.lock().await;
+        let Some(migration) = migrations.iter_mut().find(|m| m.migration_id == self.migration_id) else {
+            return;
+        };
+        // Split the borrow so the closure can update both levels at once
+        let Some(index) = migration.account_types.iter().position(|t| t.account_type == self.account_type) else {
+            return;
+        };
+        let mut progress = migration.account_types[index].clone();
+        f(migration, &mut progress);
+        migration.account_types[index] = progress;
+    }
+}
+
+#[async_trait]
+impl Migration for MigrationManager {
+    async fn start_with_options(
+        &self,
+        strategy: MigrationStrategy,
+        options: MigrationStartOptions,
+    ) -> Result<String, UpgradeError> {
+        self.start_migration_with_options(strategy, options).await
+    }
+
+    async fn rent_budget(&self) -> Result<RentBudget, UpgradeError> {
+        self.get_rent_budget().await
+    }
+
+    async fn progress(&self) -> Result<serde_json::Value, UpgradeError> {
+        self.get_progress().await
+    }
+
+    async fn get_migration(&self, migration_id: &str) -> Result<MigrationProgress, UpgradeError> {
+        MigrationManager::get_migration(self, migration_id).await
+    }
+
+    async fn retry(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        self.retry_migration(migration_id).await
+    }
+
+    async fn rollback(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        self.rollback_migration(migration_id).await
+    }
+
+    async fn cost_breakdown(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        self.get_cost_breakdown(migration_id).await
+    }
+
+    async fn coverage(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        self.get_coverage(migration_id).await
+    }
+
+    async fn record_lazy_migrations(&self, accounts: &[Pubkey]) {
+        MigrationManager::record_lazy_migrations(self, accounts).await
+    }
+}
+
+/// In-memory `Migration` that finishes every migration synchronously.
+///
+/// Each migration covers `total_accounts` accounts, of which `failing_accounts`
+/// fail on the first run so retry paths can be exercised. Calls are recorded
+/// in order for assertions.
+pub struct MockMigration {
+    total_accounts: usize,
+    failing_accounts: usize,
+    migrations: Mutex<Vec<MigrationProgress>>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl MockMigration {
+    pub fn new(total_accounts: usize) -> Self {
+        Self {
+            total_accounts,
+            failing_accounts: 0,
+            migrations: Mutex::new(Vec::new()),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_failing_accounts(mut self, failing_accounts: usize) -> Self {
+        self.failing_accounts = failing_accounts.min(self.total_accounts);
+        self
+    }
+
+    /// Operations performed so far, e.g. `["start", "retry", "rollback"]`
+    pub async fn calls(&self) -> Vec<String> {
+        self.calls.lock().await.clone()
+    }
+
+    async fn record(&self, call: &str) {
+        self.calls.lock().await.push(call.to_string());
+    }
+
+    async fn update<T>(
+        &self,
+        migration_id: &str,
+        f: impl FnOnce(&mut MigrationProgress) -> Result<T, UpgradeError>,
+    ) -> Result<T, UpgradeError> {
+        let mut migrations = self.migrations.lock().await;
+        let migration = migrations
+            .iter_mut()
+            .find(|m| m.migration_id == migration_id)
+            .ok_or_else(|| UpgradeError::MigrationError(format!("Migration not found: {}", migration_id)))?;
+        f(migration)
+    }
+}
+
+#[async_trait]
+impl Migration for MockMigration {
+    async fn start_with_options(
+        &self,
+        strategy: MigrationStrategy,
+        _options: MigrationStartOptions,
+    ) -> Result<String, UpgradeError> {
+        self.record("start").await;
+
+        let now = chrono::Utc::now().timestamp();
+        let migrated = self.total_accounts - self.failing_accounts;
+        let status = if self.failing_accounts > 0 {
+            MigrationStatus::Failed
+        } else {
+            MigrationStatus::Completed
+        };
+        let migration_id = uuid::Uuid::new_v4().to_string();
+
+        self.migrations.lock().await.push(MigrationProgress {
+            migration_id: migration_id.clone(),
+            strategy,
+            total_accounts: self.total_accounts,
+            migrated_accounts: migrated,
+            failed_accounts: self.failing_accounts,
+            status: status.clone(),
+            started_at: now,
+            completed_at: Some(now),
+            transactions_sent: migrated as u64,
+            compute_units_consumed: 0,
+            fees_paid_lamports: 0,
+            account_types: vec![AccountTypeProgress {
+                account_type: "mock".to_string(),
+                depends_on: vec![],
+                total_accounts: self.total_accounts,
+                migrated_accounts: migrated,
+                failed_accounts: self.failing_accounts,
+                status,
+                started_at: Some(now),
+                completed_at: Some(now),
+            }],
+            sample_verification: None,
+            concurrency: 0,
+            throughput: Vec::new(),
+        });
+
+        Ok(migration_id)
+    }
+
+    async fn progress(&self) -> Result<serde_json::Value, UpgradeError> {
+        let migrations = self.migrations.lock().await;
+        Ok(migrations
+            .last()
+            .map(MigrationProgress::snapshot)
+            .unwrap_or_else(|| serde_json::json!({ "status": "no_migrations" })))
+    }
+
+    async fn get_migration(&self, migration_id: &str) -> Result<MigrationProgress, UpgradeError> {
+        self.update(migration_id, |migration| Ok(migration.clone())).await
+    }
+
+    async fn retry(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        self.record("retry").await;
+        self.update(migration_id, |migration| {
+            if migration.status != MigrationStatus::Failed {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Migration {} is {:?}, only failed migrations can be retried",
+                    migration_id, migration.status
+                )));
+            }
+            migration.migrated_accounts = migration.total_accounts;
+            migration.failed_accounts = 0;
+            migration.status = MigrationStatus::Completed;
+            for progress in migration.account_types.iter_mut() {
+                progress.migrated_accounts = progress.total_accounts;
+                progress.failed_accounts = 0;
+                progress.status = MigrationStatus::Completed;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rollback(&self, migration_id: &str) -> Result<(), UpgradeError> {
+        self.record("rollback").await;
+        self.update(migration_id, |migration| {
+            if matches!(migration.status, MigrationStatus::InProgress | MigrationStatus::RolledBack) {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Migration {} is {:?} and cannot be rolled back",
+                    migration_id, migration.status
+                )));
+            }
+            migration.status = MigrationStatus::RolledBack;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn rent_budget(&self) -> Result<RentBudget, UpgradeError> {
+        let budget = TypeRentBudget {
+            account_type: "mock".to_string(),
+            accounts: self.total_accounts,
+            current_bytes: 0,
+            migrated_bytes: 0,
+            additional_rent_lamports: 0,
+        };
+        Ok(RentBudget::new(vec![budget], None, None))
+    }
+
+    async fn cost_breakdown(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        self.update(migration_id, |migration| {
+            Ok(serde_json::json!({
+                "migration_id": migration.migration_id,
+                "transactions": migration.transactions_sent,
+                "accounts": migration.migrated_accounts,
+                "total_compute_units": 0,
+                "total_fee_lamports": 0,
+                "avg_compute_units_per_account": 0.0,
+                "avg_fee_lamports_per_account": 0.0,
+            }))
+        })
+        .await
+    }
+
+    async fn coverage(&self, migration_id: &str) -> Result<serde_json::Value, UpgradeError> {
+        Err(UpgradeError::MigrationError(format!("Not a lazy migration: {}", migration_id)))
+    }
+
+    async fn record_lazy_migrations(&self, _accounts: &[Pubkey]) {}
 }
@@ -1,9 +1,24 @@
+use crate::chain_clock::ClockSample;
 use crate::error::UpgradeError;
+use crate::request_metrics::RequestMetrics;
+use crate::slo::{self, SloStatus, SloTracker};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::time::{interval, Duration};
+use tokio::time::{interval, Duration, Instant};
+
+pub const COMPONENT_SOLANA_RPC: &str = "solana_rpc";
+pub const COMPONENT_POSTGRES: &str = "postgres";
+pub const COMPONENT_SQUADS: &str = "squads";
+pub const COMPONENT_CHAIN_CLOCK: &str = "chain_clock";
+pub const COMPONENT_SECURITY: &str = "security";
+pub const COMPONENT_EXECUTION: &str = "execution";
+pub const COMPONENT_WATCH: &str = "post_upgrade_watch";
+pub const COMPONENT_EMERGENCY: &str = "emergency_pause";
+
+/// Components that must be healthy before an upgrade is executed
+pub const EXECUTION_DEPENDENCIES: [&str; 3] = [COMPONENT_SOLANA_RPC, COMPONENT_POSTGRES, COMPONENT_SQUADS];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metrics {
@@ -20,9 +35,13 @@ pub struct MonitoringService {
     metrics: Arc<Mutex<Metrics>>,
     alerts: Arc<Mutex<Vec<Alert>>>,
     health_checks: Arc<Mutex<HashMap<String, HealthStatus>>>,
+    slos: Arc<Mutex<SloTracker>>,
+    /// SLOs with a burn-rate alert raised, and whether it paged
+    burning_slos: Arc<Mutex<HashMap<String, bool>>>,
+    requests: Arc<RequestMetrics>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alert {
     pub level: AlertLevel,
     pub message: String,
@@ -30,14 +49,14 @@ pub struct Alert {
     pub component: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AlertLevel {
     Info,
     Warning,
     Critical,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum HealthStatus {
     Healthy,
     Degraded,
@@ -58,6 +77,12 @@ impl MonitoringService {
             })),
             alerts: Arc::new(Mutex::new(Vec::new())),
             health_checks: Arc::new(Mutex::new(HashMap::new())),
+            slos: Arc::new(Mutex::new(SloTracker::new(slo::slos_from_env().unwrap_or_else(|e| {
+                tracing::error!("{}; using default SLOs", e);
+                slo::default_slos()
+            })))),
+            burning_slos: Arc::new(Mutex::new(HashMap::new())),
+            requests: Arc::new(RequestMetrics::default()),
         };
 
         // Start background monitoring tasks
@@ -103,6 +128,92 @@ impl MonitoringService {
         ).await;
     }
 
+    /// Count a latency sample toward the latency SLOs on `operation`
+    pub async fn record_latency(&self, operation: &str, latency: Duration) {
+        self.slos
+            .lock()
+            .await
+            .record_latency(operation, latency.as_millis() as u64, chrono::Utc::now().timestamp());
+    }
+
+    /// Count an outcome toward the success-rate SLOs on `operation`
+    pub async fn record_outcome(&self, operation: &str, success: bool) {
+        self.slos
+            .lock()
+            .await
+            .record_outcome(operation, success, chrono::Utc::now().timestamp());
+    }
+
+    /// Per-route request counters filled by the `track_requests` middleware
+    pub fn request_metrics(&self) -> &RequestMetrics {
+        &self.requests
+    }
+
+    /// Prometheus text exposition of the request metrics and service counters
+    pub async fn render_prometheus(&self) -> String {
+        let metrics = self.get_metrics().await;
+        let mut out = self.requests.render_prometheus();
+
+        for (name, help, value) in [
+            ("goquant_proposals_created_total", "Upgrade proposals created.", metrics.proposals_created),
+            ("goquant_proposals_executed_total", "Upgrade proposals executed.", metrics.proposals_executed),
+            ("goquant_proposals_cancelled_total", "Upgrade proposals cancelled.", metrics.proposals_cancelled),
+            ("goquant_migrations_completed_total", "Account migrations completed.", metrics.migrations_completed),
+            ("goquant_rollbacks_initiated_total", "Rollbacks initiated.", metrics.rollbacks_initiated),
+        ] {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} counter\n{} {}\n", name, help, name, name, value));
+        }
+
+        out
+    }
+
+    pub async fn get_slo_status(&self) -> Vec<SloStatus> {
+        self.slos.lock().await.status(chrono::Utc::now().timestamp())
+    }
+
+    /// Alert when an SLO burns its error budget too fast: Critical for a fast
+    /// burn, Warning for a slow one. Each SLO alerts once per episode (again if
+    /// it escalates) and logs an Info alert when the burn stops.
+    pub async fn check_slo_burn_rates(&self) {
+        let firing = self.slos.lock().await.burn_rate_alerts(chrono::Utc::now().timestamp());
+        let mut burning = self.burning_slos.lock().await;
+
+        for alert in &firing {
+            let escalated = burning.get(&alert.slo).map_or(true, |paged| alert.page && !paged);
+            if !escalated {
+                continue;
+            }
+            burning.insert(alert.slo.clone(), alert.page);
+
+            let level = if alert.page { AlertLevel::Critical } else { AlertLevel::Warning };
+            self.send_alert(
+                level,
+                format!(
+                    "SLO {} burning error budget at {:.1}x over the last {}h (threshold {:.1}x)",
+                    alert.slo,
+                    alert.burn_rate,
+                    alert.long_window_seconds / 3600,
+                    alert.threshold
+                ),
+                "slo".to_string(),
+            ).await;
+        }
+
+        let recovered: Vec<String> = burning
+            .keys()
+            .filter(|name| !firing.iter().any(|a| &a.slo == *name))
+            .cloned()
+            .collect();
+        for name in recovered {
+            burning.remove(&name);
+            self.send_alert(
+                AlertLevel::Info,
+                format!("SLO {} no longer burning error budget", name),
+                "slo".to_string(),
+            ).await;
+        }
+    }
+
     pub async fn send_alert(&self, level: AlertLevel, message: String, component: String) {
         let alert = Alert {
             level: level.clone(),
@@ -169,6 +280,71 @@ impl MonitoringService {
         }
     }
 
+    /// Probe execution dependencies and record their health. A probe that
+    /// succeeds but exceeds `HEALTH_DEGRADED_LATENCY_MS` (default 2000) is Degraded.
+    pub async fn probe_dependencies(
+        &self,
+        database: &crate::database::Database,
+        multisig: &crate::multisig::MultisigCoordinator,
+    ) {
+        let latency_budget = Duration::from_millis(
+            std::env::var("HEALTH_DEGRADED_LATENCY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(2000),
+        );
+
+        let started = Instant::now();
+        let rpc = multisig.check_rpc_health().await;
+        let status = Self::probe_status(COMPONENT_SOLANA_RPC, &rpc, started.elapsed(), latency_budget);
+        self.update_health(COMPONENT_SOLANA_RPC.to_string(), status).await;
+
+        let started = Instant::now();
+        let postgres = database.ping().await;
+        let status = Self::probe_status(COMPONENT_POSTGRES, &postgres, started.elapsed(), latency_budget);
+        self.update_health(COMPONENT_POSTGRES.to_string(), status).await;
+
+        let started = Instant::now();
+        if let Some(squads) = multisig.check_squads_health().await {
+            let status = Self::probe_status(COMPONENT_SQUADS, &squads, started.elapsed(), latency_budget);
+            self.update_health(COMPONENT_SQUADS.to_string(), status).await;
+        }
+    }
+
+    fn probe_status(
+        component: &str,
+        result: &Result<(), UpgradeError>,
+        elapsed: Duration,
+        latency_budget: Duration,
+    ) -> HealthStatus {
+        match result {
+            Err(e) => {
+                tracing::warn!("Health probe for {} failed: {}", component, e);
+                HealthStatus::Unhealthy
+            }
+            Ok(()) if elapsed > latency_budget => HealthStatus::Degraded,
+            Ok(()) => HealthStatus::Healthy,
+        }
+    }
+
+    /// Fails with `DependenciesUnhealthy` naming every execution dependency
+    /// that is currently Degraded or Unhealthy
+    pub async fn ensure_execution_dependencies_healthy(&self) -> Result<(), UpgradeError> {
+        let mut unhealthy = Vec::new();
+        for component in EXECUTION_DEPENDENCIES {
+            let status = self.check_health(component).await;
+            if status != HealthStatus::Healthy {
+                unhealthy.push(format!("{}={:?}", component, status));
+            }
+        }
+
+        if unhealthy.is_empty() {
+            Ok(())
+        } else {
+            Err(UpgradeError::DependenciesUnhealthy(unhealthy.join(", ")))
+        }
+    }
+
     async fn monitor_health(
         metrics: Arc<Mutex<Metrics>>,
         alerts: Arc<Mutex<Vec<Alert>>>,
@@ -203,13 +379,68 @@ impl MonitoringService {
         }
     }
 
+    /// Raise a Warning alert for each member that has newly become inactive
+    pub async fn check_member_inactivity(
+        &self,
+        activity: &[crate::multisig::MemberActivity],
+        already_flagged: &mut std::collections::HashSet<String>,
+    ) {
+        for member in activity {
+            if member.inactive {
+                if already_flagged.insert(member.member.clone()) {
+                    self.send_alert(
+                        AlertLevel::Warning,
+                        format!(
+                            "Multisig member {} is inactive (missed {}/{} recent proposals, last approval: {:?})",
+                            member.member,
+                            member.missed_recent_proposals,
+                            member.recent_proposals,
+                            member.last_approval_at,
+                        ),
+                        "multisig".to_string(),
+                    ).await;
+                }
+            } else {
+                already_flagged.remove(&member.member);
+            }
+        }
+    }
+
+    /// Warn once when the backend clock drifts from chain time past
+    /// `threshold_seconds`, and again only after it has recovered
+    pub async fn check_clock_drift(&self, sample: &ClockSample, threshold_seconds: i64, alerted: &mut bool) {
+        if sample.drift_seconds.abs() <= threshold_seconds {
+            *alerted = false;
+            return;
+        }
+        if *alerted {
+            return;
+        }
+
+        *alerted = true;
+        self.send_alert(
+            AlertLevel::Warning,
+            format!(
+                "Backend clock is {}s {} chain time at slot {} (threshold {}s); timelock countdowns use chain time",
+                sample.drift_seconds.abs(),
+                if sample.drift_seconds > 0 { "ahead of" } else { "behind" },
+                sample.slot,
+                threshold_seconds,
+            ),
+            COMPONENT_CHAIN_CLOCK.to_string(),
+        ).await;
+    }
+
     pub async fn get_dashboard_data(&self) -> serde_json::Value {
         let metrics = self.get_metrics().await;
         let recent_alerts = self.get_alerts(10).await;
         let health_status = self.check_health("system").await;
+        let slos = self.get_slo_status().await;
 
         serde_json::json!({
             "metrics": metrics,
+            "slos": slos,
+            "requests": self.requests.summary(),
             "recent_alerts": recent_alerts,
             "health_status": format!("{:?}", health_status),
             "timestamp": std::time::SystemTime::now()
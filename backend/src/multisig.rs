@@ -1,7 +1,25 @@
+use crate::buffer_watcher::ensure_buffer_handed_off;
 use crate::error::UpgradeError;
-use crate::squads::SquadsClient;
+use crate::emergency::{OnchainPauseRegistry, OnchainPauseVotes};
+use crate::feature_flags::{
+    encode_flag_name, flag_change_digest, flag_change_target, FlagChangeProposal, FlagChangeRequest, OnchainFeatureFlags,
+};
+use crate::multisig_backend::{backend_from_env, MultisigBackend, MultisigBackendKind, NativeBackend};
+use crate::program_builder::{ExtensionCost, ProgramCapacity};
+use crate::program_errors::ErrorDecoder;
+use crate::rpc_quorum::QuorumRpc;
+use crate::signer::{sign_transaction, SharedSigner};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::instruction::Instruction;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -15,6 +33,9 @@ pub struct MultisigProposal {
     pub approvals: Vec<String>,
     pub threshold: u8,
     pub status: MultisigStatus,
+    /// Backend account voting on this proposal, once it was proposed on chain
+    #[serde(default)]
+    pub backend_transaction: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -25,28 +46,422 @@ pub enum MultisigStatus {
     Rejected,
 }
 
+/// Participation summary for a single multisig member
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberActivity {
+    pub member: String,
+    pub last_approval_at: Option<i64>,
+    pub missed_recent_proposals: usize,
+    pub recent_proposals: usize,
+    pub inactive: bool,
+}
+
+/// Multisig configuration as stored in the upgrade-manager `multisig_config` PDA
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnchainMultisigConfig {
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub upgrade_authority: Pubkey,
+    /// Bot key allowed to execute approved upgrades, besides members
+    pub execution_bot: Option<Pubkey>,
+    /// Organization of each member, by index into `members`
+    pub member_organizations: Vec<u8>,
+    /// Distinct organizations that must approve; 0 when not enforced
+    pub organization_quorum: u8,
+}
+
+impl OnchainMultisigConfig {
+    /// Decode the Anchor account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let invalid = || UpgradeError::MultisigError("Invalid multisig_config account data".to_string());
+
+        let mut offset = 8;
+        let len_bytes = data.get(offset..offset + 4).ok_or_else(invalid)?;
+        let member_count = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        offset += 4;
+
+        let mut members = Vec::with_capacity(member_count);
+        for _ in 0..member_count {
+            let bytes = data.get(offset..offset + 32).ok_or_else(invalid)?;
+            members.push(Pubkey::new_from_array(bytes.try_into().unwrap()));
+            offset += 32;
+        }
+
+        let threshold = *data.get(offset).ok_or_else(invalid)?;
+        offset += 1;
+
+        let authority_bytes = data.get(offset..offset + 32).ok_or_else(invalid)?;
+        let upgrade_authority = Pubkey::new_from_array(authority_bytes.try_into().unwrap());
+        offset += 32;
+
+        let execution_bot = match *data.get(offset).ok_or_else(invalid)? {
+            0 => None,
+            1 => {
+                let bytes = data.get(offset + 1..offset + 33).ok_or_else(invalid)?;
+                offset += 32;
+                Some(Pubkey::new_from_array(bytes.try_into().unwrap()))
+            }
+            _ => return Err(invalid()),
+        };
+        offset += 1 + 1; // execution_bot tag, bump
+
+        // Configs created before organizations existed end at the bump
+        let (member_organizations, organization_quorum) = match data.get(offset..offset + 4) {
+            Some(len_bytes) => {
+                let count = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+                offset += 4;
+                let organizations = data.get(offset..offset + count).ok_or_else(invalid)?.to_vec();
+                let quorum = *data.get(offset + count).ok_or_else(invalid)?;
+                (organizations, quorum)
+            }
+            None => (Vec::new(), 0),
+        };
+
+        Ok(Self {
+            members,
+            threshold,
+            upgrade_authority,
+            execution_bot,
+            member_organizations,
+            organization_quorum,
+        })
+    }
+}
+
+/// Latest upgrade of a managed program, from the upgrade-manager `program_meta` PDA
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnchainProgramMeta {
+    pub program: String,
+    pub version: u32,
+    pub last_upgraded_at: i64,
+    pub last_proposal: String,
+    pub code_hash: String,
+    /// The upgrade authority was removed; no further upgrades can be proposed
+    pub immutable: bool,
+    /// Authority handed over by an authority change; `None` while the program
+    /// follows the config's `upgrade_authority`
+    pub upgrade_authority: Option<String>,
+    pub meta_account: String,
+}
+
+impl OnchainProgramMeta {
+    /// Decode the Anchor account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8], meta_account: &Pubkey) -> Result<Self, UpgradeError> {
+        let invalid = || UpgradeError::SolanaError("Invalid program_meta account data".to_string());
+        let body = data.get(8..8 + 32 + 4 + 8 + 32 + 32).ok_or_else(invalid)?;
+        // `immutable` follows the bump; accounts written before it existed lack it
+        let immutable = data.get(8 + 32 + 4 + 8 + 32 + 32 + 1).is_some_and(|flag| *flag != 0);
+        // Then the `Option<Pubkey>` upgrade authority, likewise missing on older accounts
+        let authority_at = 8 + 32 + 4 + 8 + 32 + 32 + 1 + 1;
+        let upgrade_authority = match data.get(authority_at) {
+            Some(1) => {
+                let key = data.get(authority_at + 1..authority_at + 33).ok_or_else(invalid)?;
+                Some(Pubkey::new_from_array(key.try_into().unwrap()).to_string())
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            program: Pubkey::new_from_array(body[0..32].try_into().unwrap()).to_string(),
+            version: u32::from_le_bytes(body[32..36].try_into().unwrap()),
+            last_upgraded_at: i64::from_le_bytes(body[36..44].try_into().unwrap()),
+            last_proposal: Pubkey::new_from_array(body[44..76].try_into().unwrap()).to_string(),
+            code_hash: hex::encode(&body[76..108]),
+            immutable,
+            upgrade_authority,
+            meta_account: meta_account.to_string(),
+        })
+    }
+}
+
+/// Domain separator of upgrade-manager approval digests
+pub const APPROVAL_DIGEST_DOMAIN: &[u8] = b"goquant-upgrade-approval-v1";
+
+/// The digest approvers commit to, computed exactly as the upgrade-manager
+/// program does: SHA-256 of the domain, program, buffer, code hash, target
+/// version (u32 LE) and timelock duration (i64 LE)
+pub fn approval_digest(
+    program: &Pubkey,
+    buffer: &Pubkey,
+    code_hash: &[u8; 32],
+    target_version: u32,
+    timelock_duration: i64,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(APPROVAL_DIGEST_DOMAIN);
+    hasher.update(program.as_ref());
+    hasher.update(buffer.as_ref());
+    hasher.update(code_hash);
+    hasher.update(target_version.to_le_bytes());
+    hasher.update(timelock_duration.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// What an upgrade-manager proposal commits approvers to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnchainProposalCommitment {
+    pub proposal_account: String,
+    pub program: String,
+    pub buffer: String,
+    pub code_hash: String,
+    pub description: String,
+    pub target_version: u32,
+    pub approvals: Vec<String>,
+    pub threshold: u8,
+    /// `proposed`, `approved`, `timelock_active`, `executed` or `cancelled`
+    pub status: String,
+    pub approval_digest: String,
+}
+
+impl OnchainProposalCommitment {
+    /// Decode the Anchor `UpgradeProposal` account data (8-byte discriminator + borsh fields)
+    pub fn try_from_account_data(data: &[u8], proposal_account: &Pubkey) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        reader.skip(8 + 32); // id, proposer
+        let program = reader.pubkey()?;
+        let buffer = reader.pubkey()?;
+        let code_hash = hex::encode(reader.take(32)?);
+        let description_len = reader.u32()? as usize;
+        let description = String::from_utf8_lossy(reader.take(description_len)?).to_string();
+        reader.skip(8 + 8); // proposed_at, timelock_until
+        let approvals = reader.pubkeys()?.iter().map(|key| key.to_string()).collect();
+        let threshold = reader.u8()?;
+        let status = match reader.u8()? {
+            0 => "proposed",
+            1 => "approved",
+            2 => "timelock_active",
+            3 => "executed",
+            4 => "cancelled",
+            _ => return Err(UpgradeError::SolanaError("Invalid proposal status".to_string())),
+        };
+        if reader.take(1)?[0] == 1 {
+            reader.skip(8); // executed_at
+        }
+        let cancel_votes = reader.u32()? as usize;
+        reader.skip(cancel_votes * 32);
+        if reader.take(1)?[0] == 1 {
+            reader.skip(1); // cancellation_reason
+        }
+        let details_len = reader.u32()? as usize;
+        reader.skip(details_len + 1); // cancellation_details, bump
+        let target_version = reader.u32()?;
+        let approval_digest = hex::encode(reader.take(32)?);
+
+        Ok(Self {
+            proposal_account: proposal_account.to_string(),
+            program: program.to_string(),
+            buffer: buffer.to_string(),
+            code_hash,
+            description,
+            target_version,
+            approvals,
+            threshold,
+            status: status.to_string(),
+            approval_digest,
+        })
+    }
+
+    /// Recompute the digest from the proposal's fields; true when it matches
+    /// the one stored on chain
+    pub fn verify(&self, timelock_duration: i64) -> bool {
+        let (Ok(program), Ok(buffer), Ok(code_hash)) = (
+            Pubkey::from_str(&self.program),
+            Pubkey::from_str(&self.buffer),
+            hex::decode(&self.code_hash),
+        ) else {
+            return false;
+        };
+        let Ok(code_hash) = <[u8; 32]>::try_from(code_hash.as_slice()) else {
+            return false;
+        };
+
+        hex::encode(approval_digest(&program, &buffer, &code_hash, self.target_version, timelock_duration))
+            == self.approval_digest
+    }
+}
+
+/// Sequential borsh field reader over account data
+pub(crate) struct AccountReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> AccountReader<'a> {
+    /// Start reading at the first byte, for non-Anchor accounts
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Start reading after the 8-byte Anchor discriminator
+    pub(crate) fn anchor(data: &'a [u8]) -> Self {
+        Self { data, offset: 8 }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], UpgradeError> {
+        let bytes = self.data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| UpgradeError::SolanaError("Invalid account data".to_string()))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn skip(&mut self, len: usize) {
+        self.offset += len;
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, UpgradeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, UpgradeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, UpgradeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, UpgradeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn pubkey(&mut self) -> Result<Pubkey, UpgradeError> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+
+    /// Borsh `Vec<Pubkey>`
+    pub(crate) fn pubkeys(&mut self) -> Result<Vec<Pubkey>, UpgradeError> {
+        (0..self.u32()?).map(|_| self.pubkey()).collect()
+    }
+
+    /// Borsh `Vec<u8>`
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8], UpgradeError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Distinct organizations among `approvers`, as the upgrade-manager program
+/// counts them: members without an organization are not counted
+pub fn organization_count(approvers: &[String], member_organizations: &HashMap<String, String>) -> usize {
+    let mut organizations: Vec<&String> = approvers
+        .iter()
+        .filter_map(|approver| member_organizations.get(approver))
+        .collect();
+    organizations.sort();
+    organizations.dedup();
+    organizations.len()
+}
+
+/// Parse `MULTISIG_MEMBER_ORGANIZATIONS`: comma-separated `member=organization` pairs
+pub fn parse_member_organizations(value: &str) -> Result<HashMap<String, String>, UpgradeError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((member, organization)) if !member.trim().is_empty() && !organization.trim().is_empty() => {
+                Ok((member.trim().to_string(), organization.trim().to_string()))
+            }
+            _ => Err(UpgradeError::InvalidRequest(format!(
+                "Invalid MULTISIG_MEMBER_ORGANIZATIONS entry '{}', expected member=organization",
+                pair
+            ))),
+        })
+        .collect()
+}
+
+/// Backend multisig configuration, cross-checked against chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigConfigView {
+    pub members: Vec<String>,
+    pub threshold: u8,
+    pub upgrade_authority: Option<String>,
+    /// `native`, `squads_v3`, `squads_v4`, `realms` or `tss`
+    pub backend: MultisigBackendKind,
+    pub squads_vault: Option<String>,
+    /// On-chain execution bot, when one is configured
+    pub execution_bot: Option<String>,
+    /// Organization of each member that has one
+    pub member_organizations: HashMap<String, String>,
+    /// Distinct organizations that must approve; 0 when not enforced
+    pub organization_quorum: u8,
+    pub config_account: String,
+    pub verified: bool,
+    pub drift: Vec<String>,
+}
+
 pub struct MultisigCoordinator {
     proposals: Arc<Mutex<Vec<MultisigProposal>>>,
     members: Vec<String>,
     threshold: u8,
-    squads_client: Option<Arc<SquadsClient>>,
+    member_organizations: HashMap<String, String>,
+    organization_quorum: u8,
+    backend: Arc<dyn MultisigBackend>,
+    /// Upgrade-manager PDAs and instructions, whichever backend votes
+    native: NativeBackend,
+    /// Member key that proposes and executes through the backend
+    executor: Option<SharedSigner>,
+    managed_program: Option<Pubkey>,
     multisig_vault: Option<Pubkey>,
+    rpc_client: Option<RpcClient>,
+    /// Redundant providers for the buffer read that gates execution
+    rpc_quorum: Option<Arc<QuorumRpc>>,
+    last_approvals: Arc<Mutex<HashMap<String, i64>>>,
+    started_at: i64,
+    inactivity_proposal_window: usize,
+    inactivity_max_days: i64,
+    error_decoder: Arc<ErrorDecoder>,
 }
 
 impl MultisigCoordinator {
     pub async fn new() -> Result<Self, UpgradeError> {
-        // Initialize with optional Squads Protocol integration
         let rpc_url = std::env::var("SOLANA_RPC_URL")
             .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
-        
-        let multisig_vault_str = std::env::var("MULTISIG_VAULT").ok();
-        let multisig_vault = multisig_vault_str
-            .as_ref()
-            .and_then(|s| Pubkey::from_str(s).ok());
-        
-        let squads_client = multisig_vault.map(|vault| {
-            Arc::new(SquadsClient::new(rpc_url, vault, 3).unwrap())
-        });
+
+        let upgrade_manager_program = std::env::var("UPGRADE_MANAGER_PROGRAM_ID")
+            .ok()
+            .and_then(|s| Pubkey::from_str(&s).ok())
+            .unwrap_or_else(|| {
+                Pubkey::from_str("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS").unwrap()
+            });
+
+        // Native upgrade-manager multisig, or Squads v3/v4 per MULTISIG_BACKEND
+        let async_rpc = Arc::new(AsyncRpcClient::new(rpc_url.clone()));
+        let backend = backend_from_env(async_rpc.clone(), upgrade_manager_program)?;
+        let native = NativeBackend::new(async_rpc, upgrade_manager_program);
+
+        // An explicit MULTISIG_VAULT wins over the vault the backend derives
+        let multisig_vault = std::env::var("MULTISIG_VAULT")
+            .ok()
+            .and_then(|s| Pubkey::from_str(&s).ok())
+            .or_else(|| backend.vault());
+        let rpc_client = Some(RpcClient::new(rpc_url));
+
+        let managed_program = std::env::var("MANAGED_PROGRAM_ID")
+            .ok()
+            .and_then(|s| Pubkey::from_str(&s).ok());
+
+        // A member is flagged inactive after missing the last N proposals
+        // or going M days without approving anything
+        let inactivity_proposal_window = std::env::var("INACTIVITY_PROPOSAL_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let inactivity_max_days = std::env::var("INACTIVITY_MAX_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+
+        // Approvals must come from this many distinct organizations, so one
+        // party holding several member keys cannot meet the threshold alone
+        let member_organizations = parse_member_organizations(
+            &std::env::var("MULTISIG_MEMBER_ORGANIZATIONS").unwrap_or_default(),
+        )?;
+        let organization_quorum = std::env::var("MULTISIG_ORGANIZATION_QUORUM")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
         
         Ok(Self {
             proposals: Arc::new(Mutex::new(Vec::new())),
@@ -58,17 +473,116 @@ impl MultisigCoordinator {
                 "member5".to_string(),
             ],
             threshold: 3,
-            squads_client,
+            member_organizations,
+            organization_quorum,
+            backend,
+            native,
+            executor: None,
+            managed_program,
             multisig_vault,
+            rpc_client,
+            rpc_quorum: None,
+            last_approvals: Arc::new(Mutex::new(HashMap::new())),
+            started_at: chrono::Utc::now().timestamp(),
+            inactivity_proposal_window,
+            inactivity_max_days,
+            error_decoder: Arc::new(ErrorDecoder::new()),
         })
     }
 
+    /// Replace the member set and threshold, e.g. to mirror a multisig read
+    /// from chain or to build one for tests
+    pub fn with_members(mut self, members: Vec<String>, threshold: u8) -> Result<Self, UpgradeError> {
+        if threshold == 0 || threshold as usize > members.len() {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "threshold must be between 1 and {} members",
+                members.len()
+            )));
+        }
+        self.members = members;
+        self.threshold = threshold;
+        Ok(self)
+    }
+
+    /// Propose and execute through the backend with this member key. Without
+    /// one, proposals are only tracked off chain.
+    pub fn with_executor(mut self, executor: Option<SharedSigner>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Name program errors in failed multisig transactions using the programs' IDLs
+    pub fn with_error_decoder(mut self, error_decoder: Arc<ErrorDecoder>) -> Self {
+        self.error_decoder = error_decoder;
+        self
+    }
+
+    /// Read the buffer from redundant providers before executing from it
+    pub fn with_rpc_quorum(mut self, rpc_quorum: Arc<QuorumRpc>) -> Self {
+        self.rpc_quorum = Some(rpc_quorum);
+        self
+    }
+
+    pub fn backend(&self) -> Arc<dyn MultisigBackend> {
+        self.backend.clone()
+    }
+
     pub async fn propose_transaction(
         &self,
         params: crate::proposal::ProposalParams,
     ) -> Result<String, UpgradeError> {
         let proposal_id = uuid::Uuid::new_v4().to_string();
 
+        // A deployment targets its new program rather than the managed one
+        let program = match &params.deployment {
+            Some(deployment) => Some(Pubkey::from_str(&deployment.program_id).map_err(|_| UpgradeError::InvalidPubkey)?),
+            None => self.managed_program,
+        };
+
+        // Put the upgrade up for a vote on chain when there is a key to do it with
+        let mut backend_transaction = None;
+        if let (Some(executor), Some(program)) = (&self.executor, program) {
+            let proposed = match (&params.deployment, &params.authority_change) {
+                (Some(deployment), _) => {
+                    self.backend
+                        .propose_deploy(
+                            &executor.pubkey(),
+                            &program,
+                            &params.buffer,
+                            deployment.max_data_len,
+                            &params.description,
+                        )
+                        .await?
+                }
+                (None, Some(change)) => {
+                    self.backend
+                        .propose_set_authority(
+                            &executor.pubkey(),
+                            &program,
+                            change.new_authority_key()?.as_ref(),
+                            &params.description,
+                        )
+                        .await?
+                }
+                (None, None) => {
+                    self.backend
+                        .propose(&executor.pubkey(), &program, &params.buffer, &params.description)
+                        .await?
+                }
+            };
+            // TSS sessions open on the coordinator, with nothing to send
+            if !proposed.instructions.is_empty() {
+                let signature = self.send(executor, &proposed.instructions, None, true).await?;
+                tracing::info!(
+                    "Proposed on {} as {} ({})",
+                    self.backend.kind().as_str(),
+                    proposed.key,
+                    signature
+                );
+            }
+            backend_transaction = Some(proposed.key.to_string());
+        }
+
         let proposal = MultisigProposal {
             id: proposal_id.clone(),
             instruction: params.instruction,
@@ -77,6 +591,7 @@ impl MultisigCoordinator {
             approvals: vec![],
             threshold: self.threshold,
             status: MultisigStatus::Pending,
+            backend_transaction,
         };
 
         let mut proposals = self.proposals.lock().await;
@@ -87,71 +602,150 @@ impl MultisigCoordinator {
         Ok(proposal_id)
     }
 
-    pub async fn approve_proposal(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+    /// Records an approval and returns the approving member
+    pub async fn approve_proposal(&self, proposal_id: &str) -> Result<String, UpgradeError> {
+        // In real implementation, verify signer is a multisig member
+        let approver = "member1".to_string(); // Get from context
+
+        self.record_approval(proposal_id, approver).await
+    }
+
+    /// Approve with `member`'s own key, voting through the backend when the
+    /// proposal is on chain. Used by the policy bot.
+    pub async fn approve_as(&self, proposal_id: &str, member: &SharedSigner) -> Result<String, UpgradeError> {
+        let approver = member.pubkey().to_string();
+        let proposal = self.get_proposal(proposal_id).await?;
+        if proposal.approvals.contains(&approver) {
+            return Err(UpgradeError::InternalError("Already approved".to_string()));
+        }
+
+        if let Some(key) = &proposal.backend_transaction {
+            let key = Pubkey::from_str(key).map_err(|_| UpgradeError::InvalidPubkey)?;
+            let instructions = self.backend.approve(&member.pubkey(), &key).await?;
+            let signature = self.send(member, &instructions, None, true).await?;
+            tracing::info!("{} approved on {} ({})", approver, self.backend.kind().as_str(), signature);
+        }
+
+        self.record_approval(proposal_id, approver).await
+    }
+
+    /// Record `member`'s approval once the caller has checked their signature,
+    /// as for a batch approval
+    pub async fn approve_signed(&self, proposal_id: &str, member: &str) -> Result<String, UpgradeError> {
+        if !self.members.iter().any(|m| m == member) {
+            return Err(UpgradeError::NotMultisigMember);
+        }
+
+        self.record_approval(proposal_id, member.to_string()).await
+    }
+
+    async fn record_approval(&self, proposal_id: &str, approver: String) -> Result<String, UpgradeError> {
         let mut proposals = self.proposals.lock().await;
         let proposal = proposals
             .iter_mut()
             .find(|p| p.id == proposal_id)
             .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
 
-        // In real implementation, verify signer is a multisig member
-        let approver = "member1".to_string(); // Get from context
-
         if proposal.approvals.contains(&approver) {
             return Err(UpgradeError::InternalError("Already approved".to_string()));
         }
 
         proposal.approvals.push(approver.clone());
 
+        self.last_approvals
+            .lock()
+            .await
+            .insert(approver.clone(), chrono::Utc::now().timestamp());
+
         // Check if threshold met
         if proposal.approvals.len() >= proposal.threshold as usize {
             proposal.status = MultisigStatus::Approved;
             tracing::info!("Proposal approved! Threshold met: {}", proposal_id);
         }
 
-        Ok(())
+        Ok(approver)
+    }
+
+    /// Withdraws the calling member's approval and returns the member
+    pub async fn revoke_proposal_approval(&self, proposal_id: &str) -> Result<String, UpgradeError> {
+        // In real implementation, verify signer is a multisig member
+        let approver = "member1".to_string(); // Get from context
+
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        // Approvals are final once the threshold is met
+        if proposal.status != MultisigStatus::Pending {
+            return Err(UpgradeError::InvalidTransition {
+                from: format!("{:?}", proposal.status),
+                event: "Revoke".to_string(),
+            });
+        }
+        let before = proposal.approvals.len();
+        proposal.approvals.retain(|a| *a != approver);
+        if proposal.approvals.len() == before {
+            return Err(UpgradeError::InvalidRequest(format!("{} has not approved", approver)));
+        }
+
+        tracing::info!("{} revoked their approval of {}", approver, proposal_id);
+        Ok(approver)
     }
 
-    pub async fn execute_transaction(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+    /// Execute an approved proposal, running `extension` (see
+    /// `program_extension`) in the same transaction first. Returns the
+    /// signature of the submitted transaction, if one was sent, so the caller
+    /// can follow its confirmation.
+    pub async fn execute_transaction(
+        &self,
+        proposal_id: &str,
+        extension: Option<Instruction>,
+    ) -> Result<Option<Signature>, UpgradeError> {
         let mut proposals = self.proposals.lock().await;
         let proposal = proposals
             .iter_mut()
             .find(|p| p.id == proposal_id)
             .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
 
+        // TSS parties approve by joining the signing session, so take approvals from it
+        if let (MultisigBackendKind::Tss, Some(key)) = (self.backend.kind(), &proposal.backend_transaction) {
+            let key = Pubkey::from_str(key).map_err(|_| UpgradeError::InvalidPubkey)?;
+            let session = self.backend.status(&key).await?;
+            for party in session.approvals {
+                if !proposal.approvals.contains(&party) {
+                    proposal.approvals.push(party);
+                }
+            }
+            if session.executable {
+                proposal.status = MultisigStatus::Approved;
+            }
+        }
+
         if proposal.status != MultisigStatus::Approved {
             return Err(UpgradeError::InternalError(
                 "Proposal not approved".to_string(),
             ));
         }
 
-        // Execute via Squads Protocol if available
-        if let Some(squads) = &self.squads_client {
-            if let Some(vault) = self.multisig_vault {
-                // Build upgrade instruction
-                // In production, this would use actual program/buffer addresses
-                let program_id = Pubkey::default(); // Placeholder
-                let buffer = Pubkey::default(); // Placeholder
-                let upgrade_authority = vault;
-                let program_data = Pubkey::default(); // Placeholder
-                
-                let upgrade_ix = squads.build_upgrade_instruction(
-                    &program_id,
-                    &buffer,
-                    &upgrade_authority,
-                    &program_data,
-                )?;
-                
-                // Execute via Squads
-                let tx_sig = squads.execute_transaction(&vault).await?;
-                tracing::info!("Squads transaction executed: {}", tx_sig);
-            }
+        let mut signature = None;
+
+        // Execute through the backend when the proposal was put up on chain
+        if let (Some(executor), Some(key)) = (&self.executor, &proposal.backend_transaction) {
+            let key = Pubkey::from_str(key).map_err(|_| UpgradeError::InvalidPubkey)?;
+            let mut instructions: Vec<Instruction> = extension.into_iter().collect();
+            instructions.extend(self.backend.execute(&executor.pubkey(), &key).await?);
+            let authority = self.backend.authority_signer(&key);
+            let tx_sig = self.send(executor, &instructions, authority.as_ref(), false).await?;
+            tracing::info!("{} transaction executed: {}", self.backend.kind().as_str(), tx_sig);
+            signature = Some(tx_sig);
         }
 
         proposal.status = MultisigStatus::Executed;
         tracing::info!("Transaction executed: {}", proposal_id);
 
-        Ok(())
+        Ok(signature)
     }
 
     pub async fn get_proposal(&self, proposal_id: &str) -> Result<MultisigProposal, UpgradeError> {
@@ -170,5 +764,505 @@ impl MultisigCoordinator {
     pub fn get_threshold(&self) -> u8 {
         self.threshold
     }
-}
 
+    pub fn get_organization_quorum(&self) -> u8 {
+        self.organization_quorum
+    }
+
+    /// Distinct organizations among `approvers`
+    pub fn organizations_of(&self, approvers: &[String]) -> usize {
+        organization_count(approvers, &self.member_organizations)
+    }
+
+    /// Summarize each member's recent participation and flag dead keys
+    pub async fn get_member_activity(&self) -> Vec<MemberActivity> {
+        let proposals = self.proposals.lock().await;
+        let last_approvals = self.last_approvals.lock().await;
+        let now = chrono::Utc::now().timestamp();
+        let max_idle_seconds = self.inactivity_max_days * 24 * 60 * 60;
+
+        let recent: Vec<&MultisigProposal> = proposals
+            .iter()
+            .rev()
+            .take(self.inactivity_proposal_window)
+            .collect();
+
+        self.members
+            .iter()
+            .map(|member| {
+                let last_approval_at = last_approvals.get(member).copied();
+                let missed_recent_proposals = recent
+                    .iter()
+                    .filter(|p| !p.approvals.contains(member))
+                    .count();
+
+                let missed_window = recent.len() >= self.inactivity_proposal_window
+                    && missed_recent_proposals == recent.len();
+                let idle_since = last_approval_at.unwrap_or(self.started_at);
+                let idle_too_long = now - idle_since > max_idle_seconds;
+
+                MemberActivity {
+                    member: member.clone(),
+                    last_approval_at,
+                    missed_recent_proposals,
+                    recent_proposals: recent.len(),
+                    inactive: missed_window || idle_too_long,
+                }
+            })
+            .collect()
+    }
+
+    /// Probe the RPC node; fails when it is unreachable or reports itself unhealthy
+    pub async fn check_rpc_health(&self) -> Result<(), UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        client.get_health()
+            .map_err(|e| UpgradeError::SolanaError(format!("RPC health check failed: {}", e)))
+    }
+
+    /// Probe the Squads multisig, Realms governance account or TSS coordinator. `None` on the native backend.
+    pub async fn check_squads_health(&self) -> Option<Result<(), UpgradeError>> {
+        if self.backend.kind() == MultisigBackendKind::Native {
+            return None;
+        }
+
+        Some(self.backend.check_health().await)
+    }
+
+    /// Sign `instructions` with the executor, and `authority` when the backend's
+    /// authority signs directly, and send them. Proposals wait for
+    /// confirmation so members can vote straight away; executions are
+    /// followed by the confirmation tracker instead.
+    async fn send(
+        &self,
+        executor: &SharedSigner,
+        instructions: &[Instruction],
+        authority: Option<&SharedSigner>,
+        confirm: bool,
+    ) -> Result<Signature, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let blockhash = client.get_latest_blockhash()
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get blockhash: {}", e)))?;
+        let mut tx = Transaction::new_with_payer(instructions, Some(&executor.pubkey()));
+        sign_transaction(&mut tx, executor.as_ref(), &[], blockhash).await?;
+        if let Some(authority) = authority {
+            sign_transaction(&mut tx, authority.as_ref(), &[], blockhash).await?;
+        }
+
+        let result = if confirm {
+            client.send_and_confirm_transaction(&tx)
+        } else {
+            client.send_transaction(&tx)
+        };
+        result.map_err(|e| {
+            let programs: Vec<Pubkey> = instructions.iter().map(|instruction| instruction.program_id).collect();
+            let error = self.error_decoder.describe(&e.to_string(), &programs);
+            UpgradeError::MultisigError(format!("Multisig transaction failed: {}", error))
+        })
+    }
+
+    /// Address of the upgrade-manager `multisig_config` PDA
+    pub fn config_address(&self) -> Pubkey {
+        self.native.config_address()
+    }
+
+    /// Address of the upgrade-manager `program_meta` PDA for a managed program
+    pub fn program_meta_address(&self, program: &Pubkey) -> Pubkey {
+        self.native.program_meta_address(program)
+    }
+
+    /// Current version and code hash of a managed program in one account fetch
+    pub async fn fetch_program_meta(&self, program: &Pubkey) -> Result<OnchainProgramMeta, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.program_meta_address(program);
+        let account = client.get_account(&address)
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch program meta: {}", e)))?;
+
+        OnchainProgramMeta::try_from_account_data(&account.data, &address)
+    }
+
+    /// Check the buffer is held by the multisig vault, or the upgrade authority
+    /// when there is no vault, before anything executes from it
+    pub async fn verify_buffer_handoff(&self, buffer: &Pubkey) -> Result<(), UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let expected = match self.multisig_vault {
+            Some(vault) => vault,
+            None => self.backend.upgrade_authority().await?,
+        };
+        let account = match &self.rpc_quorum {
+            Some(quorum) => quorum
+                .account(buffer)
+                .await?
+                .ok_or_else(|| UpgradeError::BufferNotFound(buffer.to_string()))?,
+            None => client.get_account(buffer)
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch buffer {}: {}", buffer, e)))?,
+        };
+
+        ensure_buffer_handed_off(&account.owner, &account.data, &expected)
+    }
+
+    /// The buffer and, once `program` is deployed, its program data account
+    async fn upgrade_accounts(
+        &self,
+        program: &Pubkey,
+        buffer: &Pubkey,
+    ) -> Result<(Account, Option<Account>), UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let (program_data, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+        let (buffer_account, program_data_account) = match &self.rpc_quorum {
+            Some(quorum) => (quorum.account(buffer).await?, quorum.account(&program_data).await?),
+            None => {
+                let fetch = |address: &Pubkey| {
+                    client.get_account_with_commitment(address, client.commitment())
+                        .map(|response| response.value)
+                        .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch account {}: {}", address, e)))
+                };
+                (fetch(buffer)?, fetch(&program_data)?)
+            }
+        };
+        let buffer_account = buffer_account.ok_or_else(|| UpgradeError::BufferNotFound(buffer.to_string()))?;
+
+        Ok((buffer_account, program_data_account))
+    }
+
+    /// How the program in `buffer` fits `program`'s data account; `None`
+    /// while `program` is not deployed
+    pub async fn program_capacity(
+        &self,
+        program: &Pubkey,
+        buffer: &Pubkey,
+    ) -> Result<Option<ProgramCapacity>, UpgradeError> {
+        let (buffer_account, program_data_account) = self.upgrade_accounts(program, buffer).await?;
+
+        Ok(program_data_account
+            .map(|account| ProgramCapacity::from_account_lens(buffer_account.data.len(), account.data.len())))
+    }
+
+    /// The `ExtendProgram` step upgrading `program` from `buffer` needs, with
+    /// the rent it costs; `None` when the program fits or is not deployed
+    pub async fn extension_cost(
+        &self,
+        program: &Pubkey,
+        buffer: &Pubkey,
+    ) -> Result<Option<ExtensionCost>, UpgradeError> {
+        let (buffer_account, Some(program_data_account)) = self.upgrade_accounts(program, buffer).await? else {
+            return Ok(None);
+        };
+        let capacity = ProgramCapacity::from_account_lens(buffer_account.data.len(), program_data_account.data.len());
+        if capacity.extension_needed() == 0 {
+            return Ok(None);
+        }
+
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+        let rent_exempt_minimum = client
+            .get_minimum_balance_for_rent_exemption(capacity.extended_account_len())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get rent: {}", e)))?;
+
+        Ok(ExtensionCost::new(&capacity, rent_exempt_minimum, program_data_account.lamports))
+    }
+
+    /// `ExtendProgram` to run before upgrading `program` from `buffer`, when
+    /// the new program does not fit its data account. The executor pays the
+    /// rent; without one nothing is sent on chain, so nothing is extended.
+    pub async fn program_extension(
+        &self,
+        program: &Pubkey,
+        buffer: &Pubkey,
+    ) -> Result<Option<Instruction>, UpgradeError> {
+        let Some(executor) = &self.executor else {
+            return Ok(None);
+        };
+        let Some(cost) = self.extension_cost(program, buffer).await? else {
+            return Ok(None);
+        };
+
+        tracing::warn!(
+            "Extending program data of {} by {} bytes before the upgrade ({} lamports rent)",
+            program,
+            cost.additional_bytes,
+            cost.rent_lamports
+        );
+        Ok(Some(cost.instruction(program, &executor.pubkey())))
+    }
+
+    /// Address of the upgrade-manager proposal PDA for `buffer` on `program`
+    pub fn proposal_address(&self, program: &Pubkey, buffer: &Pubkey) -> Pubkey {
+        self.native.proposal_address(program, buffer)
+    }
+
+    pub async fn fetch_proposal_commitment(
+        &self,
+        program: &Pubkey,
+        buffer: &Pubkey,
+    ) -> Result<OnchainProposalCommitment, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.proposal_address(program, buffer);
+        let account = client.get_account(&address)
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch proposal: {}", e)))?;
+
+        OnchainProposalCommitment::try_from_account_data(&account.data, &address)
+    }
+
+    /// Upgrade-manager `approve_upgrade` instruction committing `approver` to `approval_digest`
+    pub fn build_approve_instruction(
+        &self,
+        approver: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        approval_digest: &[u8; 32],
+    ) -> Instruction {
+        self.native
+            .approve_instruction(approver, &self.proposal_address(program, buffer), approval_digest)
+    }
+
+    /// Upgrade-manager `revoke_approval` instruction withdrawing `approver`'s approval
+    pub fn build_revoke_instruction(&self, approver: &Pubkey, program: &Pubkey, buffer: &Pubkey) -> Instruction {
+        self.native
+            .revoke_instruction(approver, &self.proposal_address(program, buffer))
+    }
+
+    /// Upgrade-manager `execute_upgrade` instruction for the proposal of `buffer` on `program`
+    pub fn build_execute_instruction(&self, executor: &Pubkey, program: &Pubkey, buffer: &Pubkey) -> Instruction {
+        self.native.execute_instruction(executor, program, buffer)
+    }
+
+    /// Address of the upgrade-manager `feature_flags` PDA for a managed program
+    pub fn feature_flags_address(&self, program: &Pubkey) -> Pubkey {
+        self.native.feature_flags_address(program)
+    }
+
+    /// Feature flags of a managed program; empty until the first flag is set
+    pub async fn fetch_feature_flags(&self, program: &Pubkey) -> Result<OnchainFeatureFlags, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.feature_flags_address(program);
+        let account = client.get_account_with_commitment(&address, client.commitment())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch feature flags: {}", e)))?
+            .value;
+
+        match account {
+            Some(account) => OnchainFeatureFlags::try_from_account_data(&account.data, &address),
+            None => Ok(OnchainFeatureFlags::empty(program, &address)),
+        }
+    }
+
+    /// Propose a change to flag `name` on chain from the executor key,
+    /// against the flag's current revision
+    pub async fn propose_flag_change(
+        &self,
+        program: &Pubkey,
+        name: &str,
+        change: &FlagChangeRequest,
+    ) -> Result<FlagChangeProposal, UpgradeError> {
+        let executor = self.flag_signer()?;
+        let encoded = encode_flag_name(name)?;
+        let revision = self.fetch_feature_flags(program).await?.revision(name);
+        let timelock_duration = self.fetch_timelock_duration().await?;
+
+        let instruction = self.native
+            .propose_flag_change_instruction(&executor.pubkey(), program, &encoded, revision, change);
+        let signature = self.send(executor, &[instruction], None, true).await?;
+        let target = flag_change_target(&encoded, change.enabled, change.kill_switch, revision);
+        let approval_digest =
+            flag_change_digest(program, &encoded, change.enabled, change.kill_switch, revision, timelock_duration);
+
+        tracing::info!(
+            "Proposed setting flag {} of {} to enabled={} ({})",
+            name,
+            program,
+            change.enabled,
+            signature
+        );
+
+        Ok(FlagChangeProposal {
+            proposal_account: self.proposal_address(program, &target).to_string(),
+            program: program.to_string(),
+            name: name.to_string(),
+            enabled: change.enabled,
+            kill_switch: change.kill_switch,
+            revision,
+            approval_digest: hex::encode(approval_digest),
+            signature: signature.to_string(),
+        })
+    }
+
+    /// Apply an approved flag change proposal once its timelock has run
+    pub async fn set_flag(&self, program: &Pubkey, proposal: &Pubkey) -> Result<Signature, UpgradeError> {
+        let executor = self.flag_signer()?;
+        let instruction = self.native.set_flag_instruction(&executor.pubkey(), program, proposal);
+        let signature = self.send(executor, &[instruction], None, true).await?;
+
+        tracing::info!("Applied flag change {} on {} ({})", proposal, program, signature);
+        Ok(signature)
+    }
+
+    /// Turn kill-switch flag `name` off at once; the executor must be a member
+    pub async fn trip_kill_switch(&self, program: &Pubkey, name: &str) -> Result<Signature, UpgradeError> {
+        let executor = self.flag_signer()?;
+        let encoded = encode_flag_name(name)?;
+        let instruction = self.native.trip_kill_switch_instruction(&executor.pubkey(), program, &encoded);
+        let signature = self.send(executor, &[instruction], None, true).await?;
+
+        tracing::warn!("Kill switch {} of {} tripped by {} ({})", name, program, executor.pubkey(), signature);
+        Ok(signature)
+    }
+
+    /// Programs the multisig can pause; empty until the first is registered
+    pub async fn fetch_pause_registry(&self) -> Result<OnchainPauseRegistry, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.native.pause_registry_address();
+        let account = client.get_account_with_commitment(&address, client.commitment())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch pause registry: {}", e)))?
+            .value;
+
+        match account {
+            Some(account) => OnchainPauseRegistry::try_from_account_data(&account.data, &address),
+            None => Ok(OnchainPauseRegistry::empty(&address)),
+        }
+    }
+
+    /// Open emergency pause votes for `program`
+    pub async fn fetch_pause_votes(&self, program: &Pubkey) -> Result<OnchainPauseVotes, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.native.pause_votes_address(program);
+        let account = client.get_account_with_commitment(&address, client.commitment())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch pause votes: {}", e)))?
+            .value;
+
+        match account {
+            Some(account) => OnchainPauseVotes::try_from_account_data(&account.data, &address),
+            None => Ok(OnchainPauseVotes::empty(program, &address)),
+        }
+    }
+
+    /// Vote to pause `program` from the executor key, which must be a member.
+    /// Returns the voter and the transaction signature.
+    pub async fn emergency_pause(
+        &self,
+        program: &Pubkey,
+        reason: &str,
+        pause_accounts: &[Pubkey],
+    ) -> Result<(Pubkey, Signature), UpgradeError> {
+        let executor = self.executor.as_ref().ok_or_else(|| {
+            UpgradeError::InvalidRequest("No member key is configured to vote for an emergency pause".to_string())
+        })?;
+        let instruction = self.native
+            .emergency_pause_instruction(&executor.pubkey(), program, reason, pause_accounts);
+        let signature = self.send(executor, &[instruction], None, true).await?;
+
+        tracing::error!("Emergency pause vote for {} cast by {} ({})", program, executor.pubkey(), signature);
+        Ok((executor.pubkey(), signature))
+    }
+
+    fn flag_signer(&self) -> Result<&SharedSigner, UpgradeError> {
+        self.executor.as_ref().ok_or_else(|| {
+            UpgradeError::InvalidRequest("No member key is configured to sign feature flag changes".to_string())
+        })
+    }
+
+    /// Timelock duration from the upgrade-manager `program_upgrade_state` PDA
+    pub async fn fetch_timelock_duration(&self) -> Result<i64, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let address = self.native.upgrade_state_address();
+        let account = client.get_account(&address)
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch upgrade state: {}", e)))?;
+
+        // discriminator, authority, upgrade_buffer
+        account.data
+            .get(72..80)
+            .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(|| UpgradeError::SolanaError("Invalid program_upgrade_state account data".to_string()))
+    }
+
+    pub async fn fetch_onchain_config(&self) -> Result<OnchainMultisigConfig, UpgradeError> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| UpgradeError::InternalError("RPC client not initialized".to_string()))?;
+
+        let account = client.get_account(&self.config_address())
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch multisig config: {}", e)))?;
+
+        OnchainMultisigConfig::try_from_account_data(&account.data)
+    }
+
+    /// Return the cached config together with any drift from on-chain state
+    pub async fn get_config_view(&self) -> MultisigConfigView {
+        let mut drift = Vec::new();
+        let mut execution_bot = None;
+
+        match self.fetch_onchain_config().await {
+            Ok(onchain) => {
+                execution_bot = onchain.execution_bot.map(|bot| bot.to_string());
+
+                let onchain_members: Vec<String> =
+                    onchain.members.iter().map(|m| m.to_string()).collect();
+
+                if onchain.threshold != self.threshold {
+                    drift.push(format!(
+                        "threshold: backend={} onchain={}",
+                        self.threshold, onchain.threshold
+                    ));
+                }
+                if onchain.organization_quorum != self.organization_quorum {
+                    drift.push(format!(
+                        "organization_quorum: backend={} onchain={}",
+                        self.organization_quorum, onchain.organization_quorum
+                    ));
+                }
+
+                for member in &self.members {
+                    if !onchain_members.contains(member) {
+                        drift.push(format!("member {} not present on chain", member));
+                    }
+                }
+                for member in &onchain_members {
+                    if !self.members.contains(member) {
+                        drift.push(format!("on-chain member {} not known to backend", member));
+                    }
+                }
+
+                if let Some(vault) = self.multisig_vault {
+                    if onchain.upgrade_authority != vault {
+                        drift.push(format!(
+                            "upgrade_authority: backend={} onchain={}",
+                            vault, onchain.upgrade_authority
+                        ));
+                    }
+                }
+            }
+            Err(e) => drift.push(format!("on-chain config unavailable: {}", e)),
+        }
+
+        MultisigConfigView {
+            members: self.members.clone(),
+            threshold: self.threshold,
+            upgrade_authority: self.multisig_vault.map(|v| v.to_string()),
+            backend: self.backend.kind(),
+            squads_vault: self.multisig_vault.map(|v| v.to_string()),
+            execution_bot,
+            member_organizations: self.member_organizations.clone(),
+            organization_quorum: self.organization_quorum,
+            config_account: self.config_address().to_string(),
+            verified: drift.is_empty(),
+            drift,
+        }
+    }
+}
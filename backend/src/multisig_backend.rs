@@ -0,0 +1,665 @@
+use crate::buffer_watcher::parse_buffer;
+use crate::error::UpgradeError;
+use crate::feature_flags::{flag_change_target, FlagChangeRequest, MAX_FLAG_NAME_LEN};
+use crate::multisig::{AccountReader, OnchainMultisigConfig, OnchainProposalCommitment};
+use crate::realms::RealmsBackend;
+use crate::signer::SharedSigner;
+use crate::squads::{SquadsV3Backend, SquadsV4Backend};
+use crate::tss::TssBackend;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Which multisig holds the upgrade authority
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MultisigBackendKind {
+    /// The upgrade-manager program's own multisig
+    Native,
+    SquadsV3,
+    SquadsV4,
+    /// SPL Governance, for DAOs that vote through Realms
+    Realms,
+    /// Threshold ed25519 key held by an MPC signing service
+    Tss,
+}
+
+impl MultisigBackendKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MultisigBackendKind::Native => "native",
+            MultisigBackendKind::SquadsV3 => "squads_v3",
+            MultisigBackendKind::SquadsV4 => "squads_v4",
+            MultisigBackendKind::Realms => "realms",
+            MultisigBackendKind::Tss => "tss",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "native" => Some(MultisigBackendKind::Native),
+            "squads_v3" => Some(MultisigBackendKind::SquadsV3),
+            "squads_v4" => Some(MultisigBackendKind::SquadsV4),
+            "realms" => Some(MultisigBackendKind::Realms),
+            "tss" => Some(MultisigBackendKind::Tss),
+            _ => None,
+        }
+    }
+}
+
+/// Instructions that put an upgrade up for a vote, and the account that
+/// identifies it in later approve/execute calls
+#[derive(Debug, Clone)]
+pub struct ProposedTransaction {
+    pub key: Pubkey,
+    pub instructions: Vec<Instruction>,
+}
+
+/// Token-weighted vote totals, for backends where votes are not one per member
+#[derive(Debug, Clone, Serialize)]
+pub struct VoteTally {
+    pub yes: u64,
+    pub no: u64,
+}
+
+/// Vote state of a multisig transaction
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendTransactionStatus {
+    pub key: String,
+    pub status: String,
+    pub approvals: Vec<String>,
+    /// Signatures required, or the yes-vote percentage on Realms
+    pub threshold: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub votes: Option<VoteTally>,
+    /// Whether the transaction can be executed now
+    pub executable: bool,
+}
+
+/// A multisig that can hold a program's upgrade authority. Implementations
+/// only build instructions and read state; signing and sending is left to
+/// the caller, since members usually sign on their own devices.
+#[async_trait]
+pub trait MultisigBackend: Send + Sync {
+    fn kind(&self) -> MultisigBackendKind;
+
+    /// Program that owns the multisig accounts
+    fn program_id(&self) -> Pubkey;
+
+    /// PDA that signs the upgrade, when it can be derived without RPC
+    fn vault(&self) -> Option<Pubkey>;
+
+    /// Account that must hold the managed program's upgrade authority
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError>;
+
+    /// Propose upgrading `program` to `buffer`; `creator` signs and pays rent
+    async fn propose(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError>;
+
+    /// Whether `propose_deploy` and `propose_set_authority` are supported:
+    /// only backends that wrap arbitrary loader instructions can do more
+    /// than upgrade
+    fn wraps_loader_instructions(&self) -> bool {
+        false
+    }
+
+    /// Propose the first deployment of `program` from `buffer`, leaving the
+    /// multisig as upgrade authority
+    async fn propose_deploy(
+        &self,
+        _creator: &Pubkey,
+        _program: &Pubkey,
+        _buffer: &Pubkey,
+        _max_data_len: usize,
+        _description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        Err(UpgradeError::InvalidRequest(format!(
+            "The {} backend cannot propose initial deployments",
+            self.kind().as_str()
+        )))
+    }
+
+    /// Propose handing `program`'s upgrade authority from the multisig to
+    /// `new_authority`, or removing it with `None`
+    async fn propose_set_authority(
+        &self,
+        _creator: &Pubkey,
+        _program: &Pubkey,
+        _new_authority: Option<&Pubkey>,
+        _description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        Err(UpgradeError::InvalidRequest(format!(
+            "The {} backend cannot propose authority changes",
+            self.kind().as_str()
+        )))
+    }
+
+    async fn approve(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError>;
+
+    async fn execute(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError>;
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError>;
+
+    /// Probe the multisig account
+    async fn check_health(&self) -> Result<(), UpgradeError>;
+
+    /// Key that must sign the execute transaction besides the executor, for
+    /// backends whose authority signs directly rather than by PDA
+    fn authority_signer(&self, _transaction: &Pubkey) -> Option<SharedSigner> {
+        None
+    }
+}
+
+/// Anchor instruction discriminator: first 8 bytes of SHA-256("global:<name>")
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    Sha256::digest(format!("global:{}", name).as_bytes())[..8]
+        .try_into()
+        .unwrap()
+}
+
+/// Bytes of description the upgrade-manager program stores per proposal
+pub const MAX_ONCHAIN_DESCRIPTION_LEN: usize = 256;
+
+/// Fit `description` into the proposal account. Longer text is cut at a char
+/// boundary and pinned on chain by the SHA-256 of the full text.
+pub fn onchain_description(description: &str) -> (&str, Option<[u8; 32]>) {
+    if description.len() <= MAX_ONCHAIN_DESCRIPTION_LEN {
+        return (description, None);
+    }
+    let mut end = MAX_ONCHAIN_DESCRIPTION_LEN;
+    while !description.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&description[..end], Some(Sha256::digest(description.as_bytes()).into()))
+}
+
+/// Borsh `String`
+pub(crate) fn push_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+/// Borsh instruction as Squads v3 and SPL Governance store it:
+/// program id, `Vec<{ pubkey, is_signer, is_writable }>`, `Vec<u8>` data
+pub(crate) fn push_instruction(data: &mut Vec<u8>, instruction: &Instruction) {
+    data.extend_from_slice(instruction.program_id.as_ref());
+    data.extend_from_slice(&(instruction.accounts.len() as u32).to_le_bytes());
+    for meta in &instruction.accounts {
+        data.extend_from_slice(meta.pubkey.as_ref());
+        data.push(meta.is_signer as u8);
+        data.push(meta.is_writable as u8);
+    }
+    data.extend_from_slice(&(instruction.data.len() as u32).to_le_bytes());
+    data.extend_from_slice(&instruction.data);
+}
+
+/// Inverse of [`push_instruction`]
+pub(crate) fn read_instruction(reader: &mut AccountReader) -> Result<Instruction, UpgradeError> {
+    let program_id = reader.pubkey()?;
+    let accounts = (0..reader.u32()?)
+        .map(|_| {
+            let pubkey = reader.pubkey()?;
+            let is_signer = reader.u8()? == 1;
+            let is_writable = reader.u8()? == 1;
+            Ok(AccountMeta {
+                pubkey,
+                is_signer,
+                is_writable,
+            })
+        })
+        .collect::<Result<Vec<_>, UpgradeError>>()?;
+    let data = reader.bytes()?.to_vec();
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Mark every account as a non-signer: the vault signs by PDA inside the
+/// multisig program, not on the outer transaction
+pub(crate) fn remaining_account(pubkey: Pubkey, is_writable: bool) -> AccountMeta {
+    if is_writable {
+        AccountMeta::new(pubkey, false)
+    } else {
+        AccountMeta::new_readonly(pubkey, false)
+    }
+}
+
+pub(crate) async fn fetch_account_data(rpc_client: &AsyncRpcClient, address: &Pubkey) -> Result<Vec<u8>, UpgradeError> {
+    rpc_client
+        .get_account_data(address)
+        .await
+        .map_err(|e| UpgradeError::MultisigError(format!("Failed to fetch {}: {}", address, e)))
+}
+
+/// Backend for the upgrade-manager program's own multisig, where proposals
+/// are `UpgradeProposal` PDAs and approvals commit to the approval digest
+pub struct NativeBackend {
+    rpc_client: Arc<AsyncRpcClient>,
+    program_id: Pubkey,
+}
+
+impl NativeBackend {
+    pub fn new(rpc_client: Arc<AsyncRpcClient>, program_id: Pubkey) -> Self {
+        Self { rpc_client, program_id }
+    }
+
+    fn pda(&self, seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &self.program_id).0
+    }
+
+    pub fn config_address(&self) -> Pubkey {
+        self.pda(&[b"multisig_config"])
+    }
+
+    pub fn upgrade_state_address(&self) -> Pubkey {
+        self.pda(&[b"program_upgrade_state"])
+    }
+
+    pub fn program_meta_address(&self, program: &Pubkey) -> Pubkey {
+        self.pda(&[b"program_meta", program.as_ref()])
+    }
+
+    pub fn proposal_address(&self, program: &Pubkey, buffer: &Pubkey) -> Pubkey {
+        self.pda(&[b"proposal", program.as_ref(), buffer.as_ref()])
+    }
+
+    pub fn feature_flags_address(&self, program: &Pubkey) -> Pubkey {
+        self.pda(&[b"feature_flags", program.as_ref()])
+    }
+
+    pub fn pause_registry_address(&self) -> Pubkey {
+        self.pda(&[b"pause_registry"])
+    }
+
+    pub fn pause_votes_address(&self, program: &Pubkey) -> Pubkey {
+        self.pda(&[b"pause_votes", program.as_ref()])
+    }
+
+    /// PDA the managed programs accept as their pauser
+    pub fn pause_authority_address(&self) -> Pubkey {
+        self.pda(&[b"pause_authority"])
+    }
+
+    pub fn propose_instruction(
+        &self,
+        proposer: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+        code_hash: &[u8; 32],
+    ) -> Instruction {
+        let (description, description_hash) = onchain_description(description);
+        let mut data = instruction_discriminator("propose_upgrade").to_vec();
+        data.extend_from_slice(buffer.as_ref());
+        push_string(&mut data, description);
+        data.extend_from_slice(code_hash);
+        match description_hash {
+            Some(hash) => {
+                data.push(1);
+                data.extend_from_slice(&hash);
+            }
+            None => data.push(0),
+        }
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*proposer, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new_readonly(self.upgrade_state_address(), false),
+                AccountMeta::new_readonly(*program, false),
+                AccountMeta::new(self.proposal_address(program, buffer), false),
+                AccountMeta::new_readonly(*buffer, false),
+                AccountMeta::new_readonly(self.program_meta_address(program), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn approve_instruction(&self, approver: &Pubkey, proposal: &Pubkey, approval_digest: &[u8; 32]) -> Instruction {
+        let mut data = instruction_discriminator("approve_upgrade").to_vec();
+        data.extend_from_slice(proposal.as_ref());
+        data.extend_from_slice(approval_digest);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*approver, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new(*proposal, false),
+                AccountMeta::new_readonly(self.upgrade_state_address(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn revoke_instruction(&self, approver: &Pubkey, proposal: &Pubkey) -> Instruction {
+        let mut data = instruction_discriminator("revoke_approval").to_vec();
+        data.extend_from_slice(proposal.as_ref());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*approver, true),
+                AccountMeta::new(*proposal, false),
+            ],
+            data,
+        }
+    }
+
+    pub fn execute_instruction(&self, executor: &Pubkey, program: &Pubkey, buffer: &Pubkey) -> Instruction {
+        let proposal = self.proposal_address(program, buffer);
+        let mut data = instruction_discriminator("execute_upgrade").to_vec();
+        data.extend_from_slice(proposal.as_ref());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*executor, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new(proposal, false),
+                AccountMeta::new_readonly(self.upgrade_state_address(), false),
+                AccountMeta::new(self.program_meta_address(program), false),
+                AccountMeta::new_readonly(*buffer, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn propose_flag_change_instruction(
+        &self,
+        proposer: &Pubkey,
+        program: &Pubkey,
+        name: &[u8; MAX_FLAG_NAME_LEN],
+        revision: u32,
+        change: &FlagChangeRequest,
+    ) -> Instruction {
+        let (description, _) = onchain_description(&change.description);
+        let mut data = instruction_discriminator("propose_flag_change").to_vec();
+        data.extend_from_slice(name);
+        data.push(change.enabled as u8);
+        data.push(change.kill_switch as u8);
+        data.extend_from_slice(&revision.to_le_bytes());
+        push_string(&mut data, description);
+
+        let target = flag_change_target(name, change.enabled, change.kill_switch, revision);
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*proposer, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new_readonly(self.upgrade_state_address(), false),
+                AccountMeta::new_readonly(*program, false),
+                AccountMeta::new(self.proposal_address(program, &target), false),
+                AccountMeta::new_readonly(self.feature_flags_address(program), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn set_flag_instruction(&self, executor: &Pubkey, program: &Pubkey, proposal: &Pubkey) -> Instruction {
+        let mut data = instruction_discriminator("set_flag").to_vec();
+        data.extend_from_slice(proposal.as_ref());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*executor, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new(*proposal, false),
+                AccountMeta::new_readonly(self.upgrade_state_address(), false),
+                AccountMeta::new(self.feature_flags_address(program), false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn trip_kill_switch_instruction(
+        &self,
+        member: &Pubkey,
+        program: &Pubkey,
+        name: &[u8; MAX_FLAG_NAME_LEN],
+    ) -> Instruction {
+        let mut data = instruction_discriminator("trip_kill_switch").to_vec();
+        data.extend_from_slice(name);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*member, true),
+                AccountMeta::new_readonly(self.config_address(), false),
+                AccountMeta::new(self.feature_flags_address(program), false),
+            ],
+            data,
+        }
+    }
+
+    /// Vote to pause `program`. `pause_accounts` are the registered accounts
+    /// of its pause instruction, which the quorum vote relays the pause to.
+    pub fn emergency_pause_instruction(
+        &self,
+        voter: &Pubkey,
+        program: &Pubkey,
+        reason: &str,
+        pause_accounts: &[Pubkey],
+    ) -> Instruction {
+        let mut data = instruction_discriminator("emergency_pause").to_vec();
+        push_string(&mut data, reason);
+
+        let mut accounts = vec![
+            AccountMeta::new(*voter, true),
+            AccountMeta::new_readonly(self.config_address(), false),
+            AccountMeta::new(self.pause_registry_address(), false),
+            AccountMeta::new(self.pause_votes_address(program), false),
+            AccountMeta::new_readonly(*program, false),
+            AccountMeta::new_readonly(self.pause_authority_address(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        ];
+        accounts.extend(pause_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    async fn config(&self) -> Result<OnchainMultisigConfig, UpgradeError> {
+        let data = fetch_account_data(&self.rpc_client, &self.config_address()).await?;
+        OnchainMultisigConfig::try_from_account_data(&data)
+    }
+
+    async fn commitment(&self, proposal: &Pubkey) -> Result<OnchainProposalCommitment, UpgradeError> {
+        let data = fetch_account_data(&self.rpc_client, proposal).await?;
+        OnchainProposalCommitment::try_from_account_data(&data, proposal)
+    }
+}
+
+fn decode_digest(hex_digest: &str) -> Result<[u8; 32], UpgradeError> {
+    hex::decode(hex_digest)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| UpgradeError::MultisigError("Invalid approval digest".to_string()))
+}
+
+#[async_trait]
+impl MultisigBackend for NativeBackend {
+    fn kind(&self) -> MultisigBackendKind {
+        MultisigBackendKind::Native
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    fn vault(&self) -> Option<Pubkey> {
+        None
+    }
+
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.config().await?.upgrade_authority)
+    }
+
+    async fn propose(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        // The program stores the hash approvers commit to, so take it from the buffer itself
+        let data = fetch_account_data(&self.rpc_client, buffer).await?;
+        let (_, program_bytes) = parse_buffer(&data)
+            .ok_or_else(|| UpgradeError::InvalidRequest(format!("{} is not a loader buffer", buffer)))?;
+        let code_hash: [u8; 32] = Sha256::digest(program_bytes).into();
+
+        Ok(ProposedTransaction {
+            key: self.proposal_address(program, buffer),
+            instructions: vec![self.propose_instruction(creator, program, buffer, description, &code_hash)],
+        })
+    }
+
+    async fn approve(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let commitment = self.commitment(transaction).await?;
+        let digest = decode_digest(&commitment.approval_digest)?;
+        Ok(vec![self.approve_instruction(member, transaction, &digest)])
+    }
+
+    async fn execute(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let commitment = self.commitment(transaction).await?;
+        let program = Pubkey::from_str(&commitment.program).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let buffer = Pubkey::from_str(&commitment.buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+        Ok(vec![self.execute_instruction(member, &program, &buffer)])
+    }
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError> {
+        let commitment = self.commitment(transaction).await?;
+        Ok(BackendTransactionStatus {
+            key: transaction.to_string(),
+            executable: commitment.status == "timelock_active",
+            status: commitment.status,
+            approvals: commitment.approvals,
+            threshold: commitment.threshold as u16,
+            votes: None,
+        })
+    }
+
+    async fn check_health(&self) -> Result<(), UpgradeError> {
+        self.config().await.map(|_| ())
+    }
+}
+
+/// Select the backend from `MULTISIG_BACKEND` (`native`, `squads_v3`,
+/// `squads_v4`, `realms` or `tss`, default `native`). Squads backends need
+/// `SQUADS_MULTISIG`, the multisig account address, and take the vault from
+/// `SQUADS_VAULT_INDEX` (v4, default 0) or `SQUADS_AUTHORITY_INDEX` (v3,
+/// default 1). Realms needs `REALMS_REALM`, `REALMS_GOVERNANCE` and
+/// `REALMS_GOVERNING_TOKEN_MINT`. TSS needs `TSS_GROUP_KEY` and
+/// `TSS_COORDINATOR_URLS` (comma-separated, primary first), and reads
+/// `TSS_COORDINATOR_TOKEN`, `TSS_SIGN_ATTEMPTS` and `TSS_ROUND_TIMEOUT_SECS`.
+pub fn backend_from_env(
+    rpc_client: Arc<AsyncRpcClient>,
+    upgrade_manager_program: Pubkey,
+) -> Result<Arc<dyn MultisigBackend>, UpgradeError> {
+    let kind = match std::env::var("MULTISIG_BACKEND") {
+        Ok(value) => MultisigBackendKind::parse(&value).ok_or_else(|| {
+            UpgradeError::InvalidRequest(format!(
+                "Unknown MULTISIG_BACKEND '{}', expected native, squads_v3, squads_v4, realms or tss",
+                value
+            ))
+        })?,
+        Err(_) => MultisigBackendKind::Native,
+    };
+
+    let address = |key: &str| {
+        std::env::var(key)
+            .ok()
+            .and_then(|v| Pubkey::from_str(&v).ok())
+            .ok_or_else(|| {
+                UpgradeError::InvalidRequest(format!("{} is required for the {} backend", key, kind.as_str()))
+            })
+    };
+
+    let backend: Arc<dyn MultisigBackend> = match kind {
+        MultisigBackendKind::Native => Arc::new(NativeBackend::new(rpc_client, upgrade_manager_program)),
+        MultisigBackendKind::SquadsV3 => {
+            let authority_index = std::env::var("SQUADS_AUTHORITY_INDEX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1);
+            Arc::new(SquadsV3Backend::new(rpc_client, address("SQUADS_MULTISIG")?, authority_index))
+        }
+        MultisigBackendKind::SquadsV4 => {
+            let vault_index = std::env::var("SQUADS_VAULT_INDEX")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+            Arc::new(SquadsV4Backend::new(rpc_client, address("SQUADS_MULTISIG")?, vault_index))
+        }
+        MultisigBackendKind::Realms => {
+            let program_id = std::env::var("REALMS_PROGRAM_ID")
+                .ok()
+                .and_then(|v| Pubkey::from_str(&v).ok())
+                .unwrap_or_else(|| Pubkey::from_str(crate::realms::SPL_GOVERNANCE_PROGRAM_ID).unwrap());
+            Arc::new(RealmsBackend::new(
+                rpc_client,
+                program_id,
+                address("REALMS_REALM")?,
+                address("REALMS_GOVERNANCE")?,
+                address("REALMS_GOVERNING_TOKEN_MINT")?,
+            ))
+        }
+        MultisigBackendKind::Tss => {
+            let coordinator_urls: Vec<String> = std::env::var("TSS_COORDINATOR_URLS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect();
+            if coordinator_urls.is_empty() {
+                return Err(UpgradeError::InvalidRequest(
+                    "TSS_COORDINATOR_URLS is required for the tss backend".to_string(),
+                ));
+            }
+            let sign_attempts = std::env::var("TSS_SIGN_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3);
+            let round_timeout = std::env::var("TSS_ROUND_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20);
+            Arc::new(
+                TssBackend::new(
+                    coordinator_urls,
+                    address("TSS_GROUP_KEY")?,
+                    std::env::var("TSS_COORDINATOR_TOKEN").ok().filter(|v| !v.is_empty()),
+                )
+                .with_sign_attempts(sign_attempts)
+                .with_round_timeout(tokio::time::Duration::from_secs(round_timeout)),
+            )
+        }
+    };
+
+    tracing::info!("Multisig backend: {}", kind.as_str());
+
+    Ok(backend)
+}
@@ -0,0 +1,277 @@
+use crate::denylist;
+use crate::error::UpgradeError;
+use crate::multisig::MultisigCoordinator;
+use crate::proposal::{ProposalManager, RiskTier};
+use crate::receipts::ReceiptService;
+use crate::secrets::SecretStore;
+use crate::security::SecurityAuditor;
+use crate::signer::{LocalSigner, SharedSigner};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// One clause of a rule's `when` expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// `artifact_match`: the buffer holds exactly the CI-built release artifact
+    ArtifactMatch,
+    /// `size_delta_pct < N` or `<= N`: binary size change against the deployed program
+    SizeDelta { max_percent: f64, inclusive: bool },
+    /// `risk_tier == low`
+    RiskTierIs(RiskTier),
+    /// `risk_tier <= medium`
+    RiskTierAtMost(RiskTier),
+}
+
+impl Condition {
+    pub fn parse(clause: &str) -> Result<Self, UpgradeError> {
+        let invalid = || UpgradeError::InvalidRequest(format!("Invalid policy condition '{}'", clause));
+        let tokens: Vec<&str> = clause.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            ["artifact_match"] => Ok(Condition::ArtifactMatch),
+            ["size_delta_pct", op @ ("<" | "<="), value] => Ok(Condition::SizeDelta {
+                max_percent: value.parse().map_err(|_| invalid())?,
+                inclusive: *op == "<=",
+            }),
+            ["risk_tier", "==", tier] => RiskTier::parse(tier).map(Condition::RiskTierIs).ok_or_else(invalid),
+            ["risk_tier", "<=", tier] => RiskTier::parse(tier).map(Condition::RiskTierAtMost).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether `facts` satisfy the clause, and why. Unknown facts never satisfy it.
+    pub fn evaluate(&self, facts: &PolicyFacts) -> (bool, String) {
+        match self {
+            Condition::ArtifactMatch => match facts.artifact_match {
+                Some(true) => (true, "buffer matches the CI artifact".to_string()),
+                Some(false) => (false, "buffer does not match the CI artifact".to_string()),
+                None => (false, "no CI artifact to compare against".to_string()),
+            },
+            Condition::SizeDelta { max_percent, inclusive } => match facts.size_delta_percent {
+                Some(delta) => {
+                    let ok = if *inclusive { delta <= *max_percent } else { delta < *max_percent };
+                    let op = if *inclusive { "<=" } else { "<" };
+                    (ok, format!("size delta {:.2}% (needs {} {}%)", delta, op, max_percent))
+                }
+                None => (false, "no deployed program to compare size against".to_string()),
+            },
+            Condition::RiskTierIs(expected) => match facts.risk_tier {
+                Some(tier) => (tier == *expected, format!("risk tier {} (needs {})", tier.as_str(), expected.as_str())),
+                None => (false, "no risk tier assigned".to_string()),
+            },
+            Condition::RiskTierAtMost(max) => match facts.risk_tier {
+                Some(tier) => (tier <= *max, format!("risk tier {} (needs at most {})", tier.as_str(), max.as_str())),
+                None => (false, "no risk tier assigned".to_string()),
+            },
+        }
+    }
+}
+
+/// Rule as written in `POLICY_RULES_PATH`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRuleSpec {
+    pub name: String,
+    /// Conditions joined with `AND`, e.g.
+    /// `artifact_match AND size_delta_pct < 1 AND risk_tier == low`
+    pub when: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyRule {
+    pub name: String,
+    pub conditions: Vec<Condition>,
+}
+
+impl PolicyRule {
+    pub fn parse(spec: &PolicyRuleSpec) -> Result<Self, UpgradeError> {
+        let conditions = spec
+            .when
+            .split(" AND ")
+            .map(Condition::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| UpgradeError::InvalidRequest(format!("Policy rule '{}': {}", spec.name, e)))?;
+
+        Ok(Self {
+            name: spec.name.clone(),
+            conditions,
+        })
+    }
+}
+
+/// What is known about a proposal when rules are evaluated
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct PolicyFacts {
+    pub risk_tier: Option<RiskTier>,
+    /// `None` when the proposal has no release artifact
+    pub artifact_match: Option<bool>,
+    /// `None` when the program is not deployed yet
+    pub size_delta_percent: Option<f64>,
+    /// Matches in the binary denylist veto every rule
+    pub denylisted: bool,
+}
+
+impl PolicyFacts {
+    /// Absolute size change of `proposed` relative to `deployed`, in percent
+    pub fn size_delta(proposed: usize, deployed: usize) -> Option<f64> {
+        (deployed > 0).then(|| proposed.abs_diff(deployed) as f64 / deployed as f64 * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RuleEvaluation {
+    pub rule: String,
+    pub matched: bool,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PolicyDecision {
+    pub facts: PolicyFacts,
+    /// First rule whose conditions all held, if any
+    pub matched_rule: Option<String>,
+    pub evaluations: Vec<RuleEvaluation>,
+}
+
+impl PolicyDecision {
+    pub fn evaluate(rules: &[PolicyRule], facts: PolicyFacts) -> Self {
+        let evaluations: Vec<RuleEvaluation> = rules
+            .iter()
+            .map(|rule| {
+                let outcomes: Vec<(bool, String)> =
+                    rule.conditions.iter().map(|condition| condition.evaluate(&facts)).collect();
+                let mut reasons: Vec<String> = outcomes.iter().map(|(_, reason)| reason.clone()).collect();
+                let mut matched = outcomes.iter().all(|(ok, _)| *ok);
+                if facts.denylisted {
+                    matched = false;
+                    reasons.push("binary matches the denylist".to_string());
+                }
+
+                RuleEvaluation {
+                    rule: rule.name.clone(),
+                    matched,
+                    reasons,
+                }
+            })
+            .collect();
+
+        let matched_rule = evaluations
+            .iter()
+            .find(|evaluation| evaluation.matched)
+            .map(|evaluation| evaluation.rule.clone());
+
+        Self {
+            facts,
+            matched_rule,
+            evaluations,
+        }
+    }
+}
+
+/// Casts one bot approval on proposals that operator-written rules consider
+/// routine (e.g. a reproducible rebuild of a low-risk patch), so humans only
+/// need to supply the rest of the threshold. The bot never approves alone: it
+/// stays idle when the threshold is a single signature.
+pub struct PolicyEngine {
+    rules: Vec<PolicyRule>,
+    bot: Option<SharedSigner>,
+    proposal_manager: Arc<ProposalManager>,
+    multisig: Arc<MultisigCoordinator>,
+    auditor: Arc<SecurityAuditor>,
+    receipts: Option<Arc<ReceiptService>>,
+}
+
+impl PolicyEngine {
+    pub fn new(
+        proposal_manager: Arc<ProposalManager>,
+        multisig: Arc<MultisigCoordinator>,
+        auditor: Arc<SecurityAuditor>,
+    ) -> Self {
+        Self {
+            rules: Vec::new(),
+            bot: None,
+            proposal_manager,
+            multisig,
+            auditor,
+            receipts: None,
+        }
+    }
+
+    pub fn with_receipts(mut self, receipts: Arc<ReceiptService>) -> Self {
+        self.receipts = Some(receipts);
+        self
+    }
+
+    /// Rules from the JSON file at `POLICY_RULES_PATH` and the bot member key
+    /// from `POLICY_BOT_KEYPAIR` (file) or the `POLICY_BOT_PRIVATE_KEY`
+    /// secret. Without both, proposals are evaluated but never approved.
+    pub fn load_from_env(mut self, secrets: &SecretStore) -> Result<Self, UpgradeError> {
+        if let Ok(path) = std::env::var("POLICY_RULES_PATH") {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| UpgradeError::InternalError(format!("Failed to read {}: {}", path, e)))?;
+            let specs: Vec<PolicyRuleSpec> = serde_json::from_str(&contents)
+                .map_err(|e| UpgradeError::InternalError(format!("Invalid policy rules: {}", e)))?;
+            self.rules = specs.iter().map(PolicyRule::parse).collect::<Result<_, _>>()?;
+        }
+
+        self.bot = match std::env::var("POLICY_BOT_KEYPAIR") {
+            Ok(path) => Some(Arc::new(LocalSigner::from_file(&path)?)),
+            Err(_) => match secrets.get("POLICY_BOT_PRIVATE_KEY") {
+                Some(secret) => Some(Arc::new(LocalSigner::from_secret(&secret)?)),
+                None => None,
+            },
+        };
+
+        Ok(self)
+    }
+
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    /// Gather facts about a proposal and run every rule against them
+    pub async fn evaluate(&self, proposal_id: &str) -> Result<PolicyDecision, UpgradeError> {
+        let proposal = self.proposal_manager.get_proposal(proposal_id).await?;
+
+        let buffer = proposal.new_buffer.parse().map_err(|_| UpgradeError::InvalidPubkey)?;
+        let program = proposal.program.parse().ok();
+        let (buffer_binary, deployed) = self.auditor.fetch_upgrade_binaries(buffer, program).await?;
+        let proposed = denylist::elf_file(&buffer_binary);
+
+        let facts = PolicyFacts {
+            risk_tier: proposal.risk_tier,
+            artifact_match: proposal.source.as_ref().map(|source| {
+                hex::encode(SecurityAuditor::calculate_program_hash(proposed)) == source.artifact_hash
+            }),
+            size_delta_percent: deployed
+                .as_deref()
+                .and_then(|deployed| PolicyFacts::size_delta(proposed.len(), denylist::elf_file(deployed).len())),
+            denylisted: !self.auditor.check_known_vulnerabilities(&buffer_binary).await?.is_empty(),
+        };
+
+        Ok(PolicyDecision::evaluate(&self.rules, facts))
+    }
+
+    /// Evaluate a new proposal and cast the bot's approval if a rule matches
+    pub async fn review(&self, proposal_id: &str) -> Result<PolicyDecision, UpgradeError> {
+        let decision = self.evaluate(proposal_id).await?;
+
+        let (Some(rule), Some(bot)) = (&decision.matched_rule, &self.bot) else {
+            tracing::info!("Policy: {} needs human approval", proposal_id);
+            return Ok(decision);
+        };
+        if self.multisig.get_threshold() <= 1 {
+            tracing::warn!("Policy: not auto-approving {}, the bot would be the only signature", proposal_id);
+            return Ok(decision);
+        }
+
+        let approver = self.multisig.approve_as(proposal_id, bot).await?;
+        self.proposal_manager.record_approval(proposal_id, &approver).await?;
+        if let Some(receipts) = &self.receipts {
+            let proposal = self.proposal_manager.get_proposal(proposal_id).await?;
+            receipts.issue(&proposal, &approver).await?;
+        }
+        tracing::info!("Policy: {} auto-approved by {} under rule '{}'", proposal_id, approver, rule);
+
+        Ok(decision)
+    }
+}
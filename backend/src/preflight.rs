@@ -0,0 +1,412 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::faucet::Cluster;
+use crate::multisig::MultisigCoordinator;
+use crate::secrets::SecretStore;
+use crate::signer;
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Tables created by `migrations/`; a missing one means migrations were not applied
+pub const REQUIRED_TABLES: &[&str] = &[
+    "upgrade_proposals",
+    "approval_history",
+    "timelock_tracking",
+    "migration_progress",
+    "account_migrations",
+    "upgrade_history",
+    "rollback_events",
+    "multisig_config",
+    "program_versions",
+    "audit_log",
+    "execution_jobs",
+    "execution_job_attempts",
+    "migration_transaction_costs",
+    "program_artifacts",
+    "proposal_cancellations",
+    "events",
+    "invariant_results",
+    "approval_receipts",
+    "account_snapshots",
+    "proposal_checklist",
+    "detected_buffers",
+    "execution_confirmations",
+    "proposal_drafts",
+    "binary_denylist",
+    "execution_requests",
+    "metrics_timeseries",
+    "upgrade_announcements",
+    "api_keys",
+    "migration_locks",
+    "account_backups",
+    "proposal_links",
+    "onchain_events",
+    "onchain_event_cursors",
+    "rollback_drills",
+    "fork_tests",
+    "approval_revocations",
+    "proposal_freezes",
+    "approval_reminders",
+    "approval_reminder_opt_outs",
+    "watch_expressions",
+    "watch_findings",
+    "member_profiles",
+    "incidents",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    /// Worth a look but does not block the deploy
+    Warn,
+    Fail,
+    /// Nothing configured to check
+    Skipped,
+}
+
+impl CheckStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skipped => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+impl CheckResult {
+    pub fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            duration_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PreflightReport {
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    /// Passes unless some check failed; warnings and skips do not block
+    pub fn new(checks: Vec<CheckResult>) -> Self {
+        let passed = checks.iter().all(|check| check.status != CheckStatus::Fail);
+        Self { passed, checks }
+    }
+
+    /// One aligned line per check, then the verdict
+    pub fn render(&self) -> String {
+        let width = self.checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for check in &self.checks {
+            out.push_str(&format!(
+                "[{}] {:<width$}  {} ({} ms)\n",
+                check.status.as_str(),
+                check.name,
+                check.detail,
+                check.duration_ms,
+                width = width
+            ));
+        }
+        let failed = self.checks.iter().filter(|check| check.status == CheckStatus::Fail).count();
+        if self.passed {
+            out.push_str("Preflight passed\n");
+        } else {
+            out.push_str(&format!("Preflight failed: {} of {} checks failed\n", failed, self.checks.len()));
+        }
+        out
+    }
+}
+
+/// Tables from `REQUIRED_TABLES` absent from `present`
+pub fn missing_tables(present: &[String]) -> Vec<&'static str> {
+    REQUIRED_TABLES
+        .iter()
+        .filter(|table| !present.iter().any(|name| name == *table))
+        .copied()
+        .collect()
+}
+
+/// Compare the RPC node's genesis hash with `EXPECTED_GENESIS_HASH`, so a
+/// deploy pointed at the wrong cluster fails before it starts
+pub fn check_genesis(genesis_hash: &str, expected: Option<&str>) -> (CheckStatus, String) {
+    let cluster = Cluster::from_genesis_hash(genesis_hash);
+    match expected {
+        Some(expected) if expected != genesis_hash => (
+            CheckStatus::Fail,
+            format!(
+                "genesis hash {} ({}) does not match EXPECTED_GENESIS_HASH {} ({})",
+                genesis_hash,
+                cluster.as_str(),
+                expected,
+                Cluster::from_genesis_hash(expected).as_str()
+            ),
+        ),
+        Some(_) => (CheckStatus::Pass, format!("{} ({})", cluster.as_str(), genesis_hash)),
+        None => (
+            CheckStatus::Warn,
+            format!("{} ({}); set EXPECTED_GENESIS_HASH to pin the cluster", cluster.as_str(), genesis_hash),
+        ),
+    }
+}
+
+pub fn check_balance(signer: &str, lamports: u64, min_lamports: u64) -> (CheckStatus, String) {
+    let sol = |lamports: u64| lamports as f64 / LAMPORTS_PER_SOL as f64;
+    if lamports < min_lamports {
+        (
+            CheckStatus::Fail,
+            format!("{} holds {} SOL, below the {} SOL minimum", signer, sol(lamports), sol(min_lamports)),
+        )
+    } else {
+        (CheckStatus::Pass, format!("{} holds {} SOL", signer, sol(lamports)))
+    }
+}
+
+/// Validates everything the service depends on without starting it, for
+/// deploy pipelines: `goquant_upgrade_service --preflight [--json]`.
+/// Each check is bounded by `PREFLIGHT_TIMEOUT_SECS` (default 10) so an
+/// unreachable dependency fails the run instead of hanging it.
+pub struct Preflight {
+    timeout: Duration,
+    /// Fee payer balance below this fails (`PREFLIGHT_MIN_SIGNER_LAMPORTS`, default 0.1 SOL)
+    min_signer_lamports: u64,
+    expected_genesis_hash: Option<String>,
+    /// Comma-separated `PREFLIGHT_WEBHOOK_URLS`, plus the remote signer if one is used
+    webhook_urls: Vec<String>,
+}
+
+impl Preflight {
+    pub fn from_env() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+        let timeout_secs = var("PREFLIGHT_TIMEOUT_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let min_signer_lamports = var("PREFLIGHT_MIN_SIGNER_LAMPORTS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(LAMPORTS_PER_SOL / 10);
+
+        let mut webhook_urls: Vec<String> = var("PREFLIGHT_WEBHOOK_URLS")
+            .map(|urls| {
+                urls.split(',')
+                    .map(|url| url.trim().to_string())
+                    .filter(|url| !url.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(url) = var("FEE_PAYER_REMOTE_URL") {
+            webhook_urls.push(url);
+        }
+
+        Self {
+            timeout: Duration::from_secs(timeout_secs),
+            min_signer_lamports,
+            expected_genesis_hash: var("EXPECTED_GENESIS_HASH"),
+            webhook_urls,
+        }
+    }
+
+    pub async fn run(&self, secrets: &SecretStore) -> PreflightReport {
+        let mut checks = Vec::new();
+
+        let secrets_loaded = secrets.load().await;
+        checks.push(match &secrets_loaded {
+            Ok(()) => CheckResult::new("secrets", CheckStatus::Pass, "all required secrets present"),
+            Err(e) => CheckResult::new("secrets", CheckStatus::Fail, e.to_string()),
+        });
+        // Same as at startup: the RPC URL may embed a provider API key
+        if let Some(rpc_url) = secrets.get("SOLANA_RPC_URL") {
+            std::env::set_var("SOLANA_RPC_URL", rpc_url);
+        }
+
+        let database_url = secrets
+            .get("DATABASE_URL")
+            .unwrap_or_else(|| "postgresql://localhost/goquant_upgrades".to_string());
+        checks.push(self.timed("database", self.check_database(&database_url)).await);
+
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let rpc = AsyncRpcClient::new(rpc_url);
+        checks.push(self.timed("rpc", self.check_rpc(&rpc)).await);
+
+        match MultisigCoordinator::new().await {
+            Ok(multisig) => {
+                checks.push(self.timed("multisig_config", self.check_multisig_config(&multisig)).await);
+                checks.push(self.timed("multisig_program", self.check_multisig_program(&rpc, &multisig)).await);
+            }
+            Err(e) => {
+                checks.push(CheckResult::new("multisig_config", CheckStatus::Fail, e.to_string()));
+                checks.push(CheckResult::new(
+                    "multisig_program",
+                    CheckStatus::Skipped,
+                    "multisig backend not configured",
+                ));
+            }
+        }
+
+        checks.push(self.timed("signer_balance", self.check_signer_balance(&rpc, secrets)).await);
+        checks.push(self.timed("webhooks", self.check_webhooks()).await);
+
+        PreflightReport::new(checks)
+    }
+
+    async fn timed<F>(&self, name: &str, check: F) -> CheckResult
+    where
+        F: Future<Output = Result<(CheckStatus, String), UpgradeError>>,
+    {
+        let started = Instant::now();
+        let (status, detail) = match tokio::time::timeout(self.timeout, check).await {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(e)) => (CheckStatus::Fail, e.to_string()),
+            Err(_) => (CheckStatus::Fail, format!("timed out after {}s", self.timeout.as_secs())),
+        };
+
+        CheckResult {
+            duration_ms: started.elapsed().as_millis() as u64,
+            ..CheckResult::new(name, status, detail)
+        }
+    }
+
+    async fn check_database(&self, database_url: &str) -> Result<(CheckStatus, String), UpgradeError> {
+        let database = Database::new(database_url).await?;
+        database.ping().await?;
+
+        let missing = missing_tables(&database.list_tables().await?);
+        if missing.is_empty() {
+            Ok((CheckStatus::Pass, format!("connected, {} tables present", REQUIRED_TABLES.len())))
+        } else {
+            Ok((
+                CheckStatus::Fail,
+                format!("connected, but migrations are missing tables: {}", missing.join(", ")),
+            ))
+        }
+    }
+
+    async fn check_rpc(&self, rpc: &AsyncRpcClient) -> Result<(CheckStatus, String), UpgradeError> {
+        rpc.get_health()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("RPC health check failed: {}", e)))?;
+        let genesis_hash = rpc
+            .get_genesis_hash()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get genesis hash: {}", e)))?;
+
+        Ok(check_genesis(&genesis_hash.to_string(), self.expected_genesis_hash.as_deref()))
+    }
+
+    async fn check_multisig_config(
+        &self,
+        multisig: &MultisigCoordinator,
+    ) -> Result<(CheckStatus, String), UpgradeError> {
+        let view = multisig.get_config_view().await;
+        if view.drift.is_empty() {
+            Ok((
+                CheckStatus::Pass,
+                format!(
+                    "{} backend, {}-of-{} matches chain",
+                    view.backend.as_str(),
+                    view.threshold,
+                    view.members.len()
+                ),
+            ))
+        } else {
+            Ok((CheckStatus::Fail, view.drift.join("; ")))
+        }
+    }
+
+    /// The multisig or governance program is deployed, and for Squads and
+    /// Realms the configured multisig account exists under it
+    async fn check_multisig_program(
+        &self,
+        rpc: &AsyncRpcClient,
+        multisig: &MultisigCoordinator,
+    ) -> Result<(CheckStatus, String), UpgradeError> {
+        let backend = multisig.backend();
+        let program_id = backend.program_id();
+        let kind = backend.kind().as_str();
+
+        let account = rpc
+            .get_account(&program_id)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("{} program {} not found: {}", kind, program_id, e)))?;
+        if !account.executable {
+            return Ok((CheckStatus::Fail, format!("{} program {} is not executable", kind, program_id)));
+        }
+
+        if let Some(health) = multisig.check_squads_health().await {
+            health?;
+        }
+
+        Ok((CheckStatus::Pass, format!("{} program {} deployed", kind, program_id)))
+    }
+
+    async fn check_signer_balance(
+        &self,
+        rpc: &AsyncRpcClient,
+        secrets: &SecretStore,
+    ) -> Result<(CheckStatus, String), UpgradeError> {
+        let fee_payer = match signer::fee_payer(secrets)? {
+            Some(fee_payer) => fee_payer,
+            None => {
+                return Ok((
+                    CheckStatus::Warn,
+                    "no fee payer configured; proposals are only tracked off chain".to_string(),
+                ))
+            }
+        };
+
+        let pubkey = fee_payer.pubkey();
+        let lamports = rpc
+            .get_balance(&pubkey)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get balance of {}: {}", pubkey, e)))?;
+
+        Ok(check_balance(&pubkey.to_string(), lamports, self.min_signer_lamports))
+    }
+
+    /// Any HTTP response counts as reachable; only connection errors and 5xx fail
+    async fn check_webhooks(&self) -> Result<(CheckStatus, String), UpgradeError> {
+        if self.webhook_urls.is_empty() {
+            return Ok((CheckStatus::Skipped, "no webhooks configured".to_string()));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| UpgradeError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+
+        let mut unreachable = Vec::new();
+        for url in &self.webhook_urls {
+            match client.get(url).send().await {
+                Ok(response) if response.status().is_server_error() => {
+                    unreachable.push(format!("{} returned {}", url, response.status()));
+                }
+                Ok(_) => {}
+                Err(e) => unreachable.push(format!("{}: {}", url, e)),
+            }
+        }
+
+        if unreachable.is_empty() {
+            Ok((CheckStatus::Pass, format!("{} reachable", self.webhook_urls.len())))
+        } else {
+            Ok((CheckStatus::Fail, unreachable.join("; ")))
+        }
+    }
+}
@@ -1,13 +1,142 @@
 use crate::error::UpgradeError;
+use crate::faucet::{AirdropFunder, LAMPORTS_PER_SIGNATURE};
+use crate::signer::{self, SharedSigner};
+use crate::websocket::NotificationService;
+use futures_util::StreamExt;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Bytes per loader Write instruction; keeps each transaction under the packet size limit
+pub const BUFFER_WRITE_CHUNK_SIZE: usize = 900;
+const MAX_CONCURRENT_WRITES: usize = 8;
+const MAX_CHUNK_ATTEMPTS: u32 = 3;
+
+/// Program data room reserved for later upgrades when a deployment does not
+/// set one: twice the initial binary, as `solana program deploy` does
+pub fn default_max_data_len(program_len: usize) -> usize {
+    program_len.saturating_mul(2)
+}
+
+/// How the program in a buffer fits the program data account an upgrade
+/// writes it into. The loader refuses an upgrade from a buffer holding more
+/// bytes than the account, padding included.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ProgramCapacity {
+    /// Program bytes in the buffer
+    pub program_len: usize,
+    /// Program bytes the deployed program data account holds
+    pub capacity: usize,
+}
+
+impl ProgramCapacity {
+    /// From the buffer and program data account sizes, loader headers included
+    pub fn from_account_lens(buffer_len: usize, programdata_len: usize) -> Self {
+        Self {
+            program_len: buffer_len.saturating_sub(UpgradeableLoaderState::size_of_buffer_metadata()),
+            capacity: programdata_len.saturating_sub(UpgradeableLoaderState::size_of_programdata_metadata()),
+        }
+    }
+
+    /// Bytes `ExtendProgram` must add before the upgrade fits; 0 when it does
+    pub fn extension_needed(&self) -> usize {
+        self.program_len.saturating_sub(self.capacity)
+    }
+
+    pub fn warning(&self) -> Option<String> {
+        let extension = self.extension_needed();
+        (extension > 0).then(|| {
+            format!(
+                "Program is {} bytes but the deployed program data holds {}; execution extends it by {} bytes first",
+                self.program_len, self.capacity, extension
+            )
+        })
+    }
+
+    /// Size of the program data account once it fits the program
+    pub fn extended_account_len(&self) -> usize {
+        UpgradeableLoaderState::size_of_programdata_metadata() + self.program_len.max(self.capacity)
+    }
+
+    /// Loader `ExtendProgram` growing `program`'s data account to fit, with
+    /// the rent paid by `payer`. Anyone may extend a program, so the
+    /// multisig does not need to sign it.
+    pub fn extend_instruction(&self, program: &Pubkey, payer: &Pubkey) -> Option<Instruction> {
+        let extension = u32::try_from(self.extension_needed()).ok().filter(|bytes| *bytes > 0)?;
+        Some(bpf_loader_upgradeable::extend_program(program, Some(payer), extension))
+    }
+}
+
+/// The `ExtendProgram` step of an upgrade and the rent it moves from the payer
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct ExtensionCost {
+    pub additional_bytes: usize,
+    /// Program data account size once extended, loader header included
+    pub account_len: usize,
+    /// Lamports the loader moves into the program data account so it stays
+    /// rent exempt at its new size
+    pub rent_lamports: u64,
+}
+
+impl ExtensionCost {
+    /// `None` when the program fits. `rent_exempt_minimum` is the minimum
+    /// balance for the extended account and `programdata_lamports` what the
+    /// account holds now; the loader only charges the difference.
+    pub fn new(capacity: &ProgramCapacity, rent_exempt_minimum: u64, programdata_lamports: u64) -> Option<Self> {
+        let additional_bytes = capacity.extension_needed();
+        (additional_bytes > 0).then(|| Self {
+            additional_bytes,
+            account_len: capacity.extended_account_len(),
+            rent_lamports: rent_exempt_minimum.saturating_sub(programdata_lamports),
+        })
+    }
+
+    /// Loader `ExtendProgram` for this step, with the rent paid by `payer`
+    pub fn instruction(&self, program: &Pubkey, payer: &Pubkey) -> Instruction {
+        bpf_loader_upgradeable::extend_program(program, Some(payer), self.additional_bytes as u32)
+    }
+}
+
+/// Lamports executing an upgrade costs its payer
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct UpgradeCost {
+    /// Set when the program data account has to be extended first
+    pub extension: Option<ExtensionCost>,
+    pub fee_lamports: u64,
+    pub total_lamports: u64,
+}
+
+impl UpgradeCost {
+    pub fn new(extension: Option<ExtensionCost>, signatures: usize) -> Self {
+        let fee_lamports = LAMPORTS_PER_SIGNATURE * signatures as u64;
+        Self {
+            extension,
+            fee_lamports,
+            total_lamports: fee_lamports + extension.map_or(0, |extension| extension.rent_lamports),
+        }
+    }
+}
 
 pub struct ProgramBuilder {
     build_dir: PathBuf,
     rpc_client: Option<RpcClient>,
+    async_rpc_client: Arc<AsyncRpcClient>,
+    payer: Option<SharedSigner>,
+    notifications: Option<Arc<NotificationService>>,
+    faucet: Option<Arc<AirdropFunder>>,
 }
 
 impl ProgramBuilder {
@@ -18,9 +147,35 @@ impl ProgramBuilder {
 
         let rpc_url = std::env::var("SOLANA_RPC_URL")
             .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
-        let rpc_client = Some(RpcClient::new(rpc_url));
+        let rpc_client = Some(RpcClient::new(rpc_url.clone()));
+        let async_rpc_client = Arc::new(AsyncRpcClient::new(rpc_url));
+
+        Ok(Self {
+            build_dir,
+            rpc_client,
+            async_rpc_client,
+            payer: None,
+            notifications: None,
+            faucet: None,
+        })
+    }
+
+    /// Fee payer (and buffer authority until handoff) for buffer uploads
+    pub fn with_fee_payer(mut self, payer: Option<SharedSigner>) -> Self {
+        self.payer = payer;
+        self
+    }
 
-        Ok(Self { build_dir, rpc_client })
+    /// Stream buffer upload progress to WebSocket clients
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Top up the fee payer from the faucet before uploads on devnet/testnet
+    pub fn with_faucet(mut self, faucet: Arc<AirdropFunder>) -> Self {
+        self.faucet = Some(faucet);
+        self
     }
 
     /// Build Anchor program and return binary
@@ -61,15 +216,184 @@ impl ProgramBuilder {
     pub async fn create_buffer(&self, program_binary: &[u8]) -> Result<Pubkey, UpgradeError> {
         tracing::info!("Creating buffer account for program ({} bytes)", program_binary.len());
 
-        // In production, this would:
-        // 1. Create buffer account
-        // 2. Upload program binary in chunks
-        // 3. Set buffer authority
-        // 4. Return buffer pubkey
+        let Some(payer) = self.payer.clone() else {
+            // No fee payer configured (local development): nothing can be sent
+            tracing::warn!("No fee payer signer configured, returning placeholder buffer");
+            return Ok(Pubkey::new_unique());
+        };
 
-        // For now, return a placeholder
-        // In real implementation, use solana program deploy or manual buffer creation
-        Ok(Pubkey::new_unique())
+        let buffer = Keypair::new();
+        let buffer_pubkey = buffer.pubkey();
+
+        let lamports = self.async_rpc_client
+            .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(program_binary.len()))
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get rent: {}", e)))?;
+
+        if let Some(faucet) = &self.faucet {
+            // Rent plus one fee per write, and two signatures for the create transaction
+            let writes = program_binary.len().div_ceil(BUFFER_WRITE_CHUNK_SIZE) as u64;
+            let fees = (writes + 2) * LAMPORTS_PER_SIGNATURE;
+            faucet.ensure_funded(&payer.pubkey(), lamports + fees).await?;
+        }
+
+        let create_ixs = bpf_loader_upgradeable::create_buffer(
+            &payer.pubkey(),
+            &buffer_pubkey,
+            &payer.pubkey(),
+            lamports,
+            program_binary.len(),
+        )
+        .map_err(|e| UpgradeError::SolanaError(format!("Failed to build create_buffer: {}", e)))?;
+
+        let blockhash = self.async_rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get blockhash: {}", e)))?;
+        let mut tx = Transaction::new_with_payer(&create_ixs, Some(&payer.pubkey()));
+        signer::sign_transaction(&mut tx, payer.as_ref(), &[&buffer], blockhash).await?;
+        self.async_rpc_client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to create buffer: {}", e)))?;
+
+        self.write_buffer_chunks(&buffer_pubkey, &payer, program_binary).await?;
+
+        tracing::info!("Buffer {} uploaded", buffer_pubkey);
+
+        Ok(buffer_pubkey)
+    }
+
+    /// Create the empty loader account a deployment initializes. The address
+    /// is a fresh keypair that is dropped once the account exists: only the
+    /// loader can write to it from then on, so nobody needs to keep it.
+    pub async fn create_program_account(&self) -> Result<Pubkey, UpgradeError> {
+        let Some(payer) = self.payer.clone() else {
+            tracing::warn!("No fee payer signer configured, returning placeholder program");
+            return Ok(Pubkey::new_unique());
+        };
+
+        let program = Keypair::new();
+        let space = UpgradeableLoaderState::size_of_program();
+        let lamports = self.async_rpc_client
+            .get_minimum_balance_for_rent_exemption(space)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get rent: {}", e)))?;
+
+        if let Some(faucet) = &self.faucet {
+            faucet.ensure_funded(&payer.pubkey(), lamports + 2 * LAMPORTS_PER_SIGNATURE).await?;
+        }
+
+        let create_ix = system_instruction::create_account(
+            &payer.pubkey(),
+            &program.pubkey(),
+            lamports,
+            space as u64,
+            &bpf_loader_upgradeable::id(),
+        );
+        let blockhash = self.async_rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to get blockhash: {}", e)))?;
+        let mut tx = Transaction::new_with_payer(&[create_ix], Some(&payer.pubkey()));
+        signer::sign_transaction(&mut tx, payer.as_ref(), &[&program], blockhash).await?;
+        self.async_rpc_client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to create program account: {}", e)))?;
+
+        tracing::info!("Program account {} created", program.pubkey());
+
+        Ok(program.pubkey())
+    }
+
+    /// Write the binary into the buffer with bounded concurrency, retrying failed chunks
+    async fn write_buffer_chunks(
+        &self,
+        buffer: &Pubkey,
+        authority: &SharedSigner,
+        program_binary: &[u8],
+    ) -> Result<(), UpgradeError> {
+        let chunks: Vec<(u32, Vec<u8>)> = program_binary
+            .chunks(BUFFER_WRITE_CHUNK_SIZE)
+            .enumerate()
+            .map(|(i, chunk)| ((i * BUFFER_WRITE_CHUNK_SIZE) as u32, chunk.to_vec()))
+            .collect();
+        let total_chunks = chunks.len();
+        let confirmed = Arc::new(AtomicUsize::new(0));
+
+        let results: Vec<Result<(), UpgradeError>> = futures_util::stream::iter(chunks)
+            .map(|(offset, bytes)| {
+                let confirmed = confirmed.clone();
+                async move {
+                    self.write_chunk_with_retry(buffer, authority, offset, bytes).await?;
+
+                    let done = confirmed.fetch_add(1, Ordering::SeqCst) + 1;
+                    if let Some(notifications) = &self.notifications {
+                        notifications
+                            .notify_buffer_upload_progress(buffer.to_string(), done, total_chunks)
+                            .await;
+                    }
+                    Ok(())
+                }
+            })
+            .buffer_unordered(MAX_CONCURRENT_WRITES)
+            .collect()
+            .await;
+
+        let failed = results.iter().filter(|r| r.is_err()).count();
+        if failed > 0 {
+            return Err(UpgradeError::SolanaError(format!(
+                "Buffer upload incomplete: {}/{} chunks failed",
+                failed, total_chunks
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn write_chunk_with_retry(
+        &self,
+        buffer: &Pubkey,
+        authority: &SharedSigner,
+        offset: u32,
+        bytes: Vec<u8>,
+    ) -> Result<(), UpgradeError> {
+        let write_ix = bpf_loader_upgradeable::write(buffer, &authority.pubkey(), offset, bytes);
+        let mut last_error = String::new();
+
+        for attempt in 1..=MAX_CHUNK_ATTEMPTS {
+            // Fresh blockhash per attempt so an expired one can't fail every retry
+            let result = async {
+                let blockhash = self.async_rpc_client
+                    .get_latest_blockhash()
+                    .await
+                    .map_err(|e| UpgradeError::SolanaError(e.to_string()))?;
+                let mut tx = Transaction::new_with_payer(&[write_ix.clone()], Some(&authority.pubkey()));
+                signer::sign_transaction(&mut tx, authority.as_ref(), &[], blockhash).await?;
+                self.async_rpc_client
+                    .send_and_confirm_transaction(&tx)
+                    .await
+                    .map_err(|e| UpgradeError::SolanaError(e.to_string()))
+            }
+            .await;
+
+            match result {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    last_error = e.to_string();
+                    tracing::warn!(
+                        "Chunk at offset {} failed (attempt {}/{}): {}",
+                        offset, attempt, MAX_CHUNK_ATTEMPTS, last_error
+                    );
+                }
+            }
+        }
+
+        Err(UpgradeError::SolanaError(format!(
+            "Chunk at offset {} failed after {} attempts: {}",
+            offset, MAX_CHUNK_ATTEMPTS, last_error
+        )))
     }
 
     /// Verify program hash matches expected
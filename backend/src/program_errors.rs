@@ -0,0 +1,224 @@
+use crate::error::UpgradeError;
+use serde::Serialize;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// First code of an Anchor program's `#[error_code]` enum
+pub const ANCHOR_CUSTOM_ERROR_OFFSET: u32 = 6000;
+
+const CUSTOM_ERROR_MARKER: &str = "custom program error: 0x";
+
+/// Framework errors every Anchor program can return
+const ANCHOR_ERRORS: &[(u32, &str, &str)] = &[
+    (100, "InstructionMissing", "8 byte instruction identifier not provided"),
+    (101, "InstructionFallbackNotFound", "Fallback functions are not supported"),
+    (102, "InstructionDidNotDeserialize", "The program could not deserialize the given instruction"),
+    (103, "InstructionDidNotSerialize", "The program could not serialize the given instruction"),
+    (2000, "ConstraintMut", "A mut constraint was violated"),
+    (2001, "ConstraintHasOne", "A has one constraint was violated"),
+    (2002, "ConstraintSigner", "A signer constraint was violated"),
+    (2003, "ConstraintRaw", "A raw constraint was violated"),
+    (2004, "ConstraintOwner", "An owner constraint was violated"),
+    (2005, "ConstraintRentExempt", "A rent exemption constraint was violated"),
+    (2006, "ConstraintSeeds", "A seeds constraint was violated"),
+    (2007, "ConstraintExecutable", "An executable constraint was violated"),
+    (2011, "ConstraintClose", "A close constraint was violated"),
+    (2012, "ConstraintAddress", "An address constraint was violated"),
+    (3000, "AccountDiscriminatorAlreadySet", "The account discriminator was already set on this account"),
+    (3001, "AccountDiscriminatorNotFound", "No 8 byte discriminator was found on the account"),
+    (3002, "AccountDiscriminatorMismatch", "8 byte discriminator did not match what was expected"),
+    (3003, "AccountDidNotDeserialize", "Failed to deserialize the account"),
+    (3004, "AccountDidNotSerialize", "Failed to serialize the account"),
+    (3005, "AccountNotEnoughKeys", "Not enough account keys given to the instruction"),
+    (3006, "AccountNotMutable", "The given account is not mutable"),
+    (3007, "AccountOwnedByWrongProgram", "The given account is owned by a different program than expected"),
+    (3008, "InvalidProgramId", "Program ID was not as expected"),
+    (3009, "InvalidProgramExecutable", "Program account is not executable"),
+    (3010, "AccountNotSigner", "The given account did not sign"),
+    (3011, "AccountNotSystemOwned", "The given account is not owned by the system program"),
+    (3012, "AccountNotInitialized", "The program expected this account to be already initialized"),
+    (4100, "DeclaredProgramIdMismatch", "The declared program id does not match the actual program id"),
+];
+
+/// A named error a program can fail with
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProgramErrorInfo {
+    pub code: u32,
+    pub name: String,
+    pub message: Option<String>,
+}
+
+impl ProgramErrorInfo {
+    /// `InvalidProposalStatus (6001): Invalid proposal status`
+    pub fn describe(&self) -> String {
+        match &self.message {
+            Some(message) => format!("{} ({}): {}", self.name, self.code, message),
+            None => format!("{} ({})", self.name, self.code),
+        }
+    }
+}
+
+/// The `errors` of an Anchor IDL and the program it belongs to, read from
+/// `address` (Anchor 0.30+) or `metadata.address` (earlier versions)
+pub fn parse_idl_errors(idl: &Value) -> Result<(Option<Pubkey>, Vec<ProgramErrorInfo>), UpgradeError> {
+    let program = idl
+        .get("address")
+        .or_else(|| idl.pointer("/metadata/address"))
+        .and_then(Value::as_str)
+        .map(|address| Pubkey::from_str(address).map_err(|_| UpgradeError::InvalidPubkey))
+        .transpose()?;
+
+    let errors = idl
+        .get("errors")
+        .and_then(Value::as_array)
+        .map(|errors| {
+            errors
+                .iter()
+                .map(|error| {
+                    let code = error.get("code").and_then(Value::as_u64).and_then(|code| u32::try_from(code).ok());
+                    let name = error.get("name").and_then(Value::as_str);
+                    match (code, name) {
+                        (Some(code), Some(name)) => Ok(ProgramErrorInfo {
+                            code,
+                            name: name.to_string(),
+                            message: error.get("msg").and_then(Value::as_str).map(str::to_string),
+                        }),
+                        _ => Err(UpgradeError::InvalidRequest(format!("Invalid IDL error entry: {}", error))),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok((program, errors))
+}
+
+/// Turns `custom program error: 0x1771` in transaction errors into the
+/// error's name and message from the failing program's IDL. Codes below
+/// 6000 are Anchor framework errors; unknown codes are left as they are.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorDecoder {
+    programs: HashMap<Pubkey, Vec<ProgramErrorInfo>>,
+    /// Errors of IDLs without an address, tried for any program
+    fallback: Vec<ProgramErrorInfo>,
+}
+
+impl ErrorDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode errors of the program `idl` describes
+    pub fn with_idl(mut self, idl: &Value) -> Result<Self, UpgradeError> {
+        self.add_idl(idl)?;
+        Ok(self)
+    }
+
+    fn add_idl(&mut self, idl: &Value) -> Result<(), UpgradeError> {
+        let (program, errors) = parse_idl_errors(idl)?;
+        match program {
+            Some(program) => {
+                self.programs.insert(program, errors);
+            }
+            None => self.fallback.extend(errors),
+        }
+        Ok(())
+    }
+
+    /// The upgrade manager's IDL at `IDL_PATH` plus any listed in
+    /// `ERROR_IDL_PATHS` (comma-separated). Unreadable IDLs are logged and skipped.
+    pub fn from_env() -> Self {
+        let mut paths = vec![std::env::var("IDL_PATH")
+            .unwrap_or_else(|_| "target/idl/upgrade_manager.json".to_string())];
+        paths.extend(
+            std::env::var("ERROR_IDL_PATHS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string),
+        );
+
+        let mut decoder = Self::new();
+        for path in &paths {
+            let added = std::fs::read(path)
+                .map_err(|e| UpgradeError::InternalError(e.to_string()))
+                .and_then(|bytes| {
+                    serde_json::from_slice::<Value>(&bytes)
+                        .map_err(|e| UpgradeError::InvalidRequest(e.to_string()))
+                })
+                .and_then(|idl| decoder.add_idl(&idl));
+            if let Err(e) = added {
+                tracing::warn!("Program errors from {} will not be decoded: {}", path, e);
+            }
+        }
+        decoder
+    }
+
+    /// The error `program` (if known) returned as `code`
+    pub fn lookup(&self, program: Option<&Pubkey>, code: u32) -> Option<ProgramErrorInfo> {
+        if code < ANCHOR_CUSTOM_ERROR_OFFSET {
+            return ANCHOR_ERRORS
+                .iter()
+                .find(|(anchor_code, _, _)| *anchor_code == code)
+                .map(|(code, name, message)| ProgramErrorInfo {
+                    code: *code,
+                    name: name.to_string(),
+                    message: Some(message.to_string()),
+                });
+        }
+
+        let candidates: Vec<&ProgramErrorInfo> = match program {
+            Some(program) => match self.programs.get(program) {
+                Some(errors) => errors.iter().collect(),
+                None => self.fallback.iter().collect(),
+            },
+            // Without the failing program, only guess when one IDL could have produced the code
+            None if self.programs.len() == 1 => self.fallback.iter().chain(self.programs.values().flatten()).collect(),
+            None => self.fallback.iter().collect(),
+        };
+        candidates.into_iter().find(|error| error.code == code).cloned()
+    }
+
+    /// Rewrite the custom error codes in `raw`. `programs` holds the program
+    /// id of each instruction of the failed transaction, so the code in
+    /// `Error processing Instruction 1: ...` is looked up for the right program.
+    pub fn describe(&self, raw: &str, programs: &[Pubkey]) -> String {
+        let mut described = String::with_capacity(raw.len());
+        let mut rest = raw;
+
+        while let Some(start) = rest.find(CUSTOM_ERROR_MARKER) {
+            let digits_start = start + CUSTOM_ERROR_MARKER.len();
+            let digits_len = rest[digits_start..]
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(rest.len() - digits_start);
+            let end = digits_start + digits_len;
+
+            let instruction = instruction_index(&raw[..raw.len() - rest.len() + start]);
+            let program = instruction.and_then(|index| programs.get(index));
+            let decoded = u32::from_str_radix(&rest[digits_start..end], 16)
+                .ok()
+                .and_then(|code| self.lookup(program, code));
+
+            described.push_str(&rest[..start]);
+            match decoded {
+                Some(error) => described.push_str(&error.describe()),
+                None => described.push_str(&rest[start..end]),
+            }
+            rest = &rest[end..];
+        }
+
+        described.push_str(rest);
+        described
+    }
+}
+
+/// Index in the last `Instruction N` mention of `text`
+fn instruction_index(text: &str) -> Option<usize> {
+    let (_, after) = text.rsplit_once("Instruction ")?;
+    let digits: String = after.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
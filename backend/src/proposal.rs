@@ -1,13 +1,20 @@
+use crate::announcements::AnnouncementService;
+use crate::confirmation::ConfirmationTracker;
+use crate::database::Database;
 use crate::error::UpgradeError;
+use crate::github::CommitStatusReporter;
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_SECURITY};
 use crate::multisig::MultisigCoordinator;
 use crate::program_builder::ProgramBuilder;
 use crate::timelock::TimelockManager;
+use crate::websocket::NotificationService;
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Proposal {
     pub id: String,
     pub proposer: String,
@@ -20,15 +27,547 @@ pub struct Proposal {
     pub approval_threshold: u8,
     pub status: ProposalStatus,
     pub executed_at: Option<i64>,
+    pub source: Option<ProposalSource>,
+    pub publish_idl: bool,
+    pub cancellation: Option<Cancellation>,
+    #[serde(default)]
+    pub risk_tier: Option<RiskTier>,
+    #[serde(default)]
+    pub attachments: Vec<ProposalAttachment>,
+    /// Proposals that must execute before this one may
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Proposals this one replaced; they were cancelled when it was created
+    #[serde(default)]
+    pub supersedes: Vec<String>,
+    /// The critical finding holding the proposal while it is `Frozen`
+    #[serde(default)]
+    pub freeze: Option<ProposalFreeze>,
+    /// Set when the proposal deploys a new program rather than upgrading one
+    #[serde(default)]
+    pub deployment: Option<ProgramDeployment>,
+    /// Set when the proposal hands over the upgrade authority instead
+    #[serde(default)]
+    pub authority_change: Option<AuthorityChange>,
 }
 
+impl Proposal {
+    /// `upgrade`, `deploy` or `set_authority`, as stored
+    pub fn kind(&self) -> &'static str {
+        if self.deployment.is_some() {
+            "deploy"
+        } else if self.authority_change.is_some() {
+            "set_authority"
+        } else {
+            "upgrade"
+        }
+    }
+}
+
+/// Maximum length of free-text cancellation details (matches the on-chain limit)
+pub const MAX_CANCELLATION_DETAILS_LEN: usize = 200;
+
+/// Timelock applied to new proposals unless configured otherwise
+pub const DEFAULT_TIMELOCK_DURATION: i64 = 48 * 60 * 60;
+
+/// Bounds the on-chain program enforces in `initialize` and
+/// `propose_timelock_change`; kept in sync with the program
+pub const MIN_TIMELOCK_DURATION: i64 = 48 * 60 * 60;
+pub const MAX_TIMELOCK_DURATION: i64 = 30 * 24 * 60 * 60;
+
+/// Reject a timelock the program would refuse
+pub fn validate_timelock_duration(timelock_duration: i64) -> Result<(), UpgradeError> {
+    if !(MIN_TIMELOCK_DURATION..=MAX_TIMELOCK_DURATION).contains(&timelock_duration) {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "Timelock duration of {}s is outside {}s..={}s",
+            timelock_duration, MIN_TIMELOCK_DURATION, MAX_TIMELOCK_DURATION
+        )));
+    }
+    Ok(())
+}
+
+/// Why a proposal was cancelled (mirrors the on-chain `CancellationReason`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CancellationReason {
+    SecurityIssue,
+    BuildMismatch,
+    Superseded,
+    ProposerWithdrawn,
+    Other,
+}
+
+impl CancellationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CancellationReason::SecurityIssue => "security_issue",
+            CancellationReason::BuildMismatch => "build_mismatch",
+            CancellationReason::Superseded => "superseded",
+            CancellationReason::ProposerWithdrawn => "proposer_withdrawn",
+            CancellationReason::Other => "other",
+        }
+    }
+}
+
+/// How much scrutiny an upgrade needs, as judged by its author and reviewers.
+/// Ordered from least to most risky.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RiskTier {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl RiskTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskTier::Low => "low",
+            RiskTier::Medium => "medium",
+            RiskTier::High => "high",
+            RiskTier::Critical => "critical",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(RiskTier::Low),
+            "medium" => Some(RiskTier::Medium),
+            "high" => Some(RiskTier::High),
+            "critical" => Some(RiskTier::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Supporting material linked from a proposal (audit report, diff, runbook)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProposalAttachment {
+    pub name: String,
+    pub url: String,
+    /// Hex SHA-256 of the linked file, so reviewers can check what they read
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Cancellation {
+    pub reason: CancellationReason,
+    pub details: String,
+    pub cancelled_at: i64,
+}
+
+/// Who froze a proposal
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FreezeSource {
+    /// `SecurityAuditor` found the binary on the denylist
+    Auditor,
+    /// A security admin recorded the finding by hand
+    Admin,
+}
+
+impl FreezeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FreezeSource::Auditor => "auditor",
+            FreezeSource::Admin => "admin",
+        }
+    }
+}
+
+/// A critical finding blocking approvals and execution until an admin clears it
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProposalFreeze {
+    pub finding: String,
+    pub source: FreezeSource,
+    pub frozen_by: String,
+    /// Status the proposal returns to once the finding is cleared
+    pub frozen_from: ProposalStatus,
+    pub frozen_at: i64,
+}
+
+/// A first-time deployment: `program_id` is an uninitialized loader account
+/// that executing the proposal deploys from the buffer, with the multisig as
+/// upgrade authority
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProgramDeployment {
+    pub program_id: String,
+    /// Largest program the data account can ever hold
+    pub max_data_len: usize,
+}
+
+/// Prefixed to the description of a proposal that removes the upgrade
+/// authority, so nobody approves it without seeing what it does
+pub const IMMUTABILITY_WARNING: &str = "IRREVERSIBLE: executing this proposal removes the upgrade authority. \
+The program can never be upgraded, patched or rolled back again.";
+
+/// A change of the program's upgrade authority, e.g. to a new Squads vault.
+/// With no new authority the program becomes immutable for good.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AuthorityChange {
+    pub new_authority: Option<String>,
+}
+
+impl AuthorityChange {
+    /// Immutability must be confirmed explicitly, since it cannot be undone
+    pub fn new(new_authority: Option<Pubkey>, confirm_immutable: bool) -> Result<Self, UpgradeError> {
+        if new_authority.is_none() && !confirm_immutable {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "{} Set confirm_immutable to propose it anyway.",
+                IMMUTABILITY_WARNING
+            )));
+        }
+        Ok(Self {
+            new_authority: new_authority.map(|authority| authority.to_string()),
+        })
+    }
+
+    pub fn is_irreversible(&self) -> bool {
+        self.new_authority.is_none()
+    }
+
+    pub fn new_authority_key(&self) -> Result<Option<Pubkey>, UpgradeError> {
+        self.new_authority
+            .as_deref()
+            .map(|authority| authority.parse().map_err(|_| UpgradeError::InvalidPubkey))
+            .transpose()
+    }
+
+    /// Key standing in for the buffer: the new authority, or the default key
+    /// for immutability, as the upgrade-manager program derives it
+    pub fn target(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.new_authority_key()?.unwrap_or_default())
+    }
+
+    /// The description approvers see, leading with the warning when irreversible
+    pub fn describe(&self, description: &str) -> String {
+        if self.is_irreversible() {
+            format!("{}\n\n{}", IMMUTABILITY_WARNING, description)
+        } else {
+            description.to_string()
+        }
+    }
+}
+
+/// Optional settings supplied when creating a proposal
+#[derive(Debug, Clone, Default)]
+pub struct ProposalOptions {
+    pub source: Option<ProposalSource>,
+    /// Publish the program's Anchor IDL after a successful upgrade
+    pub publish_idl: bool,
+    pub risk_tier: Option<RiskTier>,
+    pub attachments: Vec<ProposalAttachment>,
+    /// Proposals that must execute first
+    pub depends_on: Vec<String>,
+    /// Proposals to cancel and replace
+    pub supersedes: Vec<String>,
+    /// Deploy a new program instead of upgrading the managed one
+    pub deployment: Option<ProgramDeployment>,
+    /// Change the upgrade authority instead of upgrading
+    pub authority_change: Option<AuthorityChange>,
+}
+
+/// A linked proposal and where it stands
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LinkedProposal {
+    pub id: String,
+    pub status: ProposalStatus,
+}
+
+/// A proposal's edges in the dependency and supersession graph, both the
+/// links it declared and the ones other proposals declared on it
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProposalLinks {
+    pub proposal_id: String,
+    pub depends_on: Vec<LinkedProposal>,
+    /// Proposals waiting on this one
+    pub required_by: Vec<LinkedProposal>,
+    pub supersedes: Vec<LinkedProposal>,
+    pub superseded_by: Vec<LinkedProposal>,
+    /// Whether every dependency has executed
+    pub ready: bool,
+}
+
+impl ProposalLinks {
+    pub fn build(proposal: &Proposal, proposals: &[Proposal]) -> Self {
+        let linked = |ids: &[String]| -> Vec<LinkedProposal> {
+            ids.iter()
+                .filter_map(|id| proposals.iter().find(|p| &p.id == id))
+                .map(|p| LinkedProposal {
+                    id: p.id.clone(),
+                    status: p.status.clone(),
+                })
+                .collect()
+        };
+        let referencing = |links: fn(&Proposal) -> &Vec<String>| -> Vec<LinkedProposal> {
+            proposals
+                .iter()
+                .filter(|p| links(p).contains(&proposal.id))
+                .map(|p| LinkedProposal {
+                    id: p.id.clone(),
+                    status: p.status.clone(),
+                })
+                .collect()
+        };
+
+        let depends_on = linked(&proposal.depends_on);
+        Self {
+            proposal_id: proposal.id.clone(),
+            ready: depends_on.iter().all(|p| p.status == ProposalStatus::Executed),
+            depends_on,
+            required_by: referencing(|p| &p.depends_on),
+            supersedes: linked(&proposal.supersedes),
+            superseded_by: referencing(|p| &p.supersedes),
+        }
+    }
+}
+
+/// Check the links a new proposal declares. Links can only point at existing
+/// proposals, so the graph cannot contain cycles.
+pub fn validate_links(depends_on: &[String], supersedes: &[String], proposals: &[Proposal]) -> Result<(), UpgradeError> {
+    let find = |id: &String| {
+        proposals
+            .iter()
+            .find(|p| &p.id == id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(id.clone()))
+    };
+
+    for id in depends_on {
+        if find(id)?.status == ProposalStatus::Cancelled {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Cannot depend on cancelled proposal {}",
+                id
+            )));
+        }
+        if supersedes.contains(id) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Proposal {} cannot be both a dependency and superseded",
+                id
+            )));
+        }
+    }
+    for id in supersedes {
+        if find(id)?.status.is_terminal() {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Proposal {} is already {} and cannot be superseded",
+                id,
+                find(id)?.status.as_str()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse to execute `proposal_id` until every proposal it depends on has executed
+pub fn ensure_dependencies_executed(proposal_id: &str, proposals: &[Proposal]) -> Result<(), UpgradeError> {
+    let proposal = proposals
+        .iter()
+        .find(|p| p.id == proposal_id)
+        .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+    let links = ProposalLinks::build(proposal, proposals);
+
+    if let Some(cancelled) = links.depends_on.iter().find(|p| p.status == ProposalStatus::Cancelled) {
+        return Err(UpgradeError::InvalidRequest(format!(
+            "Dependency {} was cancelled; proposal {} can no longer execute",
+            cancelled.id, proposal_id
+        )));
+    }
+
+    let pending: Vec<&str> = links
+        .depends_on
+        .iter()
+        .filter(|p| p.status != ProposalStatus::Executed)
+        .map(|p| p.id.as_str())
+        .collect();
+    if !pending.is_empty() {
+        return Err(UpgradeError::DependencyPending(pending.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// Where the proposed binary came from, when created by release automation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProposalSource {
+    pub tag: String,
+    pub commit: String,
+    pub artifact_hash: String,
+    pub release_url: Option<String>,
+}
+
+/// Sanitized proposal view served by the unauthenticated `/public` routes
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PublicProposal {
+    pub id: String,
+    pub program: String,
+    pub new_buffer: String,
+    pub description: String,
+    pub proposed_at: i64,
+    pub timelock_until: i64,
+    pub timelock_remaining_seconds: i64,
+    pub approvals: usize,
+    pub approval_threshold: u8,
+    pub status: ProposalStatus,
+    pub executed_at: Option<i64>,
+}
+
+impl PublicProposal {
+    pub fn from_proposal(proposal: &Proposal, now: i64) -> Self {
+        Self {
+            id: proposal.id.clone(),
+            program: proposal.program.clone(),
+            new_buffer: proposal.new_buffer.clone(),
+            description: proposal.description.clone(),
+            proposed_at: proposal.proposed_at,
+            timelock_until: proposal.timelock_until,
+            timelock_remaining_seconds: (proposal.timelock_until - now).max(0),
+            approvals: proposal.approvals.len(),
+            approval_threshold: proposal.approval_threshold,
+            status: proposal.status.clone(),
+            executed_at: proposal.executed_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ProposalStatus {
     Proposed,
     Approved,
     TimelockActive,
     Executed,
     Cancelled,
+    /// Held by a critical audit finding; see `Proposal::freeze`
+    Frozen,
+}
+
+/// Something that happens to a proposal and may move it to another status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalEvent {
+    /// A member approved, below the threshold
+    Approve,
+    /// The approval that meets the threshold and organization quorum; starts the timelock
+    ThresholdReached,
+    /// A member withdrew their approval, others remain
+    Revoke,
+    /// The last approval was withdrawn
+    RevokeLast,
+    Execute,
+    Cancel,
+    /// A critical finding was recorded against the proposal
+    Freeze,
+}
+
+impl ProposalStatus {
+    /// The only place proposal statuses change. Mirrors the on-chain rules:
+    ///
+    /// | from                 | event            | to             |
+    /// |----------------------|------------------|----------------|
+    /// | Proposed, Approved   | Approve          | Approved       |
+    /// | Proposed, Approved   | ThresholdReached | TimelockActive |
+    /// | Approved             | Revoke           | Approved       |
+    /// | Approved             | RevokeLast       | Proposed       |
+    /// | TimelockActive       | Execute          | Executed       |
+    /// | Proposed, Approved, TimelockActive | Freeze | Frozen   |
+    /// | any non-terminal     | Cancel           | Cancelled      |
+    ///
+    /// Executed and Cancelled are terminal and reject every event with
+    /// `AlreadyExecuted` / `AlreadyCancelled`. A frozen proposal rejects
+    /// everything but Cancel with `ProposalFrozen` and leaves only through
+    /// `unfreeze`. Other jumps are `InvalidTransition`.
+    pub fn transition(&self, event: ProposalEvent) -> Result<ProposalStatus, UpgradeError> {
+        use ProposalEvent::*;
+        use ProposalStatus::*;
+
+        match (self, event) {
+            (Executed, _) => Err(UpgradeError::AlreadyExecuted),
+            (Cancelled, _) => Err(UpgradeError::AlreadyCancelled),
+            (Proposed | Approved, Approve) => Ok(Approved),
+            (Proposed | Approved, ThresholdReached) => Ok(TimelockActive),
+            (Approved, Revoke) => Ok(Approved),
+            (Approved, RevokeLast) => Ok(Proposed),
+            (TimelockActive, Execute) => Ok(Executed),
+            (Proposed | Approved | TimelockActive | Frozen, Cancel) => Ok(Cancelled),
+            (Proposed | Approved | TimelockActive, Freeze) => Ok(Frozen),
+            (Frozen, _) => Err(UpgradeError::ProposalFrozen),
+            (from, event) => Err(UpgradeError::InvalidTransition {
+                from: format!("{:?}", from),
+                event: format!("{:?}", event),
+            }),
+        }
+    }
+
+    /// The status a cleared freeze returns to: the one the proposal was frozen from
+    pub fn unfreeze(&self, frozen_from: &ProposalStatus) -> Result<ProposalStatus, UpgradeError> {
+        use ProposalStatus::*;
+
+        match (self, frozen_from) {
+            (Frozen, Proposed | Approved | TimelockActive) => Ok(frozen_from.clone()),
+            (Frozen, other) => Err(UpgradeError::InternalError(format!(
+                "Proposal cannot have been frozen from {:?}",
+                other
+            ))),
+            (from, _) => Err(UpgradeError::InvalidTransition {
+                from: format!("{:?}", from),
+                event: "Unfreeze".to_string(),
+            }),
+        }
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ProposalStatus::Executed | ProposalStatus::Cancelled)
+    }
+
+    /// Value stored in `upgrade_proposals.status`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Proposed => "proposed",
+            ProposalStatus::Approved => "approved",
+            ProposalStatus::TimelockActive => "timelock_active",
+            ProposalStatus::Executed => "executed",
+            ProposalStatus::Cancelled => "cancelled",
+            ProposalStatus::Frozen => "frozen",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "proposed" => Some(ProposalStatus::Proposed),
+            "approved" => Some(ProposalStatus::Approved),
+            "timelock_active" => Some(ProposalStatus::TimelockActive),
+            "executed" => Some(ProposalStatus::Executed),
+            "cancelled" => Some(ProposalStatus::Cancelled),
+            "frozen" => Some(ProposalStatus::Frozen),
+            _ => None,
+        }
+    }
+}
+
+/// A proposal matched by `GET /upgrade/search`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposalSearchHit {
+    pub proposal_id: String,
+    pub program: String,
+    pub proposer: String,
+    pub version_tag: Option<String>,
+    pub status: String,
+    pub description: String,
+    pub proposed_at: i64,
+    pub rank: f32,
+    /// Description fragments with matches wrapped in `<mark>`
+    pub highlight: String,
 }
 
 pub struct ProposalManager {
@@ -36,6 +575,13 @@ pub struct ProposalManager {
     timelock_manager: Arc<TimelockManager>,
     program_builder: Arc<ProgramBuilder>,
     proposals: Arc<Mutex<Vec<Proposal>>>,
+    database: Option<Arc<Database>>,
+    confirmation: Option<Arc<ConfirmationTracker>>,
+    announcements: Option<Arc<AnnouncementService>>,
+    commit_statuses: Option<Arc<CommitStatusReporter>>,
+    monitoring: Option<Arc<MonitoringService>>,
+    notifications: Option<Arc<NotificationService>>,
+    timelock_duration: i64,
 }
 
 impl ProposalManager {
@@ -49,20 +595,139 @@ impl ProposalManager {
             timelock_manager,
             program_builder,
             proposals: Arc::new(Mutex::new(Vec::new())),
+            database: None,
+            confirmation: None,
+            announcements: None,
+            commit_statuses: None,
+            monitoring: None,
+            notifications: None,
+            timelock_duration: DEFAULT_TIMELOCK_DURATION,
         })
     }
 
+    /// Timelock for new proposals; must match the program's `timelock_duration`
+    pub fn with_timelock_duration(mut self, timelock_duration: i64) -> Result<Self, UpgradeError> {
+        validate_timelock_duration(timelock_duration)?;
+        self.timelock_duration = timelock_duration;
+        Ok(self)
+    }
+
+    pub fn timelock_duration(&self) -> i64 {
+        self.timelock_duration
+    }
+
+    /// Only mark a proposal executed once its transaction reaches the tracker's commitment
+    pub fn with_confirmation(mut self, confirmation: Arc<ConfirmationTracker>) -> Self {
+        self.confirmation = Some(confirmation);
+        self
+    }
+
+    /// Announce the execution window once a proposal enters its timelock
+    pub fn with_announcements(mut self, announcements: Arc<AnnouncementService>) -> Self {
+        self.announcements = Some(announcements);
+        self
+    }
+
+    /// Alert and notify clients when a proposal is frozen or unfrozen
+    pub fn with_freeze_alerts(
+        mut self,
+        monitoring: Arc<MonitoringService>,
+        notifications: Arc<NotificationService>,
+    ) -> Self {
+        self.monitoring = Some(monitoring);
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Post each status change to the GitHub commit the proposal was built from
+    pub fn with_commit_statuses(mut self, commit_statuses: Arc<CommitStatusReporter>) -> Self {
+        self.commit_statuses = Some(commit_statuses);
+        self
+    }
+
+    /// Mirror proposals and their status changes into `upgrade_proposals`
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Log a failed write to `upgrade_proposals`; the in-memory proposal stays authoritative
+    fn warn_unpersisted(proposal_id: &str, event: ProposalEvent, persisted: Result<ProposalStatus, UpgradeError>) {
+        if let Err(e) = persisted {
+            tracing::warn!("Failed to persist {:?} for proposal {}: {}", event, proposal_id, e);
+        }
+    }
+
+    /// Store the executed status and the upgrade_history row in one transaction
+    async fn persist_execution(&self, proposal: &Proposal) {
+        if let Some(database) = &self.database {
+            let new_program_hash = proposal.source
+                .as_ref()
+                .map(|s| s.artifact_hash.as_str())
+                .unwrap_or_default();
+            let persisted = database
+                .execute_proposal_txn(
+                    &proposal.id,
+                    proposal.executed_at.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+                    &proposal.program,
+                    new_program_hash,
+                    proposal.kind(),
+                )
+                .await;
+            Self::warn_unpersisted(&proposal.id, ProposalEvent::Execute, persisted);
+        }
+    }
+
+    /// Report in the background, so GitHub being slow or down never holds up a transition
+    fn report_commit_status(&self, proposal: &Proposal) {
+        let Some(commit_statuses) = self.commit_statuses.clone() else {
+            return;
+        };
+        if proposal.source.is_none() {
+            return;
+        }
+        let proposal = proposal.clone();
+        tokio::spawn(async move {
+            if let Err(e) = commit_statuses.report(&proposal).await {
+                tracing::warn!("Failed to report proposal {} to GitHub: {}", proposal.id, e);
+            }
+        });
+    }
+
+    pub async fn search(&self, query: &str, limit: i64) -> Result<Vec<ProposalSearchHit>, UpgradeError> {
+        let database = self.database.as_ref().ok_or_else(|| {
+            UpgradeError::InternalError("Proposal search requires a database".to_string())
+        })?;
+        database.search_proposals(query, limit).await
+    }
+
     pub async fn propose_upgrade(
         &self,
         new_program_buffer: Pubkey,
         description: String,
     ) -> Result<String, UpgradeError> {
+        self.propose_upgrade_with_options(new_program_buffer, description, ProposalOptions::default())
+            .await
+    }
+
+    pub async fn propose_upgrade_with_options(
+        &self,
+        new_program_buffer: Pubkey,
+        description: String,
+        options: ProposalOptions,
+    ) -> Result<String, UpgradeError> {
+        let mut depends_on = options.depends_on;
+        let mut supersedes = options.supersedes;
+        for links in [&mut depends_on, &mut supersedes] {
+            links.sort();
+            links.dedup();
+        }
+        validate_links(&depends_on, &supersedes, &self.proposals.lock().await)?;
+
         let proposal_id = uuid::Uuid::new_v4().to_string();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
-        let timelock_duration = 48 * 60 * 60; // 48 hours
+        // The program enforces the timelock against chain time
+        let now = self.timelock_manager.now();
+        let timelock_duration = self.timelock_duration;
         let timelock_until = now + timelock_duration;
 
         // Create proposal via multisig
@@ -70,8 +735,11 @@ impl ProposalManager {
             .multisig
             .propose_transaction(ProposalParams {
                 instruction: self.build_upgrade_instruction(&new_program_buffer)?,
+                buffer: new_program_buffer,
                 description: description.clone(),
                 timelock: timelock_duration,
+                deployment: options.deployment.clone(),
+                authority_change: options.authority_change.clone(),
             })
             .await?;
 
@@ -79,7 +747,10 @@ impl ProposalManager {
         let proposal = Proposal {
             id: proposal_id.clone(),
             proposer: "multisig".to_string(), // In real implementation, get from context
-            program: "program_id".to_string(), // In real implementation, get from config
+            program: options.deployment
+                .as_ref()
+                .map(|deployment| deployment.program_id.clone())
+                .unwrap_or_else(|| "program_id".to_string()), // In real implementation, get from config
             new_buffer: new_program_buffer.to_string(),
             description,
             proposed_at: now,
@@ -88,32 +759,81 @@ impl ProposalManager {
             approval_threshold: 3, // 3 of 5
             status: ProposalStatus::Proposed,
             executed_at: None,
+            source: options.source,
+            publish_idl: options.publish_idl,
+            cancellation: None,
+            risk_tier: options.risk_tier,
+            attachments: options.attachments,
+            depends_on,
+            supersedes,
+            freeze: None,
+            deployment: options.deployment,
+            authority_change: options.authority_change,
         };
 
+        if let Some(database) = &self.database {
+            if let Err(e) = database
+                .save_proposal(
+                    &proposal.id,
+                    &proposal.proposer,
+                    &proposal.program,
+                    &proposal.new_buffer,
+                    &proposal.description,
+                    proposal.timelock_until,
+                    proposal.approval_threshold as i32,
+                    proposal.source.as_ref().map(|source| source.tag.as_str()),
+                )
+                .await
+            {
+                tracing::warn!("Failed to persist proposal {}: {}", proposal.id, e);
+            }
+            if let Err(e) = database
+                .save_proposal_links(&proposal.id, &proposal.depends_on, &proposal.supersedes)
+                .await
+            {
+                tracing::warn!("Failed to persist links of proposal {}: {}", proposal.id, e);
+            }
+            if let Some(deployment) = &proposal.deployment {
+                if let Err(e) = database.save_proposal_deployment(&proposal.id, deployment).await {
+                    tracing::warn!("Failed to persist deployment of proposal {}: {}", proposal.id, e);
+                }
+            }
+            if let Some(change) = &proposal.authority_change {
+                if let Err(e) = database.save_proposal_authority_change(&proposal.id, change).await {
+                    tracing::warn!("Failed to persist authority change of proposal {}: {}", proposal.id, e);
+                }
+            }
+        }
+
+        self.report_commit_status(&proposal);
+        let superseded = proposal.supersedes.clone();
         let mut proposals = self.proposals.lock().await;
         proposals.push(proposal);
+        drop(proposals);
 
-        // Notify community
-        self.notify_community(&proposal_id).await?;
+        // The replacement exists now; one that executed meanwhile stays executed
+        for id in superseded {
+            if let Err(e) = self
+                .cancel_upgrade(&id, CancellationReason::Superseded, format!("Superseded by proposal {}", proposal_id))
+                .await
+            {
+                tracing::warn!("Failed to cancel proposal {} superseded by {}: {}", id, proposal_id, e);
+            }
+        }
 
         Ok(proposal_id)
     }
 
     pub async fn execute_upgrade(&self, proposal_id: &str) -> Result<(), UpgradeError> {
         let mut proposals = self.proposals.lock().await;
+        ensure_dependencies_executed(proposal_id, &proposals)?;
         let proposal = proposals
             .iter_mut()
             .find(|p| p.id == proposal_id)
             .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
 
         // Check status
-        if proposal.status == ProposalStatus::Executed {
-            return Err(UpgradeError::AlreadyExecuted);
-        }
-
-        if proposal.status == ProposalStatus::Cancelled {
-            return Err(UpgradeError::AlreadyCancelled);
-        }
+        let executed = proposal.status.transition(ProposalEvent::Execute)?;
 
         // Wait for timelock to expire
         self.wait_for_timelock(proposal_id).await?;
@@ -125,21 +845,52 @@ impl ProposalManager {
                 required: proposal.approval_threshold as usize,
             });
         }
+        let organizations = self.multisig.organizations_of(&proposal.approvals);
+        let organization_quorum = self.multisig.get_organization_quorum() as usize;
+        if organizations < organization_quorum {
+            return Err(UpgradeError::InsufficientOrganizations {
+                current: organizations,
+                required: organization_quorum,
+            });
+        }
+
+        // The deployer must not still hold the buffer it could rewrite after
+        // approval. An authority change has no buffer.
+        if proposal.authority_change.is_none() {
+            let buffer: Pubkey = proposal.new_buffer.parse().map_err(|_| UpgradeError::InvalidPubkey)?;
+            self.multisig.verify_buffer_handoff(&buffer).await?;
+        }
+
+        // A program that outgrew its data account is extended in the same transaction
+        let extension = match (&proposal.deployment, &proposal.authority_change, proposal.program.parse::<Pubkey>()) {
+            (None, None, Ok(program)) => {
+                let buffer: Pubkey = proposal.new_buffer.parse().map_err(|_| UpgradeError::InvalidPubkey)?;
+                self.multisig.program_extension(&program, &buffer).await?
+            }
+            _ => None,
+        };
 
         // Execute via multisig
-        self.multisig.execute_transaction(proposal_id).await?;
+        let signature = self.multisig.execute_transaction(proposal_id, extension).await?;
+
+        // A dropped or failed transaction errors here, leaving the proposal for a retry
+        if let (Some(confirmation), Some(signature)) = (&self.confirmation, signature) {
+            confirmation.track(proposal_id, &signature).await?;
+        }
 
         // Verify upgrade
         self.verify_upgrade().await?;
 
         // Update proposal
-        proposal.status = ProposalStatus::Executed;
+        proposal.status = executed;
         proposal.executed_at = Some(
             std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs() as i64
         );
+        self.persist_execution(proposal).await;
+        self.report_commit_status(proposal);
 
         // Announce completion
         self.announce_upgrade(proposal_id).await?;
@@ -147,27 +898,294 @@ impl ProposalManager {
         Ok(())
     }
 
-    pub async fn cancel_upgrade(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+    /// Mark a proposal executed by a transaction sent outside `execute_upgrade`,
+    /// e.g. one assembled from member signatures, once it has been confirmed
+    pub async fn record_execution(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        proposal.status = proposal.status.transition(ProposalEvent::Execute)?;
+        proposal.executed_at = Some(chrono::Utc::now().timestamp());
+        self.persist_execution(proposal).await;
+        self.report_commit_status(proposal);
+
+        self.announce_upgrade(proposal_id).await
+    }
+
+    pub async fn cancel_upgrade(
+        &self,
+        proposal_id: &str,
+        reason: CancellationReason,
+        details: String,
+    ) -> Result<(), UpgradeError> {
+        if details.len() > MAX_CANCELLATION_DETAILS_LEN {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Cancellation details exceed {} bytes",
+                MAX_CANCELLATION_DETAILS_LEN
+            )));
+        }
+        if reason == CancellationReason::Other && details.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest(
+                "Cancellation reason 'other' requires details".to_string(),
+            ));
+        }
+
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        proposal.status = proposal.status.transition(ProposalEvent::Cancel)?;
+        proposal.cancellation = Some(Cancellation {
+            reason,
+            details: details.clone(),
+            cancelled_at: chrono::Utc::now().timestamp(),
+        });
+        if let Some(database) = &self.database {
+            let persisted = database.cancel_proposal_txn(proposal_id, reason.as_str(), &details).await;
+            Self::warn_unpersisted(proposal_id, ProposalEvent::Cancel, persisted);
+        }
+        self.report_commit_status(proposal);
+
+        Ok(())
+    }
+
+    /// Record a member's approval; the approval meeting both the threshold and
+    /// the organization quorum starts the timelock
+    pub async fn record_approval(&self, proposal_id: &str, approver: &str) -> Result<ProposalStatus, UpgradeError> {
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        if proposal.approvals.iter().any(|a| a == approver) {
+            return Err(UpgradeError::InvalidRequest(format!("{} already approved", approver)));
+        }
+
+        let mut approvals = proposal.approvals.clone();
+        approvals.push(approver.to_string());
+        let event = if approvals.len() >= proposal.approval_threshold as usize
+            && self.multisig.organizations_of(&approvals) >= self.multisig.get_organization_quorum() as usize
+        {
+            ProposalEvent::ThresholdReached
+        } else {
+            ProposalEvent::Approve
+        };
+        proposal.status = proposal.status.transition(event)?;
+        proposal.approvals = approvals;
+        if let Some(database) = &self.database {
+            let persisted = database.approve_proposal_txn(proposal_id, approver, event).await;
+            Self::warn_unpersisted(proposal_id, event, persisted);
+        }
+        self.report_commit_status(proposal);
+
+        let status = proposal.status.clone();
+        if status == ProposalStatus::TimelockActive {
+            let proposal = proposal.clone();
+            drop(proposals);
+            self.notify_community(&proposal).await;
+        }
+
+        Ok(status)
+    }
+
+    /// Withdraw a member's approval. Only possible below the threshold; once
+    /// the timelock runs the approvals are final and cancelling is the way out.
+    pub async fn revoke_approval(
+        &self,
+        proposal_id: &str,
+        approver: &str,
+        reason: &str,
+    ) -> Result<ProposalStatus, UpgradeError> {
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        if !proposal.approvals.iter().any(|a| a == approver) {
+            return Err(UpgradeError::InvalidRequest(format!("{} has not approved", approver)));
+        }
+
+        let mut approvals = proposal.approvals.clone();
+        approvals.retain(|a| a != approver);
+        let event = if approvals.is_empty() {
+            ProposalEvent::RevokeLast
+        } else {
+            ProposalEvent::Revoke
+        };
+        proposal.status = proposal.status.transition(event)?;
+        proposal.approvals = approvals;
+        if let Some(database) = &self.database {
+            let persisted = database.revoke_approval_txn(proposal_id, approver, reason, event).await;
+            Self::warn_unpersisted(proposal_id, event, persisted);
+        }
+        self.report_commit_status(proposal);
+
+        Ok(proposal.status.clone())
+    }
+
+    /// Hold a proposal on a critical finding: approvals and execution are
+    /// refused until `unfreeze`. Freezing a frozen proposal is an error, so
+    /// the first finding stays the one to clear.
+    pub async fn freeze(
+        &self,
+        proposal_id: &str,
+        finding: String,
+        source: FreezeSource,
+        frozen_by: &str,
+    ) -> Result<ProposalFreeze, UpgradeError> {
+        let mut proposals = self.proposals.lock().await;
+        let proposal = proposals
+            .iter_mut()
+            .find(|p| p.id == proposal_id)
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+
+        let frozen = proposal.status.transition(ProposalEvent::Freeze)?;
+        let freeze = ProposalFreeze {
+            finding,
+            source,
+            frozen_by: frozen_by.to_string(),
+            frozen_from: proposal.status.clone(),
+            frozen_at: chrono::Utc::now().timestamp(),
+        };
+        proposal.status = frozen;
+        proposal.freeze = Some(freeze.clone());
+        if let Some(database) = &self.database {
+            let persisted = database.freeze_proposal_txn(proposal_id, &freeze).await;
+            Self::warn_unpersisted(proposal_id, ProposalEvent::Freeze, persisted);
+        }
+        self.report_commit_status(proposal);
+        drop(proposals);
+
+        let message = format!(
+            "Proposal {} frozen by {} ({}): {}",
+            proposal_id,
+            freeze.frozen_by,
+            source.as_str(),
+            freeze.finding
+        );
+        self.alert(AlertLevel::Critical, message).await;
+        if let Some(notifications) = &self.notifications {
+            notifications
+                .notify_proposal_frozen(proposal_id.to_string(), serde_json::json!(freeze))
+                .await;
+        }
+
+        Ok(freeze)
+    }
+
+    /// Clear a proposal's critical finding and return it to the status it was frozen from
+    pub async fn unfreeze(
+        &self,
+        proposal_id: &str,
+        cleared_by: &str,
+        resolution: &str,
+    ) -> Result<ProposalStatus, UpgradeError> {
         let mut proposals = self.proposals.lock().await;
         let proposal = proposals
             .iter_mut()
             .find(|p| p.id == proposal_id)
             .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
 
-        if proposal.status == ProposalStatus::Executed {
-            return Err(UpgradeError::AlreadyExecuted);
+        let freeze = proposal.freeze.clone().ok_or_else(|| UpgradeError::InvalidTransition {
+            from: format!("{:?}", proposal.status),
+            event: "Unfreeze".to_string(),
+        })?;
+        let restored = proposal.status.unfreeze(&freeze.frozen_from)?;
+        proposal.status = restored.clone();
+        proposal.freeze = None;
+        if let Some(database) = &self.database {
+            if let Err(e) = database.clear_freeze(proposal_id, cleared_by, resolution, &restored).await {
+                tracing::warn!("Failed to persist unfreeze of proposal {}: {}", proposal_id, e);
+            }
         }
+        self.report_commit_status(proposal);
+        drop(proposals);
 
-        proposal.status = ProposalStatus::Cancelled;
+        self.alert(
+            AlertLevel::Info,
+            format!("Proposal {} unfrozen by {}: {}", proposal_id, cleared_by, resolution),
+        )
+        .await;
+        if let Some(notifications) = &self.notifications {
+            let data = serde_json::json!({
+                "finding": freeze.finding,
+                "cleared_by": cleared_by,
+                "resolution": resolution,
+                "status": restored,
+            });
+            notifications
+                .notify_proposal_unfrozen(proposal_id.to_string(), data)
+                .await;
+        }
+
+        Ok(restored)
+    }
 
+    /// Refuse to act on a frozen proposal before anything outside the
+    /// proposal, like a multisig vote, has been recorded
+    pub async fn ensure_not_frozen(&self, proposal_id: &str) -> Result<(), UpgradeError> {
+        if self.get_proposal(proposal_id).await?.status == ProposalStatus::Frozen {
+            return Err(UpgradeError::ProposalFrozen);
+        }
         Ok(())
     }
 
+    async fn alert(&self, level: AlertLevel, message: String) {
+        match &self.monitoring {
+            Some(monitoring) => monitoring.send_alert(level, message, COMPONENT_SECURITY.to_string()).await,
+            None => tracing::warn!("{}", message),
+        }
+    }
+
     pub async fn list_proposals(&self) -> Result<Vec<Proposal>, UpgradeError> {
         let proposals = self.proposals.lock().await;
         Ok(proposals.clone())
     }
 
+    pub async fn get_proposal(&self, proposal_id: &str) -> Result<Proposal, UpgradeError> {
+        let proposals = self.proposals.lock().await;
+        proposals
+            .iter()
+            .find(|p| p.id == proposal_id)
+            .cloned()
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))
+    }
+
+    pub async fn get_links(&self, proposal_id: &str) -> Result<ProposalLinks, UpgradeError> {
+        let proposals = self.proposals.lock().await;
+        proposals
+            .iter()
+            .find(|p| p.id == proposal_id)
+            .map(|p| ProposalLinks::build(p, &proposals))
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))
+    }
+
+    pub async fn list_public_proposals(&self) -> Result<Vec<PublicProposal>, UpgradeError> {
+        let now = self.timelock_manager.now();
+        let proposals = self.proposals.lock().await;
+        Ok(proposals
+            .iter()
+            .map(|p| PublicProposal::from_proposal(p, now))
+            .collect())
+    }
+
+    pub async fn get_public_proposal(&self, proposal_id: &str) -> Result<PublicProposal, UpgradeError> {
+        let now = self.timelock_manager.now();
+        let proposals = self.proposals.lock().await;
+        proposals
+            .iter()
+            .find(|p| p.id == proposal_id)
+            .map(|p| PublicProposal::from_proposal(p, now))
+            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))
+    }
+
     pub async fn get_proposal_status(
         &self,
         proposal_id: &str,
@@ -183,14 +1201,17 @@ impl ProposalManager {
             "status": proposal.status,
             "approvals": proposal.approvals.len(),
             "threshold": proposal.approval_threshold,
+            "organizations": self.multisig.organizations_of(&proposal.approvals),
+            "organization_quorum": self.multisig.get_organization_quorum(),
             "timelock_until": proposal.timelock_until,
+            "timelock_remaining_seconds": (proposal.timelock_until - self.timelock_manager.now()).max(0),
             "executed_at": proposal.executed_at,
         }))
     }
 
     async fn wait_for_timelock(&self, proposal_id: &str) -> Result<(), UpgradeError> {
         let timelock_end = self.timelock_manager.get_timelock_end(proposal_id).await?;
-        let now = Utc::now().timestamp();
+        let now = self.timelock_manager.now();
 
         if now < timelock_end {
             let remaining = timelock_end - now;
@@ -215,10 +1236,16 @@ impl ProposalManager {
         Ok(())
     }
 
-    async fn notify_community(&self, proposal_id: &str) -> Result<(), UpgradeError> {
-        // Notify community via multiple channels
-        tracing::info!("Notifying community about proposal: {}", proposal_id);
-        Ok(())
+    /// Publish the execution window; a failure is logged rather than undoing the approval
+    async fn notify_community(&self, proposal: &Proposal) {
+        let Some(announcements) = &self.announcements else {
+            tracing::info!("Proposal {} entered its timelock until {}", proposal.id, proposal.timelock_until);
+            return;
+        };
+
+        if let Err(e) = announcements.announce(proposal).await {
+            tracing::error!("Failed to announce proposal {}: {}", proposal.id, e);
+        }
     }
 
     fn build_upgrade_instruction(
@@ -234,7 +1261,13 @@ impl ProposalManager {
 #[derive(Debug)]
 pub struct ProposalParams {
     pub instruction: Vec<u8>,
+    /// Buffer holding the new program, for backends that build their own upgrade instruction
+    pub buffer: Pubkey,
     pub description: String,
     pub timelock: i64,
+    /// Deploy a new program from the buffer instead of upgrading
+    pub deployment: Option<ProgramDeployment>,
+    /// Change the upgrade authority instead; `buffer` is then its target
+    pub authority_change: Option<AuthorityChange>,
 }
 
@@ -0,0 +1,494 @@
+use crate::error::UpgradeError;
+use crate::multisig::AccountReader;
+use crate::multisig_backend::{
+    fetch_account_data, push_instruction, push_string, read_instruction, remaining_account,
+    BackendTransactionStatus, MultisigBackend, MultisigBackendKind, ProposedTransaction, VoteTally,
+};
+use crate::squads::{deploy_instruction, set_authority_instruction, upgrade_instruction};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::sync::Arc;
+
+/// The SPL Governance deployment Realms uses
+pub const SPL_GOVERNANCE_PROGRAM_ID: &str = "GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw";
+
+// `GovernanceInstruction` variant indexes
+const CREATE_PROPOSAL: u8 = 6;
+const INSERT_TRANSACTION: u8 = 9;
+const SIGN_OFF_PROPOSAL: u8 = 12;
+const CAST_VOTE: u8 = 13;
+const FINALIZE_VOTE: u8 = 14;
+const EXECUTE_TRANSACTION: u8 = 16;
+
+/// `ProposalState`, in declaration order
+const PROPOSAL_STATES: [&str; 10] = [
+    "draft",
+    "signing_off",
+    "voting",
+    "succeeded",
+    "executing",
+    "completed",
+    "cancelled",
+    "defeated",
+    "executing_with_errors",
+    "vetoed",
+];
+
+/// The `ProposalV2` account fields the backend uses
+#[derive(Debug, Clone)]
+pub struct RealmsProposal {
+    pub governance: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub state: String,
+    /// Token owner record of the proposal's author
+    pub token_owner_record: Pubkey,
+    pub yes_vote_weight: u64,
+    pub deny_vote_weight: u64,
+}
+
+impl RealmsProposal {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::new(data);
+        reader.skip(1); // account_type
+        let governance = reader.pubkey()?;
+        let governing_token_mint = reader.pubkey()?;
+        let state = PROPOSAL_STATES
+            .get(reader.u8()? as usize)
+            .ok_or_else(|| UpgradeError::MultisigError("Invalid Realms proposal state".to_string()))?;
+        let token_owner_record = reader.pubkey()?;
+        reader.skip(1 + 1); // signatories_count, signatories_signed_off_count
+        if reader.u8()? == 1 {
+            reader.skip(4); // MultiChoice parameters
+        }
+
+        // Upgrades are single-option proposals, so the first option carries the yes votes
+        let mut yes_vote_weight = 0;
+        for index in 0..reader.u32()? {
+            reader.bytes()?; // label
+            let weight = reader.u64()?;
+            if index == 0 {
+                yes_vote_weight = weight;
+            }
+            reader.skip(1 + 2 + 2 + 2); // vote_result, transactions executed/count/next_index
+        }
+        let deny_vote_weight = if reader.u8()? == 1 { reader.u64()? } else { 0 };
+
+        Ok(Self {
+            governance,
+            governing_token_mint,
+            state: state.to_string(),
+            token_owner_record,
+            yes_vote_weight,
+            deny_vote_weight,
+        })
+    }
+}
+
+/// The `GovernanceV2` config fields the backend uses
+#[derive(Debug, Clone)]
+pub struct RealmsGovernanceConfig {
+    pub realm: Pubkey,
+    /// Yes-vote percentage for community proposals; `None` when disabled
+    pub community_vote_threshold: Option<u8>,
+    pub council_vote_threshold: Option<u8>,
+    pub hold_up_time: u32,
+    pub voting_base_time: u32,
+}
+
+impl RealmsGovernanceConfig {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        // VoteThreshold::{YesVotePercentage(u8), QuorumPercentage(u8), Disabled}
+        fn threshold(reader: &mut AccountReader) -> Result<Option<u8>, UpgradeError> {
+            match reader.u8()? {
+                0 | 1 => Ok(Some(reader.u8()?)),
+                _ => Ok(None),
+            }
+        }
+
+        let mut reader = AccountReader::new(data);
+        reader.skip(1); // account_type
+        let realm = reader.pubkey()?;
+        reader.skip(32 + 4); // governance_seed, reserved
+        let community_vote_threshold = threshold(&mut reader)?;
+        reader.skip(8); // min_community_weight_to_create_proposal
+        let hold_up_time = reader.u32()?;
+        let voting_base_time = reader.u32()?;
+        reader.skip(1); // community_vote_tipping
+        let council_vote_threshold = threshold(&mut reader)?;
+
+        Ok(Self {
+            realm,
+            community_vote_threshold,
+            council_vote_threshold,
+            hold_up_time,
+            voting_base_time,
+        })
+    }
+}
+
+/// Stored `ProposalTransactionV2` instructions
+pub fn decode_proposal_transaction(data: &[u8]) -> Result<Vec<Instruction>, UpgradeError> {
+    let mut reader = AccountReader::new(data);
+    reader.skip(1 + 32 + 1 + 2 + 4); // account_type, proposal, option_index, transaction_index, legacy
+    (0..reader.u32()?).map(|_| read_instruction(&mut reader)).collect()
+}
+
+/// Backend for SPL Governance (Realms). The governance account holds the
+/// upgrade authority and signs the upgrade when a passed proposal executes;
+/// votes are weighted by the members' deposited governing tokens.
+pub struct RealmsBackend {
+    rpc_client: Arc<AsyncRpcClient>,
+    program_id: Pubkey,
+    realm: Pubkey,
+    governance: Pubkey,
+    /// Mint whose holders vote, usually the realm's council mint
+    governing_token_mint: Pubkey,
+}
+
+impl RealmsBackend {
+    pub fn new(
+        rpc_client: Arc<AsyncRpcClient>,
+        program_id: Pubkey,
+        realm: Pubkey,
+        governance: Pubkey,
+        governing_token_mint: Pubkey,
+    ) -> Self {
+        Self {
+            rpc_client,
+            program_id,
+            realm,
+            governance,
+            governing_token_mint,
+        }
+    }
+
+    fn pda(&self, seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &self.program_id).0
+    }
+
+    pub fn token_owner_record_address(&self, owner: &Pubkey) -> Pubkey {
+        self.pda(&[b"governance", self.realm.as_ref(), self.governing_token_mint.as_ref(), owner.as_ref()])
+    }
+
+    /// Proposals are seeded by the upgrade they carry, so the same buffer
+    /// cannot be proposed twice
+    pub fn proposal_seed(program: &Pubkey, buffer: &Pubkey) -> Pubkey {
+        let mut hasher = Sha256::new();
+        hasher.update(program.as_ref());
+        hasher.update(buffer.as_ref());
+        Pubkey::new_from_array(hasher.finalize().into())
+    }
+
+    pub fn proposal_address(&self, proposal_seed: &Pubkey) -> Pubkey {
+        self.pda(&[
+            b"governance",
+            self.governance.as_ref(),
+            self.governing_token_mint.as_ref(),
+            proposal_seed.as_ref(),
+        ])
+    }
+
+    /// The upgrade is the proposal's only transaction: option 0, index 0
+    pub fn proposal_transaction_address(&self, proposal: &Pubkey) -> Pubkey {
+        self.pda(&[b"governance", proposal.as_ref(), &[0], &0u16.to_le_bytes()])
+    }
+
+    pub fn vote_record_address(&self, proposal: &Pubkey, token_owner_record: &Pubkey) -> Pubkey {
+        self.pda(&[b"governance", proposal.as_ref(), token_owner_record.as_ref()])
+    }
+
+    pub fn realm_config_address(&self) -> Pubkey {
+        self.pda(&[b"realm-config", self.realm.as_ref()])
+    }
+
+    pub fn proposal_deposit_address(&self, proposal: &Pubkey, payer: &Pubkey) -> Pubkey {
+        self.pda(&[b"proposal-deposit", proposal.as_ref(), payer.as_ref()])
+    }
+
+    /// `CreateProposal`, `InsertTransaction` and `SignOffProposal`, which opens
+    /// voting straight away
+    pub fn propose_instructions(
+        &self,
+        creator: &Pubkey,
+        proposal_seed: &Pubkey,
+        instruction: &Instruction,
+        name: &str,
+    ) -> Vec<Instruction> {
+        let proposal = self.proposal_address(proposal_seed);
+        let owner_record = self.token_owner_record_address(creator);
+
+        // { name, description_link, vote_type: SingleChoice, options, use_deny_option, proposal_seed }
+        let mut create = vec![CREATE_PROPOSAL];
+        push_string(&mut create, name);
+        push_string(&mut create, "");
+        create.push(0);
+        create.extend_from_slice(&1u32.to_le_bytes());
+        push_string(&mut create, "Approve");
+        create.push(1);
+        create.extend_from_slice(proposal_seed.as_ref());
+
+        // { option_index, index, legacy hold_up_time, instructions }
+        let mut insert = vec![INSERT_TRANSACTION, 0];
+        insert.extend_from_slice(&0u16.to_le_bytes());
+        insert.extend_from_slice(&0u32.to_le_bytes());
+        insert.extend_from_slice(&1u32.to_le_bytes());
+        push_instruction(&mut insert, instruction);
+
+        vec![
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.realm, false),
+                    AccountMeta::new(proposal, false),
+                    AccountMeta::new(self.governance, false),
+                    AccountMeta::new(owner_record, false),
+                    AccountMeta::new_readonly(self.governing_token_mint, false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                    AccountMeta::new_readonly(self.realm_config_address(), false),
+                    AccountMeta::new(self.proposal_deposit_address(&proposal, creator), false),
+                ],
+                data: create,
+            },
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.governance, false),
+                    AccountMeta::new(proposal, false),
+                    AccountMeta::new_readonly(owner_record, false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new(self.proposal_transaction_address(&proposal), false),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                    AccountMeta::new_readonly(sysvar::rent::id(), false),
+                ],
+                data: insert,
+            },
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new(self.realm, false),
+                    AccountMeta::new(self.governance, false),
+                    AccountMeta::new(proposal, false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new_readonly(owner_record, false),
+                ],
+                data: vec![SIGN_OFF_PROPOSAL],
+            },
+        ]
+    }
+
+    /// `CastVote` approving with the member's full weight
+    pub fn vote_instruction(&self, member: &Pubkey, proposal: &Pubkey, proposal_owner_record: &Pubkey) -> Instruction {
+        let voter_record = self.token_owner_record_address(member);
+
+        // Vote::Approve(vec![VoteChoice { rank: 0, weight_percentage: 100 }])
+        let mut data = vec![CAST_VOTE, 0];
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0, 100]);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.realm, false),
+                AccountMeta::new(self.governance, false),
+                AccountMeta::new(*proposal, false),
+                AccountMeta::new(*proposal_owner_record, false),
+                AccountMeta::new(voter_record, false),
+                AccountMeta::new_readonly(*member, true),
+                AccountMeta::new(self.vote_record_address(proposal, &voter_record), false),
+                AccountMeta::new_readonly(self.governing_token_mint, false),
+                AccountMeta::new(*member, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                AccountMeta::new_readonly(self.realm_config_address(), false),
+            ],
+            data,
+        }
+    }
+
+    /// `FinalizeVote`, tallying a proposal whose voting period has ended
+    pub fn finalize_instruction(&self, proposal: &Pubkey, proposal_owner_record: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.realm, false),
+                AccountMeta::new(self.governance, false),
+                AccountMeta::new(*proposal, false),
+                AccountMeta::new(*proposal_owner_record, false),
+                AccountMeta::new_readonly(self.governing_token_mint, false),
+                AccountMeta::new_readonly(self.realm_config_address(), false),
+            ],
+            data: vec![FINALIZE_VOTE],
+        }
+    }
+
+    /// `ExecuteTransaction`; the governance signs the wrapped instruction by PDA
+    pub fn execute_instruction(&self, proposal: &Pubkey, wrapped: &Instruction) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.governance, false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new(self.proposal_transaction_address(proposal), false),
+            AccountMeta::new_readonly(wrapped.program_id, false),
+        ];
+        accounts.extend(wrapped.accounts.iter().map(|meta| remaining_account(meta.pubkey, meta.is_writable)));
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: vec![EXECUTE_TRANSACTION],
+        }
+    }
+
+    async fn proposal(&self, proposal: &Pubkey) -> Result<RealmsProposal, UpgradeError> {
+        RealmsProposal::try_from_account_data(&fetch_account_data(&self.rpc_client, proposal).await?)
+    }
+
+    async fn governance_config(&self) -> Result<RealmsGovernanceConfig, UpgradeError> {
+        RealmsGovernanceConfig::try_from_account_data(&fetch_account_data(&self.rpc_client, &self.governance).await?)
+    }
+}
+
+#[async_trait]
+impl MultisigBackend for RealmsBackend {
+    fn kind(&self) -> MultisigBackendKind {
+        MultisigBackendKind::Realms
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    fn vault(&self) -> Option<Pubkey> {
+        Some(self.governance)
+    }
+
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.governance)
+    }
+
+    async fn propose(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let seed = Self::proposal_seed(program, buffer);
+        let upgrade = upgrade_instruction(program, buffer, &self.governance, creator);
+
+        Ok(ProposedTransaction {
+            key: self.proposal_address(&seed),
+            instructions: self.propose_instructions(creator, &seed, &upgrade, description),
+        })
+    }
+
+    fn wraps_loader_instructions(&self) -> bool {
+        true
+    }
+
+    async fn propose_deploy(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        max_data_len: usize,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let seed = Self::proposal_seed(program, buffer);
+        let deploy = deploy_instruction(program, buffer, &self.governance, max_data_len);
+
+        Ok(ProposedTransaction {
+            key: self.proposal_address(&seed),
+            instructions: self.propose_instructions(creator, &seed, &deploy, description),
+        })
+    }
+
+    async fn propose_set_authority(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        new_authority: Option<&Pubkey>,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        // The new authority stands in for the buffer, as on the native program
+        let seed = Self::proposal_seed(program, &new_authority.copied().unwrap_or_default());
+        let set_authority = set_authority_instruction(program, &self.governance, new_authority);
+
+        Ok(ProposedTransaction {
+            key: self.proposal_address(&seed),
+            instructions: self.propose_instructions(creator, &seed, &set_authority, description),
+        })
+    }
+
+    async fn approve(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let proposal = self.proposal(transaction).await?;
+        if proposal.state != "voting" {
+            return Err(UpgradeError::MultisigError(format!(
+                "Realms proposal {} is {}, not voting",
+                transaction, proposal.state
+            )));
+        }
+
+        Ok(vec![self.vote_instruction(member, transaction, &proposal.token_owner_record)])
+    }
+
+    /// Finalizes the vote first when the voting period ended without the
+    /// vote tipping early; the chain rejects both before the period and the
+    /// governance hold-up time have passed
+    async fn execute(&self, _member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let proposal = self.proposal(transaction).await?;
+        let mut instructions = match proposal.state.as_str() {
+            "voting" => vec![self.finalize_instruction(transaction, &proposal.token_owner_record)],
+            "succeeded" | "executing" => Vec::new(),
+            state => {
+                return Err(UpgradeError::MultisigError(format!(
+                    "Realms proposal {} is {} and cannot be executed",
+                    transaction, state
+                )))
+            }
+        };
+
+        let stored = decode_proposal_transaction(
+            &fetch_account_data(&self.rpc_client, &self.proposal_transaction_address(transaction)).await?,
+        )?;
+        instructions.extend(stored.iter().map(|wrapped| self.execute_instruction(transaction, wrapped)));
+        Ok(instructions)
+    }
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError> {
+        let proposal = self.proposal(transaction).await?;
+        let config = self.governance_config().await?;
+
+        // The realm's community mint votes against the community threshold, anything else is council
+        let realm = fetch_account_data(&self.rpc_client, &config.realm).await?;
+        let mut reader = AccountReader::new(&realm);
+        reader.skip(1); // account_type
+        let threshold = if proposal.governing_token_mint == reader.pubkey()? {
+            config.community_vote_threshold
+        } else {
+            config.council_vote_threshold
+        };
+
+        Ok(BackendTransactionStatus {
+            key: transaction.to_string(),
+            executable: matches!(proposal.state.as_str(), "succeeded" | "executing"),
+            status: proposal.state,
+            // Votes are token-weighted rather than one per member
+            approvals: Vec::new(),
+            threshold: threshold.unwrap_or(0) as u16,
+            votes: Some(VoteTally {
+                yes: proposal.yes_vote_weight,
+                no: proposal.deny_vote_weight,
+            }),
+        })
+    }
+
+    async fn check_health(&self) -> Result<(), UpgradeError> {
+        self.governance_config().await.map(|_| ())
+    }
+}
@@ -0,0 +1,220 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::proposal::Proposal;
+use crate::secrets::SecretStore;
+use crate::signer;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::bpf_loader_upgradeable::UpgradeableLoaderState;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Domain separator for the signed receipt message; bump when the layout changes
+pub const RECEIPT_VERSION: &str = "goquant-approval-receipt-v1";
+
+/// Signature scheme used for receipts
+pub const RECEIPT_ALGORITHM: &str = "ed25519";
+
+/// What a member approved, and when
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalReceipt {
+    pub version: String,
+    pub member: String,
+    pub proposal_id: String,
+    /// Upgrade manager proposal PDA, when the managed program is configured
+    pub proposal_pda: Option<String>,
+    pub buffer: String,
+    /// SHA-256 of the approved binary, hex encoded
+    pub buffer_hash: String,
+    pub approved_at: i64,
+}
+
+impl ApprovalReceipt {
+    /// Bytes covered by the signature: one field per line, in a fixed order, so
+    /// verifiers can rebuild them without a JSON canonicalisation step.
+    pub fn message(&self) -> String {
+        [
+            self.version.as_str(),
+            self.member.as_str(),
+            self.proposal_id.as_str(),
+            self.proposal_pda.as_deref().unwrap_or(""),
+            self.buffer.as_str(),
+            self.buffer_hash.as_str(),
+            &self.approved_at.to_string(),
+        ]
+        .join("\n")
+    }
+}
+
+/// A receipt together with the backend's signature over `message()`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignedReceipt {
+    pub receipt: ApprovalReceipt,
+    pub message: String,
+    /// Base58 signer pubkey
+    pub signer: String,
+    /// Base58 ed25519 signature
+    pub signature: String,
+}
+
+impl SignedReceipt {
+    /// Checks the signature against the receipt fields, without trusting `message`
+    pub fn verify(&self) -> bool {
+        let Ok(signer) = Pubkey::from_str(&self.signer) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_str(&self.signature) else {
+            return false;
+        };
+        let message = self.receipt.message();
+        message == self.message && signature.verify(signer.as_ref(), message.as_bytes())
+    }
+}
+
+/// All receipts for a proposal, as served by `GET /upgrade/:id/receipts`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptBundle {
+    pub proposal_id: String,
+    pub algorithm: String,
+    pub signer: String,
+    pub receipts: Vec<SignedReceipt>,
+}
+
+impl ReceiptBundle {
+    pub fn verify(&self) -> bool {
+        self.receipts
+            .iter()
+            .all(|r| r.receipt.proposal_id == self.proposal_id && r.signer == self.signer && r.verify())
+    }
+}
+
+pub struct ReceiptService {
+    database: Arc<Database>,
+    signer: Keypair,
+    rpc_url: String,
+    upgrade_manager: Option<Pubkey>,
+}
+
+impl ReceiptService {
+    /// Signs with the `RECEIPT_SIGNER_PRIVATE_KEY` secret, else the keypair file at
+    /// `RECEIPT_SIGNER_KEYPAIR`, else an ephemeral key
+    pub fn new(database: Arc<Database>, secrets: &SecretStore) -> Result<Self, UpgradeError> {
+        let signer = match (secrets.get("RECEIPT_SIGNER_PRIVATE_KEY"), std::env::var("RECEIPT_SIGNER_KEYPAIR")) {
+            (Some(secret), _) => signer::keypair_from_secret(&secret)?,
+            (None, Ok(path)) => read_keypair_file(&path).map_err(|e| {
+                UpgradeError::InternalError(format!("Failed to read receipt signer keypair: {}", e))
+            })?,
+            (None, Err(_)) => {
+                tracing::warn!(
+                    "RECEIPT_SIGNER_KEYPAIR not set, signing receipts with an ephemeral key"
+                );
+                Keypair::new()
+            }
+        };
+
+        Ok(Self::with_signer(database, signer))
+    }
+
+    pub fn with_signer(database: Arc<Database>, signer: Keypair) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let upgrade_manager = std::env::var("UPGRADE_MANAGER_PROGRAM_ID")
+            .unwrap_or_else(|_| "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string())
+            .parse()
+            .ok();
+
+        Self {
+            database,
+            signer,
+            rpc_url,
+            upgrade_manager,
+        }
+    }
+
+    pub fn signer_pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    /// Builds, signs and stores a receipt for `member`'s approval of `proposal`
+    pub async fn issue(&self, proposal: &Proposal, member: &str) -> Result<SignedReceipt, UpgradeError> {
+        let receipt = ApprovalReceipt {
+            version: RECEIPT_VERSION.to_string(),
+            member: member.to_string(),
+            proposal_id: proposal.id.clone(),
+            proposal_pda: self.proposal_pda(proposal).map(|pda| pda.to_string()),
+            buffer: proposal.new_buffer.clone(),
+            buffer_hash: self.buffer_hash(proposal).await?,
+            approved_at: chrono::Utc::now().timestamp(),
+        };
+
+        let signed = self.sign(receipt);
+
+        self.database.insert_approval_receipt(&signed).await?;
+
+        tracing::info!("Issued approval receipt for {} by {}", proposal.id, member);
+
+        Ok(signed)
+    }
+
+    pub fn sign(&self, receipt: ApprovalReceipt) -> SignedReceipt {
+        let message = receipt.message();
+        let signature = self.signer.sign_message(message.as_bytes());
+
+        SignedReceipt {
+            receipt,
+            message,
+            signer: self.signer.pubkey().to_string(),
+            signature: signature.to_string(),
+        }
+    }
+
+    /// Signs other service attestations, such as incident reports, with the receipt key
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        self.signer.sign_message(message)
+    }
+
+    pub async fn bundle(&self, proposal_id: &str) -> Result<ReceiptBundle, UpgradeError> {
+        let receipts = self.database.list_approval_receipts(proposal_id).await?;
+
+        Ok(ReceiptBundle {
+            proposal_id: proposal_id.to_string(),
+            algorithm: RECEIPT_ALGORITHM.to_string(),
+            signer: self.signer.pubkey().to_string(),
+            receipts,
+        })
+    }
+
+    fn proposal_pda(&self, proposal: &Proposal) -> Option<Pubkey> {
+        let upgrade_manager = self.upgrade_manager?;
+        let program = Pubkey::from_str(&proposal.program).ok()?;
+        let buffer = Pubkey::from_str(&proposal.new_buffer).ok()?;
+        let (pda, _) = Pubkey::find_program_address(
+            &[b"proposal", program.as_ref(), buffer.as_ref()],
+            &upgrade_manager,
+        );
+        Some(pda)
+    }
+
+    /// Prefers the verified artifact hash; otherwise hashes the buffer's program data
+    async fn buffer_hash(&self, proposal: &Proposal) -> Result<String, UpgradeError> {
+        if let Some(source) = &proposal.source {
+            return Ok(source.artifact_hash.clone());
+        }
+
+        let buffer = Pubkey::from_str(&proposal.new_buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let rpc_url = self.rpc_url.clone();
+        let data = tokio::task::spawn_blocking(move || {
+            RpcClient::new(rpc_url).get_account_data(&buffer)
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+        .map_err(|e| UpgradeError::SolanaError(e.to_string()))?;
+
+        let offset = UpgradeableLoaderState::size_of_buffer_metadata();
+        let program_data = data.get(offset..).unwrap_or_default();
+        Ok(hex::encode(Sha256::digest(program_data)))
+    }
+}
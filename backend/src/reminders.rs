@@ -0,0 +1,387 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::members::{MemberDirectory, MemberProfile};
+use crate::multisig::MultisigCoordinator;
+use crate::proposal::{Proposal, ProposalManager, ProposalStatus};
+use crate::templates::Channel;
+use crate::websocket::{Notification, NotificationService, NotificationType};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// When approval reminders go out, relative to a proposal's approval deadline
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReminderSchedule {
+    /// Approvals are due this long after the proposal is created
+    pub deadline_seconds: i64,
+    /// Reminders this long before the deadline
+    pub offsets_seconds: Vec<i64>,
+    /// After the last offset, reminders repeat this often until the deadline
+    pub final_interval_seconds: i64,
+}
+
+impl Default for ReminderSchedule {
+    fn default() -> Self {
+        Self {
+            deadline_seconds: 72 * 3600,
+            offsets_seconds: vec![24 * 3600, 6 * 3600],
+            final_interval_seconds: 3600,
+        }
+    }
+}
+
+impl ReminderSchedule {
+    /// `APPROVAL_DEADLINE_HOURS` (default 72), `APPROVAL_REMINDER_HOURS`
+    /// before the deadline (comma separated, default `24,6`) and
+    /// `APPROVAL_REMINDER_FINAL_INTERVAL_MINUTES` (default 60)
+    pub fn from_env() -> Result<Self, UpgradeError> {
+        let defaults = Self::default();
+
+        let offsets_seconds = match std::env::var("APPROVAL_REMINDER_HOURS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|hours| !hours.is_empty())
+                .map(|hours| {
+                    hours.parse::<i64>().map(|h| h * 3600).map_err(|_| {
+                        UpgradeError::InvalidRequest(format!("Invalid APPROVAL_REMINDER_HOURS entry '{}'", hours))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(_) => defaults.offsets_seconds,
+        };
+
+        Ok(Self {
+            deadline_seconds: std::env::var("APPROVAL_DEADLINE_HOURS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(|hours| hours * 3600)
+                .unwrap_or(defaults.deadline_seconds),
+            offsets_seconds,
+            final_interval_seconds: std::env::var("APPROVAL_REMINDER_FINAL_INTERVAL_MINUTES")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(|minutes| minutes * 60)
+                .unwrap_or(defaults.final_interval_seconds),
+        })
+    }
+
+    pub fn deadline(&self, proposed_at: i64) -> i64 {
+        proposed_at + self.deadline_seconds
+    }
+
+    /// Every reminder time for a proposal created at `proposed_at`, in order:
+    /// one per offset, then every `final_interval_seconds` after the last
+    /// one, so reminders get more frequent as the deadline approaches
+    pub fn reminder_times(&self, proposed_at: i64) -> Vec<i64> {
+        let deadline = self.deadline(proposed_at);
+        let mut times: Vec<i64> = self
+            .offsets_seconds
+            .iter()
+            .map(|offset| deadline - offset)
+            .filter(|time| *time > proposed_at && *time < deadline)
+            .collect();
+        times.sort_unstable();
+        times.dedup();
+
+        if let (Some(&last), true) = (times.last(), self.final_interval_seconds > 0) {
+            let mut next = last + self.final_interval_seconds;
+            while next < deadline {
+                times.push(next);
+                next += self.final_interval_seconds;
+            }
+        }
+        times
+    }
+
+    /// The reminder time `now` falls after, while the deadline has not
+    /// passed. Reminders missed while the service was down are not sent
+    /// late; only the latest one is.
+    pub fn due_at(&self, proposed_at: i64, now: i64) -> Option<i64> {
+        if now >= self.deadline(proposed_at) {
+            return None;
+        }
+        self.reminder_times(proposed_at)
+            .into_iter()
+            .take_while(|time| *time <= now)
+            .last()
+    }
+}
+
+/// How a member wants to be reminded
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReminderContact {
+    /// An `approval_reminder` event on the notification stream
+    Websocket,
+    /// A Slack-compatible incoming webhook
+    Slack(String),
+    /// An address, sent through the `EMAIL_RELAY_URL` relay
+    Email(String),
+}
+
+impl ReminderContact {
+    /// Parse `websocket`, `slack:<webhook url>` or `email:<address>`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().split_once(':') {
+            None if value.trim() == "websocket" => Some(ReminderContact::Websocket),
+            Some(("slack", url)) if !url.trim().is_empty() => Some(ReminderContact::Slack(url.trim().to_string())),
+            Some(("email", address)) if address.contains('@') => Some(ReminderContact::Email(address.trim().to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn channel(&self) -> Channel {
+        match self {
+            ReminderContact::Websocket => Channel::Websocket,
+            ReminderContact::Slack(_) => Channel::Slack,
+            ReminderContact::Email(_) => Channel::Email,
+        }
+    }
+}
+
+/// Parse `MEMBER_NOTIFICATION_CHANNELS`: comma-separated `member=channel`
+/// pairs, where the channel is `websocket`, `slack:<webhook url>` or
+/// `email:<address>`
+pub fn parse_member_channels(value: &str) -> Result<HashMap<String, ReminderContact>, UpgradeError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let invalid = || {
+                UpgradeError::InvalidRequest(format!(
+                    "Invalid MEMBER_NOTIFICATION_CHANNELS entry '{}', expected member=websocket, \
+                     member=slack:<url> or member=email:<address>",
+                    pair
+                ))
+            };
+            let (member, channel) = pair.split_once('=').ok_or_else(invalid)?;
+            let contact = ReminderContact::parse(channel).ok_or_else(invalid)?;
+            if member.trim().is_empty() {
+                return Err(invalid());
+            }
+            Ok((member.trim().to_string(), contact))
+        })
+        .collect()
+}
+
+/// Where `member` is reminded: their profile's channel, then
+/// `MEMBER_NOTIFICATION_CHANNELS`, then the notification stream. `None` when
+/// the profile turns reminders off.
+pub fn reminder_contact(
+    member: &str,
+    profile: Option<&MemberProfile>,
+    configured: &HashMap<String, ReminderContact>,
+) -> Option<ReminderContact> {
+    if profile.is_some_and(|p| !p.reminders_enabled) {
+        return None;
+    }
+    let contact = profile
+        .and_then(MemberProfile::reminder_contact)
+        .or_else(|| configured.get(member).cloned())
+        .unwrap_or(ReminderContact::Websocket);
+    Some(contact)
+}
+
+/// Members still to approve `proposal` who have not opted out of its reminders
+pub fn pending_members(members: &[String], proposal: &Proposal, opted_out: &HashSet<String>) -> Vec<String> {
+    members
+        .iter()
+        .filter(|member| !proposal.approvals.contains(member) && !opted_out.contains(*member))
+        .cloned()
+        .collect()
+}
+
+/// Reminds members who have not approved an open proposal, on the
+/// `ReminderSchedule`, through the channel each member prefers. Each
+/// reminder is claimed in the database before it is sent, so replicas do
+/// not send it twice.
+pub struct ApprovalReminderService {
+    schedule: ReminderSchedule,
+    contacts: HashMap<String, ReminderContact>,
+    email_relay_url: Option<String>,
+    check_interval: Duration,
+    database: Arc<Database>,
+    proposals: Arc<ProposalManager>,
+    multisig: Arc<MultisigCoordinator>,
+    notifications: Arc<NotificationService>,
+    http: reqwest::Client,
+}
+
+impl ApprovalReminderService {
+    /// Contacts come from member profiles, then `MEMBER_NOTIFICATION_CHANNELS`
+    /// (members in neither get the notification stream) and are checked every
+    /// `APPROVAL_REMINDER_CHECK_INTERVAL_SECS` (default 60)
+    pub fn from_env(
+        database: Arc<Database>,
+        proposals: Arc<ProposalManager>,
+        multisig: Arc<MultisigCoordinator>,
+        notifications: Arc<NotificationService>,
+    ) -> Result<Self, UpgradeError> {
+        Ok(Self {
+            schedule: ReminderSchedule::from_env()?,
+            contacts: parse_member_channels(&std::env::var("MEMBER_NOTIFICATION_CHANNELS").unwrap_or_default())?,
+            email_relay_url: std::env::var("EMAIL_RELAY_URL").ok().filter(|url| !url.is_empty()),
+            check_interval: Duration::from_secs(
+                std::env::var("APPROVAL_REMINDER_CHECK_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60),
+            ),
+            database,
+            proposals,
+            multisig,
+            notifications,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn schedule(&self) -> &ReminderSchedule {
+        &self.schedule
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(self.check_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.tick(chrono::Utc::now().timestamp()).await {
+                tracing::warn!("Approval reminder check failed: {}", e);
+            }
+        }
+    }
+
+    /// Send the reminders due at `now`; returns how many went out
+    pub async fn tick(&self, now: i64) -> Result<usize, UpgradeError> {
+        let members = self.multisig.get_members().await;
+        let directory = MemberDirectory::new(self.database.list_member_profiles().await?);
+        let mut sent = 0;
+
+        for proposal in self.proposals.list_proposals().await? {
+            // Frozen, timelocked and finished proposals need no approvals
+            if !matches!(proposal.status, ProposalStatus::Proposed | ProposalStatus::Approved) {
+                continue;
+            }
+            let Some(due_at) = self.schedule.due_at(proposal.proposed_at, now) else {
+                continue;
+            };
+
+            let opted_out = self.database.list_reminder_opt_outs(&proposal.id).await?;
+            for member in pending_members(&members, &proposal, &opted_out) {
+                let Some(contact) = reminder_contact(&member, directory.get(&member), &self.contacts) else {
+                    continue;
+                };
+                if !self
+                    .database
+                    .claim_approval_reminder(&proposal.id, &member, due_at, contact.channel())
+                    .await?
+                {
+                    continue;
+                }
+                self.remind(&proposal, &member, &contact, now).await;
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+
+    pub async fn opt_out(&self, proposal_id: &str, member: &str) -> Result<(), UpgradeError> {
+        self.ensure_member(member).await?;
+        self.proposals.get_proposal(proposal_id).await?;
+        self.database.insert_reminder_opt_out(proposal_id, member).await
+    }
+
+    pub async fn opt_in(&self, proposal_id: &str, member: &str) -> Result<(), UpgradeError> {
+        self.ensure_member(member).await?;
+        self.database.delete_reminder_opt_out(proposal_id, member).await
+    }
+
+    async fn ensure_member(&self, member: &str) -> Result<(), UpgradeError> {
+        if !self.multisig.get_members().await.iter().any(|m| m == member) {
+            return Err(UpgradeError::NotMultisigMember);
+        }
+        Ok(())
+    }
+
+    /// Delivery failures are logged; the reminder is not retried
+    async fn remind(&self, proposal: &Proposal, member: &str, contact: &ReminderContact, now: i64) {
+        let deadline = self.schedule.deadline(proposal.proposed_at);
+        let data = serde_json::json!({
+            "member": member,
+            "program": proposal.program,
+            "approvals": proposal.approvals.len(),
+            "threshold": proposal.approval_threshold,
+            "deadline": deadline,
+            "hours_remaining": (deadline - now).max(0) / 3600,
+        });
+        let message = format!(
+            "Proposal {} needs your approval ({}/{}), deadline in {}h",
+            proposal.id,
+            proposal.approvals.len(),
+            proposal.approval_threshold,
+            (deadline - now).max(0) / 3600
+        );
+        let text = self
+            .notifications
+            .templates()
+            .render("approval_reminder", contact.channel(), None, Some(&proposal.id), &data)
+            .unwrap_or_else(|| message.clone());
+
+        let result = match contact {
+            ReminderContact::Websocket => {
+                self.notifications
+                    .notify(Notification {
+                        notification_type: NotificationType::ApprovalReminder,
+                        proposal_id: Some(proposal.id.clone()),
+                        message,
+                        data,
+                    })
+                    .await;
+                Ok(())
+            }
+            ReminderContact::Slack(url) => self.post(url, serde_json::json!({ "text": text })).await,
+            ReminderContact::Email(address) => match &self.email_relay_url {
+                Some(relay) => {
+                    let body = serde_json::json!({
+                        "to": address,
+                        "subject": format!("Approval needed: proposal {}", proposal.id),
+                        "text": text,
+                    });
+                    self.post(relay, body).await
+                }
+                None => Err("EMAIL_RELAY_URL is not set".to_string()),
+            },
+        };
+
+        match result {
+            Ok(()) => tracing::info!(
+                "Reminded {} to approve {} via {}",
+                member,
+                proposal.id,
+                contact.channel().as_str()
+            ),
+            Err(e) => tracing::warn!(
+                "Failed to remind {} about {} via {}: {}",
+                member,
+                proposal.id,
+                contact.channel().as_str(),
+                e
+            ),
+        }
+    }
+
+    async fn post(&self, url: &str, body: serde_json::Value) -> Result<(), String> {
+        self.http
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
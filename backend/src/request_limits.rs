@@ -0,0 +1,176 @@
+use crate::error::UpgradeError;
+use axum::body::Body;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How large a request body a route accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyClass {
+    /// Ordinary JSON requests
+    Json,
+    /// Requests carrying attachments, program binaries, transactions or
+    /// imported history
+    Attachments,
+}
+
+/// Which body limit applies to `path` (relative to `/v1`)
+pub fn body_class(path: &str) -> BodyClass {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["upgrade", "propose" | "propose-binary"]
+        | ["upgrade", "draft"]
+        | ["upgrade", "draft", _]
+        | ["upgrade", _, "sandbox", "run"]
+        | ["history", "import"]
+        | ["integrations", "github", "release"] => BodyClass::Attachments,
+        _ => BodyClass::Json,
+    }
+}
+
+/// Routes that legitimately run for minutes: simulations, replays, drills
+/// and bulk imports. Everything else, execution included, gets the short
+/// timeout so a hung RPC call cannot hold the connection.
+pub fn is_long_running(method: &Method, path: &str) -> bool {
+    if method != Method::POST {
+        return false;
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["upgrade", _, "sandbox", "run"]
+            | ["upgrade", _, "fork-test"]
+            | ["rollback", "drill"]
+            | ["history", "import"]
+            | ["snapshots"]
+    )
+}
+
+/// Body size limits, timeouts and the slow-request threshold for the API
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    pub json_body_bytes: usize,
+    pub attachment_body_bytes: usize,
+    pub timeout: Duration,
+    pub long_timeout: Duration,
+    /// Requests slower than this are logged
+    pub slow_request: Duration,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            json_body_bytes: 1024 * 1024,
+            attachment_body_bytes: 16 * 1024 * 1024,
+            timeout: Duration::from_secs(30),
+            long_timeout: Duration::from_secs(300),
+            slow_request: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RequestLimits {
+    /// `MAX_JSON_BODY_BYTES` (default 1 MiB), `MAX_ATTACHMENT_BODY_BYTES`
+    /// (default 16 MiB), `REQUEST_TIMEOUT_SECONDS` (default 30),
+    /// `LONG_REQUEST_TIMEOUT_SECONDS` (default 300) and `SLOW_REQUEST_MS`
+    /// (default 2000)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            json_body_bytes: env("MAX_JSON_BODY_BYTES")
+                .map(|v| v as usize)
+                .unwrap_or(defaults.json_body_bytes),
+            attachment_body_bytes: env("MAX_ATTACHMENT_BODY_BYTES")
+                .map(|v| v as usize)
+                .unwrap_or(defaults.attachment_body_bytes),
+            timeout: env("REQUEST_TIMEOUT_SECONDS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            long_timeout: env("LONG_REQUEST_TIMEOUT_SECONDS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.long_timeout),
+            slow_request: env("SLOW_REQUEST_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.slow_request),
+        }
+    }
+
+    pub fn body_limit(&self, path: &str) -> usize {
+        match body_class(path) {
+            BodyClass::Json => self.json_body_bytes,
+            BodyClass::Attachments => self.attachment_body_bytes,
+        }
+    }
+
+    pub fn timeout_for(&self, method: &Method, path: &str) -> Duration {
+        if is_long_running(method, path) {
+            self.long_timeout
+        } else {
+            self.timeout
+        }
+    }
+}
+
+/// Rejects oversized bodies with `413`, cuts requests off at the route's
+/// timeout with `504` and logs slow ones. Streaming responses (SSE,
+/// WebSockets) are only timed until their headers are sent.
+pub async fn enforce(State(limits): State<Arc<RequestLimits>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+
+    let limit = limits.body_limit(&path);
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return too_large(limit).into_response();
+    }
+
+    // Chunked bodies carry no length, so the limit is applied while reading
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, limit).await {
+        Ok(bytes) => bytes,
+        Err(_) => return too_large(limit).into_response(),
+    };
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let timeout = limits.timeout_for(&method, &path);
+    let started = Instant::now();
+    let response = match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::error!("{} {} timed out after {}s", method, route, timeout.as_secs());
+            return UpgradeError::RequestTimeout(format!("{} {} took longer than {}s", method, route, timeout.as_secs()))
+                .into_response();
+        }
+    };
+
+    let elapsed = started.elapsed();
+    if elapsed > limits.slow_request {
+        tracing::warn!(
+            "Slow request: {} {} took {}ms (status {})",
+            method,
+            route,
+            elapsed.as_millis(),
+            response.status().as_u16()
+        );
+    }
+
+    response
+}
+
+fn too_large(limit: usize) -> UpgradeError {
+    UpgradeError::PayloadTooLarge(format!("Request body exceeds {} bytes", limit))
+}
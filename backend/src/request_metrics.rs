@@ -0,0 +1,166 @@
+use crate::monitoring::MonitoringService;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Route label for requests that matched no route, so probes for random
+/// paths cannot blow up the label set
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RouteMetrics {
+    pub requests: u64,
+    /// Responses per status code
+    pub statuses: BTreeMap<u16, u64>,
+    /// Count per `LATENCY_BUCKETS` bound (not cumulative), plus one overflow bucket
+    pub latency_buckets: Vec<u64>,
+    pub latency_sum_seconds: f64,
+}
+
+impl RouteMetrics {
+    pub fn errors(&self) -> u64 {
+        self.statuses.range(500..).map(|(_, n)| n).sum()
+    }
+}
+
+/// Request counts, status codes and latency histograms keyed by method and route template
+#[derive(Default)]
+pub struct RequestMetrics {
+    routes: Mutex<BTreeMap<(String, String), RouteMetrics>>,
+}
+
+impl RequestMetrics {
+    pub fn record(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        let mut routes = self.routes.lock().unwrap();
+        let metrics = routes
+            .entry((method.to_string(), route.to_string()))
+            .or_insert_with(|| RouteMetrics {
+                latency_buckets: vec![0; LATENCY_BUCKETS.len() + 1],
+                ..Default::default()
+            });
+        metrics.requests += 1;
+        *metrics.statuses.entry(status).or_default() += 1;
+        metrics.latency_buckets[bucket] += 1;
+        metrics.latency_sum_seconds += seconds;
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<(String, String), RouteMetrics> {
+        self.routes.lock().unwrap().clone()
+    }
+
+    /// Per-route view for the JSON monitoring API, slowest average first
+    pub fn summary(&self) -> Vec<serde_json::Value> {
+        let mut routes: Vec<_> = self.snapshot().into_iter().collect();
+        routes.sort_by(|(_, a), (_, b)| {
+            let avg = |m: &RouteMetrics| m.latency_sum_seconds / m.requests.max(1) as f64;
+            avg(b).total_cmp(&avg(a))
+        });
+
+        routes
+            .into_iter()
+            .map(|((method, route), metrics)| {
+                serde_json::json!({
+                    "method": method,
+                    "route": route,
+                    "requests": metrics.requests,
+                    "errors": metrics.errors(),
+                    "average_latency_ms": metrics.latency_sum_seconds * 1000.0 / metrics.requests.max(1) as f64,
+                    "p95_latency_ms": percentile_ms(&metrics, 0.95),
+                    "statuses": metrics.statuses,
+                })
+            })
+            .collect()
+    }
+
+    /// Prometheus text exposition of `http_requests_total` and
+    /// `http_request_duration_seconds`
+    pub fn render_prometheus(&self) -> String {
+        let routes = self.snapshot();
+        let mut out = String::new();
+
+        out.push_str("# HELP http_requests_total HTTP requests by route and status code.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        for ((method, route), metrics) in &routes {
+            for (status, count) in &metrics.statuses {
+                let _ = writeln!(
+                    out,
+                    "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    method,
+                    escape_label(route),
+                    status,
+                    count
+                );
+            }
+        }
+
+        out.push_str("# HELP http_request_duration_seconds HTTP request latency by route.\n");
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for ((method, route), metrics) in &routes {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape_label(route));
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&metrics.latency_buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, metrics.requests);
+            let _ = writeln!(out, "http_request_duration_seconds_sum{{{}}} {}", labels, metrics.latency_sum_seconds);
+            let _ = writeln!(out, "http_request_duration_seconds_count{{{}}} {}", labels, metrics.requests);
+        }
+
+        out
+    }
+}
+
+/// Upper bound of the bucket holding the `quantile` sample, in milliseconds.
+/// `None` when the quantile falls in the overflow bucket.
+fn percentile_ms(metrics: &RouteMetrics, quantile: f64) -> Option<f64> {
+    let rank = (metrics.requests as f64 * quantile).ceil() as u64;
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS.iter().zip(&metrics.latency_buckets) {
+        cumulative += count;
+        if cumulative >= rank {
+            return Some(bound * 1000.0);
+        }
+    }
+    None
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Records every request against its route template (e.g. `/v1/upgrade/:id/status`)
+pub async fn track_requests(
+    State(monitoring): State<Arc<MonitoringService>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    monitoring
+        .request_metrics()
+        .record(&method, &route, response.status().as_u16(), started.elapsed());
+
+    response
+}
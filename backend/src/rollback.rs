@@ -1,18 +1,223 @@
+use crate::database::Database;
 use crate::error::UpgradeError;
+use crate::incidents::{
+    Detection, IncidentChange, IncidentLink, IncidentLinkKind, IncidentManager, IncidentSeverity, IncidentSource,
+    IncidentStatus,
+};
+use crate::websocket::{Notification, NotificationService, NotificationType};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Steps of the rollback procedure, as listed in the operations runbook
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RollbackStep {
+    Pause,
+    ClosePositions,
+    ReturnFunds,
+    DeployPrevious,
+    Resume,
+}
+
+impl RollbackStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollbackStep::Pause => "pause",
+            RollbackStep::ClosePositions => "close_positions",
+            RollbackStep::ReturnFunds => "return_funds",
+            RollbackStep::DeployPrevious => "deploy_previous",
+            RollbackStep::Resume => "resume",
+        }
+    }
+}
+
+/// The runbook's rollback steps, in the order they must run
+pub const RUNBOOK_STEPS: [RollbackStep; 5] = [
+    RollbackStep::Pause,
+    RollbackStep::ClosePositions,
+    RollbackStep::ReturnFunds,
+    RollbackStep::DeployPrevious,
+    RollbackStep::Resume,
+];
+
+/// Differences between the steps a rollback ran and the runbook
+pub fn runbook_deviations(executed: &[RollbackStep]) -> Vec<String> {
+    let mut deviations: Vec<String> = RUNBOOK_STEPS
+        .iter()
+        .filter(|step| !executed.contains(step))
+        .map(|step| format!("{} did not run", step.as_str()))
+        .collect();
+
+    let ran_in_runbook_order = executed
+        .iter()
+        .filter_map(|step| RUNBOOK_STEPS.iter().position(|s| s == step))
+        .collect::<Vec<_>>()
+        .windows(2)
+        .all(|pair| pair[0] < pair[1]);
+    if !ran_in_runbook_order {
+        deviations.push(format!(
+            "steps ran as {}",
+            executed.iter().map(RollbackStep::as_str).collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    deviations
+}
+
+/// A live rollback of an upgrade, from `rollback_events`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RollbackEvent {
+    pub id: String,
+    /// The proposal whose upgrade was rolled back
+    pub proposal_id: String,
+    pub old_program_id: String,
+    pub reason: String,
+    pub rolled_back_at: i64,
+}
+
+/// The version a rollback would return the program to
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RollbackTarget {
+    /// Upgrade being undone: its proposal id, or its signature when imported
+    pub upgrade: String,
+    /// Code hash to redeploy, when recorded
+    pub program_hash: Option<String>,
+    /// Earlier upgrade that deployed that version
+    pub deployed_by: Option<String>,
+}
+
+/// Pick the rollback target from `upgrade_history` rows (newest first): the
+/// program's latest successful upgrade is undone, restoring the code it replaced
+pub fn rollback_target(history: &[Value], program: &str) -> Option<RollbackTarget> {
+    let id = |entry: &Value| {
+        entry["proposal_id"]
+            .as_str()
+            .or_else(|| entry["signature"].as_str())
+            .map(str::to_string)
+    };
+    let mut upgrades = history
+        .iter()
+        .filter(|entry| entry["program"].as_str() == Some(program) && entry["success"].as_bool() == Some(true));
+
+    let latest = upgrades.next()?;
+    let previous = upgrades.next();
+    let program_hash = latest["old_program_hash"]
+        .as_str()
+        .or_else(|| previous.and_then(|entry| entry["new_program_hash"].as_str()))
+        .map(str::to_string);
+
+    Some(RollbackTarget {
+        upgrade: id(latest)?,
+        program_hash,
+        deployed_by: previous.and_then(id),
+    })
+}
+
+/// Request body for `POST /rollback/drill`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DrillRequest {
+    /// Program to drill on (default `MANAGED_PROGRAM_ID`)
+    pub program: Option<String>,
+    /// Read from the devnet clone at `ROLLBACK_DRILL_RPC_URL` instead of
+    /// mocking every step
+    #[serde(default)]
+    pub devnet_clone: bool,
+    /// Recovery time objective to hold the drill to (default `ROLLBACK_RTO_SECS`)
+    pub rto_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DrillStepResult {
+    pub step: RollbackStep,
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub detail: String,
+}
+
+/// Whether an alert the rollback sends reached subscribers
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AlertCheck {
+    pub event_type: String,
+    pub fired: bool,
+    /// Stored for replay, so clients that were offline still see it
+    pub persisted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DrillReport {
+    pub id: String,
+    pub program: String,
+    /// `mocked` or `devnet_clone`
+    pub target: String,
+    pub started_at: i64,
+    pub duration_ms: u64,
+    pub rto_secs: u64,
+    pub within_rto: bool,
+    pub steps: Vec<DrillStepResult>,
+    pub runbook_deviations: Vec<String>,
+    pub alerts: Vec<AlertCheck>,
+    pub passed: bool,
+}
 
 pub struct RollbackHandler {
-    // In real implementation, store previous program versions
+    managed_program: String,
+    rto_secs: u64,
+    drill_rpc_url: Option<String>,
+    database: Option<Arc<Database>>,
+    notifications: Option<Arc<NotificationService>>,
+    incidents: Option<Arc<IncidentManager>>,
 }
 
 impl RollbackHandler {
     pub async fn new() -> Result<Self, UpgradeError> {
-        Ok(Self {})
+        Ok(Self {
+            managed_program: std::env::var("MANAGED_PROGRAM_ID").unwrap_or_default(),
+            rto_secs: std::env::var("ROLLBACK_RTO_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(900),
+            drill_rpc_url: std::env::var("ROLLBACK_DRILL_RPC_URL").ok().filter(|v| !v.is_empty()),
+            database: None,
+            notifications: None,
+            incidents: None,
+        })
+    }
+
+    /// Resolve rollback targets from the upgrade history and keep drill reports
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Send `rollback_initiated` when a rollback starts
+    pub fn with_notifications(mut self, notifications: Arc<NotificationService>) -> Self {
+        self.notifications = Some(notifications);
+        self
+    }
+
+    /// Open an incident, or update the program's open one, when a live
+    /// rollback starts, and post how it ended
+    pub fn with_incidents(mut self, incidents: Arc<IncidentManager>) -> Self {
+        self.incidents = Some(incidents);
+        self
     }
 
     pub async fn rollback_program(
         &self,
         old_program_id: &str,
     ) -> Result<(), UpgradeError> {
+        let incident = self.open_incident(old_program_id, None, "Rollback initiated").await;
+        let result = self.run_runbook(old_program_id).await;
+        self.close_out_incident(incident.as_deref(), None, &result).await;
+        result
+    }
+
+    async fn run_runbook(&self, old_program_id: &str) -> Result<(), UpgradeError> {
         // In real implementation, this would:
         // 1. Pause new operations
         // 2. Close all positions at current mark price
@@ -21,25 +226,290 @@ impl RollbackHandler {
         // 5. Resume operations
 
         tracing::warn!("Rolling back to program: {}", old_program_id);
+        self.announce(old_program_id, "live", None).await;
 
-        // Step 1: Pause system
-        self.pause_system().await?;
+        for step in RUNBOOK_STEPS {
+            match step {
+                RollbackStep::Pause => self.pause_system().await?,
+                RollbackStep::ClosePositions => self.emergency_close_all_positions().await?,
+                RollbackStep::ReturnFunds => self.return_all_funds().await?,
+                RollbackStep::DeployPrevious => self.deploy_old_program(old_program_id).await?,
+                RollbackStep::Resume => self.resume_system().await?,
+            }
+        }
 
-        // Step 2: Emergency close positions
-        self.emergency_close_all_positions().await?;
+        tracing::info!("Rollback completed successfully");
 
-        // Step 3: Return funds
-        self.return_all_funds().await?;
+        Ok(())
+    }
 
-        // Step 4: Deploy old program
-        self.deploy_old_program(old_program_id).await?;
+    /// Roll back the upgrade made by `proposal_id` because of `reason`, and
+    /// record it in `rollback_events` so it can be reviewed as an incident
+    /// and linked to the one tracking it
+    pub async fn rollback_upgrade(
+        &self,
+        proposal_id: &str,
+        old_program_id: &str,
+        reason: &str,
+    ) -> Result<(), UpgradeError> {
+        let incident = self.open_incident(old_program_id, Some(proposal_id), reason).await;
+        let result = self.run_runbook(old_program_id).await;
 
-        // Step 5: Resume operations
-        self.resume_system().await?;
+        let mut event = None;
+        if let (Ok(()), Some(database)) = (&result, &self.database) {
+            match database
+                .record_rollback_event(proposal_id, old_program_id, reason, 0, true)
+                .await
+            {
+                Ok(id) => event = Some(id),
+                Err(e) => tracing::error!("Failed to record rollback of {}: {}", proposal_id, e),
+            }
+        }
 
-        tracing::info!("Rollback completed successfully");
+        self.close_out_incident(incident.as_deref(), event.as_deref(), &result).await;
+        result
+    }
 
-        Ok(())
+    /// The id of the incident tracking this rollback, if incidents are on
+    async fn open_incident(&self, program: &str, proposal_id: Option<&str>, reason: &str) -> Option<String> {
+        let incidents = self.incidents.as_ref()?;
+        let detection = Detection {
+            source: IncidentSource::Rollback,
+            severity: IncidentSeverity::Critical,
+            program: program.to_string(),
+            proposal_id: proposal_id.map(str::to_string),
+            title: format!("Rollback of {}", program),
+            detail: reason.to_string(),
+            alert: None,
+        };
+        match incidents.detect(detection).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::error!("Failed to open an incident for the rollback of {}: {}", program, e);
+                None
+            }
+        }
+    }
+
+    /// Link the recorded rollback and post whether it worked
+    async fn close_out_incident(
+        &self,
+        incident: Option<&str>,
+        event: Option<&str>,
+        result: &Result<(), UpgradeError>,
+    ) {
+        let (Some(incidents), Some(incident)) = (&self.incidents, incident) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(event) = event {
+            if let Err(e) = incidents
+                .link(incident, IncidentLink::new(IncidentLinkKind::Rollback, event, None, now))
+                .await
+            {
+                tracing::warn!("Failed to link rollback {} to incident {}: {}", event, incident, e);
+            }
+        }
+
+        let change = match result {
+            Ok(()) => IncidentChange {
+                status: Some(IncidentStatus::Monitoring),
+                severity: None,
+                message: "The previous program version has been restored and is being monitored.".to_string(),
+                author: None,
+            },
+            Err(e) => IncidentChange {
+                status: Some(IncidentStatus::Identified),
+                severity: None,
+                message: format!("Automatic rollback failed: {}. Operators are rolling back by hand.", e),
+                author: None,
+            },
+        };
+        if let Err(e) = incidents.update(incident, change).await {
+            tracing::warn!("Failed to update incident {}: {}", incident, e);
+        }
+    }
+
+    /// Game-day rehearsal of `rollback_program`: every runbook step runs with
+    /// its writes mocked, reading from a devnet clone when one is given, and
+    /// is timed against the RTO. The drill also checks that the rollback alert
+    /// reaches subscribers. Nothing is signed or sent.
+    pub async fn drill(&self, request: DrillRequest) -> Result<DrillReport, UpgradeError> {
+        let program = request.program.unwrap_or_else(|| self.managed_program.clone());
+        if program.is_empty() {
+            return Err(UpgradeError::InvalidRequest(
+                "program is required when MANAGED_PROGRAM_ID is not set".to_string(),
+            ));
+        }
+        let program_id = Pubkey::from_str(&program).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let rpc = match (request.devnet_clone, &self.drill_rpc_url) {
+            (false, _) => None,
+            (true, Some(url)) => Some(AsyncRpcClient::new(url.clone())),
+            (true, None) => {
+                return Err(UpgradeError::InvalidRequest(
+                    "ROLLBACK_DRILL_RPC_URL is required for drills against a devnet clone".to_string(),
+                ))
+            }
+        };
+        let rto_secs = request.rto_secs.unwrap_or(self.rto_secs);
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let started_at = chrono::Utc::now().timestamp();
+        let started = Instant::now();
+        tracing::info!("Rollback drill {} for {}", id, program);
+
+        let alerts = vec![self.announce(&program, "drill", Some(&id)).await];
+
+        let mut steps = Vec::new();
+        for step in RUNBOOK_STEPS {
+            let step_started = Instant::now();
+            let (passed, detail) = match self.rehearse(step, &program_id, rpc.as_ref()).await {
+                Ok(detail) => (true, detail),
+                Err(e) => (false, e.to_string()),
+            };
+            steps.push(DrillStepResult {
+                step,
+                passed,
+                duration_ms: step_started.elapsed().as_millis() as u64,
+                detail,
+            });
+        }
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let within_rto = duration_ms <= rto_secs * 1000;
+        let runbook_deviations = runbook_deviations(&steps.iter().map(|s| s.step).collect::<Vec<_>>());
+        let passed = within_rto
+            && runbook_deviations.is_empty()
+            && steps.iter().all(|s| s.passed)
+            && alerts.iter().all(|a| a.fired);
+
+        let report = DrillReport {
+            id,
+            program,
+            target: if rpc.is_some() { "devnet_clone" } else { "mocked" }.to_string(),
+            started_at,
+            duration_ms,
+            rto_secs,
+            within_rto,
+            steps,
+            runbook_deviations,
+            alerts,
+            passed,
+        };
+
+        if let Some(database) = &self.database {
+            database.insert_rollback_drill(&report).await?;
+        }
+        tracing::info!(
+            "Rollback drill {} {} in {}ms (RTO {}s)",
+            report.id,
+            if report.passed { "passed" } else { "failed" },
+            report.duration_ms,
+            rto_secs
+        );
+
+        Ok(report)
+    }
+
+    pub async fn list_drills(&self, limit: i64) -> Result<Vec<DrillReport>, UpgradeError> {
+        match &self.database {
+            Some(database) => database.list_rollback_drills(limit).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The read-only half of a step: what it needs is in place, its writes are skipped
+    async fn rehearse(
+        &self,
+        step: RollbackStep,
+        program: &Pubkey,
+        rpc: Option<&AsyncRpcClient>,
+    ) -> Result<String, UpgradeError> {
+        match step {
+            RollbackStep::Pause => Ok(format!("pause of {} mocked", program)),
+            RollbackStep::ClosePositions => Ok("position closing at mark price mocked".to_string()),
+            RollbackStep::ReturnFunds => Ok("fund returns mocked".to_string()),
+            RollbackStep::DeployPrevious => {
+                let mut detail = match &self.database {
+                    Some(database) => {
+                        let history = database.list_upgrade_history(500).await?;
+                        let target = rollback_target(&history, &program.to_string()).ok_or_else(|| {
+                            UpgradeError::InvalidRequest(format!(
+                                "no successful upgrade of {} recorded to roll back",
+                                program
+                            ))
+                        })?;
+                        if target.program_hash.is_none() && target.deployed_by.is_none() {
+                            return Err(UpgradeError::InvalidRequest(format!(
+                                "the version before upgrade {} is not recorded",
+                                target.upgrade
+                            )));
+                        }
+                        format!(
+                            "would undo {} by redeploying {}",
+                            target.upgrade,
+                            target
+                                .program_hash
+                                .or(target.deployed_by)
+                                .unwrap_or_default()
+                        )
+                    }
+                    None => "rollback target not checked without a database".to_string(),
+                };
+                if let Some(rpc) = rpc {
+                    let account = rpc
+                        .get_account(program)
+                        .await
+                        .map_err(|e| UpgradeError::SolanaError(format!("{} not found on the clone: {}", program, e)))?;
+                    if !account.executable {
+                        return Err(UpgradeError::SolanaError(format!("{} is not executable on the clone", program)));
+                    }
+                    detail.push_str("; program present on the clone");
+                }
+                Ok(detail)
+            }
+            RollbackStep::Resume => Ok(format!("resume of {} mocked", program)),
+        }
+    }
+
+    /// Send `rollback_initiated` and report whether subscribers received it
+    async fn announce(&self, program: &str, mode: &str, drill_id: Option<&str>) -> AlertCheck {
+        let event_type = "rollback_initiated".to_string();
+        let Some(notifications) = &self.notifications else {
+            return AlertCheck {
+                event_type,
+                fired: false,
+                persisted: false,
+            };
+        };
+
+        let mut receiver = notifications.subscribe();
+        notifications
+            .notify(Notification {
+                notification_type: NotificationType::RollbackInitiated,
+                proposal_id: None,
+                message: format!("Rollback of {} initiated ({})", program, mode),
+                data: json!({
+                    "program": program,
+                    "mode": mode,
+                    "drill_id": drill_id,
+                }),
+            })
+            .await;
+
+        let mut check = AlertCheck {
+            event_type,
+            fired: false,
+            persisted: false,
+        };
+        while let Ok(event) = receiver.try_recv() {
+            if event.event_type == check.event_type && event.data["drill_id"].as_str() == drill_id {
+                check.fired = true;
+                check.persisted = event.seq.is_some();
+            }
+        }
+        check
     }
 
     async fn pause_system(&self) -> Result<(), UpgradeError> {
@@ -83,4 +553,3 @@ impl RollbackHandler {
         Ok(format!("Analysis for proposal: {}", proposal_id))
     }
 }
-
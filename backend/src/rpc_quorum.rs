@@ -0,0 +1,252 @@
+use crate::error::UpgradeError;
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_SOLANA_RPC};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::sync::Arc;
+
+/// How the configured providers answered one read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agreement<T> {
+    /// The answer at least `required` providers gave
+    pub value: T,
+    pub agreeing: usize,
+    /// Providers that answered with something else
+    pub diverging: Vec<usize>,
+    /// Providers that returned an error
+    pub failed: Vec<usize>,
+}
+
+/// The answer `required` of the providers agree on, where `same` decides
+/// whether two answers agree. When several answers reach the quorum the one
+/// with the most support wins, then the earliest provider's.
+pub fn agree<T: Clone>(
+    answers: &[Result<T, String>],
+    required: usize,
+    same: impl Fn(&T, &T) -> bool,
+) -> Result<Agreement<T>, UpgradeError> {
+    let failed: Vec<usize> = answers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, answer)| answer.is_err().then_some(i))
+        .collect();
+    let values: Vec<(usize, &T)> = answers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, answer)| answer.as_ref().ok().map(|value| (i, value)))
+        .collect();
+
+    let best = values
+        .iter()
+        .map(|(_, candidate)| {
+            let supporters: Vec<usize> = values
+                .iter()
+                .filter(|(_, other)| same(candidate, other))
+                .map(|(i, _)| *i)
+                .collect();
+            (*candidate, supporters)
+        })
+        .fold(None::<(&T, Vec<usize>)>, |best, (candidate, supporters)| match best {
+            Some((_, ref current)) if current.len() >= supporters.len() => best,
+            _ => Some((candidate, supporters)),
+        });
+
+    let Some((value, supporters)) = best.filter(|(_, supporters)| supporters.len() >= required.max(1)) else {
+        return Err(UpgradeError::RpcQuorumNotReached(format!(
+            "{} of {} providers must agree; {} answered, {} failed",
+            required,
+            answers.len(),
+            values.len(),
+            failed.len(),
+        )));
+    };
+
+    Ok(Agreement {
+        value: value.clone(),
+        agreeing: supporters.len(),
+        diverging: values
+            .iter()
+            .map(|(i, _)| *i)
+            .filter(|i| !supporters.contains(i))
+            .collect(),
+        failed,
+    })
+}
+
+struct Provider {
+    /// Host only, since provider URLs often embed an API key
+    label: String,
+    client: AsyncRpcClient,
+}
+
+/// Reads that gate an upgrade (buffer contents, program data and the chain
+/// clock timelocks are checked against) are asked of several RPC providers,
+/// and only an answer enough of them agree on is used. One malicious or
+/// lagging endpoint can then neither fake a buffer nor shift a timelock, and
+/// any disagreement raises a critical alert.
+pub struct QuorumRpc {
+    providers: Vec<Provider>,
+    required: usize,
+    clock_tolerance_seconds: i64,
+    monitoring: Option<Arc<MonitoringService>>,
+}
+
+impl QuorumRpc {
+    pub fn new(urls: Vec<String>, required: usize) -> Result<Self, UpgradeError> {
+        if required == 0 || required > urls.len() {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "RPC quorum of {} needs between 1 and {} providers",
+                required,
+                urls.len()
+            )));
+        }
+
+        let providers = urls
+            .into_iter()
+            .enumerate()
+            .map(|(i, url)| Provider {
+                label: reqwest::Url::parse(&url)
+                    .ok()
+                    .and_then(|parsed| parsed.host_str().map(str::to_string))
+                    .unwrap_or_else(|| format!("provider {}", i + 1)),
+                client: AsyncRpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            })
+            .collect();
+
+        Ok(Self {
+            providers,
+            required,
+            clock_tolerance_seconds: 5,
+            monitoring: None,
+        })
+    }
+
+    /// Providers from `RPC_QUORUM_URLS` (comma separated), `RPC_QUORUM_REQUIRED`
+    /// of which must agree (default 2). Chain times within
+    /// `RPC_QUORUM_CLOCK_TOLERANCE_SECONDS` (default 5) count as agreeing.
+    /// `None` when no providers are configured, leaving critical reads on
+    /// `SOLANA_RPC_URL` alone.
+    pub fn from_env() -> Result<Option<Self>, UpgradeError> {
+        let urls: Vec<String> = std::env::var("RPC_QUORUM_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        if urls.is_empty() {
+            return Ok(None);
+        }
+
+        let required = std::env::var("RPC_QUORUM_REQUIRED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let mut quorum = Self::new(urls, required)?;
+        quorum.clock_tolerance_seconds = std::env::var("RPC_QUORUM_CLOCK_TOLERANCE_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(quorum.clock_tolerance_seconds);
+        Ok(Some(quorum))
+    }
+
+    /// Raise a critical alert whenever providers disagree
+    pub fn with_alerts(mut self, monitoring: Arc<MonitoringService>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    pub fn required(&self) -> usize {
+        self.required
+    }
+
+    pub fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// An account, or `None` if it does not exist, as the quorum sees it.
+    /// Owner, lamports and data must all match.
+    pub async fn account(&self, address: &Pubkey) -> Result<Option<Account>, UpgradeError> {
+        let answers = futures_util::future::join_all(self.providers.iter().map(|provider| async move {
+            provider
+                .client
+                .get_account_with_commitment(address, provider.client.commitment())
+                .await
+                .map(|response| response.value)
+                .map_err(|e| e.to_string())
+        }))
+        .await;
+
+        self.settle(&format!("account {}", address), answers, |a, b| a == b).await
+    }
+
+    /// `(slot, unix_timestamp)` from the `Clock` sysvar. Providers are a few
+    /// slots apart at best, so only the timestamps are compared.
+    pub async fn clock(&self) -> Result<(u64, i64), UpgradeError> {
+        let answers = futures_util::future::join_all(self.providers.iter().map(|provider| async move {
+            let data = provider
+                .client
+                .get_account_data(&sysvar::clock::id())
+                .await
+                .map_err(|e| e.to_string())?;
+            crate::chain_clock::decode_clock_sysvar(&data).map_err(|e| e.to_string())
+        }))
+        .await;
+
+        let tolerance = self.clock_tolerance_seconds;
+        self.settle("Clock sysvar", answers, |a: &(u64, i64), b| (a.1 - b.1).abs() <= tolerance)
+            .await
+    }
+
+    async fn settle<T: Clone>(
+        &self,
+        what: &str,
+        answers: Vec<Result<T, String>>,
+        same: impl Fn(&T, &T) -> bool,
+    ) -> Result<T, UpgradeError> {
+        for (provider, answer) in self.providers.iter().zip(&answers) {
+            if let Err(e) = answer {
+                tracing::warn!("RPC provider {} failed to read {}: {}", provider.label, what, e);
+            }
+        }
+
+        let agreement = match agree(&answers, self.required, same) {
+            Ok(agreement) => agreement,
+            Err(e) => {
+                self.alert(format!("No RPC quorum reading {}: {}", what, e)).await;
+                return Err(e);
+            }
+        };
+
+        if !agreement.diverging.is_empty() {
+            let diverging: Vec<&str> = agreement
+                .diverging
+                .iter()
+                .map(|&i| self.providers[i].label.as_str())
+                .collect();
+            self.alert(format!(
+                "RPC providers {} disagree with the quorum ({} of {}) reading {}",
+                diverging.join(", "),
+                agreement.agreeing,
+                self.providers.len(),
+                what,
+            ))
+            .await;
+        }
+
+        Ok(agreement.value)
+    }
+
+    async fn alert(&self, message: String) {
+        match &self.monitoring {
+            Some(monitoring) => {
+                monitoring
+                    .send_alert(AlertLevel::Critical, message, COMPONENT_SOLANA_RPC.to_string())
+                    .await
+            }
+            None => tracing::error!("{}", message),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::migration::SharedMigrator;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// z-score of the 95% interval the confidence score is reported at
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// How much of a finished batch migration is read back from the chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingConfig {
+    /// `MIGRATION_SAMPLE_PERCENT` of migrated accounts to verify (default 5, 0 disables)
+    pub sample_percent: f64,
+    /// `MIGRATION_SAMPLE_MAX_ERROR_PERCENT` of sampled accounts allowed to fail (default 0)
+    pub max_error_percent: f64,
+}
+
+impl SamplingConfig {
+    pub fn from_env() -> Self {
+        let percent = |key: &str, default: f64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(|v: f64| v.clamp(0.0, 100.0))
+                .unwrap_or(default)
+        };
+
+        Self {
+            sample_percent: percent("MIGRATION_SAMPLE_PERCENT", 5.0),
+            max_error_percent: percent("MIGRATION_SAMPLE_MAX_ERROR_PERCENT", 0.0),
+        }
+    }
+}
+
+/// Accounts to sample out of `population`: at least one when sampling is on
+/// and anything was migrated
+pub fn sample_size(population: usize, percent: f64) -> usize {
+    if population == 0 || percent <= 0.0 {
+        return 0;
+    }
+    ((population as f64 * percent / 100.0).ceil() as usize).clamp(1, population)
+}
+
+/// Upper bound of the 95% Wilson score interval for an error rate of
+/// `failures` out of `sampled`
+pub fn wilson_upper_bound(failures: usize, sampled: usize) -> f64 {
+    if sampled == 0 {
+        return 1.0;
+    }
+    let n = sampled as f64;
+    let p = failures as f64 / n;
+    let z2 = CONFIDENCE_Z * CONFIDENCE_Z;
+    let center = p + z2 / (2.0 * n);
+    let margin = CONFIDENCE_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center + margin) / (1.0 + z2 / n)).min(1.0)
+}
+
+/// Pre-migration data of one migrated account
+#[derive(Debug, Clone)]
+pub struct AccountBackup {
+    pub account: String,
+    pub account_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Outcome of re-verifying one sampled account
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SampledAccount {
+    pub account: String,
+    pub account_type: String,
+    pub verified: bool,
+    /// Why verification failed
+    pub reason: Option<String>,
+}
+
+/// Result of verifying a random sample of a migration's accounts
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SampleVerification {
+    pub sample_percent: f64,
+    /// Migrated accounts the sample was drawn from
+    pub population: usize,
+    pub sampled: usize,
+    pub verified: usize,
+    pub failed: usize,
+    pub error_rate_percent: f64,
+    /// With 95% confidence, at least this percent of all migrated accounts
+    /// would verify (one minus the Wilson upper bound of the error rate)
+    pub confidence_percent: f64,
+    pub max_error_percent: f64,
+    pub passed: bool,
+    pub failures: Vec<SampledAccount>,
+}
+
+impl SampleVerification {
+    pub fn summarize(config: &SamplingConfig, population: usize, results: Vec<SampledAccount>) -> Self {
+        let sampled = results.len();
+        let failures: Vec<SampledAccount> = results.into_iter().filter(|r| !r.verified).collect();
+        let failed = failures.len();
+        let error_rate_percent = if sampled > 0 {
+            failed as f64 / sampled as f64 * 100.0
+        } else {
+            0.0
+        };
+        let confidence_percent = if sampled > 0 {
+            (1.0 - wilson_upper_bound(failed, sampled)) * 100.0
+        } else {
+            0.0
+        };
+
+        Self {
+            sample_percent: config.sample_percent,
+            population,
+            sampled,
+            verified: sampled - failed,
+            failed,
+            error_rate_percent,
+            confidence_percent,
+            max_error_percent: config.max_error_percent,
+            passed: error_rate_percent <= config.max_error_percent,
+            failures,
+        }
+    }
+}
+
+/// Reads a random sample of a batch migration's accounts back from the chain
+/// and runs each type's `verify()` against the account's backup
+#[derive(Clone)]
+pub struct SampleVerifier {
+    config: SamplingConfig,
+    database: Arc<Database>,
+    rpc_client: Arc<RpcClient>,
+}
+
+impl SampleVerifier {
+    pub fn new(config: SamplingConfig, database: Arc<Database>, rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            config,
+            database,
+            rpc_client,
+        }
+    }
+
+    /// `None` when sampling is disabled
+    pub async fn verify(
+        &self,
+        migration_id: &str,
+        migrators: &HashMap<String, SharedMigrator>,
+    ) -> Result<Option<SampleVerification>, UpgradeError> {
+        if self.config.sample_percent <= 0.0 {
+            return Ok(None);
+        }
+
+        let population = self.database.count_account_backups(migration_id).await? as usize;
+        let size = sample_size(population, self.config.sample_percent);
+        let backups = self.database.sample_account_backups(migration_id, size as i64).await?;
+
+        let client = self.rpc_client.clone();
+        let migrators = migrators.clone();
+        let results = tokio::task::spawn_blocking(move || {
+            backups
+                .iter()
+                .map(|backup| verify_account(&client, &migrators, backup))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?;
+
+        Ok(Some(SampleVerification::summarize(&self.config, population, results)))
+    }
+}
+
+fn verify_account(
+    client: &RpcClient,
+    migrators: &HashMap<String, SharedMigrator>,
+    backup: &AccountBackup,
+) -> SampledAccount {
+    let outcome = check_account(client, migrators, backup);
+
+    SampledAccount {
+        account: backup.account.clone(),
+        account_type: backup.account_type.clone(),
+        verified: outcome.is_ok(),
+        reason: outcome.err(),
+    }
+}
+
+fn check_account(
+    client: &RpcClient,
+    migrators: &HashMap<String, SharedMigrator>,
+    backup: &AccountBackup,
+) -> Result<(), String> {
+    let migrator = migrators
+        .get(&backup.account_type)
+        .ok_or_else(|| format!("no migrator for {}", backup.account_type))?;
+    let pubkey = Pubkey::from_str(&backup.account).map_err(|e| e.to_string())?;
+    let current = client
+        .get_account_data(&pubkey)
+        .map_err(|e| format!("could not read account: {}", e))?;
+
+    match migrator.verify(&backup.data, &current) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("migrated data does not match its backup".to_string()),
+        Err(e) => Err(format!("verification error: {:?}", e)),
+    }
+}
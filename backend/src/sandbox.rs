@@ -0,0 +1,434 @@
+use crate::error::UpgradeError;
+use crate::proposal::Proposal;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Most transactions one sandbox run executes
+pub const MAX_SANDBOX_TRANSACTIONS: usize = 16;
+
+/// Most accounts one run copies from the cluster
+pub const MAX_CLONED_ACCOUNTS: usize = 32;
+
+/// Placeholder for the sandbox's fee payer, the only signer it can provide
+pub const PAYER_PLACEHOLDER: &str = "payer";
+
+const PAYER_LAMPORTS: u64 = 1_000_000_000_000;
+
+/// Request body for `POST /upgrade/:id/sandbox/run`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxRunRequest {
+    pub transactions: Vec<SandboxTransaction>,
+    /// Accounts copied from the cluster into the sandbox before the run
+    #[serde(default)]
+    pub clone_accounts: Vec<String>,
+    /// Accounts created in the sandbox with the given state
+    #[serde(default)]
+    pub accounts: Vec<SandboxAccount>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxTransaction {
+    pub instructions: Vec<SandboxInstruction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxInstruction {
+    pub program_id: String,
+    pub accounts: Vec<SandboxAccountMeta>,
+    /// Base64 instruction data
+    #[serde(default)]
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxAccountMeta {
+    /// An address, or `payer` for the sandbox's fee payer
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxAccount {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    /// Base64 account data
+    #[serde(default)]
+    pub data: String,
+}
+
+/// Account state before or after a run; `data` is base64
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub owner: String,
+    pub data_len: usize,
+    pub data: String,
+}
+
+impl AccountState {
+    fn from_account(account: &Account) -> Self {
+        Self {
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            data_len: account.data.len(),
+            data: base64::engine::general_purpose::STANDARD.encode(&account.data),
+        }
+    }
+}
+
+/// How one account changed over the run. `before`/`after` are `None` when
+/// the account did not exist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountDiff {
+    pub pubkey: String,
+    pub before: Option<AccountState>,
+    pub after: Option<AccountState>,
+    /// Byte ranges `[start, end)` whose data differs, over the shorter length
+    pub changed_ranges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionOutcome {
+    pub index: usize,
+    pub success: bool,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SandboxReport {
+    pub proposal_id: String,
+    pub program: String,
+    pub buffer: String,
+    pub transactions: Vec<TransactionOutcome>,
+    /// Accounts the run changed, the fee payer excluded
+    pub account_diffs: Vec<AccountDiff>,
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, UpgradeError> {
+    Pubkey::from_str(value).map_err(|_| UpgradeError::InvalidRequest(format!("Invalid address: {}", value)))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, UpgradeError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| UpgradeError::InvalidRequest(format!("Invalid base64 data: {}", e)))
+}
+
+impl SandboxTransaction {
+    /// Resolve the instructions, with `payer` standing in for the placeholder.
+    /// Only the payer may sign, since the sandbox holds no other keys.
+    pub fn instructions(&self, payer: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        if self.instructions.is_empty() {
+            return Err(UpgradeError::InvalidRequest("transaction has no instructions".to_string()));
+        }
+
+        self.instructions
+            .iter()
+            .map(|instruction| {
+                let accounts = instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| {
+                        let pubkey = if meta.pubkey == PAYER_PLACEHOLDER {
+                            *payer
+                        } else {
+                            parse_pubkey(&meta.pubkey)?
+                        };
+                        if meta.is_signer && pubkey != *payer {
+                            return Err(UpgradeError::InvalidRequest(format!(
+                                "{} cannot sign in the sandbox; only `{}` can",
+                                meta.pubkey, PAYER_PLACEHOLDER
+                            )));
+                        }
+                        Ok(AccountMeta {
+                            pubkey,
+                            is_signer: meta.is_signer,
+                            is_writable: meta.is_writable,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+                Ok(Instruction {
+                    program_id: parse_pubkey(&instruction.program_id)?,
+                    accounts,
+                    data: decode_base64(&instruction.data)?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl SandboxRunRequest {
+    pub fn validate(&self) -> Result<(), UpgradeError> {
+        if self.transactions.is_empty() {
+            return Err(UpgradeError::InvalidRequest("at least one transaction is required".to_string()));
+        }
+        if self.transactions.len() > MAX_SANDBOX_TRANSACTIONS {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "at most {} transactions per run",
+                MAX_SANDBOX_TRANSACTIONS
+            )));
+        }
+        if self.clone_accounts.len() > MAX_CLONED_ACCOUNTS {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "at most {} cloned accounts per run",
+                MAX_CLONED_ACCOUNTS
+            )));
+        }
+        Ok(())
+    }
+
+    fn seeded_accounts(&self) -> Result<Vec<(Pubkey, Account)>, UpgradeError> {
+        self.accounts
+            .iter()
+            .map(|account| {
+                Ok((
+                    parse_pubkey(&account.pubkey)?,
+                    Account {
+                        lamports: account.lamports,
+                        data: decode_base64(&account.data)?,
+                        owner: parse_pubkey(&account.owner)?,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Compare account states taken before and after a run, keeping the accounts that changed
+pub fn diff_accounts(
+    before: &BTreeMap<Pubkey, Option<Account>>,
+    after: &BTreeMap<Pubkey, Option<Account>>,
+) -> Vec<AccountDiff> {
+    after
+        .iter()
+        .filter_map(|(pubkey, after)| {
+            let before = before.get(pubkey).cloned().flatten();
+            if before.as_ref() == after.as_ref() {
+                return None;
+            }
+
+            let changed_ranges = match (&before, after) {
+                (Some(before), Some(after)) => changed_ranges(&before.data, &after.data),
+                _ => Vec::new(),
+            };
+
+            Some(AccountDiff {
+                pubkey: pubkey.to_string(),
+                before: before.as_ref().map(AccountState::from_account),
+                after: after.as_ref().map(AccountState::from_account),
+                changed_ranges,
+            })
+        })
+        .collect()
+}
+
+fn changed_ranges(before: &[u8], after: &[u8]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, (a, b)) in before.iter().zip(after).enumerate() {
+        if a == b {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some((_, end)) if *end == index => *end = index + 1,
+            _ => ranges.push((index, index + 1)),
+        }
+    }
+    ranges
+}
+
+/// Runs reviewer-supplied transactions against a proposed binary in a local
+/// bank (solana-program-test), so an upgrade can be exercised without devnet
+/// access. The ELF is loaded at the program's address under the plain BPF
+/// loader, so a program that reads its own programdata account will not find
+/// one. The cluster is only read, to clone accounts.
+pub struct Sandbox {
+    rpc_url: String,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sandbox {
+    pub fn new() -> Self {
+        Self {
+            rpc_url: std::env::var("SOLANA_RPC_URL")
+                .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string()),
+        }
+    }
+
+    /// Execute `request` against `elf`, the binary `proposal` would deploy
+    pub async fn run(
+        &self,
+        proposal: &Proposal,
+        elf: Vec<u8>,
+        request: SandboxRunRequest,
+    ) -> Result<SandboxReport, UpgradeError> {
+        request.validate()?;
+        if elf.is_empty() {
+            return Err(UpgradeError::InvalidRequest(format!("Proposal {} has no program binary", proposal.id)));
+        }
+        let program = proposal.program.parse::<Pubkey>().map_err(|_| UpgradeError::InvalidPubkey)?;
+        let buffer = proposal.new_buffer.parse::<Pubkey>().map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        let payer = Keypair::new();
+        let transactions = request
+            .transactions
+            .iter()
+            .map(|transaction| transaction.instructions(&payer.pubkey()))
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+        let mut accounts = request.seeded_accounts()?;
+        let clone = request
+            .clone_accounts
+            .iter()
+            .map(|pubkey| parse_pubkey(pubkey))
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+        let rpc_url = self.rpc_url.clone();
+        let cloned = tokio::task::spawn_blocking(move || {
+            let rpc_client = RpcClient::new(rpc_url);
+            clone
+                .into_iter()
+                .map(|pubkey| {
+                    rpc_client
+                        .get_account(&pubkey)
+                        .map(|account| (pubkey, account))
+                        .map_err(|e| UpgradeError::SolanaError(format!("Failed to clone {}: {}", pubkey, e)))
+                })
+                .collect::<Result<Vec<_>, UpgradeError>>()
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))??;
+        accounts.extend(cloned);
+
+        // Every account the run touches is diffed, except the payer, whose
+        // balance only moves by fees
+        let mut watched: Vec<Pubkey> = accounts.iter().map(|(pubkey, _)| *pubkey).collect();
+        watched.extend(
+            transactions
+                .iter()
+                .flatten()
+                .flat_map(|instruction| instruction.accounts.iter().map(|meta| meta.pubkey)),
+        );
+        watched.retain(|pubkey| *pubkey != payer.pubkey() && *pubkey != program);
+        watched.sort();
+        watched.dedup();
+
+        // The banks server is not Send, so the run gets its own runtime
+        let (outcomes, account_diffs) = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+                .block_on(execute(program, elf, accounts, payer, transactions, watched))
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))??;
+
+        tracing::info!(
+            "Sandbox run for {}: {} of {} transactions succeeded",
+            proposal.id,
+            outcomes.iter().filter(|o| o.success).count(),
+            outcomes.len()
+        );
+
+        Ok(SandboxReport {
+            proposal_id: proposal.id.clone(),
+            program: program.to_string(),
+            buffer: buffer.to_string(),
+            transactions: outcomes,
+            account_diffs,
+        })
+    }
+}
+
+async fn execute(
+    program: Pubkey,
+    elf: Vec<u8>,
+    accounts: Vec<(Pubkey, Account)>,
+    payer: Keypair,
+    transactions: Vec<Vec<Instruction>>,
+    watched: Vec<Pubkey>,
+) -> Result<(Vec<TransactionOutcome>, Vec<AccountDiff>), UpgradeError> {
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        program,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    for (pubkey, account) in accounts {
+        program_test.add_account(pubkey, account);
+    }
+    program_test.add_account(payer.pubkey(), Account::new(PAYER_LAMPORTS, 0, &system_program::id()));
+
+    let (mut banks_client, _, recent_blockhash) = program_test.start().await;
+    let before = read_accounts(&mut banks_client, &watched).await?;
+
+    let mut outcomes = Vec::with_capacity(transactions.len());
+    for (index, instructions) in transactions.into_iter().enumerate() {
+        let transaction =
+            Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&payer], recent_blockhash);
+        let result = banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Sandbox transaction {} failed to run: {}", index, e)))?;
+
+        let (logs, compute_units_consumed) = match result.metadata {
+            Some(metadata) => (metadata.log_messages, Some(metadata.compute_units_consumed)),
+            None => (Vec::new(), None),
+        };
+        outcomes.push(TransactionOutcome {
+            index,
+            success: result.result.is_ok(),
+            error: result.result.err().map(|e| e.to_string()),
+            logs,
+            compute_units_consumed,
+        });
+    }
+
+    let after = read_accounts(&mut banks_client, &watched).await?;
+    Ok((outcomes, diff_accounts(&before, &after)))
+}
+
+async fn read_accounts(
+    banks_client: &mut BanksClient,
+    pubkeys: &[Pubkey],
+) -> Result<BTreeMap<Pubkey, Option<Account>>, UpgradeError> {
+    let mut accounts = BTreeMap::new();
+    for pubkey in pubkeys {
+        let account = banks_client
+            .get_account(*pubkey)
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Sandbox could not read {}: {}", pubkey, e)))?;
+        accounts.insert(*pubkey, account);
+    }
+    Ok(accounts)
+}
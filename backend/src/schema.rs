@@ -0,0 +1,50 @@
+//! JSON Schema of the types the REST API and WebSocket feed send, built with
+//! the `schema` feature. `src/bin/schema.rs` writes it out and
+//! `scripts/generate-types.sh` turns it into TypeScript and Python models, so
+//! clients are generated from the same structs the backend serializes.
+
+use crate::migration::MigrationProgress;
+use crate::proposal::{Proposal, PublicProposal};
+use crate::websocket::{Event, NotificationType};
+use schemars::gen::SchemaSettings;
+use schemars::schema::{Metadata, RootSchema, Schema, SchemaObject};
+
+/// Types every client gets; whatever they reference is included
+pub const API_TYPES: [&str; 4] = ["Proposal", "PublicProposal", "Event", "MigrationProgress"];
+
+/// Definitions for `API_TYPES` and everything they reference, without a root
+/// type of their own
+pub fn api_schema() -> RootSchema {
+    let mut generator = SchemaSettings::draft07().into_generator();
+    generator.subschema_for::<Proposal>();
+    generator.subschema_for::<PublicProposal>();
+    generator.subschema_for::<Event>();
+    generator.subschema_for::<MigrationProgress>();
+
+    let meta_schema = generator.settings().meta_schema.clone();
+    let mut definitions = generator.take_definitions();
+
+    // `Event::event_type` is a plain string in Rust; clients get the closed set
+    if let Some(Schema::Object(event)) = definitions.get_mut("Event") {
+        if let Some(Schema::Object(event_type)) = event.object().properties.get_mut("type") {
+            event_type.enum_values = Some(
+                NotificationType::ALL
+                    .iter()
+                    .map(|notification| notification.as_str().into())
+                    .collect(),
+            );
+        }
+    }
+
+    RootSchema {
+        meta_schema,
+        schema: SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                title: Some("GoQuant upgrade service API".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        definitions,
+    }
+}
@@ -0,0 +1,473 @@
+use crate::error::UpgradeError;
+use crate::signer;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// Secrets the service reads through the store
+pub const MANAGED_SECRETS: &[&str] = &[
+    "DATABASE_URL",
+    "SOLANA_RPC_URL",
+    "RPC_QUORUM_URLS",
+    "GITHUB_WEBHOOK_SECRET",
+    "GITHUB_TOKEN",
+    "EXECUTOR_TOKENS",
+    "CHECKLIST_ROLE_TOKENS",
+    "SECURITY_ADMIN_TOKENS",
+    "FEE_PAYER_PRIVATE_KEY",
+    "FEE_PAYER_REMOTE_TOKEN",
+    "RECEIPT_SIGNER_PRIVATE_KEY",
+    "POLICY_BOT_PRIVATE_KEY",
+    "API_BOOTSTRAP_KEY",
+    "APPROVAL_LINK_SECRET",
+];
+
+/// Secrets consumed once at startup; a rotated value only applies after a restart
+const RESTART_REQUIRED: &[&str] = &[
+    "DATABASE_URL",
+    "SOLANA_RPC_URL",
+    "RPC_QUORUM_URLS",
+    "FEE_PAYER_PRIVATE_KEY",
+    "FEE_PAYER_REMOTE_TOKEN",
+    "RECEIPT_SIGNER_PRIVATE_KEY",
+    "POLICY_BOT_PRIVATE_KEY",
+];
+
+/// Where secret values come from
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Current values for whichever of `keys` the provider holds
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError>;
+}
+
+/// Process environment
+pub struct EnvSecrets;
+
+#[async_trait]
+impl SecretsProvider for EnvSecrets {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError> {
+        Ok(keys
+            .iter()
+            .filter_map(|key| std::env::var(key).ok().map(|value| (key.to_string(), value)))
+            .collect())
+    }
+}
+
+/// One file per secret, named after the key (Docker/Kubernetes secret mounts)
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FileSecrets {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError> {
+        let mut values = HashMap::new();
+        for key in keys {
+            match tokio::fs::read_to_string(self.dir.join(key)).await {
+                Ok(value) => {
+                    values.insert(key.to_string(), value.trim_end_matches(['\r', '\n']).to_string());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(UpgradeError::InternalError(format!(
+                        "Failed to read secret {}: {}",
+                        key, e
+                    )))
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// HashiCorp Vault KV v2 secret whose fields are named after the keys
+pub struct VaultSecrets {
+    http: reqwest::Client,
+    addr: String,
+    token: String,
+    /// e.g. `secret/data/goquant-upgrade-service`
+    path: String,
+}
+
+impl VaultSecrets {
+    pub fn new(addr: impl Into<String>, token: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            addr: addr.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            path: path.into().trim_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for VaultSecrets {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError> {
+        let body: serde_json::Value = self.http
+            .get(format!("{}/v1/{}", self.addr, self.path))
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| UpgradeError::InternalError(format!("Vault request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Invalid Vault response: {}", e)))?;
+
+        Ok(pick_keys(&body["data"]["data"], keys))
+    }
+}
+
+/// AWS Secrets Manager secret holding a JSON object whose fields are named after the keys
+pub struct AwsSecretsManager {
+    http: reqwest::Client,
+    region: String,
+    secret_id: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsSecretsManager {
+    /// Credentials come from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// optionally `AWS_SESSION_TOKEN`
+    pub fn from_env(region: String, secret_id: String) -> Result<Self, UpgradeError> {
+        let credential = |key: &str| {
+            std::env::var(key).map_err(|_| {
+                UpgradeError::InvalidRequest(format!("{} is required for the aws secrets provider", key))
+            })
+        };
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            region,
+            secret_id,
+            access_key_id: credential("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: credential("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+
+    /// SigV4 `Authorization` header for a `GetSecretValue` call
+    fn authorization(&self, host: &str, amz_date: &str, payload: &str) -> String {
+        const TARGET: &str = "secretsmanager.GetSecretValue";
+        let date = &amz_date[..8];
+
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1"),
+            ("host", host),
+            ("x-amz-date", amz_date),
+            ("x-amz-target", TARGET),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token));
+        }
+        headers.sort_by_key(|(name, _)| *name);
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(payload.as_bytes()))
+        );
+
+        let scope = format!("{}/{}/secretsmanager/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "secretsmanager", "aws4_request"]
+            .iter()
+            .fold(
+                hmac_sha256(format!("AWS4{}", self.secret_access_key).as_bytes(), date),
+                |key, part| hmac_sha256(&key, part),
+            );
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[async_trait]
+impl SecretsProvider for AwsSecretsManager {
+    fn name(&self) -> &'static str {
+        "aws"
+    }
+
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError> {
+        let host = format!("secretsmanager.{}.amazonaws.com", self.region);
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload = serde_json::json!({ "SecretId": self.secret_id }).to_string();
+
+        let mut request = self.http
+            .post(format!("https://{}/", host))
+            .header("content-type", "application/x-amz-json-1.1")
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-target", "secretsmanager.GetSecretValue")
+            .header("authorization", self.authorization(&host, &amz_date, &payload));
+        if let Some(token) = &self.session_token {
+            request = request.header("x-amz-security-token", token);
+        }
+
+        let body: serde_json::Value = request
+            .body(payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| UpgradeError::InternalError(format!("Secrets Manager request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Invalid Secrets Manager response: {}", e)))?;
+
+        let secret: serde_json::Value = body["SecretString"]
+            .as_str()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .ok_or_else(|| {
+                UpgradeError::InternalError("Secrets Manager secret is not a JSON object".to_string())
+            })?;
+
+        Ok(pick_keys(&secret, keys))
+    }
+}
+
+fn pick_keys(object: &serde_json::Value, keys: &[&str]) -> HashMap<String, String> {
+    keys.iter()
+        .filter_map(|key| object[*key].as_str().map(|value| (key.to_string(), value.to_string())))
+        .collect()
+}
+
+/// Cached view of the managed secrets, validated at startup and refreshed
+/// periodically so rotated values are picked up.
+///
+/// Lookups fall back to the process environment for keys the provider does
+/// not hold.
+pub struct SecretStore {
+    provider: Box<dyn SecretsProvider>,
+    values: RwLock<HashMap<String, String>>,
+    required: Vec<String>,
+}
+
+impl SecretStore {
+    pub fn new(provider: Box<dyn SecretsProvider>) -> Self {
+        Self {
+            provider,
+            values: RwLock::new(HashMap::new()),
+            required: Vec::new(),
+        }
+    }
+
+    /// Secrets straight from the environment, without a separate provider
+    pub fn env() -> Self {
+        Self::new(Box::new(EnvSecrets))
+    }
+
+    /// Selects the provider with `SECRETS_PROVIDER` (env, file, vault or aws; default env).
+    /// `SECRETS_REQUIRED` lists keys that must be present at startup.
+    pub fn from_env() -> Result<Self, UpgradeError> {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let required = |key: &str, provider: &str| {
+            var(key).ok_or_else(|| {
+                UpgradeError::InvalidRequest(format!("{} is required for the {} secrets provider", key, provider))
+            })
+        };
+
+        let provider: Box<dyn SecretsProvider> = match var("SECRETS_PROVIDER").as_deref().unwrap_or("env") {
+            "env" => Box::new(EnvSecrets),
+            "file" => Box::new(FileSecrets::new(
+                var("SECRETS_DIR").unwrap_or_else(|| "/run/secrets".to_string()),
+            )),
+            "vault" => Box::new(VaultSecrets::new(
+                required("VAULT_ADDR", "vault")?,
+                required("VAULT_TOKEN", "vault")?,
+                var("VAULT_SECRET_PATH")
+                    .unwrap_or_else(|| "secret/data/goquant-upgrade-service".to_string()),
+            )),
+            "aws" => Box::new(AwsSecretsManager::from_env(
+                required("AWS_REGION", "aws")?,
+                required("AWS_SECRET_ID", "aws")?,
+            )?),
+            other => {
+                return Err(UpgradeError::InvalidRequest(format!(
+                    "Unknown SECRETS_PROVIDER '{}', expected env, file, vault or aws",
+                    other
+                )))
+            }
+        };
+
+        let mut store = Self::new(provider);
+        store.required = var("SECRETS_REQUIRED")
+            .map(|v| v.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect())
+            .unwrap_or_default();
+        Ok(store)
+    }
+
+    pub fn with_required(mut self, keys: &[&str]) -> Self {
+        self.required = keys.iter().map(|k| k.to_string()).collect();
+        self
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    /// Startup load: fetches every managed secret and fails if a required one
+    /// is missing or any value is malformed. Errors name keys, never values.
+    pub async fn load(&self) -> Result<(), UpgradeError> {
+        let fetched = self.provider.fetch(MANAGED_SECRETS).await?;
+
+        let mut problems: Vec<String> = self.required
+            .iter()
+            .filter(|key| !fetched.contains_key(*key) && std::env::var(key).is_err())
+            .map(|key| format!("{} is missing", key))
+            .collect();
+        problems.extend(
+            fetched
+                .iter()
+                .filter_map(|(key, value)| validate(key, value).err().map(|e| format!("{}: {}", key, e))),
+        );
+        if !problems.is_empty() {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Invalid secrets from {} provider: {}",
+                self.provider.name(),
+                problems.join(", ")
+            )));
+        }
+
+        tracing::info!("Loaded {} secrets from {} provider", fetched.len(), self.provider.name());
+        *self.values.write().unwrap() = fetched;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.values
+            .read()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .or_else(|| std::env::var(key).ok())
+    }
+
+    pub fn require(&self, key: &str) -> Result<String, UpgradeError> {
+        self.get(key)
+            .ok_or_else(|| UpgradeError::InternalError(format!("Secret {} not configured", key)))
+    }
+
+    /// Re-fetch the managed secrets and return the keys whose value changed.
+    /// A malformed or vanished value keeps the previous one.
+    pub async fn refresh(&self) -> Result<Vec<String>, UpgradeError> {
+        let fetched = self.provider.fetch(MANAGED_SECRETS).await?;
+        let mut values = self.values.write().unwrap();
+
+        let mut rotated = Vec::new();
+        for (key, value) in fetched {
+            if values.get(&key) == Some(&value) {
+                continue;
+            }
+            if let Err(e) = validate(&key, &value) {
+                tracing::error!("Ignoring rotated secret {}: {}", key, e);
+                continue;
+            }
+            values.insert(key.clone(), value);
+            rotated.push(key);
+        }
+
+        rotated.sort();
+        Ok(rotated)
+    }
+
+    /// Refresh every `SECRETS_REFRESH_INTERVAL_SECS` (default 300)
+    pub fn spawn_rotation(self: Arc<Self>) {
+        let interval_secs = std::env::var("SECRETS_REFRESH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match self.refresh().await {
+                    Ok(rotated) => {
+                        for key in rotated {
+                            if RESTART_REQUIRED.contains(&key.as_str()) {
+                                tracing::warn!("Secret {} rotated; restart the service to apply it", key);
+                            } else {
+                                tracing::info!("Secret {} rotated", key);
+                            }
+                        }
+                    }
+                    Err(e) => tracing::warn!("Secret refresh failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Format checks for secrets whose shape is known
+fn validate(key: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err("empty value".to_string());
+    }
+
+    match key {
+        "DATABASE_URL" if !(value.starts_with("postgres://") || value.starts_with("postgresql://")) => {
+            Err("expected a postgres:// URL".to_string())
+        }
+        "SOLANA_RPC_URL" if !(value.starts_with("http://") || value.starts_with("https://")) => {
+            Err("expected an http(s) URL".to_string())
+        }
+        "RPC_QUORUM_URLS"
+            if !value
+                .split(',')
+                .map(str::trim)
+                .all(|url| url.starts_with("http://") || url.starts_with("https://")) =>
+        {
+            Err("expected comma separated http(s) URLs".to_string())
+        }
+        "FEE_PAYER_PRIVATE_KEY" | "RECEIPT_SIGNER_PRIVATE_KEY" => signer::keypair_from_secret(value)
+            .map(|_| ())
+            .map_err(|_| "not a valid secret key".to_string()),
+        _ => Ok(()),
+    }
+}
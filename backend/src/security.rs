@@ -1,23 +1,64 @@
+use crate::binary_analysis::{BinaryImpact, BinaryProfile};
+use crate::database::Database;
+use crate::denylist::{self, DenylistEntry};
 use crate::error::UpgradeError;
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_SECURITY};
+use crate::proposal::{FreezeSource, ProposalManager, MIN_TIMELOCK_DURATION};
+use crate::rpc_quorum::QuorumRpc;
+use crate::secrets::SecretStore;
 use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
 use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
 
 /// Security audit checks for upgrade proposals
-pub struct SecurityAuditor;
+#[derive(Default)]
+pub struct SecurityAuditor {
+    database: Option<Arc<Database>>,
+    monitoring: Option<Arc<MonitoringService>>,
+    rpc_quorum: Option<Arc<QuorumRpc>>,
+    proposals: Option<Arc<ProposalManager>>,
+}
 
 impl SecurityAuditor {
+    /// Check binaries against the `binary_denylist` table
+    pub fn with_denylist(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Raise a critical alert whenever a binary matches the denylist
+    pub fn with_alerts(mut self, monitoring: Arc<MonitoringService>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Freeze a proposal whose binary turns out to be denylisted
+    pub fn with_freeze(mut self, proposals: Arc<ProposalManager>) -> Self {
+        self.proposals = Some(proposals);
+        self
+    }
+
+    /// Read buffers and program data from redundant providers
+    pub fn with_rpc_quorum(mut self, rpc_quorum: Arc<QuorumRpc>) -> Self {
+        self.rpc_quorum = Some(rpc_quorum);
+        self
+    }
+
     /// Audit an upgrade proposal before execution
     pub async fn audit_proposal(
         &self,
-        program_hash: &[u8; 32],
+        program_binary: &[u8],
         buffer_pubkey: &Pubkey,
         description: &str,
     ) -> Result<AuditResult, UpgradeError> {
         let mut issues = Vec::new();
         let mut warnings = Vec::new();
+        let program_hash = Self::calculate_program_hash(denylist::elf_file(program_binary));
 
         // Check 1: Program hash verification
-        if !self.verify_program_hash(program_hash).await? {
+        if !self.verify_program_hash(&program_hash).await? {
             issues.push("Program hash verification failed".to_string());
         }
 
@@ -32,8 +73,12 @@ impl SecurityAuditor {
         }
 
         // Check 4: Check for known vulnerabilities
-        if self.check_known_vulnerabilities(program_hash).await? {
-            issues.push("Program matches known vulnerable pattern".to_string());
+        let matches = self.check_known_vulnerabilities(program_binary).await?;
+        if !matches.is_empty() {
+            let summary = denylist::describe(&matches);
+            self.alert_denylist_match(&format!("Program {}", hex::encode(program_hash)), &summary)
+                .await;
+            issues.push(format!("Program matches known vulnerable pattern: {}", summary));
         }
 
         // Check 5: Code review requirement
@@ -76,11 +121,56 @@ impl SecurityAuditor {
         Ok(true) // Placeholder
     }
 
-    async fn check_known_vulnerabilities(&self, _hash: &[u8; 32]) -> Result<bool, UpgradeError> {
-        // In production, check against database of known vulnerable hashes
-        // or patterns
-        
-        Ok(false) // Placeholder
+    /// Denylist entries matching the binary; none when no denylist is configured
+    pub async fn check_known_vulnerabilities(&self, program_binary: &[u8]) -> Result<Vec<DenylistEntry>, UpgradeError> {
+        let Some(database) = &self.database else {
+            return Ok(Vec::new());
+        };
+
+        let entries = database.list_denylist().await?;
+        Ok(denylist::find_matches(&entries, program_binary)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Refuse a proposal whose buffer matches the denylist, and freeze it
+    /// until an admin clears the finding. Checked again at execution time,
+    /// since entries can be added after a proposal is approved.
+    pub async fn ensure_not_denylisted(&self, proposal_id: &str, buffer: Pubkey) -> Result<(), UpgradeError> {
+        if self.database.is_none() {
+            return Ok(());
+        }
+
+        let (binary, _) = self.fetch_upgrade_binaries(buffer, None).await?;
+        let matches = self.check_known_vulnerabilities(&binary).await?;
+        if matches.is_empty() {
+            return Ok(());
+        }
+
+        let summary = denylist::describe(&matches);
+        self.alert_denylist_match(&format!("Proposal {}", proposal_id), &summary)
+            .await;
+        if let Some(proposals) = &self.proposals {
+            let finding = format!("Binary matches the denylist: {}", summary);
+            match proposals.freeze(proposal_id, finding, FreezeSource::Auditor, "security_auditor").await {
+                Ok(_) | Err(UpgradeError::ProposalFrozen) => {}
+                Err(e) => tracing::warn!("Failed to freeze proposal {}: {}", proposal_id, e),
+            }
+        }
+        Err(UpgradeError::KnownVulnerability(summary))
+    }
+
+    async fn alert_denylist_match(&self, subject: &str, summary: &str) {
+        let message = format!("{} matches the binary denylist: {}", subject, summary);
+        match &self.monitoring {
+            Some(monitoring) => {
+                monitoring
+                    .send_alert(AlertLevel::Critical, message, COMPONENT_SECURITY.to_string())
+                    .await
+            }
+            None => tracing::error!("{}", message),
+        }
     }
 
     /// Verify multisig configuration is secure
@@ -100,9 +190,10 @@ impl SecurityAuditor {
             ));
         }
 
-        if members.len() > 20 {
+        // The program has room for 10 members
+        if members.len() > 10 {
             return Err(UpgradeError::InternalError(
-                "Multisig should not exceed 20 members".to_string(),
+                "Multisig should not exceed 10 members".to_string(),
             ));
         }
 
@@ -141,17 +232,101 @@ impl SecurityAuditor {
 
     /// Verify timelock duration is adequate
     pub fn verify_timelock(&self, timelock_seconds: i64) -> Result<bool, UpgradeError> {
-        const MIN_TIMELOCK: i64 = 48 * 60 * 60; // 48 hours minimum
-
-        if timelock_seconds < MIN_TIMELOCK {
+        if timelock_seconds < MIN_TIMELOCK_DURATION {
             return Err(UpgradeError::InternalError(
-                format!("Timelock must be at least {} seconds (48 hours)", MIN_TIMELOCK),
+                format!("Timelock must be at least {} seconds (48 hours)", MIN_TIMELOCK_DURATION),
             ));
         }
 
         Ok(true)
     }
 
+    /// Static comparison of a proposed binary against the deployed one:
+    /// new syscalls, newly referenced programs (possible CPI targets) and
+    /// stack use. Without a deployed binary everything counts as new.
+    pub fn analyze_binary_impact(
+        &self,
+        proposed: &[u8],
+        deployed: Option<&[u8]>,
+    ) -> Result<BinaryImpact, UpgradeError> {
+        let proposed = BinaryProfile::analyze(proposed)?;
+        let deployed = match deployed {
+            Some(elf) => match BinaryProfile::analyze(elf) {
+                Ok(profile) => Some(profile),
+                Err(e) => {
+                    tracing::warn!("Deployed binary could not be analyzed: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Ok(BinaryImpact::compare(deployed.as_ref(), proposed))
+    }
+
+    /// Program bytes in `buffer` and, when `program` is deployed, its current
+    /// program data. Both keep the loader's trailing zero padding.
+    pub async fn fetch_upgrade_binaries(
+        &self,
+        buffer: Pubkey,
+        program: Option<Pubkey>,
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>), UpgradeError> {
+        if let Some(quorum) = &self.rpc_quorum {
+            let buffer_data = quorum
+                .account(&buffer)
+                .await?
+                .ok_or_else(|| UpgradeError::BufferNotFound(buffer.to_string()))?
+                .data;
+            let proposed = buffer_data
+                .get(UpgradeableLoaderState::size_of_buffer_metadata()..)
+                .unwrap_or_default()
+                .to_vec();
+
+            let deployed = match program {
+                Some(program) => {
+                    let (program_data, _) =
+                        Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+                    quorum.account(&program_data).await?.and_then(|account| {
+                        account
+                            .data
+                            .get(UpgradeableLoaderState::size_of_programdata_metadata()..)
+                            .map(<[u8]>::to_vec)
+                    })
+                }
+                None => None,
+            };
+
+            return Ok((proposed, deployed));
+        }
+
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+
+        tokio::task::spawn_blocking(move || {
+            let rpc_client = RpcClient::new(rpc_url);
+
+            let buffer_data = rpc_client
+                .get_account_data(&buffer)
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch buffer {}: {}", buffer, e)))?;
+            let proposed = buffer_data
+                .get(UpgradeableLoaderState::size_of_buffer_metadata()..)
+                .unwrap_or_default()
+                .to_vec();
+
+            let deployed = program.and_then(|program| {
+                let (program_data, _) =
+                    Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+                let data = rpc_client.get_account_data(&program_data).ok()?;
+                data.get(UpgradeableLoaderState::size_of_programdata_metadata()..)
+                    .map(<[u8]>::to_vec)
+            });
+
+            Ok((proposed, deployed))
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+    }
+
     /// Calculate program hash for verification
     pub fn calculate_program_hash(program_binary: &[u8]) -> [u8; 32] {
         let mut hasher = Sha256::new();
@@ -164,6 +339,57 @@ impl SecurityAuditor {
     }
 }
 
+/// Check that the caller holds the Executor role, which is required to override
+/// execution safety gates. Executor tokens come from the `EXECUTOR_TOKENS` secret (comma separated).
+pub fn verify_executor_token(token: Option<&str>, secrets: &SecretStore) -> Result<(), UpgradeError> {
+    executor_identity(token, secrets).map(|_| ())
+}
+
+/// Who holds an executor token. `EXECUTOR_TOKENS` entries may be `name:token`
+/// pairs; a bare token is identified by a short digest of itself, so two
+/// different tokens always count as two different executors.
+pub fn executor_identity(token: Option<&str>, secrets: &SecretStore) -> Result<String, UpgradeError> {
+    verify_role_token(token, secrets, "EXECUTOR_TOKENS", "Executor")
+}
+
+/// Check that the caller may manage the binary denylist. Tokens come from the
+/// `SECURITY_ADMIN_TOKENS` secret (comma separated).
+pub fn verify_security_admin_token(token: Option<&str>, secrets: &SecretStore) -> Result<(), UpgradeError> {
+    security_admin_identity(token, secrets).map(|_| ())
+}
+
+/// Who holds a security admin token, named like `executor_identity`
+pub fn security_admin_identity(token: Option<&str>, secrets: &SecretStore) -> Result<String, UpgradeError> {
+    verify_role_token(token, secrets, "SECURITY_ADMIN_TOKENS", "Security admin")
+}
+
+fn verify_role_token(
+    token: Option<&str>,
+    secrets: &SecretStore,
+    secret: &str,
+    role: &str,
+) -> Result<String, UpgradeError> {
+    let token = token
+        .ok_or_else(|| UpgradeError::Forbidden(format!("{} token required", role)))?;
+
+    let allowed = secrets.get(secret).unwrap_or_default();
+    allowed
+        .split(',')
+        .map(str::trim)
+        .find_map(|entry| {
+            let (name, expected) = match entry.split_once(':') {
+                Some((name, expected)) => (name.trim().to_string(), expected.trim()),
+                None => (token_identity(entry), entry),
+            };
+            (!expected.is_empty() && expected == token).then_some(name)
+        })
+        .ok_or_else(|| UpgradeError::Forbidden(format!("{} role required", role)))
+}
+
+fn token_identity(token: &str) -> String {
+    format!("token-{}", &hex::encode(Sha256::digest(token.as_bytes()))[..8])
+}
+
 #[derive(Debug, Clone)]
 pub struct AuditResult {
     pub passed: bool,
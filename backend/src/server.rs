@@ -0,0 +1,161 @@
+use crate::error::UpgradeError;
+use axum::http::{HeaderValue, Method};
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// Listener, CORS and TLS settings, read from the environment
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind_addr: SocketAddr,
+    /// `None` allows any origin
+    pub allowed_origins: Option<Vec<String>>,
+    pub allowed_methods: Vec<String>,
+    pub tls: Option<TlsConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// How often the certificate files are checked for changes
+    pub reload_interval_secs: u64,
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Result<Self, UpgradeError> {
+        let bind_addr = std::env::var("BIND_ADDR")
+            .unwrap_or_else(|_| "0.0.0.0:3000".to_string())
+            .parse()
+            .map_err(|e| UpgradeError::InvalidRequest(format!("Invalid BIND_ADDR: {}", e)))?;
+
+        let allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS")
+            .ok()
+            .map(|v| split_list(&v))
+            .filter(|origins| !origins.iter().any(|o| o == "*"));
+
+        let allowed_methods = std::env::var("CORS_ALLOWED_METHODS")
+            .map(|v| split_list(&v))
+            .unwrap_or_else(|_| vec!["GET".to_string(), "POST".to_string()]);
+
+        let tls = match (std::env::var("TLS_CERT_PATH"), std::env::var("TLS_KEY_PATH")) {
+            (Ok(cert_path), Ok(key_path)) => Some(TlsConfig {
+                cert_path: cert_path.into(),
+                key_path: key_path.into(),
+                reload_interval_secs: std::env::var("TLS_RELOAD_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60),
+            }),
+            (Err(_), Err(_)) => None,
+            _ => {
+                return Err(UpgradeError::InvalidRequest(
+                    "TLS_CERT_PATH and TLS_KEY_PATH must be set together".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            bind_addr,
+            allowed_origins,
+            allowed_methods,
+            tls,
+        })
+    }
+
+    pub fn cors_layer(&self) -> Result<CorsLayer, UpgradeError> {
+        let methods = self
+            .allowed_methods
+            .iter()
+            .map(|m| {
+                Method::from_bytes(m.to_uppercase().as_bytes())
+                    .map_err(|_| UpgradeError::InvalidRequest(format!("Invalid CORS method: {}", m)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let origin = match &self.allowed_origins {
+            Some(origins) => AllowOrigin::list(
+                origins
+                    .iter()
+                    .map(|o| {
+                        HeaderValue::from_str(o).map_err(|_| {
+                            UpgradeError::InvalidRequest(format!("Invalid CORS origin: {}", o))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => {
+                tracing::warn!("CORS_ALLOWED_ORIGINS not set, allowing any origin");
+                AllowOrigin::any()
+            }
+        };
+
+        Ok(CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods(methods)
+            .allow_headers(Any))
+    }
+}
+
+/// Serves `app` over plain HTTP, or HTTPS when TLS is configured
+pub async fn serve(config: &ServerConfig, app: Router) -> anyhow::Result<()> {
+    match &config.tls {
+        Some(tls) => {
+            let rustls = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+            tokio::spawn(watch_certificates(tls.clone(), rustls.clone()));
+
+            tracing::info!("Server listening on https://{}", config.bind_addr);
+            axum_server::bind_rustls(config.bind_addr, rustls)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(config.bind_addr).await?;
+            tracing::info!("Server listening on http://{}", config.bind_addr);
+            axum::serve(listener, app).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reloads the certificate when either PEM file changes on disk, so renewed
+/// certificates are picked up without a restart.
+async fn watch_certificates(tls: TlsConfig, rustls: RustlsConfig) {
+    let mut last_modified = modified_at(&tls);
+    let mut interval =
+        tokio::time::interval(tokio::time::Duration::from_secs(tls.reload_interval_secs));
+
+    loop {
+        interval.tick().await;
+        let modified = modified_at(&tls);
+        if modified == last_modified {
+            continue;
+        }
+
+        match rustls.reload_from_pem_file(&tls.cert_path, &tls.key_path).await {
+            Ok(()) => {
+                tracing::info!("Reloaded TLS certificate from {}", tls.cert_path.display());
+                last_modified = modified;
+            }
+            Err(e) => tracing::error!("Failed to reload TLS certificate: {}", e),
+        }
+    }
+}
+
+fn modified_at(tls: &TlsConfig) -> Option<(SystemTime, SystemTime)> {
+    let cert = std::fs::metadata(&tls.cert_path).and_then(|m| m.modified()).ok()?;
+    let key = std::fs::metadata(&tls.key_path).and_then(|m| m.modified()).ok()?;
+    Some((cert, key))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
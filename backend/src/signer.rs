@@ -0,0 +1,221 @@
+use crate::error::UpgradeError;
+use crate::secrets::SecretStore;
+use async_trait::async_trait;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Signs transaction messages for one key, wherever that key lives
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, UpgradeError>;
+}
+
+pub type SharedSigner = Arc<dyn TransactionSigner>;
+
+/// Key held in process memory, loaded from a keypair file or the environment
+pub struct LocalSigner {
+    keypair: Keypair,
+}
+
+impl LocalSigner {
+    pub fn new(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, UpgradeError> {
+        let keypair = read_keypair_file(path).map_err(|e| {
+            UpgradeError::InternalError(format!("Failed to read keypair file {}: {}", path, e))
+        })?;
+        Ok(Self::new(keypair))
+    }
+
+    /// Accepts a base58 secret key or the JSON byte array written by `solana-keygen`
+    pub fn from_secret(secret: &str) -> Result<Self, UpgradeError> {
+        Ok(Self::new(keypair_from_secret(secret)?))
+    }
+}
+
+/// Parses a base58 secret key or a `solana-keygen` JSON byte array
+pub fn keypair_from_secret(secret: &str) -> Result<Keypair, UpgradeError> {
+    let secret = secret.trim();
+    let bytes = if secret.starts_with('[') {
+        serde_json::from_str::<Vec<u8>>(secret)
+            .map_err(|_| UpgradeError::InternalError("Malformed secret key byte array".to_string()))?
+    } else {
+        bs58::decode(secret)
+            .into_vec()
+            .map_err(|_| UpgradeError::InternalError("Malformed base58 secret key".to_string()))?
+    };
+
+    Keypair::from_bytes(&bytes)
+        .map_err(|e| UpgradeError::InternalError(format!("Invalid secret key: {}", e)))
+}
+
+#[async_trait]
+impl TransactionSigner for LocalSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, UpgradeError> {
+        Ok(self.keypair.sign_message(message))
+    }
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest<'a> {
+    pubkey: String,
+    /// Base64 serialized transaction message
+    message: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    /// Base58 ed25519 signature
+    signature: String,
+}
+
+/// Key held by an HTTP signing service (a KMS-backed proxy, a threshold
+/// signer, ...). The service never hands out the key; every signature it
+/// returns is verified before use.
+pub struct RemoteSigner {
+    http: reqwest::Client,
+    url: String,
+    pubkey: Pubkey,
+    auth_token: Option<String>,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey, auth_token: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            pubkey,
+            auth_token,
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, UpgradeError> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(message);
+        let mut request = self.http.post(&self.url).json(&RemoteSignRequest {
+            pubkey: self.pubkey.to_string(),
+            message: &encoded,
+        });
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| UpgradeError::InternalError(format!("Remote signer request failed: {}", e)))?
+            .json::<RemoteSignResponse>()
+            .await
+            .map_err(|e| UpgradeError::InternalError(format!("Invalid remote signer response: {}", e)))?;
+
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|_| UpgradeError::InternalError("Remote signer returned a malformed signature".to_string()))?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(UpgradeError::InternalError(format!(
+                "Remote signer returned a signature that does not verify for {}",
+                self.pubkey
+            )));
+        }
+
+        Ok(signature)
+    }
+}
+
+/// Builds the fee payer signer selected by `FEE_PAYER_SIGNER`:
+///
+/// - `file`: keypair file at `FEE_PAYER_KEYPAIR`
+/// - `env`: secret key injected as `FEE_PAYER_PRIVATE_KEY`
+/// - `remote`: signing service at `FEE_PAYER_REMOTE_URL` for `FEE_PAYER_PUBKEY`,
+///   authenticated with `FEE_PAYER_REMOTE_TOKEN` when set
+///
+/// `FEE_PAYER_PRIVATE_KEY` and `FEE_PAYER_REMOTE_TOKEN` are read from `secrets`.
+/// When unset, the mode is inferred from whichever of those variables is present.
+/// Returns `None` if no fee payer is configured.
+pub fn fee_payer(secrets: &SecretStore) -> Result<Option<SharedSigner>, UpgradeError> {
+    let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+    let mode = match var("FEE_PAYER_SIGNER") {
+        Some(mode) => mode,
+        None if var("FEE_PAYER_KEYPAIR").is_some() => "file".to_string(),
+        None if secrets.get("FEE_PAYER_PRIVATE_KEY").is_some() => "env".to_string(),
+        None if var("FEE_PAYER_REMOTE_URL").is_some() => "remote".to_string(),
+        None => return Ok(None),
+    };
+    let required = |key: &str| {
+        var(key).or_else(|| secrets.get(key)).ok_or_else(|| {
+            UpgradeError::InvalidRequest(format!("{} is required for the {} fee payer signer", key, mode))
+        })
+    };
+
+    let signer: SharedSigner = match mode.as_str() {
+        "file" => Arc::new(LocalSigner::from_file(&required("FEE_PAYER_KEYPAIR")?)?),
+        "env" => Arc::new(LocalSigner::from_secret(&required("FEE_PAYER_PRIVATE_KEY")?)?),
+        "remote" => {
+            let pubkey = Pubkey::from_str(&required("FEE_PAYER_PUBKEY")?)
+                .map_err(|_| UpgradeError::InvalidPubkey)?;
+            Arc::new(RemoteSigner::new(
+                required("FEE_PAYER_REMOTE_URL")?,
+                pubkey,
+                secrets.get("FEE_PAYER_REMOTE_TOKEN"),
+            ))
+        }
+        other => {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Unknown FEE_PAYER_SIGNER '{}', expected file, env or remote",
+                other
+            )))
+        }
+    };
+
+    tracing::info!("Fee payer {} ({} signer)", signer.pubkey(), mode);
+
+    Ok(Some(signer))
+}
+
+/// Sets `blockhash`, signs with the in-memory `keypairs`, then asks `signer`
+/// for its signature over the same message.
+pub async fn sign_transaction(
+    tx: &mut Transaction,
+    signer: &dyn TransactionSigner,
+    keypairs: &[&Keypair],
+    blockhash: Hash,
+) -> Result<(), UpgradeError> {
+    tx.try_partial_sign(keypairs, blockhash)
+        .map_err(|e| UpgradeError::SolanaError(format!("Failed to sign transaction: {}", e)))?;
+
+    let pubkey = signer.pubkey();
+    let position = tx
+        .get_signing_keypair_positions(&[pubkey])
+        .map_err(|e| UpgradeError::SolanaError(e.to_string()))?
+        .first()
+        .copied()
+        .flatten()
+        .ok_or_else(|| {
+            UpgradeError::SolanaError(format!("{} is not a signer of this transaction", pubkey))
+        })?;
+
+    tx.signatures[position] = signer.sign_message(&tx.message_data()).await?;
+
+    Ok(())
+}
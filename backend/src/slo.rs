@@ -0,0 +1,255 @@
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Width of the buckets samples are counted in
+const BUCKET_SECONDS: i64 = 60;
+
+/// Multiwindow burn-rate alert rules: (long window, short window, burn rate, page?).
+/// A rule fires when both windows burn faster than the rate. 14.4x over an hour
+/// spends 2% of a 30 day budget; 6x over six hours spends 5%.
+const BURN_RATE_RULES: [(i64, i64, f64, bool); 2] = [
+    (3600, 300, 14.4, true),
+    (6 * 3600, 1800, 6.0, false),
+];
+
+/// What makes a sample good
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SloKind {
+    /// Good when the operation finished within `threshold_ms`
+    Latency { threshold_ms: u64 },
+    /// Good when the operation succeeded
+    SuccessRate,
+}
+
+/// A service level objective over one operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloDefinition {
+    pub name: String,
+    /// Operation whose samples count toward this SLO
+    pub operation: String,
+    #[serde(flatten)]
+    pub kind: SloKind,
+    /// Fraction of good samples required, e.g. 0.99
+    pub target: f64,
+    /// Rolling compliance window
+    #[serde(default = "default_window_seconds")]
+    pub window_seconds: i64,
+}
+
+fn default_window_seconds() -> i64 {
+    30 * 24 * 3600
+}
+
+/// Targets used when `SLO_CONFIG` is not set
+pub fn default_slos() -> Vec<SloDefinition> {
+    vec![
+        SloDefinition {
+            name: "status_read_latency".to_string(),
+            operation: "status_read".to_string(),
+            kind: SloKind::Latency { threshold_ms: 200 },
+            target: 0.99,
+            window_seconds: default_window_seconds(),
+        },
+        SloDefinition {
+            name: "execution_success".to_string(),
+            operation: "execution".to_string(),
+            kind: SloKind::SuccessRate,
+            target: 0.995,
+            window_seconds: default_window_seconds(),
+        },
+    ]
+}
+
+/// SLO definitions from the JSON file at `SLO_CONFIG`, or the defaults
+pub fn slos_from_env() -> Result<Vec<SloDefinition>, UpgradeError> {
+    let path = match std::env::var("SLO_CONFIG") {
+        Ok(path) => path,
+        Err(_) => return Ok(default_slos()),
+    };
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| UpgradeError::InternalError(format!("Failed to read {}: {}", path, e)))?;
+    let slos: Vec<SloDefinition> = serde_json::from_str(&contents)
+        .map_err(|e| UpgradeError::InternalError(format!("Invalid SLO config: {}", e)))?;
+
+    if let Some(slo) = slos.iter().find(|s| !(0.0..1.0).contains(&s.target)) {
+        return Err(UpgradeError::InternalError(format!(
+            "SLO {} target must be in [0, 1), got {}",
+            slo.name, slo.target
+        )));
+    }
+
+    Ok(slos)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    good: u64,
+    total: u64,
+}
+
+/// Rolling compliance and burn rates for one SLO
+#[derive(Debug, Clone, Serialize)]
+pub struct SloStatus {
+    pub name: String,
+    pub operation: String,
+    pub target: f64,
+    pub window_seconds: i64,
+    pub total: u64,
+    pub good: u64,
+    /// Fraction of good samples in the window; 1.0 with no samples
+    pub compliance: f64,
+    /// Share of the window's error budget still unspent (negative once blown)
+    pub error_budget_remaining: f64,
+    /// Error rate relative to the budgeted rate, keyed by window in seconds
+    pub burn_rates: BTreeMap<i64, f64>,
+    pub met: bool,
+}
+
+/// A burn-rate rule currently firing for an SLO
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurnRateAlert {
+    pub slo: String,
+    pub long_window_seconds: i64,
+    pub burn_rate: f64,
+    pub threshold: f64,
+    /// Fast burn; page rather than ticket
+    pub page: bool,
+}
+
+/// Per-minute good/total counts for every configured SLO
+pub struct SloTracker {
+    slos: Vec<SloDefinition>,
+    buckets: HashMap<String, BTreeMap<i64, Bucket>>,
+}
+
+impl SloTracker {
+    pub fn new(slos: Vec<SloDefinition>) -> Self {
+        Self {
+            slos,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn definitions(&self) -> &[SloDefinition] {
+        &self.slos
+    }
+
+    /// Record one latency sample; counts toward latency SLOs on `operation`
+    pub fn record_latency(&mut self, operation: &str, latency_ms: u64, now: i64) {
+        self.record(operation, now, |kind| match kind {
+            SloKind::Latency { threshold_ms } => Some(latency_ms <= *threshold_ms),
+            SloKind::SuccessRate => None,
+        });
+    }
+
+    /// Record one outcome; counts toward success-rate SLOs on `operation`
+    pub fn record_outcome(&mut self, operation: &str, success: bool, now: i64) {
+        self.record(operation, now, |kind| match kind {
+            SloKind::SuccessRate => Some(success),
+            SloKind::Latency { .. } => None,
+        });
+    }
+
+    fn record(&mut self, operation: &str, now: i64, good: impl Fn(&SloKind) -> Option<bool>) {
+        let bucket_start = now - now.rem_euclid(BUCKET_SECONDS);
+
+        for slo in self.slos.iter().filter(|s| s.operation == operation) {
+            let Some(good) = good(&slo.kind) else {
+                continue;
+            };
+
+            let buckets = self.buckets.entry(slo.name.clone()).or_default();
+            let bucket = buckets.entry(bucket_start).or_default();
+            bucket.total += 1;
+            if good {
+                bucket.good += 1;
+            }
+
+            // Drop buckets older than anything we report on
+            let horizon = slo.window_seconds.max(BURN_RATE_RULES[1].0);
+            let cutoff = bucket_start - horizon;
+            while buckets.first_key_value().is_some_and(|(start, _)| *start < cutoff) {
+                buckets.pop_first();
+            }
+        }
+    }
+
+    fn counts(&self, slo: &SloDefinition, window_seconds: i64, now: i64) -> Bucket {
+        let since = now - window_seconds;
+        self.buckets
+            .get(&slo.name)
+            .map(|buckets| {
+                buckets
+                    .range(since - since.rem_euclid(BUCKET_SECONDS)..)
+                    .fold(Bucket::default(), |acc, (_, b)| Bucket {
+                        good: acc.good + b.good,
+                        total: acc.total + b.total,
+                    })
+            })
+            .unwrap_or_default()
+    }
+
+    fn burn_rate(&self, slo: &SloDefinition, window_seconds: i64, now: i64) -> f64 {
+        let counts = self.counts(slo, window_seconds, now);
+        if counts.total == 0 {
+            return 0.0;
+        }
+        let error_rate = (counts.total - counts.good) as f64 / counts.total as f64;
+        error_rate / (1.0 - slo.target)
+    }
+
+    pub fn status(&self, now: i64) -> Vec<SloStatus> {
+        self.slos
+            .iter()
+            .map(|slo| {
+                let counts = self.counts(slo, slo.window_seconds, now);
+                let compliance = if counts.total == 0 {
+                    1.0
+                } else {
+                    counts.good as f64 / counts.total as f64
+                };
+                let burn_rates = BURN_RATE_RULES
+                    .iter()
+                    .flat_map(|(long, short, _, _)| [*long, *short])
+                    .map(|window| (window, self.burn_rate(slo, window, now)))
+                    .collect();
+
+                SloStatus {
+                    name: slo.name.clone(),
+                    operation: slo.operation.clone(),
+                    target: slo.target,
+                    window_seconds: slo.window_seconds,
+                    total: counts.total,
+                    good: counts.good,
+                    compliance,
+                    error_budget_remaining: 1.0 - (1.0 - compliance) / (1.0 - slo.target),
+                    burn_rates,
+                    met: compliance >= slo.target,
+                }
+            })
+            .collect()
+    }
+
+    /// The most severe burn-rate rule firing for each SLO
+    pub fn burn_rate_alerts(&self, now: i64) -> Vec<BurnRateAlert> {
+        self.slos
+            .iter()
+            .filter_map(|slo| {
+                BURN_RATE_RULES.iter().find_map(|(long, short, threshold, page)| {
+                    let long_rate = self.burn_rate(slo, *long, now);
+                    let short_rate = self.burn_rate(slo, *short, now);
+                    (long_rate > *threshold && short_rate > *threshold).then(|| BurnRateAlert {
+                        slo: slo.name.clone(),
+                        long_window_seconds: *long,
+                        burn_rate: long_rate,
+                        threshold: *threshold,
+                        page: *page,
+                    })
+                })
+            })
+            .collect()
+    }
+}
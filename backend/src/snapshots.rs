@@ -0,0 +1,363 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Why a snapshot was taken
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotLabel {
+    PreUpgrade,
+    PostUpgrade,
+    Manual,
+}
+
+impl SnapshotLabel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotLabel::PreUpgrade => "pre_upgrade",
+            SnapshotLabel::PostUpgrade => "post_upgrade",
+            SnapshotLabel::Manual => "manual",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pre_upgrade" => Some(SnapshotLabel::PreUpgrade),
+            "post_upgrade" => Some(SnapshotLabel::PostUpgrade),
+            "manual" => Some(SnapshotLabel::Manual),
+            _ => None,
+        }
+    }
+}
+
+/// Accounts sharing one data length
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SizeBucket {
+    pub accounts: u64,
+    pub lamports: u64,
+}
+
+/// Aggregate view of every account owned by the managed program at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSetSnapshot {
+    pub snapshot_id: String,
+    pub program: String,
+    pub proposal_id: Option<String>,
+    pub label: SnapshotLabel,
+    pub account_count: u64,
+    pub total_lamports: u64,
+    /// Keyed by data length; Anchor accounts of one type share a length, so
+    /// this doubles as a per-type breakdown
+    pub size_buckets: BTreeMap<u64, SizeBucket>,
+    pub taken_at: i64,
+}
+
+impl AccountSetSnapshot {
+    /// Aggregates `(data_len, lamports)` pairs into a snapshot
+    pub fn from_accounts(
+        program: &str,
+        proposal_id: Option<&str>,
+        label: SnapshotLabel,
+        accounts: impl IntoIterator<Item = (u64, u64)>,
+    ) -> Self {
+        let mut size_buckets: BTreeMap<u64, SizeBucket> = BTreeMap::new();
+        let mut account_count = 0u64;
+        let mut total_lamports = 0u64;
+
+        for (data_len, lamports) in accounts {
+            let bucket = size_buckets.entry(data_len).or_default();
+            bucket.accounts += 1;
+            bucket.lamports = bucket.lamports.saturating_add(lamports);
+            account_count += 1;
+            total_lamports = total_lamports.saturating_add(lamports);
+        }
+
+        Self {
+            snapshot_id: uuid::Uuid::new_v4().to_string(),
+            program: program.to_string(),
+            proposal_id: proposal_id.map(str::to_string),
+            label,
+            account_count,
+            total_lamports,
+            size_buckets,
+            taken_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// More accounts closed than `max_closure_pct` allows
+    MassClosure,
+    /// More accounts created than `max_growth_pct` allows
+    UnexpectedGrowth,
+    /// Lamports held by the program's accounts fell more than `max_lamport_drain_pct`
+    LamportDrain,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotAnomaly {
+    pub kind: AnomalyKind,
+    pub detail: String,
+}
+
+/// Change in one size bucket between two snapshots
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeBucketChange {
+    pub data_len: u64,
+    pub before: SizeBucket,
+    pub after: SizeBucket,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub from_snapshot: String,
+    pub to_snapshot: String,
+    pub program: String,
+    pub accounts_before: u64,
+    pub accounts_after: u64,
+    pub lamports_before: u64,
+    pub lamports_after: u64,
+    /// Only the buckets that changed
+    pub size_changes: Vec<SizeBucketChange>,
+    pub anomalies: Vec<SnapshotAnomaly>,
+}
+
+impl SnapshotDiff {
+    pub fn has_anomalies(&self) -> bool {
+        !self.anomalies.is_empty()
+    }
+}
+
+/// When a change between snapshots counts as an anomaly
+#[derive(Debug, Clone)]
+pub struct AnomalyThresholds {
+    pub max_closure_pct: f64,
+    pub max_growth_pct: f64,
+    pub max_lamport_drain_pct: f64,
+    /// Below this many accounts the percentages are too noisy to flag
+    pub min_accounts: u64,
+}
+
+impl Default for AnomalyThresholds {
+    fn default() -> Self {
+        Self {
+            max_closure_pct: 10.0,
+            max_growth_pct: 50.0,
+            max_lamport_drain_pct: 10.0,
+            min_accounts: 10,
+        }
+    }
+}
+
+impl AnomalyThresholds {
+    /// Reads `SNAPSHOT_MAX_CLOSURE_PCT`, `SNAPSHOT_MAX_GROWTH_PCT`,
+    /// `SNAPSHOT_MAX_LAMPORT_DRAIN_PCT` and `SNAPSHOT_MIN_ACCOUNTS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str| std::env::var(key).ok().and_then(|v| v.parse().ok());
+
+        Self {
+            max_closure_pct: read("SNAPSHOT_MAX_CLOSURE_PCT").unwrap_or(defaults.max_closure_pct),
+            max_growth_pct: read("SNAPSHOT_MAX_GROWTH_PCT").unwrap_or(defaults.max_growth_pct),
+            max_lamport_drain_pct: read("SNAPSHOT_MAX_LAMPORT_DRAIN_PCT")
+                .unwrap_or(defaults.max_lamport_drain_pct),
+            min_accounts: std::env::var("SNAPSHOT_MIN_ACCOUNTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.min_accounts),
+        }
+    }
+}
+
+/// Compares two snapshots of the same program and flags anomalies
+pub fn diff(
+    before: &AccountSetSnapshot,
+    after: &AccountSetSnapshot,
+    thresholds: &AnomalyThresholds,
+) -> SnapshotDiff {
+    let sizes: BTreeSet<u64> = before
+        .size_buckets
+        .keys()
+        .chain(after.size_buckets.keys())
+        .copied()
+        .collect();
+
+    let size_changes = sizes
+        .into_iter()
+        .filter_map(|data_len| {
+            let old = before.size_buckets.get(&data_len).copied().unwrap_or_default();
+            let new = after.size_buckets.get(&data_len).copied().unwrap_or_default();
+            (old != new).then_some(SizeBucketChange { data_len, before: old, after: new })
+        })
+        .collect();
+
+    let mut anomalies = Vec::new();
+    if before.account_count >= thresholds.min_accounts {
+        let pct = |from: u64, to: u64| (to as f64 - from as f64) / from as f64 * 100.0;
+
+        let account_change = pct(before.account_count, after.account_count);
+        if -account_change > thresholds.max_closure_pct {
+            anomalies.push(SnapshotAnomaly {
+                kind: AnomalyKind::MassClosure,
+                detail: format!(
+                    "{} of {} accounts closed ({:.1}%, limit {}%)",
+                    before.account_count - after.account_count,
+                    before.account_count,
+                    -account_change,
+                    thresholds.max_closure_pct
+                ),
+            });
+        }
+        if account_change > thresholds.max_growth_pct {
+            anomalies.push(SnapshotAnomaly {
+                kind: AnomalyKind::UnexpectedGrowth,
+                detail: format!(
+                    "{} accounts created on top of {} ({:.1}%, limit {}%)",
+                    after.account_count - before.account_count,
+                    before.account_count,
+                    account_change,
+                    thresholds.max_growth_pct
+                ),
+            });
+        }
+
+        if before.total_lamports > 0 {
+            let lamport_change = pct(before.total_lamports, after.total_lamports);
+            if -lamport_change > thresholds.max_lamport_drain_pct {
+                anomalies.push(SnapshotAnomaly {
+                    kind: AnomalyKind::LamportDrain,
+                    detail: format!(
+                        "lamports fell from {} to {} ({:.1}%, limit {}%)",
+                        before.total_lamports,
+                        after.total_lamports,
+                        -lamport_change,
+                        thresholds.max_lamport_drain_pct
+                    ),
+                });
+            }
+        }
+    }
+
+    SnapshotDiff {
+        from_snapshot: before.snapshot_id.clone(),
+        to_snapshot: after.snapshot_id.clone(),
+        program: after.program.clone(),
+        accounts_before: before.account_count,
+        accounts_after: after.account_count,
+        lamports_before: before.total_lamports,
+        lamports_after: after.total_lamports,
+        size_changes,
+        anomalies,
+    }
+}
+
+/// Takes and compares snapshots of the managed program's account set
+pub struct SnapshotService {
+    database: Arc<Database>,
+    rpc_url: String,
+    managed_program: String,
+    thresholds: AnomalyThresholds,
+}
+
+impl SnapshotService {
+    pub fn new(database: Arc<Database>) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+
+        Self {
+            database,
+            rpc_url,
+            managed_program: std::env::var("MANAGED_PROGRAM_ID")
+                .unwrap_or_else(|_| "program_id".to_string()),
+            thresholds: AnomalyThresholds::from_env(),
+        }
+    }
+
+    /// Snapshot every account owned by the managed program and store it
+    pub async fn capture(
+        &self,
+        label: SnapshotLabel,
+        proposal_id: Option<&str>,
+    ) -> Result<AccountSetSnapshot, UpgradeError> {
+        let program = Pubkey::from_str(&self.managed_program).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let rpc_url = self.rpc_url.clone();
+
+        let accounts = tokio::task::spawn_blocking(move || {
+            RpcClient::new(rpc_url).get_program_accounts(&program)
+        })
+        .await
+        .map_err(|e| UpgradeError::InternalError(e.to_string()))?
+        .map_err(|e| UpgradeError::SolanaError(format!("Snapshot query failed: {}", e)))?;
+
+        let snapshot = AccountSetSnapshot::from_accounts(
+            &self.managed_program,
+            proposal_id,
+            label,
+            accounts
+                .iter()
+                .map(|(_, account)| (account.data.len() as u64, account.lamports)),
+        );
+
+        self.database.insert_account_snapshot(&snapshot).await?;
+
+        tracing::info!(
+            "Snapshot {} ({}): {} accounts, {} lamports",
+            snapshot.snapshot_id,
+            label.as_str(),
+            snapshot.account_count,
+            snapshot.total_lamports
+        );
+
+        Ok(snapshot)
+    }
+
+    pub async fn compare(&self, from: &str, to: &str) -> Result<SnapshotDiff, UpgradeError> {
+        let before = self.load(from).await?;
+        let after = self.load(to).await?;
+
+        if before.program != after.program {
+            return Err(UpgradeError::InvalidRequest(
+                "Snapshots belong to different programs".to_string(),
+            ));
+        }
+
+        Ok(diff(&before, &after, &self.thresholds))
+    }
+
+    /// Compare the proposal's latest pre- and post-upgrade snapshots and store
+    /// the result on its upgrade_history row
+    pub async fn compare_upgrade(&self, proposal_id: &str) -> Result<SnapshotDiff, UpgradeError> {
+        let before = self.database
+            .latest_account_snapshot(proposal_id, SnapshotLabel::PreUpgrade)
+            .await?
+            .ok_or_else(|| UpgradeError::SnapshotNotFound(format!("pre_upgrade for {}", proposal_id)))?;
+        let after = self.database
+            .latest_account_snapshot(proposal_id, SnapshotLabel::PostUpgrade)
+            .await?
+            .ok_or_else(|| UpgradeError::SnapshotNotFound(format!("post_upgrade for {}", proposal_id)))?;
+
+        let diff = diff(&before, &after, &self.thresholds);
+
+        for anomaly in &diff.anomalies {
+            tracing::warn!("Account set anomaly after {}: {}", proposal_id, anomaly.detail);
+        }
+
+        self.database.save_upgrade_account_diff(proposal_id, &diff).await?;
+
+        Ok(diff)
+    }
+
+    async fn load(&self, snapshot_id: &str) -> Result<AccountSetSnapshot, UpgradeError> {
+        self.database
+            .get_account_snapshot(snapshot_id)
+            .await?
+            .ok_or_else(|| UpgradeError::SnapshotNotFound(snapshot_id.to_string()))
+    }
+}
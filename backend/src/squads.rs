@@ -1,155 +1,749 @@
 use crate::error::UpgradeError;
-use solana_sdk::{
-    instruction::Instruction,
-    pubkey::Pubkey,
-    signature::Keypair,
-    transaction::Transaction,
+use crate::multisig::AccountReader;
+use crate::multisig_backend::{
+    fetch_account_data, instruction_discriminator, push_instruction, push_string, read_instruction,
+    remaining_account, BackendTransactionStatus, MultisigBackend, MultisigBackendKind, ProposedTransaction,
 };
-use solana_client::rpc_client::RpcClient;
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::loader_upgradeable_instruction::UpgradeableLoaderInstruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
+use std::sync::Arc;
 
-/// Squads Protocol integration for multisig transactions
-pub struct SquadsClient {
-    rpc_client: RpcClient,
-    multisig_vault: Pubkey,
-    threshold: u8,
+/// Squads Multisig v3 (squads-mpl)
+pub const SQUADS_V3_PROGRAM_ID: &str = "SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu";
+
+/// Squads Multisig v4
+pub const SQUADS_V4_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf";
+
+/// Loader `Upgrade` instruction signed by the multisig vault. Reclaimed buffer
+/// lamports go to `spill`.
+pub fn upgrade_instruction(program: &Pubkey, buffer: &Pubkey, vault: &Pubkey, spill: &Pubkey) -> Instruction {
+    bpf_loader_upgradeable::upgrade(program, buffer, vault, spill)
+}
+
+/// Loader `DeployWithMaxDataLen` instruction signed by the multisig vault,
+/// which pays for the program data account and becomes the upgrade
+/// authority. `program` must already be an uninitialized loader account.
+pub fn deploy_instruction(program: &Pubkey, buffer: &Pubkey, vault: &Pubkey, max_data_len: usize) -> Instruction {
+    let (programdata, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    Instruction::new_with_bincode(
+        bpf_loader_upgradeable::id(),
+        &UpgradeableLoaderInstruction::DeployWithMaxDataLen { max_data_len },
+        vec![
+            AccountMeta::new(*vault, true),
+            AccountMeta::new(programdata, false),
+            AccountMeta::new(*program, false),
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(*vault, true),
+        ],
+    )
+}
+
+/// Loader `SetAuthority` instruction signed by the multisig vault; `None`
+/// makes the program immutable
+pub fn set_authority_instruction(program: &Pubkey, vault: &Pubkey, new_authority: Option<&Pubkey>) -> Instruction {
+    bpf_loader_upgradeable::set_upgrade_authority(program, vault, new_authority)
+}
+
+/// Squads v3: transactions are `MsTransaction` PDAs holding one `MsInstruction`
+/// PDA per instruction, each executed separately once the transaction is
+/// `ExecuteReady`
+pub struct SquadsV3Backend {
+    rpc_client: Arc<AsyncRpcClient>,
+    program_id: Pubkey,
+    multisig: Pubkey,
+    authority_index: u32,
+}
+
+/// The `Ms` account fields the backend uses
+#[derive(Debug, Clone)]
+pub struct SquadsV3Multisig {
+    pub threshold: u16,
+    pub transaction_index: u32,
+    pub keys: Vec<Pubkey>,
 }
 
-impl SquadsClient {
-    pub fn new(rpc_url: String, multisig_vault: Pubkey, threshold: u8) -> Result<Self, UpgradeError> {
-        let rpc_client = RpcClient::new(rpc_url);
+impl SquadsV3Multisig {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        let threshold = reader.u16()?;
+        reader.skip(2); // authority_index
+        let transaction_index = reader.u32()?;
+        reader.skip(4 + 1 + 32 + 1); // ms_change_index, bump, create_key, allow_external_execute
+        let keys = reader.pubkeys()?;
+
         Ok(Self {
-            rpc_client,
-            multisig_vault,
             threshold,
+            transaction_index,
+            keys,
         })
     }
+}
 
-    /// Create a multisig transaction proposal
-    pub async fn create_transaction(
-        &self,
-        instructions: Vec<Instruction>,
-        >,
-        description: String,
-    ) -> Result<String, UpgradeError> {
-        // In production, this would:
-        // 1. Create a transaction proposal in Squads Protocol
-        // 2. Return the proposal transaction key
-        
-        // Squads Protocol uses MS (Multisig) program
-        // Transaction key is derived from: [multisig_vault, transaction_index]
-        
-        let proposal_id = uuid::Uuid::new_v4().to_string();
-        
-        tracing::info!(
-            "Creating Squads transaction proposal: {} with {} instructions",
-            proposal_id,
-            instructions.len()
-        );
-        
-        // Placeholder: In real implementation, call Squads MS program
-        // let transaction_key = self.create_squads_transaction(instructions).await?;
-        
-        Ok(proposal_id)
-    }
-
-    /// Approve a multisig transaction
-    pub async fn approve_transaction(
-        &self,
-        transaction_key: &Pubkey,
-        member_keypair: &Keypair,
-    ) -> Result<String, UpgradeError> {
-        // In production, this would:
-        // 1. Build approve instruction for Squads MS program
-        // 2. Sign with member keypair
-        // 3. Send transaction
-        // 4. Return transaction signature
-        
-        tracing::info!("Approving Squads transaction: {}", transaction_key);
-        
-        // Placeholder: In real implementation
-        // let approve_ix = self.build_approve_instruction(transaction_key, member_keypair.pubkey())?;
-        // let tx = Transaction::new_signed_with_payer(...);
-        // let sig = self.rpc_client.send_and_confirm_transaction(&tx)?;
-        
-        Ok("approval_signature".to_string())
-    }
-
-    /// Execute a multisig transaction (after threshold met)
-    pub async fn execute_transaction(
+/// The `MsTransaction` account fields the backend uses
+#[derive(Debug, Clone)]
+pub struct SquadsV3Transaction {
+    pub status: String,
+    pub instruction_index: u8,
+    pub approved: Vec<Pubkey>,
+    pub executed_index: u8,
+}
+
+impl SquadsV3Transaction {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        reader.skip(32 + 32 + 4 + 4 + 1); // creator, ms, transaction_index, authority_index, authority_bump
+        let status = match reader.u8()? {
+            0 => "draft",
+            1 => "active",
+            2 => "execute_ready",
+            3 => "executed",
+            4 => "rejected",
+            5 => "cancelled",
+            _ => return Err(UpgradeError::MultisigError("Invalid Squads v3 transaction status".to_string())),
+        };
+        let instruction_index = reader.u8()?;
+        reader.skip(1); // bump
+        let approved = reader.pubkeys()?;
+        reader.pubkeys()?; // rejected
+        reader.pubkeys()?; // cancelled
+        let executed_index = reader.u8()?;
+
+        Ok(Self {
+            status: status.to_string(),
+            instruction_index,
+            approved,
+            executed_index,
+        })
+    }
+}
+
+/// A stored `MsInstruction`, decoded back into the instruction it wraps
+pub fn decode_v3_instruction(data: &[u8]) -> Result<Instruction, UpgradeError> {
+    read_instruction(&mut AccountReader::anchor(data))
+}
+
+impl SquadsV3Backend {
+    pub fn new(rpc_client: Arc<AsyncRpcClient>, multisig: Pubkey, authority_index: u32) -> Self {
+        Self {
+            rpc_client,
+            program_id: Pubkey::from_str(SQUADS_V3_PROGRAM_ID).unwrap(),
+            multisig,
+            authority_index,
+        }
+    }
+
+    pub fn authority_address(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"squad", self.multisig.as_ref(), &self.authority_index.to_le_bytes(), b"authority"],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn transaction_address(&self, transaction_index: u32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"squad", self.multisig.as_ref(), &transaction_index.to_le_bytes(), b"transaction"],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn instruction_address(&self, transaction: &Pubkey, instruction_index: u8) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"squad", transaction.as_ref(), &[instruction_index], b"instruction"],
+            &self.program_id,
+        )
+        .0
+    }
+
+    /// `create_transaction`, `add_instruction` and `activate_transaction` for
+    /// a transaction holding `instruction`
+    pub fn propose_instructions(&self, creator: &Pubkey, transaction_index: u32, instruction: &Instruction) -> Vec<Instruction> {
+        let transaction = self.transaction_address(transaction_index);
+
+        let mut create = instruction_discriminator("create_transaction").to_vec();
+        create.extend_from_slice(&self.authority_index.to_le_bytes());
+
+        // IncomingInstruction { program_id, keys: Vec<MsAccountMeta>, data: Vec<u8> }
+        let mut add = instruction_discriminator("add_instruction").to_vec();
+        push_instruction(&mut add, instruction);
+
+        vec![
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new(self.multisig, false),
+                    AccountMeta::new(transaction, false),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                ],
+                data: create,
+            },
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.multisig, false),
+                    AccountMeta::new(transaction, false),
+                    AccountMeta::new(self.instruction_address(&transaction, 1), false),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                ],
+                data: add,
+            },
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.multisig, false),
+                    AccountMeta::new(transaction, false),
+                    AccountMeta::new(*creator, true),
+                ],
+                data: instruction_discriminator("activate_transaction").to_vec(),
+            },
+        ]
+    }
+
+    pub fn approve_instruction(&self, member: &Pubkey, transaction: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(self.multisig, false),
+                AccountMeta::new(*transaction, false),
+                AccountMeta::new(*member, true),
+            ],
+            data: instruction_discriminator("approve_transaction").to_vec(),
+        }
+    }
+
+    /// `execute_instruction` for one stored instruction; the program account
+    /// and the wrapped instruction's accounts follow as remaining accounts
+    pub fn execute_instruction(
         &self,
-        transaction_key: &Pubkey,
-    ) -> Result<String, UpgradeError> {
-        // In production, this would:
-        // 1. Verify threshold is met
-        // 2. Build execute instruction
-        // 3. Execute transaction
-        // 4. Return transaction signature
-        
-        tracing::info!("Executing Squads transaction: {}", transaction_key);
-        
-        // Placeholder: In real implementation
-        // let execute_ix = self.build_execute_instruction(transaction_key)?;
-        // let tx = Transaction::new_signed_with_payer(...);
-        // let sig = self.rpc_client.send_and_confirm_transaction(&tx)?;
-        
-        Ok("execution_signature".to_string())
-    }
-
-    /// Get transaction status from Squads
-    pub async fn get_transaction_status(
+        member: &Pubkey,
+        transaction: &Pubkey,
+        instruction_index: u8,
+        wrapped: &Instruction,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.multisig, false),
+            AccountMeta::new(*transaction, false),
+            AccountMeta::new(self.instruction_address(transaction, instruction_index), false),
+            AccountMeta::new(*member, true),
+            AccountMeta::new_readonly(wrapped.program_id, false),
+        ];
+        accounts.extend(wrapped.accounts.iter().map(|meta| remaining_account(meta.pubkey, meta.is_writable)));
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: instruction_discriminator("execute_instruction").to_vec(),
+        }
+    }
+
+    async fn multisig_account(&self) -> Result<SquadsV3Multisig, UpgradeError> {
+        SquadsV3Multisig::try_from_account_data(&fetch_account_data(&self.rpc_client, &self.multisig).await?)
+    }
+
+    async fn transaction_account(&self, transaction: &Pubkey) -> Result<SquadsV3Transaction, UpgradeError> {
+        SquadsV3Transaction::try_from_account_data(&fetch_account_data(&self.rpc_client, transaction).await?)
+    }
+}
+
+#[async_trait]
+impl MultisigBackend for SquadsV3Backend {
+    fn kind(&self) -> MultisigBackendKind {
+        MultisigBackendKind::SquadsV3
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    fn vault(&self) -> Option<Pubkey> {
+        Some(self.authority_address())
+    }
+
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.authority_address())
+    }
+
+    async fn propose(
         &self,
-        transaction_key: &Pubkey,
-    ) -> Result<SquadsTransactionStatus, UpgradeError> {
-        // In production, query Squads MS program account
-        // to get transaction status, approvals, etc.
-        
-        Ok(SquadsTransactionStatus {
-            key: *transaction_key,
-            status: "pending".to_string(),
-            approvals: vec![],
-            threshold: self.threshold,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        _description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let upgrade = upgrade_instruction(program, buffer, &self.authority_address(), creator);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &upgrade),
         })
     }
 
-    /// Build upgrade instruction for Squads
-    pub fn build_upgrade_instruction(
+    fn wraps_loader_instructions(&self) -> bool {
+        true
+    }
+
+    async fn propose_deploy(
         &self,
-        program_id: &Pubkey,
+        creator: &Pubkey,
+        program: &Pubkey,
         buffer: &Pubkey,
-        upgrade_authority: &Pubkey,
-        program_data: &Pubkey,
-    ) -> Result<Instruction, UpgradeError> {
-        // Build BPF upgradeable loader upgrade instruction
-        // This would be wrapped in a Squads transaction
-        
-        use solana_sdk::instruction::AccountMeta;
-        
-        let accounts = vec![
-            AccountMeta::new(*program_id, false),
-            AccountMeta::new(*buffer, false),
-            AccountMeta::new(*upgrade_authority, true),
-            AccountMeta::new(*program_data, false),
-        ];
-        
-        // BPF Upgradeable Loader Program ID
-        let bpf_upgradeable_loader = Pubkey::from_str(
-            "BPFLoaderUpgradeab1e11111111111111111111111"
-        ).map_err(|_| UpgradeError::InternalError("Invalid BPF loader ID".to_string()))?;
-        
-        Ok(Instruction {
-            program_id: bpf_upgradeable_loader,
-            accounts,
-            data: vec![3, 0, 0, 0], // Upgrade instruction discriminator
+        max_data_len: usize,
+        _description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let deploy = deploy_instruction(program, buffer, &self.authority_address(), max_data_len);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &deploy),
+        })
+    }
+
+    async fn propose_set_authority(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        new_authority: Option<&Pubkey>,
+        _description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let set_authority = set_authority_instruction(program, &self.authority_address(), new_authority);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &set_authority),
+        })
+    }
+
+    async fn approve(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        Ok(vec![self.approve_instruction(member, transaction)])
+    }
+
+    async fn execute(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let state = self.transaction_account(transaction).await?;
+        if state.status != "execute_ready" {
+            return Err(UpgradeError::MultisigError(format!(
+                "Squads transaction {} is {}, not execute_ready",
+                transaction, state.status
+            )));
+        }
+
+        let mut instructions = Vec::new();
+        for index in state.executed_index + 1..=state.instruction_index {
+            let address = self.instruction_address(transaction, index);
+            let wrapped = decode_v3_instruction(&fetch_account_data(&self.rpc_client, &address).await?)?;
+            instructions.push(self.execute_instruction(member, transaction, index, &wrapped));
+        }
+        Ok(instructions)
+    }
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError> {
+        let multisig = self.multisig_account().await?;
+        let state = self.transaction_account(transaction).await?;
+
+        Ok(BackendTransactionStatus {
+            key: transaction.to_string(),
+            executable: state.status == "execute_ready",
+            status: state.status,
+            approvals: state.approved.iter().map(|key| key.to_string()).collect(),
+            threshold: multisig.threshold,
+            votes: None,
         })
     }
+
+    async fn check_health(&self) -> Result<(), UpgradeError> {
+        self.multisig_account().await.map(|_| ())
+    }
+}
+
+/// Squads v4: a `VaultTransaction` PDA stores the compiled message and a
+/// separate `Proposal` PDA collects votes
+pub struct SquadsV4Backend {
+    rpc_client: Arc<AsyncRpcClient>,
+    program_id: Pubkey,
+    multisig: Pubkey,
+    vault_index: u8,
 }
 
+/// The v4 `Multisig` account fields the backend uses
 #[derive(Debug, Clone)]
-pub struct SquadsTransactionStatus {
-    pub key: Pubkey,
+pub struct SquadsV4Multisig {
+    pub threshold: u16,
+    pub time_lock: u32,
+    pub transaction_index: u64,
+    pub members: Vec<Pubkey>,
+}
+
+impl SquadsV4Multisig {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        reader.skip(32 + 32); // create_key, config_authority
+        let threshold = reader.u16()?;
+        let time_lock = reader.u32()?;
+        let transaction_index = reader.u64()?;
+        reader.skip(8); // stale_transaction_index
+        if reader.u8()? == 1 {
+            reader.skip(32); // rent_collector
+        }
+        reader.skip(1); // bump
+        let members = (0..reader.u32()?)
+            .map(|_| {
+                let key = reader.pubkey()?;
+                reader.skip(1); // permissions
+                Ok(key)
+            })
+            .collect::<Result<Vec<_>, UpgradeError>>()?;
+
+        Ok(Self {
+            threshold,
+            time_lock,
+            transaction_index,
+            members,
+        })
+    }
+}
+
+/// The v4 `Proposal` account fields the backend uses
+#[derive(Debug, Clone)]
+pub struct SquadsV4Proposal {
     pub status: String,
-    pub approvals: Vec<Pubkey>,
-    pub threshold: u8,
+    pub approved: Vec<Pubkey>,
+}
+
+impl SquadsV4Proposal {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        reader.skip(32 + 8); // multisig, transaction_index
+        let status = match reader.u8()? {
+            0 => "draft",
+            1 => "active",
+            2 => "rejected",
+            3 => "approved",
+            4 => "executing",
+            5 => "executed",
+            6 => "cancelled",
+            _ => return Err(UpgradeError::MultisigError("Invalid Squads v4 proposal status".to_string())),
+        };
+        if status != "executing" {
+            reader.skip(8); // timestamp
+        }
+        reader.skip(1); // bump
+        let approved = reader.pubkeys()?;
+
+        Ok(Self {
+            status: status.to_string(),
+            approved,
+        })
+    }
+}
+
+/// Accounts of a stored `VaultTransactionMessage`, with their writability
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquadsV4Transaction {
+    pub index: u64,
+    pub account_keys: Vec<AccountMeta>,
+}
+
+impl SquadsV4Transaction {
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, UpgradeError> {
+        let mut reader = AccountReader::anchor(data);
+        reader.skip(32 + 32); // multisig, creator
+        let index = reader.u64()?;
+        reader.skip(1 + 1 + 1); // bump, vault_index, vault_bump
+        reader.bytes()?; // ephemeral_signer_bumps
+        let num_signers = reader.u8()? as usize;
+        let num_writable_signers = reader.u8()? as usize;
+        let num_writable_non_signers = reader.u8()? as usize;
+        let keys = reader.pubkeys()?;
+
+        let account_keys = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let is_writable = if i < num_signers {
+                    i < num_writable_signers
+                } else {
+                    i - num_signers < num_writable_non_signers
+                };
+                remaining_account(key, is_writable)
+            })
+            .collect();
+
+        Ok(Self { index, account_keys })
+    }
+}
+
+/// Squads' compact `TransactionMessage` for `instructions` with `vault` as
+/// payer: u8-length vectors, u16-length instruction data, no lookup tables
+pub fn compile_v4_message(vault: &Pubkey, instructions: &[Instruction]) -> Vec<u8> {
+    let message = Message::new(instructions, Some(vault));
+    let header = message.header;
+    let num_signers = header.num_required_signatures;
+    let num_writable_signers = num_signers - header.num_readonly_signed_accounts;
+    let num_writable_non_signers =
+        message.account_keys.len() as u8 - num_signers - header.num_readonly_unsigned_accounts;
+
+    let mut data = vec![num_signers, num_writable_signers, num_writable_non_signers];
+    data.push(message.account_keys.len() as u8);
+    for key in &message.account_keys {
+        data.extend_from_slice(key.as_ref());
+    }
+    data.push(message.instructions.len() as u8);
+    for instruction in &message.instructions {
+        data.push(instruction.program_id_index);
+        data.push(instruction.accounts.len() as u8);
+        data.extend_from_slice(&instruction.accounts);
+        data.extend_from_slice(&(instruction.data.len() as u16).to_le_bytes());
+        data.extend_from_slice(&instruction.data);
+    }
+    data.push(0); // address_table_lookups
+    data
+}
+
+impl SquadsV4Backend {
+    pub fn new(rpc_client: Arc<AsyncRpcClient>, multisig: Pubkey, vault_index: u8) -> Self {
+        Self {
+            rpc_client,
+            program_id: Pubkey::from_str(SQUADS_V4_PROGRAM_ID).unwrap(),
+            multisig,
+            vault_index,
+        }
+    }
+
+    pub fn vault_address(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"multisig", self.multisig.as_ref(), b"vault", &[self.vault_index]],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn transaction_address(&self, transaction_index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"multisig", self.multisig.as_ref(), b"transaction", &transaction_index.to_le_bytes()],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn proposal_address(&self, transaction_index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"multisig",
+                self.multisig.as_ref(),
+                b"transaction",
+                &transaction_index.to_le_bytes(),
+                b"proposal",
+            ],
+            &self.program_id,
+        )
+        .0
+    }
+
+    /// `vault_transaction_create` and `proposal_create` for a transaction
+    /// running `instruction` from the vault
+    pub fn propose_instructions(
+        &self,
+        creator: &Pubkey,
+        transaction_index: u64,
+        instruction: &Instruction,
+        memo: &str,
+    ) -> Vec<Instruction> {
+        // VaultTransactionCreateArgs { vault_index, ephemeral_signers, transaction_message, memo }
+        let message = compile_v4_message(&self.vault_address(), std::slice::from_ref(instruction));
+        let mut create = instruction_discriminator("vault_transaction_create").to_vec();
+        create.push(self.vault_index);
+        create.push(0);
+        create.extend_from_slice(&(message.len() as u32).to_le_bytes());
+        create.extend_from_slice(&message);
+        create.push(1);
+        push_string(&mut create, memo);
+
+        // ProposalCreateArgs { transaction_index, draft }
+        let mut proposal = instruction_discriminator("proposal_create").to_vec();
+        proposal.extend_from_slice(&transaction_index.to_le_bytes());
+        proposal.push(0);
+
+        vec![
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new(self.multisig, false),
+                    AccountMeta::new(self.transaction_address(transaction_index), false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                ],
+                data: create,
+            },
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(self.multisig, false),
+                    AccountMeta::new(self.proposal_address(transaction_index), false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new(*creator, true),
+                    AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                ],
+                data: proposal,
+            },
+        ]
+    }
+
+    pub fn approve_instruction(&self, member: &Pubkey, transaction_index: u64) -> Instruction {
+        // ProposalVoteArgs { memo: None }
+        let mut data = instruction_discriminator("proposal_approve").to_vec();
+        data.push(0);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.multisig, false),
+                AccountMeta::new(*member, true),
+                AccountMeta::new(self.proposal_address(transaction_index), false),
+            ],
+            data,
+        }
+    }
+
+    pub fn execute_instruction(&self, member: &Pubkey, stored: &SquadsV4Transaction) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.multisig, false),
+            AccountMeta::new(self.proposal_address(stored.index), false),
+            AccountMeta::new_readonly(self.transaction_address(stored.index), false),
+            AccountMeta::new_readonly(*member, true),
+        ];
+        accounts.extend(stored.account_keys.iter().cloned());
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: instruction_discriminator("vault_transaction_execute").to_vec(),
+        }
+    }
+
+    async fn multisig_account(&self) -> Result<SquadsV4Multisig, UpgradeError> {
+        SquadsV4Multisig::try_from_account_data(&fetch_account_data(&self.rpc_client, &self.multisig).await?)
+    }
+
+    async fn transaction_account(&self, transaction: &Pubkey) -> Result<SquadsV4Transaction, UpgradeError> {
+        SquadsV4Transaction::try_from_account_data(&fetch_account_data(&self.rpc_client, transaction).await?)
+    }
 }
 
+#[async_trait]
+impl MultisigBackend for SquadsV4Backend {
+    fn kind(&self) -> MultisigBackendKind {
+        MultisigBackendKind::SquadsV4
+    }
+
+    fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    fn vault(&self) -> Option<Pubkey> {
+        Some(self.vault_address())
+    }
+
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.vault_address())
+    }
+
+    async fn propose(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let upgrade = upgrade_instruction(program, buffer, &self.vault_address(), creator);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &upgrade, description),
+        })
+    }
+
+    fn wraps_loader_instructions(&self) -> bool {
+        true
+    }
+
+    async fn propose_deploy(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        max_data_len: usize,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let deploy = deploy_instruction(program, buffer, &self.vault_address(), max_data_len);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &deploy, description),
+        })
+    }
+
+    async fn propose_set_authority(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        new_authority: Option<&Pubkey>,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        let transaction_index = self.multisig_account().await?.transaction_index + 1;
+        let set_authority = set_authority_instruction(program, &self.vault_address(), new_authority);
+
+        Ok(ProposedTransaction {
+            key: self.transaction_address(transaction_index),
+            instructions: self.propose_instructions(creator, transaction_index, &set_authority, description),
+        })
+    }
+
+    async fn approve(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let stored = self.transaction_account(transaction).await?;
+        Ok(vec![self.approve_instruction(member, stored.index)])
+    }
+
+    async fn execute(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let stored = self.transaction_account(transaction).await?;
+        Ok(vec![self.execute_instruction(member, &stored)])
+    }
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError> {
+        let multisig = self.multisig_account().await?;
+        let stored = self.transaction_account(transaction).await?;
+        let proposal = SquadsV4Proposal::try_from_account_data(
+            &fetch_account_data(&self.rpc_client, &self.proposal_address(stored.index)).await?,
+        )?;
+
+        Ok(BackendTransactionStatus {
+            key: transaction.to_string(),
+            // The v4 time lock is enforced on chain; callers may still see a rejection
+            executable: proposal.status == "approved",
+            status: proposal.status,
+            approvals: proposal.approved.iter().map(|key| key.to_string()).collect(),
+            threshold: multisig.threshold,
+            votes: None,
+        })
+    }
+
+    async fn check_health(&self) -> Result<(), UpgradeError> {
+        self.multisig_account().await.map(|_| ())
+    }
+}
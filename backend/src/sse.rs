@@ -0,0 +1,83 @@
+use crate::migration::{Migration, MigrationProgress};
+use crate::websocket::Event;
+use axum::response::sse::Event as SseEvent;
+use futures_util::stream::{self, Stream};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+struct ProgressStream {
+    receiver: broadcast::Receiver<Event>,
+    migrations: Arc<dyn Migration>,
+    migration_id: String,
+    initial: Option<MigrationProgress>,
+    finished: bool,
+}
+
+/// Progress snapshots for one migration as Server-Sent Events.
+///
+/// Starts with the current snapshot, then follows the `migration_progress`
+/// notifications that also feed the WebSocket channel, and ends once the
+/// migration completes, fails or is rolled back. `receiver` must be subscribed before `initial`
+/// is read so no update falls between the two. If the stream lags behind the
+/// broadcast channel, the missed updates are replaced by a fresh snapshot.
+pub fn migration_progress_stream(
+    receiver: broadcast::Receiver<Event>,
+    migrations: Arc<dyn Migration>,
+    initial: MigrationProgress,
+) -> impl Stream<Item = Result<SseEvent, Infallible>> {
+    let state = ProgressStream {
+        receiver,
+        migrations,
+        migration_id: initial.migration_id.clone(),
+        initial: Some(initial),
+        finished: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+
+        if let Some(progress) = state.initial.take() {
+            state.finished = progress.is_finished();
+            return Some((Ok(progress_event(&progress.snapshot(), None)), state));
+        }
+
+        loop {
+            match state.receiver.recv().await {
+                Ok(event) => {
+                    if event.event_type != "migration_progress"
+                        || event.proposal_id.as_deref() != Some(state.migration_id.as_str())
+                    {
+                        continue;
+                    }
+                    state.finished = is_finished(&event.data);
+                    return Some((Ok(progress_event(&event.data, event.seq)), state));
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("SSE client lagged behind by {} notifications", n);
+                    let progress = state.migrations.get_migration(&state.migration_id).await.ok()?;
+                    state.finished = progress.is_finished();
+                    return Some((Ok(progress_event(&progress.snapshot(), None)), state));
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+fn progress_event(snapshot: &serde_json::Value, seq: Option<i64>) -> SseEvent {
+    let event = SseEvent::default().event("progress").data(snapshot.to_string());
+    match seq {
+        Some(seq) => event.id(seq.to_string()),
+        None => event,
+    }
+}
+
+fn is_finished(snapshot: &serde_json::Value) -> bool {
+    matches!(
+        snapshot["status"].as_str(),
+        Some("Completed") | Some("Failed") | Some("RolledBack")
+    )
+}
@@ -0,0 +1,203 @@
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Where a rendered notification is delivered
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Websocket,
+    Email,
+    Slack,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Websocket => "websocket",
+            Channel::Email => "email",
+            Channel::Slack => "slack",
+        }
+    }
+}
+
+/// Template key used when a notification type has no channel-specific text
+const DEFAULT_KEY: &str = "default";
+
+/// locale -> notification type -> channel (or `default`) -> template
+pub type TemplateTable = HashMap<String, HashMap<String, HashMap<String, String>>>;
+
+/// Notification wording, per locale, notification type and channel.
+///
+/// Templates interpolate `{{name}}` from the notification's data, plus
+/// `proposal_id` and `type`. Nested fields use dots (`{{proposal.program}}`)
+/// and numbers take a precision (`{{progress_percent:.2}}`). Unknown
+/// variables are left in place so a typo shows up in the message.
+#[derive(Debug, Clone)]
+pub struct NotificationTemplates {
+    default_locale: String,
+    templates: TemplateTable,
+}
+
+impl NotificationTemplates {
+    /// English wording the service ships with
+    pub fn builtin() -> Self {
+        let english = [
+            ("proposal_created", "New upgrade proposal created"),
+            ("proposal_approved", "Proposal approved: {{approvals}}/{{threshold}}"),
+            ("timelock_expired", "Timelock expired - upgrade can now be executed"),
+            ("timelock_tick", "Timelock ends in {{remaining_seconds}}s"),
+            ("upgrade_executed", "Upgrade executed successfully"),
+            ("upgrade_failed", "Upgrade failed: {{error}}"),
+            ("migration_progress", "Migration progress: {{progress_percent:.2}}%"),
+            ("rollback_initiated", "Rollback of {{program}} initiated ({{mode}})"),
+            ("buffer_upload_progress", "Buffer upload progress: {{progress_percent:.2}}%"),
+            ("buffer_detected", "Vault-owned buffer {{buffer}} detected without a proposal"),
+            ("proposal_frozen", "Proposal frozen: {{finding}}"),
+            ("proposal_unfrozen", "Proposal unfrozen: {{resolution}}"),
+            (
+                "approval_reminder",
+                "Proposal {{proposal_id}} needs your approval ({{approvals}}/{{threshold}}), \
+                 deadline in {{hours_remaining}}h",
+            ),
+            (
+                "upgrade_announced",
+                "{{title}}: execution window {{window_start_utc}} to {{window_end_utc}}. \
+                 Affected markets: {{affected_markets}}. Migration: {{migration_estimate}}.",
+            ),
+        ];
+
+        let types = english
+            .iter()
+            .map(|(event_type, text)| {
+                let channels = HashMap::from([(DEFAULT_KEY.to_string(), text.to_string())]);
+                (event_type.to_string(), channels)
+            })
+            .collect();
+
+        Self {
+            default_locale: "en".to_string(),
+            templates: HashMap::from([("en".to_string(), types)]),
+        }
+    }
+
+    /// Built-in templates, overridden by the JSON file at
+    /// `NOTIFICATION_TEMPLATES_PATH`, rendered in `NOTIFICATION_LOCALE`
+    /// (default `en`)
+    pub fn from_env() -> Result<Self, UpgradeError> {
+        let mut templates = Self::builtin();
+
+        if let Ok(path) = std::env::var("NOTIFICATION_TEMPLATES_PATH") {
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                UpgradeError::InvalidRequest(format!("Failed to read notification templates {}: {}", path, e))
+            })?;
+            let table: TemplateTable = serde_json::from_str(&contents).map_err(|e| {
+                UpgradeError::InvalidRequest(format!("Invalid notification templates {}: {}", path, e))
+            })?;
+            templates.merge(table);
+        }
+
+        if let Ok(locale) = std::env::var("NOTIFICATION_LOCALE") {
+            templates = templates.with_default_locale(&locale);
+        }
+
+        Ok(templates)
+    }
+
+    pub fn with_default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = locale.to_string();
+        self
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    /// Add or replace individual templates, keeping everything not mentioned
+    pub fn merge(&mut self, table: TemplateTable) {
+        for (locale, types) in table {
+            let existing = self.templates.entry(locale).or_default();
+            for (event_type, channels) in types {
+                existing.entry(event_type).or_default().extend(channels);
+            }
+        }
+    }
+
+    pub fn locales(&self) -> Vec<String> {
+        let mut locales: Vec<String> = self.templates.keys().cloned().collect();
+        locales.sort();
+        locales
+    }
+
+    /// The template for a notification, falling back from the channel to the
+    /// type's default text, then from `locale` to the default locale and `en`
+    pub fn template(&self, event_type: &str, channel: Channel, locale: Option<&str>) -> Option<&str> {
+        let locales = [locale, Some(self.default_locale.as_str()), Some("en")];
+        locales.into_iter().flatten().find_map(|locale| {
+            let channels = self.templates.get(locale)?.get(event_type)?;
+            channels
+                .get(channel.as_str())
+                .or_else(|| channels.get(DEFAULT_KEY))
+                .map(String::as_str)
+        })
+    }
+
+    /// Render a notification's text; `None` when no template covers its type
+    pub fn render(
+        &self,
+        event_type: &str,
+        channel: Channel,
+        locale: Option<&str>,
+        proposal_id: Option<&str>,
+        data: &Value,
+    ) -> Option<String> {
+        let template = self.template(event_type, channel, locale)?;
+
+        let mut vars = match data {
+            Value::Object(fields) => fields.clone(),
+            _ => serde_json::Map::new(),
+        };
+        vars.insert("type".to_string(), Value::from(event_type));
+        if let Some(proposal_id) = proposal_id {
+            vars.insert("proposal_id".to_string(), Value::from(proposal_id));
+        }
+
+        Some(interpolate(template, &Value::Object(vars)))
+    }
+}
+
+/// Replace every `{{path}}` or `{{path:.N}}` in `template` with its value in `vars`
+pub fn interpolate(template: &str, vars: &Value) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+
+        let placeholder = &rest[start..start + 2 + len + 2];
+        let (path, precision) = match rest[start + 2..start + 2 + len].trim().split_once(":.") {
+            Some((path, digits)) => (path.trim(), digits.trim().parse::<usize>().ok()),
+            None => (rest[start + 2..start + 2 + len].trim(), None),
+        };
+
+        let value = path.split('.').try_fold(vars, |value, key| value.get(key));
+        match (value, precision) {
+            (Some(Value::Number(n)), Some(precision)) => {
+                output.push_str(&format!("{:.*}", precision, n.as_f64().unwrap_or(0.0)));
+            }
+            (Some(Value::String(s)), _) => output.push_str(s),
+            (Some(Value::Null), _) => {}
+            (Some(value), _) => output.push_str(&value.to_string()),
+            (None, _) => output.push_str(placeholder),
+        }
+
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,316 @@
+//! Fixtures for tests, built with the `test-utils` feature.
+//!
+//! Builders start from a realistic state (an upgrade proposed at
+//! `PROPOSED_AT` with the default timelock, a batch migration in progress)
+//! so a test only spells out what it is about. `TestServices` wires the
+//! proposal manager to an off-chain multisig, timelock and program builder.
+
+use crate::migration::{AccountTypeProgress, MigrationProgress, MigrationStatus, MigrationStrategy};
+use crate::multisig::{MultisigCoordinator, MultisigProposal, MultisigStatus};
+use crate::program_builder::ProgramBuilder;
+use crate::proposal::{
+    AuthorityChange, ProgramDeployment, Proposal, ProposalManager, ProposalStatus, DEFAULT_TIMELOCK_DURATION,
+};
+use crate::timelock::TimelockManager;
+use std::sync::Arc;
+
+pub const PROPOSED_AT: i64 = 1_700_000_000;
+pub const PROGRAM: &str = "program_id";
+pub const BUFFER: &str = "Buffer1111111111111111111111111111111111111";
+
+/// Members of the default test multisig, with a threshold of `THRESHOLD`
+pub const MEMBERS: [&str; 5] = ["member1", "member2", "member3", "member4", "member5"];
+pub const THRESHOLD: u8 = 3;
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Builds a `Proposal`, by default a `Proposed` upgrade with no approvals
+pub struct ProposalBuilder {
+    proposal: Proposal,
+}
+
+impl ProposalBuilder {
+    pub fn new(id: &str) -> Self {
+        Self {
+            proposal: Proposal {
+                id: id.to_string(),
+                proposer: "multisig".to_string(),
+                program: PROGRAM.to_string(),
+                new_buffer: BUFFER.to_string(),
+                description: format!("Upgrade {}", id),
+                proposed_at: PROPOSED_AT,
+                timelock_until: PROPOSED_AT + DEFAULT_TIMELOCK_DURATION,
+                approvals: Vec::new(),
+                approval_threshold: THRESHOLD,
+                status: ProposalStatus::Proposed,
+                executed_at: None,
+                source: None,
+                publish_idl: false,
+                cancellation: None,
+                risk_tier: None,
+                attachments: Vec::new(),
+                depends_on: Vec::new(),
+                supersedes: Vec::new(),
+                freeze: None,
+                deployment: None,
+                authority_change: None,
+            },
+        }
+    }
+
+    pub fn status(mut self, status: ProposalStatus) -> Self {
+        self.proposal.status = status;
+        self
+    }
+
+    pub fn approvals(mut self, members: &[&str]) -> Self {
+        self.proposal.approvals = strings(members);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.proposal.approval_threshold = threshold;
+        self
+    }
+
+    /// Moves the timelock along with the proposal time
+    pub fn proposed_at(mut self, proposed_at: i64) -> Self {
+        self.proposal.timelock_until += proposed_at - self.proposal.proposed_at;
+        self.proposal.proposed_at = proposed_at;
+        self
+    }
+
+    pub fn timelock_until(mut self, timelock_until: i64) -> Self {
+        self.proposal.timelock_until = timelock_until;
+        self
+    }
+
+    pub fn program(mut self, program: &str) -> Self {
+        self.proposal.program = program.to_string();
+        self
+    }
+
+    pub fn buffer(mut self, buffer: &str) -> Self {
+        self.proposal.new_buffer = buffer.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.proposal.description = description.to_string();
+        self
+    }
+
+    pub fn depends_on(mut self, proposals: &[&str]) -> Self {
+        self.proposal.depends_on = strings(proposals);
+        self
+    }
+
+    /// Executed at `executed_at`
+    pub fn executed(mut self, executed_at: i64) -> Self {
+        self.proposal.status = ProposalStatus::Executed;
+        self.proposal.executed_at = Some(executed_at);
+        self
+    }
+
+    pub fn deployment(mut self, deployment: ProgramDeployment) -> Self {
+        self.proposal.deployment = Some(deployment);
+        self
+    }
+
+    pub fn authority_change(mut self, change: AuthorityChange) -> Self {
+        self.proposal.authority_change = Some(change);
+        self
+    }
+
+    pub fn build(self) -> Proposal {
+        self.proposal
+    }
+}
+
+/// Builds a `MultisigProposal`, by default `Pending` with no approvals
+pub struct MultisigProposalBuilder {
+    proposal: MultisigProposal,
+}
+
+impl MultisigProposalBuilder {
+    pub fn new(id: &str) -> Self {
+        Self {
+            proposal: MultisigProposal {
+                id: id.to_string(),
+                instruction: Vec::new(),
+                description: format!("Upgrade {}", id),
+                timelock: PROPOSED_AT + DEFAULT_TIMELOCK_DURATION,
+                approvals: Vec::new(),
+                threshold: THRESHOLD,
+                status: MultisigStatus::Pending,
+                backend_transaction: None,
+            },
+        }
+    }
+
+    pub fn approvals(mut self, members: &[&str]) -> Self {
+        self.proposal.approvals = strings(members);
+        self
+    }
+
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.proposal.threshold = threshold;
+        self
+    }
+
+    pub fn status(mut self, status: MultisigStatus) -> Self {
+        self.proposal.status = status;
+        self
+    }
+
+    pub fn timelock(mut self, timelock: i64) -> Self {
+        self.proposal.timelock = timelock;
+        self
+    }
+
+    pub fn backend_transaction(mut self, transaction: &str) -> Self {
+        self.proposal.backend_transaction = Some(transaction.to_string());
+        self
+    }
+
+    pub fn build(self) -> MultisigProposal {
+        self.proposal
+    }
+}
+
+/// Builds a `MigrationProgress`, by default a batch migration of 100
+/// accounts that has just started
+pub struct MigrationProgressBuilder {
+    progress: MigrationProgress,
+}
+
+impl MigrationProgressBuilder {
+    pub fn new(migration_id: &str) -> Self {
+        Self {
+            progress: MigrationProgress {
+                migration_id: migration_id.to_string(),
+                strategy: MigrationStrategy::Batch,
+                total_accounts: 100,
+                migrated_accounts: 0,
+                failed_accounts: 0,
+                status: MigrationStatus::InProgress,
+                started_at: PROPOSED_AT,
+                completed_at: None,
+                transactions_sent: 0,
+                compute_units_consumed: 0,
+                fees_paid_lamports: 0,
+                account_types: Vec::new(),
+                sample_verification: None,
+                concurrency: 4,
+                throughput: Vec::new(),
+            },
+        }
+    }
+
+    pub fn strategy(mut self, strategy: MigrationStrategy) -> Self {
+        self.progress.strategy = strategy;
+        self
+    }
+
+    /// Account counts; one transaction is counted per migrated or failed account
+    pub fn accounts(mut self, total: usize, migrated: usize, failed: usize) -> Self {
+        self.progress.total_accounts = total;
+        self.progress.migrated_accounts = migrated;
+        self.progress.failed_accounts = failed;
+        self.progress.transactions_sent = (migrated + failed) as u64;
+        self
+    }
+
+    pub fn status(mut self, status: MigrationStatus) -> Self {
+        self.progress.status = status;
+        self
+    }
+
+    /// Completed at `completed_at`, with every account migrated
+    pub fn completed(mut self, completed_at: i64) -> Self {
+        self.progress.migrated_accounts = self.progress.total_accounts.saturating_sub(self.progress.failed_accounts);
+        self.progress.status = MigrationStatus::Completed;
+        self.progress.completed_at = Some(completed_at);
+        self
+    }
+
+    /// Add an account type that has not started, after `depends_on`
+    pub fn account_type(mut self, account_type: &str, depends_on: &[&str], total: usize) -> Self {
+        self.progress.account_types.push(AccountTypeProgress {
+            account_type: account_type.to_string(),
+            depends_on: strings(depends_on),
+            total_accounts: total,
+            migrated_accounts: 0,
+            failed_accounts: 0,
+            status: MigrationStatus::NotStarted,
+            started_at: None,
+            completed_at: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> MigrationProgress {
+        self.progress
+    }
+}
+
+/// A multisig coordinator with `members` and `threshold` that only tracks
+/// proposals off chain. Panics on an invalid threshold.
+pub async fn mock_multisig(members: &[&str], threshold: u8) -> Arc<MultisigCoordinator> {
+    let multisig = MultisigCoordinator::new()
+        .await
+        .and_then(|multisig| multisig.with_members(strings(members), threshold))
+        .expect("test multisig");
+    Arc::new(multisig)
+}
+
+/// A timelock manager on system time, without a database
+pub async fn mock_timelock() -> Arc<TimelockManager> {
+    Arc::new(TimelockManager::new().await.expect("test timelock"))
+}
+
+/// A program builder without a fee payer, so it never uploads
+pub async fn mock_program_builder() -> Arc<ProgramBuilder> {
+    Arc::new(ProgramBuilder::new().await.expect("test program builder"))
+}
+
+/// A proposal manager and the services behind it
+pub struct TestServices {
+    pub multisig: Arc<MultisigCoordinator>,
+    pub timelock: Arc<TimelockManager>,
+    pub builder: Arc<ProgramBuilder>,
+    pub proposals: Arc<ProposalManager>,
+}
+
+impl TestServices {
+    /// The default multisig: `MEMBERS` with a threshold of `THRESHOLD`
+    pub async fn new() -> Self {
+        Self::with_members(&MEMBERS, THRESHOLD).await
+    }
+
+    pub async fn with_members(members: &[&str], threshold: u8) -> Self {
+        let multisig = mock_multisig(members, threshold).await;
+        let timelock = mock_timelock().await;
+        let builder = mock_program_builder().await;
+        let proposals = ProposalManager::new(multisig.clone(), timelock.clone(), builder.clone())
+            .await
+            .expect("test proposal manager");
+
+        Self {
+            multisig,
+            timelock,
+            builder,
+            proposals: Arc::new(proposals),
+        }
+    }
+
+    /// Propose upgrading to `BUFFER`; returns the proposal id
+    pub async fn propose(&self, description: &str) -> String {
+        self.proposals
+            .propose_upgrade(BUFFER.parse().unwrap(), description.to_string())
+            .await
+            .expect("test proposal")
+    }
+}
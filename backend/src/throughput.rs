@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+/// Throughput points kept per migration; older points are dropped first
+pub const MAX_THROUGHPUT_POINTS: usize = 1_000;
+
+/// Whether an RPC error is the provider throttling us
+pub fn is_rate_limit_error(message: &str) -> bool {
+    message.contains("429") || message.to_ascii_lowercase().contains("too many requests")
+}
+
+/// Bounds and targets of adaptive batch migration concurrency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputConfig {
+    /// `MIGRATION_MIN_CONCURRENCY` (default 1)
+    pub min_concurrency: usize,
+    /// `MIGRATION_MAX_CONCURRENCY` (default 32)
+    pub max_concurrency: usize,
+    /// `MIGRATION_INITIAL_CONCURRENCY` (default 4)
+    pub initial_concurrency: usize,
+    /// `MIGRATION_TARGET_CONFIRMATION_MS`: average confirmation time above
+    /// which concurrency is scaled down (default 2000)
+    pub target_confirmation_ms: u64,
+    /// `MIGRATION_MAX_RPC_ERROR_PERCENT` of a batch allowed to fail before
+    /// concurrency is halved (default 5)
+    pub max_error_percent: f64,
+}
+
+impl Default for ThroughputConfig {
+    fn default() -> Self {
+        Self {
+            min_concurrency: 1,
+            max_concurrency: 32,
+            initial_concurrency: 4,
+            target_confirmation_ms: 2_000,
+            max_error_percent: 5.0,
+        }
+    }
+}
+
+impl ThroughputConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |key: &str| std::env::var(key).ok();
+
+        let min_concurrency = var("MIGRATION_MIN_CONCURRENCY")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.min_concurrency)
+            .max(1);
+        let max_concurrency = var("MIGRATION_MAX_CONCURRENCY")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.max_concurrency)
+            .max(min_concurrency);
+
+        Self {
+            min_concurrency,
+            max_concurrency,
+            initial_concurrency: var("MIGRATION_INITIAL_CONCURRENCY")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.initial_concurrency)
+                .clamp(min_concurrency, max_concurrency),
+            target_confirmation_ms: var("MIGRATION_TARGET_CONFIRMATION_MS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.target_confirmation_ms),
+            max_error_percent: var("MIGRATION_MAX_RPC_ERROR_PERCENT")
+                .and_then(|v| v.parse().ok())
+                .map(|v: f64| v.clamp(0.0, 100.0))
+                .unwrap_or(defaults.max_error_percent),
+        }
+    }
+}
+
+/// What one parallel batch of account migrations ran into
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchOutcome {
+    pub accounts: usize,
+    /// Accounts that failed for any reason, including rate limits
+    pub errors: usize,
+    /// Requests the RPC provider throttled (HTTP 429)
+    pub rate_limited: usize,
+    pub total_confirmation_ms: u64,
+    /// Wall time of the whole batch
+    pub elapsed_ms: u64,
+}
+
+impl BatchOutcome {
+    pub fn error_percent(&self) -> f64 {
+        if self.accounts == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.accounts as f64 * 100.0
+    }
+
+    pub fn avg_confirmation_ms(&self) -> u64 {
+        self.total_confirmation_ms / self.accounts.max(1) as u64
+    }
+
+    /// Accounts that migrated per second of wall time
+    pub fn accounts_per_second(&self) -> f64 {
+        if self.elapsed_ms == 0 {
+            return 0.0;
+        }
+        (self.accounts - self.errors) as f64 * 1_000.0 / self.elapsed_ms as f64
+    }
+}
+
+/// One batch on a migration's throughput curve
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ThroughputPoint {
+    pub at: i64,
+    /// Accounts migrated in parallel in this batch
+    pub concurrency: usize,
+    pub accounts: usize,
+    pub accounts_per_second: f64,
+    pub error_percent: f64,
+    pub rate_limited: usize,
+    pub avg_confirmation_ms: u64,
+}
+
+/// Picks how many accounts to migrate in parallel from how the last batch
+/// went: additive increase while the RPC keeps up, halving on throttling or
+/// errors, and a gentler step down when confirmations slow past the target.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyController {
+    config: ThroughputConfig,
+    current: usize,
+}
+
+impl ConcurrencyController {
+    pub fn new(config: ThroughputConfig) -> Self {
+        Self {
+            current: config.initial_concurrency.clamp(config.min_concurrency, config.max_concurrency),
+            config,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Record a batch run at the current concurrency and return the next one
+    pub fn observe(&mut self, outcome: &BatchOutcome) -> usize {
+        let next = if outcome.rate_limited > 0 || outcome.error_percent() > self.config.max_error_percent {
+            self.current / 2
+        } else if outcome.avg_confirmation_ms() > self.config.target_confirmation_ms {
+            self.current - (self.current / 4).max(1)
+        } else if outcome.accounts >= self.current {
+            // Only a full batch says anything about running more in parallel
+            self.current + 1
+        } else {
+            self.current
+        };
+        self.current = next.clamp(self.config.min_concurrency, self.config.max_concurrency);
+        self.current
+    }
+}
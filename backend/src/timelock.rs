@@ -1,24 +1,51 @@
+use crate::chain_clock::ChainClock;
+use crate::database::Database;
 use crate::error::UpgradeError;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Timelock deadlines are chain timestamps, compared against `ChainClock`
+/// rather than system time so the backend agrees with the program
 pub struct TimelockManager {
     timelocks: Arc<Mutex<HashMap<String, i64>>>,
+    clock: Arc<ChainClock>,
+    database: Option<Arc<Database>>,
 }
 
 impl TimelockManager {
     pub async fn new() -> Result<Self, UpgradeError> {
         Ok(Self {
             timelocks: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(ChainClock::system()),
+            database: None,
         })
     }
 
+    pub fn with_clock(mut self, clock: Arc<ChainClock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Fall back to `upgrade_proposals` for timelocks set by other
+    /// instances. Deadlines never change once set, so they are cached as read.
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Current chain time, corrected for the last measured drift
+    pub fn now(&self) -> i64 {
+        self.clock.now()
+    }
+
+    /// System time minus chain time at the last clock sample
+    pub fn drift_seconds(&self) -> Option<i64> {
+        self.clock.drift_seconds()
+    }
+
     pub async fn set_timelock(&self, proposal_id: String, duration_seconds: i64) -> Result<(), UpgradeError> {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = self.now();
         let timelock_end = now + duration_seconds;
 
         let mut timelocks = self.timelocks.lock().await;
@@ -28,28 +55,29 @@ impl TimelockManager {
     }
 
     pub async fn get_timelock_end(&self, proposal_id: &str) -> Result<i64, UpgradeError> {
-        let timelocks = self.timelocks.lock().await;
-        timelocks
-            .get(proposal_id)
-            .copied()
-            .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))
+        if let Some(timelock_end) = self.timelocks.lock().await.get(proposal_id).copied() {
+            return Ok(timelock_end);
+        }
+
+        let timelock_end = match &self.database {
+            Some(database) => database.get_timelock_until(proposal_id).await?,
+            None => None,
+        }
+        .ok_or_else(|| UpgradeError::ProposalNotFound(proposal_id.to_string()))?;
+        self.timelocks.lock().await.insert(proposal_id.to_string(), timelock_end);
+
+        Ok(timelock_end)
     }
 
     pub async fn is_timelock_expired(&self, proposal_id: &str) -> Result<bool, UpgradeError> {
         let timelock_end = self.get_timelock_end(proposal_id).await?;
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = self.now();
         Ok(now >= timelock_end)
     }
 
     pub async fn get_remaining_time(&self, proposal_id: &str) -> Result<i64, UpgradeError> {
         let timelock_end = self.get_timelock_end(proposal_id).await?;
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = self.now();
         let remaining = timelock_end - now;
         Ok(remaining.max(0))
     }
@@ -60,10 +88,7 @@ impl TimelockManager {
             tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
 
             let timelocks = self.timelocks.lock().await;
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64;
+            let now = self.now();
 
             for (proposal_id, timelock_end) in timelocks.iter() {
                 let remaining = timelock_end - now;
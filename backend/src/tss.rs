@@ -0,0 +1,397 @@
+use crate::error::UpgradeError;
+use crate::multisig_backend::{BackendTransactionStatus, MultisigBackend, MultisigBackendKind, ProposedTransaction};
+use crate::signer::{SharedSigner, TransactionSigner};
+use crate::squads::upgrade_instruction;
+use async_trait::async_trait;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Whether a coordinator error is worth another attempt: the coordinator
+/// could not be reached (`None`), timed out, is failing, or has too few
+/// parties online for a round yet (409)
+pub fn is_retryable_status(status: Option<u16>) -> bool {
+    match status {
+        None => true,
+        Some(status) => status == 408 || status == 409 || status >= 500,
+    }
+}
+
+/// A threshold key as the coordinator describes it
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct TssGroupKey {
+    pub parties: Vec<String>,
+    pub threshold: u16,
+}
+
+/// A signing session: the upgrade it authorizes and the parties that joined it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TssSession {
+    pub session: String,
+    pub program: String,
+    pub buffer: String,
+    /// `open`, `signed` or `cancelled`
+    pub status: String,
+    /// Parties that joined the session, i.e. approved the upgrade
+    pub participants: Vec<String>,
+    pub threshold: u16,
+}
+
+impl TssSession {
+    /// Enough parties joined for a signing round
+    pub fn executable(&self) -> bool {
+        self.status == "open" && self.participants.len() >= self.threshold as usize
+    }
+
+    pub fn to_status(&self) -> BackendTransactionStatus {
+        BackendTransactionStatus {
+            key: self.session.clone(),
+            status: self.status.clone(),
+            approvals: self.participants.clone(),
+            threshold: self.threshold,
+            votes: None,
+            executable: self.executable(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TssSignResponse {
+    /// Base58 aggregate ed25519 signature
+    signature: String,
+    /// Parties that took part in the round
+    #[serde(default)]
+    signers: Vec<String>,
+}
+
+#[derive(Debug)]
+struct CoordinatorError {
+    status: Option<u16>,
+    message: String,
+}
+
+impl From<CoordinatorError> for UpgradeError {
+    fn from(e: CoordinatorError) -> Self {
+        UpgradeError::MultisigError(e.message)
+    }
+}
+
+/// HTTP client for the threshold-ed25519 coordinator. Requests go to the
+/// first coordinator and fail over to the next ones in order while a
+/// coordinator is unreachable or answering with a server error.
+#[derive(Clone)]
+struct CoordinatorClient {
+    http: reqwest::Client,
+    urls: Vec<String>,
+    auth_token: Option<String>,
+    timeout: Duration,
+}
+
+impl CoordinatorClient {
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<T, CoordinatorError> {
+        let mut last_error = CoordinatorError {
+            status: None,
+            message: "No TSS coordinator configured".to_string(),
+        };
+
+        for url in &self.urls {
+            let mut request = self
+                .http
+                .request(method.clone(), format!("{}{}", url, path))
+                .timeout(self.timeout);
+            if let Some(token) = &self.auth_token {
+                request = request.bearer_auth(token);
+            }
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::warn!("TSS coordinator {} unreachable: {}", url, e);
+                    last_error = CoordinatorError {
+                        status: None,
+                        message: format!("TSS coordinator {} unreachable: {}", url, e),
+                    };
+                    continue;
+                }
+            };
+
+            let status = response.status();
+            if status.is_success() {
+                return response.json::<T>().await.map_err(|e| CoordinatorError {
+                    status: None,
+                    message: format!("Invalid TSS coordinator response: {}", e),
+                });
+            }
+
+            let error = CoordinatorError {
+                status: Some(status.as_u16()),
+                message: format!(
+                    "TSS coordinator {} returned {}: {}",
+                    url,
+                    status,
+                    response.text().await.unwrap_or_default()
+                ),
+            };
+            if !status.is_server_error() {
+                return Err(error);
+            }
+            tracing::warn!("{}", error.message);
+            last_error = error;
+        }
+
+        Err(last_error)
+    }
+}
+
+/// Upgrade authority held as a threshold ed25519 key by an MPC signing
+/// service, for teams that prefer MPC over an on-chain multisig. The
+/// authority is an ordinary key to the chain, so nothing is stored on chain
+/// before execution:
+///
+/// - proposing opens a signing session on the coordinator, bound to the
+///   program and buffer; its id takes the place of a transaction account
+/// - parties approve by joining the session from their own devices, and the
+///   session's participants are the proposal's approvals
+/// - executing sends the loader `Upgrade` signed by the group key, whose
+///   signature comes from a signing round among the participants
+///
+/// The coordinator is expected to serve `GET /keys/{key}`, `POST /sessions`,
+/// `GET /sessions/{id}` and `POST /sessions/{id}/sign`, and to refuse rounds
+/// for messages other than the session's upgrade.
+pub struct TssBackend {
+    client: CoordinatorClient,
+    group_key: Pubkey,
+    sign_attempts: u32,
+}
+
+impl TssBackend {
+    /// `coordinator_urls` lists the primary coordinator first, then fallbacks
+    pub fn new(coordinator_urls: Vec<String>, group_key: Pubkey, auth_token: Option<String>) -> Self {
+        Self {
+            client: CoordinatorClient {
+                http: reqwest::Client::new(),
+                urls: coordinator_urls
+                    .into_iter()
+                    .map(|url| url.trim_end_matches('/').to_string())
+                    .collect(),
+                auth_token,
+                timeout: Duration::from_secs(20),
+            },
+            group_key,
+            sign_attempts: 3,
+        }
+    }
+
+    /// Signing rounds to attempt before failing the execution (default 3)
+    pub fn with_sign_attempts(mut self, sign_attempts: u32) -> Self {
+        self.sign_attempts = sign_attempts.max(1);
+        self
+    }
+
+    /// Per-request timeout, which bounds each signing round (default 20s)
+    pub fn with_round_timeout(mut self, timeout: Duration) -> Self {
+        self.client.timeout = timeout;
+        self
+    }
+
+    pub fn group_key(&self) -> Pubkey {
+        self.group_key
+    }
+
+    pub async fn group(&self) -> Result<TssGroupKey, UpgradeError> {
+        Ok(self
+            .client
+            .request(reqwest::Method::GET, &format!("/keys/{}", self.group_key), None)
+            .await?)
+    }
+
+    pub async fn session(&self, session: &Pubkey) -> Result<TssSession, UpgradeError> {
+        Ok(self
+            .client
+            .request(reqwest::Method::GET, &format!("/sessions/{}", session), None)
+            .await?)
+    }
+}
+
+#[async_trait]
+impl MultisigBackend for TssBackend {
+    fn kind(&self) -> MultisigBackendKind {
+        MultisigBackendKind::Tss
+    }
+
+    /// No multisig program is involved; the group key signs the loader directly
+    fn program_id(&self) -> Pubkey {
+        bpf_loader_upgradeable::id()
+    }
+
+    fn vault(&self) -> Option<Pubkey> {
+        Some(self.group_key)
+    }
+
+    async fn upgrade_authority(&self) -> Result<Pubkey, UpgradeError> {
+        Ok(self.group_key)
+    }
+
+    async fn propose(
+        &self,
+        creator: &Pubkey,
+        program: &Pubkey,
+        buffer: &Pubkey,
+        description: &str,
+    ) -> Result<ProposedTransaction, UpgradeError> {
+        // Session ids are random 32 bytes, so they read like the other backends' accounts
+        let session = Keypair::new().pubkey();
+        let _: Value = self
+            .client
+            .request(
+                reqwest::Method::POST,
+                "/sessions",
+                Some(&json!({
+                    "session": session.to_string(),
+                    "key": self.group_key.to_string(),
+                    "program": program.to_string(),
+                    "buffer": buffer.to_string(),
+                    "description": description,
+                    "proposer": creator.to_string(),
+                })),
+            )
+            .await?;
+
+        Ok(ProposedTransaction {
+            key: session,
+            instructions: Vec::new(),
+        })
+    }
+
+    async fn approve(&self, _member: &Pubkey, _transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        Err(UpgradeError::MultisigError(
+            "TSS parties approve by joining the signing session from their own devices".to_string(),
+        ))
+    }
+
+    async fn execute(&self, member: &Pubkey, transaction: &Pubkey) -> Result<Vec<Instruction>, UpgradeError> {
+        let session = self.session(transaction).await?;
+        if !session.executable() {
+            return Err(UpgradeError::MultisigError(format!(
+                "TSS session {} is {} with {} of {} parties",
+                transaction,
+                session.status,
+                session.participants.len(),
+                session.threshold
+            )));
+        }
+        let program = Pubkey::from_str(&session.program).map_err(|_| UpgradeError::InvalidPubkey)?;
+        let buffer = Pubkey::from_str(&session.buffer).map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        Ok(vec![upgrade_instruction(&program, &buffer, &self.group_key, member)])
+    }
+
+    async fn status(&self, transaction: &Pubkey) -> Result<BackendTransactionStatus, UpgradeError> {
+        Ok(self.session(transaction).await?.to_status())
+    }
+
+    async fn check_health(&self) -> Result<(), UpgradeError> {
+        let group = self.group().await?;
+        if group.threshold == 0 || group.parties.len() < group.threshold as usize {
+            return Err(UpgradeError::MultisigError(format!(
+                "TSS key {} has {} parties for a threshold of {}",
+                self.group_key,
+                group.parties.len(),
+                group.threshold
+            )));
+        }
+        Ok(())
+    }
+
+    fn authority_signer(&self, transaction: &Pubkey) -> Option<SharedSigner> {
+        Some(Arc::new(TssSessionSigner {
+            client: self.client.clone(),
+            group_key: self.group_key,
+            session: *transaction,
+            sign_attempts: self.sign_attempts,
+        }))
+    }
+}
+
+/// Signs as the group key by running a signing round in one session. A round
+/// that fails because a party dropped out or the coordinator went away is
+/// retried, letting the coordinator pick a new quorum among the parties still
+/// online; attempts stay within the transaction's blockhash lifetime.
+pub struct TssSessionSigner {
+    client: CoordinatorClient,
+    group_key: Pubkey,
+    session: Pubkey,
+    sign_attempts: u32,
+}
+
+#[async_trait]
+impl TransactionSigner for TssSessionSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.group_key
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, UpgradeError> {
+        let body = json!({
+            "key": self.group_key.to_string(),
+            "message": base64::engine::general_purpose::STANDARD.encode(message),
+        });
+        let path = format!("/sessions/{}/sign", self.session);
+
+        let mut attempt = 1;
+        let response = loop {
+            match self
+                .client
+                .request::<TssSignResponse>(reqwest::Method::POST, &path, Some(&body))
+                .await
+            {
+                Ok(response) => break response,
+                Err(e) if attempt < self.sign_attempts && is_retryable_status(e.status) => {
+                    tracing::warn!(
+                        "TSS signing round {} of {} for session {} failed: {}",
+                        attempt,
+                        self.sign_attempts,
+                        self.session,
+                        e.message
+                    );
+                    attempt += 1;
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let signature = Signature::from_str(&response.signature)
+            .map_err(|_| UpgradeError::MultisigError("TSS coordinator returned a malformed signature".to_string()))?;
+        if !signature.verify(self.group_key.as_ref(), message) {
+            return Err(UpgradeError::MultisigError(format!(
+                "TSS signature does not verify for group key {}",
+                self.group_key
+            )));
+        }
+
+        tracing::info!(
+            "TSS session {} signed by {} in round {}",
+            self.session,
+            response.signers.join(", "),
+            attempt
+        );
+
+        Ok(signature)
+    }
+}
@@ -0,0 +1,153 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// An execution started by one executor and waiting for a second to confirm
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExecutionRequest {
+    pub proposal_id: String,
+    pub initiated_by: String,
+    pub initiated_at: i64,
+    pub expires_at: i64,
+    /// Skip the dependency health gate once confirmed
+    pub forced: bool,
+}
+
+impl ExecutionRequest {
+    pub fn new(proposal_id: &str, initiated_by: &str, forced: bool, now: i64, window_seconds: i64) -> Self {
+        Self {
+            proposal_id: proposal_id.to_string(),
+            initiated_by: initiated_by.to_string(),
+            initiated_at: now,
+            expires_at: now + window_seconds,
+            forced,
+        }
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+
+    /// Whether `initiator` may start a new request while this one exists.
+    /// Only the original initiator can restart a live request; anyone else
+    /// is expected to confirm it.
+    pub fn check_replace(&self, initiator: &str, now: i64) -> Result<(), UpgradeError> {
+        if self.is_expired(now) || self.initiated_by == initiator {
+            return Ok(());
+        }
+        Err(UpgradeError::InvalidRequest(format!(
+            "Execution of {} already initiated by {}; confirm it instead",
+            self.proposal_id, self.initiated_by
+        )))
+    }
+
+    /// `confirmer` must be someone other than the initiator, within the window
+    pub fn check_confirmation(&self, confirmer: &str, now: i64) -> Result<(), UpgradeError> {
+        if confirmer == self.initiated_by {
+            return Err(UpgradeError::Forbidden(
+                "Execution must be confirmed by a different executor".to_string(),
+            ));
+        }
+        if self.is_expired(now) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "Execution request for {} expired at {}; initiate it again",
+                self.proposal_id, self.expires_at
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Two-person rule for execution: after approvals and the timelock, one
+/// executor initiates and a different one must confirm within a short window
+/// before the job is queued, so no single key holder decides when an upgrade
+/// lands.
+pub struct TwoPersonRule {
+    database: Arc<Database>,
+    enabled: bool,
+    window_seconds: i64,
+}
+
+impl TwoPersonRule {
+    /// Enabled by `EXECUTION_TWO_PERSON_RULE=true`; confirmations are accepted
+    /// for `EXECUTION_CONFIRM_WINDOW_SECS` (default 300)
+    pub fn from_env(database: Arc<Database>) -> Self {
+        let enabled = std::env::var("EXECUTION_TWO_PERSON_RULE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        let window_seconds = std::env::var("EXECUTION_CONFIRM_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+
+        Self {
+            database,
+            enabled,
+            window_seconds,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn window_seconds(&self) -> i64 {
+        self.window_seconds
+    }
+
+    /// Start the confirmation window. The initiator may restart their own
+    /// request; a live request from someone else has to be confirmed instead.
+    pub async fn initiate(
+        &self,
+        proposal_id: &str,
+        initiator: &str,
+        forced: bool,
+    ) -> Result<ExecutionRequest, UpgradeError> {
+        let now = now();
+        if let Some(existing) = self.database.get_execution_request(proposal_id).await? {
+            existing.check_replace(initiator, now)?;
+        }
+
+        let request = ExecutionRequest::new(proposal_id, initiator, forced, now, self.window_seconds);
+        self.database.save_execution_request(&request).await?;
+        tracing::info!(
+            "Execution of {} initiated by {}, awaiting confirmation until {}",
+            proposal_id,
+            initiator,
+            request.expires_at
+        );
+
+        Ok(request)
+    }
+
+    pub async fn pending(&self, proposal_id: &str) -> Result<ExecutionRequest, UpgradeError> {
+        self.database
+            .get_execution_request(proposal_id)
+            .await?
+            .ok_or_else(|| {
+                UpgradeError::InvalidRequest(format!("No execution of {} awaiting confirmation", proposal_id))
+            })
+    }
+
+    /// Consume the pending request if `confirmer` may confirm it
+    pub async fn confirm(&self, proposal_id: &str, confirmer: &str) -> Result<ExecutionRequest, UpgradeError> {
+        let request = self
+            .database
+            .take_execution_request(proposal_id, |request| request.check_confirmation(confirmer, now()))
+            .await?;
+        tracing::info!(
+            "Execution of {} initiated by {} confirmed by {}",
+            proposal_id,
+            request.initiated_by,
+            confirmer
+        );
+
+        Ok(request)
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
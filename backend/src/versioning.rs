@@ -0,0 +1,84 @@
+use axum::extract::{Request, State};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Json, Response};
+use std::sync::Arc;
+
+/// Current API version; routes are served under `/v{CURRENT_VERSION}`
+pub const CURRENT_VERSION: &str = "1";
+
+/// Versions a client may ask for with `Accept-Version`
+pub const SUPPORTED_VERSIONS: &[&str] = &["1"];
+
+pub const ACCEPT_VERSION: HeaderName = HeaderName::from_static("accept-version");
+pub const API_VERSION: HeaderName = HeaderName::from_static("api-version");
+
+/// Deprecation policy for the unversioned legacy routes
+#[derive(Debug, Clone)]
+pub struct LegacyRoutes {
+    /// HTTP-date after which the legacy routes may be removed
+    pub sunset: String,
+}
+
+impl LegacyRoutes {
+    /// Reads `API_LEGACY_SUNSET` (RFC 3339, default 2027-01-01T00:00:00Z)
+    pub fn from_env() -> Self {
+        let sunset = std::env::var("API_LEGACY_SUNSET")
+            .ok()
+            .and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok())
+            .map(|d| d.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|| {
+                chrono::DateTime::parse_from_rfc3339("2027-01-01T00:00:00Z")
+                    .unwrap()
+                    .with_timezone(&chrono::Utc)
+            });
+
+        Self {
+            sunset: sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        }
+    }
+}
+
+/// Rejects requests for an unsupported `Accept-Version` and tags every
+/// response with the version that served it.
+pub async fn negotiate_version(request: Request, next: Next) -> Response {
+    if let Some(requested) = request.headers().get(&ACCEPT_VERSION) {
+        let requested = requested.to_str().unwrap_or_default().trim().trim_start_matches('v');
+        if !SUPPORTED_VERSIONS.contains(&requested) {
+            return (
+                StatusCode::NOT_ACCEPTABLE,
+                Json(serde_json::json!({
+                    "error": format!("Unsupported API version: {}", requested),
+                    "supported_versions": SUPPORTED_VERSIONS,
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(API_VERSION, HeaderValue::from_static(CURRENT_VERSION));
+    response
+}
+
+/// Marks an unversioned route as deprecated and points at its `/v1` successor
+pub async fn deprecate_legacy_route(
+    State(legacy): State<Arc<LegacyRoutes>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let successor = format!("</v{}{}>; rel=\"successor-version\"", CURRENT_VERSION, request.uri().path());
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(sunset) = HeaderValue::from_str(&legacy.sunset) {
+        headers.insert("sunset", sunset);
+    }
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(axum::http::header::LINK, link);
+    }
+    response
+}
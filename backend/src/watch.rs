@@ -0,0 +1,432 @@
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::incidents::{Detection, IncidentManager, IncidentSeverity, IncidentSource};
+use crate::indexer::event_discriminator;
+use crate::monitoring::{AlertLevel, MonitoringService, COMPONENT_WATCH};
+use crate::rollback::RollbackHandler;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Longest window an expression may be watched for after an upgrade
+pub const MAX_WATCH_WINDOW_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// Page size of `getSignaturesForAddress`
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// What an expression looks for in the managed program's transaction logs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchMatcher {
+    /// Log lines containing `substring`, e.g. `"Error Code: OracleStale"`
+    LogSubstring { substring: String },
+    /// Anchor events (`Program data:` lines) starting with this hex discriminator
+    Event { discriminator: String },
+}
+
+impl WatchMatcher {
+    /// Number of lines in `logs` that match
+    pub fn count(&self, logs: &[String]) -> u32 {
+        match self {
+            WatchMatcher::LogSubstring { substring } => {
+                logs.iter().filter(|log| log.contains(substring.as_str())).count() as u32
+            }
+            WatchMatcher::Event { discriminator } => {
+                let Ok(discriminator) = hex::decode(discriminator) else {
+                    return 0;
+                };
+                logs.iter()
+                    .filter_map(|log| event_data(log))
+                    .filter(|data| data.starts_with(&discriminator))
+                    .count() as u32
+            }
+        }
+    }
+}
+
+fn event_data(log: &str) -> Option<Vec<u8>> {
+    let encoded = log.strip_prefix("Program data: ")?;
+    base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchKind {
+    /// Any match is an anomaly, e.g. an error event the old version never emitted
+    Anomaly,
+    /// Fewer than `min_matches` over the window is an anomaly, e.g. a crank
+    /// heartbeat event that stopped
+    Heartbeat,
+}
+
+impl WatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchKind::Anomaly => "anomaly",
+            WatchKind::Heartbeat => "heartbeat",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "anomaly" => Some(WatchKind::Anomaly),
+            "heartbeat" => Some(WatchKind::Heartbeat),
+            _ => None,
+        }
+    }
+}
+
+/// Operator-registered expression watched for a window after every upgrade
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchExpression {
+    pub id: String,
+    pub name: String,
+    pub matcher: WatchMatcher,
+    pub kind: WatchKind,
+    /// Matches a heartbeat needs over the window
+    pub min_matches: u32,
+    pub window_seconds: i64,
+    /// Roll the program back on an anomaly rather than only alerting
+    pub rollback: bool,
+    pub created_by: Option<String>,
+    pub created_at: i64,
+}
+
+impl WatchExpression {
+    /// The anomaly `matches` after `elapsed` seconds show, if any. Heartbeats
+    /// are judged once their window has closed.
+    pub fn verdict(&self, matches: u32, elapsed: i64) -> Option<String> {
+        match self.kind {
+            WatchKind::Anomaly if matches > 0 => Some(format!(
+                "{} matching log line(s) within {}s of the upgrade",
+                matches, elapsed
+            )),
+            WatchKind::Heartbeat if elapsed >= self.window_seconds && matches < self.min_matches => Some(format!(
+                "{} of {} expected matches in {}s",
+                matches, self.min_matches, self.window_seconds
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Request body for `POST /monitoring/watch-expressions`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWatchExpression {
+    pub name: String,
+    pub matcher: WatchMatcher,
+    pub kind: WatchKind,
+    pub min_matches: Option<u32>,
+    pub window_seconds: i64,
+    #[serde(default)]
+    pub rollback: bool,
+    pub created_by: Option<String>,
+}
+
+impl NewWatchExpression {
+    /// Validate the expression. An event may be given by name instead of
+    /// discriminator; it is stored as the Anchor discriminator of that name.
+    pub fn into_expression(self, now: i64) -> Result<WatchExpression, UpgradeError> {
+        if self.name.trim().is_empty() {
+            return Err(UpgradeError::InvalidRequest("name must not be empty".to_string()));
+        }
+        if !(1..=MAX_WATCH_WINDOW_SECONDS).contains(&self.window_seconds) {
+            return Err(UpgradeError::InvalidRequest(format!(
+                "window_seconds must be between 1 and {}",
+                MAX_WATCH_WINDOW_SECONDS
+            )));
+        }
+
+        let matcher = match self.matcher {
+            WatchMatcher::LogSubstring { substring } if substring.is_empty() => {
+                return Err(UpgradeError::InvalidRequest("substring must not be empty".to_string()));
+            }
+            WatchMatcher::Event { discriminator } => {
+                let value = discriminator.trim();
+                let value = value.strip_prefix("0x").unwrap_or(value);
+                let discriminator = if value.len() == 16 && hex::decode(value).is_ok() {
+                    value.to_lowercase()
+                } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    hex::encode(event_discriminator(value))
+                } else {
+                    return Err(UpgradeError::InvalidRequest(
+                        "discriminator must be 8 hex bytes or an event name".to_string(),
+                    ));
+                };
+                WatchMatcher::Event { discriminator }
+            }
+            matcher => matcher,
+        };
+
+        let min_matches = match self.kind {
+            WatchKind::Anomaly => 0,
+            WatchKind::Heartbeat => match self.min_matches.unwrap_or(1) {
+                0 => return Err(UpgradeError::InvalidRequest("min_matches must be at least 1".to_string())),
+                n => n,
+            },
+        };
+
+        Ok(WatchExpression {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name.trim().to_string(),
+            matcher,
+            kind: self.kind,
+            min_matches,
+            window_seconds: self.window_seconds,
+            rollback: self.rollback,
+            created_by: self.created_by,
+            created_at: now,
+        })
+    }
+}
+
+/// Outcome of one expression after one upgrade (see `watch_findings` table)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchFinding {
+    pub proposal_id: String,
+    pub expression_id: String,
+    pub name: String,
+    pub matches: u32,
+    pub anomaly: bool,
+    pub detail: String,
+    /// Whether this finding triggered the rollback
+    pub rolled_back: bool,
+    pub evaluated_at: i64,
+}
+
+/// Reads the managed program's logs after an upgrade and judges the
+/// registered watch expressions against them. Anomalies raise a critical
+/// alert, and roll the program back when the expression asks for it.
+pub struct PostUpgradeWatch {
+    database: Arc<Database>,
+    rpc_client: Arc<AsyncRpcClient>,
+    monitoring: Option<Arc<MonitoringService>>,
+    rollback_handler: Option<Arc<RollbackHandler>>,
+    incidents: Option<Arc<IncidentManager>>,
+    managed_program: String,
+    poll_interval: Duration,
+}
+
+impl PostUpgradeWatch {
+    pub fn new(database: Arc<Database>) -> Self {
+        let rpc_url = std::env::var("SOLANA_RPC_URL")
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+
+        Self {
+            database,
+            rpc_client: Arc::new(AsyncRpcClient::new(rpc_url)),
+            monitoring: None,
+            rollback_handler: None,
+            incidents: None,
+            managed_program: std::env::var("MANAGED_PROGRAM_ID")
+                .unwrap_or_else(|_| "program_id".to_string()),
+            poll_interval: Duration::from_secs(
+                std::env::var("WATCH_POLL_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(15),
+            ),
+        }
+    }
+
+    pub fn with_alerts(mut self, monitoring: Arc<MonitoringService>) -> Self {
+        self.monitoring = Some(monitoring);
+        self
+    }
+
+    /// Roll back on anomalies of expressions registered with `rollback`
+    pub fn with_rollback(mut self, rollback_handler: Arc<RollbackHandler>) -> Self {
+        self.rollback_handler = Some(rollback_handler);
+        self
+    }
+
+    /// Open an incident, or add to the open one, on every anomaly
+    pub fn with_incidents(mut self, incidents: Arc<IncidentManager>) -> Self {
+        self.incidents = Some(incidents);
+        self
+    }
+
+    pub async fn findings(&self, proposal_id: &str) -> Result<Vec<WatchFinding>, UpgradeError> {
+        self.database.list_watch_findings(proposal_id).await
+    }
+
+    /// Follow the managed program from now until every expression has a
+    /// verdict: its first anomaly, or a quiet window
+    pub async fn watch(&self, proposal_id: &str) -> Result<Vec<WatchFinding>, UpgradeError> {
+        let expressions = self.database.list_watch_expressions().await?;
+        if expressions.is_empty() {
+            return Ok(Vec::new());
+        }
+        let program = Pubkey::from_str(&self.managed_program).map_err(|_| UpgradeError::InvalidPubkey)?;
+
+        tracing::info!("Watching {} expressions after proposal {}", expressions.len(), proposal_id);
+
+        let started = chrono::Utc::now().timestamp();
+        // Only transactions after the upgrade count
+        let mut until = self.newest_signature(&program).await?;
+        let mut counts = vec![0u32; expressions.len()];
+        let mut findings: Vec<Option<WatchFinding>> = vec![None; expressions.len()];
+        let mut rolled_back = false;
+
+        while findings.iter().any(Option::is_none) {
+            tokio::time::sleep(self.poll_interval).await;
+
+            match self.new_logs(&program, &mut until).await {
+                Ok(transactions) => {
+                    for logs in &transactions {
+                        for (count, expression) in counts.iter_mut().zip(&expressions) {
+                            *count += expression.matcher.count(logs);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to read logs of {}: {}", program, e),
+            }
+
+            let now = chrono::Utc::now().timestamp();
+            let elapsed = now - started;
+            for (i, expression) in expressions.iter().enumerate() {
+                if findings[i].is_some() {
+                    continue;
+                }
+                let mut finding = WatchFinding {
+                    proposal_id: proposal_id.to_string(),
+                    expression_id: expression.id.clone(),
+                    name: expression.name.clone(),
+                    matches: counts[i],
+                    anomaly: false,
+                    detail: String::new(),
+                    rolled_back: false,
+                    evaluated_at: now,
+                };
+                match expression.verdict(counts[i], elapsed) {
+                    Some(detail) => {
+                        finding.anomaly = true;
+                        finding.detail = detail;
+                        if expression.rollback && !rolled_back {
+                            finding.rolled_back = self.roll_back(&finding).await;
+                            rolled_back = finding.rolled_back;
+                        }
+                        self.alert(&finding).await;
+                    }
+                    None if elapsed >= expression.window_seconds => {
+                        finding.detail = format!("{} matches in {}s", counts[i], expression.window_seconds);
+                    }
+                    None => continue,
+                }
+
+                if let Err(e) = self.database.record_watch_finding(&finding).await {
+                    tracing::warn!("Failed to record watch finding '{}': {}", finding.name, e);
+                }
+                findings[i] = Some(finding);
+            }
+        }
+
+        Ok(findings.into_iter().flatten().collect())
+    }
+
+    async fn alert(&self, finding: &WatchFinding) {
+        let message = format!(
+            "Watch expression '{}' flagged proposal {}: {}{}",
+            finding.name,
+            finding.proposal_id,
+            finding.detail,
+            if finding.rolled_back { "; rolling back" } else { "" }
+        );
+        tracing::error!("{}", message);
+
+        if let Some(monitoring) = &self.monitoring {
+            monitoring
+                .send_alert(AlertLevel::Critical, message.clone(), COMPONENT_WATCH.to_string())
+                .await;
+        }
+
+        if let Some(incidents) = &self.incidents {
+            let detection = Detection {
+                source: IncidentSource::Watch,
+                severity: IncidentSeverity::Major,
+                program: self.managed_program.clone(),
+                proposal_id: Some(finding.proposal_id.clone()),
+                title: format!("Unexpected behaviour after an upgrade: {}", finding.name),
+                detail: format!("Watch expression '{}': {}", finding.name, finding.detail),
+                alert: Some((COMPONENT_WATCH.to_string(), message)),
+            };
+            if let Err(e) = incidents.detect(detection).await {
+                tracing::error!("Failed to open an incident for watch finding '{}': {}", finding.name, e);
+            }
+        }
+    }
+
+    async fn roll_back(&self, finding: &WatchFinding) -> bool {
+        let Some(rollback) = &self.rollback_handler else {
+            tracing::error!("No rollback handler configured; manual rollback required for {}", finding.proposal_id);
+            return false;
+        };
+        let reason = format!("Watch expression {}: {}", finding.name, finding.detail);
+        match rollback.rollback_upgrade(&finding.proposal_id, &self.managed_program, &reason).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Rollback after watch anomaly failed: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn newest_signature(&self, program: &Pubkey) -> Result<Option<Signature>, UpgradeError> {
+        let page = self.signatures(program, None, Some(1)).await?;
+        Ok(page.into_iter().next())
+    }
+
+    /// Logs of the program's transactions since `until`, oldest first,
+    /// advancing `until` past them. Failed transactions are included: their
+    /// errors are often what an expression looks for.
+    async fn new_logs(&self, program: &Pubkey, until: &mut Option<Signature>) -> Result<Vec<Vec<String>>, UpgradeError> {
+        let signatures = self.signatures(program, *until, Some(SIGNATURE_PAGE)).await?;
+
+        let mut transactions = Vec::with_capacity(signatures.len());
+        for signature in signatures.iter().rev() {
+            let transaction = self.rpc_client
+                .get_transaction(signature, UiTransactionEncoding::Json)
+                .await
+                .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch transaction {}: {}", signature, e)))?;
+            let logs: Option<Vec<String>> = transaction.transaction.meta
+                .and_then(|meta| meta.log_messages.into());
+            transactions.push(logs.unwrap_or_default());
+            *until = Some(*signature);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Newest first
+    async fn signatures(
+        &self,
+        program: &Pubkey,
+        until: Option<Signature>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Signature>, UpgradeError> {
+        self.rpc_client
+            .get_signatures_for_address_with_config(
+                program,
+                GetConfirmedSignaturesForAddress2Config {
+                    until,
+                    limit,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| UpgradeError::SolanaError(format!("Failed to fetch signatures: {}", e)))?
+            .iter()
+            .map(|status| {
+                Signature::from_str(&status.signature)
+                    .map_err(|e| UpgradeError::SolanaError(format!("Invalid signature: {}", e)))
+            })
+            .collect()
+    }
+}
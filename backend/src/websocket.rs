@@ -1,11 +1,18 @@
+use crate::buffer_watcher::DetectedBuffer;
+use crate::cluster::ClusterBus;
+use crate::database::Database;
+use crate::error::UpgradeError;
+use crate::templates::{Channel, NotificationTemplates};
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
 use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
-pub type NotificationSender = broadcast::Sender<Notification>;
+pub type NotificationSender = broadcast::Sender<Event>;
 
 #[derive(Debug, Clone)]
 pub struct Notification {
@@ -20,57 +27,249 @@ pub enum NotificationType {
     ProposalCreated,
     ProposalApproved,
     TimelockExpired,
+    TimelockTick,
     UpgradeExecuted,
+    UpgradeFailed,
     MigrationProgress,
     RollbackInitiated,
+    BufferUploadProgress,
+    BufferDetected,
+    UpgradeAnnounced,
+    ProposalFrozen,
+    ProposalUnfrozen,
+    ApprovalReminder,
+}
+
+/// A notification as delivered to clients. `seq` is assigned when the event is
+/// persisted and is `None` if the service runs without a database.
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Event {
+    pub seq: Option<i64>,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub proposal_id: Option<String>,
+    pub message: String,
+    pub data: serde_json::Value,
+    pub timestamp: i64,
+}
+
+impl Event {
+    fn to_message(&self) -> Message {
+        Message::Text(json!(self).to_string())
+    }
 }
 
 impl NotificationType {
-    fn as_str(&self) -> &'static str {
+    pub const ALL: [NotificationType; 14] = [
+        NotificationType::ProposalCreated,
+        NotificationType::ProposalApproved,
+        NotificationType::TimelockExpired,
+        NotificationType::TimelockTick,
+        NotificationType::UpgradeExecuted,
+        NotificationType::UpgradeFailed,
+        NotificationType::MigrationProgress,
+        NotificationType::RollbackInitiated,
+        NotificationType::BufferUploadProgress,
+        NotificationType::BufferDetected,
+        NotificationType::UpgradeAnnounced,
+        NotificationType::ProposalFrozen,
+        NotificationType::ProposalUnfrozen,
+        NotificationType::ApprovalReminder,
+    ];
+
+    /// The `type` of events carrying this notification
+    pub fn as_str(&self) -> &'static str {
         match self {
             NotificationType::ProposalCreated => "proposal_created",
             NotificationType::ProposalApproved => "proposal_approved",
             NotificationType::TimelockExpired => "timelock_expired",
+            NotificationType::TimelockTick => "timelock_tick",
             NotificationType::UpgradeExecuted => "upgrade_executed",
+            NotificationType::UpgradeFailed => "upgrade_failed",
             NotificationType::MigrationProgress => "migration_progress",
             NotificationType::RollbackInitiated => "rollback_initiated",
+            NotificationType::BufferUploadProgress => "buffer_upload_progress",
+            NotificationType::BufferDetected => "buffer_detected",
+            NotificationType::UpgradeAnnounced => "upgrade_announced",
+            NotificationType::ProposalFrozen => "proposal_frozen",
+            NotificationType::ProposalUnfrozen => "proposal_unfrozen",
+            NotificationType::ApprovalReminder => "approval_reminder",
+        }
+    }
+}
+
+/// Delivery counters exposed through `/metrics`
+#[derive(Debug, Default)]
+pub struct NotificationStats {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    lagged_clients: AtomicU64,
+    connected_clients: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationStatsSnapshot {
+    pub notifications_sent: u64,
+    pub notifications_dropped: u64,
+    pub lagged_client_events: u64,
+    pub connected_clients: u64,
+}
+
+impl NotificationStats {
+    pub fn snapshot(&self) -> NotificationStatsSnapshot {
+        NotificationStatsSnapshot {
+            notifications_sent: self.sent.load(Ordering::Relaxed),
+            notifications_dropped: self.dropped.load(Ordering::Relaxed),
+            lagged_client_events: self.lagged_clients.load(Ordering::Relaxed),
+            connected_clients: self.connected_clients.load(Ordering::Relaxed),
         }
     }
 }
 
 pub struct NotificationService {
     sender: NotificationSender,
+    client_queue_size: usize,
+    stats: Arc<NotificationStats>,
+    database: Option<Arc<Database>>,
+    templates: Arc<NotificationTemplates>,
+    cluster: Option<Arc<ClusterBus>>,
 }
 
 impl NotificationService {
     pub fn new() -> Self {
-        let (sender, _) = broadcast::channel(100);
-        Self { sender }
+        let capacity = std::env::var("WS_BROADCAST_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1024);
+        let client_queue_size = std::env::var("WS_CLIENT_QUEUE_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(256);
+
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            client_queue_size,
+            stats: Arc::new(NotificationStats::default()),
+            database: None,
+            templates: Arc::new(NotificationTemplates::builtin()),
+            cluster: None,
+        }
+    }
+
+    /// Persist every notification so clients can replay what they missed
+    pub fn with_database(mut self, database: Arc<Database>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Word notifications from deployment-specific templates
+    pub fn with_templates(mut self, templates: NotificationTemplates) -> Self {
+        self.templates = Arc::new(templates);
+        self
+    }
+
+    /// Announce persisted events to the other replicas
+    pub fn with_cluster(mut self, cluster: Arc<ClusterBus>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    pub fn templates(&self) -> &NotificationTemplates {
+        &self.templates
+    }
+
+    /// The event's text for `channel` in `locale`, or the message it was
+    /// sent with if no template covers its type
+    pub fn render(&self, event: &Event, channel: Channel, locale: Option<&str>) -> String {
+        render_event(&self.templates, event, channel, locale)
     }
 
     pub fn get_sender(&self) -> NotificationSender {
         self.sender.clone()
     }
 
+    /// Live notifications, shared by the WebSocket and SSE endpoints
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    pub fn stats(&self) -> NotificationStatsSnapshot {
+        self.stats.snapshot()
+    }
+
     pub async fn notify(&self, notification: Notification) {
-        let json = json!({
-            "type": notification.notification_type.as_str(),
-            "proposal_id": notification.proposal_id,
-            "message": notification.message,
-            "data": notification.data,
-            "timestamp": std::time::SystemTime::now()
+        let mut event = Event {
+            seq: None,
+            event_type: notification.notification_type.as_str().to_string(),
+            proposal_id: notification.proposal_id,
+            message: notification.message,
+            data: notification.data,
+            timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs() as i64,
-        });
+        };
+        event.message = self.render(&event, Channel::Websocket, None);
+
+        if let Some(database) = &self.database {
+            match database.insert_event(&event).await {
+                Ok(seq) => event.seq = Some(seq),
+                Err(e) => warn!("Failed to persist notification: {}", e),
+            }
+        }
 
-        if let Err(e) = self.sender.send(notification.clone()) {
+        if let (Some(cluster), Some(seq)) = (&self.cluster, event.seq) {
+            if let Err(e) = cluster.announce(seq).await {
+                warn!("Failed to announce notification {} to the cluster: {}", seq, e);
+            }
+        }
+
+        let json = json!(event);
+        if let Err(e) = self.sender.send(event) {
             warn!("Failed to send notification: {}", e);
         } else {
+            self.stats.sent.fetch_add(1, Ordering::Relaxed);
             info!("Notification sent: {}", json);
         }
     }
 
+    /// Deliver to this instance's connected clients only. Nothing is persisted,
+    /// replayed or announced to the cluster, for frequent updates that are
+    /// stale a moment later and that every instance sends on its own.
+    pub fn notify_transient(&self, notification: Notification) {
+        let mut event = Event {
+            seq: None,
+            event_type: notification.notification_type.as_str().to_string(),
+            proposal_id: notification.proposal_id,
+            message: notification.message,
+            data: notification.data,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as i64,
+        };
+        event.message = self.render(&event, Channel::Websocket, None);
+        self.relay(event);
+    }
+
+    /// Deliver an event another instance already persisted to this
+    /// instance's subscribers
+    pub fn relay(&self, event: Event) {
+        if self.sender.send(event).is_ok() {
+            self.stats.sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Persisted events with `seq > since_seq`, oldest first
+    pub async fn events_since(&self, since_seq: i64, limit: i64) -> Result<Vec<Event>, UpgradeError> {
+        match &self.database {
+            Some(database) => database.list_events_since(since_seq, limit).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
     pub async fn notify_proposal_created(&self, proposal_id: String, data: serde_json::Value) {
         self.notify(Notification {
             notification_type: NotificationType::ProposalCreated,
@@ -116,48 +315,221 @@ impl NotificationService {
         .await;
     }
 
-    pub async fn notify_migration_progress(
-        &self,
-        migration_id: String,
-        progress: f64,
-        migrated: usize,
-        total: usize,
-    ) {
+    /// `error` names the program error when the failing program's IDL is known
+    pub async fn notify_upgrade_failed(&self, proposal_id: String, program: String, error: String) {
+        self.notify(Notification {
+            notification_type: NotificationType::UpgradeFailed,
+            proposal_id: Some(proposal_id),
+            message: format!("Upgrade failed: {}", error),
+            data: json!({
+                "program": program,
+                "error": error,
+            }),
+        })
+        .await;
+    }
+
+    /// `snapshot` is the migration's progress as served by `/migration/progress`
+    pub async fn notify_migration_progress(&self, migration_id: String, snapshot: serde_json::Value) {
+        let progress = snapshot["progress_percent"].as_f64().unwrap_or(0.0);
         self.notify(Notification {
             notification_type: NotificationType::MigrationProgress,
             proposal_id: Some(migration_id),
             message: format!("Migration progress: {:.2}%", progress),
+            data: snapshot,
+        })
+        .await;
+    }
+
+    pub async fn notify_buffer_upload_progress(
+        &self,
+        buffer: String,
+        confirmed_chunks: usize,
+        total_chunks: usize,
+    ) {
+        let progress = if total_chunks > 0 {
+            (confirmed_chunks as f64 / total_chunks as f64) * 100.0
+        } else {
+            100.0
+        };
+
+        self.notify(Notification {
+            notification_type: NotificationType::BufferUploadProgress,
+            proposal_id: None,
+            message: format!("Buffer upload progress: {:.2}%", progress),
             data: json!({
+                "buffer": buffer,
                 "progress_percent": progress,
-                "migrated_accounts": migrated,
-                "total_accounts": total,
+                "confirmed_chunks": confirmed_chunks,
+                "total_chunks": total_chunks,
             }),
         })
         .await;
     }
+
+    /// `data` is the proposal's freeze: finding, source, who froze it and when
+    pub async fn notify_proposal_frozen(&self, proposal_id: String, data: serde_json::Value) {
+        self.notify(Notification {
+            notification_type: NotificationType::ProposalFrozen,
+            proposal_id: Some(proposal_id),
+            message: format!("Proposal frozen: {}", data["finding"].as_str().unwrap_or_default()),
+            data,
+        })
+        .await;
+    }
+
+    pub async fn notify_proposal_unfrozen(&self, proposal_id: String, data: serde_json::Value) {
+        self.notify(Notification {
+            notification_type: NotificationType::ProposalUnfrozen,
+            proposal_id: Some(proposal_id),
+            message: format!("Proposal unfrozen: {}", data["resolution"].as_str().unwrap_or_default()),
+            data,
+        })
+        .await;
+    }
+
+    pub async fn notify_buffer_detected(&self, detected: &DetectedBuffer) {
+        self.notify(Notification {
+            notification_type: NotificationType::BufferDetected,
+            proposal_id: None,
+            message: format!("Vault-owned buffer {} detected without a proposal", detected.buffer),
+            data: json!(detected),
+        })
+        .await;
+    }
+}
+
+/// Maximum number of events replayed when a client connects with `since_seq`
+const MAX_REPLAY_EVENTS: i64 = 1000;
+
+/// Message telling a client it fell behind and must resync over REST
+fn render_event(templates: &NotificationTemplates, event: &Event, channel: Channel, locale: Option<&str>) -> String {
+    templates
+        .render(&event.event_type, channel, locale, event.proposal_id.as_deref(), &event.data)
+        .unwrap_or_else(|| event.message.clone())
+}
+
+fn resync_message(missed: u64, last_seq: Option<i64>) -> Message {
+    let since_seq = last_seq.unwrap_or(0);
+    let json = json!({
+        "type": "resync_required",
+        "proposal_id": null,
+        "message": format!(
+            "You missed {} events, call GET /events?since_seq={} to resync",
+            missed, since_seq
+        ),
+        "data": {
+            "missed_events": missed,
+            "since_seq": since_seq,
+        },
+        "timestamp": std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64,
+    });
+    Message::Text(json.to_string())
 }
 
+/// Serve one WebSocket client.
+///
+/// Each client gets its own bounded queue fed from the shared broadcast channel,
+/// so a slow socket only loses its own messages. Anything lost, either because
+/// the client's queue was full or because it lagged behind the broadcast
+/// channel, is counted and reported to the client as a `resync_required`
+/// message before the next delivered event.
+///
+/// With `since_seq`, persisted events after that sequence number are replayed
+/// in order before the live stream resumes. With `locale`, messages are
+/// re-rendered from that locale's templates for this client.
 pub async fn handle_websocket(
     socket: WebSocket,
-    mut receiver: broadcast::Receiver<Notification>,
+    service: Arc<NotificationService>,
+    since_seq: Option<i64>,
+    locale: Option<String>,
 ) {
     let (mut sender, mut receiver_ws) = socket.split();
+    // Subscribe before reading the backlog so nothing falls between the two
+    let mut receiver = service.subscribe();
+    let (queue_tx, mut queue_rx) = mpsc::channel::<Message>(service.client_queue_size);
+    let stats = service.stats.clone();
+    let templates = service.templates.clone();
+    let localize = move |mut event: Event| {
+        if locale.is_some() {
+            event.message = render_event(&templates, &event, Channel::Websocket, locale.as_deref());
+        }
+        event
+    };
 
-    // Spawn task to send notifications
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(notification) = receiver.recv().await {
-            let json = json!({
-                "type": notification.notification_type.as_str(),
-                "proposal_id": notification.proposal_id,
-                "message": notification.message,
-                "data": notification.data,
-                "timestamp": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64,
-            });
+    stats.connected_clients.fetch_add(1, Ordering::Relaxed);
+
+    let mut last_seq = since_seq;
+    if let Some(since_seq) = since_seq {
+        match service.events_since(since_seq, MAX_REPLAY_EVENTS).await {
+            Ok(events) => {
+                for event in events {
+                    last_seq = event.seq;
+                    if sender.send(localize(event).to_message()).await.is_err() {
+                        stats.connected_clients.fetch_sub(1, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to replay events since {}: {}", since_seq, e),
+        }
+    }
 
-            if sender.send(Message::Text(json.to_string())).await.is_err() {
+    // Move notifications from the broadcast channel into this client's queue
+    let mut forward_task = tokio::spawn(async move {
+        let mut missed: u64 = 0;
+
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("WebSocket client lagged behind by {} notifications", n);
+                    stats.lagged_clients.fetch_add(1, Ordering::Relaxed);
+                    stats.dropped.fetch_add(n, Ordering::Relaxed);
+                    missed += n;
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            // Already delivered by the replay
+            if let (Some(seq), Some(last)) = (event.seq, last_seq) {
+                if seq <= last {
+                    continue;
+                }
+            }
+
+            if missed > 0 {
+                match queue_tx.try_send(resync_message(missed, last_seq)) {
+                    Ok(()) => missed = 0,
+                    Err(mpsc::error::TrySendError::Full(_)) => {}
+                    Err(mpsc::error::TrySendError::Closed(_)) => break,
+                }
+            }
+
+            let seq = event.seq;
+            match queue_tx.try_send(localize(event).to_message()) {
+                Ok(()) => {
+                    if seq.is_some() {
+                        last_seq = seq;
+                    }
+                }
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    stats.dropped.fetch_add(1, Ordering::Relaxed);
+                    missed += 1;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => break,
+            }
+        }
+    });
+
+    // Drain the client's queue onto the socket
+    let mut send_task = tokio::spawn(async move {
+        while let Some(message) = queue_rx.recv().await {
+            if sender.send(message).await.is_err() {
                 break;
             }
         }
@@ -173,10 +545,20 @@ pub async fn handle_websocket(
     });
 
     tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
+        _ = (&mut forward_task) => {
+            send_task.abort();
+            recv_task.abort();
+        }
+        _ = (&mut send_task) => {
+            forward_task.abort();
+            recv_task.abort();
+        }
+        _ = (&mut recv_task) => {
+            forward_task.abort();
+            send_task.abort();
+        }
     };
 
+    service.stats.connected_clients.fetch_sub(1, Ordering::Relaxed);
     info!("WebSocket connection closed");
 }
-
@@ -0,0 +1,104 @@
+use goquant_upgrade_service::accountability::*;
+use goquant_upgrade_service::activity::{ActivityEntry, ActivityKind};
+use goquant_upgrade_service::members::{MemberDirectory, MemberProfile};
+use goquant_upgrade_service::rollback::RollbackEvent;
+use goquant_upgrade_service::test_utils::{ProposalBuilder, PROPOSED_AT};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn entry(at: i64, kind: ActivityKind, actor: Option<&str>, summary: &str, data: serde_json::Value) -> ActivityEntry {
+    ActivityEntry {
+        at,
+        kind,
+        actor: actor.map(str::to_string),
+        summary: summary.to_string(),
+        data,
+    }
+}
+
+fn rollback() -> RollbackEvent {
+    RollbackEvent {
+        id: "rollback-1".to_string(),
+        proposal_id: "p1".to_string(),
+        old_program_id: "program_id".to_string(),
+        reason: "Invariants failed: vault balance".to_string(),
+        rolled_back_at: PROPOSED_AT + 5_000,
+    }
+}
+
+fn report() -> IncidentReport {
+    let proposal = ProposalBuilder::new("p1").executed(PROPOSED_AT + 4_000).build();
+    let activity = vec![
+        entry(PROPOSED_AT + 60, ActivityKind::Approval, Some("member1"), "Approved", serde_json::json!({})),
+        entry(
+            PROPOSED_AT + 100,
+            ActivityKind::Audit,
+            None,
+            "Fork test failed",
+            serde_json::json!({ "passed": false }),
+        ),
+        entry(PROPOSED_AT + 200, ActivityKind::Approval, Some("member2"), "Approved", serde_json::json!({})),
+        entry(PROPOSED_AT + 300, ActivityKind::Comment, Some("member3"), "Looks fine", serde_json::json!({})),
+    ];
+    let directory = MemberDirectory::new(vec![MemberProfile {
+        member: "member2".to_string(),
+        name: "Bob".to_string(),
+        organization: Some("Ops".to_string()),
+        contact: None,
+        notification_channel: None,
+        reminders_enabled: true,
+        updated_by: None,
+        updated_at: 0,
+    }]);
+
+    IncidentReport::build(
+        "incident-1",
+        &rollback(),
+        &proposal,
+        &activity,
+        &[],
+        &directory,
+        PROPOSED_AT + 6_000,
+    )
+}
+
+#[test]
+fn test_report_accounts_for_each_approval() {
+    let report = report();
+    assert_eq!(report.incident_id, "incident-1");
+    assert_eq!(report.rollback_id, "rollback-1");
+    assert_eq!(report.approvals.len(), 2);
+
+    let first = &report.approvals[0];
+    assert_eq!(first.member, "member1");
+    assert_eq!(first.review_seconds, 60);
+    assert!(first.evidence.is_empty());
+
+    let second = &report.approvals[1];
+    assert_eq!(second.display_name, "Bob (Ops)");
+    assert_eq!(second.evidence.len(), 1);
+
+    assert_eq!(
+        report.findings,
+        [
+            "member1 approved after 60s with no audit or checklist evidence recorded",
+            "Bob (Ops) approved despite: Fork test failed",
+        ]
+    );
+    assert_eq!(report.timing.first_approval_at, Some(PROPOSED_AT + 60));
+    assert_eq!(report.timing.last_approval_at, Some(PROPOSED_AT + 200));
+    assert_eq!(report.timing.live_seconds, Some(1_000));
+}
+
+#[test]
+fn test_signed_report_verifies() {
+    let signer = Keypair::new();
+    let report = report();
+    let signature = signer.sign_message(report.message().as_bytes());
+
+    let signed = SignedIncidentReport::new(report, &signer.pubkey(), &signature);
+    assert!(signed.verify());
+
+    let mut tampered = signed.clone();
+    tampered.report.findings.clear();
+    assert!(!tampered.verify());
+}
@@ -0,0 +1,70 @@
+use goquant_upgrade_service::activity::*;
+use goquant_upgrade_service::drafts::{DraftChanges, ProposalDraft, ReviewVerdict};
+use goquant_upgrade_service::test_utils::{ProposalBuilder, PROPOSED_AT};
+use solana_sdk::pubkey::Pubkey;
+
+fn entry(at: i64, kind: ActivityKind, summary: &str) -> ActivityEntry {
+    ActivityEntry {
+        at,
+        kind,
+        actor: None,
+        summary: summary.to_string(),
+        data: serde_json::json!({}),
+    }
+}
+
+#[test]
+fn test_merge_orders_by_time_and_filters_kinds() {
+    let entries = vec![
+        entry(30, ActivityKind::Execution, "Execution attempt 1 succeeded"),
+        entry(10, ActivityKind::Approval, "Approved"),
+        entry(20, ActivityKind::Audit, "Fork test passed"),
+        entry(10, ActivityKind::Notification, "Proposal approved"),
+    ];
+
+    let summaries: Vec<_> = merge(entries.clone(), &[]).into_iter().map(|e| e.summary).collect();
+    assert_eq!(
+        summaries,
+        ["Approved", "Proposal approved", "Fork test passed", "Execution attempt 1 succeeded"]
+    );
+
+    let audits = merge(entries, &[ActivityKind::Audit, ActivityKind::Approval]);
+    assert_eq!(audits.len(), 2);
+    assert_eq!(audits[0].kind, ActivityKind::Approval);
+}
+
+#[test]
+fn test_parse_kind_list() {
+    assert_eq!(
+        ActivityKind::parse_list("approval, audit,").unwrap(),
+        vec![ActivityKind::Approval, ActivityKind::Audit]
+    );
+    assert!(ActivityKind::parse_list("approval,gossip").is_err());
+    assert_eq!(ActivityKind::parse(ActivityKind::Onchain.as_str()), Some(ActivityKind::Onchain));
+}
+
+#[test]
+fn test_proposal_and_draft_entries() {
+    let proposal = ProposalBuilder::new("p1").executed(PROPOSED_AT + 100).build();
+    let entries = proposal_entries(&proposal);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].at, PROPOSED_AT);
+    assert_eq!(entries[0].actor.as_deref(), Some("multisig"));
+    assert_eq!(entries[1].summary, "Executed");
+
+    let changes = DraftChanges {
+        new_buffer: Some(Pubkey::new_unique().to_string()),
+        description: Some("Upgrade to v2".to_string()),
+        ..Default::default()
+    };
+    let mut draft = ProposalDraft::new("alice", changes, 100).unwrap();
+    draft.review("bob", ReviewVerdict::RequestChanges, Some("Add a rollback plan".to_string()), 110).unwrap();
+    draft.review("carol", ReviewVerdict::Approve, None, 120).unwrap();
+
+    let comments = draft_comments(&draft);
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].kind, ActivityKind::Comment);
+    assert_eq!(comments[0].actor.as_deref(), Some("bob"));
+    assert_eq!(comments[0].summary, "Requested changes to revision 1: Add a rollback plan");
+    assert_eq!(comments[1].summary, "Approved revision 1");
+}
@@ -0,0 +1,47 @@
+use goquant_upgrade_service::announcements::{format_duration, Announcement};
+use goquant_upgrade_service::proposal::RiskTier;
+
+fn announcement() -> Announcement {
+    Announcement {
+        proposal_id: "proposal-1".to_string(),
+        program: "Program11111111111111111111111111111".to_string(),
+        title: "Scheduled upgrade v2.0.0".to_string(),
+        window_start: 1_700_000_000,
+        window_end: 1_700_003_600,
+        affected_markets: vec!["SOL-PERP".to_string(), "BTC-PERP".to_string()],
+        estimated_migration_seconds: Some(1260),
+        risk_tier: Some(RiskTier::Low),
+        published_at: 1_699_900_000,
+    }
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(45), "45s");
+    assert_eq!(format_duration(720), "12m");
+    assert_eq!(format_duration(3900), "1h 05m");
+}
+
+#[test]
+fn test_template_data() {
+    let data = announcement().template_data();
+    assert_eq!(data["window_start_utc"], "2023-11-14 22:13 UTC");
+    assert_eq!(data["window_end_utc"], "2023-11-14 23:13 UTC");
+    assert_eq!(data["affected_markets"], "SOL-PERP, BTC-PERP");
+    assert_eq!(data["migration_estimate"], "21m");
+    assert_eq!(data["risk_tier"], "low");
+}
+
+#[test]
+fn test_template_data_without_markets_or_migrations() {
+    let data = Announcement {
+        affected_markets: Vec::new(),
+        estimated_migration_seconds: None,
+        risk_tier: None,
+        ..announcement()
+    }
+    .template_data();
+    assert_eq!(data["affected_markets"], "none listed");
+    assert_eq!(data["migration_estimate"], "no account migration expected");
+    assert_eq!(data["risk_tier"], "unassessed");
+}
@@ -0,0 +1,83 @@
+use axum::http::{HeaderMap, HeaderValue, Method};
+use goquant_upgrade_service::api_keys::{
+    generate_key, hash_key, presented_key, required_scope, NewApiKey, Scope, KEY_PREFIX,
+};
+
+#[test]
+fn test_required_scope_by_route() {
+    assert_eq!(required_scope(&Method::GET, "/public/proposals"), None);
+    assert_eq!(required_scope(&Method::POST, "/integrations/github/release"), None);
+    assert_eq!(required_scope(&Method::GET, "/upgrade/proposals"), Some(Scope::Read));
+    assert_eq!(required_scope(&Method::GET, "/api-keys"), Some(Scope::Admin));
+    assert_eq!(required_scope(&Method::GET, "/multisig/members/abc/profile"), Some(Scope::Read));
+    assert_eq!(required_scope(&Method::PUT, "/multisig/members/abc/profile"), Some(Scope::Admin));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/propose"), Some(Scope::Propose));
+    assert_eq!(required_scope(&Method::PATCH, "/upgrade/draft/abc"), Some(Scope::Propose));
+    assert_eq!(required_scope(&Method::POST, "/buffers/abc/propose"), Some(Scope::Propose));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/cancel"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/freeze"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/buffers/abc/dismiss"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/incidents"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/rollback/drill"), Some(Scope::Execute));
+    assert_eq!(required_scope(&Method::POST, "/snapshots"), Some(Scope::Execute));
+    assert_eq!(required_scope(&Method::DELETE, "/security/denylist/abc"), Some(Scope::Admin));
+    // Writes nobody mapped need admin rather than falling to a lower scope
+    assert_eq!(required_scope(&Method::POST, "/unmapped/route"), Some(Scope::Admin));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/fork-test"), Some(Scope::Read));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/approve"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/revoke"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/approve-batch"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/reminders/opt-out"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/checklist/audit"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/upgrade/abc/execute/confirm"), Some(Scope::Execute));
+    assert_eq!(required_scope(&Method::POST, "/migration/abc/rollback"), Some(Scope::Execute));
+    assert_eq!(required_scope(&Method::POST, "/programs/abc/flags/leverage/kill"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::POST, "/programs/abc/flag-proposals/def/apply"), Some(Scope::Execute));
+    assert_eq!(required_scope(&Method::POST, "/programs/abc/flags/leverage/propose"), Some(Scope::Propose));
+    assert_eq!(required_scope(&Method::POST, "/programs/abc/emergency-pause"), Some(Scope::Approve));
+    assert_eq!(required_scope(&Method::GET, "/programs/abc/emergency-pause"), Some(Scope::Read));
+}
+
+#[test]
+fn test_generated_keys_are_unique_and_hashed() {
+    let (a, b) = (generate_key(), generate_key());
+    assert!(a.starts_with(KEY_PREFIX));
+    assert_eq!(a.len(), KEY_PREFIX.len() + 64);
+    assert_ne!(a, b);
+    assert_eq!(hash_key(&a), hash_key(&a));
+    assert_ne!(hash_key(&a), hash_key(&b));
+    assert_eq!(hash_key(&a).len(), 64);
+}
+
+#[test]
+fn test_new_key_validation() {
+    let key = generate_key();
+    let record = NewApiKey {
+        name: " alice ".to_string(),
+        scopes: vec![Scope::Approve, Scope::Read, Scope::Approve],
+    }
+    .into_key(&key, Some("bootstrap".to_string()), 1_700_000_000)
+    .unwrap();
+    assert_eq!(record.name, "alice");
+    assert_eq!(record.scopes, vec![Scope::Read, Scope::Approve]);
+    assert!(key.starts_with(&record.prefix));
+    assert!(record.prefix.len() < key.len());
+
+    let no_scopes = NewApiKey {
+        name: "alice".to_string(),
+        scopes: Vec::new(),
+    };
+    assert!(no_scopes.into_key(&key, None, 1_700_000_000).is_err());
+}
+
+#[test]
+fn test_presented_key_headers() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(presented_key(&headers), None);
+
+    headers.insert("x-api-key", HeaderValue::from_static("gq_fromheader"));
+    assert_eq!(presented_key(&headers), Some("gq_fromheader"));
+
+    headers.insert("authorization", HeaderValue::from_static("Bearer gq_bearer"));
+    assert_eq!(presented_key(&headers), Some("gq_bearer"));
+}
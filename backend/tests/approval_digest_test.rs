@@ -0,0 +1,85 @@
+use goquant_upgrade_service::multisig::{approval_digest, OnchainProposalCommitment};
+use solana_sdk::pubkey::Pubkey;
+
+/// `UpgradeProposal` account data as the program serializes it
+fn proposal_account(program: &Pubkey, buffer: &Pubkey, code_hash: &[u8; 32], digest: &[u8; 32]) -> Vec<u8> {
+    let mut data = vec![0u8; 8]; // discriminator
+    data.extend_from_slice(&[1; 8]); // id
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // proposer
+    data.extend_from_slice(program.as_ref());
+    data.extend_from_slice(buffer.as_ref());
+    data.extend_from_slice(code_hash);
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(b"v2.0");
+    data.extend_from_slice(&100i64.to_le_bytes()); // proposed_at
+    data.extend_from_slice(&200i64.to_le_bytes()); // timelock_until
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // approvals
+    data.extend_from_slice(&[3, 0]); // approval_threshold, status
+    data.push(0); // executed_at
+    data.extend_from_slice(&0u32.to_le_bytes()); // cancel_votes
+    data.push(0); // cancellation_reason
+    data.extend_from_slice(&0u32.to_le_bytes()); // cancellation_details
+    data.push(255); // bump
+    data.extend_from_slice(&2u32.to_le_bytes()); // target_version
+    data.extend_from_slice(digest);
+    data
+}
+
+/// Known-answer digest, shared with the upgrade-manager program test
+/// `test_approval_digest_matches_backend_vector`
+const APPROVAL_DIGEST_VECTOR: &str = "cb5e7460c9d7f8e9248dc41b8bede8800633a7557496a466699c93bb2d743a4e";
+
+#[test]
+fn test_digest_matches_program_vector() {
+    let digest = approval_digest(
+        &Pubkey::new_from_array([1; 32]),
+        &Pubkey::new_from_array([2; 32]),
+        &[3; 32],
+        7,
+        48 * 60 * 60,
+    );
+    assert_eq!(hex::encode(digest), APPROVAL_DIGEST_VECTOR);
+}
+
+#[test]
+fn test_commitment_decodes_and_verifies() {
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let code_hash = [9u8; 32];
+    let digest = approval_digest(&program, &buffer, &code_hash, 2, 3600);
+    let data = proposal_account(&program, &buffer, &code_hash, &digest);
+
+    let commitment = OnchainProposalCommitment::try_from_account_data(&data, &Pubkey::new_unique()).unwrap();
+
+    assert_eq!(commitment.program, program.to_string());
+    assert_eq!(commitment.description, "v2.0");
+    assert_eq!(commitment.target_version, 2);
+    assert_eq!(commitment.approvals.len(), 1);
+    assert_eq!(commitment.approval_digest, hex::encode(digest));
+    assert!(commitment.verify(3600));
+    // A different timelock is a different commitment
+    assert!(!commitment.verify(60));
+}
+
+#[test]
+fn test_digest_binds_every_field() {
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let base = approval_digest(&program, &buffer, &[1; 32], 1, 3600);
+
+    assert_ne!(base, approval_digest(&Pubkey::new_unique(), &buffer, &[1; 32], 1, 3600));
+    assert_ne!(base, approval_digest(&program, &Pubkey::new_unique(), &[1; 32], 1, 3600));
+    assert_ne!(base, approval_digest(&program, &buffer, &[2; 32], 1, 3600));
+    assert_ne!(base, approval_digest(&program, &buffer, &[1; 32], 2, 3600));
+    assert_ne!(base, approval_digest(&program, &buffer, &[1; 32], 1, 3601));
+}
+
+#[test]
+fn test_truncated_account_rejected() {
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let data = proposal_account(&program, &buffer, &[0; 32], &[0; 32]);
+
+    assert!(OnchainProposalCommitment::try_from_account_data(&data[..data.len() - 1], &program).is_err());
+}
@@ -0,0 +1,34 @@
+use goquant_upgrade_service::approval_links::{link_token, percent_encode, solana_pay_url, verify_link_token};
+
+const MEMBER: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+
+#[test]
+fn test_percent_encode() {
+    assert_eq!(percent_encode("abc-._~019"), "abc-._~019");
+    assert_eq!(percent_encode("https://x.io/a?b=1&c=2"), "https%3A%2F%2Fx.io%2Fa%3Fb%3D1%26c%3D2");
+}
+
+#[test]
+fn test_link_token_binds_proposal_member_and_expiry() {
+    let token = link_token("secret", "proposal-1", MEMBER, 1_700_000_000);
+    assert_eq!(token.len(), 64);
+    assert!(verify_link_token("secret", "proposal-1", MEMBER, 1_700_000_000, &token));
+
+    assert!(!verify_link_token("other", "proposal-1", MEMBER, 1_700_000_000, &token));
+    assert!(!verify_link_token("secret", "proposal-2", MEMBER, 1_700_000_000, &token));
+    assert!(!verify_link_token("secret", "proposal-1", "member2", 1_700_000_000, &token));
+    assert!(!verify_link_token("secret", "proposal-1", MEMBER, 1_700_000_001, &token));
+    assert!(!verify_link_token("secret", "proposal-1", MEMBER, 1_700_000_000, "not-hex"));
+}
+
+#[test]
+fn test_solana_pay_url_is_an_encoded_transaction_request() {
+    let url = solana_pay_url("https://upgrades.example.com/", "proposal-1", MEMBER, 1_700_000_000, "ab12");
+    assert_eq!(
+        url,
+        format!(
+            "solana:https%3A%2F%2Fupgrades.example.com%2Fv1%2Fpublic%2Fapprove-link%2Fproposal-1%3Fmember%3D{}%26expires%3D1700000000%26token%3Dab12",
+            MEMBER
+        )
+    );
+}
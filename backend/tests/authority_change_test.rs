@@ -0,0 +1,32 @@
+use goquant_upgrade_service::proposal::{AuthorityChange, IMMUTABILITY_WARNING};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test_immutability_needs_explicit_confirmation() {
+    let refused = AuthorityChange::new(None, false).unwrap_err();
+    assert!(refused.to_string().contains("IRREVERSIBLE"));
+
+    let change = AuthorityChange::new(None, true).unwrap();
+    assert!(change.is_irreversible());
+    assert_eq!(change.target().unwrap(), Pubkey::default());
+}
+
+#[test]
+fn test_handover_targets_the_new_authority() {
+    let vault = Pubkey::new_unique();
+    let change = AuthorityChange::new(Some(vault), false).unwrap();
+
+    assert!(!change.is_irreversible());
+    assert_eq!(change.new_authority_key().unwrap(), Some(vault));
+    assert_eq!(change.target().unwrap(), vault);
+    assert_eq!(change.describe("Move to the v4 vault"), "Move to the v4 vault");
+}
+
+#[test]
+fn test_irreversible_description_leads_with_the_warning() {
+    let change = AuthorityChange::new(None, true).unwrap();
+    let description = change.describe("Freeze the DEX program");
+
+    assert!(description.starts_with(IMMUTABILITY_WARNING));
+    assert!(description.ends_with("Freeze the DEX program"));
+}
@@ -0,0 +1,48 @@
+use goquant_upgrade_service::backfill::{find_upgrades, LoaderUpgrade};
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+
+#[test]
+fn test_finds_loader_upgrade_instructions() {
+    let (program, buffer, authority, spill) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let message = Message::new(
+        &[
+            system_instruction::transfer(&authority, &spill, 1),
+            bpf_loader_upgradeable::set_upgrade_authority(&program, &authority, Some(&spill)),
+            bpf_loader_upgradeable::upgrade(&program, &buffer, &authority, &spill),
+        ],
+        Some(&authority),
+    );
+
+    let upgrades = find_upgrades(&message.account_keys, &message.instructions);
+    assert_eq!(
+        upgrades,
+        vec![LoaderUpgrade {
+            program,
+            buffer,
+            authority,
+        }]
+    );
+}
+
+#[test]
+fn test_ignores_transactions_without_upgrades() {
+    let (from, to) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let message = Message::new(&[system_instruction::transfer(&from, &to, 1)], Some(&from));
+    assert!(find_upgrades(&message.account_keys, &message.instructions).is_empty());
+
+    // Truncated account lists are skipped rather than misread
+    let mut message = Message::new(
+        &[bpf_loader_upgradeable::upgrade(&to, &to, &from, &from)],
+        Some(&from),
+    );
+    message.instructions[0].accounts.truncate(3);
+    assert!(find_upgrades(&message.account_keys, &message.instructions).is_empty());
+}
@@ -0,0 +1,83 @@
+use goquant_upgrade_service::batch_approval::*;
+use goquant_upgrade_service::proposal::{Proposal, ProposalStatus};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn proposal(id: &str, buffer: &str) -> Proposal {
+    Proposal {
+        id: id.to_string(),
+        proposer: "multisig".to_string(),
+        program: "program_id".to_string(),
+        new_buffer: buffer.to_string(),
+        description: format!("Upgrade {}", id),
+        proposed_at: 1_700_000_000,
+        timelock_until: 1_700_172_800,
+        approvals: vec![],
+        approval_threshold: 3,
+        status: ProposalStatus::Proposed,
+        executed_at: None,
+        source: None,
+        publish_idl: false,
+        cancellation: None,
+        risk_tier: None,
+        attachments: vec![],
+        depends_on: vec![],
+        supersedes: vec![],
+        freeze: None,
+        deployment: None,
+        authority_change: None,
+    }
+}
+
+fn digests(proposals: &[Proposal]) -> Vec<(String, String)> {
+    proposals
+        .iter()
+        .map(|p| (p.id.clone(), proposal_digest(p)))
+        .collect()
+}
+
+#[test]
+fn test_message_lists_every_digest_in_order() {
+    let proposals = [proposal("p1", "BufferA"), proposal("p2", "BufferB")];
+    let message = batch_message("member", &digests(&proposals));
+
+    let lines: Vec<&str> = message.lines().collect();
+    assert_eq!(lines[0], BATCH_APPROVAL_VERSION);
+    assert_eq!(lines[1], "member");
+    assert_eq!(lines[2], format!("p1:{}", proposal_digest(&proposals[0])));
+    assert_eq!(lines[3], format!("p2:{}", proposal_digest(&proposals[1])));
+}
+
+#[test]
+fn test_digest_changes_with_the_buffer() {
+    assert_eq!(proposal_digest(&proposal("p1", "BufferA")), proposal_digest(&proposal("p1", "BufferA")));
+    assert_ne!(proposal_digest(&proposal("p1", "BufferA")), proposal_digest(&proposal("p1", "BufferB")));
+}
+
+#[test]
+fn test_one_signature_covers_the_batch() {
+    let member = Keypair::new();
+    let proposals = [proposal("p1", "BufferA"), proposal("p2", "BufferB")];
+    let message = batch_message(&member.pubkey().to_string(), &digests(&proposals));
+    let signature = member.sign_message(message.as_bytes()).to_string();
+
+    assert!(verify_batch_signature(&member.pubkey().to_string(), &message, &signature).is_ok());
+
+    // A buffer swapped after signing no longer matches
+    let swapped = [proposal("p1", "BufferA"), proposal("p2", "BufferEvil")];
+    let tampered = batch_message(&member.pubkey().to_string(), &digests(&swapped));
+    assert!(verify_batch_signature(&member.pubkey().to_string(), &tampered, &signature).is_err());
+
+    // Nor does another member's key
+    let other = Keypair::new().pubkey().to_string();
+    assert!(verify_batch_signature(&other, &message, &signature).is_err());
+}
+
+#[test]
+fn test_rejects_empty_oversized_and_repeated_batches() {
+    let ids = |count: usize| (0..count).map(|i| format!("p{}", i)).collect::<Vec<_>>();
+
+    assert!(validate_batch(&ids(3)).is_ok());
+    assert!(validate_batch(&[]).is_err());
+    assert!(validate_batch(&ids(MAX_BATCH_SIZE + 1)).is_err());
+    assert!(validate_batch(&["p1".to_string(), "p2".to_string(), "p1".to_string()]).is_err());
+}
@@ -0,0 +1,123 @@
+use goquant_upgrade_service::binary_analysis::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+const TOKEN_2022: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+/// `stxdw [r10 - offset], r1`
+fn store_to_stack(offset: i16) -> [u8; 8] {
+    let mut ix = [0x7b, 0x1a, 0, 0, 0, 0, 0, 0];
+    ix[2..4].copy_from_slice(&(-offset).to_le_bytes());
+    ix
+}
+
+/// A minimal SBF ELF with `.text`, `.rodata` and undefined dynamic symbols
+fn elf(text: &[u8], rodata: &[u8], imports: &[&str]) -> Vec<u8> {
+    let mut dynstr = vec![0u8];
+    let mut dynsym = vec![0u8; 24];
+    for name in imports {
+        let mut symbol = [0u8; 24];
+        symbol[0..4].copy_from_slice(&(dynstr.len() as u32).to_le_bytes());
+        symbol[4] = 0x10; // global, no type
+        dynsym.extend_from_slice(&symbol);
+        dynstr.extend_from_slice(name.as_bytes());
+        dynstr.push(0);
+    }
+
+    let names = b"\0.text\0.rodata\0.dynsym\0.dynstr\0.shstrtab\0".to_vec();
+    // name offset, type, flags, link, contents
+    let sections: [(u32, u32, u64, u32, &[u8]); 5] = [
+        (1, 1, 0x6, 0, text),
+        (7, 1, 0x2, 0, rodata),
+        (15, 11, 0x2, 4, &dynsym),
+        (23, 3, 0x2, 0, &dynstr),
+        (31, 3, 0, 0, &names),
+    ];
+
+    let mut out = vec![0u8; 64];
+    out[..6].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1]);
+    let mut offsets = Vec::new();
+    for (_, _, _, _, data) in &sections {
+        offsets.push(out.len() as u64);
+        out.extend_from_slice(data);
+    }
+
+    let shoff = out.len() as u64;
+    out.extend_from_slice(&[0u8; 64]); // null section
+    for ((name, kind, flags, link, data), offset) in sections.iter().zip(&offsets) {
+        let mut header = [0u8; 64];
+        header[0..4].copy_from_slice(&name.to_le_bytes());
+        header[4..8].copy_from_slice(&kind.to_le_bytes());
+        header[8..16].copy_from_slice(&flags.to_le_bytes());
+        header[24..32].copy_from_slice(&offset.to_le_bytes());
+        header[32..40].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        header[40..44].copy_from_slice(&link.to_le_bytes());
+        out.extend_from_slice(&header);
+    }
+
+    out[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+    out[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+    out[0x3c..0x3e].copy_from_slice(&6u16.to_le_bytes());
+    out[0x3e..0x40].copy_from_slice(&5u16.to_le_bytes());
+    out
+}
+
+#[test]
+fn test_profile_reads_syscalls_programs_and_stack() {
+    let token_2022 = Pubkey::from_str(TOKEN_2022).unwrap();
+    let mut rodata = b"some panic message".to_vec();
+    rodata.extend_from_slice(token_2022.as_ref());
+    let text = [store_to_stack(8), store_to_stack(512), [0x95, 0, 0, 0, 0, 0, 0, 0]].concat();
+
+    let profile = BinaryProfile::analyze(&elf(&text, &rodata, &["sol_log_", "sol_invoke_signed_rust"])).unwrap();
+
+    assert_eq!(
+        profile.syscalls.iter().map(String::as_str).collect::<Vec<_>>(),
+        vec!["sol_invoke_signed_rust", "sol_log_"]
+    );
+    assert!(profile.performs_cpi());
+    assert!(profile.known_programs.contains(TOKEN_2022));
+    assert_eq!(profile.max_stack_offset, 512);
+    assert_eq!(profile.text_size, text.len() as u64);
+}
+
+#[test]
+fn test_rejects_non_elf_input() {
+    assert!(BinaryProfile::analyze(b"not an elf").is_err());
+    assert!(BinaryProfile::analyze(&elf(&[], &[], &[])[..70]).is_err());
+}
+
+#[test]
+fn test_impact_flags_new_cpi_targets_and_syscalls() {
+    let deployed = BinaryProfile::analyze(&elf(&store_to_stack(64), &[], &["sol_log_"])).unwrap();
+
+    let token_2022 = Pubkey::from_str(TOKEN_2022).unwrap();
+    let proposed = elf(
+        &store_to_stack(3800),
+        token_2022.as_ref(),
+        &["sol_log_", "sol_invoke_signed_rust", "sol_get_clock_sysvar"],
+    );
+    let impact = BinaryImpact::compare(Some(&deployed), BinaryProfile::analyze(&proposed).unwrap());
+
+    assert!(impact.compared_to_deployed);
+    assert_eq!(impact.added_syscalls, vec!["sol_get_clock_sysvar", "sol_invoke_signed_rust"]);
+    assert_eq!(impact.added_programs[0].name, "SPL Token-2022");
+    assert_eq!(impact.max_stack_offset_before, Some(64));
+    assert_eq!(impact.max_stack_offset_after, 3800);
+
+    let warnings = impact.warnings.join("\n");
+    assert!(warnings.contains("cross-program invocations"));
+    assert!(warnings.contains("New external program calls detected: SPL Token-2022"));
+    assert!(warnings.contains("New syscalls: sol_get_clock_sysvar"));
+    assert!(warnings.contains("Stack use reaches 3800"));
+}
+
+#[test]
+fn test_unchanged_binary_has_no_warnings() {
+    let binary = elf(&store_to_stack(128), &[], &["sol_log_", "sol_invoke_signed_rust"]);
+    let profile = BinaryProfile::analyze(&binary).unwrap();
+    let impact = BinaryImpact::compare(Some(&profile), profile.clone());
+
+    assert!(impact.warnings.is_empty());
+    assert!(impact.added_syscalls.is_empty() && impact.removed_syscalls.is_empty());
+}
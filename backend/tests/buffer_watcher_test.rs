@@ -0,0 +1,75 @@
+use goquant_upgrade_service::buffer_watcher::*;
+use goquant_upgrade_service::error::UpgradeError;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::pubkey::Pubkey;
+
+fn buffer_data(authority: Option<Pubkey>, program: &[u8]) -> Vec<u8> {
+    let mut data = vec![1, 0, 0, 0];
+    match authority {
+        Some(authority) => {
+            data.push(1);
+            data.extend_from_slice(authority.as_ref());
+        }
+        None => data.extend_from_slice(&[0; 33]),
+    }
+    data.extend_from_slice(program);
+    data
+}
+
+#[test]
+fn test_parse_buffer_splits_authority_and_program() {
+    let vault = Pubkey::new_unique();
+    let data = buffer_data(Some(vault), b"\x7fELF program");
+
+    let (authority, program) = parse_buffer(&data).unwrap();
+
+    assert_eq!(authority, Some(vault));
+    assert_eq!(program, b"\x7fELF program");
+}
+
+#[test]
+fn test_parse_buffer_rejects_other_loader_accounts() {
+    // Immutable buffer: no authority
+    let (authority, _) = parse_buffer(&buffer_data(None, b"")).unwrap();
+    assert_eq!(authority, None);
+
+    // ProgramData tag
+    let mut program_data = buffer_data(Some(Pubkey::new_unique()), b"");
+    program_data[0] = 3;
+    assert!(parse_buffer(&program_data).is_none());
+
+    assert!(parse_buffer(&[1, 0, 0, 0, 1]).is_none());
+}
+
+#[test]
+fn test_buffer_must_be_handed_to_the_vault() {
+    let vault = Pubkey::new_unique();
+    let loader = bpf_loader_upgradeable::id();
+
+    assert!(ensure_buffer_handed_off(&loader, &buffer_data(Some(vault), b"program"), &vault).is_ok());
+
+    // Still held by the deployer, who could rewrite it after approval
+    let deployer = Pubkey::new_unique();
+    let held = ensure_buffer_handed_off(&loader, &buffer_data(Some(deployer), b"program"), &vault);
+    assert!(matches!(held, Err(UpgradeError::BufferNotHandedOff(_))));
+
+    let immutable = ensure_buffer_handed_off(&loader, &buffer_data(None, b"program"), &vault);
+    assert!(matches!(immutable, Err(UpgradeError::BufferNotHandedOff(_))));
+
+    // Same bytes in an account the loader does not own
+    let foreign = ensure_buffer_handed_off(&Pubkey::new_unique(), &buffer_data(Some(vault), b""), &vault);
+    assert!(matches!(foreign, Err(UpgradeError::BufferNotHandedOff(_))));
+}
+
+#[test]
+fn test_status_round_trip() {
+    for status in [
+        DetectedBufferStatus::Pending,
+        DetectedBufferStatus::Proposed,
+        DetectedBufferStatus::Dismissed,
+        DetectedBufferStatus::Closed,
+    ] {
+        assert_eq!(DetectedBufferStatus::parse(status.as_str()), Some(status));
+    }
+    assert_eq!(DetectedBufferStatus::parse("unknown"), None);
+}
@@ -0,0 +1,53 @@
+use goquant_upgrade_service::chain_clock::*;
+use goquant_upgrade_service::timelock::TimelockManager;
+use std::sync::Arc;
+
+fn clock_sysvar(slot: u64, unix_timestamp: i64) -> Vec<u8> {
+    let mut data = slot.to_le_bytes().to_vec();
+    data.extend_from_slice(&1_699_000_000i64.to_le_bytes()); // epoch_start_timestamp
+    data.extend_from_slice(&500u64.to_le_bytes()); // epoch
+    data.extend_from_slice(&501u64.to_le_bytes()); // leader_schedule_epoch
+    data.extend_from_slice(&unix_timestamp.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_decode_clock_sysvar() {
+    let (slot, unix_timestamp) = decode_clock_sysvar(&clock_sysvar(250_000_000, 1_700_000_123)).unwrap();
+    assert_eq!(slot, 250_000_000);
+    assert_eq!(unix_timestamp, 1_700_000_123);
+
+    assert!(decode_clock_sysvar(&[0u8; 39]).is_err());
+}
+
+#[test]
+fn test_now_is_corrected_by_measured_drift() {
+    let clock = ChainClock::system();
+    assert_eq!(clock.drift_seconds(), None);
+
+    let backend_now = chrono::Utc::now().timestamp();
+    let sample = clock.record(42, backend_now - 90, backend_now);
+    assert_eq!(sample.drift_seconds, 90);
+    assert_eq!(clock.last_sample(), Some(sample));
+
+    let now = clock.now();
+    assert!((backend_now - 91..=backend_now - 89).contains(&now), "chain time is 90s behind");
+}
+
+#[tokio::test]
+async fn test_timelock_counts_down_in_chain_time() {
+    let clock = Arc::new(ChainClock::system());
+    let timelock = TimelockManager::new().await.unwrap().with_clock(clock.clone());
+    timelock.set_timelock("p1".to_string(), 60).await.unwrap();
+    let end = timelock.get_timelock_end("p1").await.unwrap();
+
+    // The backend clock jumps 2 minutes ahead of the chain: by system time the
+    // timelock has passed, but the program would still reject execution
+    let backend_now = chrono::Utc::now().timestamp() + 120;
+    clock.record(1, end - 60, backend_now);
+    assert!(!timelock.is_timelock_expired("p1").await.unwrap());
+    assert!(timelock.get_remaining_time("p1").await.unwrap() > 0);
+
+    clock.record(2, end, chrono::Utc::now().timestamp());
+    assert!(timelock.is_timelock_expired("p1").await.unwrap());
+}
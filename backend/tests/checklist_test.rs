@@ -0,0 +1,52 @@
+use goquant_upgrade_service::checklist::*;
+use goquant_upgrade_service::secrets::SecretStore;
+
+fn completion(item: &str, role: &str) -> ChecklistCompletion {
+    ChecklistCompletion {
+        proposal_id: "p1".to_string(),
+        item: item.to_string(),
+        role: role.to_string(),
+        completed_by: None,
+        evidence: None,
+        completed_at: 1_700_000_000,
+    }
+}
+
+#[test]
+fn test_default_checklist_blocks_until_all_items_signed_off() {
+    let items = default_items();
+
+    let status = evaluate(&items, &[completion("audit_uploaded", "auditor")]);
+    assert!(!status.complete);
+    assert_eq!(
+        status.missing,
+        vec!["devnet_canary_passed", "changelog_present", "idl_diff_reviewed"]
+    );
+
+    let all: Vec<ChecklistCompletion> = items.iter().map(|i| completion(&i.key, &i.roles[0])).collect();
+    let status = evaluate(&items, &all);
+    assert!(status.complete);
+    assert!(status.items.iter().all(|i| i.completed));
+}
+
+#[test]
+fn test_optional_items_do_not_block() {
+    let mut items = default_items();
+    items.retain(|i| i.key == "changelog_present");
+    items[0].required = false;
+
+    let status = evaluate(&items, &[]);
+
+    assert!(status.complete);
+    assert!(!status.items[0].completed);
+}
+
+#[test]
+fn test_role_tokens() {
+    std::env::set_var("CHECKLIST_ROLE_TOKENS", "auditor:audit-token, ci:ci-token");
+    let secrets = SecretStore::env();
+
+    assert_eq!(role_for_token("audit-token", &secrets).as_deref(), Some("auditor"));
+    assert_eq!(role_for_token("ci-token", &secrets).as_deref(), Some("ci"));
+    assert_eq!(role_for_token("other", &secrets), None);
+}
@@ -0,0 +1,25 @@
+use goquant_upgrade_service::cluster::{EventAnnouncement, SharedState};
+
+#[test]
+fn test_shared_state_parsing() {
+    assert_eq!(SharedState::parse("").unwrap(), SharedState::Memory);
+    assert_eq!(SharedState::parse("memory").unwrap(), SharedState::Memory);
+    assert_eq!(SharedState::parse(" Postgres ").unwrap(), SharedState::Postgres);
+    assert!(SharedState::parse("redis").is_err());
+}
+
+#[test]
+fn test_event_announcement_round_trip() {
+    let announcement = EventAnnouncement {
+        instance: "upgrades-1".to_string(),
+        seq: 42,
+    };
+    assert_eq!(announcement.to_payload(), "upgrades-1:42");
+    assert_eq!(EventAnnouncement::parse("upgrades-1:42"), Some(announcement));
+
+    // Instance ids may themselves contain colons
+    assert_eq!(EventAnnouncement::parse("host:8080:7").unwrap().instance, "host:8080");
+    assert_eq!(EventAnnouncement::parse("upgrades-1:x"), None);
+    assert_eq!(EventAnnouncement::parse(":42"), None);
+    assert_eq!(EventAnnouncement::parse("42"), None);
+}
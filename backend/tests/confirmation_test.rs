@@ -0,0 +1,150 @@
+use goquant_upgrade_service::confirmation::*;
+use goquant_upgrade_service::faucet::Cluster;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+fn rpc_status(
+    confirmation_status: Option<TransactionConfirmationStatus>,
+    err: Option<TransactionError>,
+) -> TransactionStatus {
+    TransactionStatus {
+        slot: 100,
+        confirmations: None,
+        status: err.clone().map_or(Ok(()), Err),
+        err,
+        confirmation_status,
+    }
+}
+
+#[test]
+fn test_levels_are_ordered() {
+    assert!(ConfirmationLevel::Processed < ConfirmationLevel::Confirmed);
+    assert!(ConfirmationLevel::Confirmed < ConfirmationLevel::Finalized);
+
+    for level in [ConfirmationLevel::Processed, ConfirmationLevel::Confirmed, ConfirmationLevel::Finalized] {
+        assert_eq!(ConfirmationLevel::parse(level.as_str()), Some(level));
+    }
+    assert_eq!(ConfirmationLevel::parse("max"), None);
+}
+
+#[test]
+fn test_status_round_trips() {
+    for status in [
+        ConfirmationStatus::Submitted,
+        ConfirmationStatus::Processed,
+        ConfirmationStatus::Confirmed,
+        ConfirmationStatus::Finalized,
+        ConfirmationStatus::Failed,
+        ConfirmationStatus::Dropped,
+    ] {
+        assert_eq!(ConfirmationStatus::parse(status.as_str()), Some(status));
+    }
+}
+
+#[test]
+fn test_status_from_rpc() {
+    assert_eq!(
+        ConfirmationStatus::from_transaction_status(&rpc_status(Some(TransactionConfirmationStatus::Processed), None)),
+        ConfirmationStatus::Processed
+    );
+    assert_eq!(
+        ConfirmationStatus::from_transaction_status(&rpc_status(Some(TransactionConfirmationStatus::Confirmed), None)),
+        ConfirmationStatus::Confirmed
+    );
+    // Old rooted transactions come back without a confirmation level
+    assert_eq!(
+        ConfirmationStatus::from_transaction_status(&rpc_status(None, None)),
+        ConfirmationStatus::Finalized
+    );
+    // An error wins over the commitment it landed at
+    assert_eq!(
+        ConfirmationStatus::from_transaction_status(&rpc_status(
+            Some(TransactionConfirmationStatus::Finalized),
+            Some(TransactionError::AccountNotFound),
+        )),
+        ConfirmationStatus::Failed
+    );
+}
+
+#[test]
+fn test_reaches_target_commitment() {
+    assert!(!ConfirmationStatus::Submitted.reaches(ConfirmationLevel::Processed));
+    assert!(ConfirmationStatus::Processed.reaches(ConfirmationLevel::Processed));
+    assert!(!ConfirmationStatus::Processed.reaches(ConfirmationLevel::Confirmed));
+    assert!(ConfirmationStatus::Confirmed.reaches(ConfirmationLevel::Confirmed));
+    assert!(!ConfirmationStatus::Confirmed.reaches(ConfirmationLevel::Finalized));
+    assert!(ConfirmationStatus::Finalized.reaches(ConfirmationLevel::Finalized));
+    assert!(ConfirmationStatus::Finalized.reaches(ConfirmationLevel::Processed));
+
+    // Terminal failures never count as landed
+    assert!(!ConfirmationStatus::Failed.reaches(ConfirmationLevel::Processed));
+    assert!(!ConfirmationStatus::Dropped.reaches(ConfirmationLevel::Processed));
+}
+
+#[test]
+fn test_commitment_from_env() {
+    std::env::set_var("EXECUTION_COMMITMENT", "confirmed");
+    assert_eq!(ConfirmationConfig::from_env().target, ConfirmationLevel::Confirmed);
+
+    // Unknown values fall back to the safest level
+    std::env::set_var("EXECUTION_COMMITMENT", "recent");
+    assert_eq!(ConfirmationConfig::from_env().target, ConfirmationLevel::Finalized);
+    std::env::remove_var("EXECUTION_COMMITMENT");
+}
+
+#[test]
+fn test_explorer_links_follow_the_cluster() {
+    let signature = "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+    let mainnet = ExplorerLinks::new(signature, Cluster::Mainnet).unwrap();
+    assert_eq!(mainnet.solana_explorer, format!("https://explorer.solana.com/tx/{}", signature));
+    assert_eq!(mainnet.solscan, format!("https://solscan.io/tx/{}", signature));
+
+    let devnet = ExplorerLinks::new(signature, Cluster::Devnet).unwrap();
+    assert!(devnet.solana_explorer.ends_with("?cluster=devnet"));
+    assert!(devnet.solscan.ends_with("?cluster=devnet"));
+
+    // Public explorers cannot see a local validator
+    assert_eq!(ExplorerLinks::new(signature, Cluster::Local), None);
+}
+
+#[test]
+fn test_transaction_details_read_from_rpc_response() {
+    let response = serde_json::json!({
+        "slot": 245_000_123u64,
+        "blockTime": 1_699_300_000,
+        "transaction": {
+            "signatures": ["5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW"],
+            "message": {
+                "accountKeys": ["11111111111111111111111111111111"],
+                "header": {
+                    "numRequiredSignatures": 1,
+                    "numReadonlySignedAccounts": 0,
+                    "numReadonlyUnsignedAccounts": 0
+                },
+                "recentBlockhash": "11111111111111111111111111111111",
+                "instructions": []
+            }
+        },
+        "meta": {
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 10_000,
+            "preBalances": [],
+            "postBalances": [],
+            "computeUnitsConsumed": 48_211
+        }
+    });
+    let transaction = serde_json::from_value(response).unwrap();
+
+    let details = TransactionDetails::from_transaction(&transaction).unwrap();
+    assert_eq!(
+        details,
+        TransactionDetails {
+            slot: 245_000_123,
+            block_time: Some(1_699_300_000),
+            fee_lamports: 10_000,
+            compute_units: Some(48_211),
+        }
+    );
+}
@@ -0,0 +1,50 @@
+use goquant_upgrade_service::countdown::*;
+use goquant_upgrade_service::proposal::{Proposal, ProposalStatus};
+use goquant_upgrade_service::test_utils::ProposalBuilder;
+
+fn proposal(status: ProposalStatus, timelock_until: i64) -> Proposal {
+    ProposalBuilder::new("p1")
+        .status(status)
+        .timelock_until(timelock_until)
+        .build()
+}
+
+#[test]
+fn test_ticks_every_minute_then_every_second() {
+    // 185s out: 60s steps that land exactly on the final minute
+    let mut remaining = 185;
+    let mut delays = Vec::new();
+    while remaining > 58 {
+        let delay = next_tick_delay(remaining);
+        delays.push(delay);
+        remaining -= delay;
+    }
+    assert_eq!(delays, vec![60, 60, 5, 1, 1]);
+    assert_eq!(next_tick_delay(1), 1);
+}
+
+#[test]
+fn test_countdown_uses_chain_time() {
+    let now = 1_700_000_000;
+    let countdown = Countdown::new(&proposal(ProposalStatus::TimelockActive, now + 125), now, Some(4));
+
+    assert_eq!(countdown.remaining_seconds, 125);
+    assert!(!countdown.expired);
+    assert_eq!(countdown.next_tick_seconds, Some(60));
+    assert_eq!(countdown.chain_time, now);
+    assert_eq!(countdown.clock_drift_seconds, Some(4));
+}
+
+#[test]
+fn test_countdown_stops_at_expiry_and_for_finished_proposals() {
+    let now = 1_700_000_000;
+
+    let expired = Countdown::new(&proposal(ProposalStatus::TimelockActive, now - 10), now, None);
+    assert_eq!(expired.remaining_seconds, 0);
+    assert!(expired.expired);
+    assert_eq!(expired.next_tick_seconds, None);
+
+    let cancelled = Countdown::new(&proposal(ProposalStatus::Cancelled, now + 600), now, None);
+    assert_eq!(cancelled.remaining_seconds, 600);
+    assert_eq!(cancelled.next_tick_seconds, None);
+}
@@ -0,0 +1,146 @@
+//! Queries run against a migrated Postgres database.
+//!
+//! Ignored by default; `scripts/e2e.sh` migrates the database at
+//! `E2E_DATABASE_URL` and runs them with `--ignored`.
+
+use goquant_upgrade_service::backfill::HistoricalUpgrade;
+use goquant_upgrade_service::database::Database;
+use goquant_upgrade_service::proposal::{ProposalEvent, ProposalStatus};
+use serde_json::Value;
+use solana_sdk::signature::{Keypair, Signer};
+
+/// A key no earlier run used; `Pubkey::new_unique` restarts every run
+fn random_key() -> String {
+    Keypair::new().pubkey().to_string()
+}
+
+async fn database() -> Database {
+    let database_url = std::env::var("E2E_DATABASE_URL")
+        .expect("E2E_DATABASE_URL must point at a migrated Postgres database");
+    Database::new(&database_url).await.unwrap()
+}
+
+/// Save a proposal for `program` and approve it into its timelock
+async fn timelocked_proposal(database: &Database, program: &str) -> String {
+    let proposal_id = uuid::Uuid::new_v4().to_string();
+    let proposer = random_key();
+    database
+        .save_proposal(
+            &proposal_id,
+            &proposer,
+            program,
+            &random_key(),
+            "database test",
+            1_700_000_000,
+            1,
+            None,
+        )
+        .await
+        .unwrap();
+    let status = database
+        .approve_proposal_txn(&proposal_id, &proposer, ProposalEvent::ThresholdReached)
+        .await
+        .unwrap();
+    assert_eq!(status, ProposalStatus::TimelockActive);
+    proposal_id
+}
+
+async fn history_row_where(database: &Database, field: &str, value: &str) -> Value {
+    database
+        .list_upgrade_history(1000)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|row| row[field] == value)
+        .unwrap_or_else(|| panic!("no upgrade_history row with {} {}", field, value))
+}
+
+async fn history_row(database: &Database, proposal_id: &str) -> Value {
+    history_row_where(database, "proposal_id", proposal_id).await
+}
+
+#[tokio::test]
+#[ignore = "requires a migrated Postgres database at E2E_DATABASE_URL; run scripts/e2e.sh"]
+async fn test_execute_proposal_records_versioned_history() {
+    let database = database().await;
+    let program = random_key();
+
+    let deploy = timelocked_proposal(&database, &program).await;
+    let status = database
+        .execute_proposal_txn(&deploy, 1_700_000_100, &program, "aa", "deploy")
+        .await
+        .unwrap();
+    assert_eq!(status, ProposalStatus::Executed);
+    assert_eq!(database.get_proposal(&deploy).await.unwrap()["status"], "executed");
+    let row = history_row(&database, &deploy).await;
+    assert_eq!(row["kind"], "deploy");
+    assert_eq!(row["deployment"], true);
+    assert_eq!(row["version"], 1);
+
+    // Upgrades count on from the deployment
+    let upgrade = timelocked_proposal(&database, &program).await;
+    database
+        .execute_proposal_txn(&upgrade, 1_700_000_200, &program, "bb", "upgrade")
+        .await
+        .unwrap();
+    let row = history_row(&database, &upgrade).await;
+    assert_eq!(row["kind"], "upgrade");
+    assert_eq!(row["deployment"], false);
+    assert_eq!(row["version"], 2);
+    assert_eq!(row["new_program_hash"], "bb");
+
+    // An executed proposal cannot run twice
+    assert!(database
+        .execute_proposal_txn(&upgrade, 1_700_000_300, &program, "bb", "upgrade")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires a migrated Postgres database at E2E_DATABASE_URL; run scripts/e2e.sh"]
+async fn test_import_upgrade_history_once_per_signature() {
+    let database = database().await;
+    let upgrade = HistoricalUpgrade {
+        signature: random_key(),
+        slot: 250_000_000,
+        executed_at: 1_700_000_000,
+        program: random_key(),
+        buffer: random_key(),
+        authority: random_key(),
+        success: true,
+        error: None,
+    };
+
+    assert!(database.import_upgrade_history(&upgrade).await.unwrap());
+    assert!(!database.import_upgrade_history(&upgrade).await.unwrap());
+
+    let row = history_row_where(&database, "signature", &upgrade.signature).await;
+    assert_eq!(row["program"], upgrade.program.as_str());
+    assert_eq!(row["imported"], true);
+    assert_eq!(row["proposal_id"], Value::Null);
+}
+
+#[tokio::test]
+#[ignore = "requires a migrated Postgres database at E2E_DATABASE_URL; run scripts/e2e.sh"]
+async fn test_failed_execution_job_is_requeued_then_failed() {
+    let database = database().await;
+    let proposal_id = uuid::Uuid::new_v4().to_string();
+    let job = database
+        .enqueue_execution_job(&uuid::Uuid::new_v4().to_string(), &proposal_id, 3, false)
+        .await
+        .unwrap();
+
+    // A retryable failure goes back to the queue after the backoff
+    database.fail_execution_job(&job.job_id, "rpc timeout", false, 60).await.unwrap();
+    let requeued = database.get_execution_job_for_proposal(&proposal_id).await.unwrap().unwrap();
+    assert_eq!(requeued.status, "queued");
+    assert_eq!(requeued.last_error.as_deref(), Some("rpc timeout"));
+    assert_eq!(requeued.completed_at, None);
+    assert!(requeued.next_attempt_at >= chrono::Utc::now().timestamp() + 55);
+
+    database.fail_execution_job(&job.job_id, "buffer closed", true, 0).await.unwrap();
+    let failed = database.get_execution_job_for_proposal(&proposal_id).await.unwrap().unwrap();
+    assert_eq!(failed.status, "failed");
+    assert_eq!(failed.last_error.as_deref(), Some("buffer closed"));
+    assert!(failed.completed_at.is_some());
+}
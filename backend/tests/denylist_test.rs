@@ -0,0 +1,97 @@
+use goquant_upgrade_service::denylist::*;
+use goquant_upgrade_service::security::SecurityAuditor;
+
+fn entry(kind: DenylistKind, value: &str) -> DenylistEntry {
+    NewDenylistEntry {
+        kind,
+        value: value.to_string(),
+        reason: "known bad".to_string(),
+        added_by: None,
+    }
+    .into_entry(0)
+    .unwrap()
+}
+
+/// ELF header declaring one 64-byte section header table at `shoff`
+fn elf_with_padding(body: &[u8], padding: usize) -> Vec<u8> {
+    let mut elf = vec![0u8; 64];
+    elf[..4].copy_from_slice(b"\x7fELF");
+    elf.extend_from_slice(body);
+    let shoff = elf.len() as u64;
+    elf.extend_from_slice(&[0u8; 64]);
+    elf[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+    elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x3c..0x3e].copy_from_slice(&1u16.to_le_bytes());
+    elf.resize(elf.len() + padding, 0);
+    elf
+}
+
+#[test]
+fn test_new_entries_are_normalized_and_validated() {
+    let hash = entry(DenylistKind::Hash, &format!("0x{}", "AB".repeat(32)));
+    assert_eq!(hash.value, "ab".repeat(32));
+
+    let pattern = entry(DenylistKind::Pattern, "DE AD ?? be ef");
+    assert_eq!(pattern.value, "dead??beef");
+
+    let invalid = [
+        (DenylistKind::Hash, "abcd"),
+        (DenylistKind::Hash, &"zz".repeat(32)),
+        (DenylistKind::Pattern, "dead??"),
+        (DenylistKind::Pattern, "deadbee"),
+        (DenylistKind::Pattern, "de ad be xx"),
+    ];
+    for (kind, value) in invalid {
+        let new = NewDenylistEntry {
+            kind,
+            value: value.to_string(),
+            reason: "known bad".to_string(),
+            added_by: None,
+        };
+        assert!(new.into_entry(0).is_err(), "{} should be rejected", value);
+    }
+
+    let no_reason = NewDenylistEntry {
+        kind: DenylistKind::Pattern,
+        value: "deadbeef".to_string(),
+        reason: " ".to_string(),
+        added_by: None,
+    };
+    assert!(no_reason.into_entry(0).is_err());
+}
+
+#[test]
+fn test_patterns_match_with_wildcards() {
+    let entries = vec![
+        entry(DenylistKind::Pattern, "01 02 ?? 04 05"),
+        entry(DenylistKind::Pattern, "aa bb cc dd"),
+    ];
+    let binary = [0xff, 0x01, 0x02, 0x77, 0x04, 0x05, 0xff];
+
+    let matches = find_matches(&entries, &binary);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].value, "0102??0405");
+    assert!(find_matches(&entries, &binary[..5]).is_empty());
+}
+
+#[test]
+fn test_hash_matches_padded_on_chain_bytes() {
+    let artifact = elf_with_padding(b"program code", 0);
+    let on_chain = elf_with_padding(b"program code", 4096);
+    assert_eq!(elf_file(&on_chain), &artifact[..]);
+
+    let hash = hex::encode(SecurityAuditor::calculate_program_hash(&artifact));
+    let entries = vec![entry(DenylistKind::Hash, &hash)];
+
+    assert_eq!(find_matches(&entries, &artifact).len(), 1);
+    assert_eq!(find_matches(&entries, &on_chain).len(), 1);
+    assert!(find_matches(&entries, &elf_with_padding(b"patched code", 4096)).is_empty());
+}
+
+#[test]
+fn test_describe_lists_reasons() {
+    let matches = vec![entry(DenylistKind::Pattern, "deadbeef")];
+    let summary = describe(&matches);
+    assert!(summary.starts_with("known bad (pattern "));
+    assert!(summary.contains(&matches[0].id));
+}
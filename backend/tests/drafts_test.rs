@@ -0,0 +1,101 @@
+use goquant_upgrade_service::drafts::*;
+use goquant_upgrade_service::proposal::RiskTier;
+use solana_sdk::pubkey::Pubkey;
+
+fn complete_draft() -> ProposalDraft {
+    ProposalDraft::new(
+        "alice",
+        DraftChanges {
+            new_buffer: Some(Pubkey::new_unique().to_string()),
+            description: Some("Upgrade to v2".to_string()),
+            ..Default::default()
+        },
+        100,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_new_draft_starts_at_revision_one() {
+    let draft = ProposalDraft::new("alice", DraftChanges::default(), 100).unwrap();
+
+    assert_eq!(draft.revision, 1);
+    assert_eq!(draft.status, DraftStatus::Draft);
+    assert_eq!(draft.risk_tier, RiskTier::Medium);
+    assert!(draft.check_submittable(0).is_err(), "no buffer or description yet");
+    assert!(ProposalDraft::new(" ", DraftChanges::default(), 100).is_err());
+}
+
+#[test]
+fn test_edit_keeps_unset_fields_and_rejects_bad_buffer() {
+    let mut draft = complete_draft();
+    let buffer = draft.new_buffer.clone();
+
+    draft
+        .edit(
+            DraftChanges {
+                risk_tier: Some(RiskTier::Critical),
+                ..Default::default()
+            },
+            200,
+        )
+        .unwrap();
+    assert_eq!(draft.revision, 2);
+    assert_eq!(draft.new_buffer, buffer);
+    assert_eq!(draft.description, "Upgrade to v2");
+    assert_eq!(draft.risk_tier, RiskTier::Critical);
+    assert_eq!(draft.updated_at, 200);
+
+    let bad = DraftChanges {
+        new_buffer: Some("not-a-pubkey".to_string()),
+        ..Default::default()
+    };
+    assert!(draft.edit(bad, 300).is_err());
+    assert_eq!(draft.revision, 2);
+}
+
+#[test]
+fn test_reviews_only_count_for_current_revision() {
+    let mut draft = complete_draft();
+    draft.review("bob", ReviewVerdict::Approve, None, 110).unwrap();
+    draft.review("carol", ReviewVerdict::Approve, None, 120).unwrap();
+    assert_eq!(draft.approvals(), vec!["bob", "carol"]);
+    assert!(draft.check_submittable(2).is_ok());
+
+    draft
+        .edit(
+            DraftChanges {
+                description: Some("Upgrade to v2, with changelog".to_string()),
+                ..Default::default()
+            },
+            130,
+        )
+        .unwrap();
+    assert!(draft.approvals().is_empty());
+    assert_eq!(draft.reviews.len(), 2, "earlier reviews stay in the history");
+    assert!(draft.check_submittable(1).is_err());
+}
+
+#[test]
+fn test_change_requests_block_submission_until_revised() {
+    let mut draft = complete_draft();
+    draft.review("bob", ReviewVerdict::RequestChanges, Some("add audit".to_string()), 110).unwrap();
+    draft.review("carol", ReviewVerdict::Approve, None, 120).unwrap();
+    assert!(draft.check_submittable(1).is_err());
+
+    // A reviewer's newer verdict on the same revision replaces the old one
+    draft.review("bob", ReviewVerdict::Approve, None, 130).unwrap();
+    assert_eq!(draft.reviews.len(), 2);
+    assert!(draft.check_submittable(2).is_ok());
+}
+
+#[test]
+fn test_authors_cannot_review_and_closed_drafts_are_frozen() {
+    let mut draft = complete_draft();
+    assert!(draft.review("alice", ReviewVerdict::Approve, None, 110).is_err());
+
+    draft.status = DraftStatus::Submitted;
+    assert!(draft.edit(DraftChanges::default(), 120).is_err());
+    assert!(draft.review("bob", ReviewVerdict::Approve, None, 120).is_err());
+    assert!(draft.check_submittable(0).is_err());
+}
@@ -0,0 +1,178 @@
+//! Processes the end-to-end tests run against: a `solana-test-validator`
+//! with upgrade-manager preloaded, and the upgrade service binary.
+
+use crate::program;
+use reqwest::StatusCode;
+use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{Keypair, Signer};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free port")
+}
+
+pub fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// The upgrade-manager build to preload, from `UPGRADE_MANAGER_SO` or `anchor build` output
+fn program_so() -> PathBuf {
+    let path = std::env::var("UPGRADE_MANAGER_SO")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| repo_root().join("target/deploy/upgrade_manager.so"));
+    assert!(
+        path.exists(),
+        "{} not found; run `anchor build` or set UPGRADE_MANAGER_SO",
+        path.display()
+    );
+    path
+}
+
+/// A local validator on free ports with upgrade-manager deployed as an
+/// upgradeable program whose upgrade authority is `payer`
+pub struct TestValidator {
+    process: Child,
+    ledger: PathBuf,
+    pub rpc_url: String,
+    pub payer: Keypair,
+}
+
+impl TestValidator {
+    pub fn start() -> Self {
+        let binary = std::env::var("SOLANA_TEST_VALIDATOR")
+            .unwrap_or_else(|_| "solana-test-validator".to_string());
+        let payer = Keypair::new();
+        let rpc_port = free_port();
+        let ledger = std::env::temp_dir().join(format!("goquant-e2e-ledger-{}", rpc_port));
+
+        let process = Command::new(&binary)
+            .arg("--reset")
+            .arg("--quiet")
+            .arg("--ledger")
+            .arg(&ledger)
+            .args(["--rpc-port", &rpc_port.to_string()])
+            .args(["--faucet-port", &free_port().to_string()])
+            .args(["--mint", &payer.pubkey().to_string()])
+            .arg("--upgradeable-program")
+            .arg(program::PROGRAM_ID)
+            .arg(program_so())
+            .arg(payer.pubkey().to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .unwrap_or_else(|e| panic!("failed to start {}: {}", binary, e));
+
+        let validator = Self {
+            process,
+            ledger,
+            rpc_url: format!("http://127.0.0.1:{}", rpc_port),
+            payer,
+        };
+
+        let rpc = validator.rpc();
+        let started = Instant::now();
+        while rpc.get_health().is_err() || rpc.get_balance(&validator.payer.pubkey()).unwrap_or(0) == 0 {
+            assert!(started.elapsed() < STARTUP_TIMEOUT, "validator did not become healthy");
+            std::thread::sleep(Duration::from_millis(500));
+        }
+
+        validator
+    }
+
+    pub fn rpc(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.rpc_url.clone(), CommitmentConfig::confirmed())
+    }
+}
+
+impl Drop for TestValidator {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_dir_all(&self.ledger);
+    }
+}
+
+/// The upgrade service binary pointed at a `TestValidator`. Needs a migrated
+/// Postgres database at `E2E_DATABASE_URL`.
+pub struct Backend {
+    process: Child,
+    pub base_url: String,
+}
+
+impl Backend {
+    pub async fn start(validator: &TestValidator, extra_env: &[(&str, &str)]) -> Self {
+        let database_url = std::env::var("E2E_DATABASE_URL")
+            .expect("E2E_DATABASE_URL must point at a migrated Postgres database");
+        let port = free_port();
+
+        let process = Command::new(env!("CARGO_BIN_EXE_goquant-upgrade-service"))
+            .env("BIND_ADDR", format!("127.0.0.1:{}", port))
+            .env("DATABASE_URL", database_url)
+            .env("SOLANA_RPC_URL", &validator.rpc_url)
+            .env("UPGRADE_MANAGER_PROGRAM_ID", program::PROGRAM_ID)
+            .envs(extra_env.iter().copied())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .expect("failed to start goquant-upgrade-service");
+
+        let backend = Self {
+            process,
+            base_url: format!("http://127.0.0.1:{}", port),
+        };
+
+        let health_url = format!("{}/v1/monitoring/health", backend.base_url);
+        let started = Instant::now();
+        loop {
+            if let Ok(response) = reqwest::get(&health_url).await {
+                if response.status().is_success() {
+                    break;
+                }
+            }
+            assert!(started.elapsed() < STARTUP_TIMEOUT, "service did not start");
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        backend
+    }
+
+    /// GET `/v1{path}`, returning the status and JSON body
+    pub async fn get(&self, path: &str) -> (StatusCode, Value) {
+        let response = reqwest::get(format!("{}/v1{}", self.base_url, path)).await.unwrap();
+        (response.status(), response.json().await.unwrap_or(Value::Null))
+    }
+
+    /// POST to `/v1{path}` with `headers` and an optional JSON body,
+    /// returning the status and JSON body
+    pub async fn post(&self, path: &str, headers: &[(&str, &str)], body: Option<Value>) -> (StatusCode, Value) {
+        let mut request = reqwest::Client::new().post(format!("{}/v1{}", self.base_url, path));
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = request.send().await.unwrap();
+        (response.status(), response.json().await.unwrap_or(Value::Null))
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
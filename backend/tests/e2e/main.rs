@@ -0,0 +1,236 @@
+//! End-to-end tests against a local `solana-test-validator`.
+//!
+//! Ignored by default; `scripts/e2e.sh` builds the program, migrates the
+//! database and runs them with `--ignored`.
+
+mod harness;
+mod program;
+
+use goquant_upgrade_service::multisig::{approval_digest, OnchainMultisigConfig, OnchainProgramMeta};
+use goquant_upgrade_service::receipts::SignedReceipt;
+use harness::{Backend, TestValidator};
+use program::{ProposalState, UpgradeStatus};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use std::time::Duration;
+
+/// Short enough to wait out in a test, long enough to observe
+const TIMELOCK_SECONDS: i64 = 5;
+
+/// Bytes written per loader `Write` instruction, to stay under the packet limit
+const WRITE_CHUNK: usize = 900;
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction], signers: &[&Keypair]) -> Result<Signature, String> {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), all_signers.as_slice(), blockhash);
+    rpc.send_and_confirm_transaction(&tx).map_err(|e| e.to_string())
+}
+
+fn funded_keypair(rpc: &RpcClient, payer: &Keypair) -> Keypair {
+    let keypair = Keypair::new();
+    send(rpc, payer, &[system_instruction::transfer(&payer.pubkey(), &keypair.pubkey(), 1_000_000_000)], &[])
+        .expect("funding transfer failed");
+    keypair
+}
+
+/// Upload `program_bytes` to a new loader buffer and return its address
+fn write_buffer(rpc: &RpcClient, payer: &Keypair, program_bytes: &[u8]) -> Pubkey {
+    let buffer = Keypair::new();
+    let lamports = rpc
+        .get_minimum_balance_for_rent_exemption(UpgradeableLoaderState::size_of_buffer(program_bytes.len()))
+        .unwrap();
+    let create = bpf_loader_upgradeable::create_buffer(
+        &payer.pubkey(),
+        &buffer.pubkey(),
+        &payer.pubkey(),
+        lamports,
+        program_bytes.len(),
+    )
+    .unwrap();
+    send(rpc, payer, &create, &[&buffer]).expect("create_buffer failed");
+
+    for (i, chunk) in program_bytes.chunks(WRITE_CHUNK).enumerate() {
+        let write = bpf_loader_upgradeable::write(
+            &buffer.pubkey(),
+            &payer.pubkey(),
+            (i * WRITE_CHUNK) as u32,
+            chunk.to_vec(),
+        );
+        send(rpc, payer, &[write], &[]).expect("buffer write failed");
+    }
+
+    buffer.pubkey()
+}
+
+fn proposal_state(rpc: &RpcClient, proposal: &Pubkey) -> ProposalState {
+    ProposalState::decode(&rpc.get_account_data(proposal).unwrap())
+}
+
+#[test]
+#[ignore = "requires solana-test-validator and a built upgrade_manager.so; run scripts/e2e.sh"]
+fn test_onchain_upgrade_flow() {
+    let validator = TestValidator::start();
+    let rpc = validator.rpc();
+    let proposer = &validator.payer;
+    let member2 = funded_keypair(&rpc, proposer);
+    let member3 = funded_keypair(&rpc, proposer);
+    let outsider = funded_keypair(&rpc, proposer);
+    let members = [proposer.pubkey(), member2.pubkey(), member3.pubkey()];
+
+    send(&rpc, proposer, &[program::initialize(&proposer.pubkey(), &members, 3, TIMELOCK_SECONDS)], &[])
+        .expect("initialize failed");
+    let config = OnchainMultisigConfig::try_from_account_data(
+        &rpc.get_account_data(&program::multisig_config()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(config.members, members);
+    assert_eq!(config.threshold, 3);
+    assert_eq!(config.upgrade_authority, proposer.pubkey());
+
+    let program_bytes: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+    let code_hash: [u8; 32] = Sha256::digest(&program_bytes).into();
+    let buffer = write_buffer(&rpc, proposer, &program_bytes);
+    let target = program::program_id();
+    let proposal = program::proposal(&target, &buffer);
+
+    send(
+        &rpc,
+        proposer,
+        &[program::propose_upgrade(&proposer.pubkey(), &target, &buffer, "e2e upgrade", code_hash)],
+        &[],
+    )
+    .expect("propose_upgrade failed");
+    let state = proposal_state(&rpc, &proposal);
+    assert_eq!(state.status, UpgradeStatus::Proposed);
+    assert_eq!(state.new_buffer, buffer);
+    assert_eq!(state.code_hash, code_hash);
+    assert_eq!(state.approvals, vec![proposer.pubkey()]);
+    assert_eq!(state.target_version, 1);
+    let digest = state.approval_digest;
+    assert_eq!(digest, approval_digest(&target, &buffer, &code_hash, 1, TIMELOCK_SECONDS));
+
+    // Only members may approve
+    assert!(send(&rpc, &outsider, &[program::approve_upgrade(&outsider.pubkey(), &proposal, &digest)], &[]).is_err());
+
+    send(&rpc, &member2, &[program::approve_upgrade(&member2.pubkey(), &proposal, &digest)], &[]).unwrap();
+    assert_eq!(proposal_state(&rpc, &proposal).status, UpgradeStatus::Approved);
+
+    // Executing below threshold is rejected
+    assert!(send(&rpc, proposer, &[program::execute_upgrade(&proposer.pubkey(), &proposal, &target)], &[]).is_err());
+
+    send(&rpc, &member3, &[program::approve_upgrade(&member3.pubkey(), &proposal, &digest)], &[]).unwrap();
+    let state = proposal_state(&rpc, &proposal);
+    assert_eq!(state.status, UpgradeStatus::TimelockActive);
+    assert_eq!(state.approvals.len(), 3);
+
+    // The timelock holds until it expires
+    assert!(send(&rpc, proposer, &[program::execute_upgrade(&proposer.pubkey(), &proposal, &target)], &[]).is_err());
+    assert_eq!(proposal_state(&rpc, &proposal).status, UpgradeStatus::TimelockActive);
+
+    while (rpc.get_block_time(rpc.get_slot().unwrap()).unwrap_or(0)) < state.timelock_until {
+        std::thread::sleep(Duration::from_millis(500));
+    }
+
+    // Non-members cannot execute even once the timelock has passed
+    assert!(send(&rpc, &outsider, &[program::execute_upgrade(&outsider.pubkey(), &proposal, &target)], &[]).is_err());
+
+    send(&rpc, proposer, &[program::execute_upgrade(&proposer.pubkey(), &proposal, &target)], &[])
+        .expect("execute_upgrade failed after the timelock");
+    let state = proposal_state(&rpc, &proposal);
+    assert_eq!(state.status, UpgradeStatus::Executed);
+    assert!(state.executed_at.unwrap() >= state.timelock_until);
+
+    let meta_account = program::program_meta(&target);
+    let meta = OnchainProgramMeta::try_from_account_data(&rpc.get_account_data(&meta_account).unwrap(), &meta_account)
+        .unwrap();
+    assert_eq!(meta.program, target.to_string());
+    assert_eq!(meta.version, 1);
+    assert_eq!(meta.last_proposal, proposal.to_string());
+    assert_eq!(meta.code_hash, hex::encode(code_hash));
+
+    // An executed proposal cannot run twice
+    assert!(send(&rpc, proposer, &[program::execute_upgrade(&proposer.pubkey(), &proposal, &target)], &[]).is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires solana-test-validator, Postgres at E2E_DATABASE_URL and a built upgrade_manager.so; run scripts/e2e.sh"]
+async fn test_api_upgrade_flow() {
+    let validator = tokio::task::spawn_blocking(TestValidator::start).await.unwrap();
+    let payer = Keypair::from_bytes(&validator.payer.to_bytes()).unwrap();
+    let rpc_url = validator.rpc_url.clone();
+    let buffer = tokio::task::spawn_blocking(move || {
+        write_buffer(&RpcClient::new(rpc_url), &payer, b"e2e program bytes")
+    })
+    .await
+    .unwrap();
+
+    let backend = Backend::start(
+        &validator,
+        &[
+            ("CHECKLIST_ROLE_TOKENS", "auditor:e2e-auditor,release_manager:e2e-release,ci:e2e-ci,reviewer:e2e-reviewer"),
+            ("EXECUTOR_TOKENS", "e2e-executor"),
+        ],
+    )
+    .await;
+    let (status, proposed) = backend
+        .post(
+            "/upgrade/propose",
+            &[],
+            Some(json!({
+                "new_program_buffer": buffer.to_string(),
+                "description": "e2e upgrade",
+                "publish_idl": false,
+            })),
+        )
+        .await;
+    assert!(status.is_success(), "propose failed: {} {}", status, proposed);
+    let proposal_id = proposed["proposal_id"].as_str().unwrap().to_string();
+
+    let (_, view) = backend.get(&format!("/upgrade/{}/status", proposal_id)).await;
+    assert_eq!(view["status"], "proposed");
+    assert_eq!(view["timelock_until"], proposed["timelock_until"]);
+
+    let (status, approval) = backend.post(&format!("/upgrade/{}/approve", proposal_id), &[], None).await;
+    assert!(status.is_success(), "approve failed: {} {}", status, approval);
+    assert_eq!(approval["proposal_id"], proposal_id.as_str());
+    let receipt: SignedReceipt = serde_json::from_value(approval["receipt"].clone()).unwrap();
+    assert!(receipt.verify(), "approval receipt does not verify");
+    assert_eq!(receipt.receipt.proposal_id, proposal_id);
+    assert_eq!(receipt.receipt.buffer, buffer.to_string());
+
+    // Execution is refused until every checklist item is signed off
+    let execute = format!("/upgrade/{}/execute?force=true", proposal_id);
+    let executor = [("x-executor-token", "e2e-executor")];
+    let (status, _) = backend.post(&execute, &executor, None).await;
+    assert_eq!(status.as_u16(), 409);
+
+    for (item, token) in [
+        ("audit_uploaded", "e2e-auditor"),
+        ("devnet_canary_passed", "e2e-ci"),
+        ("changelog_present", "e2e-release"),
+        ("idl_diff_reviewed", "e2e-reviewer"),
+    ] {
+        let (status, _) = backend
+            .post(
+                &format!("/upgrade/{}/checklist/{}", proposal_id, item),
+                &[("X-Checklist-Token", token)],
+                None,
+            )
+            .await;
+        assert!(status.is_success(), "{} sign-off failed: {}", item, status);
+    }
+
+    let (status, queued) = backend.post(&execute, &executor, None).await;
+    assert!(status.is_success(), "execute was not accepted: {} {}", status, queued);
+    assert_eq!(queued["proposal_id"], proposal_id.as_str());
+    assert!(!queued["job_id"].as_str().unwrap().is_empty());
+}
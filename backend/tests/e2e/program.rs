@@ -0,0 +1,225 @@
+//! Instruction builders and account decoding for the upgrade-manager program.
+//! Hand-rolled so the harness does not depend on the program crate, which is
+//! built against a different Solana version.
+
+use sha2::{Digest, Sha256};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{system_program, sysvar};
+use std::str::FromStr;
+
+pub const PROGRAM_ID: &str = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS";
+
+pub fn program_id() -> Pubkey {
+    Pubkey::from_str(PROGRAM_ID).unwrap()
+}
+
+fn discriminator(instruction: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", instruction).as_bytes());
+    hash[..8].try_into().unwrap()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &program_id()).0
+}
+
+pub fn multisig_config() -> Pubkey {
+    pda(&[b"multisig_config"])
+}
+
+pub fn program_upgrade_state() -> Pubkey {
+    pda(&[b"program_upgrade_state"])
+}
+
+pub fn proposal(program: &Pubkey, buffer: &Pubkey) -> Pubkey {
+    pda(&[b"proposal", program.as_ref(), buffer.as_ref()])
+}
+
+pub fn program_meta(program: &Pubkey) -> Pubkey {
+    pda(&[b"program_meta", program.as_ref()])
+}
+
+fn push_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+pub fn initialize(authority: &Pubkey, members: &[Pubkey], threshold: u8, timelock_duration: i64) -> Instruction {
+    let mut data = discriminator("initialize").to_vec();
+    data.extend_from_slice(&(members.len() as u32).to_le_bytes());
+    for member in members {
+        data.extend_from_slice(member.as_ref());
+    }
+    data.push(threshold);
+    data.extend_from_slice(&timelock_duration.to_le_bytes());
+
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(multisig_config(), false),
+            AccountMeta::new(program_upgrade_state(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data,
+    }
+}
+
+pub fn propose_upgrade(
+    proposer: &Pubkey,
+    program: &Pubkey,
+    buffer: &Pubkey,
+    description: &str,
+    code_hash: [u8; 32],
+) -> Instruction {
+    let mut data = discriminator("propose_upgrade").to_vec();
+    data.extend_from_slice(buffer.as_ref());
+    push_string(&mut data, description);
+    data.extend_from_slice(&code_hash);
+
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(multisig_config(), false),
+            AccountMeta::new_readonly(program_upgrade_state(), false),
+            AccountMeta::new_readonly(*program, false),
+            AccountMeta::new(proposal(program, buffer), false),
+            AccountMeta::new_readonly(*buffer, false),
+            AccountMeta::new_readonly(program_meta(program), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+pub fn approve_upgrade(approver: &Pubkey, proposal: &Pubkey, approval_digest: &[u8; 32]) -> Instruction {
+    let mut data = discriminator("approve_upgrade").to_vec();
+    data.extend_from_slice(proposal.as_ref());
+    data.extend_from_slice(approval_digest);
+
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(*approver, true),
+            AccountMeta::new_readonly(multisig_config(), false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(program_upgrade_state(), false),
+        ],
+        data,
+    }
+}
+
+pub fn execute_upgrade(executor: &Pubkey, proposal: &Pubkey, program: &Pubkey) -> Instruction {
+    let mut data = discriminator("execute_upgrade").to_vec();
+    data.extend_from_slice(proposal.as_ref());
+
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(*executor, true),
+            AccountMeta::new_readonly(multisig_config(), false),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new_readonly(program_upgrade_state(), false),
+            AccountMeta::new(program_meta(program), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// `UpgradeStatus` discriminants, in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeStatus {
+    Proposed,
+    Approved,
+    TimelockActive,
+    Executed,
+    Cancelled,
+}
+
+/// The fields of an `UpgradeProposal` account the tests assert on
+#[derive(Debug)]
+pub struct ProposalState {
+    pub new_buffer: Pubkey,
+    pub code_hash: [u8; 32],
+    pub timelock_until: i64,
+    pub approvals: Vec<Pubkey>,
+    pub status: UpgradeStatus,
+    pub executed_at: Option<i64>,
+    pub target_version: u32,
+    pub approval_digest: [u8; 32],
+}
+
+impl ProposalState {
+    pub fn decode(data: &[u8]) -> Self {
+        let mut reader = Reader { data, offset: 8 };
+        reader.skip(8 + 32 + 32); // id, proposer, program
+        let new_buffer = reader.pubkey();
+        let code_hash = reader.take(32).try_into().unwrap();
+        let description_len = reader.u32() as usize;
+        reader.skip(description_len + 8); // description, proposed_at
+        let timelock_until = reader.i64();
+        let approvals = (0..reader.u32()).map(|_| reader.pubkey()).collect();
+        reader.skip(1); // approval_threshold
+        let status = match reader.take(1)[0] {
+            0 => UpgradeStatus::Proposed,
+            1 => UpgradeStatus::Approved,
+            2 => UpgradeStatus::TimelockActive,
+            3 => UpgradeStatus::Executed,
+            4 => UpgradeStatus::Cancelled,
+            other => panic!("unknown UpgradeStatus {}", other),
+        };
+        let executed_at = (reader.take(1)[0] == 1).then(|| reader.i64());
+        let cancel_votes = reader.u32() as usize;
+        reader.skip(cancel_votes * 32);
+        if reader.take(1)[0] == 1 {
+            reader.skip(1); // cancellation_reason
+        }
+        let details_len = reader.u32() as usize;
+        reader.skip(details_len + 1); // cancellation_details, bump
+        let target_version = reader.u32();
+        let approval_digest = reader.take(32).try_into().unwrap();
+
+        Self {
+            new_buffer,
+            code_hash,
+            timelock_until,
+            approvals,
+            status,
+            executed_at,
+            target_version,
+            approval_digest,
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        bytes
+    }
+
+    fn skip(&mut self, len: usize) {
+        self.offset += len;
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take(4).try_into().unwrap())
+    }
+
+    fn i64(&mut self) -> i64 {
+        i64::from_le_bytes(self.take(8).try_into().unwrap())
+    }
+
+    fn pubkey(&mut self) -> Pubkey {
+        Pubkey::new_from_array(self.take(32).try_into().unwrap())
+    }
+}
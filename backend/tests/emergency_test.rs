@@ -0,0 +1,100 @@
+use base64::Engine;
+use goquant_upgrade_service::emergency::*;
+use goquant_upgrade_service::indexer::{decode_event, event_discriminator};
+use solana_sdk::pubkey::Pubkey;
+
+fn push_timestamp(data: &mut Vec<u8>, timestamp: Option<i64>) {
+    match timestamp {
+        Some(timestamp) => {
+            data.push(1);
+            data.extend_from_slice(&timestamp.to_le_bytes());
+        }
+        None => data.push(0),
+    }
+}
+
+fn push_keys(data: &mut Vec<u8>, keys: &[Pubkey]) {
+    data.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+    for key in keys {
+        data.extend_from_slice(key.as_ref());
+    }
+}
+
+#[test]
+fn test_decodes_pause_registry() {
+    let (program, vault, account) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = vec![0; 8];
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(program.as_ref());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&[0xca, 0xfe]);
+    push_keys(&mut data, &[vault]);
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    push_timestamp(&mut data, Some(1_700_000_600));
+    data.extend_from_slice(&[2, 254]);
+
+    let registry = OnchainPauseRegistry::try_from_account_data(&data, &account).unwrap();
+    assert_eq!(registry.emergency_quorum, 2);
+    let entry = registry.get(&program.to_string()).unwrap();
+    assert_eq!(entry.pause_data, "cafe");
+    assert_eq!(entry.pause_account_keys().unwrap(), vec![vault]);
+    assert_eq!(entry.paused_at, Some(1_700_000_600));
+    assert!(registry.get(&Pubkey::new_unique().to_string()).is_none());
+
+    assert!(OnchainPauseRegistry::try_from_account_data(&data[..40], &account).is_err());
+}
+
+#[test]
+fn test_pause_votes_lapse_after_the_window() {
+    let (program, account, member) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let reason = "exploit in settlement";
+    let mut data = vec![0; 8];
+    data.extend_from_slice(program.as_ref());
+    push_keys(&mut data, &[member]);
+    data.extend_from_slice(&(reason.len() as u32).to_le_bytes());
+    data.extend_from_slice(reason.as_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    push_timestamp(&mut data, None);
+    data.push(255);
+
+    let votes = OnchainPauseVotes::try_from_account_data(&data, &account).unwrap();
+    assert_eq!(votes.votes, vec![member.to_string()]);
+    assert_eq!(votes.reason, reason);
+    assert_eq!(votes.live_votes(1_700_000_000 + EMERGENCY_VOTE_WINDOW), 1);
+    assert_eq!(votes.live_votes(1_700_000_001 + EMERGENCY_VOTE_WINDOW), 0);
+    assert_eq!(OnchainPauseVotes::empty(&program, &account).live_votes(0), 0);
+}
+
+#[test]
+fn test_pause_needs_a_bounded_reason() {
+    let request = |reason: &str| EmergencyPauseRequest { reason: reason.to_string() };
+    assert!(request("oracle drift on SOL-PERP").validate().is_ok());
+    assert!(request(" ").validate().is_err());
+    assert!(request(&"x".repeat(MAX_PAUSE_REASON_LEN + 1)).validate().is_err());
+
+    // A vote only paused the program when it moved `paused_at` forward
+    assert!(pause_executed(None, Some(1_700_000_000)));
+    assert!(pause_executed(Some(1_600_000_000), Some(1_700_000_000)));
+    assert!(!pause_executed(Some(1_600_000_000), Some(1_600_000_000)));
+    assert!(!pause_executed(None, None));
+}
+
+#[test]
+fn test_indexes_executed_pause() {
+    let (program, first, second) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let reason = "exploit in settlement";
+    let mut data = event_discriminator("EmergencyPauseExecutedEvent").to_vec();
+    data.extend_from_slice(program.as_ref());
+    push_keys(&mut data, &[first, second]);
+    data.extend_from_slice(&(reason.len() as u32).to_le_bytes());
+    data.extend_from_slice(reason.as_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    let log = format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(data));
+
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.proposal, None);
+    assert_eq!(event.data["program"], program.to_string());
+    assert_eq!(event.data["voters"][1], second.to_string());
+    assert_eq!(event.data["reason"], reason);
+    assert_eq!(event.data["paused_at"], 1_700_000_000);
+}
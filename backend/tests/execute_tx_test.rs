@@ -0,0 +1,83 @@
+use base64::Engine;
+use goquant_upgrade_service::execute_tx::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::str::FromStr;
+
+fn unsigned(fee_payer: &Keypair, members: &[&Keypair]) -> Transaction {
+    let signers: Vec<Pubkey> = members.iter().map(|m| m.pubkey()).collect();
+    let message = Message::new(&[attestation_instruction("proposal-1", &signers)], Some(&fee_payer.pubkey()));
+    let mut tx = Transaction::new_unsigned(message);
+    tx.message.recent_blockhash = Hash::new_unique();
+    tx
+}
+
+#[test]
+fn test_attestation_requires_every_signer() {
+    let members = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let ix = attestation_instruction("proposal-1", &members);
+
+    assert_eq!(ix.program_id, Pubkey::from_str(MEMO_PROGRAM_ID).unwrap());
+    assert_eq!(ix.data, b"goquant-execute:proposal-1");
+    assert_eq!(ix.accounts.len(), 3);
+    assert!(ix.accounts.iter().all(|meta| meta.is_signer && !meta.is_writable));
+    let listed: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+    assert_eq!(listed, members);
+}
+
+#[test]
+fn test_signatures_assemble_into_valid_transaction() {
+    let fee_payer = Keypair::new();
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let mut tx = unsigned(&fee_payer, &[&alice, &bob]);
+    assert_eq!(tx.message.header.num_required_signatures, 3);
+
+    // Members sign the message bytes offline, in any order
+    let message = tx.message_data();
+    apply_signature(&mut tx, &bob.pubkey(), bob.sign_message(&message)).unwrap();
+    apply_signature(&mut tx, &alice.pubkey(), alice.sign_message(&message)).unwrap();
+    assert!(tx.verify().is_err(), "fee payer has not signed yet");
+
+    apply_signature(&mut tx, &fee_payer.pubkey(), fee_payer.sign_message(&message)).unwrap();
+    assert!(tx.verify().is_ok());
+}
+
+#[test]
+fn test_rejects_bad_signatures() {
+    let fee_payer = Keypair::new();
+    let alice = Keypair::new();
+    let outsider = Keypair::new();
+    let mut tx = unsigned(&fee_payer, &[&alice]);
+    let message = tx.message_data();
+
+    // Not a signer of this transaction
+    assert!(apply_signature(&mut tx, &outsider.pubkey(), outsider.sign_message(&message)).is_err());
+    // Alice's slot with someone else's signature
+    assert!(apply_signature(&mut tx, &alice.pubkey(), outsider.sign_message(&message)).is_err());
+    // Alice's key over a different message
+    assert!(apply_signature(&mut tx, &alice.pubkey(), alice.sign_message(b"something else")).is_err());
+
+    assert!(tx.signatures.iter().all(|s| *s == Default::default()));
+}
+
+#[test]
+fn test_encoded_transaction_round_trips() {
+    let fee_payer = Keypair::new();
+    let alice = Keypair::new();
+    let mut tx = unsigned(&fee_payer, &[&alice]);
+    let message = tx.message_data();
+    apply_signature(&mut tx, &alice.pubkey(), alice.sign_message(&message)).unwrap();
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encode_transaction(&tx).unwrap())
+        .unwrap();
+    let decoded: Transaction = bincode::deserialize(&bytes).unwrap();
+
+    // Partial signatures survive the export
+    assert_eq!(decoded, tx);
+    assert_eq!(decoded.message_data(), message);
+}
@@ -0,0 +1,15 @@
+use goquant_upgrade_service::execution_queue::retry_delay;
+use std::time::Duration;
+
+#[test]
+fn test_retry_delay_doubles_up_to_the_cap() {
+    let base = Duration::from_secs(15);
+    let max = Duration::from_secs(600);
+
+    let delays: Vec<u64> = (1..=7).map(|attempt| retry_delay(attempt, base, max).as_secs()).collect();
+    assert_eq!(delays, vec![15, 30, 60, 120, 240, 480, 600]);
+
+    // No overflow however many attempts are configured
+    assert_eq!(retry_delay(i32::MAX, base, max), max);
+    assert_eq!(retry_delay(0, base, max), base);
+}
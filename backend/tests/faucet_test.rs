@@ -0,0 +1,44 @@
+use goquant_upgrade_service::faucet::*;
+
+fn config(request_lamports: u64, max_requests: u32) -> AirdropConfig {
+    AirdropConfig {
+        enabled: true,
+        reserve_lamports: 0,
+        request_lamports,
+        max_requests,
+    }
+}
+
+#[test]
+fn test_cluster_from_genesis_hash() {
+    assert_eq!(
+        Cluster::from_genesis_hash("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+        Cluster::Mainnet
+    );
+    assert_eq!(
+        Cluster::from_genesis_hash("EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+        Cluster::Devnet
+    );
+    assert_eq!(Cluster::from_genesis_hash("anything-else"), Cluster::Local);
+
+    assert!(!Cluster::Mainnet.has_faucet());
+    assert!(Cluster::Devnet.has_faucet());
+    assert!(Cluster::Local.has_faucet());
+}
+
+#[test]
+fn test_requests_needed_rounds_up() {
+    let config = config(1_000, 5);
+
+    assert_eq!(config.requests_needed(5_000, 5_000), Some(0));
+    assert_eq!(config.requests_needed(4_999, 5_000), Some(1));
+    assert_eq!(config.requests_needed(0, 2_500), Some(3));
+}
+
+#[test]
+fn test_requests_needed_respects_cap() {
+    let config = config(1_000, 2);
+
+    assert_eq!(config.requests_needed(0, 2_000), Some(2));
+    assert_eq!(config.requests_needed(0, 2_001), None);
+}
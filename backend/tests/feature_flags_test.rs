@@ -0,0 +1,94 @@
+use base64::Engine;
+use goquant_upgrade_service::feature_flags::*;
+use goquant_upgrade_service::indexer::{decode_event, event_discriminator};
+use solana_sdk::pubkey::Pubkey;
+
+/// `FeatureFlags` account data as the upgrade-manager program writes it
+fn account_data(program: &Pubkey, flags: &[(&str, bool, bool, u32)], updated_by: &Pubkey) -> Vec<u8> {
+    let mut data = vec![0; 8];
+    data.extend_from_slice(program.as_ref());
+    data.extend_from_slice(&(flags.len() as u32).to_le_bytes());
+    for (name, enabled, kill_switch, revision) in flags {
+        data.extend_from_slice(&encode_flag_name(name).unwrap());
+        data.push(*enabled as u8);
+        data.push(*kill_switch as u8);
+        data.extend_from_slice(&revision.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(updated_by.as_ref());
+    }
+    data.push(255);
+    data
+}
+
+#[test]
+fn test_flag_names_are_validated() {
+    let encoded = encode_flag_name("leveraged_orders").unwrap();
+    assert_eq!(decode_flag_name(&encoded), "leveraged_orders");
+    assert!(encode_flag_name(&"x".repeat(MAX_FLAG_NAME_LEN)).is_ok());
+
+    assert!(encode_flag_name("").is_err());
+    assert!(encode_flag_name(&"x".repeat(MAX_FLAG_NAME_LEN + 1)).is_err());
+    assert!(encode_flag_name("new fees").is_err());
+    assert!(encode_flag_name("frais_réduits").is_err());
+}
+
+#[test]
+fn test_decodes_feature_flags_account() {
+    let (program, account, member) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let data = account_data(
+        &program,
+        &[("leveraged_orders", false, true, 2), ("new_fee_tiers", true, false, 1)],
+        &member,
+    );
+
+    let flags = OnchainFeatureFlags::try_from_account_data(&data, &account).unwrap();
+    assert_eq!(flags.program, program.to_string());
+    assert_eq!(flags.account, account.to_string());
+    assert_eq!(flags.flags.len(), 2);
+
+    let leveraged = flags.get("leveraged_orders").unwrap();
+    assert!(!leveraged.enabled && leveraged.kill_switch);
+    assert_eq!(leveraged.updated_by, member.to_string());
+    assert_eq!(flags.revision("new_fee_tiers"), 1);
+    assert_eq!(flags.revision("unknown"), 0);
+
+    assert!(OnchainFeatureFlags::try_from_account_data(&data[..60], &account).is_err());
+}
+
+#[test]
+fn test_flag_change_commits_to_revision_and_timelock() {
+    let program = Pubkey::new_unique();
+    let name = encode_flag_name("leveraged_orders").unwrap();
+    let digest = flag_change_digest(&program, &name, true, false, 0, 172_800);
+
+    assert_ne!(digest, flag_change_digest(&program, &name, true, false, 1, 172_800));
+    assert_ne!(digest, flag_change_digest(&program, &name, true, true, 0, 172_800));
+    assert_ne!(digest, flag_change_digest(&program, &name, true, false, 0, 259_200));
+
+    // Each change to each revision gets its own proposal account
+    let target = flag_change_target(&name, true, false, 0);
+    assert_eq!(target, flag_change_target(&name, true, false, 0));
+    assert_ne!(target, flag_change_target(&name, true, false, 1));
+    assert_ne!(target, flag_change_target(&name, false, false, 0));
+}
+
+#[test]
+fn test_indexes_tripped_kill_switch() {
+    let (program, member) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = event_discriminator("FeatureFlagSetEvent").to_vec();
+    data.extend_from_slice(program.as_ref());
+    data.extend_from_slice(&encode_flag_name("leveraged_orders").unwrap());
+    data.extend_from_slice(&[0, 1]);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.push(0); // no proposal
+    data.extend_from_slice(member.as_ref());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    let log = format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(data));
+
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.proposal, None);
+    assert_eq!(event.data["name"], "leveraged_orders");
+    assert_eq!(event.data["enabled"], false);
+    assert_eq!(event.data["revision"], 3);
+    assert_eq!(event.data["updated_by"], member.to_string());
+}
@@ -0,0 +1,69 @@
+use goquant_upgrade_service::fork_replay::{
+    programdata_with_elf, success_rate, ReplayOutcome, PROGRAMDATA_METADATA_SIZE,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn programdata(elf: &[u8]) -> Vec<u8> {
+    let mut data = vec![3, 0, 0, 0];
+    data.extend_from_slice(&42u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(elf);
+    data
+}
+
+fn outcome(error: Option<&str>) -> ReplayOutcome {
+    ReplayOutcome {
+        success: error.is_none(),
+        error: error.map(str::to_string),
+        compute_units_consumed: Some(5_000),
+    }
+}
+
+#[test]
+fn test_programdata_keeps_slot_and_authority() {
+    let current = programdata(b"\x7fELF old program");
+
+    let upgraded = programdata_with_elf(&current, b"\x7fELF new").unwrap();
+
+    assert_eq!(upgraded[..PROGRAMDATA_METADATA_SIZE], current[..PROGRAMDATA_METADATA_SIZE]);
+    assert_eq!(&upgraded[PROGRAMDATA_METADATA_SIZE..], b"\x7fELF new");
+}
+
+#[test]
+fn test_programdata_rejects_other_accounts() {
+    // Buffer tag
+    let mut buffer = programdata(b"");
+    buffer[0] = 1;
+    assert!(programdata_with_elf(&buffer, b"elf").is_none());
+
+    assert!(programdata_with_elf(&[3, 0, 0, 0], b"elf").is_none());
+}
+
+#[test]
+fn test_divergence_is_a_changed_result() {
+    let ok = outcome(None);
+    let failed = outcome(Some("Error processing Instruction 0: custom program error: 0x1771"));
+
+    assert!(!ok.diverges_from(&ok));
+    assert!(failed.diverges_from(&ok));
+    assert!(ok.diverges_from(&failed));
+    // Failing differently counts too
+    assert!(outcome(Some("insufficient funds")).diverges_from(&failed));
+
+    // Compute usage alone does not
+    let heavier = ReplayOutcome {
+        compute_units_consumed: Some(9_000),
+        ..ok.clone()
+    };
+    assert!(!heavier.diverges_from(&ok));
+}
+
+#[test]
+fn test_success_rate() {
+    let ok = outcome(None);
+    let failed = outcome(Some("failed"));
+
+    assert_eq!(success_rate(&[&ok, &ok, &ok, &failed]), 75.0);
+    assert_eq!(success_rate(&[]), 0.0);
+}
@@ -0,0 +1,87 @@
+use goquant_upgrade_service::github::{commit_status, is_commit_sha};
+use goquant_upgrade_service::proposal::{
+    Cancellation, CancellationReason, Proposal, ProposalSource, ProposalStatus,
+};
+
+fn released_proposal(status: ProposalStatus) -> Proposal {
+    Proposal {
+        id: "proposal-1".to_string(),
+        proposer: "multisig".to_string(),
+        program: "program_id".to_string(),
+        new_buffer: "Buffer11111111111111111111111111111111".to_string(),
+        description: "v2.1.0".to_string(),
+        proposed_at: 1_704_067_200,
+        timelock_until: 1_704_283_200,
+        approvals: vec![],
+        approval_threshold: 3,
+        status,
+        executed_at: None,
+        source: Some(ProposalSource {
+            tag: "v2.1.0".to_string(),
+            commit: "main".to_string(),
+            artifact_hash: "ab".repeat(32),
+            release_url: None,
+        }),
+        publish_idl: true,
+        cancellation: None,
+        risk_tier: None,
+        attachments: vec![],
+        depends_on: vec![],
+        supersedes: vec![],
+        freeze: None,
+        deployment: None,
+        authority_change: None,
+    }
+}
+
+#[test]
+fn test_commit_status_follows_the_proposal() {
+    let mut proposal = released_proposal(ProposalStatus::Proposed);
+    let status = commit_status(&proposal, "goquant/upgrade", None);
+    assert_eq!(status.state, "pending");
+    assert_eq!(status.description, "Upgrade proposed, 0/3 approvals");
+    assert_eq!(status.context, "goquant/upgrade");
+
+    proposal.status = ProposalStatus::Approved;
+    proposal.approvals = vec!["alice".to_string(), "bob".to_string()];
+    assert_eq!(
+        commit_status(&proposal, "ctx", None).description,
+        "Upgrade approved by 2/3 members"
+    );
+
+    proposal.status = ProposalStatus::TimelockActive;
+    assert_eq!(
+        commit_status(&proposal, "ctx", None).description,
+        "Approved, executable after 2024-01-03 12:00 UTC"
+    );
+
+    proposal.status = ProposalStatus::Frozen;
+    assert_eq!(commit_status(&proposal, "ctx", None).state, "failure");
+
+    proposal.status = ProposalStatus::Executed;
+    let status = commit_status(&proposal, "ctx", Some("https://upgrades.example.com/proposals/proposal-1".to_string()));
+    assert_eq!(status.state, "success");
+    assert_eq!(
+        serde_json::to_value(&status).unwrap()["target_url"],
+        "https://upgrades.example.com/proposals/proposal-1"
+    );
+
+    proposal.status = ProposalStatus::Cancelled;
+    proposal.cancellation = Some(Cancellation {
+        reason: CancellationReason::SecurityIssue,
+        details: "reentrancy".to_string(),
+        cancelled_at: 1_704_100_000,
+    });
+    let status = commit_status(&proposal, "ctx", None);
+    assert_eq!(status.state, "failure");
+    assert_eq!(status.description, "Upgrade cancelled (security_issue)");
+    assert!(serde_json::to_value(&status).unwrap().get("target_url").is_none());
+}
+
+#[test]
+fn test_commit_sha_detection() {
+    assert!(is_commit_sha("0123456789abcdef0123456789ABCDEF01234567"));
+    assert!(!is_commit_sha("main"));
+    assert!(!is_commit_sha("0123456"));
+    assert!(!is_commit_sha("0123456789abcdef0123456789abcdef0123456g"));
+}
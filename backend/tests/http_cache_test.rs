@@ -0,0 +1,87 @@
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, Request, StatusCode};
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use goquant_upgrade_service::http_cache::*;
+use tower::ServiceExt;
+use tower_http::compression::CompressionLayer;
+
+fn app() -> Router {
+    let proposals = || async { Json(vec!["proposal"; 200]) };
+    Router::new()
+        .nest(
+            "/v1",
+            Router::new()
+                .route("/upgrade/proposals", get(proposals))
+                .route("/public/history", get(proposals)),
+        )
+        .layer(middleware::from_fn(conditional_get))
+        .layer(CompressionLayer::new())
+}
+
+async fn get_with(uri: &str, headers: &[(header::HeaderName, &str)]) -> axum::response::Response {
+    let mut request = Request::builder().uri(uri);
+    for (name, value) in headers {
+        request = request.header(name, *value);
+    }
+    app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+#[test]
+fn test_cache_control_per_route() {
+    assert_eq!(cache_control_for("/v1/artifacts/:hash/download"), CACHE_IMMUTABLE);
+    assert_eq!(cache_control_for("/public/proposals/:id"), CACHE_PUBLIC);
+    assert_eq!(cache_control_for("/v1/monitoring/health"), CACHE_NONE);
+    assert_eq!(cache_control_for("/metrics"), CACHE_NONE);
+    assert_eq!(cache_control_for("/v1/upgrade/proposals"), CACHE_REVALIDATE);
+}
+
+#[test]
+fn test_if_none_match_uses_weak_comparison() {
+    let etag = etag_for(b"body");
+    let strong = etag.trim_start_matches("W/").to_string();
+
+    let mut headers = HeaderMap::new();
+    assert!(!if_none_match(&headers, &etag));
+
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&format!("\"other\", {}", strong)).unwrap());
+    assert!(if_none_match(&headers, &etag));
+
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+    assert!(if_none_match(&headers, &etag));
+
+    headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("W/\"other\""));
+    assert!(!if_none_match(&headers, &etag));
+}
+
+#[tokio::test]
+async fn test_revalidation_returns_not_modified() {
+    let first = get_with("/v1/upgrade/proposals", &[]).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(first.headers()[header::CACHE_CONTROL], CACHE_REVALIDATE);
+    let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+    let second = get_with("/v1/upgrade/proposals", &[(header::IF_NONE_MATCH, &etag)]).await;
+    assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(second.headers()[header::ETAG], etag.as_str());
+    let body = axum::body::to_bytes(second.into_body(), usize::MAX).await.unwrap();
+    assert!(body.is_empty());
+
+    let public = get_with("/v1/public/history", &[]).await;
+    assert_eq!(public.headers()[header::CACHE_CONTROL], CACHE_PUBLIC);
+}
+
+#[tokio::test]
+async fn test_compressed_responses_keep_the_etag() {
+    let plain = get_with("/v1/upgrade/proposals", &[]).await;
+    let gzip = get_with("/v1/upgrade/proposals", &[(header::ACCEPT_ENCODING, "gzip")]).await;
+    let brotli = get_with("/v1/upgrade/proposals", &[(header::ACCEPT_ENCODING, "br")]).await;
+
+    assert_eq!(gzip.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(brotli.headers()[header::CONTENT_ENCODING], "br");
+    assert_eq!(gzip.headers()[header::ETAG], plain.headers()[header::ETAG]);
+
+    let plain_len = axum::body::to_bytes(plain.into_body(), usize::MAX).await.unwrap().len();
+    let gzip_len = axum::body::to_bytes(gzip.into_body(), usize::MAX).await.unwrap().len();
+    assert!(gzip_len < plain_len);
+}
@@ -0,0 +1,113 @@
+use goquant_upgrade_service::incidents::*;
+
+const NOW: i64 = 1_700_000_000;
+
+fn open_request() -> OpenIncidentRequest {
+    OpenIncidentRequest {
+        title: "Elevated liquidation failures".to_string(),
+        severity: IncidentSeverity::Minor,
+        message: "We are looking into failed liquidations.".to_string(),
+        program: Some("Prog1111111111111111111111111111111111111".to_string()),
+        proposal_id: Some("p1".to_string()),
+        opened_by: Some("ops".to_string()),
+    }
+}
+
+fn detection(severity: IncidentSeverity) -> Detection {
+    Detection {
+        source: IncidentSource::Watch,
+        severity,
+        program: "Prog1111111111111111111111111111111111111".to_string(),
+        proposal_id: Some("p1".to_string()),
+        title: "Unexpected behaviour after an upgrade: errors".to_string(),
+        detail: "Watch expression 'errors': 12 matches".to_string(),
+        alert: Some(("post_upgrade_watch".to_string(), "Watch expression 'errors' flagged p1".to_string())),
+    }
+}
+
+#[test]
+fn test_incident_lifecycle() {
+    let mut incident = open_request().into_incident(IncidentSource::Manual, NOW).unwrap();
+    assert_eq!(incident.status, IncidentStatus::Investigating);
+    assert_eq!(incident.updates.len(), 1);
+    assert_eq!(incident.linked(IncidentLinkKind::Proposal).collect::<Vec<_>>(), ["p1"]);
+
+    incident
+        .post(
+            IncidentChange {
+                status: Some(IncidentStatus::Identified),
+                severity: Some(IncidentSeverity::Major),
+                message: "Rolling back upgrade p1".to_string(),
+                author: Some("ops".to_string()),
+            },
+            NOW + 60,
+        )
+        .unwrap();
+    assert_eq!(incident.severity, IncidentSeverity::Major);
+    assert_eq!(incident.updated_at, NOW + 60);
+
+    let close = CloseIncidentRequest {
+        message: None,
+        author: Some("ops".to_string()),
+    };
+    incident.post(close.into_change(), NOW + 120).unwrap();
+    assert!(!incident.is_active());
+    assert_eq!(incident.resolved_at, Some(NOW + 120));
+
+    let late = IncidentChange {
+        status: None,
+        severity: None,
+        message: "One more thing".to_string(),
+        author: None,
+    };
+    assert!(incident.post(late, NOW + 180).is_err());
+}
+
+#[test]
+fn test_rejects_blank_title_and_message() {
+    let mut request = open_request();
+    request.title = "  ".to_string();
+    assert!(request.into_incident(IncidentSource::Manual, NOW).is_err());
+
+    let mut request = open_request();
+    request.message = String::new();
+    assert!(request.into_incident(IncidentSource::Manual, NOW).is_err());
+}
+
+#[test]
+fn test_detections_fold_into_the_open_incident() {
+    let mut incident = detection(IncidentSeverity::Major).into_incident(NOW).unwrap();
+    assert_eq!(incident.source, IncidentSource::Watch);
+    assert_eq!(incident.links.len(), 2);
+
+    incident.absorb(&detection(IncidentSeverity::Minor), NOW + 10).unwrap();
+    assert_eq!(incident.severity, IncidentSeverity::Major);
+    // The same proposal and alert are not linked twice
+    assert_eq!(incident.links.len(), 2);
+
+    let mut rollback = detection(IncidentSeverity::Critical);
+    rollback.alert = None;
+    incident.absorb(&rollback, NOW + 20).unwrap();
+    assert_eq!(incident.severity, IncidentSeverity::Critical);
+    assert_eq!(incident.updates.len(), 3);
+}
+
+#[test]
+fn test_status_summary() {
+    let quiet = StatusSummary::build(&[], NOW);
+    assert_eq!(quiet.status, ServiceStatus::Operational);
+
+    let active = open_request().into_incident(IncidentSource::Manual, NOW).unwrap();
+    let mut resolved = detection(IncidentSeverity::Critical).into_incident(NOW - 100).unwrap();
+    resolved.post(CloseIncidentRequest { message: None, author: None }.into_change(), NOW - 50).unwrap();
+    let mut old = detection(IncidentSeverity::Critical).into_incident(NOW - 30 * 24 * 3600).unwrap();
+    old.post(CloseIncidentRequest { message: None, author: None }.into_change(), NOW - 29 * 24 * 3600)
+        .unwrap();
+
+    let summary = StatusSummary::build(&[old, resolved, active], NOW);
+    assert_eq!(summary.status, ServiceStatus::Degraded);
+    assert_eq!(summary.active.len(), 1);
+    assert_eq!(summary.recently_resolved.len(), 1);
+    // Public updates are newest first
+    assert_eq!(summary.recently_resolved[0].updates[0].status, IncidentStatus::Resolved);
+}
@@ -0,0 +1,92 @@
+use base64::Engine;
+use goquant_upgrade_service::indexer::{decode_event, event_discriminator};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+fn program_data(name: &str, fields: &[&[u8]]) -> String {
+    let mut data = event_discriminator(name).to_vec();
+    for field in fields {
+        data.extend_from_slice(field);
+    }
+    format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(data))
+}
+
+#[test]
+fn test_discriminator_matches_anchor() {
+    assert_eq!(event_discriminator("AccountMigratedEvent"), [109, 3, 25, 119, 155, 108, 69, 61]);
+}
+
+#[test]
+fn test_decodes_proposal_events() {
+    let (proposal, approver) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let log = program_data(
+        "ProposalApprovedEvent",
+        &[proposal.as_ref(), approver.as_ref(), &2u64.to_le_bytes(), &[3], &[1], &[2]],
+    );
+
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.name, "ProposalApprovedEvent");
+    assert_eq!(event.proposal, Some(proposal.to_string()));
+    assert_eq!(
+        event.data,
+        json!({
+            "proposal_id": proposal.to_string(),
+            "approver": approver.to_string(),
+            "approvals": 2,
+            "threshold": 3,
+            "organizations": 1,
+            "organization_quorum": 2,
+        })
+    );
+
+    let details = b"bad build";
+    let log = program_data(
+        "ProposalCancelledEvent",
+        &[proposal.as_ref(), approver.as_ref(), &[1], &(details.len() as u32).to_le_bytes(), details, &[2]],
+    );
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.data["reason"], "build_mismatch");
+    assert_eq!(event.data["details"], "bad build");
+    assert_eq!(event.data["votes"], 2);
+
+    let log = program_data(
+        "ApprovalRevokedEvent",
+        &[proposal.as_ref(), approver.as_ref(), &1u64.to_le_bytes(), &[3]],
+    );
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.proposal, Some(proposal.to_string()));
+    assert_eq!(event.data["approver"], approver.to_string());
+    assert_eq!(event.data["approvals"], 1);
+}
+
+#[test]
+fn test_decodes_events_without_a_proposal() {
+    let account = Pubkey::new_unique();
+    let log = program_data(
+        "AccountMigratedEvent",
+        &[account.as_ref(), &2u32.to_le_bytes(), &1_700_000_000i64.to_le_bytes()],
+    );
+
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.proposal, None);
+    assert_eq!(event.data["account"], account.to_string());
+    assert_eq!(event.data["migrated_at"], 1_700_000_000);
+
+    let migration_id = uuid::Uuid::new_v4();
+    let log = program_data(
+        "MigrationProgressEvent",
+        &[migration_id.as_bytes(), &120u64.to_le_bytes(), &100u64.to_le_bytes(), &3u64.to_le_bytes()],
+    );
+    let event = decode_event(&log).unwrap();
+    assert_eq!(event.data["migration_id"], migration_id.to_string());
+    assert_eq!(event.data["migrated"], 100);
+    assert_eq!(event.data["failed"], 3);
+}
+
+#[test]
+fn test_ignores_other_logs() {
+    assert!(decode_event("Program log: Instruction: Approve").is_none());
+    assert!(decode_event(&program_data("SomeoneElsesEvent", &[&[0; 64]])).is_none());
+    // Truncated event data
+    assert!(decode_event(&program_data("ProposalApprovedEvent", &[&[0; 40]])).is_none());
+}
@@ -0,0 +1,76 @@
+use goquant_upgrade_service::members::*;
+use goquant_upgrade_service::reminders::{reminder_contact, ReminderContact};
+use std::collections::HashMap;
+
+const ALICE: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+const BOB: &str = "SRMuApVNdxXokk5GT7XD5cUUgXMBCoAz2LHeuAoKWRt";
+
+fn update(name: &str, organization: Option<&str>) -> MemberProfileUpdate {
+    MemberProfileUpdate {
+        name: name.to_string(),
+        organization: organization.map(str::to_string),
+        contact: None,
+        notification_channel: None,
+        reminders_enabled: true,
+        updated_by: None,
+    }
+}
+
+#[test]
+fn test_profiles_label_members() {
+    let alice = update(" Alice ", Some("Ops")).into_profile(ALICE, 0).unwrap();
+    assert_eq!(alice.display_name(), "Alice (Ops)");
+
+    let directory = MemberDirectory::new(vec![alice.clone()]);
+    assert_eq!(directory.display_name(ALICE), "Alice (Ops)");
+    // Members without a profile keep their pubkey
+    assert_eq!(directory.display_name(BOB), BOB);
+
+    let labelled = serde_json::json!(directory.annotate(ALICE, serde_json::json!({ "member": ALICE, "inactive": false })));
+    assert_eq!(labelled["display_name"], "Alice (Ops)");
+    assert_eq!(labelled["inactive"], false);
+    assert_eq!(labelled["profile"]["name"], "Alice");
+}
+
+#[test]
+fn test_profile_updates_are_validated() {
+    assert!(update("Alice", None).into_profile("not-a-pubkey", 0).is_err());
+    assert!(update("  ", None).into_profile(ALICE, 0).is_err());
+    assert!(update(&"a".repeat(65), None).into_profile(ALICE, 0).is_err());
+
+    let mut channel = update("Alice", Some(""));
+    channel.notification_channel = Some("pager:alice".to_string());
+    assert!(channel.clone().into_profile(ALICE, 0).is_err());
+
+    channel.notification_channel = Some("email:alice@goquant.io".to_string());
+    let profile = channel.into_profile(ALICE, 0).unwrap();
+    // Blank optional fields are dropped
+    assert_eq!(profile.organization, None);
+    assert_eq!(profile.display_name(), "Alice");
+}
+
+#[test]
+fn test_profiles_override_configured_reminder_channels() {
+    let configured = HashMap::from([
+        (ALICE.to_string(), ReminderContact::Slack("https://hooks.slack.com/a".to_string())),
+        (BOB.to_string(), ReminderContact::Slack("https://hooks.slack.com/b".to_string())),
+    ]);
+    let mut alice = update("Alice", None);
+    alice.notification_channel = Some("email:alice@goquant.io".to_string());
+    let alice = alice.into_profile(ALICE, 0).unwrap();
+
+    assert_eq!(
+        reminder_contact(ALICE, Some(&alice), &configured),
+        Some(ReminderContact::Email("alice@goquant.io".to_string()))
+    );
+    assert_eq!(
+        reminder_contact(BOB, None, &configured),
+        Some(ReminderContact::Slack("https://hooks.slack.com/b".to_string()))
+    );
+    assert_eq!(reminder_contact("carol", None, &configured), Some(ReminderContact::Websocket));
+
+    let mut muted = update("Bob", None);
+    muted.reminders_enabled = false;
+    let muted = muted.into_profile(BOB, 0).unwrap();
+    assert_eq!(reminder_contact(BOB, Some(&muted), &configured), None);
+}
@@ -0,0 +1,75 @@
+use goquant_upgrade_service::metrics_history::{
+    snapshot_points, HistoryQuery, WindowStats, DEFAULT_HISTORY_SECONDS, METRIC_NAMES,
+};
+use goquant_upgrade_service::monitoring::Metrics;
+
+fn metrics() -> Metrics {
+    Metrics {
+        proposals_created: 12,
+        proposals_executed: 9,
+        proposals_cancelled: 1,
+        migrations_completed: 3,
+        rollbacks_initiated: 0,
+        average_timelock_duration: 0.0,
+        average_approval_time: 0.0,
+    }
+}
+
+fn query(metric: &str, from: Option<i64>, to: Option<i64>, step: Option<i64>) -> HistoryQuery {
+    HistoryQuery {
+        metric: metric.to_string(),
+        from,
+        to,
+        step,
+    }
+}
+
+#[test]
+fn test_snapshot_points_cover_every_metric() {
+    let window = WindowStats {
+        proposals_created: 2,
+        proposals_executed: 1,
+        approvals: 4,
+        average_approval_latency_seconds: Some(5400.0),
+    };
+    let points = snapshot_points(&metrics(), 1000, 7, &window, 1_700_000_000);
+
+    assert_eq!(points.len(), METRIC_NAMES.len());
+    assert!(points.iter().all(|p| p.recorded_at == 1_700_000_000));
+    let value = |name: &str| points.iter().find(|p| p.metric == name).unwrap().value;
+    assert_eq!(value("approvals"), 4.0);
+    assert_eq!(value("approval_latency_seconds"), 5400.0);
+    assert_eq!(value("proposals_created_total"), 12.0);
+    assert_eq!(value("http_errors_total"), 7.0);
+}
+
+#[test]
+fn test_quiet_window_has_no_latency_point() {
+    let points = snapshot_points(&metrics(), 0, 0, &WindowStats::default(), 1_700_000_000);
+    assert!(points.iter().all(|p| p.metric != "approval_latency_seconds"));
+    assert!(points.iter().any(|p| p.metric == "approvals" && p.value == 0.0));
+}
+
+#[test]
+fn test_history_query_defaults_and_step() {
+    let now = 1_700_000_000;
+
+    let range = query("approvals", None, None, None).resolve(now, 300).unwrap();
+    assert_eq!(range.to, now);
+    assert_eq!(range.from, now - DEFAULT_HISTORY_SECONDS);
+    // A week in 1000 buckets is coarser than the snapshot interval
+    assert_eq!(range.step, 605);
+
+    let range = query("approvals", Some(now - 3600), Some(now), None).resolve(now, 300).unwrap();
+    assert_eq!(range.step, 300);
+
+    let range = query("approvals", Some(now - 3600), Some(now), Some(900)).resolve(now, 300).unwrap();
+    assert_eq!(range.step, 900);
+}
+
+#[test]
+fn test_history_query_rejects_bad_input() {
+    let now = 1_700_000_000;
+    assert!(query("proposals_deleted", None, None, None).resolve(now, 300).is_err());
+    assert!(query("approvals", Some(now), Some(now - 60), None).resolve(now, 300).is_err());
+}
@@ -0,0 +1,267 @@
+use goquant_upgrade_service::multisig_backend::*;
+use goquant_upgrade_service::squads::*;
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+fn rpc() -> Arc<AsyncRpcClient> {
+    Arc::new(AsyncRpcClient::new("http://localhost:8899".to_string()))
+}
+
+fn anchor_account(name: &str, fields: &[u8]) -> Vec<u8> {
+    let mut data = Sha256::digest(format!("account:{}", name).as_bytes())[..8].to_vec();
+    data.extend_from_slice(fields);
+    data
+}
+
+#[test]
+fn test_backend_kind_round_trip() {
+    for kind in [
+        MultisigBackendKind::Native,
+        MultisigBackendKind::SquadsV3,
+        MultisigBackendKind::SquadsV4,
+        MultisigBackendKind::Realms,
+        MultisigBackendKind::Tss,
+    ] {
+        assert_eq!(MultisigBackendKind::parse(kind.as_str()), Some(kind));
+    }
+    assert_eq!(MultisigBackendKind::parse("squads"), None);
+}
+
+#[test]
+fn test_instruction_discriminator_is_anchor_sighash() {
+    let expected = &Sha256::digest(b"global:approve_transaction")[..8];
+    assert_eq!(&instruction_discriminator("approve_transaction")[..], expected);
+}
+
+#[test]
+fn test_squads_vaults_use_version_specific_seeds() {
+    let multisig = Pubkey::new_unique();
+    let v3 = SquadsV3Backend::new(rpc(), multisig, 1);
+    let v4 = SquadsV4Backend::new(rpc(), multisig, 0);
+
+    let v3_program = Pubkey::from_str(SQUADS_V3_PROGRAM_ID).unwrap();
+    let v4_program = Pubkey::from_str(SQUADS_V4_PROGRAM_ID).unwrap();
+    let (v3_authority, _) = Pubkey::find_program_address(
+        &[b"squad", multisig.as_ref(), &1u32.to_le_bytes(), b"authority"],
+        &v3_program,
+    );
+    let (v4_vault, _) =
+        Pubkey::find_program_address(&[b"multisig", multisig.as_ref(), b"vault", &[0]], &v4_program);
+
+    assert_eq!(v3.vault(), Some(v3_authority));
+    assert_eq!(v4.vault(), Some(v4_vault));
+    assert_ne!(v3_authority, v4_vault);
+    assert_eq!(v3.kind(), MultisigBackendKind::SquadsV3);
+    assert_eq!(v4.kind(), MultisigBackendKind::SquadsV4);
+}
+
+#[test]
+fn test_v3_propose_wraps_upgrade_in_transaction() {
+    let v3 = SquadsV3Backend::new(rpc(), Pubkey::new_unique(), 1);
+    let creator = Pubkey::new_unique();
+    let upgrade = upgrade_instruction(&Pubkey::new_unique(), &Pubkey::new_unique(), &v3.authority_address(), &creator);
+
+    let instructions = v3.propose_instructions(&creator, 7, &upgrade);
+    let transaction = v3.transaction_address(7);
+
+    assert_eq!(instructions.len(), 3);
+    assert_eq!(instructions[0].data[..8], instruction_discriminator("create_transaction"));
+    assert_eq!(instructions[0].data[8..], 1u32.to_le_bytes());
+    assert_eq!(instructions[1].data[..8], instruction_discriminator("add_instruction"));
+    assert_eq!(instructions[1].data[8..40], bpf_loader_upgradeable::id().to_bytes());
+    assert_eq!(instructions[1].accounts[2].pubkey, v3.instruction_address(&transaction, 1));
+    assert_eq!(instructions[2].data, instruction_discriminator("activate_transaction"));
+    assert!(instructions.iter().all(|ix| ix.accounts[1].pubkey == transaction));
+}
+
+#[test]
+fn test_v3_instruction_account_decodes_to_upgrade() {
+    let v3 = SquadsV3Backend::new(rpc(), Pubkey::new_unique(), 1);
+    let upgrade = upgrade_instruction(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &v3.authority_address(),
+        &Pubkey::new_unique(),
+    );
+
+    // MsInstruction: program_id, keys, data, then bookkeeping fields
+    let mut fields = upgrade.program_id.to_bytes().to_vec();
+    fields.extend_from_slice(&(upgrade.accounts.len() as u32).to_le_bytes());
+    for meta in &upgrade.accounts {
+        fields.extend_from_slice(meta.pubkey.as_ref());
+        fields.push(meta.is_signer as u8);
+        fields.push(meta.is_writable as u8);
+    }
+    fields.extend_from_slice(&(upgrade.data.len() as u32).to_le_bytes());
+    fields.extend_from_slice(&upgrade.data);
+    fields.extend_from_slice(&[1, 255, 0]);
+
+    let decoded = decode_v3_instruction(&anchor_account("MsInstruction", &fields)).unwrap();
+    assert_eq!(decoded, upgrade);
+
+    let transaction = v3.transaction_address(1);
+    let execute = v3.execute_instruction(&Pubkey::new_unique(), &transaction, 1, &decoded);
+    assert_eq!(execute.accounts[4].pubkey, bpf_loader_upgradeable::id());
+    assert_eq!(execute.accounts.len(), 5 + upgrade.accounts.len());
+    assert!(execute.accounts[5..].iter().all(|meta| !meta.is_signer));
+}
+
+#[test]
+fn test_deploy_is_paid_and_signed_by_the_vault() {
+    let v4 = SquadsV4Backend::new(rpc(), Pubkey::new_unique(), 0);
+    let vault = v4.vault_address();
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let deploy = deploy_instruction(&program, &buffer, &vault, 400_000);
+
+    let (programdata, _) = Pubkey::find_program_address(&[program.as_ref()], &bpf_loader_upgradeable::id());
+    assert_eq!(deploy.program_id, bpf_loader_upgradeable::id());
+    assert_eq!(deploy.accounts[0].pubkey, vault);
+    assert_eq!(deploy.accounts[1].pubkey, programdata);
+    assert_eq!(deploy.accounts[2].pubkey, program);
+    assert_eq!(deploy.accounts[3].pubkey, buffer);
+    assert_eq!(deploy.accounts[7].pubkey, vault);
+    // Only the vault signs, so the multisig can execute it alone
+    assert!(deploy.accounts.iter().filter(|meta| meta.is_signer).all(|meta| meta.pubkey == vault));
+
+    // DeployWithMaxDataLen is loader instruction 2, followed by the length
+    assert_eq!(deploy.data[..4], 2u32.to_le_bytes());
+    assert_eq!(deploy.data[4..], 400_000u64.to_le_bytes());
+}
+
+#[test]
+fn test_set_authority_is_signed_by_the_vault() {
+    let vault = Pubkey::new_unique();
+    let program = Pubkey::new_unique();
+    let new_authority = Pubkey::new_unique();
+
+    let handover = set_authority_instruction(&program, &vault, Some(&new_authority));
+    assert_eq!(handover.program_id, bpf_loader_upgradeable::id());
+    assert!(handover.accounts.iter().any(|meta| meta.pubkey == new_authority && !meta.is_signer));
+    assert!(handover.accounts.iter().filter(|meta| meta.is_signer).all(|meta| meta.pubkey == vault));
+
+    // Without a new authority the instruction leaves none behind
+    let immutable = set_authority_instruction(&program, &vault, None);
+    assert_eq!(immutable.accounts.len(), handover.accounts.len() - 1);
+}
+
+#[test]
+fn test_v4_message_is_compact_and_paid_by_vault() {
+    let v4 = SquadsV4Backend::new(rpc(), Pubkey::new_unique(), 0);
+    let vault = v4.vault_address();
+    let upgrade = upgrade_instruction(&Pubkey::new_unique(), &Pubkey::new_unique(), &vault, &Pubkey::new_unique());
+
+    let message = compile_v4_message(&vault, std::slice::from_ref(&upgrade));
+    let compiled = Message::new(std::slice::from_ref(&upgrade), Some(&vault));
+    let key_count = compiled.account_keys.len();
+
+    assert_eq!(message[0], 1, "only the vault signs");
+    assert_eq!(message[1], 1, "the vault is writable");
+    assert_eq!(message[3] as usize, key_count);
+    assert_eq!(message[4..36], vault.to_bytes());
+
+    let instructions_at = 4 + 32 * key_count;
+    assert_eq!(message[instructions_at], 1);
+    let data_len_at = instructions_at + 3 + upgrade.accounts.len();
+    let data_len = u16::from_le_bytes([message[data_len_at], message[data_len_at + 1]]) as usize;
+    assert_eq!(data_len, upgrade.data.len());
+    assert_eq!(message.len(), data_len_at + 2 + data_len + 1);
+    assert_eq!(*message.last().unwrap(), 0, "no address lookup tables");
+}
+
+#[test]
+fn test_v4_transaction_account_keeps_writability() {
+    let v4 = SquadsV4Backend::new(rpc(), Pubkey::new_unique(), 0);
+    let vault = v4.vault_address();
+    let readonly = Pubkey::new_unique();
+    let writable = Pubkey::new_unique();
+
+    // VaultTransaction: multisig, creator, index, bumps, ephemeral bumps, message
+    let mut fields = [[1u8; 32], [2u8; 32]].concat();
+    fields.extend_from_slice(&9u64.to_le_bytes());
+    fields.extend_from_slice(&[254, 0, 253]);
+    fields.extend_from_slice(&0u32.to_le_bytes());
+    fields.extend_from_slice(&[1, 1, 1]);
+    fields.extend_from_slice(&3u32.to_le_bytes());
+    for key in [vault, writable, readonly] {
+        fields.extend_from_slice(key.as_ref());
+    }
+
+    let stored = SquadsV4Transaction::try_from_account_data(&anchor_account("VaultTransaction", &fields)).unwrap();
+    assert_eq!(stored.index, 9);
+    let writability: Vec<bool> = stored.account_keys.iter().map(|meta| meta.is_writable).collect();
+    assert_eq!(writability, vec![true, true, false]);
+    assert!(stored.account_keys.iter().all(|meta| !meta.is_signer));
+
+    let member = Pubkey::new_unique();
+    let execute = v4.execute_instruction(&member, &stored);
+    assert_eq!(execute.accounts[1].pubkey, v4.proposal_address(9));
+    assert_eq!(execute.accounts[2].pubkey, v4.transaction_address(9));
+    assert_eq!(execute.accounts[3].pubkey, member);
+    assert_eq!(execute.accounts.len(), 4 + 3);
+}
+
+#[test]
+fn test_v4_proposal_status_decodes() {
+    let member = Pubkey::new_unique();
+    let mut fields = [[1u8; 32]].concat();
+    fields.extend_from_slice(&9u64.to_le_bytes());
+    fields.push(3); // Approved { timestamp }
+    fields.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    fields.push(255);
+    fields.extend_from_slice(&1u32.to_le_bytes());
+    fields.extend_from_slice(member.as_ref());
+
+    let proposal = SquadsV4Proposal::try_from_account_data(&anchor_account("Proposal", &fields)).unwrap();
+    assert_eq!(proposal.status, "approved");
+    assert_eq!(proposal.approved, vec![member]);
+}
+
+#[test]
+fn test_native_backend_uses_upgrade_manager_pdas() {
+    let program_id = Pubkey::new_unique();
+    let native = NativeBackend::new(rpc(), program_id);
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+
+    let execute = native.execute_instruction(&Pubkey::new_unique(), &program, &buffer);
+    assert_eq!(execute.program_id, program_id);
+    assert_eq!(execute.data[..8], instruction_discriminator("execute_upgrade"));
+    assert_eq!(execute.data[8..], native.proposal_address(&program, &buffer).to_bytes());
+    // The program checks the buffer's authority before executing
+    assert_eq!(execute.accounts[5].pubkey, buffer);
+
+    let approver = Pubkey::new_unique();
+    let revoke = native.revoke_instruction(&approver, &native.proposal_address(&program, &buffer));
+    assert_eq!(revoke.data[..8], instruction_discriminator("revoke_approval"));
+    assert_eq!(revoke.data[8..], native.proposal_address(&program, &buffer).to_bytes());
+    assert!(revoke.accounts[0].is_signer && revoke.accounts[0].pubkey == approver);
+    assert_eq!(native.vault(), None);
+    assert_eq!(native.kind(), MultisigBackendKind::Native);
+}
+
+#[test]
+fn test_long_descriptions_are_pinned_by_hash() {
+    let short = "Upgrade to v1.4.0";
+    assert_eq!(onchain_description(short), (short, None));
+
+    // Cut at a char boundary below the limit, hashing the full text
+    let long = format!("{}é", "x".repeat(MAX_ONCHAIN_DESCRIPTION_LEN - 1));
+    let (stored, hash) = onchain_description(&long);
+    assert_eq!(stored.len(), MAX_ONCHAIN_DESCRIPTION_LEN - 1);
+    assert_eq!(hash, Some(Sha256::digest(long.as_bytes()).into()));
+
+    let native = NativeBackend::new(rpc(), Pubkey::new_unique());
+    let buffer = Pubkey::new_unique();
+    let propose = native.propose_instruction(&Pubkey::new_unique(), &Pubkey::new_unique(), &buffer, &long, &[7; 32]);
+    // discriminator, buffer, description, code_hash, Some(description_hash)
+    let tail = 8 + 32 + 4 + stored.len() + 32;
+    assert_eq!(propose.data.len(), tail + 1 + 32);
+    assert_eq!(propose.data[tail], 1);
+    assert_eq!(propose.data[tail + 1..], hash.unwrap());
+}
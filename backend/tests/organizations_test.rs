@@ -0,0 +1,52 @@
+use goquant_upgrade_service::multisig::{organization_count, parse_member_organizations, OnchainMultisigConfig};
+use solana_sdk::pubkey::Pubkey;
+
+fn config_data(members: &[Pubkey], organizations: Option<(&[u8], u8)>) -> Vec<u8> {
+    let mut data = vec![0; 8];
+    data.extend_from_slice(&(members.len() as u32).to_le_bytes());
+    for member in members {
+        data.extend_from_slice(member.as_ref());
+    }
+    data.push(2); // threshold
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.push(0); // execution_bot
+    data.push(255); // bump
+    if let Some((member_organizations, quorum)) = organizations {
+        data.extend_from_slice(&(member_organizations.len() as u32).to_le_bytes());
+        data.extend_from_slice(member_organizations);
+        data.push(quorum);
+    }
+    data
+}
+
+#[test]
+fn test_counts_distinct_organizations() {
+    let organizations = parse_member_organizations("alice=acme, bob=acme,carol=globex").unwrap();
+    let approvers = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(organization_count(&approvers(&["alice", "bob"]), &organizations), 1);
+    assert_eq!(organization_count(&approvers(&["alice", "carol"]), &organizations), 2);
+    // Members without an organization are not counted
+    assert_eq!(organization_count(&approvers(&["alice", "dave"]), &organizations), 1);
+}
+
+#[test]
+fn test_rejects_malformed_organizations() {
+    assert!(parse_member_organizations("").unwrap().is_empty());
+    assert!(parse_member_organizations("alice").is_err());
+    assert!(parse_member_organizations("alice=").is_err());
+}
+
+#[test]
+fn test_decodes_config_organizations() {
+    let members = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let config = OnchainMultisigConfig::try_from_account_data(&config_data(&members, Some((&[0, 0, 1], 2)))).unwrap();
+    assert_eq!(config.member_organizations, vec![0, 0, 1]);
+    assert_eq!(config.organization_quorum, 2);
+
+    // Configs from before organizations existed end at the bump
+    let config = OnchainMultisigConfig::try_from_account_data(&config_data(&members, None)).unwrap();
+    assert!(config.member_organizations.is_empty());
+    assert_eq!(config.organization_quorum, 0);
+}
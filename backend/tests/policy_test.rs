@@ -0,0 +1,101 @@
+use goquant_upgrade_service::github::release_risk_tier;
+use goquant_upgrade_service::policy::{Condition, PolicyDecision, PolicyFacts, PolicyRule, PolicyRuleSpec};
+use goquant_upgrade_service::proposal::RiskTier;
+
+fn routine_rebuild() -> PolicyRule {
+    PolicyRule::parse(&PolicyRuleSpec {
+        name: "routine-rebuild".to_string(),
+        when: "artifact_match AND size_delta_pct < 1 AND risk_tier == low".to_string(),
+    })
+    .unwrap()
+}
+
+fn routine_facts() -> PolicyFacts {
+    PolicyFacts {
+        risk_tier: Some(RiskTier::Low),
+        artifact_match: Some(true),
+        size_delta_percent: PolicyFacts::size_delta(100_400, 100_000),
+        denylisted: false,
+    }
+}
+
+#[test]
+fn test_parse_conditions() {
+    assert_eq!(
+        routine_rebuild().conditions,
+        vec![
+            Condition::ArtifactMatch,
+            Condition::SizeDelta { max_percent: 1.0, inclusive: false },
+            Condition::RiskTierIs(RiskTier::Low),
+        ]
+    );
+    assert_eq!(
+        Condition::parse("risk_tier <= medium").unwrap(),
+        Condition::RiskTierAtMost(RiskTier::Medium)
+    );
+
+    assert!(Condition::parse("risk_tier == patch").is_err());
+    assert!(Condition::parse("size_delta_pct > 1").is_err());
+    assert!(PolicyRule::parse(&PolicyRuleSpec {
+        name: "typo".to_string(),
+        when: "artifact_match AND sized_delta_pct < 1".to_string(),
+    })
+    .is_err());
+}
+
+#[test]
+fn test_routine_rebuild_matches() {
+    let decision = PolicyDecision::evaluate(&[routine_rebuild()], routine_facts());
+    assert_eq!(decision.matched_rule.as_deref(), Some("routine-rebuild"));
+    assert_eq!(decision.evaluations[0].reasons[1], "size delta 0.40% (needs < 1%)");
+}
+
+#[test]
+fn test_any_failed_or_unknown_condition_needs_humans() {
+    let rules = [routine_rebuild()];
+
+    let grown = PolicyFacts {
+        size_delta_percent: PolicyFacts::size_delta(98_000, 100_000),
+        ..routine_facts()
+    };
+    assert_eq!(PolicyDecision::evaluate(&rules, grown).matched_rule, None);
+
+    let riskier = PolicyFacts {
+        risk_tier: Some(RiskTier::Medium),
+        ..routine_facts()
+    };
+    assert_eq!(PolicyDecision::evaluate(&rules, riskier).matched_rule, None);
+
+    let no_artifact = PolicyFacts {
+        artifact_match: None,
+        ..routine_facts()
+    };
+    let decision = PolicyDecision::evaluate(&rules, no_artifact);
+    assert_eq!(decision.matched_rule, None);
+    assert_eq!(decision.evaluations[0].reasons[0], "no CI artifact to compare against");
+
+    let first_deploy = PolicyFacts {
+        size_delta_percent: PolicyFacts::size_delta(100_000, 0),
+        ..routine_facts()
+    };
+    assert_eq!(PolicyDecision::evaluate(&rules, first_deploy).matched_rule, None);
+}
+
+#[test]
+fn test_denylist_vetoes_every_rule() {
+    let denylisted = PolicyFacts {
+        denylisted: true,
+        ..routine_facts()
+    };
+    let decision = PolicyDecision::evaluate(&[routine_rebuild()], denylisted);
+    assert_eq!(decision.matched_rule, None);
+    assert!(decision.evaluations[0].reasons.contains(&"binary matches the denylist".to_string()));
+}
+
+#[test]
+fn test_release_risk_tier() {
+    assert_eq!(release_risk_tier(Some("Fixes rounding\n\nRisk: Low\n")), Some(RiskTier::Low));
+    assert_eq!(release_risk_tier(Some("risk: critical")), Some(RiskTier::Critical));
+    assert_eq!(release_risk_tier(Some("Risky change: none")), None);
+    assert_eq!(release_risk_tier(None), None);
+}
@@ -0,0 +1,57 @@
+use goquant_upgrade_service::preflight::{
+    check_balance, check_genesis, missing_tables, CheckResult, CheckStatus, PreflightReport, REQUIRED_TABLES,
+};
+
+const DEVNET: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+const MAINNET: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+#[test]
+fn test_report_fails_only_on_failed_checks() {
+    let report = PreflightReport::new(vec![
+        CheckResult::new("database", CheckStatus::Pass, "connected"),
+        CheckResult::new("signer_balance", CheckStatus::Warn, "no fee payer configured"),
+        CheckResult::new("webhooks", CheckStatus::Skipped, "no webhooks configured"),
+    ]);
+    assert!(report.passed);
+    assert!(report.render().ends_with("Preflight passed\n"));
+
+    let report = PreflightReport::new(vec![
+        CheckResult::new("database", CheckStatus::Pass, "connected"),
+        CheckResult::new("rpc", CheckStatus::Fail, "RPC health check failed"),
+    ]);
+    assert!(!report.passed);
+    let rendered = report.render();
+    assert!(rendered.contains("[FAIL] rpc       RPC health check failed"));
+    assert!(rendered.ends_with("Preflight failed: 1 of 2 checks failed\n"));
+}
+
+#[test]
+fn test_missing_tables() {
+    let mut present: Vec<String> = REQUIRED_TABLES.iter().map(|table| table.to_string()).collect();
+    present.push("_sqlx_migrations".to_string());
+    assert!(missing_tables(&present).is_empty());
+
+    present.retain(|table| table != "execution_requests");
+    assert_eq!(missing_tables(&present), vec!["execution_requests"]);
+}
+
+#[test]
+fn test_genesis_hash_pins_cluster() {
+    assert_eq!(check_genesis(MAINNET, Some(MAINNET)).0, CheckStatus::Pass);
+
+    let (status, detail) = check_genesis(DEVNET, Some(MAINNET));
+    assert_eq!(status, CheckStatus::Fail);
+    assert!(detail.contains("devnet"));
+    assert!(detail.contains("mainnet-beta"));
+
+    assert_eq!(check_genesis(DEVNET, None).0, CheckStatus::Warn);
+}
+
+#[test]
+fn test_signer_balance_minimum() {
+    assert_eq!(check_balance("payer", 100_000_000, 100_000_000).0, CheckStatus::Pass);
+
+    let (status, detail) = check_balance("payer", 50_000_000, 100_000_000);
+    assert_eq!(status, CheckStatus::Fail);
+    assert_eq!(detail, "payer holds 0.05 SOL, below the 0.1 SOL minimum");
+}
@@ -0,0 +1,56 @@
+use goquant_upgrade_service::program_builder::*;
+use solana_sdk::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use solana_sdk::pubkey::Pubkey;
+
+fn capacity(program_len: usize, capacity: usize) -> ProgramCapacity {
+    ProgramCapacity::from_account_lens(
+        UpgradeableLoaderState::size_of_buffer_metadata() + program_len,
+        UpgradeableLoaderState::size_of_programdata_metadata() + capacity,
+    )
+}
+
+#[test]
+fn test_capacity_strips_loader_headers() {
+    let fits = capacity(1_000, 2_000);
+    assert_eq!(fits, ProgramCapacity { program_len: 1_000, capacity: 2_000 });
+    assert_eq!(fits.extension_needed(), 0);
+    assert!(fits.warning().is_none());
+    assert!(fits.extend_instruction(&Pubkey::new_unique(), &Pubkey::new_unique()).is_none());
+
+    // Exactly full still fits
+    assert_eq!(capacity(2_000, 2_000).extension_needed(), 0);
+}
+
+#[test]
+fn test_oversized_program_is_extended() {
+    let (program, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let grown = capacity(3_000, 2_000);
+    assert_eq!(grown.extension_needed(), 1_000);
+    assert!(grown.warning().unwrap().contains("extends it by 1000 bytes"));
+
+    let instruction = grown.extend_instruction(&program, &payer).unwrap();
+    assert_eq!(instruction, bpf_loader_upgradeable::extend_program(&program, Some(&payer), 1_000));
+    assert_eq!(instruction.program_id, bpf_loader_upgradeable::id());
+    assert!(instruction.accounts.iter().all(|meta| !meta.is_signer || meta.pubkey == payer));
+}
+
+#[test]
+fn test_extension_cost_charges_missing_rent() {
+    let grown = capacity(3_000, 2_000);
+    let cost = ExtensionCost::new(&grown, 50_000, 30_000).unwrap();
+    assert_eq!(cost.additional_bytes, 1_000);
+    assert_eq!(cost.account_len, UpgradeableLoaderState::size_of_programdata_metadata() + 3_000);
+    assert_eq!(cost.rent_lamports, 20_000);
+
+    // A program data account holding more than the minimum costs nothing extra
+    assert_eq!(ExtensionCost::new(&grown, 50_000, 60_000).unwrap().rent_lamports, 0);
+    assert!(ExtensionCost::new(&capacity(1_000, 2_000), 50_000, 30_000).is_none());
+
+    let (program, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert_eq!(cost.instruction(&program, &payer), grown.extend_instruction(&program, &payer).unwrap());
+
+    let total = UpgradeCost::new(Some(cost), 4);
+    assert_eq!(total.fee_lamports, 20_000);
+    assert_eq!(total.total_lamports, 40_000);
+    assert_eq!(UpgradeCost::new(None, 4).total_lamports, 20_000);
+}
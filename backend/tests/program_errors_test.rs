@@ -0,0 +1,68 @@
+use goquant_upgrade_service::program_errors::{parse_idl_errors, ErrorDecoder};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+const UPGRADE_MANAGER: &str = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS";
+
+fn upgrade_manager_idl() -> serde_json::Value {
+    json!({
+        "version": "0.1.0",
+        "name": "upgrade_manager",
+        "errors": [
+            { "code": 6000, "name": "NotMultisigMember", "msg": "Not a multisig member" },
+            { "code": 6001, "name": "InvalidProposalStatus", "msg": "Invalid proposal status" }
+        ],
+        "metadata": { "address": UPGRADE_MANAGER }
+    })
+}
+
+#[test]
+fn test_parses_idl_errors_and_address() {
+    let (program, errors) = parse_idl_errors(&upgrade_manager_idl()).unwrap();
+    assert_eq!(program, Some(UPGRADE_MANAGER.parse().unwrap()));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].describe(), "InvalidProposalStatus (6001): Invalid proposal status");
+
+    // Anchor 0.30 IDLs carry the address at the top level
+    let (program, errors) = parse_idl_errors(&json!({ "address": UPGRADE_MANAGER, "errors": [] })).unwrap();
+    assert!(program.is_some() && errors.is_empty());
+
+    assert!(parse_idl_errors(&json!({ "errors": [{ "name": "MissingCode" }] })).is_err());
+}
+
+#[test]
+fn test_describes_custom_program_errors() {
+    let upgrade_manager: Pubkey = UPGRADE_MANAGER.parse().unwrap();
+    let other = Pubkey::new_unique();
+    let decoder = ErrorDecoder::new().with_idl(&upgrade_manager_idl()).unwrap();
+
+    let raw = "RPC response error -32002: Transaction simulation failed: \
+               Error processing Instruction 1: custom program error: 0x1771";
+    assert_eq!(
+        decoder.describe(raw, &[other, upgrade_manager]),
+        "RPC response error -32002: Transaction simulation failed: \
+         Error processing Instruction 1: InvalidProposalStatus (6001): Invalid proposal status"
+    );
+
+    // The same code from a program without an IDL stays raw
+    let raw = "Error processing Instruction 0: custom program error: 0x1771";
+    assert_eq!(decoder.describe(raw, &[other, upgrade_manager]), raw);
+
+    // With the programs unknown, the only loaded IDL is assumed
+    assert_eq!(
+        decoder.describe(raw, &[]),
+        "Error processing Instruction 0: InvalidProposalStatus (6001): Invalid proposal status"
+    );
+
+    // Anchor framework errors need no IDL
+    assert_eq!(
+        ErrorDecoder::new().describe("Error processing Instruction 0: custom program error: 0xbbf", &[]),
+        "Error processing Instruction 0: AccountOwnedByWrongProgram (3007): \
+         The given account is owned by a different program than expected"
+    );
+
+    // Unknown codes and other errors are left alone
+    let raw = "Error processing Instruction 0: custom program error: 0x1";
+    assert_eq!(decoder.describe(raw, &[upgrade_manager]), raw);
+    assert_eq!(decoder.describe("Blockhash not found", &[]), "Blockhash not found");
+}
@@ -0,0 +1,103 @@
+use goquant_upgrade_service::error::UpgradeError;
+use goquant_upgrade_service::proposal::{
+    ensure_dependencies_executed, validate_links, Proposal, ProposalLinks, ProposalStatus,
+};
+
+fn proposal(id: &str, status: ProposalStatus) -> Proposal {
+    Proposal {
+        id: id.to_string(),
+        proposer: "multisig".to_string(),
+        program: "program_id".to_string(),
+        new_buffer: "Buffer11111111111111111111111111111111".to_string(),
+        description: format!("Upgrade {}", id),
+        proposed_at: 1_700_000_000,
+        timelock_until: 1_700_172_800,
+        approvals: vec![],
+        approval_threshold: 3,
+        status,
+        executed_at: None,
+        source: None,
+        publish_idl: false,
+        cancellation: None,
+        risk_tier: None,
+        attachments: vec![],
+        depends_on: vec![],
+        supersedes: vec![],
+        freeze: None,
+        deployment: None,
+        authority_change: None,
+    }
+}
+
+fn ids(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn test_links_must_point_at_usable_proposals() {
+    let proposals = vec![
+        proposal("executed", ProposalStatus::Executed),
+        proposal("open", ProposalStatus::Approved),
+        proposal("cancelled", ProposalStatus::Cancelled),
+    ];
+
+    assert!(validate_links(&ids(&["executed", "open"]), &[], &proposals).is_ok());
+    assert!(validate_links(&[], &ids(&["open"]), &proposals).is_ok());
+    assert!(matches!(
+        validate_links(&ids(&["missing"]), &[], &proposals),
+        Err(UpgradeError::ProposalNotFound(_))
+    ));
+    assert!(validate_links(&ids(&["cancelled"]), &[], &proposals).is_err());
+    assert!(validate_links(&[], &ids(&["executed"]), &proposals).is_err());
+    assert!(validate_links(&ids(&["open"]), &ids(&["open"]), &proposals).is_err());
+}
+
+#[test]
+fn test_execution_waits_for_dependencies() {
+    let mut dependent = proposal("dependent", ProposalStatus::TimelockActive);
+    dependent.depends_on = ids(&["first", "second"]);
+    let mut proposals = vec![
+        proposal("first", ProposalStatus::Executed),
+        proposal("second", ProposalStatus::TimelockActive),
+        dependent,
+    ];
+
+    assert!(matches!(
+        ensure_dependencies_executed("dependent", &proposals),
+        Err(UpgradeError::DependencyPending(pending)) if pending == "second"
+    ));
+
+    proposals[1].status = ProposalStatus::Cancelled;
+    assert!(matches!(
+        ensure_dependencies_executed("dependent", &proposals),
+        Err(UpgradeError::InvalidRequest(_))
+    ));
+
+    proposals[1].status = ProposalStatus::Executed;
+    assert!(ensure_dependencies_executed("dependent", &proposals).is_ok());
+    assert!(ensure_dependencies_executed("first", &proposals).is_ok());
+}
+
+#[test]
+fn test_links_include_both_directions() {
+    let mut replacement = proposal("replacement", ProposalStatus::Proposed);
+    replacement.depends_on = ids(&["base"]);
+    replacement.supersedes = ids(&["old"]);
+    let proposals = vec![
+        proposal("base", ProposalStatus::TimelockActive),
+        proposal("old", ProposalStatus::Cancelled),
+        replacement,
+    ];
+
+    let links = ProposalLinks::build(&proposals[2], &proposals);
+    assert_eq!(links.depends_on[0].id, "base");
+    assert_eq!(links.supersedes[0].status, ProposalStatus::Cancelled);
+    assert!(!links.ready);
+
+    let links = ProposalLinks::build(&proposals[0], &proposals);
+    assert_eq!(links.required_by[0].id, "replacement");
+    assert!(links.ready);
+
+    let links = ProposalLinks::build(&proposals[1], &proposals);
+    assert_eq!(links.superseded_by[0].id, "replacement");
+}
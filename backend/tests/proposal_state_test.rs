@@ -0,0 +1,113 @@
+use goquant_upgrade_service::proposal::{ProposalEvent, ProposalStatus};
+use goquant_upgrade_service::UpgradeError;
+
+const ALL_STATUSES: [ProposalStatus; 6] = [
+    ProposalStatus::Proposed,
+    ProposalStatus::Approved,
+    ProposalStatus::TimelockActive,
+    ProposalStatus::Executed,
+    ProposalStatus::Cancelled,
+    ProposalStatus::Frozen,
+];
+
+const ALL_EVENTS: [ProposalEvent; 7] = [
+    ProposalEvent::Approve,
+    ProposalEvent::ThresholdReached,
+    ProposalEvent::Revoke,
+    ProposalEvent::RevokeLast,
+    ProposalEvent::Execute,
+    ProposalEvent::Cancel,
+    ProposalEvent::Freeze,
+];
+
+fn expected(from: &ProposalStatus, event: ProposalEvent) -> Option<ProposalStatus> {
+    use ProposalEvent::*;
+    use ProposalStatus::*;
+
+    match (from, event) {
+        (Proposed, Approve) | (Approved, Approve) => Some(Approved),
+        (Proposed, ThresholdReached) | (Approved, ThresholdReached) => Some(TimelockActive),
+        (Approved, Revoke) => Some(Approved),
+        (Approved, RevokeLast) => Some(Proposed),
+        (TimelockActive, Execute) => Some(Executed),
+        (Proposed, Cancel) | (Approved, Cancel) | (TimelockActive, Cancel) | (Frozen, Cancel) => Some(Cancelled),
+        (Proposed, Freeze) | (Approved, Freeze) | (TimelockActive, Freeze) => Some(Frozen),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_transition_table() {
+    for from in ALL_STATUSES.iter() {
+        for event in ALL_EVENTS {
+            let result = from.transition(event);
+            match expected(from, event) {
+                Some(to) => assert_eq!(result.unwrap(), to, "{:?} + {:?}", from, event),
+                None => assert!(result.is_err(), "{:?} + {:?} should be rejected", from, event),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_terminal_states_report_specific_errors() {
+    for event in ALL_EVENTS {
+        assert!(matches!(
+            ProposalStatus::Executed.transition(event),
+            Err(UpgradeError::AlreadyExecuted)
+        ));
+        assert!(matches!(
+            ProposalStatus::Cancelled.transition(event),
+            Err(UpgradeError::AlreadyCancelled)
+        ));
+    }
+}
+
+#[test]
+fn test_execute_requires_timelock() {
+    assert!(matches!(
+        ProposalStatus::Approved.transition(ProposalEvent::Execute),
+        Err(UpgradeError::InvalidTransition { .. })
+    ));
+    assert!(matches!(
+        ProposalStatus::TimelockActive.transition(ProposalEvent::Approve),
+        Err(UpgradeError::InvalidTransition { .. })
+    ));
+}
+
+#[test]
+fn test_approvals_cannot_be_revoked_once_timelock_runs() {
+    assert!(matches!(
+        ProposalStatus::TimelockActive.transition(ProposalEvent::Revoke),
+        Err(UpgradeError::InvalidTransition { .. })
+    ));
+    assert!(matches!(
+        ProposalStatus::TimelockActive.transition(ProposalEvent::RevokeLast),
+        Err(UpgradeError::InvalidTransition { .. })
+    ));
+}
+
+#[test]
+fn test_status_round_trips_through_database_value() {
+    for status in ALL_STATUSES.iter() {
+        assert_eq!(ProposalStatus::parse(status.as_str()).as_ref(), Some(status));
+    }
+}
+
+#[test]
+fn test_frozen_proposals_only_cancel_or_unfreeze() {
+    for event in ALL_EVENTS.into_iter().filter(|event| *event != ProposalEvent::Cancel) {
+        assert!(matches!(
+            ProposalStatus::Frozen.transition(event),
+            Err(UpgradeError::ProposalFrozen)
+        ));
+    }
+
+    // Unfreezing returns to where the proposal was frozen from
+    for from in [ProposalStatus::Proposed, ProposalStatus::Approved, ProposalStatus::TimelockActive] {
+        let frozen = from.transition(ProposalEvent::Freeze).unwrap();
+        assert_eq!(frozen.unfreeze(&from).unwrap(), from);
+    }
+    assert!(ProposalStatus::Approved.unfreeze(&ProposalStatus::Proposed).is_err());
+    assert!(ProposalStatus::Frozen.unfreeze(&ProposalStatus::Executed).is_err());
+}
@@ -1,68 +1,30 @@
+use goquant_upgrade_service::test_utils::{self, TestServices};
 use goquant_upgrade_service::*;
-use tokio_test;
 
 #[tokio::test]
 async fn test_proposal_creation() {
-    let multisig = std::sync::Arc::new(
-        multisig::MultisigCoordinator::new().await.unwrap()
-    );
-    let timelock = std::sync::Arc::new(
-        timelock::TimelockManager::new().await.unwrap()
-    );
-    let builder = std::sync::Arc::new(
-        program_builder::ProgramBuilder::new().await.unwrap()
-    );
+    let services = TestServices::new().await;
 
-    let proposal_manager = proposal::ProposalManager::new(
-        multisig, timelock, builder
-    ).await.unwrap();
-
-    let buffer_pubkey = "Buffer11111111111111111111111111111111"
-        .parse()
-        .unwrap();
-    
-    let proposal_id = proposal_manager
-        .propose_upgrade(buffer_pubkey, "Test upgrade".to_string())
-        .await
-        .unwrap();
+    let proposal_id = services.propose("Test upgrade").await;
 
     assert!(!proposal_id.is_empty());
 
-    let proposals = proposal_manager.list_proposals().await.unwrap();
+    let proposals = services.proposals.list_proposals().await.unwrap();
     assert_eq!(proposals.len(), 1);
     assert_eq!(proposals[0].id, proposal_id);
 }
 
 #[tokio::test]
 async fn test_proposal_approval_flow() {
-    let multisig = std::sync::Arc::new(
-        multisig::MultisigCoordinator::new().await.unwrap()
-    );
-    let timelock = std::sync::Arc::new(
-        timelock::TimelockManager::new().await.unwrap()
-    );
-    let builder = std::sync::Arc::new(
-        program_builder::ProgramBuilder::new().await.unwrap()
-    );
-
-    let proposal_manager = proposal::ProposalManager::new(
-        multisig.clone(), timelock, builder
-    ).await.unwrap();
+    let services = TestServices::new().await;
 
     // Create proposal
-    let buffer_pubkey = "Buffer11111111111111111111111111111111"
-        .parse()
-        .unwrap();
-    
-    let proposal_id = proposal_manager
-        .propose_upgrade(buffer_pubkey, "Test upgrade".to_string())
-        .await
-        .unwrap();
+    let proposal_id = services.propose("Test upgrade").await;
 
     // Approve proposal
-    multisig.approve_proposal(&proposal_id).await.unwrap();
+    services.proposals.record_approval(&proposal_id, "member1").await.unwrap();
 
-    let status = proposal_manager
+    let status = services.proposals
         .get_proposal_status(&proposal_id)
         .await
         .unwrap();
@@ -72,19 +34,7 @@ async fn test_proposal_approval_flow() {
 
 #[tokio::test]
 async fn test_timelock_enforcement() {
-    let multisig = std::sync::Arc::new(
-        multisig::MultisigCoordinator::new().await.unwrap()
-    );
-    let timelock = std::sync::Arc::new(
-        timelock::TimelockManager::new().await.unwrap()
-    );
-    let builder = std::sync::Arc::new(
-        program_builder::ProgramBuilder::new().await.unwrap()
-    );
-
-    let proposal_manager = proposal::ProposalManager::new(
-        multisig, timelock.clone(), builder
-    ).await.unwrap();
+    let timelock = test_utils::mock_timelock().await;
 
     let proposal_id = "test-proposal".to_string();
     
@@ -101,34 +51,60 @@ async fn test_timelock_enforcement() {
 
 #[tokio::test]
 async fn test_proposal_cancellation() {
-    let multisig = std::sync::Arc::new(
-        multisig::MultisigCoordinator::new().await.unwrap()
-    );
-    let timelock = std::sync::Arc::new(
-        timelock::TimelockManager::new().await.unwrap()
-    );
-    let builder = std::sync::Arc::new(
-        program_builder::ProgramBuilder::new().await.unwrap()
-    );
-
-    let proposal_manager = proposal::ProposalManager::new(
-        multisig, timelock, builder
-    ).await.unwrap();
+    let services = TestServices::new().await;
+    let proposal_manager = &services.proposals;
 
     // Create proposal
-    let buffer_pubkey = "Buffer11111111111111111111111111111111"
-        .parse()
-        .unwrap();
-    
-    let proposal_id = proposal_manager
-        .propose_upgrade(buffer_pubkey, "Test upgrade".to_string())
-        .await
-        .unwrap();
+    let proposal_id = services.propose("Test upgrade").await;
 
     // Cancel proposal
-    proposal_manager.cancel_upgrade(&proposal_id).await.unwrap();
+    proposal_manager
+        .cancel_upgrade(
+            &proposal_id,
+            proposal::CancellationReason::Superseded,
+            "Replaced by v2.0.1".to_string(),
+        )
+        .await
+        .unwrap();
 
     let proposals = proposal_manager.list_proposals().await.unwrap();
     let proposal = proposals.iter().find(|p| p.id == proposal_id).unwrap();
     assert_eq!(proposal.status, proposal::ProposalStatus::Cancelled);
-}
\ No newline at end of file
+
+    let cancellation = proposal.cancellation.as_ref().unwrap();
+    assert_eq!(cancellation.reason, proposal::CancellationReason::Superseded);
+
+    // "other" must come with an explanation
+    let other_id = services.propose("Second upgrade").await;
+    assert!(proposal_manager
+        .cancel_upgrade(&other_id, proposal::CancellationReason::Other, String::new())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_multisig_members_can_be_replaced() {
+    let services = TestServices::with_members(&["alice", "bob"], 2).await;
+    assert_eq!(services.multisig.get_members().await, vec!["alice", "bob"]);
+    assert_eq!(services.multisig.get_threshold(), 2);
+
+    let multisig = multisig::MultisigCoordinator::new().await.unwrap();
+    assert!(multisig.with_members(vec!["alice".to_string()], 2).is_err());
+}
+
+#[tokio::test]
+async fn test_timelock_duration_matches_program_bounds() {
+    for duration in [0, proposal::MIN_TIMELOCK_DURATION - 1, proposal::MAX_TIMELOCK_DURATION + 1] {
+        assert!(proposal::validate_timelock_duration(duration).is_err());
+    }
+    proposal::validate_timelock_duration(proposal::MIN_TIMELOCK_DURATION).unwrap();
+    proposal::validate_timelock_duration(proposal::MAX_TIMELOCK_DURATION).unwrap();
+
+    let proposal_manager = proposal::ProposalManager::new(
+        test_utils::mock_multisig(&test_utils::MEMBERS, test_utils::THRESHOLD).await,
+        test_utils::mock_timelock().await,
+        test_utils::mock_program_builder().await,
+    ).await.unwrap();
+    assert_eq!(proposal_manager.timelock_duration(), proposal::DEFAULT_TIMELOCK_DURATION);
+    assert!(proposal_manager.with_timelock_duration(60).is_err());
+}
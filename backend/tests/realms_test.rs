@@ -0,0 +1,114 @@
+use goquant_upgrade_service::multisig_backend::*;
+use goquant_upgrade_service::realms::*;
+use goquant_upgrade_service::squads::upgrade_instruction;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+
+fn backend() -> RealmsBackend {
+    RealmsBackend::new(
+        Arc::new(AsyncRpcClient::new("http://localhost:8899".to_string())),
+        Pubkey::from_str(SPL_GOVERNANCE_PROGRAM_ID).unwrap(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    )
+}
+
+fn push_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+#[test]
+fn test_governance_is_the_upgrade_authority() {
+    let realms = backend();
+    let governance = realms.vault().unwrap();
+    let program = Pubkey::new_unique();
+    let buffer = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+
+    let seed = RealmsBackend::proposal_seed(&program, &buffer);
+    assert_eq!(seed, RealmsBackend::proposal_seed(&program, &buffer));
+    assert_ne!(seed, RealmsBackend::proposal_seed(&program, &Pubkey::new_unique()));
+
+    let upgrade = upgrade_instruction(&program, &buffer, &governance, &creator);
+    let instructions = realms.propose_instructions(&creator, &seed, &upgrade, "Upgrade to v2");
+    let proposal = realms.proposal_address(&seed);
+
+    assert_eq!(instructions.len(), 3);
+    assert_eq!(instructions[0].data[0], 6, "CreateProposal");
+    assert_eq!(instructions[0].data[instructions[0].data.len() - 32..], seed.to_bytes());
+    assert_eq!(instructions[1].data[0], 9, "InsertTransaction");
+    assert_eq!(instructions[1].accounts[4].pubkey, realms.proposal_transaction_address(&proposal));
+    assert_eq!(instructions[1].data[12..44], bpf_loader_upgradeable::id().to_bytes());
+    assert_eq!(instructions[2].data, vec![12], "SignOffProposal");
+    assert_eq!(realms.kind(), MultisigBackendKind::Realms);
+}
+
+#[test]
+fn test_execute_passes_upgrade_accounts_unsigned() {
+    let realms = backend();
+    let proposal = Pubkey::new_unique();
+    let upgrade = upgrade_instruction(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &realms.vault().unwrap(),
+        &Pubkey::new_unique(),
+    );
+
+    let execute = realms.execute_instruction(&proposal, &upgrade);
+    assert_eq!(execute.data, vec![16]);
+    assert_eq!(execute.accounts[2].pubkey, realms.proposal_transaction_address(&proposal));
+    assert_eq!(execute.accounts[3].pubkey, bpf_loader_upgradeable::id());
+    assert_eq!(execute.accounts.len(), 4 + upgrade.accounts.len());
+    assert!(execute.accounts.iter().all(|meta| !meta.is_signer));
+}
+
+#[test]
+fn test_proposal_transaction_decodes_inserted_instruction() {
+    let realms = backend();
+    let upgrade = upgrade_instruction(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &realms.vault().unwrap(),
+        &Pubkey::new_unique(),
+    );
+    let instructions = realms.propose_instructions(&Pubkey::new_unique(), &Pubkey::new_unique(), &upgrade, "v2");
+
+    // ProposalTransactionV2 stores the InsertTransaction arguments after its header
+    let mut data = vec![13];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&instructions[1].data[1..]);
+    data.extend_from_slice(&[0, 0, 0]);
+
+    assert_eq!(decode_proposal_transaction(&data).unwrap(), vec![upgrade]);
+}
+
+#[test]
+fn test_proposal_account_decodes_votes() {
+    let governance = Pubkey::new_unique();
+    let owner_record = Pubkey::new_unique();
+
+    let mut data = vec![14];
+    data.extend_from_slice(governance.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.push(2); // Voting
+    data.extend_from_slice(owner_record.as_ref());
+    data.extend_from_slice(&[0, 0, 0]); // signatories, SingleChoice
+    data.extend_from_slice(&1u32.to_le_bytes());
+    push_string(&mut data, "Approve");
+    data.extend_from_slice(&600u64.to_le_bytes());
+    data.extend_from_slice(&[0; 7]);
+    data.push(1);
+    data.extend_from_slice(&250u64.to_le_bytes());
+
+    let proposal = RealmsProposal::try_from_account_data(&data).unwrap();
+    assert_eq!(proposal.governance, governance);
+    assert_eq!(proposal.state, "voting");
+    assert_eq!(proposal.token_owner_record, owner_record);
+    assert_eq!(proposal.yes_vote_weight, 600);
+    assert_eq!(proposal.deny_vote_weight, 250);
+}
@@ -0,0 +1,46 @@
+use goquant_upgrade_service::receipts::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn signed_receipt(signer: &Keypair) -> SignedReceipt {
+    let receipt = ApprovalReceipt {
+        version: RECEIPT_VERSION.to_string(),
+        member: "member1".to_string(),
+        proposal_id: "proposal-1".to_string(),
+        proposal_pda: None,
+        buffer: "Buffer11111111111111111111111111111111".to_string(),
+        buffer_hash: "ab".repeat(32),
+        approved_at: 1_700_000_000,
+    };
+    let message = receipt.message();
+    let signature = signer.sign_message(message.as_bytes());
+
+    SignedReceipt {
+        receipt,
+        message,
+        signer: signer.pubkey().to_string(),
+        signature: signature.to_string(),
+    }
+}
+
+#[test]
+fn test_receipt_verifies() {
+    let signer = Keypair::new();
+    let signed = signed_receipt(&signer);
+    assert!(signed.verify());
+
+    let bundle = ReceiptBundle {
+        proposal_id: "proposal-1".to_string(),
+        algorithm: RECEIPT_ALGORITHM.to_string(),
+        signer: signer.pubkey().to_string(),
+        receipts: vec![signed],
+    };
+    assert!(bundle.verify());
+}
+
+#[test]
+fn test_tampered_receipt_fails_verification() {
+    let signer = Keypair::new();
+    let mut signed = signed_receipt(&signer);
+    signed.receipt.buffer_hash = "cd".repeat(32);
+    assert!(!signed.verify());
+}
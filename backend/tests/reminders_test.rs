@@ -0,0 +1,84 @@
+use goquant_upgrade_service::proposal::{Proposal, ProposalStatus};
+use goquant_upgrade_service::reminders::*;
+use goquant_upgrade_service::test_utils::ProposalBuilder;
+use std::collections::HashSet;
+
+const HOUR: i64 = 3600;
+const PROPOSED_AT: i64 = 1_700_000_000;
+
+fn proposal(approvals: &[&str]) -> Proposal {
+    ProposalBuilder::new("p1")
+        .status(ProposalStatus::Approved)
+        .proposed_at(PROPOSED_AT)
+        .approvals(approvals)
+        .build()
+}
+
+#[test]
+fn test_reminders_escalate_toward_the_deadline() {
+    let schedule = ReminderSchedule::default();
+    let deadline = PROPOSED_AT + 72 * HOUR;
+
+    let times = schedule.reminder_times(PROPOSED_AT);
+    let before_deadline: Vec<i64> = times.iter().map(|t| (deadline - t) / HOUR).collect();
+    // 24h and 6h out, then hourly
+    assert_eq!(before_deadline, vec![24, 6, 5, 4, 3, 2, 1]);
+}
+
+#[test]
+fn test_only_the_latest_missed_reminder_is_due() {
+    let schedule = ReminderSchedule::default();
+    let deadline = PROPOSED_AT + 72 * HOUR;
+
+    assert_eq!(schedule.due_at(PROPOSED_AT, PROPOSED_AT + HOUR), None);
+    assert_eq!(schedule.due_at(PROPOSED_AT, deadline - 24 * HOUR), Some(deadline - 24 * HOUR));
+    assert_eq!(schedule.due_at(PROPOSED_AT, deadline - 10 * HOUR), Some(deadline - 24 * HOUR));
+    // Down from 20h to 4h out: only the 4h reminder goes out
+    assert_eq!(schedule.due_at(PROPOSED_AT, deadline - 4 * HOUR), Some(deadline - 4 * HOUR));
+    assert_eq!(schedule.due_at(PROPOSED_AT, deadline), None);
+}
+
+#[test]
+fn test_offsets_beyond_the_deadline_window_are_skipped() {
+    let schedule = ReminderSchedule {
+        deadline_seconds: 12 * HOUR,
+        offsets_seconds: vec![24 * HOUR, 6 * HOUR],
+        final_interval_seconds: 0,
+    };
+
+    assert_eq!(schedule.reminder_times(PROPOSED_AT), vec![PROPOSED_AT + 6 * HOUR]);
+}
+
+#[test]
+fn test_parses_member_channels() {
+    let contacts = parse_member_channels(
+        "member1=slack:https://hooks.slack.com/services/T0/B0/x, member2=email:ops@goquant.io,member3=websocket",
+    )
+    .unwrap();
+
+    assert_eq!(
+        contacts["member1"],
+        ReminderContact::Slack("https://hooks.slack.com/services/T0/B0/x".to_string())
+    );
+    assert_eq!(contacts["member2"], ReminderContact::Email("ops@goquant.io".to_string()));
+    assert_eq!(contacts["member3"], ReminderContact::Websocket);
+
+    assert!(parse_member_channels("").unwrap().is_empty());
+    assert!(parse_member_channels("member1=pager").is_err());
+    assert!(parse_member_channels("member1=email:nobody").is_err());
+    assert!(parse_member_channels("=websocket").is_err());
+}
+
+#[test]
+fn test_skips_approvers_and_opted_out_members() {
+    let members: Vec<String> = ["member1", "member2", "member3", "member4"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    let opted_out = HashSet::from(["member4".to_string()]);
+
+    assert_eq!(
+        pending_members(&members, &proposal(&["member1"]), &opted_out),
+        vec!["member2".to_string(), "member3".to_string()]
+    );
+}
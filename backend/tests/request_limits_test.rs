@@ -0,0 +1,36 @@
+use axum::http::Method;
+use goquant_upgrade_service::request_limits::*;
+use std::time::Duration;
+
+#[test]
+fn test_attachment_routes_get_the_larger_body_limit() {
+    assert_eq!(body_class("/upgrade/propose"), BodyClass::Attachments);
+    assert_eq!(body_class("/upgrade/propose-binary"), BodyClass::Attachments);
+    assert_eq!(body_class("/upgrade/draft/d1"), BodyClass::Attachments);
+    assert_eq!(body_class("/upgrade/abc/sandbox/run"), BodyClass::Attachments);
+    assert_eq!(body_class("/history/import"), BodyClass::Attachments);
+
+    assert_eq!(body_class("/upgrade/abc/approve"), BodyClass::Json);
+    assert_eq!(body_class("/upgrade/approve-batch"), BodyClass::Json);
+    assert_eq!(body_class("/migration/start"), BodyClass::Json);
+
+    let limits = RequestLimits::default();
+    assert_eq!(limits.body_limit("/upgrade/propose"), limits.attachment_body_bytes);
+    assert_eq!(limits.body_limit("/upgrade/abc/cancel"), limits.json_body_bytes);
+}
+
+#[test]
+fn test_execution_gets_the_short_timeout() {
+    let limits = RequestLimits {
+        timeout: Duration::from_secs(30),
+        long_timeout: Duration::from_secs(300),
+        ..RequestLimits::default()
+    };
+
+    assert_eq!(limits.timeout_for(&Method::POST, "/upgrade/abc/execute"), Duration::from_secs(30));
+    assert_eq!(limits.timeout_for(&Method::POST, "/upgrade/abc/execute/confirm"), Duration::from_secs(30));
+    assert_eq!(limits.timeout_for(&Method::POST, "/upgrade/abc/fork-test"), Duration::from_secs(300));
+    assert_eq!(limits.timeout_for(&Method::POST, "/rollback/drill"), Duration::from_secs(300));
+    // Reading a stored fork test is quick
+    assert_eq!(limits.timeout_for(&Method::GET, "/upgrade/abc/fork-test"), Duration::from_secs(30));
+}
@@ -0,0 +1,55 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::{middleware, Router};
+use goquant_upgrade_service::monitoring::MonitoringService;
+use goquant_upgrade_service::request_metrics::*;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+#[test]
+fn test_histogram_and_status_breakdown() {
+    let metrics = RequestMetrics::default();
+    metrics.record("GET", "/v1/upgrade/:id/status", 200, Duration::from_millis(3));
+    metrics.record("GET", "/v1/upgrade/:id/status", 200, Duration::from_millis(40));
+    metrics.record("GET", "/v1/upgrade/:id/status", 503, Duration::from_secs(30));
+
+    let snapshot = metrics.snapshot();
+    let route = &snapshot[&("GET".to_string(), "/v1/upgrade/:id/status".to_string())];
+    assert_eq!(route.requests, 3);
+    assert_eq!(route.statuses[&200], 2);
+    assert_eq!(route.errors(), 1);
+    assert_eq!(route.latency_buckets[0], 1);
+    assert_eq!(route.latency_buckets[LATENCY_BUCKETS.len()], 1);
+
+    let text = metrics.render_prometheus();
+    assert!(text.contains(r#"http_requests_total{method="GET",route="/v1/upgrade/:id/status",status="503"} 1"#));
+    assert!(text.contains(r#"http_request_duration_seconds_bucket{method="GET",route="/v1/upgrade/:id/status",le="0.05"} 2"#));
+    assert!(text.contains(r#"http_request_duration_seconds_bucket{method="GET",route="/v1/upgrade/:id/status",le="+Inf"} 3"#));
+}
+
+#[tokio::test]
+async fn test_middleware_labels_by_route_template() {
+    let monitoring = Arc::new(MonitoringService::new());
+    let app = Router::new()
+        .nest(
+            "/v1",
+            Router::new().route("/upgrade/:id/status", get(|| async { "ok" })),
+        )
+        .layer(middleware::from_fn_with_state(monitoring.clone(), track_requests));
+
+    for path in ["/v1/upgrade/a/status", "/v1/upgrade/b/status", "/nope"] {
+        app.clone()
+            .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let snapshot = monitoring.request_metrics().snapshot();
+    assert_eq!(snapshot[&("GET".to_string(), "/v1/upgrade/:id/status".to_string())].requests, 2);
+    assert_eq!(
+        snapshot[&("GET".to_string(), UNMATCHED_ROUTE.to_string())].statuses[&StatusCode::NOT_FOUND.as_u16()],
+        1
+    );
+}
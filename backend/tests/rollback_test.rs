@@ -0,0 +1,75 @@
+use goquant_upgrade_service::rollback::*;
+use goquant_upgrade_service::websocket::NotificationService;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+#[test]
+fn test_runbook_deviations() {
+    assert!(runbook_deviations(&RUNBOOK_STEPS).is_empty());
+
+    let skipped = [
+        RollbackStep::Pause,
+        RollbackStep::DeployPrevious,
+        RollbackStep::Resume,
+    ];
+    assert_eq!(
+        runbook_deviations(&skipped),
+        vec!["close_positions did not run", "return_funds did not run"]
+    );
+
+    // Redeploying before positions are closed is out of order
+    let reordered = [
+        RollbackStep::Pause,
+        RollbackStep::DeployPrevious,
+        RollbackStep::ClosePositions,
+        RollbackStep::ReturnFunds,
+        RollbackStep::Resume,
+    ];
+    assert_eq!(runbook_deviations(&reordered).len(), 1);
+}
+
+#[test]
+fn test_rollback_target_undoes_latest_successful_upgrade() {
+    let program = Pubkey::new_unique().to_string();
+    let history = vec![
+        json!({ "proposal_id": "failed", "program": program, "success": false, "new_program_hash": "ccc" }),
+        json!({ "proposal_id": "latest", "program": program, "success": true, "old_program_hash": null, "new_program_hash": "bbb" }),
+        json!({ "proposal_id": "other", "program": "Other111", "success": true, "new_program_hash": "zzz" }),
+        json!({ "proposal_id": null, "signature": "5sig", "program": program, "success": true, "new_program_hash": "aaa" }),
+    ];
+
+    assert_eq!(
+        rollback_target(&history, &program),
+        Some(RollbackTarget {
+            upgrade: "latest".to_string(),
+            program_hash: Some("aaa".to_string()),
+            deployed_by: Some("5sig".to_string()),
+        })
+    );
+    assert_eq!(rollback_target(&history, &Pubkey::new_unique().to_string()), None);
+}
+
+#[tokio::test]
+async fn test_mocked_drill_runs_runbook_and_fires_alert() {
+    let notifications = Arc::new(NotificationService::new());
+    let handler = RollbackHandler::new().await.unwrap().with_notifications(notifications);
+    let program = Pubkey::new_unique().to_string();
+
+    let report = handler
+        .drill(DrillRequest {
+            program: Some(program.clone()),
+            devnet_clone: false,
+            rto_secs: Some(60),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(report.program, program);
+    assert_eq!(report.target, "mocked");
+    assert_eq!(report.steps.iter().map(|s| s.step).collect::<Vec<_>>(), RUNBOOK_STEPS.to_vec());
+    assert!(report.runbook_deviations.is_empty());
+    assert!(report.alerts.iter().all(|alert| alert.fired && !alert.persisted));
+    assert!(report.within_rto);
+    assert!(report.passed);
+}
@@ -0,0 +1,59 @@
+use goquant_upgrade_service::rpc_quorum::{agree, QuorumRpc};
+
+fn answers(values: &[Option<i64>]) -> Vec<Result<i64, String>> {
+    values
+        .iter()
+        .map(|value| value.ok_or_else(|| "connection refused".to_string()))
+        .collect()
+}
+
+#[test]
+fn test_two_of_three_agree() {
+    let agreement = agree(&answers(&[Some(7), Some(7), Some(9)]), 2, |a, b| a == b).unwrap();
+
+    assert_eq!(agreement.value, 7);
+    assert_eq!(agreement.agreeing, 2);
+    assert_eq!(agreement.diverging, vec![2]);
+    assert!(agreement.failed.is_empty());
+}
+
+#[test]
+fn test_failed_providers_are_not_divergence() {
+    let agreement = agree(&answers(&[None, Some(7), Some(7)]), 2, |a, b| a == b).unwrap();
+
+    assert_eq!(agreement.value, 7);
+    assert!(agreement.diverging.is_empty());
+    assert_eq!(agreement.failed, vec![0]);
+}
+
+#[test]
+fn test_no_quorum_is_an_error() {
+    // Every provider answers differently
+    assert!(agree(&answers(&[Some(1), Some(2), Some(3)]), 2, |a, b| a == b).is_err());
+    // Only one answer left to agree with itself
+    assert!(agree(&answers(&[Some(1), None, None]), 2, |a, b| a == b).is_err());
+    assert!(agree(&answers(&[]), 2, |a, b| a == b).is_err());
+}
+
+#[test]
+fn test_clock_answers_agree_within_tolerance() {
+    let within_five_seconds = |a: &i64, b: &i64| (a - b).abs() <= 5;
+
+    let agreement = agree(&answers(&[Some(1_000), Some(1_003), Some(1_600)]), 2, within_five_seconds).unwrap();
+    assert_eq!(agreement.value, 1_000);
+    assert_eq!(agreement.diverging, vec![2]);
+
+    // A provider lagging ten minutes cannot outvote the other two
+    let agreement = agree(&answers(&[Some(400), Some(1_000), Some(1_002)]), 2, within_five_seconds).unwrap();
+    assert_eq!(agreement.value, 1_000);
+    assert_eq!(agreement.diverging, vec![0]);
+}
+
+#[test]
+fn test_quorum_must_fit_the_providers() {
+    let urls = |count: usize| (0..count).map(|i| format!("https://rpc{}.example.com", i)).collect::<Vec<_>>();
+
+    assert!(QuorumRpc::new(urls(3), 2).is_ok());
+    assert!(QuorumRpc::new(urls(3), 4).is_err());
+    assert!(QuorumRpc::new(urls(3), 0).is_err());
+}
@@ -0,0 +1,63 @@
+use goquant_upgrade_service::sampling::{
+    sample_size, wilson_upper_bound, SampleVerification, SampledAccount, SamplingConfig,
+};
+
+fn config(max_error_percent: f64) -> SamplingConfig {
+    SamplingConfig {
+        sample_percent: 5.0,
+        max_error_percent,
+    }
+}
+
+fn sampled(index: usize, verified: bool) -> SampledAccount {
+    SampledAccount {
+        account: format!("account-{}", index),
+        account_type: "user_account".to_string(),
+        verified,
+        reason: (!verified).then(|| "migrated data does not match its backup".to_string()),
+    }
+}
+
+#[test]
+fn test_sample_size() {
+    assert_eq!(sample_size(1000, 5.0), 50);
+    assert_eq!(sample_size(10, 5.0), 1);
+    assert_eq!(sample_size(10, 100.0), 10);
+    assert_eq!(sample_size(0, 5.0), 0);
+    assert_eq!(sample_size(1000, 0.0), 0);
+}
+
+#[test]
+fn test_wilson_upper_bound() {
+    // No failures in 50 samples still leaves room for a ~7% error rate
+    assert!((wilson_upper_bound(0, 50) - 0.0714).abs() < 0.001);
+    assert!(wilson_upper_bound(0, 500) < wilson_upper_bound(0, 50));
+    assert!(wilson_upper_bound(5, 50) > 0.1);
+    assert_eq!(wilson_upper_bound(0, 0), 1.0);
+}
+
+#[test]
+fn test_clean_sample_passes() {
+    let results = (0..50).map(|i| sampled(i, true)).collect();
+    let report = SampleVerification::summarize(&config(0.0), 1000, results);
+
+    assert!(report.passed);
+    assert_eq!(report.verified, 50);
+    assert_eq!(report.error_rate_percent, 0.0);
+    assert!((report.confidence_percent - 92.86).abs() < 0.01);
+    assert!(report.failures.is_empty());
+}
+
+#[test]
+fn test_failures_over_threshold_fail_the_migration() {
+    let results: Vec<_> = (0..50).map(|i| sampled(i, i % 25 != 0)).collect();
+
+    let strict = SampleVerification::summarize(&config(0.0), 1000, results.clone());
+    assert!(!strict.passed);
+    assert_eq!(strict.failed, 2);
+    assert_eq!(strict.error_rate_percent, 4.0);
+    assert_eq!(strict.failures[0].account, "account-0");
+
+    let tolerant = SampleVerification::summarize(&config(5.0), 1000, results);
+    assert!(tolerant.passed);
+}
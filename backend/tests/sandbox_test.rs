@@ -0,0 +1,100 @@
+use goquant_upgrade_service::sandbox::{
+    diff_accounts, SandboxAccountMeta, SandboxInstruction, SandboxRunRequest, SandboxTransaction,
+    MAX_SANDBOX_TRANSACTIONS, PAYER_PLACEHOLDER,
+};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+fn meta(pubkey: &str, is_signer: bool, is_writable: bool) -> SandboxAccountMeta {
+    SandboxAccountMeta {
+        pubkey: pubkey.to_string(),
+        is_signer,
+        is_writable,
+    }
+}
+
+fn transaction(accounts: Vec<SandboxAccountMeta>) -> SandboxTransaction {
+    SandboxTransaction {
+        instructions: vec![SandboxInstruction {
+            program_id: Pubkey::new_unique().to_string(),
+            accounts,
+            data: "AQID".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_payer_placeholder_resolves_to_sandbox_payer() {
+    let payer = Pubkey::new_unique();
+    let market = Pubkey::new_unique();
+    let instructions = transaction(vec![
+        meta(PAYER_PLACEHOLDER, true, true),
+        meta(&market.to_string(), false, true),
+    ])
+    .instructions(&payer)
+    .unwrap();
+
+    assert_eq!(instructions.len(), 1);
+    assert_eq!(instructions[0].accounts[0].pubkey, payer);
+    assert!(instructions[0].accounts[0].is_signer);
+    assert_eq!(instructions[0].accounts[1].pubkey, market);
+    assert_eq!(instructions[0].data, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_only_the_payer_can_sign() {
+    let payer = Pubkey::new_unique();
+    let other = Pubkey::new_unique().to_string();
+    assert!(transaction(vec![meta(&other, true, false)]).instructions(&payer).is_err());
+    assert!(transaction(vec![meta("not-a-key", false, false)]).instructions(&payer).is_err());
+    assert!(SandboxTransaction { instructions: vec![] }.instructions(&payer).is_err());
+}
+
+#[test]
+fn test_run_request_limits() {
+    let request = |count: usize| SandboxRunRequest {
+        transactions: vec![transaction(vec![]); count],
+        clone_accounts: vec![],
+        accounts: vec![],
+    };
+    assert!(request(0).validate().is_err());
+    assert!(request(1).validate().is_ok());
+    assert!(request(MAX_SANDBOX_TRANSACTIONS).validate().is_ok());
+    assert!(request(MAX_SANDBOX_TRANSACTIONS + 1).validate().is_err());
+}
+
+#[test]
+fn test_diff_reports_changed_accounts_and_byte_ranges() {
+    let owner = Pubkey::new_unique();
+    let (changed, unchanged, created) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let account = |lamports: u64, data: Vec<u8>| Account {
+        lamports,
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    };
+
+    let before = BTreeMap::from([
+        (changed, Some(account(10, vec![0, 0, 0, 0, 0, 0]))),
+        (unchanged, Some(account(5, vec![1]))),
+        (created, None),
+    ]);
+    let after = BTreeMap::from([
+        (changed, Some(account(10, vec![0, 9, 9, 0, 7, 0]))),
+        (unchanged, Some(account(5, vec![1]))),
+        (created, Some(account(1, vec![]))),
+    ]);
+
+    let diffs = diff_accounts(&before, &after);
+    assert_eq!(diffs.len(), 2);
+
+    let diff = diffs.iter().find(|d| d.pubkey == changed.to_string()).unwrap();
+    assert_eq!(diff.changed_ranges, vec![(1, 3), (4, 5)]);
+    assert_eq!(diff.after.as_ref().unwrap().data_len, 6);
+
+    let diff = diffs.iter().find(|d| d.pubkey == created.to_string()).unwrap();
+    assert!(diff.before.is_none());
+    assert_eq!(diff.after.as_ref().unwrap().lamports, 1);
+}
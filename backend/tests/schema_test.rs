@@ -0,0 +1,57 @@
+use goquant_upgrade_service::proposal::{AuthorityChange, ProposalStatus};
+use goquant_upgrade_service::schema::*;
+use goquant_upgrade_service::test_utils::{MigrationProgressBuilder, ProposalBuilder};
+use goquant_upgrade_service::websocket::NotificationType;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+fn definitions() -> Value {
+    serde_json::to_value(api_schema()).unwrap()["definitions"].clone()
+}
+
+fn properties(definition: &Value) -> BTreeSet<String> {
+    definition["properties"].as_object().unwrap().keys().cloned().collect()
+}
+
+fn fields(value: impl serde::Serialize) -> BTreeSet<String> {
+    serde_json::to_value(value).unwrap().as_object().unwrap().keys().cloned().collect()
+}
+
+#[test]
+fn test_schema_matches_serialized_fields() {
+    let definitions = definitions();
+    for name in API_TYPES {
+        assert!(definitions.get(name).is_some(), "{} missing", name);
+    }
+
+    let proposal = ProposalBuilder::new("p1")
+        .authority_change(AuthorityChange { new_authority: None })
+        .build();
+    assert_eq!(properties(&definitions["Proposal"]), fields(&proposal));
+    assert_eq!(properties(&definitions["AuthorityChange"]), fields(proposal.authority_change.as_ref().unwrap()));
+
+    let progress = MigrationProgressBuilder::new("m1").account_type("user", &[], 10).build();
+    assert_eq!(properties(&definitions["MigrationProgress"]), fields(&progress));
+    assert_eq!(properties(&definitions["AccountTypeProgress"]), fields(&progress.account_types[0]));
+}
+
+#[test]
+fn test_enums_list_serialized_values() {
+    let definitions = definitions();
+    // Enums with documented variants become a `oneOf` of one-value enums
+    let values = |name: &str| -> Vec<Value> {
+        let definition = &definitions[name];
+        match definition["oneOf"].as_array() {
+            Some(variants) => variants.iter().flat_map(|v| v["enum"].as_array().cloned().unwrap_or_default()).collect(),
+            None => definition["enum"].as_array().cloned().unwrap_or_default(),
+        }
+    };
+
+    assert!(values("ProposalStatus").contains(&serde_json::to_value(ProposalStatus::TimelockActive).unwrap()));
+    assert!(values("RiskTier").contains(&Value::from("critical")));
+
+    let event_types = definitions["Event"]["properties"]["type"]["enum"].as_array().unwrap().clone();
+    assert_eq!(event_types.len(), NotificationType::ALL.len());
+    assert!(event_types.contains(&Value::from("proposal_created")));
+    assert!(event_types.contains(&Value::from("migration_progress")));
+}
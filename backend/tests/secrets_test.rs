@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use goquant_upgrade_service::error::UpgradeError;
+use goquant_upgrade_service::secrets::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct StaticSecrets(Arc<Mutex<HashMap<String, String>>>);
+
+impl StaticSecrets {
+    fn set(&self, key: &str, value: &str) {
+        self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for StaticSecrets {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    async fn fetch(&self, keys: &[&str]) -> Result<HashMap<String, String>, UpgradeError> {
+        let values = self.0.lock().unwrap();
+        Ok(keys
+            .iter()
+            .filter_map(|key| values.get(*key).map(|v| (key.to_string(), v.clone())))
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn test_load_reports_missing_and_malformed_keys_without_values() {
+    let provider = StaticSecrets::default();
+    provider.set("DATABASE_URL", "mysql://user:hunter2@db/upgrades");
+    let store = SecretStore::new(Box::new(provider)).with_required(&["GITHUB_WEBHOOK_SECRET"]);
+
+    let err = store.load().await.unwrap_err().to_string();
+
+    assert!(err.contains("DATABASE_URL"));
+    assert!(err.contains("GITHUB_WEBHOOK_SECRET is missing"));
+    assert!(!err.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn test_refresh_applies_valid_rotations_only() {
+    let provider = StaticSecrets::default();
+    provider.set("GITHUB_WEBHOOK_SECRET", "old-secret");
+    provider.set("SOLANA_RPC_URL", "https://rpc.example.com");
+    let store = SecretStore::new(Box::new(provider.clone()));
+    store.load().await.unwrap();
+
+    provider.set("GITHUB_WEBHOOK_SECRET", "new-secret");
+    provider.set("SOLANA_RPC_URL", "not a url");
+    let rotated = store.refresh().await.unwrap();
+
+    assert_eq!(rotated, vec!["GITHUB_WEBHOOK_SECRET".to_string()]);
+    assert_eq!(store.get("GITHUB_WEBHOOK_SECRET").as_deref(), Some("new-secret"));
+    assert_eq!(store.get("SOLANA_RPC_URL").as_deref(), Some("https://rpc.example.com"));
+}
+
+#[tokio::test]
+async fn test_file_secrets_read_one_file_per_key() {
+    let dir = std::env::temp_dir().join(format!("goquant-secrets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("EXECUTOR_TOKENS"), "bot-token\n").unwrap();
+
+    let values = FileSecrets::new(&dir)
+        .fetch(&["EXECUTOR_TOKENS", "GITHUB_WEBHOOK_SECRET"])
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(values.get("EXECUTOR_TOKENS").map(String::as_str), Some("bot-token"));
+    assert!(!values.contains_key("GITHUB_WEBHOOK_SECRET"));
+}
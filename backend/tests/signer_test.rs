@@ -0,0 +1,48 @@
+use goquant_upgrade_service::signer::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+#[test]
+fn test_secret_key_formats() {
+    let keypair = Keypair::new();
+
+    let from_base58 = LocalSigner::from_secret(&keypair.to_base58_string()).unwrap();
+    let from_json = LocalSigner::from_secret(&serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap()).unwrap();
+
+    assert_eq!(from_base58.pubkey(), keypair.pubkey());
+    assert_eq!(from_json.pubkey(), keypair.pubkey());
+    assert!(LocalSigner::from_secret("not a key").is_err());
+}
+
+#[tokio::test]
+async fn test_sign_transaction_with_signer_and_local_keypair() {
+    let payer = LocalSigner::new(Keypair::new());
+    let account = Keypair::new();
+    let ix = system_instruction::create_account(
+        &payer.pubkey(),
+        &account.pubkey(),
+        1_000_000,
+        0,
+        &solana_sdk::system_program::id(),
+    );
+    let mut tx = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+
+    sign_transaction(&mut tx, &payer, &[&account], Hash::new_unique())
+        .await
+        .unwrap();
+
+    assert!(tx.is_signed());
+    assert!(tx.verify().is_ok());
+}
+
+#[tokio::test]
+async fn test_sign_transaction_rejects_non_signer() {
+    let payer = LocalSigner::new(Keypair::new());
+    let other = Keypair::new();
+    let ix = system_instruction::transfer(&other.pubkey(), &Keypair::new().pubkey(), 1);
+    let mut tx = Transaction::new_with_payer(&[ix], Some(&other.pubkey()));
+
+    assert!(sign_transaction(&mut tx, &payer, &[], Hash::new_unique()).await.is_err());
+}
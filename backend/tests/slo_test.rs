@@ -0,0 +1,87 @@
+use goquant_upgrade_service::slo::*;
+
+const NOW: i64 = 1_700_000_000;
+
+fn latency_slo() -> SloDefinition {
+    SloDefinition {
+        name: "status_read_latency".to_string(),
+        operation: "status_read".to_string(),
+        kind: SloKind::Latency { threshold_ms: 200 },
+        target: 0.99,
+        window_seconds: 24 * 3600,
+    }
+}
+
+#[test]
+fn test_compliance_counts_only_matching_operation_and_kind() {
+    let mut tracker = SloTracker::new(default_slos());
+
+    for _ in 0..99 {
+        tracker.record_latency("status_read", 50, NOW);
+    }
+    tracker.record_latency("status_read", 500, NOW);
+    tracker.record_latency("other", 500, NOW);
+    tracker.record_outcome("status_read", false, NOW);
+
+    let status = tracker.status(NOW);
+    let latency = status.iter().find(|s| s.name == "status_read_latency").unwrap();
+    assert_eq!(latency.total, 100);
+    assert_eq!(latency.good, 99);
+    assert!(latency.met);
+    assert!(latency.error_budget_remaining.abs() < 1e-9);
+
+    let execution = status.iter().find(|s| s.name == "execution_success").unwrap();
+    assert_eq!(execution.total, 0);
+    assert_eq!(execution.compliance, 1.0);
+}
+
+#[test]
+fn test_samples_leave_the_rolling_window() {
+    let mut tracker = SloTracker::new(vec![latency_slo()]);
+
+    tracker.record_latency("status_read", 500, NOW - 2 * 24 * 3600);
+    tracker.record_latency("status_read", 50, NOW);
+
+    let status = &tracker.status(NOW)[0];
+    assert_eq!(status.total, 1);
+    assert!(status.met);
+}
+
+#[test]
+fn test_fast_burn_pages_and_slow_burn_warns() {
+    let mut fast = SloTracker::new(vec![latency_slo()]);
+    for i in 0..100 {
+        fast.record_latency("status_read", if i < 20 { 500 } else { 50 }, NOW - 60);
+    }
+    let alerts = fast.burn_rate_alerts(NOW);
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].page);
+    assert_eq!(alerts[0].long_window_seconds, 3600);
+
+    // 8% errors spread over the last 6 hours, none in the last hour
+    let mut slow = SloTracker::new(vec![latency_slo()]);
+    for minute in 60..360 {
+        for i in 0..25 {
+            slow.record_latency("status_read", if i < 2 { 500 } else { 50 }, NOW - minute * 60);
+        }
+    }
+    assert!(slow.burn_rate_alerts(NOW).is_empty());
+
+    for i in 0..25 {
+        slow.record_latency("status_read", if i < 2 { 500 } else { 50 }, NOW - 600);
+    }
+    let alerts = slow.burn_rate_alerts(NOW);
+    assert_eq!(alerts.len(), 1);
+    assert!(!alerts[0].page);
+}
+
+#[test]
+fn test_slo_definition_config_format() {
+    let slos: Vec<SloDefinition> = serde_json::from_str(
+        r#"[{"name": "exec", "operation": "execution", "kind": "success_rate", "target": 0.995}]"#,
+    )
+    .unwrap();
+
+    assert_eq!(slos[0].kind, SloKind::SuccessRate);
+    assert_eq!(slos[0].window_seconds, 30 * 24 * 3600);
+}
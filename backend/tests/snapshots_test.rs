@@ -0,0 +1,78 @@
+use goquant_upgrade_service::snapshots::*;
+
+fn snapshot(accounts: &[(u64, u64)]) -> AccountSetSnapshot {
+    AccountSetSnapshot::from_accounts(
+        "Program11111111111111111111111111111",
+        Some("proposal-1"),
+        SnapshotLabel::PreUpgrade,
+        accounts.iter().copied(),
+    )
+}
+
+fn uniform(count: usize, data_len: u64, lamports: u64) -> Vec<(u64, u64)> {
+    vec![(data_len, lamports); count]
+}
+
+#[test]
+fn test_snapshot_buckets_by_data_length() {
+    let mut accounts = uniform(3, 165, 1_000);
+    accounts.push((512, 5_000));
+
+    let snapshot = snapshot(&accounts);
+
+    assert_eq!(snapshot.account_count, 4);
+    assert_eq!(snapshot.total_lamports, 8_000);
+    assert_eq!(snapshot.size_buckets[&165], SizeBucket { accounts: 3, lamports: 3_000 });
+    assert_eq!(snapshot.size_buckets[&512], SizeBucket { accounts: 1, lamports: 5_000 });
+}
+
+#[test]
+fn test_unchanged_set_has_no_anomalies() {
+    let before = snapshot(&uniform(100, 165, 1_000));
+    let after = snapshot(&uniform(100, 165, 1_000));
+
+    let diff = diff(&before, &after, &AnomalyThresholds::default());
+
+    assert!(diff.size_changes.is_empty());
+    assert!(!diff.has_anomalies());
+}
+
+#[test]
+fn test_mass_closure_flagged() {
+    let before = snapshot(&uniform(100, 165, 1_000));
+    let after = snapshot(&uniform(70, 165, 1_000));
+
+    let diff = diff(&before, &after, &AnomalyThresholds::default());
+
+    let kinds: Vec<AnomalyKind> = diff.anomalies.iter().map(|a| a.kind).collect();
+    assert_eq!(kinds, vec![AnomalyKind::MassClosure, AnomalyKind::LamportDrain]);
+    assert_eq!(diff.size_changes.len(), 1);
+    assert_eq!(diff.size_changes[0].after.accounts, 70);
+}
+
+#[test]
+fn test_unexpected_growth_flagged() {
+    let before = snapshot(&uniform(20, 165, 1_000));
+    let mut accounts = uniform(20, 165, 1_000);
+    accounts.extend(uniform(20, 256, 2_000));
+    let after = snapshot(&accounts);
+
+    let diff = diff(&before, &after, &AnomalyThresholds::default());
+
+    let kinds: Vec<AnomalyKind> = diff.anomalies.iter().map(|a| a.kind).collect();
+    assert_eq!(kinds, vec![AnomalyKind::UnexpectedGrowth]);
+    assert_eq!(diff.size_changes[0].data_len, 256);
+    assert_eq!(diff.size_changes[0].before, SizeBucket::default());
+}
+
+#[test]
+fn test_small_sets_not_flagged() {
+    let before = snapshot(&uniform(5, 165, 1_000));
+    let after = snapshot(&uniform(1, 165, 1_000));
+
+    let diff = diff(&before, &after, &AnomalyThresholds::default());
+
+    assert!(!diff.has_anomalies());
+    assert_eq!(diff.accounts_before, 5);
+    assert_eq!(diff.accounts_after, 1);
+}
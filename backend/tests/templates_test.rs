@@ -0,0 +1,82 @@
+use goquant_upgrade_service::templates::*;
+use serde_json::json;
+use std::collections::HashMap;
+
+fn table(locale: &str, event_type: &str, channel: &str, text: &str) -> TemplateTable {
+    HashMap::from([(
+        locale.to_string(),
+        HashMap::from([(
+            event_type.to_string(),
+            HashMap::from([(channel.to_string(), text.to_string())]),
+        )]),
+    )])
+}
+
+#[test]
+fn test_builtin_templates_match_service_wording() {
+    let templates = NotificationTemplates::builtin();
+
+    let approved = templates.render(
+        "proposal_approved",
+        Channel::Websocket,
+        None,
+        Some("p1"),
+        &json!({ "approvals": 2, "threshold": 3 }),
+    );
+    assert_eq!(approved.as_deref(), Some("Proposal approved: 2/3"));
+
+    let progress = templates.render(
+        "migration_progress",
+        Channel::Email,
+        None,
+        None,
+        &json!({ "progress_percent": 41.5 }),
+    );
+    assert_eq!(progress.as_deref(), Some("Migration progress: 41.50%"));
+
+    assert_eq!(templates.render("unknown", Channel::Slack, None, None, &json!({})), None);
+}
+
+#[test]
+fn test_channel_and_locale_fall_back() {
+    let mut templates = NotificationTemplates::builtin();
+    templates.merge(table("en", "proposal_approved", "slack", ":white_check_mark: {{proposal_id}}"));
+    templates.merge(table("de", "proposal_approved", "default", "Genehmigt: {{approvals}}/{{threshold}}"));
+
+    let data = json!({ "approvals": 1, "threshold": 3 });
+    let render = |channel, locale| templates.render("proposal_approved", channel, locale, Some("p1"), &data);
+
+    assert_eq!(render(Channel::Slack, None).as_deref(), Some(":white_check_mark: p1"));
+    assert_eq!(render(Channel::Email, None).as_deref(), Some("Proposal approved: 1/3"));
+    assert_eq!(render(Channel::Slack, Some("de")).as_deref(), Some("Genehmigt: 1/3"));
+    assert_eq!(render(Channel::Slack, Some("fr")).as_deref(), Some(":white_check_mark: p1"));
+
+    // Types the locale doesn't translate keep the English text
+    let executed = templates.render("upgrade_executed", Channel::Websocket, Some("de"), None, &json!({}));
+    assert_eq!(executed.as_deref(), Some("Upgrade executed successfully"));
+    assert_eq!(templates.locales(), vec!["de".to_string(), "en".to_string()]);
+}
+
+#[test]
+fn test_default_locale_applies_without_client_locale() {
+    let mut templates = NotificationTemplates::builtin().with_default_locale("de");
+    templates.merge(table("de", "timelock_expired", "default", "Zeitsperre abgelaufen"));
+
+    let message = templates.render("timelock_expired", Channel::Websocket, None, None, &json!({}));
+    assert_eq!(message.as_deref(), Some("Zeitsperre abgelaufen"));
+}
+
+#[test]
+fn test_interpolate_paths_precision_and_missing_variables() {
+    let vars = json!({
+        "proposal": { "program": "Prog111" },
+        "ratio": 2,
+        "note": null,
+        "flags": [1, 2],
+    });
+
+    assert_eq!(interpolate("{{ proposal.program }} at {{ratio:.1}}x", &vars), "Prog111 at 2.0x");
+    assert_eq!(interpolate("[{{note}}] {{flags}}", &vars), "[] [1,2]");
+    assert_eq!(interpolate("{{missing}} and {{proposal.nope}}", &vars), "{{missing}} and {{proposal.nope}}");
+    assert_eq!(interpolate("unterminated {{ratio", &vars), "unterminated {{ratio");
+}
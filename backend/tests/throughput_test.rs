@@ -0,0 +1,72 @@
+use goquant_upgrade_service::throughput::{is_rate_limit_error, BatchOutcome, ConcurrencyController, ThroughputConfig};
+
+fn config() -> ThroughputConfig {
+    ThroughputConfig {
+        min_concurrency: 1,
+        max_concurrency: 8,
+        initial_concurrency: 4,
+        target_confirmation_ms: 1_000,
+        max_error_percent: 10.0,
+    }
+}
+
+fn batch(accounts: usize, errors: usize, rate_limited: usize, avg_confirmation_ms: u64) -> BatchOutcome {
+    BatchOutcome {
+        accounts,
+        errors,
+        rate_limited,
+        total_confirmation_ms: avg_confirmation_ms * accounts as u64,
+        elapsed_ms: avg_confirmation_ms,
+    }
+}
+
+#[test]
+fn test_ramps_up_while_rpc_keeps_up() {
+    let mut controller = ConcurrencyController::new(config());
+
+    assert_eq!(controller.observe(&batch(4, 0, 0, 500)), 5);
+    assert_eq!(controller.observe(&batch(5, 0, 0, 500)), 6);
+    // A short final batch says nothing about more parallelism
+    assert_eq!(controller.observe(&batch(2, 0, 0, 500)), 6);
+    for _ in 0..5 {
+        controller.observe(&batch(controller.current(), 0, 0, 500));
+    }
+    assert_eq!(controller.current(), 8);
+}
+
+#[test]
+fn test_backs_off_on_throttling_errors_and_slow_confirmations() {
+    let mut controller = ConcurrencyController::new(ThroughputConfig {
+        initial_concurrency: 8,
+        ..config()
+    });
+
+    assert_eq!(controller.observe(&batch(8, 1, 1, 500)), 4);
+    // 2 of 4 failed, above the 10% allowed
+    assert_eq!(controller.observe(&batch(4, 2, 0, 500)), 2);
+    assert_eq!(controller.observe(&batch(2, 0, 0, 1_500)), 1);
+    // Never below the minimum
+    assert_eq!(controller.observe(&batch(1, 1, 1, 500)), 1);
+}
+
+#[test]
+fn test_batch_rates() {
+    let outcome = BatchOutcome {
+        accounts: 10,
+        errors: 2,
+        rate_limited: 0,
+        total_confirmation_ms: 15_000,
+        elapsed_ms: 2_000,
+    };
+
+    assert_eq!(outcome.error_percent(), 20.0);
+    assert_eq!(outcome.avg_confirmation_ms(), 1_500);
+    assert_eq!(outcome.accounts_per_second(), 4.0);
+}
+
+#[test]
+fn test_detects_rate_limit_errors() {
+    assert!(is_rate_limit_error("HTTP status client error (429 Too Many Requests) for url"));
+    assert!(is_rate_limit_error("too many requests for a specific RPC call"));
+    assert!(!is_rate_limit_error("connection refused"));
+}
@@ -0,0 +1,57 @@
+use goquant_upgrade_service::multisig_backend::{MultisigBackend, MultisigBackendKind};
+use goquant_upgrade_service::tss::{is_retryable_status, TssBackend, TssSession};
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::pubkey::Pubkey;
+
+fn session(status: &str, participants: &[&str]) -> TssSession {
+    TssSession {
+        session: Pubkey::new_unique().to_string(),
+        program: Pubkey::new_unique().to_string(),
+        buffer: Pubkey::new_unique().to_string(),
+        status: status.to_string(),
+        participants: participants.iter().map(|p| p.to_string()).collect(),
+        threshold: 2,
+    }
+}
+
+#[test]
+fn test_session_participants_are_approvals() {
+    let waiting = session("open", &["alice"]);
+    let status = waiting.to_status();
+    assert_eq!(status.approvals, vec!["alice".to_string()]);
+    assert_eq!(status.threshold, 2);
+    assert!(!status.executable);
+
+    assert!(session("open", &["alice", "bob"]).executable());
+    // A signed or cancelled session cannot run another round
+    assert!(!session("signed", &["alice", "bob"]).executable());
+    assert!(!session("cancelled", &["alice", "bob", "carol"]).executable());
+}
+
+#[test]
+fn test_retries_only_transient_coordinator_errors() {
+    assert!(is_retryable_status(None));
+    assert!(is_retryable_status(Some(409)));
+    assert!(is_retryable_status(Some(503)));
+    assert!(!is_retryable_status(Some(400)));
+    assert!(!is_retryable_status(Some(403)));
+    assert!(!is_retryable_status(Some(404)));
+}
+
+#[tokio::test]
+async fn test_group_key_is_the_upgrade_authority() {
+    let group_key = Pubkey::new_unique();
+    let backend = TssBackend::new(vec!["http://localhost:9/".to_string()], group_key, None);
+
+    assert_eq!(backend.kind(), MultisigBackendKind::Tss);
+    assert_eq!(backend.vault(), Some(group_key));
+    assert_eq!(backend.upgrade_authority().await.unwrap(), group_key);
+    assert_eq!(backend.program_id(), bpf_loader_upgradeable::id());
+
+    // Execution is co-signed by the group key through the session's signing round
+    let signer = backend.authority_signer(&Pubkey::new_unique()).unwrap();
+    assert_eq!(signer.pubkey(), group_key);
+
+    // Approvals happen on the parties' devices, not through instructions
+    assert!(backend.approve(&Pubkey::new_unique(), &Pubkey::new_unique()).await.is_err());
+}
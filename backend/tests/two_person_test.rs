@@ -0,0 +1,49 @@
+use goquant_upgrade_service::error::UpgradeError;
+use goquant_upgrade_service::secrets::SecretStore;
+use goquant_upgrade_service::security::{executor_identity, verify_executor_token};
+use goquant_upgrade_service::two_person::ExecutionRequest;
+
+#[test]
+fn test_confirmation_needs_a_different_executor_within_the_window() {
+    let request = ExecutionRequest::new("proposal-1", "alice", false, 1_000, 300);
+    assert_eq!(request.expires_at, 1_300);
+
+    assert!(matches!(
+        request.check_confirmation("alice", 1_010),
+        Err(UpgradeError::Forbidden(_))
+    ));
+    assert!(request.check_confirmation("bob", 1_300).is_ok());
+    assert!(matches!(
+        request.check_confirmation("bob", 1_301),
+        Err(UpgradeError::InvalidRequest(_))
+    ));
+}
+
+#[test]
+fn test_only_the_initiator_restarts_a_live_request() {
+    let request = ExecutionRequest::new("proposal-1", "alice", true, 1_000, 300);
+
+    assert!(request.check_replace("alice", 1_100).is_ok());
+    assert!(request.check_replace("bob", 1_100).is_err());
+    assert!(request.check_replace("bob", 1_301).is_ok());
+}
+
+#[test]
+fn test_executor_identities() {
+    std::env::set_var("EXECUTOR_TOKENS", "alice:alice-token, bob:bob-token, bare-token");
+    let secrets = SecretStore::env();
+
+    assert_eq!(executor_identity(Some("alice-token"), &secrets).unwrap(), "alice");
+    assert_eq!(executor_identity(Some("bob-token"), &secrets).unwrap(), "bob");
+
+    let bare = executor_identity(Some("bare-token"), &secrets).unwrap();
+    assert!(bare.starts_with("token-"));
+    assert!(!bare.contains("bare-token"));
+
+    assert!(verify_executor_token(Some("alice-token"), &secrets).is_ok());
+    assert!(matches!(
+        executor_identity(Some("other"), &secrets),
+        Err(UpgradeError::Forbidden(_))
+    ));
+    assert!(executor_identity(None, &secrets).is_err());
+}
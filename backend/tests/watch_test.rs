@@ -0,0 +1,108 @@
+use base64::Engine;
+use goquant_upgrade_service::indexer::event_discriminator;
+use goquant_upgrade_service::watch::*;
+
+fn expression(matcher: WatchMatcher, kind: WatchKind, min_matches: Option<u32>) -> WatchExpression {
+    NewWatchExpression {
+        name: "dex".to_string(),
+        matcher,
+        kind,
+        min_matches,
+        window_seconds: 600,
+        rollback: false,
+        created_by: None,
+    }
+    .into_expression(0)
+    .unwrap()
+}
+
+fn event_log(name: &str, payload: &[u8]) -> String {
+    let mut data = event_discriminator(name).to_vec();
+    data.extend_from_slice(payload);
+    format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(data))
+}
+
+#[test]
+fn test_matchers_count_log_lines() {
+    let logs = vec![
+        "Program log: Instruction: PlaceOrder".to_string(),
+        event_log("CrankHeartbeat", &[1, 2, 3]),
+        "Program log: AnchorError occurred. Error Code: OracleStale.".to_string(),
+        event_log("CrankHeartbeat", &[]),
+        event_log("OrderFilled", &[9]),
+    ];
+
+    let errors = expression(
+        WatchMatcher::LogSubstring { substring: "Error Code: OracleStale".to_string() },
+        WatchKind::Anomaly,
+        None,
+    );
+    assert_eq!(errors.matcher.count(&logs), 1);
+
+    // Event names are stored as their discriminator
+    let heartbeat = expression(
+        WatchMatcher::Event { discriminator: "CrankHeartbeat".to_string() },
+        WatchKind::Heartbeat,
+        Some(2),
+    );
+    assert_eq!(
+        heartbeat.matcher,
+        WatchMatcher::Event { discriminator: hex::encode(event_discriminator("CrankHeartbeat")) }
+    );
+    assert_eq!(heartbeat.matcher.count(&logs), 2);
+
+    let by_hex = expression(
+        WatchMatcher::Event { discriminator: format!("0x{}", hex::encode_upper(event_discriminator("OrderFilled"))) },
+        WatchKind::Anomaly,
+        None,
+    );
+    assert_eq!(by_hex.matcher.count(&logs), 1);
+}
+
+#[test]
+fn test_verdicts_flag_errors_at_once_and_missing_heartbeats_at_window_end() {
+    let errors = expression(
+        WatchMatcher::LogSubstring { substring: "Error".to_string() },
+        WatchKind::Anomaly,
+        None,
+    );
+    assert_eq!(errors.verdict(0, 600), None);
+    assert!(errors.verdict(1, 30).is_some());
+
+    let heartbeat = expression(
+        WatchMatcher::Event { discriminator: "CrankHeartbeat".to_string() },
+        WatchKind::Heartbeat,
+        Some(3),
+    );
+    assert_eq!(heartbeat.verdict(0, 300), None);
+    assert_eq!(heartbeat.verdict(3, 600), None);
+    assert_eq!(heartbeat.verdict(2, 600).as_deref(), Some("2 of 3 expected matches in 600s"));
+}
+
+#[test]
+fn test_new_expressions_are_validated() {
+    let new = |matcher: WatchMatcher, kind: WatchKind, min_matches: Option<u32>, window_seconds: i64| NewWatchExpression {
+        name: "dex".to_string(),
+        matcher,
+        kind,
+        min_matches,
+        window_seconds,
+        rollback: true,
+        created_by: None,
+    };
+    let substring = || WatchMatcher::LogSubstring { substring: "panicked".to_string() };
+
+    assert!(new(substring(), WatchKind::Anomaly, None, 0).into_expression(0).is_err());
+    assert!(new(substring(), WatchKind::Anomaly, None, MAX_WATCH_WINDOW_SECONDS + 1).into_expression(0).is_err());
+    assert!(new(substring(), WatchKind::Heartbeat, Some(0), 60).into_expression(0).is_err());
+    assert!(new(WatchMatcher::LogSubstring { substring: String::new() }, WatchKind::Anomaly, None, 60)
+        .into_expression(0)
+        .is_err());
+    assert!(new(WatchMatcher::Event { discriminator: "not an event!".to_string() }, WatchKind::Anomaly, None, 60)
+        .into_expression(0)
+        .is_err());
+
+    let heartbeat = new(substring(), WatchKind::Heartbeat, None, 60).into_expression(0).unwrap();
+    assert_eq!(heartbeat.min_matches, 1);
+    assert!(heartbeat.rollback);
+}
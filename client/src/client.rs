@@ -0,0 +1,324 @@
+use crate::error::{ClientError, Result};
+use crate::types::*;
+use futures_util::stream::{BoxStream, StreamExt};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+/// API version prefix the client talks to
+pub const API_PREFIX: &str = "/v1";
+
+/// How requests authenticate against the service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    None,
+    /// Sent as `X-API-Key`
+    ApiKey(String),
+    /// Sent as `Authorization: Bearer <token>`
+    Bearer(String),
+}
+
+/// Exponential backoff for transient failures (connection errors, 429, 502-504).
+///
+/// Reads are retried on any transient failure. Writes are only retried when
+/// the connection could not be established, so a request the service may
+/// already have acted on is never sent twice.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+pub struct UpgradeClientBuilder {
+    base_url: String,
+    auth: Auth,
+    executor_token: Option<String>,
+    receipt_signer: Option<String>,
+    retry: RetryPolicy,
+    timeout: Duration,
+}
+
+impl UpgradeClientBuilder {
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Token sent as `X-Executor-Token` when forcing execution
+    pub fn executor_token(mut self, token: impl Into<String>) -> Self {
+        self.executor_token = Some(token.into());
+        self
+    }
+
+    /// Pin the service's receipt signer; `approve_signed` rejects receipts from any other key
+    pub fn receipt_signer(mut self, signer: impl Into<String>) -> Self {
+        self.receipt_signer = Some(signer.into());
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Result<UpgradeClient> {
+        let base_url = self.base_url.trim_end_matches('/').to_string();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(ClientError::InvalidBaseUrl(base_url));
+        }
+
+        let http = reqwest::Client::builder().timeout(self.timeout).build()?;
+
+        Ok(UpgradeClient {
+            http,
+            base_url,
+            auth: self.auth,
+            executor_token: self.executor_token,
+            receipt_signer: self.receipt_signer,
+            retry: self.retry,
+        })
+    }
+}
+
+/// Typed client for the upgrade service API
+#[derive(Clone)]
+pub struct UpgradeClient {
+    http: reqwest::Client,
+    base_url: String,
+    auth: Auth,
+    executor_token: Option<String>,
+    receipt_signer: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl UpgradeClient {
+    /// `base_url` is the service root, e.g. `https://upgrades.example.com`;
+    /// requests go to its `/v1` routes
+    pub fn builder(base_url: impl Into<String>) -> UpgradeClientBuilder {
+        UpgradeClientBuilder {
+            base_url: base_url.into(),
+            auth: Auth::None,
+            executor_token: None,
+            receipt_signer: None,
+            retry: RetryPolicy::default(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub async fn propose(&self, request: &ProposeRequest) -> Result<ProposeResponse> {
+        self.send(Method::POST, "/upgrade/propose", |r| r.json(request)).await
+    }
+
+    pub async fn approve(&self, proposal_id: &str) -> Result<ApprovalResponse> {
+        self.send(Method::POST, &format!("/upgrade/{}/approve", proposal_id), |r| r)
+            .await
+    }
+
+    /// Approve and verify the signed receipt returned for the approval.
+    ///
+    /// Fails with `InvalidReceipt` if the signature does not verify, the
+    /// receipt is for another proposal, or it was not signed by the pinned
+    /// `receipt_signer`.
+    pub async fn approve_signed(&self, proposal_id: &str) -> Result<ApprovalResponse> {
+        let approval = self.approve(proposal_id).await?;
+
+        approval.receipt.verify()?;
+        if approval.receipt.receipt.proposal_id != proposal_id {
+            return Err(ClientError::InvalidReceipt(
+                "receipt is for a different proposal".to_string(),
+            ));
+        }
+        if let Some(expected) = &self.receipt_signer {
+            if &approval.receipt.signer != expected {
+                return Err(ClientError::InvalidReceipt(format!(
+                    "receipt signed by {}, expected {}",
+                    approval.receipt.signer, expected
+                )));
+            }
+        }
+
+        Ok(approval)
+    }
+
+    /// Queue execution. `force` skips the dependency health gate and needs an executor token.
+    pub async fn execute(&self, proposal_id: &str, force: bool) -> Result<ExecutionQueued> {
+        let path = format!("/upgrade/{}/execute", proposal_id);
+        let executor_token = self.executor_token.clone();
+
+        self.send(Method::POST, &path, move |r| {
+            let r = r.query(&[("force", force)]);
+            match (&executor_token, force) {
+                (Some(token), true) => r.header("x-executor-token", token),
+                _ => r,
+            }
+        })
+        .await
+    }
+
+    pub async fn cancel(
+        &self,
+        proposal_id: &str,
+        reason: CancellationReason,
+        details: &str,
+    ) -> Result<CancelResponse> {
+        let body = serde_json::json!({ "reason": reason, "details": details });
+        self.send(Method::POST, &format!("/upgrade/{}/cancel", proposal_id), |r| r.json(&body))
+            .await
+    }
+
+    pub async fn status(&self, proposal_id: &str) -> Result<ProposalStatusView> {
+        self.send(Method::GET, &format!("/upgrade/{}/status", proposal_id), |r| r)
+            .await
+    }
+
+    /// Every receipt for the proposal, verified before being returned
+    pub async fn receipts(&self, proposal_id: &str) -> Result<ReceiptBundle> {
+        let bundle: ReceiptBundle = self
+            .send(Method::GET, &format!("/upgrade/{}/receipts", proposal_id), |r| r)
+            .await?;
+        bundle.verify()?;
+        Ok(bundle)
+    }
+
+    /// Persisted events after `since_seq`, oldest first
+    pub async fn events(&self, since_seq: i64, limit: i64) -> Result<Vec<Event>> {
+        self.send(Method::GET, "/events", |r| {
+            r.query(&[("since_seq", since_seq), ("limit", limit)])
+        })
+        .await
+    }
+
+    /// Live notifications over the WebSocket. With `since_seq`, persisted
+    /// events after it are replayed first. A `resync_required` event means
+    /// the connection fell behind; call `events` from its `since_seq` to catch up.
+    pub async fn subscribe_events(
+        &self,
+        since_seq: Option<i64>,
+    ) -> Result<BoxStream<'static, Result<Event>>> {
+        let mut url = format!("{}{}/ws", self.base_url, API_PREFIX)
+            .replacen("http", "ws", 1);
+        if let Some(since_seq) = since_seq {
+            url.push_str(&format!("?since_seq={}", since_seq));
+        }
+
+        let mut request = url.into_client_request()?;
+        if let Some((name, value)) = self.auth_header() {
+            let value = HeaderValue::from_str(&value)
+                .map_err(|e| ClientError::InvalidBaseUrl(e.to_string()))?;
+            request.headers_mut().insert(name, value);
+        }
+
+        let (socket, _) = tokio_tungstenite::connect_async(request).await?;
+
+        let events = socket.filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(serde_json::from_str(&text).map_err(Into::into)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e.into())),
+            }
+        });
+
+        Ok(events.boxed())
+    }
+
+    fn auth_header(&self) -> Option<(&'static str, String)> {
+        match &self.auth {
+            Auth::None => None,
+            Auth::ApiKey(key) => Some(("x-api-key", key.clone())),
+            Auth::Bearer(token) => Some(("authorization", format!("Bearer {}", token))),
+        }
+    }
+
+    async fn send<T, F>(&self, method: Method, path: &str, build: F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let url = format!("{}{}{}", self.base_url, API_PREFIX, path);
+        let idempotent = method == Method::GET;
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self.http.request(method.clone(), &url);
+            if let Some((name, value)) = self.auth_header() {
+                request = request.header(name, value);
+            }
+
+            let result = match build(request).send().await {
+                Ok(response) => Self::decode(response).await,
+                Err(e) => Err(e.into()),
+            };
+
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            let not_sent = matches!(&err, ClientError::Http(e) if e.is_connect());
+            if attempt >= self.retry.max_retries || !err.is_transient() || !(idempotent || not_sent) {
+                return Err(err);
+            }
+
+            tokio::time::sleep(self.retry.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn decode<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+        let status = response.status();
+        let body = response.bytes().await?;
+
+        if status.is_success() {
+            return Ok(serde_json::from_slice(&body)?);
+        }
+
+        let message = serde_json::from_slice::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR.as_str())
+                    .to_string()
+            });
+
+        Err(ClientError::Api {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
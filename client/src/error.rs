@@ -0,0 +1,50 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The service answered with an error status; `message` is its `error` field
+    #[error("API error ({status}): {message}")]
+    Api { status: u16, message: String },
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+
+    #[error("Invalid response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("Invalid receipt: {0}")]
+    InvalidReceipt(String),
+
+    #[error("Invalid base URL: {0}")]
+    InvalidBaseUrl(String),
+}
+
+impl ClientError {
+    /// Whether retrying the same request may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Http(e) => e.is_connect() || e.is_timeout(),
+            ClientError::Api { status, .. } => matches!(status, 429 | 502 | 503 | 504),
+            _ => false,
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ClientError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        ClientError::WebSocket(Box::new(e))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,12 @@
+//! Typed client for the GoQuant upgrade service.
+//!
+//! Wraps the `/v1` REST routes and the WebSocket notification stream so bots
+//! and tooling share one implementation of auth, retries and receipt checks.
+
+pub mod client;
+pub mod error;
+pub mod types;
+
+pub use client::{Auth, RetryPolicy, UpgradeClient, UpgradeClientBuilder};
+pub use error::{ClientError, Result};
+pub use types::*;
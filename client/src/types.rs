@@ -0,0 +1,196 @@
+use crate::error::{ClientError, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Domain separator the service signs receipts with
+pub const RECEIPT_VERSION: &str = "goquant-approval-receipt-v1";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Proposed,
+    Approved,
+    TimelockActive,
+    Executed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CancellationReason {
+    SecurityIssue,
+    BuildMismatch,
+    Superseded,
+    ProposerWithdrawn,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposeRequest {
+    pub new_program_buffer: String,
+    pub description: String,
+    pub publish_idl: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProposeResponse {
+    pub proposal_id: String,
+    pub timelock_until: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProposalStatusView {
+    pub id: String,
+    pub status: ProposalStatus,
+    pub approvals: usize,
+    pub threshold: usize,
+    pub timelock_until: i64,
+    pub executed_at: Option<i64>,
+    /// Latest execution transaction, once one has been sent
+    #[serde(default)]
+    pub execution: Option<ExecutionConfirmation>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    Submitted,
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed,
+    Dropped,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionConfirmation {
+    pub signature: String,
+    pub status: ConfirmationStatus,
+    /// `processed`, `confirmed` or `finalized`
+    pub target_commitment: String,
+    pub rebroadcasts: u32,
+    pub last_valid_block_height: Option<u64>,
+    pub error: Option<String>,
+    pub submitted_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApprovalResponse {
+    pub proposal_id: String,
+    pub proposal_status: ProposalStatus,
+    pub receipt: SignedReceipt,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionQueued {
+    pub status: String,
+    pub proposal_id: String,
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CancelResponse {
+    pub proposal_id: String,
+    pub reason: CancellationReason,
+    pub details: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApprovalReceipt {
+    pub version: String,
+    pub member: String,
+    pub proposal_id: String,
+    pub proposal_pda: Option<String>,
+    pub buffer: String,
+    pub buffer_hash: String,
+    pub approved_at: i64,
+}
+
+impl ApprovalReceipt {
+    /// Signed bytes: the fields joined with `\n`, as documented for `/upgrade/:id/receipts`
+    pub fn message(&self) -> String {
+        [
+            self.version.as_str(),
+            self.member.as_str(),
+            self.proposal_id.as_str(),
+            self.proposal_pda.as_deref().unwrap_or(""),
+            self.buffer.as_str(),
+            self.buffer_hash.as_str(),
+            &self.approved_at.to_string(),
+        ]
+        .join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignedReceipt {
+    pub receipt: ApprovalReceipt,
+    pub message: String,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl SignedReceipt {
+    /// Checks the ed25519 signature against the receipt fields
+    pub fn verify(&self) -> Result<()> {
+        let invalid = |reason: &str| ClientError::InvalidReceipt(reason.to_string());
+
+        if self.receipt.version != RECEIPT_VERSION {
+            return Err(invalid("unsupported receipt version"));
+        }
+        let message = self.receipt.message();
+        if message != self.message {
+            return Err(invalid("message does not match receipt fields"));
+        }
+
+        let signer: [u8; 32] = bs58::decode(&self.signer)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("malformed signer"))?;
+        let signature: [u8; 64] = bs58::decode(&self.signature)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("malformed signature"))?;
+
+        VerifyingKey::from_bytes(&signer)
+            .map_err(|_| invalid("malformed signer"))?
+            .verify_strict(message.as_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| invalid("signature does not verify"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptBundle {
+    pub proposal_id: String,
+    pub algorithm: String,
+    pub signer: String,
+    pub receipts: Vec<SignedReceipt>,
+}
+
+impl ReceiptBundle {
+    pub fn verify(&self) -> Result<()> {
+        for receipt in &self.receipts {
+            if receipt.receipt.proposal_id != self.proposal_id || receipt.signer != self.signer {
+                return Err(ClientError::InvalidReceipt(
+                    "receipt does not belong to this bundle".to_string(),
+                ));
+            }
+            receipt.verify()?;
+        }
+        Ok(())
+    }
+}
+
+/// A notification from `GET /events` or the WebSocket stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub seq: Option<i64>,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub proposal_id: Option<String>,
+    pub message: String,
+    pub data: serde_json::Value,
+    pub timestamp: i64,
+}
@@ -0,0 +1,204 @@
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ed25519_dalek::{Signer, SigningKey};
+use goquant_upgrade_client::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+fn client(base_url: String) -> UpgradeClient {
+    UpgradeClient::builder(base_url)
+        .auth(Auth::ApiKey("key-1".to_string()))
+        .retry(RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        })
+        .build()
+        .unwrap()
+}
+
+fn signed_receipt(key: &SigningKey, proposal_id: &str) -> SignedReceipt {
+    let receipt = ApprovalReceipt {
+        version: RECEIPT_VERSION.to_string(),
+        member: "member1".to_string(),
+        proposal_id: proposal_id.to_string(),
+        proposal_pda: None,
+        buffer: "Buffer11111111111111111111111111111111".to_string(),
+        buffer_hash: "ab".repeat(32),
+        approved_at: 1_700_000_000,
+    };
+    let message = receipt.message();
+    let signature = key.sign(message.as_bytes());
+
+    SignedReceipt {
+        receipt,
+        message,
+        signer: bs58::encode(key.verifying_key().as_bytes()).into_string(),
+        signature: bs58::encode(signature.to_bytes()).into_string(),
+    }
+}
+
+fn status_body() -> serde_json::Value {
+    serde_json::json!({
+        "id": "p1",
+        "status": "TimelockActive",
+        "approvals": 3,
+        "threshold": 3,
+        "timelock_until": 1_699_123_456,
+        "executed_at": null
+    })
+}
+
+#[tokio::test]
+async fn test_status_sends_api_key_under_v1() {
+    let app = Router::new().route(
+        "/v1/upgrade/:id/status",
+        get(|headers: HeaderMap| async move {
+            assert_eq!(headers["x-api-key"], "key-1");
+            Json(status_body())
+        }),
+    );
+    let client = client(serve(app).await);
+
+    let status = client.status("p1").await.unwrap();
+
+    assert_eq!(status.status, ProposalStatus::TimelockActive);
+    assert_eq!(status.approvals, 3);
+}
+
+#[tokio::test]
+async fn test_reads_retry_on_unavailable() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route(
+            "/v1/upgrade/:id/status",
+            get(|State(calls): State<Arc<AtomicUsize>>| async move {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(StatusCode::SERVICE_UNAVAILABLE)
+                } else {
+                    Ok(Json(status_body()))
+                }
+            }),
+        )
+        .with_state(calls.clone());
+    let client = client(serve(app).await);
+
+    client.status("p1").await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_writes_not_retried_after_reaching_service() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route(
+            "/v1/upgrade/:id/approve",
+            post(|State(calls): State<Arc<AtomicUsize>>| async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({ "error": "busy" })),
+                )
+            }),
+        )
+        .with_state(calls.clone());
+    let client = client(serve(app).await);
+
+    let err = client.approve("p1").await.unwrap_err();
+
+    assert_eq!(err.status(), Some(503));
+    assert!(matches!(err, ClientError::Api { ref message, .. } if message == "busy"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_approve_signed_checks_receipt() {
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    let receipt = signed_receipt(&key, "p1");
+    let signer = receipt.signer.clone();
+    let app = Router::new().route(
+        "/v1/upgrade/:id/approve",
+        post(move || async move {
+            Json(serde_json::json!({
+                "status": "approved",
+                "proposal_id": "p1",
+                "proposal_status": "Approved",
+                "receipt": receipt
+            }))
+        }),
+    );
+    let base_url = serve(app).await;
+
+    let pinned = UpgradeClient::builder(base_url.clone())
+        .receipt_signer(signer)
+        .build()
+        .unwrap();
+    let approval = pinned.approve_signed("p1").await.unwrap();
+    assert_eq!(approval.proposal_status, ProposalStatus::Approved);
+
+    let other_signer = bs58::encode([1u8; 32]).into_string();
+    let mismatched = UpgradeClient::builder(base_url)
+        .receipt_signer(other_signer)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        mismatched.approve_signed("p1").await,
+        Err(ClientError::InvalidReceipt(_))
+    ));
+}
+
+#[test]
+fn test_tampered_receipt_rejected() {
+    let key = SigningKey::from_bytes(&[7u8; 32]);
+    let mut receipt = signed_receipt(&key, "p1");
+    assert!(receipt.verify().is_ok());
+
+    receipt.receipt.buffer_hash = "cd".repeat(32);
+    receipt.message = receipt.receipt.message();
+    assert!(receipt.verify().is_err());
+}
+
+#[tokio::test]
+async fn test_subscribe_events_replays_from_seq() {
+    use axum::extract::ws::{Message, WebSocketUpgrade};
+    use axum::extract::Query;
+    use futures_util::StreamExt;
+
+    let app = Router::new().route(
+        "/v1/ws",
+        get(
+            |ws: WebSocketUpgrade, Query(query): Query<std::collections::HashMap<String, i64>>| async move {
+                let since_seq = query["since_seq"];
+                ws.on_upgrade(move |mut socket| async move {
+                    let event = serde_json::json!({
+                        "seq": since_seq + 1,
+                        "type": "proposal_approved",
+                        "proposal_id": "p1",
+                        "message": "approved",
+                        "data": {},
+                        "timestamp": 1_700_000_000
+                    });
+                    socket.send(Message::Text(event.to_string())).await.unwrap();
+                })
+            },
+        ),
+    );
+    let client = client(serve(app).await);
+
+    let mut events = client.subscribe_events(Some(41)).await.unwrap();
+    let event = events.next().await.unwrap().unwrap();
+
+    assert_eq!(event.seq, Some(42));
+    assert_eq!(event.event_type, "proposal_approved");
+}
@@ -0,0 +1,23 @@
+-- Persisted execution queue so upgrade execution survives HTTP timeouts and restarts
+
+CREATE TABLE IF NOT EXISTS execution_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    job_id VARCHAR(255) UNIQUE NOT NULL,
+    proposal_id VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL CHECK (status IN ('queued', 'running', 'failed', 'done')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+    last_error TEXT,
+    locked_at TIMESTAMP,
+    completed_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- At most one live (or finished) job per proposal: this is what makes execution exactly-once
+CREATE UNIQUE INDEX IF NOT EXISTS idx_execution_jobs_active_proposal
+    ON execution_jobs(proposal_id) WHERE status IN ('queued', 'running', 'done');
+CREATE INDEX IF NOT EXISTS idx_execution_jobs_status ON execution_jobs(status, created_at);
+
+CREATE TRIGGER update_execution_jobs_updated_at BEFORE UPDATE ON execution_jobs
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,13 @@
+-- Per-transaction cost profile for account migrations
+
+CREATE TABLE IF NOT EXISTS migration_transaction_costs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    migration_id VARCHAR(255) NOT NULL REFERENCES migration_progress(migration_id),
+    account_pubkey VARCHAR(44) NOT NULL,
+    signature VARCHAR(88) NOT NULL,
+    compute_units BIGINT NOT NULL,
+    fee_lamports BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_migration_costs_migration ON migration_transaction_costs(migration_id);
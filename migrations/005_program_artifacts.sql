@@ -0,0 +1,14 @@
+-- Registry of every built program binary, so historical upgrades can be re-verified
+
+CREATE TABLE IF NOT EXISTS program_artifacts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    program_hash VARCHAR(64) UNIQUE NOT NULL, -- SHA-256 of the .so
+    program_name VARCHAR(255) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    source_commit VARCHAR(64),
+    toolchain JSONB NOT NULL DEFAULT '{}', -- rustc / solana / anchor versions
+    storage_key TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_program_artifacts_commit ON program_artifacts(source_commit);
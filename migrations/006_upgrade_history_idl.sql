@@ -0,0 +1,3 @@
+-- Hash of the Anchor IDL published alongside an upgrade (NULL when not published)
+
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS idl_hash VARCHAR(64);
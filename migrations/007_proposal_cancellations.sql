@@ -0,0 +1,12 @@
+-- Structured cancellation reasons, mirrored from the on-chain proposal account
+
+CREATE TABLE IF NOT EXISTS proposal_cancellations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    proposal_id VARCHAR(255) UNIQUE NOT NULL,
+    reason VARCHAR(30) NOT NULL CHECK (reason IN ('security_issue', 'build_mismatch', 'superseded', 'proposer_withdrawn', 'other')),
+    details TEXT NOT NULL DEFAULT '',
+    cancelled_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_cancellations_reason ON proposal_cancellations(reason);
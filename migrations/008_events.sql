@@ -0,0 +1,12 @@
+-- Every notification broadcast to WebSocket clients, for replay after reconnects
+
+CREATE TABLE IF NOT EXISTS events (
+    seq BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(50) NOT NULL,
+    proposal_id VARCHAR(255),
+    message TEXT NOT NULL,
+    data JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_created_at ON events(created_at);
@@ -0,0 +1,3 @@
+-- Jobs enqueued with force=true skip the dependency health gate
+
+ALTER TABLE execution_jobs ADD COLUMN IF NOT EXISTS forced BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,13 @@
+-- Results of operator-registered invariant checks, per proposal or migration
+
+CREATE TABLE IF NOT EXISTS invariant_results (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    subject_id VARCHAR(255) NOT NULL, -- proposal_id or migration_id
+    check_name VARCHAR(255) NOT NULL,
+    phase VARCHAR(20) NOT NULL CHECK (phase IN ('pre_execution', 'post_upgrade', 'post_migration')),
+    passed BOOLEAN NOT NULL,
+    detail TEXT NOT NULL DEFAULT '',
+    checked_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_invariant_results_subject ON invariant_results(subject_id, checked_at);
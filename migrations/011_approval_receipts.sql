@@ -0,0 +1,16 @@
+-- Signed approval receipts, bundled by GET /upgrade/:id/receipts
+
+CREATE TABLE IF NOT EXISTS approval_receipts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    proposal_id VARCHAR(255) NOT NULL,
+    member VARCHAR(44) NOT NULL,
+    proposal_pda VARCHAR(44),
+    buffer VARCHAR(44) NOT NULL,
+    buffer_hash VARCHAR(64) NOT NULL,
+    approved_at TIMESTAMP NOT NULL,
+    signer VARCHAR(44) NOT NULL,
+    signature VARCHAR(88) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_receipts_proposal ON approval_receipts(proposal_id, approved_at);
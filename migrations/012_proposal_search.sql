@@ -0,0 +1,12 @@
+-- Full-text search over proposals (GET /upgrade/search)
+
+ALTER TABLE upgrade_proposals ADD COLUMN IF NOT EXISTS version_tag VARCHAR(100);
+
+ALTER TABLE upgrade_proposals ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(description, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(version_tag, '')), 'B') ||
+        setweight(to_tsvector('simple', program || ' ' || proposer), 'C')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_proposals_search ON upgrade_proposals USING GIN (search_vector);
@@ -0,0 +1,17 @@
+-- Snapshots of the managed program's account set, taken around upgrades or on demand
+
+CREATE TABLE IF NOT EXISTS account_snapshots (
+    snapshot_id VARCHAR(255) PRIMARY KEY,
+    program VARCHAR(44) NOT NULL,
+    proposal_id VARCHAR(255), -- set for the automatic pre/post-upgrade snapshots
+    label VARCHAR(20) NOT NULL, -- 'pre_upgrade', 'post_upgrade' or 'manual'
+    account_count BIGINT NOT NULL,
+    total_lamports BIGINT NOT NULL,
+    size_buckets JSONB NOT NULL DEFAULT '{}', -- data length -> {accounts, lamports}
+    taken_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_snapshots_proposal ON account_snapshots(proposal_id, label);
+
+-- Pre/post comparison stored with the upgrade record
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS account_diff JSONB;
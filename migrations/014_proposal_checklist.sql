@@ -0,0 +1,11 @@
+-- Pre-flight checklist sign-offs; execution is refused until every required item is present
+
+CREATE TABLE IF NOT EXISTS proposal_checklist (
+    proposal_id VARCHAR(255) NOT NULL,
+    item VARCHAR(64) NOT NULL,
+    role VARCHAR(64) NOT NULL,
+    completed_by VARCHAR(255),
+    evidence TEXT,
+    completed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, item)
+);
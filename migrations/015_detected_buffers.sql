@@ -0,0 +1,14 @@
+-- Buffers owned by the multisig vault found on-chain by the buffer watcher
+
+CREATE TABLE IF NOT EXISTS detected_buffers (
+    buffer VARCHAR(44) PRIMARY KEY,
+    authority VARCHAR(44) NOT NULL,
+    program_len BIGINT NOT NULL,
+    program_hash VARCHAR(64) NOT NULL,
+    lamports BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    proposal_id VARCHAR(255),
+    detected_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_detected_buffers_status ON detected_buffers(status, detected_at);
@@ -0,0 +1,15 @@
+-- Commitment progress of execution transactions, one row per signature
+
+CREATE TABLE IF NOT EXISTS execution_confirmations (
+    signature VARCHAR(88) PRIMARY KEY,
+    proposal_id VARCHAR(255) NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    target_commitment VARCHAR(16) NOT NULL,
+    rebroadcasts INTEGER NOT NULL DEFAULT 0,
+    last_valid_block_height BIGINT,
+    error TEXT,
+    submitted_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_confirmations_proposal ON execution_confirmations(proposal_id, submitted_at DESC);
@@ -0,0 +1,12 @@
+-- Proposals that are still being edited and reviewed before going on chain
+
+CREATE TABLE IF NOT EXISTS proposal_drafts (
+    draft_id VARCHAR(255) PRIMARY KEY,
+    author VARCHAR(255) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'draft',
+    draft JSONB NOT NULL, -- ProposalDraft, including its review history
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_drafts_status ON proposal_drafts(status, updated_at);
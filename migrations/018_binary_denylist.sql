@@ -0,0 +1,11 @@
+-- Program binaries and byte patterns that must never be proposed or executed
+
+CREATE TABLE IF NOT EXISTS binary_denylist (
+    id VARCHAR(36) PRIMARY KEY,
+    kind VARCHAR(16) NOT NULL,
+    value TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    added_by VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (kind, value)
+);
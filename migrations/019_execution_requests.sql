@@ -0,0 +1,9 @@
+-- Executions initiated by one executor and awaiting confirmation by another
+
+CREATE TABLE IF NOT EXISTS execution_requests (
+    proposal_id VARCHAR(255) PRIMARY KEY,
+    initiated_by VARCHAR(255) NOT NULL,
+    initiated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL,
+    forced BOOLEAN NOT NULL DEFAULT FALSE
+);
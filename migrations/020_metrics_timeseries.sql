@@ -0,0 +1,18 @@
+-- Periodic snapshots of service metrics, for graphing trends over months
+
+CREATE TABLE IF NOT EXISTS metrics_timeseries (
+    metric VARCHAR(64) NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    recorded_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_metrics_timeseries_metric ON metrics_timeseries(metric, recorded_at);
+
+-- On TimescaleDB the table becomes a hypertable; plain Postgres keeps the table as is
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN
+        PERFORM create_hypertable('metrics_timeseries', 'recorded_at', if_not_exists => TRUE, migrate_data => TRUE);
+    END IF;
+END
+$$;
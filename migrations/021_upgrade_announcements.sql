@@ -0,0 +1,10 @@
+-- Published notices of scheduled upgrades, one per proposal
+
+CREATE TABLE IF NOT EXISTS upgrade_announcements (
+    proposal_id VARCHAR(255) PRIMARY KEY,
+    announcement JSONB NOT NULL, -- Announcement as served by /public/announcements
+    window_end TIMESTAMP NOT NULL,
+    published_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_upgrade_announcements_window ON upgrade_announcements(window_end);
@@ -0,0 +1,19 @@
+-- Per-user API keys. Only a SHA-256 digest of each key is stored.
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    prefix VARCHAR(32) NOT NULL, -- Leading characters of the key, for telling keys apart
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL, -- read, propose, approve, execute, admin
+    created_by VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    rotated_at TIMESTAMP,
+    -- The key replaced by the last rotation, accepted until previous_expires_at
+    previous_key_hash VARCHAR(64),
+    previous_expires_at TIMESTAMP,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_previous_hash ON api_keys(previous_key_hash);
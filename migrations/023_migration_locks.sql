@@ -0,0 +1,7 @@
+-- The batch migration currently allowed to run for each program
+
+CREATE TABLE IF NOT EXISTS migration_locks (
+    program VARCHAR(255) PRIMARY KEY,
+    migration_id VARCHAR(255) NOT NULL,
+    acquired_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
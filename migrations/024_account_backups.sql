@@ -0,0 +1,11 @@
+-- Account data as it was before a batch migration rewrote it, used to verify
+-- a sample of migrated accounts against the chain afterwards
+
+CREATE TABLE IF NOT EXISTS account_backups (
+    migration_id VARCHAR(255) NOT NULL,
+    account_pubkey VARCHAR(44) NOT NULL,
+    account_type VARCHAR(255) NOT NULL,
+    data BYTEA NOT NULL,
+    backed_up_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (migration_id, account_pubkey)
+);
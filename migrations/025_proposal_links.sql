@@ -0,0 +1,12 @@
+-- Ordering and replacement links between proposals: `depends_on` proposals
+-- must execute first, `supersedes` proposals were cancelled in favour of this one
+
+CREATE TABLE IF NOT EXISTS proposal_links (
+    proposal_id VARCHAR(255) NOT NULL,
+    linked_proposal_id VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('depends_on', 'supersedes')),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, linked_proposal_id, kind)
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_links_linked ON proposal_links(linked_proposal_id);
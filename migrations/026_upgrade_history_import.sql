@@ -0,0 +1,13 @@
+-- Upgrades imported from chain history rather than executed by this service.
+-- They have no proposal, so proposal_id becomes optional; the transaction
+-- signature keeps repeated imports from duplicating rows.
+
+ALTER TABLE upgrade_history ALTER COLUMN proposal_id DROP NOT NULL;
+
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS signature VARCHAR(88);
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS slot BIGINT;
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS buffer VARCHAR(44);
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS authority VARCHAR(44);
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS imported BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_upgrade_history_signature ON upgrade_history(signature);
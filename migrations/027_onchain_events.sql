@@ -0,0 +1,27 @@
+-- Anchor events emitted by the upgrade-manager program, projected from its
+-- transaction logs so backend records can be checked against chain history
+
+CREATE TABLE IF NOT EXISTS onchain_events (
+    id BIGSERIAL PRIMARY KEY,
+    signature VARCHAR(128) NOT NULL,
+    slot BIGINT NOT NULL,
+    event_index INTEGER NOT NULL,
+    event_name VARCHAR(64) NOT NULL,
+    -- On-chain proposal account, for proposal events
+    proposal VARCHAR(64),
+    data JSONB NOT NULL,
+    block_time TIMESTAMP,
+    indexed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (signature, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_onchain_events_proposal ON onchain_events(proposal, id);
+CREATE INDEX IF NOT EXISTS idx_onchain_events_name ON onchain_events(event_name, id);
+
+-- Newest transaction the projection has processed, per program
+CREATE TABLE IF NOT EXISTS onchain_event_cursors (
+    program VARCHAR(64) PRIMARY KEY,
+    last_signature VARCHAR(128) NOT NULL,
+    last_slot BIGINT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,15 @@
+-- Reports of rollback drills, so the team can see how recently (and how
+-- quickly) a rollback was rehearsed
+
+CREATE TABLE IF NOT EXISTS rollback_drills (
+    id VARCHAR(36) PRIMARY KEY,
+    program VARCHAR(64) NOT NULL,
+    -- mocked or devnet_clone
+    target VARCHAR(16) NOT NULL,
+    passed BOOLEAN NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    report JSONB NOT NULL,
+    started_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rollback_drills_started ON rollback_drills(started_at DESC);
@@ -0,0 +1,12 @@
+-- Fork test reports: a proposal's binary replayed against recent mainnet
+-- transactions on a forked validator before execution
+
+CREATE TABLE IF NOT EXISTS fork_tests (
+    id VARCHAR(36) PRIMARY KEY,
+    proposal_id VARCHAR(255) NOT NULL,
+    passed BOOLEAN NOT NULL,
+    report JSONB NOT NULL,
+    ran_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_fork_tests_proposal ON fork_tests(proposal_id, ran_at DESC);
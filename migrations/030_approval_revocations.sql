@@ -0,0 +1,11 @@
+-- Approvals members withdrew before their proposal reached its threshold
+
+CREATE TABLE IF NOT EXISTS approval_revocations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    proposal_id VARCHAR(255) NOT NULL,
+    approver VARCHAR(44) NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    revoked_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_revocations_proposal ON approval_revocations(proposal_id, revoked_at);
@@ -0,0 +1,22 @@
+-- Critical audit findings holding a proposal in the frozen status until an
+-- admin clears them
+
+ALTER TABLE upgrade_proposals DROP CONSTRAINT IF EXISTS upgrade_proposals_status_check;
+ALTER TABLE upgrade_proposals ADD CONSTRAINT upgrade_proposals_status_check
+    CHECK (status IN ('proposed', 'approved', 'timelock_active', 'executed', 'cancelled', 'frozen'));
+
+CREATE TABLE IF NOT EXISTS proposal_freezes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    proposal_id VARCHAR(255) NOT NULL,
+    finding TEXT NOT NULL,
+    source VARCHAR(20) NOT NULL CHECK (source IN ('auditor', 'admin')),
+    frozen_by VARCHAR(255) NOT NULL,
+    -- Status restored when the finding is cleared
+    frozen_from VARCHAR(20) NOT NULL,
+    frozen_at TIMESTAMP NOT NULL,
+    cleared_by VARCHAR(255),
+    resolution TEXT,
+    cleared_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_proposal_freezes_proposal ON proposal_freezes(proposal_id, frozen_at DESC);
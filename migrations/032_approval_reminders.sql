@@ -0,0 +1,19 @@
+-- Approval reminders sent to members, one row per proposal, member and
+-- scheduled reminder time, and the proposals members muted reminders for
+
+CREATE TABLE IF NOT EXISTS approval_reminders (
+    proposal_id VARCHAR(255) NOT NULL,
+    member VARCHAR(44) NOT NULL,
+    -- Scheduled reminder time; claiming it is what stops a second send
+    due_at TIMESTAMP NOT NULL,
+    channel VARCHAR(20) NOT NULL CHECK (channel IN ('websocket', 'email', 'slack')),
+    sent_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, member, due_at)
+);
+
+CREATE TABLE IF NOT EXISTS approval_reminder_opt_outs (
+    proposal_id VARCHAR(255) NOT NULL,
+    member VARCHAR(44) NOT NULL,
+    opted_out_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, member)
+);
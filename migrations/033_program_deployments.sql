@@ -0,0 +1,12 @@
+-- First-time program deployments governed like upgrades. The proposal
+-- records the program data room it reserves; the history row of an executed
+-- deployment is version 1 and later upgrades of that program count on.
+
+ALTER TABLE upgrade_proposals ADD COLUMN IF NOT EXISTS kind VARCHAR(10) NOT NULL DEFAULT 'upgrade'
+    CHECK (kind IN ('upgrade', 'deploy'));
+ALTER TABLE upgrade_proposals ADD COLUMN IF NOT EXISTS max_data_len BIGINT;
+
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS deployment BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS version INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_upgrade_history_program_version ON upgrade_history(program, version);
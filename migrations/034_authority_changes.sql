@@ -0,0 +1,11 @@
+-- Proposals that hand a program's upgrade authority to a new key, or remove
+-- it for good. History rows now say what kind of proposal produced them.
+
+ALTER TABLE upgrade_proposals DROP CONSTRAINT IF EXISTS upgrade_proposals_kind_check;
+ALTER TABLE upgrade_proposals ADD CONSTRAINT upgrade_proposals_kind_check
+    CHECK (kind IN ('upgrade', 'deploy', 'set_authority'));
+-- NULL on a set_authority proposal means the program becomes immutable
+ALTER TABLE upgrade_proposals ADD COLUMN IF NOT EXISTS new_authority VARCHAR(44);
+
+ALTER TABLE upgrade_history ADD COLUMN IF NOT EXISTS kind VARCHAR(20) NOT NULL DEFAULT 'upgrade';
+UPDATE upgrade_history SET kind = 'deploy' WHERE deployment;
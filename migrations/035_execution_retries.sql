@@ -0,0 +1,19 @@
+-- Retries of failed execution jobs back off exponentially; every attempt is
+-- kept, including ones that succeeded or were held back
+
+ALTER TABLE execution_jobs ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+DROP INDEX IF EXISTS idx_execution_jobs_status;
+CREATE INDEX IF NOT EXISTS idx_execution_jobs_status ON execution_jobs(status, next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS execution_job_attempts (
+    job_id VARCHAR(255) NOT NULL REFERENCES execution_jobs(job_id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    outcome VARCHAR(20) NOT NULL CHECK (outcome IN ('succeeded', 'held', 'retrying', 'failed')),
+    error TEXT,
+    duration_ms BIGINT NOT NULL,
+    -- Set when the job was requeued; when the next attempt may start
+    retry_at TIMESTAMP,
+    finished_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, attempt, finished_at)
+);
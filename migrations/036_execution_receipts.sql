@@ -0,0 +1,8 @@
+-- Where an execution landed and what it cost, read back once it reached the
+-- target commitment
+
+ALTER TABLE execution_confirmations ADD COLUMN IF NOT EXISTS slot BIGINT;
+ALTER TABLE execution_confirmations ADD COLUMN IF NOT EXISTS block_time TIMESTAMP;
+ALTER TABLE execution_confirmations ADD COLUMN IF NOT EXISTS fee_lamports BIGINT;
+ALTER TABLE execution_confirmations ADD COLUMN IF NOT EXISTS compute_units BIGINT;
+ALTER TABLE execution_confirmations ADD COLUMN IF NOT EXISTS cluster VARCHAR(16);
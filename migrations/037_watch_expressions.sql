@@ -0,0 +1,26 @@
+-- Log substrings and event discriminators of the managed program watched for
+-- a window after every upgrade, and what each watch concluded
+
+CREATE TABLE IF NOT EXISTS watch_expressions (
+    id VARCHAR(36) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    matcher JSONB NOT NULL,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('anomaly', 'heartbeat')),
+    min_matches INTEGER NOT NULL DEFAULT 0,
+    window_seconds BIGINT NOT NULL,
+    rollback BOOLEAN NOT NULL DEFAULT FALSE,
+    created_by VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS watch_findings (
+    proposal_id VARCHAR(255) NOT NULL REFERENCES upgrade_proposals(proposal_id),
+    expression_id VARCHAR(36) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    matches INTEGER NOT NULL,
+    anomaly BOOLEAN NOT NULL,
+    detail TEXT NOT NULL,
+    rolled_back BOOLEAN NOT NULL DEFAULT FALSE,
+    evaluated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (proposal_id, expression_id)
+);
@@ -0,0 +1,12 @@
+-- Display metadata and reminder settings for multisig members, keyed by pubkey
+
+CREATE TABLE IF NOT EXISTS member_profiles (
+    member VARCHAR(44) PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    organization VARCHAR(64),
+    contact VARCHAR(255),
+    notification_channel TEXT,
+    reminders_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by VARCHAR(255),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,16 @@
+-- Incidents opened by operators or automatically when a rollback starts or
+-- failure detection fires, with their status updates and links to
+-- proposals, alerts and rollbacks
+
+CREATE TABLE IF NOT EXISTS incidents (
+    id VARCHAR(36) PRIMARY KEY,
+    status VARCHAR(16) NOT NULL CHECK (status IN ('investigating', 'identified', 'monitoring', 'resolved')),
+    program VARCHAR(44),
+    incident JSONB NOT NULL, -- Incident, including its updates and links
+    opened_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_incidents_active ON incidents(program) WHERE status <> 'resolved';
+CREATE INDEX IF NOT EXISTS idx_incidents_opened_at ON incidents(opened_at DESC);
@@ -1,9 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{
-    program::invoke_signed,
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke_signed, set_return_data},
     system_instruction,
     sysvar::rent::Rent,
 };
+use anchor_lang::system_program;
+
+pub mod transforms;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -18,11 +23,29 @@ pub mod upgrade_manager {
         threshold: u8,
         timelock_duration: i64,
     ) -> Result<()> {
+        require!(!members.is_empty(), UpgradeError::NoMembers);
+        require!(members.len() <= MAX_MEMBERS, UpgradeError::TooManyMembers);
+        for (i, member) in members.iter().enumerate() {
+            require!(!members[..i].contains(member), UpgradeError::DuplicateMember);
+        }
+        require!(threshold >= MIN_THRESHOLD, UpgradeError::ThresholdTooLow);
+        require!(
+            threshold as usize <= members.len(),
+            UpgradeError::ThresholdExceedsMembers
+        );
+        require!(
+            valid_timelock_duration(timelock_duration),
+            UpgradeError::InvalidTimelockDuration
+        );
+
         let config = &mut ctx.accounts.multisig_config;
         config.members = members;
         config.threshold = threshold;
         config.upgrade_authority = ctx.accounts.authority.key();
+        config.execution_bot = None;
         config.bump = ctx.bumps.multisig_config;
+        config.member_organizations = Vec::new();
+        config.organization_quorum = 0;
 
         let state = &mut ctx.accounts.program_upgrade_state;
         state.authority = ctx.accounts.authority.key();
@@ -47,7 +70,33 @@ pub mod upgrade_manager {
         ctx: Context<ProposeUpgrade>,
         new_program_buffer: Pubkey,
         description: String,
+        code_hash: [u8; 32],
+        description_hash: Option<[u8; 32]>,
     ) -> Result<()> {
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            UpgradeError::DescriptionTooLong
+        );
+
+        let meta = read_program_meta(&ctx.accounts.program_meta)?;
+        require!(
+            !meta.as_ref().is_some_and(|meta| meta.immutable),
+            UpgradeError::ProgramImmutable
+        );
+
+        // The version this upgrade will produce, so approvals cannot be replayed
+        // against a program that has moved on
+        let target_version = meta.map_or(1, |meta| meta.version.saturating_add(1));
+        let timelock_duration = ctx.accounts.program_upgrade_state.timelock_duration;
+        let approval_digest = approval_digest(
+            &ctx.accounts.program.key(),
+            &new_program_buffer,
+            &code_hash,
+            target_version,
+            timelock_duration,
+        );
+
+        let proposal_key = ctx.accounts.proposal.key();
         let proposal = &mut ctx.accounts.proposal;
         let config = &ctx.accounts.multisig_config;
         let clock = Clock::get()?;
@@ -59,12 +108,13 @@ pub mod upgrade_manager {
         );
 
         // Initialize proposal
-        proposal.id = ctx.accounts.proposal.key().to_bytes()[..8]
+        proposal.id = proposal_key.to_bytes()[..8]
             .try_into()
             .map_err(|_| UpgradeError::InvalidProposalId)?;
         proposal.proposer = ctx.accounts.proposer.key();
         proposal.program = ctx.accounts.program.key();
         proposal.new_buffer = new_program_buffer;
+        proposal.code_hash = code_hash;
         proposal.description = description;
         proposal.proposed_at = clock.unix_timestamp;
         proposal.timelock_until = clock.unix_timestamp + ctx.accounts.program_upgrade_state.timelock_duration;
@@ -72,26 +122,40 @@ pub mod upgrade_manager {
         proposal.approval_threshold = config.threshold;
         proposal.status = UpgradeStatus::Proposed;
         proposal.executed_at = None;
+        proposal.cancel_votes = Vec::new();
+        proposal.cancellation_reason = None;
+        proposal.cancellation_details = String::new();
         proposal.bump = ctx.bumps.proposal;
+        proposal.target_version = target_version;
+        proposal.approval_digest = approval_digest;
+        proposal.organization_quorum = config.organization_quorum;
+        proposal.action = ProposalAction::Upgrade;
+        proposal.description_hash = description_hash;
 
         msg!("Upgrade proposed: buffer={}, timelock_until={}", 
              new_program_buffer, proposal.timelock_until);
 
         emit!(ProposalCreatedEvent {
-            proposal_id: ctx.accounts.proposal.key(),
+            proposal_id: proposal_key,
             proposer: ctx.accounts.proposer.key(),
             new_buffer: new_program_buffer,
             timelock_until: proposal.timelock_until,
+            target_version,
+            approval_digest,
         });
 
         Ok(())
     }
 
-    /// Approve an upgrade proposal
+    /// Approve an upgrade proposal. `approval_digest` must match the digest
+    /// committed in the proposal, so the approver's signature covers exactly
+    /// the program, code, version and timelock that will execute.
     pub fn approve_upgrade(
         ctx: Context<ApproveUpgrade>,
         _proposal_id: Pubkey,
+        approval_digest: [u8; 32],
     ) -> Result<()> {
+        let proposal_key = ctx.accounts.proposal.key();
         let proposal = &mut ctx.accounts.proposal;
         let config = &ctx.accounts.multisig_config;
         let clock = Clock::get()?;
@@ -115,11 +179,19 @@ pub mod upgrade_manager {
             UpgradeError::AlreadyApproved
         );
 
+        require!(
+            approval_digest == proposal.approval_digest,
+            UpgradeError::ApprovalDigestMismatch
+        );
+
         // Add approval
         proposal.approvals.push(ctx.accounts.approver.key());
+        let organizations = config.organization_count(&proposal.approvals);
 
-        // Check if threshold met
-        if proposal.approvals.len() >= proposal.approval_threshold as usize {
+        // Check if threshold met, by enough distinct organizations
+        if proposal.approvals.len() >= proposal.approval_threshold as usize
+            && organizations >= proposal.organization_quorum as usize
+        {
             proposal.status = UpgradeStatus::TimelockActive;
             proposal.timelock_until = clock.unix_timestamp + 
                 ctx.accounts.program_upgrade_state.timelock_duration;
@@ -128,15 +200,59 @@ pub mod upgrade_manager {
                  proposal.timelock_until);
         } else {
             proposal.status = UpgradeStatus::Approved;
-            msg!("Approval added. {}/{} approvals", 
-                 proposal.approvals.len(), proposal.approval_threshold);
+            msg!("Approval added. {}/{} approvals, {}/{} organizations", 
+                 proposal.approvals.len(), proposal.approval_threshold,
+                 organizations, proposal.organization_quorum);
         }
 
         emit!(ProposalApprovedEvent {
-            proposal_id: ctx.accounts.proposal.key(),
+            proposal_id: proposal_key,
             approver: ctx.accounts.approver.key(),
             approvals: proposal.approvals.len(),
             threshold: proposal.approval_threshold,
+            organizations: organizations as u8,
+            organization_quorum: proposal.organization_quorum,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw an approval, e.g. after new audit findings. Only possible
+    /// while the proposal is below threshold; once the timelock runs the
+    /// approval set is final and `cancel_upgrade` is the way out.
+    pub fn revoke_approval(
+        ctx: Context<RevokeApproval>,
+        _proposal_id: Pubkey,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let approver = ctx.accounts.approver.key();
+
+        require!(
+            proposal.status == UpgradeStatus::Proposed ||
+            proposal.status == UpgradeStatus::Approved,
+            UpgradeError::InvalidProposalStatus
+        );
+
+        let index = proposal
+            .approvals
+            .iter()
+            .position(|key| *key == approver)
+            .ok_or(UpgradeError::NotApproved)?;
+        proposal.approvals.remove(index);
+
+        // Back to the proposer's approval alone, or none at all
+        if proposal.approvals.len() <= 1 {
+            proposal.status = UpgradeStatus::Proposed;
+        }
+
+        msg!("Approval revoked. {}/{} approvals",
+             proposal.approvals.len(), proposal.approval_threshold);
+
+        emit!(ApprovalRevokedEvent {
+            proposal_id: proposal.key(),
+            approver,
+            approvals: proposal.approvals.len(),
+            threshold: proposal.approval_threshold,
         });
 
         Ok(())
@@ -147,6 +263,15 @@ pub mod upgrade_manager {
         ctx: Context<ExecuteUpgrade>,
         _proposal_id: Pubkey,
     ) -> Result<()> {
+        let proposal_key = ctx.accounts.proposal.key();
+        let executor = ctx.accounts.executor.key();
+
+        // Only members, the upgrade authority or the execution bot may execute
+        require!(
+            ctx.accounts.multisig_config.can_execute(&executor),
+            UpgradeError::UnauthorizedExecutor
+        );
+
         let proposal = &mut ctx.accounts.proposal;
         let clock = Clock::get()?;
 
@@ -161,12 +286,54 @@ pub mod upgrade_manager {
             proposal.approvals.len() >= proposal.approval_threshold as usize,
             UpgradeError::InsufficientApprovals
         );
+        require!(
+            ctx.accounts.multisig_config.organization_count(&proposal.approvals)
+                >= proposal.organization_quorum as usize,
+            UpgradeError::InsufficientOrganizations
+        );
 
         // Verify proposal is in correct status
         require!(
             proposal.status == UpgradeStatus::TimelockActive,
             UpgradeError::InvalidProposalStatus
         );
+        require!(
+            proposal.action == ProposalAction::Upgrade,
+            UpgradeError::WrongProposalAction
+        );
+        require!(
+            !ctx.accounts.program_meta.immutable,
+            UpgradeError::ProgramImmutable
+        );
+
+        // What was approved must still be what executes
+        let current_version = ctx.accounts.program_meta.version;
+        let expected_digest = approval_digest(
+            &proposal.program,
+            &proposal.new_buffer,
+            &proposal.code_hash,
+            current_version.saturating_add(1),
+            ctx.accounts.program_upgrade_state.timelock_duration,
+        );
+        require!(
+            proposal.target_version == current_version.saturating_add(1)
+                && expected_digest == proposal.approval_digest,
+            UpgradeError::StaleApprovalDigest
+        );
+
+        // The buffer must already belong to the program's upgrade authority;
+        // while the deployer still holds it they could rewrite it after approval
+        let upgrade_authority = ctx
+            .accounts
+            .program_meta
+            .upgrade_authority
+            .unwrap_or(ctx.accounts.multisig_config.upgrade_authority);
+        let buffer = &ctx.accounts.buffer;
+        require!(
+            *buffer.owner == bpf_loader_upgradeable::ID
+                && buffer_authority(&buffer.try_borrow_data()?) == Some(upgrade_authority),
+            UpgradeError::BufferAuthorityNotTransferred
+        );
 
         // Verify proposal can be executed
         // The actual BPF upgrade will be executed by the multisig via Squads Protocol
@@ -189,191 +356,1572 @@ pub mod upgrade_manager {
         proposal.status = UpgradeStatus::Executed;
         proposal.executed_at = Some(clock.unix_timestamp);
 
+        // Record the new code in the program's metadata account
+        let meta = &mut ctx.accounts.program_meta;
+        if meta.program == Pubkey::default() {
+            meta.program = proposal.program;
+            meta.bump = ctx.bumps.program_meta;
+        }
+        meta.version = meta.version.saturating_add(1);
+        meta.last_upgraded_at = clock.unix_timestamp;
+        meta.last_proposal = proposal_key;
+        meta.code_hash = proposal.code_hash;
+
         msg!("Upgrade executed successfully!");
 
         emit!(UpgradeExecutedEvent {
-            proposal_id: ctx.accounts.proposal.key(),
+            proposal_id: proposal_key,
             program: proposal.program,
+            executor,
             executed_at: proposal.executed_at.unwrap(),
+            version: meta.version,
+            code_hash: meta.code_hash,
         });
 
         Ok(())
     }
 
-    /// Cancel an upgrade proposal (emergency only)
-    pub fn cancel_upgrade(
-        ctx: Context<CancelUpgrade>,
-        _proposal_id: Pubkey,
+    /// Propose handing the program's upgrade authority to `new_authority`,
+    /// e.g. a new Squads vault, or making the program immutable with `None`.
+    /// Immutability can never be undone, so it must be confirmed with
+    /// `confirm_immutable`. The change goes through the same approvals and
+    /// timelock as an upgrade. `nonce` is chosen by the proposer and only
+    /// keeps the proposal address free, so the same target can be proposed
+    /// again after a cancellation or a hand-back.
+    pub fn propose_authority_change(
+        ctx: Context<ProposeAuthorityChange>,
+        new_authority: Option<Pubkey>,
+        nonce: u64,
+        description: String,
+        confirm_immutable: bool,
     ) -> Result<()> {
-        let proposal = &mut ctx.accounts.proposal;
-        let config = &ctx.accounts.multisig_config;
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            UpgradeError::DescriptionTooLong
+        );
 
-        // Verify canceller is a multisig member
+        let config = &ctx.accounts.multisig_config;
         require!(
-            config.members.contains(&ctx.accounts.canceller.key()),
+            config.members.contains(&ctx.accounts.proposer.key()),
             UpgradeError::NotMultisigMember
         );
-
-        // Can only cancel before execution
         require!(
-            proposal.status != UpgradeStatus::Executed,
-            UpgradeError::CannotCancelExecuted
+            new_authority.is_some() || confirm_immutable,
+            UpgradeError::ImmutabilityNotConfirmed
+        );
+        let meta = read_program_meta(&ctx.accounts.program_meta)?;
+        require!(
+            !meta.as_ref().is_some_and(|meta| meta.immutable),
+            UpgradeError::ProgramImmutable
+        );
+        let current_authority = meta
+            .and_then(|meta| meta.upgrade_authority)
+            .unwrap_or(config.upgrade_authority);
+        require!(
+            new_authority != Some(current_authority),
+            UpgradeError::AuthorityUnchanged
         );
 
-        proposal.status = UpgradeStatus::Cancelled;
+        let program = ctx.accounts.program.key();
+        let proposer = ctx.accounts.proposer.key();
+        let proposal_key = ctx.accounts.proposal.key();
+        let timelock_duration = ctx.accounts.program_upgrade_state.timelock_duration;
+        let approval_digest = authority_change_digest(&program, new_authority.as_ref(), timelock_duration);
+        let clock = Clock::get()?;
 
-        msg!("Proposal cancelled");
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = proposal_key.to_bytes()[..8]
+            .try_into()
+            .map_err(|_| UpgradeError::InvalidProposalId)?;
+        proposal.proposer = proposer;
+        proposal.program = program;
+        proposal.new_buffer = authority_target(new_authority.as_ref(), nonce);
+        proposal.code_hash = [0; 32];
+        proposal.description = description;
+        proposal.proposed_at = clock.unix_timestamp;
+        proposal.timelock_until = clock.unix_timestamp + timelock_duration;
+        proposal.approvals = vec![proposer];
+        proposal.approval_threshold = config.threshold;
+        proposal.status = UpgradeStatus::Proposed;
+        proposal.executed_at = None;
+        proposal.cancel_votes = Vec::new();
+        proposal.cancellation_reason = None;
+        proposal.cancellation_details = String::new();
+        proposal.bump = ctx.bumps.proposal;
+        proposal.target_version = 0;
+        proposal.approval_digest = approval_digest;
+        proposal.organization_quorum = config.organization_quorum;
+        proposal.action = ProposalAction::SetAuthority { new_authority };
+        proposal.description_hash = None;
+
+        match new_authority {
+            Some(authority) => msg!("Authority change proposed: {} -> {}", program, authority),
+            None => msg!("IRREVERSIBLE: proposed making {} immutable", program),
+        }
 
-        emit!(ProposalCancelledEvent {
-            proposal_id: ctx.accounts.proposal.key(),
-            canceller: ctx.accounts.canceller.key(),
+        emit!(AuthorityChangeProposedEvent {
+            proposal_id: proposal_key,
+            proposer,
+            program,
+            new_authority,
+            timelock_until: proposal.timelock_until,
+            approval_digest,
         });
 
         Ok(())
     }
 
-    /// Migrate account state from old to new program version
-    pub fn migrate_account(
-        ctx: Context<MigrateAccount>,
-        old_account: Pubkey,
+    /// Execute an approved authority change after the timelock. As with
+    /// upgrades, the loader `SetAuthority` itself is sent by the multisig;
+    /// this authorizes it and records the change in the program's metadata.
+    /// A new authority applies to this program only; the config's
+    /// `upgrade_authority` stays in force for the other managed programs.
+    /// Immutability means no further upgrade can be proposed for it.
+    pub fn execute_authority_change(
+        ctx: Context<ExecuteAuthorityChange>,
+        _proposal_id: Pubkey,
     ) -> Result<()> {
-        let migration = &mut ctx.accounts.account_version;
+        let proposal_key = ctx.accounts.proposal.key();
+        let executor = ctx.accounts.executor.key();
+        let config = &ctx.accounts.multisig_config;
+
+        require!(config.can_execute(&executor), UpgradeError::UnauthorizedExecutor);
+        require!(!ctx.accounts.program_meta.immutable, UpgradeError::ProgramImmutable);
+
+        let proposal = &mut ctx.accounts.proposal;
         let clock = Clock::get()?;
 
-        // Check if already migrated
         require!(
-            !migration.migrated,
-            UpgradeError::AlreadyMigrated
+            clock.unix_timestamp >= proposal.timelock_until,
+            UpgradeError::TimelockActive
+        );
+        require!(
+            proposal.approvals.len() >= proposal.approval_threshold as usize,
+            UpgradeError::InsufficientApprovals
+        );
+        require!(
+            config.organization_count(&proposal.approvals) >= proposal.organization_quorum as usize,
+            UpgradeError::InsufficientOrganizations
+        );
+        require!(
+            proposal.status == UpgradeStatus::TimelockActive,
+            UpgradeError::InvalidProposalStatus
+        );
+        let ProposalAction::SetAuthority { new_authority } = proposal.action else {
+            return err!(UpgradeError::WrongProposalAction);
+        };
+
+        // The timelock approvers agreed to must still be in force
+        let expected_digest = authority_change_digest(
+            &proposal.program,
+            new_authority.as_ref(),
+            ctx.accounts.program_upgrade_state.timelock_duration,
+        );
+        require!(
+            expected_digest == proposal.approval_digest,
+            UpgradeError::StaleApprovalDigest
         );
 
-        // Perform migration logic here
-        // This is a placeholder - actual migration depends on account structure
-        migration.version += 1;
-        migration.migrated = true;
-        migration.migrated_at = Some(clock.unix_timestamp);
+        proposal.status = UpgradeStatus::Executed;
+        proposal.executed_at = Some(clock.unix_timestamp);
+
+        let meta = &mut ctx.accounts.program_meta;
+        if meta.program == Pubkey::default() {
+            meta.program = proposal.program;
+            meta.bump = ctx.bumps.program_meta;
+        }
 
-        msg!("Account migrated: version={}", migration.version);
+        let previous_authority = meta.upgrade_authority.unwrap_or(config.upgrade_authority);
+        match new_authority {
+            Some(authority) => {
+                meta.upgrade_authority = Some(authority);
+                msg!("Upgrade authority of {} handed to {}", proposal.program, authority);
+            }
+            None => {
+                meta.immutable = true;
+                msg!("Program {} is now immutable", proposal.program);
+            }
+        }
 
-        emit!(AccountMigratedEvent {
-            account: old_account,
-            new_version: migration.version,
-            migrated_at: migration.migrated_at.unwrap(),
+        emit!(AuthorityChangedEvent {
+            proposal_id: proposal_key,
+            program: proposal.program,
+            previous_authority,
+            new_authority,
+            executor,
+            executed_at: clock.unix_timestamp,
         });
 
         Ok(())
     }
-}
-
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + MultisigConfig::LEN,
-        seeds = [b"multisig_config"],
-        bump
-    )]
-    pub multisig_config: Account<'info, MultisigConfig>,
+    /// Propose setting feature flag `name` of the program, so risky behaviour
+    /// can ship dark and be switched on without an upgrade. `revision` is the
+    /// flag's current revision (0 for a new flag); approvals commit to it, so
+    /// a proposal made before another change to the flag cannot execute.
+    /// `kill_switch` marks the flag as one any member may turn off instantly.
+    pub fn propose_flag_change(
+        ctx: Context<ProposeFlagChange>,
+        name: [u8; 32],
+        enabled: bool,
+        kill_switch: bool,
+        revision: u32,
+        description: String,
+    ) -> Result<()> {
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            UpgradeError::DescriptionTooLong
+        );
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + ProgramUpgradeState::LEN,
-        seeds = [b"program_upgrade_state"],
-        bump
-    )]
-    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+        let config = &ctx.accounts.multisig_config;
+        require!(
+            config.members.contains(&ctx.accounts.proposer.key()),
+            UpgradeError::NotMultisigMember
+        );
 
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        let current_revision = {
+            let flags_info = ctx.accounts.feature_flags.to_account_info();
+            if flags_info.owner == &crate::ID && !flags_info.data_is_empty() {
+                let data = flags_info.try_borrow_data()?;
+                FeatureFlags::try_deserialize(&mut &data[..])?.revision(&name)
+            } else {
+                0
+            }
+        };
+        require!(revision == current_revision, UpgradeError::StaleFlagRevision);
+
+        let program = ctx.accounts.program.key();
+        let proposer = ctx.accounts.proposer.key();
+        let proposal_key = ctx.accounts.proposal.key();
+        let timelock_duration = ctx.accounts.program_upgrade_state.timelock_duration;
+        let approval_digest = flag_change_digest(&program, &name, enabled, kill_switch, revision, timelock_duration);
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-pub struct ProposeUpgrade<'info> {
-    #[account(mut)]
-    pub proposer: Signer<'info>,
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = proposal_key.to_bytes()[..8]
+            .try_into()
+            .map_err(|_| UpgradeError::InvalidProposalId)?;
+        proposal.proposer = proposer;
+        proposal.program = program;
+        proposal.new_buffer = flag_change_target(&name, enabled, kill_switch, revision);
+        proposal.code_hash = [0; 32];
+        proposal.description = description;
+        proposal.proposed_at = clock.unix_timestamp;
+        proposal.timelock_until = clock.unix_timestamp + timelock_duration;
+        proposal.approvals = vec![proposer];
+        proposal.approval_threshold = config.threshold;
+        proposal.status = UpgradeStatus::Proposed;
+        proposal.executed_at = None;
+        proposal.cancel_votes = Vec::new();
+        proposal.cancellation_reason = None;
+        proposal.cancellation_details = String::new();
+        proposal.bump = ctx.bumps.proposal;
+        proposal.target_version = revision.saturating_add(1);
+        proposal.approval_digest = approval_digest;
+        proposal.organization_quorum = config.organization_quorum;
+        proposal.action = ProposalAction::SetFlag { name, enabled, kill_switch };
+        proposal.description_hash = None;
+
+        msg!("Flag change proposed for {}: enabled={}, kill_switch={}", program, enabled, kill_switch);
+
+        emit!(FlagChangeProposedEvent {
+            proposal_id: proposal_key,
+            proposer,
+            program,
+            name,
+            enabled,
+            kill_switch,
+            revision: proposal.target_version,
+            timelock_until: proposal.timelock_until,
+            approval_digest,
+        });
 
-    #[account(
-        seeds = [b"multisig_config"],
-        bump = multisig_config.bump
-    )]
-    pub multisig_config: Account<'info, MultisigConfig>,
+        Ok(())
+    }
 
-    #[account(
-        seeds = [b"program_upgrade_state"],
-        bump = program_upgrade_state.bump
-    )]
-    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+    /// Apply an approved flag change after the timelock, creating the
+    /// program's `FeatureFlags` account on first use
+    pub fn set_flag(
+        ctx: Context<SetFlag>,
+        _proposal_id: Pubkey,
+    ) -> Result<()> {
+        let proposal_key = ctx.accounts.proposal.key();
+        let executor = ctx.accounts.executor.key();
+        let config = &ctx.accounts.multisig_config;
 
-    /// CHECK: Program to be upgraded
-    pub program: UncheckedAccount<'info>,
+        require!(config.can_execute(&executor), UpgradeError::UnauthorizedExecutor);
 
-    #[account(
-        init,
-        payer = proposer,
-        space = 8 + UpgradeProposal::LEN,
-        seeds = [b"proposal", program.key().as_ref(), new_program_buffer.key().as_ref()],
-        bump
-    )]
-    pub proposal: Account<'info, UpgradeProposal>,
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
 
-    /// CHECK: New program buffer account
-    pub new_program_buffer: UncheckedAccount<'info>,
+        require!(
+            clock.unix_timestamp >= proposal.timelock_until,
+            UpgradeError::TimelockActive
+        );
+        require!(
+            proposal.approvals.len() >= proposal.approval_threshold as usize,
+            UpgradeError::InsufficientApprovals
+        );
+        require!(
+            config.organization_count(&proposal.approvals) >= proposal.organization_quorum as usize,
+            UpgradeError::InsufficientOrganizations
+        );
+        require!(
+            proposal.status == UpgradeStatus::TimelockActive,
+            UpgradeError::InvalidProposalStatus
+        );
+        let ProposalAction::SetFlag { name, enabled, kill_switch } = proposal.action else {
+            return err!(UpgradeError::WrongProposalAction);
+        };
+
+        let flags = &mut ctx.accounts.feature_flags;
+        if flags.program == Pubkey::default() {
+            flags.program = proposal.program;
+            flags.bump = ctx.bumps.feature_flags;
+        }
 
-    pub system_program: Program<'info, System>,
-}
+        // The flag and timelock approvers agreed to must still be in force
+        let revision = flags.revision(&name);
+        require!(
+            proposal.target_version == revision.saturating_add(1),
+            UpgradeError::StaleFlagRevision
+        );
+        let expected_digest = flag_change_digest(
+            &proposal.program,
+            &name,
+            enabled,
+            kill_switch,
+            revision,
+            ctx.accounts.program_upgrade_state.timelock_duration,
+        );
+        require!(
+            expected_digest == proposal.approval_digest,
+            UpgradeError::StaleApprovalDigest
+        );
 
-#[derive(Accounts)]
-pub struct ApproveUpgrade<'info> {
-    #[account(mut)]
-    pub approver: Signer<'info>,
+        let revision = flags.set(name, enabled, Some(kill_switch), executor, clock.unix_timestamp)?;
 
-    #[account(
-        seeds = [b"multisig_config"],
-        bump = multisig_config.bump
-    )]
-    pub multisig_config: Account<'info, MultisigConfig>,
+        proposal.status = UpgradeStatus::Executed;
+        proposal.executed_at = Some(clock.unix_timestamp);
 
-    #[account(
-        mut,
-        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
-        bump = proposal.bump
-    )]
-    pub proposal: Account<'info, UpgradeProposal>,
+        msg!("Feature flag of {} set: enabled={}, revision {}", proposal.program, enabled, revision);
 
-    #[account(
-        seeds = [b"program_upgrade_state"],
-        bump = program_upgrade_state.bump
-    )]
-    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
-}
+        emit!(FeatureFlagSetEvent {
+            program: proposal.program,
+            name,
+            enabled,
+            kill_switch,
+            revision,
+            proposal_id: Some(proposal_key),
+            updated_by: executor,
+            updated_at: clock.unix_timestamp,
+        });
 
-#[derive(Accounts)]
-pub struct ExecuteUpgrade<'info> {
-    #[account(mut)]
-    pub executor: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
+    /// Turn a kill-switch flag off straight away, without approvals or a
+    /// timelock. Any member may pull it; turning the flag back on goes
+    /// through `propose_flag_change` like any other change.
+    pub fn trip_kill_switch(
+        ctx: Context<TripKillSwitch>,
+        name: [u8; 32],
+    ) -> Result<()> {
+        let member = ctx.accounts.member.key();
+        require!(
+            ctx.accounts.multisig_config.members.contains(&member),
+            UpgradeError::NotMultisigMember
+        );
+
+        let flags = &mut ctx.accounts.feature_flags;
+        let flag = flags.get(&name).ok_or(UpgradeError::UnknownFeatureFlag)?;
+        require!(flag.kill_switch, UpgradeError::NotKillSwitch);
+
+        let clock = Clock::get()?;
+        let revision = flags.set(name, false, None, member, clock.unix_timestamp)?;
+
+        msg!("KILL SWITCH: feature flag of {} turned off by {}", flags.program, member);
+
+        emit!(FeatureFlagSetEvent {
+            program: flags.program,
+            name,
+            enabled: false,
+            kill_switch: true,
+            revision,
+            proposal_id: None,
+            updated_by: member,
+            updated_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an upgrade proposal (emergency only).
+    ///
+    /// The proposer can withdraw their own proposal before the threshold is met.
+    /// Anyone else records a cancel vote: `threshold` votes cancel a proposal that
+    /// is still collecting approvals, and once the timelock is running
+    /// `MultisigConfig::cancellation_quorum` votes are needed.
+    pub fn cancel_upgrade(
+        ctx: Context<CancelUpgrade>,
+        _proposal_id: Pubkey,
+        reason: CancellationReason,
+        details: String,
+    ) -> Result<()> {
+        let proposal_key = ctx.accounts.proposal.key();
+        let canceller = ctx.accounts.canceller.key();
+        let proposal = &mut ctx.accounts.proposal;
+        let config = &ctx.accounts.multisig_config;
+
+        // Verify canceller is a multisig member
+        require!(
+            config.members.contains(&canceller),
+            UpgradeError::NotMultisigMember
+        );
+
+        // Can only cancel before execution
+        require!(
+            proposal.status != UpgradeStatus::Executed,
+            UpgradeError::CannotCancelExecuted
+        );
+        require!(
+            proposal.status != UpgradeStatus::Cancelled,
+            UpgradeError::InvalidProposalStatus
+        );
+
+        require!(
+            details.len() <= MAX_CANCELLATION_DETAILS_LEN,
+            UpgradeError::CancellationDetailsTooLong
+        );
+        require!(
+            reason != CancellationReason::Other || !details.trim().is_empty(),
+            UpgradeError::CancellationDetailsRequired
+        );
+
+        let timelock_active = proposal.status == UpgradeStatus::TimelockActive;
+        let proposer_withdrawal = !timelock_active && canceller == proposal.proposer;
+
+        if !proposer_withdrawal {
+            require!(
+                !proposal.cancel_votes.contains(&canceller),
+                UpgradeError::AlreadyVotedToCancel
+            );
+
+            proposal.cancel_votes.push(canceller);
+            let required = if timelock_active {
+                config.cancellation_quorum()
+            } else {
+                config.threshold as usize
+            };
+
+            if proposal.cancel_votes.len() < required {
+                msg!("Cancel vote recorded. {}/{} votes", proposal.cancel_votes.len(), required);
+
+                emit!(CancelVoteCastEvent {
+                    proposal_id: proposal_key,
+                    voter: canceller,
+                    votes: proposal.cancel_votes.len() as u8,
+                    required: required as u8,
+                    reason,
+                });
+
+                return Ok(());
+            }
+        }
+
+        proposal.status = UpgradeStatus::Cancelled;
+        proposal.cancellation_reason = Some(reason);
+        proposal.cancellation_details = details.clone();
+
+        msg!("Proposal cancelled");
+
+        emit!(ProposalCancelledEvent {
+            proposal_id: proposal_key,
+            canceller,
+            reason,
+            details,
+            votes: proposal.cancel_votes.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Set or clear the bot key allowed to execute approved upgrades
+    pub fn set_execution_bot(
+        ctx: Context<SetExecutionBot>,
+        execution_bot: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.multisig_config;
+        config.execution_bot = execution_bot;
+
+        emit!(ExecutionBotUpdatedEvent {
+            authority: ctx.accounts.authority.key(),
+            execution_bot,
+        });
+
+        Ok(())
+    }
+
+    /// Propose changing the timelock applied to new proposals, within the
+    /// same bounds as `initialize`. The change goes through the same approvals
+    /// and, under the current duration, the same timelock as an upgrade. Its
+    /// proposal is filed under the upgrade manager itself, as the timelock
+    /// covers every managed program; `nonce` works as for authority changes.
+    pub fn propose_timelock_change(
+        ctx: Context<ProposeTimelockChange>,
+        timelock_duration: i64,
+        nonce: u64,
+        description: String,
+    ) -> Result<()> {
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN,
+            UpgradeError::DescriptionTooLong
+        );
+        require!(
+            valid_timelock_duration(timelock_duration),
+            UpgradeError::InvalidTimelockDuration
+        );
+
+        let config = &ctx.accounts.multisig_config;
+        require!(
+            config.members.contains(&ctx.accounts.proposer.key()),
+            UpgradeError::NotMultisigMember
+        );
+        let current_duration = ctx.accounts.program_upgrade_state.timelock_duration;
+        require!(
+            timelock_duration != current_duration,
+            UpgradeError::TimelockUnchanged
+        );
+
+        let proposer = ctx.accounts.proposer.key();
+        let proposal_key = ctx.accounts.proposal.key();
+        let approval_digest = timelock_change_digest(timelock_duration, current_duration);
+        let clock = Clock::get()?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = proposal_key.to_bytes()[..8]
+            .try_into()
+            .map_err(|_| UpgradeError::InvalidProposalId)?;
+        proposal.proposer = proposer;
+        proposal.program = crate::ID;
+        proposal.new_buffer = timelock_target(timelock_duration, nonce);
+        proposal.code_hash = [0; 32];
+        proposal.description = description;
+        proposal.proposed_at = clock.unix_timestamp;
+        proposal.timelock_until = clock.unix_timestamp + current_duration;
+        proposal.approvals = vec![proposer];
+        proposal.approval_threshold = config.threshold;
+        proposal.status = UpgradeStatus::Proposed;
+        proposal.executed_at = None;
+        proposal.cancel_votes = Vec::new();
+        proposal.cancellation_reason = None;
+        proposal.cancellation_details = String::new();
+        proposal.bump = ctx.bumps.proposal;
+        proposal.target_version = 0;
+        proposal.approval_digest = approval_digest;
+        proposal.organization_quorum = config.organization_quorum;
+        proposal.action = ProposalAction::SetTimelock { timelock_duration };
+        proposal.description_hash = None;
+
+        msg!("Timelock change proposed: {}s -> {}s", current_duration, timelock_duration);
+
+        emit!(TimelockChangeProposedEvent {
+            proposal_id: proposal_key,
+            proposer,
+            previous_duration: current_duration,
+            timelock_duration,
+            timelock_until: proposal.timelock_until,
+            approval_digest,
+        });
+
+        Ok(())
+    }
+
+    /// Apply an approved timelock change after its timelock. Approvals
+    /// commit to the timelock, so proposals approved under the old duration
+    /// must be re-approved before they can execute.
+    pub fn set_timelock_duration(
+        ctx: Context<SetTimelockDuration>,
+        _proposal_id: Pubkey,
+    ) -> Result<()> {
+        let proposal_key = ctx.accounts.proposal.key();
+        let executor = ctx.accounts.executor.key();
+        let config = &ctx.accounts.multisig_config;
+
+        require!(config.can_execute(&executor), UpgradeError::UnauthorizedExecutor);
+
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= proposal.timelock_until,
+            UpgradeError::TimelockActive
+        );
+        require!(
+            proposal.approvals.len() >= proposal.approval_threshold as usize,
+            UpgradeError::InsufficientApprovals
+        );
+        require!(
+            config.organization_count(&proposal.approvals) >= proposal.organization_quorum as usize,
+            UpgradeError::InsufficientOrganizations
+        );
+        require!(
+            proposal.status == UpgradeStatus::TimelockActive,
+            UpgradeError::InvalidProposalStatus
+        );
+        let ProposalAction::SetTimelock { timelock_duration } = proposal.action else {
+            return err!(UpgradeError::WrongProposalAction);
+        };
+
+        // The duration approvers changed from must still be in force
+        let state = &mut ctx.accounts.program_upgrade_state;
+        let previous_duration = state.timelock_duration;
+        require!(
+            timelock_change_digest(timelock_duration, previous_duration) == proposal.approval_digest,
+            UpgradeError::StaleApprovalDigest
+        );
+
+        state.timelock_duration = timelock_duration;
+        proposal.status = UpgradeStatus::Executed;
+        proposal.executed_at = Some(clock.unix_timestamp);
+
+        msg!("Timelock duration changed from {}s to {}s", previous_duration, timelock_duration);
+
+        emit!(TimelockDurationUpdatedEvent {
+            proposal_id: proposal_key,
+            executor,
+            previous_duration,
+            timelock_duration,
+        });
+
+        Ok(())
+    }
+
+    /// Assign each member to an organization and require approvals from at
+    /// least `organization_quorum` distinct organizations, so one party
+    /// holding several keys cannot meet the threshold alone.
+    /// `member_organizations[i]` is the organization of `members[i]`; a quorum
+    /// of 0 turns the check off. Proposals keep the quorum they were created with.
+    pub fn set_member_organizations(
+        ctx: Context<SetMemberOrganizations>,
+        member_organizations: Vec<u8>,
+        organization_quorum: u8,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.multisig_config;
+        require!(
+            member_organizations.len() == config.members.len(),
+            UpgradeError::InvalidOrganizationConfig
+        );
+        config.member_organizations = member_organizations;
+        require!(
+            organization_quorum as usize <= config.organization_count(&config.members),
+            UpgradeError::InvalidOrganizationConfig
+        );
+        config.organization_quorum = organization_quorum;
+
+        msg!("Organization quorum set to {}", organization_quorum);
+
+        emit!(OrganizationsUpdatedEvent {
+            authority: ctx.accounts.authority.key(),
+            member_organizations: config.member_organizations.clone(),
+            organization_quorum,
+        });
+
+        Ok(())
+    }
+
+    /// Register `program` as pausable, or replace its entry. `pause_data` is
+    /// the managed program's pause instruction data and `pause_accounts` the
+    /// writable accounts it takes after the `pause_authority` PDA, which the
+    /// managed program must accept as its pauser.
+    pub fn register_pausable_program(
+        ctx: Context<RegisterPausableProgram>,
+        pause_data: Vec<u8>,
+        pause_accounts: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            pause_data.len() <= MAX_PAUSE_DATA_LEN && pause_accounts.len() <= MAX_PAUSE_ACCOUNTS,
+            UpgradeError::InvalidPauseInstruction
+        );
+
+        let program = ctx.accounts.program.key();
+        let clock = Clock::get()?;
+        let registry = &mut ctx.accounts.pause_registry;
+        if registry.bump == 0 {
+            let config = &ctx.accounts.multisig_config;
+            registry.emergency_quorum = default_emergency_quorum(config.threshold, config.members.len());
+            registry.bump = ctx.bumps.pause_registry;
+        }
+
+        let entry = PausableProgram {
+            program,
+            pause_data,
+            pause_accounts,
+            registered_at: clock.unix_timestamp,
+            paused_at: None,
+        };
+        match registry.programs.iter().position(|p| p.program == program) {
+            Some(index) => registry.programs[index] = entry,
+            None => {
+                require!(
+                    registry.programs.len() < MAX_PAUSABLE_PROGRAMS,
+                    UpgradeError::TooManyPausablePrograms
+                );
+                registry.programs.push(entry);
+            }
+        }
+
+        msg!("Program {} registered for emergency pause", program);
+
+        emit!(PausableProgramRegisteredEvent {
+            authority: ctx.accounts.authority.key(),
+            program,
+            registered: true,
+        });
+
+        Ok(())
+    }
+
+    /// Take `program` out of the pause registry
+    pub fn remove_pausable_program(
+        ctx: Context<UpdatePauseRegistry>,
+        program: Pubkey,
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.pause_registry;
+        let index = registry
+            .programs
+            .iter()
+            .position(|p| p.program == program)
+            .ok_or(UpgradeError::NotPausable)?;
+        registry.programs.remove(index);
+
+        msg!("Program {} removed from the pause registry", program);
+
+        emit!(PausableProgramRegisteredEvent {
+            authority: ctx.accounts.authority.key(),
+            program,
+            registered: false,
+        });
+
+        Ok(())
+    }
+
+    /// Set how many members must vote for an emergency pause: at least two
+    /// once the multisig has two members, and at most the upgrade threshold
+    pub fn set_emergency_quorum(
+        ctx: Context<UpdatePauseRegistry>,
+        emergency_quorum: u8,
+    ) -> Result<()> {
+        let config = &ctx.accounts.multisig_config;
+        let min_quorum = min_emergency_quorum(config.members.len());
+        require!(
+            emergency_quorum >= min_quorum && emergency_quorum <= config.threshold.max(min_quorum),
+            UpgradeError::InvalidEmergencyQuorum
+        );
+        ctx.accounts.pause_registry.emergency_quorum = emergency_quorum;
+
+        msg!("Emergency pause quorum set to {}", emergency_quorum);
+
+        Ok(())
+    }
+
+    /// Vote to pause a registered program. Once `emergency_quorum` members
+    /// have voted within `EMERGENCY_VOTE_WINDOW`, the program's pause
+    /// instruction is invoked with the `pause_authority` PDA as signer, with
+    /// no timelock. The remaining accounts must be the registered
+    /// `pause_accounts`, in order.
+    pub fn emergency_pause<'info>(
+        ctx: Context<'_, '_, '_, 'info, EmergencyPause<'info>>,
+        reason: String,
+    ) -> Result<()> {
+        let voter = ctx.accounts.voter.key();
+        let program = ctx.accounts.program.key();
+        require!(
+            ctx.accounts.multisig_config.members.contains(&voter),
+            UpgradeError::NotMultisigMember
+        );
+        require!(
+            !reason.trim().is_empty() && reason.len() <= MAX_PAUSE_REASON_LEN,
+            UpgradeError::InvalidPauseReason
+        );
+
+        // Registries created before the two-vote minimum may hold a lower quorum
+        let registry = &mut ctx.accounts.pause_registry;
+        let required = registry
+            .emergency_quorum
+            .max(min_emergency_quorum(ctx.accounts.multisig_config.members.len()));
+        let entry = registry
+            .programs
+            .iter_mut()
+            .find(|p| p.program == program)
+            .ok_or(UpgradeError::NotPausable)?;
+
+        let clock = Clock::get()?;
+        let pause = &mut ctx.accounts.pause_votes;
+        if pause.program == Pubkey::default() {
+            pause.program = program;
+            pause.bump = ctx.bumps.pause_votes;
+        }
+        // Votes lapse, so an old alarm cannot add up with a new one
+        if pause.votes.is_empty() || clock.unix_timestamp > pause.opened_at + EMERGENCY_VOTE_WINDOW {
+            pause.votes.clear();
+            pause.opened_at = clock.unix_timestamp;
+            pause.reason = reason.clone();
+        }
+        require!(!pause.votes.contains(&voter), UpgradeError::AlreadyVotedToPause);
+        pause.votes.push(voter);
+
+        msg!(
+            "EMERGENCY PAUSE vote for {} by {}: {}/{} ({})",
+            program,
+            voter,
+            pause.votes.len(),
+            required,
+            reason
+        );
+
+        emit!(EmergencyPauseVoteEvent {
+            program,
+            voter,
+            votes: pause.votes.len() as u8,
+            required,
+            reason: reason.clone(),
+        });
+
+        if pause.votes.len() < required as usize {
+            return Ok(());
+        }
+
+        // Only the registered accounts, so voters cannot aim the pause elsewhere
+        require!(
+            ctx.remaining_accounts.len() == entry.pause_accounts.len()
+                && ctx.remaining_accounts
+                    .iter()
+                    .zip(&entry.pause_accounts)
+                    .all(|(account, key)| account.key == key),
+            UpgradeError::PauseAccountsMismatch
+        );
+
+        let pause_authority = ctx.accounts.pause_authority.to_account_info();
+        let mut accounts = vec![AccountMeta::new_readonly(pause_authority.key(), true)];
+        accounts.extend(entry.pause_accounts.iter().map(|key| AccountMeta::new(*key, false)));
+        let instruction = Instruction {
+            program_id: program,
+            accounts,
+            data: entry.pause_data.clone(),
+        };
+        let mut account_infos = vec![pause_authority, ctx.accounts.program.to_account_info()];
+        account_infos.extend(ctx.remaining_accounts.iter().cloned());
+        invoke_signed(
+            &instruction,
+            &account_infos,
+            &[&[b"pause_authority", &[ctx.bumps.pause_authority]]],
+        )?;
+
+        entry.paused_at = Some(clock.unix_timestamp);
+        pause.last_paused_at = Some(clock.unix_timestamp);
+        let voters = std::mem::take(&mut pause.votes);
+
+        msg!("EMERGENCY PAUSE: {} paused by {} members", program, voters.len());
+
+        emit!(EmergencyPauseExecutedEvent {
+            program,
+            voters,
+            reason: pause.reason.clone(),
+            paused_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Open the progress record of a batch migration. `migrate_account` calls
+    /// that pass it count towards `migrated`; the signer becomes the only key
+    /// allowed to do so.
+    pub fn start_migration(
+        ctx: Context<StartMigration>,
+        migration_id: [u8; 16],
+        total: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.multisig_config.can_execute(&ctx.accounts.authority.key()),
+            UpgradeError::UnauthorizedExecutor
+        );
+
+        let clock = Clock::get()?;
+        let state = &mut ctx.accounts.migration_state;
+        state.migration_id = migration_id;
+        state.authority = ctx.accounts.authority.key();
+        state.total = total;
+        state.migrated = 0;
+        state.failed = 0;
+        state.started_at = clock.unix_timestamp;
+        state.updated_at = clock.unix_timestamp;
+        state.bump = ctx.bumps.migration_state;
+
+        emit!(MigrationProgressEvent {
+            migration_id,
+            total,
+            migrated: 0,
+            failed: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Report how many accounts currently stand failed. A failed migrate
+    /// instruction rolls back and cannot count itself, so the migration's
+    /// authority reports the figure, lowering it when retries succeed.
+    pub fn record_migration_failures(
+        ctx: Context<RecordMigrationFailures>,
+        failed: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.migration_state;
+        state.failed = failed;
+        state.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(MigrationProgressEvent {
+            migration_id: state.migration_id,
+            total: state.total,
+            migrated: state.migrated,
+            failed,
+        });
+
+        Ok(())
+    }
+
+    /// Migrate account state from old to new program version
+    pub fn migrate_account(
+        ctx: Context<MigrateAccount>,
+        old_account: Pubkey,
+    ) -> Result<()> {
+        process_migration(ctx, old_account, false)
+    }
+
+    /// Lazy migration entry point, meant to be CPI'd by the managed program the
+    /// first time it touches an account after an upgrade. A no-op for accounts
+    /// that are already at the latest version. The program cannot be invoked
+    /// back, so instead of calling its migrate hook this returns the step as
+    /// `MigrateHookArgs` return data for the caller to apply, and only records
+    /// it when the program's `migration_caller` PDA signs.
+    pub fn migrate_if_needed(
+        ctx: Context<MigrateAccount>,
+        old_account: Pubkey,
+    ) -> Result<()> {
+        process_migration(ctx, old_account, true)
+    }
+}
+
+/// A program's metadata account, or `None` before its first executed
+/// upgrade or authority change
+fn read_program_meta(account: &UncheckedAccount) -> Result<Option<ProgramMeta>> {
+    if account.owner == &crate::ID && !account.data_is_empty() {
+        let data = account.try_borrow_data()?;
+        Ok(Some(ProgramMeta::try_deserialize(&mut &data[..])?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn process_migration(
+    ctx: Context<MigrateAccount>,
+    old_account: Pubkey,
+    lazy: bool,
+) -> Result<()> {
+    let account_info = ctx.accounts.old_account.to_account_info();
+    let managed_program = ctx.accounts.managed_program.key();
+    let clock = Clock::get()?;
+
+    // Only the managed program that owns the account can rewrite it
+    require!(
+        ctx.accounts.managed_program.executable && managed_program != crate::ID,
+        UpgradeError::InvalidAccountOwner
+    );
+    require_keys_eq!(*account_info.owner, managed_program, UpgradeError::InvalidAccountOwner);
+    if lazy {
+        let (caller, _) = Pubkey::find_program_address(&[b"migration_caller"], &managed_program);
+        require!(
+            ctx.accounts.migration_caller.as_ref().is_some_and(|signer| signer.key() == caller),
+            UpgradeError::NotMigrationCaller
+        );
+    }
+
+    let discriminator: [u8; 8] = {
+        let data = account_info.try_borrow_data()?;
+        require!(data.len() >= 8, UpgradeError::InvalidAccountData);
+        data[..8].try_into().unwrap()
+    };
+
+    let migration = &mut ctx.accounts.account_version;
+    if migration.bump == 0 {
+        // Freshly created version record: unversioned accounts are v1
+        migration.version = 1;
+        migration.bump = ctx.bumps.account_version;
+    }
+
+    let transform = match transforms::find_transform(&discriminator, migration.version) {
+        Some(transform) => transform,
+        // Lazy callers invoke this on every access; nothing to do is success,
+        // with no step returned
+        None if lazy => {
+            set_return_data(&[]);
+            return Ok(());
+        }
+        None if migration.migrated => return err!(UpgradeError::AlreadyMigrated),
+        None => return err!(UpgradeError::NoMigrationPath),
+    };
+
+    let old_len = account_info.data_len();
+    let new_len = (transform.new_len)(old_len);
+
+    // Top up rent exemption for the larger account
+    let required_lamports = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account_info.lamports());
+    if required_lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.migrator.to_account_info(),
+                    to: account_info.clone(),
+                },
+            ),
+            required_lamports,
+        )?;
+    }
+
+    let step = MigrateHookArgs {
+        from_version: transform.from_version,
+        to_version: transform.to_version,
+        new_len: new_len as u64,
+        migrated_at: clock.unix_timestamp,
+    };
+    if lazy {
+        // The calling program applies the step once this returns
+        set_return_data(&step.try_to_vec()?);
+    } else {
+        // The owning program resizes and rewrites its own account
+        let migration_authority = ctx.accounts.migration_authority.to_account_info();
+        let instruction = Instruction {
+            program_id: managed_program,
+            accounts: vec![
+                AccountMeta::new_readonly(migration_authority.key(), true),
+                AccountMeta::new(old_account, false),
+            ],
+            data: migrate_hook_data(&step)?,
+        };
+        invoke_signed(
+            &instruction,
+            &[
+                migration_authority,
+                account_info.clone(),
+                ctx.accounts.managed_program.to_account_info(),
+            ],
+            &[&[b"migration_authority", &[ctx.bumps.migration_authority]]],
+        )?;
+        require!(account_info.data_len() == new_len, UpgradeError::InvalidAccountData);
+    }
+
+    // Version bump happens in the same transaction as the rewrite, so a
+    // failed transform leaves both untouched
+    let migration = &mut ctx.accounts.account_version;
+    migration.version = transform.to_version;
+    migration.migrated = true;
+    migration.migrated_at = Some(clock.unix_timestamp);
+
+    msg!("Account migrated: version={}, size {} -> {}", migration.version, old_len, new_len);
+
+    if let Some(state) = ctx.accounts.migration_state.as_mut() {
+        state.migrated = state.migrated.saturating_add(1);
+        state.updated_at = clock.unix_timestamp;
+    }
+
+    emit!(AccountMigratedEvent {
+        account: old_account,
+        new_version: migration.version,
+        migrated_at: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MultisigConfig::LEN,
+        seeds = [b"multisig_config"],
+        bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramUpgradeState::LEN,
+        seeds = [b"program_upgrade_state"],
+        bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeUpgrade<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    /// CHECK: Program to be upgraded
+    pub program: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + UpgradeProposal::LEN,
+        seeds = [b"proposal", program.key().as_ref(), new_program_buffer.key().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    /// CHECK: New program buffer account
+    pub new_program_buffer: UncheckedAccount<'info>,
+
+    /// CHECK: Read for the current version when it exists; may be uninitialized
+    #[account(
+        seeds = [b"program_meta", program.key().as_ref()],
+        bump
+    )]
+    pub program_meta: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveUpgrade<'info> {
+    #[account(mut)]
+    pub approver: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeApproval<'info> {
+    pub approver: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteUpgrade<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    #[account(
+        init_if_needed,
+        payer = executor,
+        space = 8 + ProgramMeta::LEN,
+        seeds = [b"program_meta", proposal.program.as_ref()],
+        bump
+    )]
+    pub program_meta: Account<'info, ProgramMeta>,
+
+    /// CHECK: loader buffer being deployed; owner and authority are checked in the handler
+    #[account(address = proposal.new_buffer @ UpgradeError::InvalidProposalId)]
+    pub buffer: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_authority: Option<Pubkey>, nonce: u64)]
+pub struct ProposeAuthorityChange<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    /// CHECK: Program whose authority changes
+    pub program: UncheckedAccount<'info>,
+
+    /// Shares the upgrade proposal seeds, with the authority target in place
+    /// of the buffer, so approval and cancellation work unchanged
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + UpgradeProposal::LEN,
+        seeds = [b"proposal", program.key().as_ref(), authority_target(new_authority.as_ref(), nonce).as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    /// CHECK: Read for immutability when it exists; may be uninitialized
+    #[account(
+        seeds = [b"program_meta", program.key().as_ref()],
+        bump
+    )]
+    pub program_meta: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAuthorityChange<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    #[account(
+        init_if_needed,
+        payer = executor,
+        space = 8 + ProgramMeta::LEN,
+        seeds = [b"program_meta", proposal.program.as_ref()],
+        bump
+    )]
+    pub program_meta: Account<'info, ProgramMeta>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: [u8; 32], enabled: bool, kill_switch: bool, revision: u32)]
+pub struct ProposeFlagChange<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    /// CHECK: Program whose flag changes
+    pub program: UncheckedAccount<'info>,
+
+    /// Shares the upgrade proposal seeds, with the flag change in place of
+    /// the buffer, so approval and cancellation work unchanged
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + UpgradeProposal::LEN,
+        seeds = [
+            b"proposal",
+            program.key().as_ref(),
+            flag_change_target(&name, enabled, kill_switch, revision).as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    /// CHECK: Read for the flag's current revision when it exists; may be uninitialized
+    #[account(
+        seeds = [b"feature_flags", program.key().as_ref()],
+        bump
+    )]
+    pub feature_flags: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFlag<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    #[account(
+        init_if_needed,
+        payer = executor,
+        space = 8 + FeatureFlags::LEN,
+        seeds = [b"feature_flags", proposal.program.as_ref()],
+        bump
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TripKillSwitch<'info> {
+    pub member: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"feature_flags", feature_flags.program.as_ref()],
+        bump = feature_flags.bump
+    )]
+    pub feature_flags: Account<'info, FeatureFlags>,
+}
+
+#[derive(Accounts)]
+pub struct CancelUpgrade<'info> {
+    #[account(mut)]
+    pub canceller: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+}
+
+#[derive(Accounts)]
+pub struct SetExecutionBot<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump,
+        constraint = multisig_config.upgrade_authority == authority.key() @ UpgradeError::NotUpgradeAuthority
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(timelock_duration: i64, nonce: u64)]
+pub struct ProposeTimelockChange<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+
+    /// Shares the upgrade proposal seeds, with the upgrade manager as the
+    /// program and the timelock target in place of the buffer, so approval
+    /// and cancellation work unchanged
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + UpgradeProposal::LEN,
+        seeds = [b"proposal", crate::ID.as_ref(), timelock_target(timelock_duration, nonce).as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTimelockDuration<'info> {
+    pub executor: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
         seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
         bump = proposal.bump
     )]
-    pub proposal: Account<'info, UpgradeProposal>,
+    pub proposal: Account<'info, UpgradeProposal>,
+
+    #[account(
+        mut,
+        seeds = [b"program_upgrade_state"],
+        bump = program_upgrade_state.bump
+    )]
+    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+}
+
+#[derive(Accounts)]
+pub struct SetMemberOrganizations<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump,
+        constraint = multisig_config.upgrade_authority == authority.key() @ UpgradeError::NotUpgradeAuthority
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterPausableProgram<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump,
+        constraint = multisig_config.upgrade_authority == authority.key() @ UpgradeError::NotUpgradeAuthority
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PauseRegistry::LEN,
+        seeds = [b"pause_registry"],
+        bump
+    )]
+    pub pause_registry: Account<'info, PauseRegistry>,
+
+    /// CHECK: Managed program that can be paused
+    pub program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePauseRegistry<'info> {
+    pub authority: Signer<'info>,
 
     #[account(
-        seeds = [b"program_upgrade_state"],
-        bump = program_upgrade_state.bump
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump,
+        constraint = multisig_config.upgrade_authority == authority.key() @ UpgradeError::NotUpgradeAuthority
     )]
-    pub program_upgrade_state: Account<'info, ProgramUpgradeState>,
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"pause_registry"],
+        bump = pause_registry.bump
+    )]
+    pub pause_registry: Account<'info, PauseRegistry>,
 }
 
 #[derive(Accounts)]
-pub struct CancelUpgrade<'info> {
+pub struct EmergencyPause<'info> {
     #[account(mut)]
-    pub canceller: Signer<'info>,
+    pub voter: Signer<'info>,
 
     #[account(
         seeds = [b"multisig_config"],
@@ -383,10 +1931,31 @@ pub struct CancelUpgrade<'info> {
 
     #[account(
         mut,
-        seeds = [b"proposal", proposal.program.as_ref(), proposal.new_buffer.as_ref()],
-        bump = proposal.bump
+        seeds = [b"pause_registry"],
+        bump = pause_registry.bump
     )]
-    pub proposal: Account<'info, UpgradeProposal>,
+    pub pause_registry: Account<'info, PauseRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = 8 + PauseVotes::LEN,
+        seeds = [b"pause_votes", program.key().as_ref()],
+        bump
+    )]
+    pub pause_votes: Account<'info, PauseVotes>,
+
+    /// CHECK: Registered managed program; checked against the registry in the handler
+    pub program: UncheckedAccount<'info>,
+
+    /// CHECK: Signs the pause CPI; holds no data
+    #[account(
+        seeds = [b"pause_authority"],
+        bump
+    )]
+    pub pause_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -395,24 +1964,89 @@ pub struct MigrateAccount<'info> {
     pub migrator: Signer<'info>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = migrator,
+        space = 8 + AccountVersion::LEN,
         seeds = [b"account_version", old_account.key().as_ref()],
         bump
     )]
     pub account_version: Account<'info, AccountVersion>,
 
-    /// CHECK: Old account to migrate from
+    /// CHECK: Owner and discriminator are validated in the instruction
+    #[account(mut)]
     pub old_account: UncheckedAccount<'info>,
 
+    /// CHECK: Must own `old_account`; checked in the handler
+    pub managed_program: UncheckedAccount<'info>,
+
+    /// CHECK: Signs the migrate hook CPI; holds no data
+    #[account(
+        seeds = [b"migration_authority"],
+        bump
+    )]
+    pub migration_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Batch migration to count this account towards. Lazy callers pass the
+    /// upgrade-manager program ID in its place.
+    #[account(
+        mut,
+        seeds = [b"migration_state", migration_state.migration_id.as_ref()],
+        bump = migration_state.bump,
+        constraint = migration_state.authority == migrator.key() @ UpgradeError::NotMigrationAuthority
+    )]
+    pub migration_state: Option<Account<'info, MigrationState>>,
+
+    /// The managed program's `["migration_caller"]` PDA, signing its lazy
+    /// migrations. Last, so batch callers can leave it out.
+    pub migration_caller: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(migration_id: [u8; 16])]
+pub struct StartMigration<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"multisig_config"],
+        bump = multisig_config.bump
+    )]
+    pub multisig_config: Account<'info, MultisigConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MigrationState::LEN,
+        seeds = [b"migration_state", migration_id.as_ref()],
+        bump
+    )]
+    pub migration_state: Account<'info, MigrationState>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RecordMigrationFailures<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"migration_state", migration_state.migration_id.as_ref()],
+        bump = migration_state.bump,
+        has_one = authority @ UpgradeError::NotMigrationAuthority
+    )]
+    pub migration_state: Account<'info, MigrationState>,
+}
+
 #[account]
 pub struct UpgradeProposal {
     pub id: [u8; 8],
     pub proposer: Pubkey,
     pub program: Pubkey,
     pub new_buffer: Pubkey,
+    pub code_hash: [u8; 32],
     pub description: String,
     pub proposed_at: i64,
     pub timelock_until: i64,
@@ -420,7 +2054,20 @@ pub struct UpgradeProposal {
     pub approval_threshold: u8,
     pub status: UpgradeStatus,
     pub executed_at: Option<i64>,
+    pub cancel_votes: Vec<Pubkey>,
+    pub cancellation_reason: Option<CancellationReason>,
+    pub cancellation_details: String,
     pub bump: u8,
+    /// `ProgramMeta` version this upgrade produces
+    pub target_version: u32,
+    /// `approval_digest` over this proposal; approvals must echo it
+    pub approval_digest: [u8; 32],
+    /// Distinct organizations that must approve, from the config at proposal time
+    pub organization_quorum: u8,
+    /// What executing the proposal does
+    pub action: ProposalAction,
+    /// SHA-256 of the full description when it is longer than the on-chain copy
+    pub description_hash: Option<[u8; 32]>,
 }
 
 impl UpgradeProposal {
@@ -429,14 +2076,23 @@ impl UpgradeProposal {
         32 +                        // proposer
         32 +                        // program
         32 +                        // new_buffer
-        4 + 256 +                   // description (String)
+        32 +                        // code_hash
+        4 + MAX_DESCRIPTION_LEN +   // description (String)
         8 +                         // proposed_at
         8 +                         // timelock_until
         4 + (32 * 10) +             // approvals (max 10 members)
         1 +                         // approval_threshold
         1 +                         // status
         1 + 8 +                     // executed_at (Option<i64>)
-        1;                          // bump
+        4 + (32 * 10) +             // cancel_votes (max 10 members)
+        1 + 1 +                     // cancellation_reason (Option<enum>)
+        4 + MAX_CANCELLATION_DETAILS_LEN + // cancellation_details (String)
+        1 +                         // bump
+        4 +                         // target_version
+        32 +                        // approval_digest
+        1 +                         // organization_quorum
+        1 + 32 + 1 + 1 +            // action (largest: SetFlag { [u8; 32], bool, bool })
+        1 + 32;                     // description_hash
 }
 
 #[account]
@@ -444,14 +2100,48 @@ pub struct MultisigConfig {
     pub members: Vec<Pubkey>,
     pub threshold: u8,
     pub upgrade_authority: Pubkey,
+    pub execution_bot: Option<Pubkey>,
     pub bump: u8,
+    /// Organization of each member, by index into `members`
+    pub member_organizations: Vec<u8>,
+    /// Distinct organizations needed to approve; 0 disables the check
+    pub organization_quorum: u8,
 }
 
 impl MultisigConfig {
     pub const LEN: usize = 4 + (32 * 10) +  // members (max 10)
         1 +                                  // threshold
         32 +                                 // upgrade_authority
-        1;                                   // bump
+        1 + 32 +                             // execution_bot
+        1 +                                  // bump
+        4 + 10 +                             // member_organizations (max 10)
+        1;                                   // organization_quorum
+
+    /// Whether `key` may execute approved upgrades: a member, the upgrade
+    /// authority, or the configured execution bot
+    pub fn can_execute(&self, key: &Pubkey) -> bool {
+        self.members.contains(key)
+            || self.upgrade_authority == *key
+            || self.execution_bot == Some(*key)
+    }
+
+    /// Votes needed to cancel a proposal whose timelock is running:
+    /// threshold + 1, capped at the member count
+    pub fn cancellation_quorum(&self) -> usize {
+        (self.threshold as usize + 1).min(self.members.len())
+    }
+
+    /// Distinct organizations among `keys`; keys without an organization are not counted
+    pub fn organization_count(&self, keys: &[Pubkey]) -> usize {
+        let mut organizations: Vec<u8> = keys
+            .iter()
+            .filter_map(|key| self.members.iter().position(|member| member == key))
+            .filter_map(|index| self.member_organizations.get(index).copied())
+            .collect();
+        organizations.sort_unstable();
+        organizations.dedup();
+        organizations.len()
+    }
 }
 
 #[account]
@@ -479,6 +2169,218 @@ pub struct PendingUpgrade {
     pub approved_by: Vec<Pubkey>,
 }
 
+/// Most members `MultisigConfig` and the proposal vote lists have room for
+pub const MAX_MEMBERS: usize = 10;
+
+/// Smallest threshold `initialize` accepts, so no single key can upgrade alone
+pub const MIN_THRESHOLD: u8 = 2;
+
+/// Shortest timelock the program accepts: members get at least two days to
+/// review an approved upgrade before it can run
+pub const MIN_TIMELOCK_DURATION: i64 = 48 * 60 * 60;
+
+/// Longest timelock the program accepts, so a typo cannot lock upgrades out
+pub const MAX_TIMELOCK_DURATION: i64 = 30 * 24 * 60 * 60;
+
+/// Whether `timelock_duration` lies within the program's bounds
+pub fn valid_timelock_duration(timelock_duration: i64) -> bool {
+    (MIN_TIMELOCK_DURATION..=MAX_TIMELOCK_DURATION).contains(&timelock_duration)
+}
+
+/// Maximum length in bytes of a proposal description; longer documents live
+/// off chain and are pinned by `description_hash`
+pub const MAX_DESCRIPTION_LEN: usize = 256;
+
+/// Maximum length of free-text cancellation details
+pub const MAX_CANCELLATION_DETAILS_LEN: usize = 200;
+
+/// Domain separator for approval digests; bump when the layout changes
+pub const APPROVAL_DIGEST_DOMAIN: &[u8] = b"goquant-upgrade-approval-v1";
+
+/// Canonical digest approvers sign over: SHA-256 of the domain, program,
+/// buffer, code hash, target version (u32 LE) and timelock duration (i64 LE)
+pub fn approval_digest(
+    program: &Pubkey,
+    buffer: &Pubkey,
+    code_hash: &[u8; 32],
+    target_version: u32,
+    timelock_duration: i64,
+) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[
+        APPROVAL_DIGEST_DOMAIN,
+        program.as_ref(),
+        buffer.as_ref(),
+        code_hash,
+        &target_version.to_le_bytes(),
+        &timelock_duration.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Domain separator for authority change digests
+pub const AUTHORITY_CHANGE_DIGEST_DOMAIN: &[u8] = b"goquant-authority-change-v1";
+
+/// Digest approvers sign over for an authority change: SHA-256 of the
+/// domain, program, a byte telling a new authority from immutability, the
+/// new authority (zeros for none) and timelock duration (i64 LE)
+pub fn authority_change_digest(
+    program: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    timelock_duration: i64,
+) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[
+        AUTHORITY_CHANGE_DIGEST_DOMAIN,
+        program.as_ref(),
+        &[new_authority.is_some() as u8],
+        new_authority.copied().unwrap_or_default().as_ref(),
+        &timelock_duration.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Key standing in for the buffer in an authority change proposal's
+/// address: a hash of the new authority (the default key for immutability)
+/// and the proposer's nonce
+pub fn authority_target(new_authority: Option<&Pubkey>, nonce: u64) -> Pubkey {
+    Pubkey::new_from_array(
+        solana_sha256_hasher::hashv(&[
+            AUTHORITY_CHANGE_DIGEST_DOMAIN,
+            &[new_authority.is_some() as u8],
+            new_authority.copied().unwrap_or_default().as_ref(),
+            &nonce.to_le_bytes(),
+        ])
+        .to_bytes(),
+    )
+}
+
+/// Domain separator for timelock change digests
+pub const TIMELOCK_CHANGE_DIGEST_DOMAIN: &[u8] = b"goquant-timelock-change-v1";
+
+/// Digest approvers sign over for a timelock change: SHA-256 of the domain,
+/// the new duration and the duration it replaces (both i64 LE)
+pub fn timelock_change_digest(timelock_duration: i64, previous_duration: i64) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[
+        TIMELOCK_CHANGE_DIGEST_DOMAIN,
+        &timelock_duration.to_le_bytes(),
+        &previous_duration.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Key standing in for the buffer in a timelock change proposal's address:
+/// a hash of the new duration and the proposer's nonce
+pub fn timelock_target(timelock_duration: i64, nonce: u64) -> Pubkey {
+    Pubkey::new_from_array(
+        solana_sha256_hasher::hashv(&[
+            TIMELOCK_CHANGE_DIGEST_DOMAIN,
+            &timelock_duration.to_le_bytes(),
+            &nonce.to_le_bytes(),
+        ])
+        .to_bytes(),
+    )
+}
+
+/// Most programs the pause registry has room for
+pub const MAX_PAUSABLE_PROGRAMS: usize = 8;
+
+/// Longest pause instruction data a registry entry holds
+pub const MAX_PAUSE_DATA_LEN: usize = 64;
+
+/// Most accounts a pause instruction can take besides the pause authority
+pub const MAX_PAUSE_ACCOUNTS: usize = 4;
+
+/// Maximum length of an emergency pause reason
+pub const MAX_PAUSE_REASON_LEN: usize = 200;
+
+/// How long emergency pause votes stay open before they lapse
+pub const EMERGENCY_VOTE_WINDOW: i64 = 60 * 60;
+
+/// Fewest votes an emergency pause takes: two, so no single member can halt
+/// a program, unless the multisig has only one member
+pub fn min_emergency_quorum(members: usize) -> u8 {
+    members.clamp(1, 2) as u8
+}
+
+/// Emergency quorum of a new pause registry: half the upgrade threshold,
+/// rounded up, and never below `min_emergency_quorum`
+pub fn default_emergency_quorum(threshold: u8, members: usize) -> u8 {
+    threshold.div_ceil(2).max(min_emergency_quorum(members))
+}
+
+/// Most flags one program's `FeatureFlags` account has room for
+pub const MAX_FEATURE_FLAGS: usize = 32;
+
+/// Domain separator for flag change digests
+pub const FLAG_CHANGE_DIGEST_DOMAIN: &[u8] = b"goquant-flag-change-v1";
+
+/// Digest approvers sign over for a flag change: SHA-256 of the domain,
+/// program, flag name, enabled and kill-switch bytes, the flag's revision
+/// before the change (u32 LE) and timelock duration (i64 LE)
+pub fn flag_change_digest(
+    program: &Pubkey,
+    name: &[u8; 32],
+    enabled: bool,
+    kill_switch: bool,
+    revision: u32,
+    timelock_duration: i64,
+) -> [u8; 32] {
+    solana_sha256_hasher::hashv(&[
+        FLAG_CHANGE_DIGEST_DOMAIN,
+        program.as_ref(),
+        name,
+        &[enabled as u8, kill_switch as u8],
+        &revision.to_le_bytes(),
+        &timelock_duration.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Key standing in for the buffer in a flag change proposal's address: a
+/// hash of the change, so each change to each revision gets its own proposal
+pub fn flag_change_target(name: &[u8; 32], enabled: bool, kill_switch: bool, revision: u32) -> Pubkey {
+    Pubkey::new_from_array(
+        solana_sha256_hasher::hashv(&[
+            FLAG_CHANGE_DIGEST_DOMAIN,
+            name,
+            &[enabled as u8, kill_switch as u8],
+            &revision.to_le_bytes(),
+        ])
+        .to_bytes(),
+    )
+}
+
+/// Authority of a BPF upgradeable loader buffer: the `Buffer` variant tag
+/// (u32 LE 1) followed by an `Option<Pubkey>`. `None` for anything else.
+pub fn buffer_authority(data: &[u8]) -> Option<Pubkey> {
+    if data.len() < 37 || data[..4] != 1u32.to_le_bytes() || data[4] != 1 {
+        return None;
+    }
+    Some(Pubkey::new_from_array(data[5..37].try_into().ok()?))
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum CancellationReason {
+    SecurityIssue,
+    BuildMismatch,
+    Superseded,
+    ProposerWithdrawn,
+    Other,
+}
+
+/// What executing a proposal does
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum ProposalAction {
+    /// Deploy the proposal's buffer
+    Upgrade,
+    /// Hand the upgrade authority to `new_authority`, or with `None` make the
+    /// program immutable for good
+    SetAuthority { new_authority: Option<Pubkey> },
+    /// Set feature flag `name` in the program's `FeatureFlags` account
+    SetFlag { name: [u8; 32], enabled: bool, kill_switch: bool },
+    /// Set the timelock applied to new proposals
+    SetTimelock { timelock_duration: i64 },
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
 pub enum UpgradeStatus {
     Proposed,
@@ -488,6 +2390,196 @@ pub enum UpgradeStatus {
     Cancelled,
 }
 
+/// Managed programs the multisig can pause in an emergency, through a CPI
+/// signed by the `pause_authority` PDA
+#[account]
+pub struct PauseRegistry {
+    pub programs: Vec<PausableProgram>,
+    /// Member votes that pause a program, below the upgrade threshold
+    pub emergency_quorum: u8,
+    pub bump: u8,
+}
+
+impl PauseRegistry {
+    pub const LEN: usize = 4 + (PausableProgram::LEN * MAX_PAUSABLE_PROGRAMS) + // programs
+        1 +                         // emergency_quorum
+        1;                          // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct PausableProgram {
+    pub program: Pubkey,
+    /// Instruction data of the managed program's pause instruction
+    pub pause_data: Vec<u8>,
+    /// Writable accounts the pause instruction takes after the pause authority
+    pub pause_accounts: Vec<Pubkey>,
+    pub registered_at: i64,
+    pub paused_at: Option<i64>,
+}
+
+impl PausableProgram {
+    pub const LEN: usize = 32 +     // program
+        4 + MAX_PAUSE_DATA_LEN +    // pause_data
+        4 + (32 * MAX_PAUSE_ACCOUNTS) + // pause_accounts
+        8 +                         // registered_at
+        1 + 8;                      // paused_at (Option<i64>)
+}
+
+/// Open emergency pause votes for one program
+#[account]
+pub struct PauseVotes {
+    pub program: Pubkey,
+    pub votes: Vec<Pubkey>,
+    /// Reason given with the first vote
+    pub reason: String,
+    pub opened_at: i64,
+    pub last_paused_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl PauseVotes {
+    pub const LEN: usize = 32 +     // program
+        4 + (32 * MAX_MEMBERS) +    // votes
+        4 + MAX_PAUSE_REASON_LEN +  // reason (String)
+        8 +                         // opened_at
+        1 + 8 +                     // last_paused_at (Option<i64>)
+        1;                          // bump
+}
+
+/// Feature flags of a managed program, which the program reads from this
+/// PDA to gate risky behaviour
+#[account]
+pub struct FeatureFlags {
+    pub program: Pubkey,
+    pub flags: Vec<FeatureFlag>,
+    pub bump: u8,
+}
+
+impl FeatureFlags {
+    pub const LEN: usize = 32 +     // program
+        4 + (FeatureFlag::LEN * MAX_FEATURE_FLAGS) + // flags
+        1;                          // bump
+
+    pub fn get(&self, name: &[u8; 32]) -> Option<&FeatureFlag> {
+        self.flags.iter().find(|flag| flag.name == *name)
+    }
+
+    /// Whether `name` is on; unknown flags are off
+    pub fn is_enabled(&self, name: &[u8; 32]) -> bool {
+        self.get(name).is_some_and(|flag| flag.enabled)
+    }
+
+    /// Changes made to `name` so far; 0 for a flag that was never set
+    pub fn revision(&self, name: &[u8; 32]) -> u32 {
+        self.get(name).map_or(0, |flag| flag.revision)
+    }
+
+    /// Set `name`, adding it when new, keeping its kill-switch marking when
+    /// `kill_switch` is `None`. Returns the flag's new revision.
+    fn set(
+        &mut self,
+        name: [u8; 32],
+        enabled: bool,
+        kill_switch: Option<bool>,
+        updated_by: Pubkey,
+        updated_at: i64,
+    ) -> Result<u32> {
+        let flag = match self.flags.iter().position(|flag| flag.name == name) {
+            Some(index) => &mut self.flags[index],
+            None => {
+                require!(self.flags.len() < MAX_FEATURE_FLAGS, UpgradeError::TooManyFeatureFlags);
+                self.flags.push(FeatureFlag {
+                    name,
+                    enabled: false,
+                    kill_switch: false,
+                    revision: 0,
+                    updated_at,
+                    updated_by,
+                });
+                self.flags.last_mut().unwrap()
+            }
+        };
+        flag.enabled = enabled;
+        flag.kill_switch = kill_switch.unwrap_or(flag.kill_switch);
+        flag.revision = flag.revision.saturating_add(1);
+        flag.updated_at = updated_at;
+        flag.updated_by = updated_by;
+        Ok(flag.revision)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub struct FeatureFlag {
+    /// UTF-8 name, zero-padded
+    pub name: [u8; 32],
+    pub enabled: bool,
+    /// Any member may turn the flag off without approvals
+    pub kill_switch: bool,
+    pub revision: u32,
+    pub updated_at: i64,
+    pub updated_by: Pubkey,
+}
+
+impl FeatureFlag {
+    pub const LEN: usize = 32 +     // name
+        1 +                         // enabled
+        1 +                         // kill_switch
+        4 +                         // revision
+        8 +                         // updated_at
+        32;                         // updated_by
+}
+
+/// Latest upgrade of a managed program, readable in a single account fetch
+#[account]
+pub struct ProgramMeta {
+    pub program: Pubkey,
+    pub version: u32,
+    pub last_upgraded_at: i64,
+    pub last_proposal: Pubkey,
+    pub code_hash: [u8; 32],
+    pub bump: u8,
+    /// Upgrade authority removed by an executed authority change
+    pub immutable: bool,
+    /// Upgrade authority handed over by an executed authority change; `None`
+    /// while the program follows the config's `upgrade_authority`
+    pub upgrade_authority: Option<Pubkey>,
+}
+
+impl ProgramMeta {
+    pub const LEN: usize = 32 +     // program
+        4 +                         // version
+        8 +                         // last_upgraded_at
+        32 +                        // last_proposal
+        32 +                        // code_hash
+        1 +                         // bump
+        1 +                         // immutable
+        1 + 32;                     // upgrade_authority
+}
+
+/// On-chain progress of one batch migration, keyed by the backend's migration ID
+#[account]
+pub struct MigrationState {
+    pub migration_id: [u8; 16],
+    pub authority: Pubkey,
+    pub total: u64,
+    pub migrated: u64,
+    pub failed: u64,
+    pub started_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl MigrationState {
+    pub const LEN: usize = 16 +     // migration_id
+        32 +                        // authority
+        8 +                         // total
+        8 +                         // migrated
+        8 +                         // failed
+        8 +                         // started_at
+        8 +                         // updated_at
+        1;                          // bump
+}
+
 #[account]
 pub struct AccountVersion {
     pub version: u32,
@@ -503,6 +2595,30 @@ impl AccountVersion {
         1;                          // bump
 }
 
+/// Discriminator of the migrate hook a managed program exposes, Anchor's for
+/// an instruction named `upgrade_manager_migrate`
+pub const MIGRATE_HOOK_DISCRIMINATOR: [u8; 8] = [126, 182, 253, 9, 11, 138, 92, 112];
+
+/// One migration step for the owning program to apply: resize the account to
+/// `new_len` and rewrite it from the `from_version` layout, e.g. with the
+/// matching `transforms::find_transform`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MigrateHookArgs {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub new_len: u64,
+    pub migrated_at: i64,
+}
+
+/// Data of the migrate hook CPI: the discriminator, then the borsh `args`.
+/// The hook takes the `migration_authority` PDA (signer) and the account
+/// (writable).
+pub fn migrate_hook_data(args: &MigrateHookArgs) -> Result<Vec<u8>> {
+    let mut data = MIGRATE_HOOK_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+    Ok(data)
+}
+
 #[error_code]
 pub enum UpgradeError {
     #[msg("Not a multisig member")]
@@ -521,6 +2637,84 @@ pub enum UpgradeError {
     AlreadyMigrated,
     #[msg("Invalid proposal ID")]
     InvalidProposalId,
+    #[msg("Account is not owned by the managed program")]
+    InvalidAccountOwner,
+    #[msg("Account data does not match the expected layout")]
+    InvalidAccountData,
+    #[msg("No migration registered for this account type and version")]
+    NoMigrationPath,
+    #[msg("Already voted to cancel")]
+    AlreadyVotedToCancel,
+    #[msg("Cancellation details too long")]
+    CancellationDetailsTooLong,
+    #[msg("Cancellation reason 'Other' requires details")]
+    CancellationDetailsRequired,
+    #[msg("Executor must be a multisig member, the upgrade authority or the execution bot")]
+    UnauthorizedExecutor,
+    #[msg("Only the upgrade authority can change this setting")]
+    NotUpgradeAuthority,
+    #[msg("Approval digest does not match the proposal")]
+    ApprovalDigestMismatch,
+    #[msg("Program version or timelock changed since the proposal was approved")]
+    StaleApprovalDigest,
+    #[msg("Approvals do not span enough distinct organizations")]
+    InsufficientOrganizations,
+    #[msg("Every member needs an organization and the quorum cannot exceed the organizations")]
+    InvalidOrganizationConfig,
+    #[msg("Buffer authority has not been handed to the upgrade authority")]
+    BufferAuthorityNotTransferred,
+    #[msg("Only the key that started the migration can update its progress")]
+    NotMigrationAuthority,
+    #[msg("Signer has not approved this proposal")]
+    NotApproved,
+    #[msg("Removing the upgrade authority is irreversible and must be confirmed")]
+    ImmutabilityNotConfirmed,
+    #[msg("The new authority is already the upgrade authority")]
+    AuthorityUnchanged,
+    #[msg("The proposal is for a different action")]
+    WrongProposalAction,
+    #[msg("Timelock duration must be between 48 hours and 30 days")]
+    InvalidTimelockDuration,
+    #[msg("The multisig needs at least one member")]
+    NoMembers,
+    #[msg("The multisig has room for at most 10 members")]
+    TooManyMembers,
+    #[msg("Members must be unique")]
+    DuplicateMember,
+    #[msg("Threshold must be at least 2")]
+    ThresholdTooLow,
+    #[msg("Threshold cannot exceed the number of members")]
+    ThresholdExceedsMembers,
+    #[msg("Description exceeds 256 bytes; store it off chain and pass its hash")]
+    DescriptionTooLong,
+    #[msg("The program has no room for more feature flags")]
+    TooManyFeatureFlags,
+    #[msg("No feature flag with this name")]
+    UnknownFeatureFlag,
+    #[msg("Only kill-switch flags can be turned off without approvals")]
+    NotKillSwitch,
+    #[msg("The flag changed since this proposal was made")]
+    StaleFlagRevision,
+    #[msg("The pause registry has room for at most 8 programs")]
+    TooManyPausablePrograms,
+    #[msg("Pause data is limited to 64 bytes and 4 accounts")]
+    InvalidPauseInstruction,
+    #[msg("Program is not registered for emergency pause")]
+    NotPausable,
+    #[msg("Emergency quorum must be at least 2 and at most the upgrade threshold")]
+    InvalidEmergencyQuorum,
+    #[msg("A pause reason of at most 200 bytes is required")]
+    InvalidPauseReason,
+    #[msg("Already voted to pause")]
+    AlreadyVotedToPause,
+    #[msg("Accounts do not match the registered pause accounts")]
+    PauseAccountsMismatch,
+    #[msg("The program is immutable and can no longer be upgraded")]
+    ProgramImmutable,
+    #[msg("Lazy migrations must be signed by the managed program's migration_caller PDA")]
+    NotMigrationCaller,
+    #[msg("The proposed timelock duration is already in force")]
+    TimelockUnchanged,
 }
 
 #[event]
@@ -537,6 +2731,19 @@ pub struct ProposalCreatedEvent {
     pub proposer: Pubkey,
     pub new_buffer: Pubkey,
     pub timelock_until: i64,
+    pub target_version: u32,
+    pub approval_digest: [u8; 32],
+}
+
+#[event]
+pub struct AuthorityChangeProposedEvent {
+    pub proposal_id: Pubkey,
+    pub proposer: Pubkey,
+    pub program: Pubkey,
+    /// `None` makes the program immutable
+    pub new_authority: Option<Pubkey>,
+    pub timelock_until: i64,
+    pub approval_digest: [u8; 32],
 }
 
 #[event]
@@ -545,19 +2752,145 @@ pub struct ProposalApprovedEvent {
     pub approver: Pubkey,
     pub approvals: usize,
     pub threshold: u8,
+    pub organizations: u8,
+    pub organization_quorum: u8,
+}
+
+#[event]
+pub struct ApprovalRevokedEvent {
+    pub proposal_id: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: usize,
+    pub threshold: u8,
 }
 
 #[event]
 pub struct UpgradeExecutedEvent {
     pub proposal_id: Pubkey,
     pub program: Pubkey,
+    pub executor: Pubkey,
+    pub executed_at: i64,
+    pub version: u32,
+    pub code_hash: [u8; 32],
+}
+
+#[event]
+pub struct AuthorityChangedEvent {
+    pub proposal_id: Pubkey,
+    pub program: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Option<Pubkey>,
+    pub executor: Pubkey,
     pub executed_at: i64,
 }
 
+#[event]
+pub struct FlagChangeProposedEvent {
+    pub proposal_id: Pubkey,
+    pub proposer: Pubkey,
+    pub program: Pubkey,
+    pub name: [u8; 32],
+    pub enabled: bool,
+    pub kill_switch: bool,
+    /// Flag revision the change produces
+    pub revision: u32,
+    pub timelock_until: i64,
+    pub approval_digest: [u8; 32],
+}
+
+#[event]
+pub struct FeatureFlagSetEvent {
+    pub program: Pubkey,
+    pub name: [u8; 32],
+    pub enabled: bool,
+    pub kill_switch: bool,
+    pub revision: u32,
+    /// `None` when a kill switch was tripped
+    pub proposal_id: Option<Pubkey>,
+    pub updated_by: Pubkey,
+    pub updated_at: i64,
+}
+
 #[event]
 pub struct ProposalCancelledEvent {
     pub proposal_id: Pubkey,
     pub canceller: Pubkey,
+    pub reason: CancellationReason,
+    pub details: String,
+    pub votes: u8,
+}
+
+#[event]
+pub struct CancelVoteCastEvent {
+    pub proposal_id: Pubkey,
+    pub voter: Pubkey,
+    pub votes: u8,
+    pub required: u8,
+    pub reason: CancellationReason,
+}
+
+#[event]
+pub struct ExecutionBotUpdatedEvent {
+    pub authority: Pubkey,
+    pub execution_bot: Option<Pubkey>,
+}
+
+#[event]
+pub struct TimelockChangeProposedEvent {
+    pub proposal_id: Pubkey,
+    pub proposer: Pubkey,
+    pub previous_duration: i64,
+    pub timelock_duration: i64,
+    pub timelock_until: i64,
+    pub approval_digest: [u8; 32],
+}
+
+#[event]
+pub struct TimelockDurationUpdatedEvent {
+    pub proposal_id: Pubkey,
+    pub executor: Pubkey,
+    pub previous_duration: i64,
+    pub timelock_duration: i64,
+}
+
+#[event]
+pub struct OrganizationsUpdatedEvent {
+    pub authority: Pubkey,
+    pub member_organizations: Vec<u8>,
+    pub organization_quorum: u8,
+}
+
+#[event]
+pub struct PausableProgramRegisteredEvent {
+    pub authority: Pubkey,
+    pub program: Pubkey,
+    /// `false` when the program was removed
+    pub registered: bool,
+}
+
+#[event]
+pub struct EmergencyPauseVoteEvent {
+    pub program: Pubkey,
+    pub voter: Pubkey,
+    pub votes: u8,
+    pub required: u8,
+    pub reason: String,
+}
+
+#[event]
+pub struct EmergencyPauseExecutedEvent {
+    pub program: Pubkey,
+    pub voters: Vec<Pubkey>,
+    pub reason: String,
+    pub paused_at: i64,
+}
+
+#[event]
+pub struct MigrationProgressEvent {
+    pub migration_id: [u8; 16],
+    pub total: u64,
+    pub migrated: u64,
+    pub failed: u64,
 }
 
 #[event]
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+/// In-place data layout transformation for one account type and version step.
+///
+/// Each transform is compiled in behind its own cargo feature so a deployment
+/// only ships the migrations it actually needs.
+pub struct AccountTransform {
+    pub account_name: &'static str,
+    /// Anchor account discriminator: first 8 bytes of sha256("account:<Name>")
+    pub discriminator: [u8; 8],
+    pub from_version: u32,
+    pub to_version: u32,
+    /// New account data length given the current length
+    pub new_len: fn(usize) -> usize,
+    /// Rewrite `data` (already resized to `new_len`) from the old layout
+    pub apply: fn(data: &mut [u8], old_len: usize, now: i64) -> Result<()>,
+}
+
+/// All transforms enabled in this build
+pub fn registry() -> Vec<AccountTransform> {
+    #[allow(unused_mut)]
+    let mut transforms = Vec::new();
+
+    #[cfg(feature = "transform-user-account-v2")]
+    transforms.push(user_account_v1_to_v2());
+
+    transforms
+}
+
+/// Find the transform for an account's discriminator at its current version
+pub fn find_transform(discriminator: &[u8; 8], from_version: u32) -> Option<AccountTransform> {
+    registry()
+        .into_iter()
+        .find(|t| t.from_version == from_version && &t.discriminator == discriminator)
+}
+
+/// UserAccount v1 { owner: Pubkey, balance: u64 }
+///   -> v2 { owner: Pubkey, balance: u64, last_active: i64 }
+#[cfg(feature = "transform-user-account-v2")]
+fn user_account_v1_to_v2() -> AccountTransform {
+    use crate::UpgradeError;
+
+    const V1_LEN: usize = 8 + 32 + 8;
+
+    fn new_len(_old_len: usize) -> usize {
+        V1_LEN + 8
+    }
+
+    fn apply(data: &mut [u8], old_len: usize, now: i64) -> Result<()> {
+        require!(old_len == V1_LEN, UpgradeError::InvalidAccountData);
+        data[V1_LEN..V1_LEN + 8].copy_from_slice(&now.to_le_bytes());
+        Ok(())
+    }
+
+    AccountTransform {
+        account_name: "UserAccount",
+        discriminator: [211, 33, 136, 16, 186, 110, 242, 127],
+        from_version: 1,
+        to_version: 2,
+        new_len,
+        apply,
+    }
+}
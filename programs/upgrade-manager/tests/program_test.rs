@@ -0,0 +1,1297 @@
+//! solana-program-test suite for the upgrade-manager instruction logic.
+//!
+//! Loads the SBF build, so run `anchor build` first and point the loader at
+//! it: `SBF_OUT_DIR=../../target/deploy cargo test --test program_test`.
+//! Anchor 0.32 makes CPIs through `solana-invoke`, which has no off-chain
+//! syscall stubs, so the program cannot run as a native builtin.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use solana_program_test::{BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::{system_instruction, system_program, sysvar};
+use upgrade_manager::{
+    approval_digest, authority_change_digest, authority_target, default_emergency_quorum, flag_change_digest, flag_change_target,
+    migrate_hook_data, timelock_change_digest, timelock_target, CancellationReason, FeatureFlags, MigrateHookArgs, MigrationState, MultisigConfig, PauseRegistry, PauseVotes, ProgramMeta, ProgramUpgradeState, ProposalAction,
+    UpgradeError, UpgradeProposal, UpgradeStatus, EMERGENCY_VOTE_WINDOW, MAX_DESCRIPTION_LEN, MIGRATE_HOOK_DISCRIMINATOR, MAX_MEMBERS, MAX_TIMELOCK_DURATION, MIN_TIMELOCK_DURATION,
+};
+
+const TIMELOCK: i64 = 48 * 60 * 60;
+
+struct Env {
+    context: ProgramTestContext,
+    members: Vec<Keypair>,
+    program: Pubkey,
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &upgrade_manager::ID).0
+}
+
+fn multisig_config() -> Pubkey {
+    pda(&[b"multisig_config"])
+}
+
+fn program_upgrade_state() -> Pubkey {
+    pda(&[b"program_upgrade_state"])
+}
+
+fn proposal_address(program: &Pubkey, buffer: &Pubkey) -> Pubkey {
+    pda(&[b"proposal", program.as_ref(), buffer.as_ref()])
+}
+
+fn program_meta(program: &Pubkey) -> Pubkey {
+    pda(&[b"program_meta", program.as_ref()])
+}
+
+fn migration_state(migration_id: &[u8; 16]) -> Pubkey {
+    pda(&[b"migration_state", migration_id.as_ref()])
+}
+
+fn feature_flags(program: &Pubkey) -> Pubkey {
+    pda(&[b"feature_flags", program.as_ref()])
+}
+
+fn pause_registry() -> Pubkey {
+    pda(&[b"pause_registry"])
+}
+
+fn pause_votes(program: &Pubkey) -> Pubkey {
+    pda(&[b"pause_votes", program.as_ref()])
+}
+
+/// Zero-padded flag name
+fn flag(name: &str) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    bytes
+}
+
+/// Start a bank with upgrade-manager initialized for `member_count` funded members
+async fn setup(member_count: usize, threshold: u8) -> Env {
+    let program_test = ProgramTest::new("upgrade_manager", upgrade_manager::ID, None);
+    let mut context = program_test.start_with_context().await;
+    let members: Vec<Keypair> = (0..member_count).map(|_| Keypair::new()).collect();
+
+    let funding: Vec<Instruction> = members
+        .iter()
+        .map(|m| system_instruction::transfer(&context.payer.pubkey(), &m.pubkey(), 1_000_000_000))
+        .collect();
+    let payer = context.payer.insecure_clone();
+    send(&mut context, &payer, &funding).await.unwrap();
+
+    let initialize = initialize_instruction(&payer, members.iter().map(|m| m.pubkey()).collect(), threshold, TIMELOCK);
+    send(&mut context, &payer, &[initialize]).await.unwrap();
+
+    Env {
+        context,
+        members,
+        program: Pubkey::new_unique(),
+    }
+}
+
+fn initialize_instruction(authority: &Keypair, members: Vec<Pubkey>, threshold: u8, timelock_duration: i64) -> Instruction {
+    Instruction {
+        program_id: upgrade_manager::ID,
+        accounts: upgrade_manager::accounts::Initialize {
+            authority: authority.pubkey(),
+            multisig_config: multisig_config(),
+            program_upgrade_state: program_upgrade_state(),
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+        data: upgrade_manager::instruction::Initialize {
+            members,
+            threshold,
+            timelock_duration,
+        }
+        .data(),
+    }
+}
+
+async fn send(
+    context: &mut ProgramTestContext,
+    signer: &Keypair,
+    instructions: &[Instruction],
+) -> Result<(), BanksClientError> {
+    // A fresh blockhash keeps retried instructions from being deduplicated
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&signer.pubkey()), &[signer], blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn assert_program_error(result: Result<(), BanksClientError>, expected: UpgradeError) {
+    match result.expect_err("instruction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, u32::from(expected), "unexpected error code")
+        }
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+impl Env {
+    /// Write a loader buffer owned by `authority` at `buffer`
+    fn write_buffer(&mut self, buffer: Pubkey, authority: Pubkey) {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        let account = Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: bpf_loader_upgradeable::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(&buffer, &account.into());
+    }
+
+    async fn propose(&mut self, proposer: usize) -> Pubkey {
+        self.try_propose(proposer, "test upgrade".to_string(), None).await.unwrap()
+    }
+
+    async fn try_propose(
+        &mut self,
+        proposer: usize,
+        description: String,
+        description_hash: Option<[u8; 32]>,
+    ) -> Result<Pubkey, BanksClientError> {
+        let proposer = self.members[proposer].insecure_clone();
+        let buffer = Pubkey::new_unique();
+        // Handed to the upgrade authority, as the release pipeline does
+        let upgrade_authority = self.context.payer.pubkey();
+        self.write_buffer(buffer, upgrade_authority);
+        let proposal = proposal_address(&self.program, &buffer);
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ProposeUpgrade {
+                proposer: proposer.pubkey(),
+                multisig_config: multisig_config(),
+                program_upgrade_state: program_upgrade_state(),
+                program: self.program,
+                proposal,
+                new_program_buffer: buffer,
+                program_meta: program_meta(&self.program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ProposeUpgrade {
+                new_program_buffer: buffer,
+                description,
+                code_hash: [7; 32],
+                description_hash,
+            }
+            .data(),
+        };
+        send(&mut self.context, &proposer, &[ix]).await?;
+        Ok(proposal)
+    }
+
+    async fn approve(&mut self, signer: &Keypair, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let digest = self.proposal(proposal).await.approval_digest;
+        self.approve_with_digest(signer, proposal, digest).await
+    }
+
+    async fn approve_with_digest(
+        &mut self,
+        signer: &Keypair,
+        proposal: Pubkey,
+        approval_digest: [u8; 32],
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ApproveUpgrade {
+                approver: signer.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+                program_upgrade_state: program_upgrade_state(),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ApproveUpgrade {
+                _proposal_id: proposal,
+                approval_digest,
+            }
+            .data(),
+        };
+        send(&mut self.context, signer, &[ix]).await
+    }
+
+    async fn approve_member(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let signer = self.members[member].insecure_clone();
+        self.approve(&signer, proposal).await
+    }
+
+    async fn revoke_member(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let approver = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::RevokeApproval {
+                approver: approver.pubkey(),
+                proposal,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::RevokeApproval {
+                _proposal_id: proposal,
+            }
+            .data(),
+        };
+        send(&mut self.context, &approver, &[ix]).await
+    }
+
+    async fn execute(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let executor = self.members[member].insecure_clone();
+        let buffer = self.proposal(proposal).await.new_buffer;
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ExecuteUpgrade {
+                executor: executor.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+                program_upgrade_state: program_upgrade_state(),
+                program_meta: program_meta(&self.program),
+                buffer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ExecuteUpgrade { _proposal_id: proposal }.data(),
+        };
+        send(&mut self.context, &executor, &[ix]).await
+    }
+
+    async fn propose_authority_change(
+        &mut self,
+        proposer: usize,
+        new_authority: Option<Pubkey>,
+        nonce: u64,
+        confirm_immutable: bool,
+    ) -> Result<Pubkey, BanksClientError> {
+        let proposer = self.members[proposer].insecure_clone();
+        let proposal = proposal_address(&self.program, &authority_target(new_authority.as_ref(), nonce));
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ProposeAuthorityChange {
+                proposer: proposer.pubkey(),
+                multisig_config: multisig_config(),
+                program_upgrade_state: program_upgrade_state(),
+                program: self.program,
+                proposal,
+                program_meta: program_meta(&self.program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ProposeAuthorityChange {
+                new_authority,
+                nonce,
+                description: "hand over authority".to_string(),
+                confirm_immutable,
+            }
+            .data(),
+        };
+        send(&mut self.context, &proposer, &[ix]).await.map(|_| proposal)
+    }
+
+    async fn execute_authority_change(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let executor = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ExecuteAuthorityChange {
+                executor: executor.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+                program_upgrade_state: program_upgrade_state(),
+                program_meta: program_meta(&self.program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ExecuteAuthorityChange { _proposal_id: proposal }.data(),
+        };
+        send(&mut self.context, &executor, &[ix]).await
+    }
+
+    async fn propose_flag_change(
+        &mut self,
+        proposer: usize,
+        name: [u8; 32],
+        enabled: bool,
+        kill_switch: bool,
+        revision: u32,
+    ) -> Result<Pubkey, BanksClientError> {
+        let proposer = self.members[proposer].insecure_clone();
+        let proposal = proposal_address(&self.program, &flag_change_target(&name, enabled, kill_switch, revision));
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ProposeFlagChange {
+                proposer: proposer.pubkey(),
+                multisig_config: multisig_config(),
+                program_upgrade_state: program_upgrade_state(),
+                program: self.program,
+                proposal,
+                feature_flags: feature_flags(&self.program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ProposeFlagChange {
+                name,
+                enabled,
+                kill_switch,
+                revision,
+                description: "flip flag".to_string(),
+            }
+            .data(),
+        };
+        send(&mut self.context, &proposer, &[ix]).await.map(|_| proposal)
+    }
+
+    async fn set_flag(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let executor = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::SetFlag {
+                executor: executor.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+                program_upgrade_state: program_upgrade_state(),
+                feature_flags: feature_flags(&self.program),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::SetFlag { _proposal_id: proposal }.data(),
+        };
+        send(&mut self.context, &executor, &[ix]).await
+    }
+
+    /// Propose, approve with member 1 and apply a flag change once the timelock ends
+    async fn change_flag(&mut self, name: [u8; 32], enabled: bool, kill_switch: bool) {
+        let revision = self.flags().await.map_or(0, |flags| flags.revision(&name));
+        let proposal = self.propose_flag_change(0, name, enabled, kill_switch, revision).await.unwrap();
+        self.approve_member(1, proposal).await.unwrap();
+        let timelock_until = self.proposal(proposal).await.timelock_until;
+        self.set_time(timelock_until).await;
+        self.set_flag(0, proposal).await.unwrap();
+    }
+
+    async fn trip_kill_switch(&mut self, member: usize, name: [u8; 32]) -> Result<(), BanksClientError> {
+        let member = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::TripKillSwitch {
+                member: member.pubkey(),
+                multisig_config: multisig_config(),
+                feature_flags: feature_flags(&self.program),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::TripKillSwitch { name }.data(),
+        };
+        send(&mut self.context, &member, &[ix]).await
+    }
+
+    async fn flags(&mut self) -> Option<FeatureFlags> {
+        let address = feature_flags(&self.program);
+        let account = self.context.banks_client.get_account(address).await.unwrap()?;
+        Some(FeatureFlags::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    async fn register_pausable(&mut self, signer: &Keypair, pause_accounts: Vec<Pubkey>) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::RegisterPausableProgram {
+                authority: signer.pubkey(),
+                multisig_config: multisig_config(),
+                pause_registry: pause_registry(),
+                program: self.program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::RegisterPausableProgram {
+                pause_data: vec![1],
+                pause_accounts,
+            }
+            .data(),
+        };
+        send(&mut self.context, signer, &[ix]).await
+    }
+
+    async fn set_emergency_quorum(&mut self, emergency_quorum: u8) -> Result<(), BanksClientError> {
+        let authority = self.context.payer.insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::UpdatePauseRegistry {
+                authority: authority.pubkey(),
+                multisig_config: multisig_config(),
+                pause_registry: pause_registry(),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::SetEmergencyQuorum { emergency_quorum }.data(),
+        };
+        send(&mut self.context, &authority, &[ix]).await
+    }
+
+    async fn emergency_pause(&mut self, member: usize, reason: &str, pause_accounts: &[Pubkey]) -> Result<(), BanksClientError> {
+        let voter = self.members[member].insecure_clone();
+        let mut accounts = upgrade_manager::accounts::EmergencyPause {
+            voter: voter.pubkey(),
+            multisig_config: multisig_config(),
+            pause_registry: pause_registry(),
+            pause_votes: pause_votes(&self.program),
+            program: self.program,
+            pause_authority: pda(&[b"pause_authority"]),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(pause_accounts.iter().map(|key| AccountMeta::new(*key, false)));
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts,
+            data: upgrade_manager::instruction::EmergencyPause { reason: reason.to_string() }.data(),
+        };
+        send(&mut self.context, &voter, &[ix]).await
+    }
+
+    async fn cancel(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let canceller = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::CancelUpgrade {
+                canceller: canceller.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::CancelUpgrade {
+                _proposal_id: proposal,
+                reason: CancellationReason::SecurityIssue,
+                details: "test".to_string(),
+            }
+            .data(),
+        };
+        send(&mut self.context, &canceller, &[ix]).await
+    }
+
+    async fn set_organizations(&mut self, member_organizations: Vec<u8>, organization_quorum: u8) -> Result<(), BanksClientError> {
+        let authority = self.context.payer.insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::SetMemberOrganizations {
+                authority: authority.pubkey(),
+                multisig_config: multisig_config(),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::SetMemberOrganizations {
+                member_organizations,
+                organization_quorum,
+            }
+            .data(),
+        };
+        send(&mut self.context, &authority, &[ix]).await
+    }
+
+    async fn propose_timelock_change(
+        &mut self,
+        proposer: usize,
+        timelock_duration: i64,
+        nonce: u64,
+    ) -> Result<Pubkey, BanksClientError> {
+        let proposer = self.members[proposer].insecure_clone();
+        let proposal = proposal_address(&upgrade_manager::ID, &timelock_target(timelock_duration, nonce));
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::ProposeTimelockChange {
+                proposer: proposer.pubkey(),
+                multisig_config: multisig_config(),
+                program_upgrade_state: program_upgrade_state(),
+                proposal,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::ProposeTimelockChange {
+                timelock_duration,
+                nonce,
+                description: "longer review window".to_string(),
+            }
+            .data(),
+        };
+        send(&mut self.context, &proposer, &[ix]).await.map(|_| proposal)
+    }
+
+    async fn set_timelock_duration(&mut self, member: usize, proposal: Pubkey) -> Result<(), BanksClientError> {
+        let executor = self.members[member].insecure_clone();
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::SetTimelockDuration {
+                executor: executor.pubkey(),
+                multisig_config: multisig_config(),
+                proposal,
+                program_upgrade_state: program_upgrade_state(),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::SetTimelockDuration { _proposal_id: proposal }.data(),
+        };
+        send(&mut self.context, &executor, &[ix]).await
+    }
+
+    async fn start_migration(&mut self, signer: &Keypair, migration_id: [u8; 16], total: u64) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::StartMigration {
+                authority: signer.pubkey(),
+                multisig_config: multisig_config(),
+                migration_state: migration_state(&migration_id),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::StartMigration { migration_id, total }.data(),
+        };
+        send(&mut self.context, signer, &[ix]).await
+    }
+
+    async fn record_migration_failures(
+        &mut self,
+        signer: &Keypair,
+        migration_id: [u8; 16],
+        failed: u64,
+    ) -> Result<(), BanksClientError> {
+        let ix = Instruction {
+            program_id: upgrade_manager::ID,
+            accounts: upgrade_manager::accounts::RecordMigrationFailures {
+                authority: signer.pubkey(),
+                migration_state: migration_state(&migration_id),
+            }
+            .to_account_metas(None),
+            data: upgrade_manager::instruction::RecordMigrationFailures { failed }.data(),
+        };
+        send(&mut self.context, signer, &[ix]).await
+    }
+
+    async fn account<T: AccountDeserialize>(&mut self, address: Pubkey) -> T {
+        let account = self.context.banks_client.get_account(address).await.unwrap().unwrap();
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    async fn proposal(&mut self, address: Pubkey) -> UpgradeProposal {
+        self.account(address).await
+    }
+
+    async fn set_time(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Propose from member 0 and approve with members 1..threshold
+    async fn approved_proposal(&mut self, threshold: usize) -> Pubkey {
+        let proposal = self.propose(0).await;
+        for member in 1..threshold {
+            self.approve_member(member, proposal).await.unwrap();
+        }
+        proposal
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_stores_config() {
+    let mut env = setup(3, 2).await;
+
+    let config: MultisigConfig = env.account(multisig_config()).await;
+
+    assert_eq!(config.members, env.members.iter().map(|m| m.pubkey()).collect::<Vec<_>>());
+    assert_eq!(config.threshold, 2);
+    assert_eq!(config.execution_bot, None);
+}
+
+#[tokio::test]
+async fn test_proposer_counts_as_first_approval() {
+    let mut env = setup(3, 2).await;
+
+    let proposal = env.propose(0).await;
+    let state = env.proposal(proposal).await;
+
+    assert!(state.status == UpgradeStatus::Proposed);
+    assert_eq!(state.approvals, vec![env.members[0].pubkey()]);
+    assert_eq!(state.approval_threshold, 2);
+}
+
+#[tokio::test]
+async fn test_duplicate_approval_rejected() {
+    let mut env = setup(3, 3).await;
+    let proposal = env.propose(0).await;
+
+    assert_program_error(env.approve_member(0, proposal).await, UpgradeError::AlreadyApproved);
+
+    env.approve_member(1, proposal).await.unwrap();
+    assert_program_error(env.approve_member(1, proposal).await, UpgradeError::AlreadyApproved);
+    assert_eq!(env.proposal(proposal).await.approvals.len(), 2);
+}
+
+#[tokio::test]
+async fn test_non_member_rejected() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.propose(0).await;
+    let outsider = Keypair::new();
+    let payer = env.context.payer.insecure_clone();
+    send(
+        &mut env.context,
+        &payer,
+        &[system_instruction::transfer(&payer.pubkey(), &outsider.pubkey(), 1_000_000_000)],
+    )
+    .await
+    .unwrap();
+
+    assert_program_error(env.approve(&outsider, proposal).await, UpgradeError::NotMultisigMember);
+    assert_eq!(env.proposal(proposal).await.approvals.len(), 1);
+}
+
+#[tokio::test]
+async fn test_threshold_reached_exactly_starts_timelock() {
+    let mut env = setup(5, 3).await;
+    let proposal = env.propose(0).await;
+
+    // One short of the threshold
+    env.approve_member(1, proposal).await.unwrap();
+    let state = env.proposal(proposal).await;
+    assert!(state.status == UpgradeStatus::Approved);
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::InvalidProposalStatus);
+
+    env.approve_member(2, proposal).await.unwrap();
+    let state = env.proposal(proposal).await;
+    assert!(state.status == UpgradeStatus::TimelockActive);
+    assert_eq!(state.approvals.len(), 3);
+
+    // Approvals past the threshold are refused once the timelock runs
+    assert_program_error(env.approve_member(3, proposal).await, UpgradeError::InvalidProposalStatus);
+}
+
+#[tokio::test]
+async fn test_approval_revoked_below_threshold() {
+    let mut env = setup(5, 3).await;
+    let proposal = env.propose(0).await;
+    env.approve_member(1, proposal).await.unwrap();
+
+    env.revoke_member(1, proposal).await.unwrap();
+    let state = env.proposal(proposal).await;
+    assert!(state.status == UpgradeStatus::Proposed);
+    assert_eq!(state.approvals, vec![env.members[0].pubkey()]);
+
+    // Nothing left to revoke, but the member may approve again
+    assert_program_error(env.revoke_member(1, proposal).await, UpgradeError::NotApproved);
+    assert_program_error(env.revoke_member(2, proposal).await, UpgradeError::NotApproved);
+    env.approve_member(1, proposal).await.unwrap();
+    env.approve_member(2, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::TimelockActive);
+}
+
+#[tokio::test]
+async fn test_approval_cannot_be_revoked_after_threshold() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.approved_proposal(2).await;
+
+    assert_program_error(env.revoke_member(1, proposal).await, UpgradeError::InvalidProposalStatus);
+    assert_eq!(env.proposal(proposal).await.approvals.len(), 2);
+}
+
+#[tokio::test]
+async fn test_threshold_equal_to_member_count_needs_everyone() {
+    let mut env = setup(3, 3).await;
+    let proposal = env.propose(0).await;
+
+    env.approve_member(1, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Approved);
+
+    env.approve_member(2, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::TimelockActive);
+}
+
+#[tokio::test]
+async fn test_timelock_boundary() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.approved_proposal(2).await;
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+
+    env.set_time(timelock_until - 1).await;
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::TimelockActive);
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::TimelockActive);
+
+    env.set_time(timelock_until).await;
+    env.execute(0, proposal).await.unwrap();
+
+    let state = env.proposal(proposal).await;
+    assert!(state.status == UpgradeStatus::Executed);
+    assert_eq!(state.executed_at, Some(timelock_until));
+
+    let meta: ProgramMeta = env.account(program_meta(&env.program)).await;
+    assert_eq!(meta.version, 1);
+    assert_eq!(meta.last_proposal, proposal);
+    assert_eq!(meta.code_hash, [7; 32]);
+}
+
+#[tokio::test]
+async fn test_executed_proposal_cannot_run_twice() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.approved_proposal(2).await;
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+    env.set_time(timelock_until).await;
+    env.execute(0, proposal).await.unwrap();
+
+    assert_program_error(env.execute(1, proposal).await, UpgradeError::InvalidProposalStatus);
+    assert_program_error(env.cancel(1, proposal).await, UpgradeError::CannotCancelExecuted);
+}
+
+#[tokio::test]
+async fn test_cancelled_proposal_cannot_execute() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.approved_proposal(2).await;
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+
+    // Cancelling during the timelock takes threshold + 1 votes
+    env.cancel(0, proposal).await.unwrap();
+    env.cancel(1, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::TimelockActive);
+    env.cancel(2, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Cancelled);
+
+    env.set_time(timelock_until).await;
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::InvalidProposalStatus);
+    assert_program_error(env.approve_member(0, proposal).await, UpgradeError::InvalidProposalStatus);
+}
+
+#[tokio::test]
+async fn test_withdrawn_proposal_cannot_be_approved() {
+    let mut env = setup(3, 3).await;
+    let proposal = env.propose(0).await;
+
+    env.cancel(0, proposal).await.unwrap();
+
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Cancelled);
+    assert_program_error(env.approve_member(1, proposal).await, UpgradeError::InvalidProposalStatus);
+}
+
+/// Known-answer digest, shared with `backend/tests/approval_digest_test.rs`
+/// so both sides are held to the same field order
+const APPROVAL_DIGEST_VECTOR: &str = "cb5e7460c9d7f8e9248dc41b8bede8800633a7557496a466699c93bb2d743a4e";
+
+#[test]
+fn test_approval_digest_matches_backend_vector() {
+    let digest = approval_digest(
+        &Pubkey::new_from_array([1; 32]),
+        &Pubkey::new_from_array([2; 32]),
+        &[3; 32],
+        7,
+        TIMELOCK,
+    );
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    assert_eq!(hex, APPROVAL_DIGEST_VECTOR);
+}
+
+#[tokio::test]
+async fn test_proposal_commits_to_approval_digest() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.propose(0).await;
+    let state = env.proposal(proposal).await;
+
+    assert_eq!(state.target_version, 1);
+    assert_eq!(
+        state.approval_digest,
+        approval_digest(&env.program, &state.new_buffer, &[7; 32], 1, TIMELOCK)
+    );
+
+    // A digest over anything else, e.g. a different binary, is refused
+    let blind = approval_digest(&env.program, &state.new_buffer, &[8; 32], 1, TIMELOCK);
+    let member = env.members[1].insecure_clone();
+    assert_program_error(
+        env.approve_with_digest(&member, proposal, blind).await,
+        UpgradeError::ApprovalDigestMismatch,
+    );
+    assert_eq!(env.proposal(proposal).await.approvals.len(), 1);
+}
+
+#[tokio::test]
+async fn test_approvals_go_stale_when_version_moves() {
+    let mut env = setup(3, 2).await;
+    let first = env.approved_proposal(2).await;
+    let second = env.approved_proposal(2).await;
+    assert_eq!(env.proposal(second).await.target_version, 1);
+
+    let timelock_until = env.proposal(second).await.timelock_until;
+    env.set_time(timelock_until).await;
+    env.execute(0, first).await.unwrap();
+
+    // `second` was approved against version 1, which now exists
+    assert_program_error(env.execute(0, second).await, UpgradeError::StaleApprovalDigest);
+
+    let third = env.propose(1).await;
+    assert_eq!(env.proposal(third).await.target_version, 2);
+}
+
+#[tokio::test]
+async fn test_organization_quorum_blocks_single_org_threshold() {
+    let mut env = setup(4, 2).await;
+    // Members 0 and 1 belong to the same organization
+    env.set_organizations(vec![0, 0, 1, 2], 2).await.unwrap();
+    let proposal = env.propose(0).await;
+    assert_eq!(env.proposal(proposal).await.organization_quorum, 2);
+
+    // Two keys of one organization meet the threshold but not the quorum
+    env.approve_member(1, proposal).await.unwrap();
+    let state = env.proposal(proposal).await;
+    assert!(state.status == UpgradeStatus::Approved);
+    env.set_time(state.timelock_until).await;
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::InsufficientOrganizations);
+
+    env.approve_member(2, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::TimelockActive);
+}
+
+#[tokio::test]
+async fn test_organization_config_validated() {
+    let mut env = setup(3, 2).await;
+
+    // One organization per member, and no more organizations than exist
+    assert_program_error(env.set_organizations(vec![0, 1], 1).await, UpgradeError::InvalidOrganizationConfig);
+    assert_program_error(env.set_organizations(vec![0, 0, 1], 3).await, UpgradeError::InvalidOrganizationConfig);
+
+    env.set_organizations(vec![0, 0, 1], 2).await.unwrap();
+    let config: MultisigConfig = env.account(multisig_config()).await;
+    assert_eq!(config.member_organizations, vec![0, 0, 1]);
+    assert_eq!(config.organization_quorum, 2);
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_out_of_range_timelock() {
+    let program_test = ProgramTest::new("upgrade_manager", upgrade_manager::ID, None);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    let members = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    for timelock_duration in [0, MIN_TIMELOCK_DURATION - 1, MAX_TIMELOCK_DURATION + 1] {
+        let ix = initialize_instruction(&payer, members.clone(), 2, timelock_duration);
+        assert_program_error(send(&mut context, &payer, &[ix]).await, UpgradeError::InvalidTimelockDuration);
+    }
+
+    let ix = initialize_instruction(&payer, members, 2, MIN_TIMELOCK_DURATION);
+    send(&mut context, &payer, &[ix]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_initialize_validates_members_and_threshold() {
+    let program_test = ProgramTest::new("upgrade_manager", upgrade_manager::ID, None);
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    let members: Vec<Pubkey> = (0..MAX_MEMBERS).map(|_| Pubkey::new_unique()).collect();
+
+    let too_many = [members.clone(), vec![Pubkey::new_unique()]].concat();
+    let duplicated = vec![members[0], members[1], members[0]];
+    for (members, threshold, expected) in [
+        (vec![], 2, UpgradeError::NoMembers),
+        (too_many, 2, UpgradeError::TooManyMembers),
+        (duplicated, 2, UpgradeError::DuplicateMember),
+        (members[..3].to_vec(), 1, UpgradeError::ThresholdTooLow),
+        (members[..3].to_vec(), 4, UpgradeError::ThresholdExceedsMembers),
+    ] {
+        let ix = initialize_instruction(&payer, members, threshold, TIMELOCK);
+        assert_program_error(send(&mut context, &payer, &[ix]).await, expected);
+    }
+
+    // The largest multisig fits the config account
+    let ix = initialize_instruction(&payer, members.clone(), MAX_MEMBERS as u8, TIMELOCK);
+    send(&mut context, &payer, &[ix]).await.unwrap();
+    let account = context.banks_client.get_account(multisig_config()).await.unwrap().unwrap();
+    let config = MultisigConfig::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(config.members, members);
+}
+
+#[tokio::test]
+async fn test_timelock_change_runs_the_full_approval_flow() {
+    let mut env = setup(3, 2).await;
+    assert_program_error(
+        env.propose_timelock_change(0, 60, 0).await.map(|_| ()),
+        UpgradeError::InvalidTimelockDuration,
+    );
+    assert_program_error(
+        env.propose_timelock_change(0, TIMELOCK, 0).await.map(|_| ()),
+        UpgradeError::TimelockUnchanged,
+    );
+    let proposal = env.propose_timelock_change(0, TIMELOCK * 2, 0).await.unwrap();
+    let state = env.proposal(proposal).await;
+    assert!(state.action == ProposalAction::SetTimelock { timelock_duration: TIMELOCK * 2 });
+    assert_eq!(state.approval_digest, timelock_change_digest(TIMELOCK * 2, TIMELOCK));
+
+    // A single member cannot change it; it needs the threshold and the
+    // current timelock like any upgrade
+    env.set_time(state.timelock_until).await;
+    assert_program_error(env.set_timelock_duration(0, proposal).await, UpgradeError::InsufficientApprovals);
+    env.approve_member(1, proposal).await.unwrap();
+    assert_program_error(env.set_timelock_duration(0, proposal).await, UpgradeError::TimelockActive);
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+    env.set_time(timelock_until).await;
+    env.set_timelock_duration(0, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Executed);
+
+    let state: ProgramUpgradeState = env.account(program_upgrade_state()).await;
+    assert_eq!(state.timelock_duration, TIMELOCK * 2);
+
+    // New proposals pick up the new duration
+    env.set_time(1_700_000_000).await;
+    let proposal = env.propose(0).await;
+    assert_eq!(env.proposal(proposal).await.timelock_until, 1_700_000_000 + TIMELOCK * 2);
+}
+
+#[tokio::test]
+async fn test_description_length_bounded() {
+    let mut env = setup(3, 2).await;
+
+    let result = env.try_propose(0, "x".repeat(MAX_DESCRIPTION_LEN + 1), None).await;
+    assert_program_error(result.map(|_| ()), UpgradeError::DescriptionTooLong);
+
+    // The longest description fits the account, with the hash of the full text
+    let proposal = env
+        .try_propose(0, "x".repeat(MAX_DESCRIPTION_LEN), Some([9; 32]))
+        .await
+        .unwrap();
+    let state = env.proposal(proposal).await;
+    assert_eq!(state.description.len(), MAX_DESCRIPTION_LEN);
+    assert_eq!(state.description_hash, Some([9; 32]));
+}
+
+#[tokio::test]
+async fn test_buffer_must_be_handed_to_upgrade_authority() {
+    let mut env = setup(3, 2).await;
+    let proposal = env.approved_proposal(2).await;
+    let state = env.proposal(proposal).await;
+    env.set_time(state.timelock_until).await;
+
+    // The deployer took the buffer back after approval
+    let deployer = env.members[0].pubkey();
+    env.write_buffer(state.new_buffer, deployer);
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::BufferAuthorityNotTransferred);
+
+    let upgrade_authority = env.context.payer.pubkey();
+    env.write_buffer(state.new_buffer, upgrade_authority);
+    env.execute(0, proposal).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_migration_state_tracks_progress() {
+    let mut env = setup(3, 2).await;
+    let migration_id = [9; 16];
+    let runner = env.members[0].insecure_clone();
+
+    env.start_migration(&runner, migration_id, 120).await.unwrap();
+    let state: MigrationState = env.account(migration_state(&migration_id)).await;
+    assert_eq!(state.authority, runner.pubkey());
+    assert_eq!((state.total, state.migrated, state.failed), (120, 0, 0));
+
+    env.record_migration_failures(&runner, migration_id, 3).await.unwrap();
+    let state: MigrationState = env.account(migration_state(&migration_id)).await;
+    assert_eq!(state.failed, 3);
+
+    // Only the key that started the migration reports on it
+    let other = env.members[1].insecure_clone();
+    assert_program_error(
+        env.record_migration_failures(&other, migration_id, 0).await,
+        UpgradeError::NotMigrationAuthority,
+    );
+}
+
+#[tokio::test]
+async fn test_migration_state_needs_an_executor() {
+    let mut env = setup(3, 2).await;
+    let outsider = Keypair::new();
+    let payer = env.context.payer.insecure_clone();
+    send(
+        &mut env.context,
+        &payer,
+        &[system_instruction::transfer(&payer.pubkey(), &outsider.pubkey(), 1_000_000_000)],
+    )
+    .await
+    .unwrap();
+
+    assert_program_error(
+        env.start_migration(&outsider, [1; 16], 10).await,
+        UpgradeError::UnauthorizedExecutor,
+    );
+}
+
+#[test]
+fn test_migrate_hook_is_an_anchor_instruction() {
+    let sighash = solana_sdk::hash::hash(b"global:upgrade_manager_migrate");
+    assert_eq!(MIGRATE_HOOK_DISCRIMINATOR, sighash.to_bytes()[..8]);
+
+    let args = MigrateHookArgs { from_version: 1, to_version: 2, new_len: 56, migrated_at: 1_700_000_000 };
+    let data = migrate_hook_data(&args).unwrap();
+    assert_eq!(data[..8], MIGRATE_HOOK_DISCRIMINATOR);
+    assert_eq!(data[8..12], 1u32.to_le_bytes());
+    assert_eq!(data[12..16], 2u32.to_le_bytes());
+    assert_eq!(data[16..24], 56u64.to_le_bytes());
+    assert_eq!(data[24..], 1_700_000_000i64.to_le_bytes());
+}
+
+#[tokio::test]
+async fn test_immutability_must_be_confirmed() {
+    let mut env = setup(3, 2).await;
+
+    assert_program_error(
+        env.propose_authority_change(0, None, 0, false).await.map(|_| ()),
+        UpgradeError::ImmutabilityNotConfirmed,
+    );
+    let proposal = env.propose_authority_change(0, None, 0, true).await.unwrap();
+
+    let state = env.proposal(proposal).await;
+    assert!(state.action == ProposalAction::SetAuthority { new_authority: None });
+    assert_eq!(state.approval_digest, authority_change_digest(&env.program, None, TIMELOCK));
+}
+
+#[tokio::test]
+async fn test_authority_change_runs_the_full_approval_flow() {
+    let mut env = setup(3, 2).await;
+    let vault = Pubkey::new_unique();
+    let proposal = env.propose_authority_change(0, Some(vault), 0, false).await.unwrap();
+
+    // Needs the threshold and the timelock like any upgrade
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+    env.set_time(timelock_until).await;
+    assert_program_error(env.execute_authority_change(0, proposal).await, UpgradeError::InsufficientApprovals);
+    env.approve_member(1, proposal).await.unwrap();
+    assert_program_error(env.execute_authority_change(0, proposal).await, UpgradeError::TimelockActive);
+
+    let state = env.proposal(proposal).await;
+    env.set_time(state.timelock_until).await;
+    // Not something execute_upgrade can run
+    assert_program_error(env.execute(0, proposal).await, UpgradeError::WrongProposalAction);
+    env.execute_authority_change(0, proposal).await.unwrap();
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Executed);
+
+    // Buffers for this program must now be handed to the new authority; the
+    // config's authority still covers the other managed programs
+    let meta: ProgramMeta = env.account(program_meta(&env.program)).await;
+    assert_eq!(meta.upgrade_authority, Some(vault));
+    assert!(!meta.immutable);
+    let config: MultisigConfig = env.account(multisig_config()).await;
+    assert_eq!(config.upgrade_authority, env.context.payer.pubkey());
+}
+
+#[tokio::test]
+async fn test_authority_change_can_be_proposed_again_with_a_new_nonce() {
+    let mut env = setup(3, 2).await;
+    let vault = Pubkey::new_unique();
+    let cancelled = env.propose_authority_change(0, Some(vault), 0, false).await.unwrap();
+    env.cancel(0, cancelled).await.unwrap();
+
+    // The cancelled proposal still holds its address
+    assert!(env.propose_authority_change(0, Some(vault), 0, false).await.is_err());
+    let proposal = env.propose_authority_change(0, Some(vault), 1, false).await.unwrap();
+    assert_ne!(proposal, cancelled);
+    assert!(env.proposal(proposal).await.status == UpgradeStatus::Proposed);
+}
+
+#[tokio::test]
+async fn test_immutable_program_cannot_be_upgraded() {
+    let mut env = setup(3, 2).await;
+    let pending = env.approved_proposal(2).await;
+    let proposal = env.propose_authority_change(0, None, 0, true).await.unwrap();
+    env.approve_member(1, proposal).await.unwrap();
+
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+    env.set_time(timelock_until).await;
+    env.execute_authority_change(0, proposal).await.unwrap();
+
+    let meta: ProgramMeta = env.account(program_meta(&env.program)).await;
+    assert!(meta.immutable);
+    assert_eq!(meta.version, 0);
+    // The upgrade authority is kept for the other managed programs
+    let config: MultisigConfig = env.account(multisig_config()).await;
+    assert_eq!(config.upgrade_authority, env.context.payer.pubkey());
+
+    assert_program_error(
+        env.try_propose(0, "after immutability".to_string(), None).await.map(|_| ()),
+        UpgradeError::ProgramImmutable,
+    );
+    assert_program_error(
+        env.propose_authority_change(0, Some(Pubkey::new_unique()), 0, false).await.map(|_| ()),
+        UpgradeError::ProgramImmutable,
+    );
+    // Nor can an upgrade approved before it executes
+    assert_program_error(env.execute(0, pending).await, UpgradeError::ProgramImmutable);
+}
+
+#[test]
+fn test_authority_target_depends_on_the_nonce() {
+    let vault = Pubkey::new_from_array([4; 32]);
+
+    assert_eq!(authority_target(Some(&vault), 0), authority_target(Some(&vault), 0));
+    assert_ne!(authority_target(Some(&vault), 0), authority_target(Some(&vault), 1));
+    assert_ne!(authority_target(None, 0), authority_target(Some(&Pubkey::default()), 0));
+    // The address no longer is the authority itself
+    assert_ne!(authority_target(Some(&vault), 0), vault);
+}
+
+#[tokio::test]
+async fn test_authority_change_to_current_authority_rejected() {
+    let mut env = setup(3, 2).await;
+    let current = env.context.payer.pubkey();
+
+    assert_program_error(
+        env.propose_authority_change(0, Some(current), 0, false).await.map(|_| ()),
+        UpgradeError::AuthorityUnchanged,
+    );
+}
+
+#[tokio::test]
+async fn test_flag_change_runs_the_full_approval_flow() {
+    let mut env = setup(3, 2).await;
+    let name = flag("leveraged_orders");
+    let proposal = env.propose_flag_change(0, name, true, false, 0).await.unwrap();
+
+    let state = env.proposal(proposal).await;
+    assert!(state.action == ProposalAction::SetFlag { name, enabled: true, kill_switch: false });
+    assert_eq!(state.approval_digest, flag_change_digest(&env.program, &name, true, false, 0, TIMELOCK));
+
+    env.set_time(state.timelock_until).await;
+    assert_program_error(env.set_flag(0, proposal).await, UpgradeError::InsufficientApprovals);
+    env.approve_member(1, proposal).await.unwrap();
+    assert_program_error(env.set_flag(0, proposal).await, UpgradeError::TimelockActive);
+
+    let timelock_until = env.proposal(proposal).await.timelock_until;
+    env.set_time(timelock_until).await;
+    assert_program_error(env.execute_authority_change(0, proposal).await, UpgradeError::WrongProposalAction);
+    env.set_flag(0, proposal).await.unwrap();
+
+    let flags = env.flags().await.unwrap();
+    assert_eq!(flags.program, env.program);
+    assert!(flags.is_enabled(&name));
+    assert_eq!(flags.revision(&name), 1);
+    assert!(!flags.is_enabled(&flag("unknown")));
+}
+
+#[tokio::test]
+async fn test_flag_change_commits_to_the_flag_revision() {
+    let mut env = setup(3, 2).await;
+    let name = flag("leveraged_orders");
+
+    assert_program_error(
+        env.propose_flag_change(0, name, true, false, 1).await.map(|_| ()),
+        UpgradeError::StaleFlagRevision,
+    );
+
+    // Two changes proposed against the same revision; only the first can land
+    let first = env.propose_flag_change(0, name, true, false, 0).await.unwrap();
+    let second = env.propose_flag_change(0, name, true, true, 0).await.unwrap();
+    env.approve_member(1, first).await.unwrap();
+    env.approve_member(1, second).await.unwrap();
+    let timelock_until = env.proposal(second).await.timelock_until;
+    env.set_time(timelock_until).await;
+
+    env.set_flag(0, first).await.unwrap();
+    assert_program_error(env.set_flag(0, second).await, UpgradeError::StaleFlagRevision);
+}
+
+#[tokio::test]
+async fn test_kill_switch_trips_without_approvals() {
+    let mut env = setup(3, 2).await;
+    let guarded = flag("leveraged_orders");
+    let plain = flag("new_fee_tiers");
+    env.change_flag(guarded, true, true).await;
+    env.change_flag(plain, true, false).await;
+
+    assert_program_error(env.trip_kill_switch(2, plain).await, UpgradeError::NotKillSwitch);
+    assert_program_error(env.trip_kill_switch(2, flag("unknown")).await, UpgradeError::UnknownFeatureFlag);
+
+    // Any single member, no timelock
+    env.trip_kill_switch(2, guarded).await.unwrap();
+    let flags = env.flags().await.unwrap();
+    assert!(!flags.is_enabled(&guarded));
+    assert!(flags.get(&guarded).unwrap().kill_switch);
+    assert_eq!(flags.revision(&guarded), 2);
+    assert!(flags.is_enabled(&plain));
+}
+
+#[tokio::test]
+async fn test_pause_registry_managed_by_upgrade_authority() {
+    let mut env = setup(4, 4).await;
+    let stranger = env.members[0].insecure_clone();
+    assert_program_error(env.register_pausable(&stranger, vec![]).await, UpgradeError::NotUpgradeAuthority);
+
+    let authority = env.context.payer.insecure_clone();
+    assert_program_error(
+        env.register_pausable(&authority, vec![Pubkey::new_unique(); 5]).await,
+        UpgradeError::InvalidPauseInstruction,
+    );
+    env.register_pausable(&authority, vec![Pubkey::new_unique()]).await.unwrap();
+
+    let registry: PauseRegistry = env.account(pause_registry()).await;
+    assert_eq!(registry.emergency_quorum, 2, "half the threshold by default");
+    assert_eq!(registry.programs.len(), 1);
+    assert_eq!(registry.programs[0].program, env.program);
+    assert_eq!(registry.programs[0].paused_at, None);
+
+    assert_program_error(env.set_emergency_quorum(0).await, UpgradeError::InvalidEmergencyQuorum);
+    assert_program_error(env.set_emergency_quorum(1).await, UpgradeError::InvalidEmergencyQuorum);
+    assert_program_error(env.set_emergency_quorum(5).await, UpgradeError::InvalidEmergencyQuorum);
+    env.set_emergency_quorum(3).await.unwrap();
+}
+
+#[test]
+fn test_emergency_quorum_never_lets_one_member_pause() {
+    assert_eq!(default_emergency_quorum(1, 3), 2);
+    assert_eq!(default_emergency_quorum(2, 3), 2);
+    assert_eq!(default_emergency_quorum(5, 7), 3);
+    // Capped at the members there are
+    assert_eq!(default_emergency_quorum(1, 1), 1);
+}
+
+#[tokio::test]
+async fn test_emergency_pause_votes_until_quorum() {
+    let mut env = setup(4, 4).await;
+    let authority = env.context.payer.insecure_clone();
+    let vault = Pubkey::new_unique();
+
+    env.register_pausable(&authority, vec![vault]).await.unwrap();
+    assert_program_error(env.emergency_pause(0, " ", &[vault]).await, UpgradeError::InvalidPauseReason);
+
+    env.emergency_pause(0, "exploit in settlement", &[vault]).await.unwrap();
+    assert_program_error(env.emergency_pause(0, "exploit in settlement", &[vault]).await, UpgradeError::AlreadyVotedToPause);
+
+    let votes: PauseVotes = env.account(pause_votes(&env.program)).await;
+    assert_eq!(votes.votes, vec![env.members[0].pubkey()]);
+    assert_eq!(votes.reason, "exploit in settlement");
+    assert_eq!(votes.last_paused_at, None);
+
+    // The quorum vote may only relay the pause to the registered accounts
+    assert_program_error(
+        env.emergency_pause(1, "exploit in settlement", &[Pubkey::new_unique()]).await,
+        UpgradeError::PauseAccountsMismatch,
+    );
+}
+
+#[tokio::test]
+async fn test_emergency_pause_votes_lapse() {
+    let mut env = setup(4, 4).await;
+    let authority = env.context.payer.insecure_clone();
+    env.register_pausable(&authority, vec![]).await.unwrap();
+    env.set_emergency_quorum(3).await.unwrap();
+
+    env.emergency_pause(0, "oracle drift", &[]).await.unwrap();
+    env.emergency_pause(1, "oracle drift", &[]).await.unwrap();
+    let opened_at = env.account::<PauseVotes>(pause_votes(&env.program)).await.opened_at;
+
+    env.set_time(opened_at + EMERGENCY_VOTE_WINDOW + 1).await;
+    env.emergency_pause(2, "unrelated alarm", &[]).await.unwrap();
+
+    let votes: PauseVotes = env.account(pause_votes(&env.program)).await;
+    assert_eq!(votes.votes, vec![env.members[2].pubkey()]);
+    assert_eq!(votes.reason, "unrelated alarm");
+}
+
+#[tokio::test]
+async fn test_unregistered_program_cannot_be_paused() {
+    let mut env = setup(4, 4).await;
+    let authority = env.context.payer.insecure_clone();
+    env.register_pausable(&authority, vec![]).await.unwrap();
+    env.program = Pubkey::new_unique();
+
+    assert_program_error(env.emergency_pause(0, "exploit", &[]).await, UpgradeError::NotPausable);
+}
@@ -112,8 +112,11 @@ describe("upgrade-manager", () => {
     // First, we need to add the approver to the members list
     // In a real scenario, this would be one of the existing members
     
+    // Approvals echo the digest the proposal committed to
+    const { approvalDigest } = await program.account.upgradeProposal.fetch(proposal);
+
     const tx = await program.methods
-      .approveUpgrade(proposal)
+      .approveUpgrade(proposal, approvalDigest)
       .accounts({
         approver: authority, // Using authority as approver for test
         multisigConfig,
@@ -0,0 +1,36 @@
+#!/bin/bash
+
+# End-to-end tests against solana-test-validator
+#
+# Requires the Solana CLI (solana-test-validator), Anchor and a Postgres
+# database at E2E_DATABASE_URL. The database is migrated before the run.
+
+set -e
+
+cd "$(dirname "$0")/.."
+
+if [ -z "$E2E_DATABASE_URL" ]; then
+    echo "E2E_DATABASE_URL must point at an empty Postgres database"
+    exit 1
+fi
+
+if ! command -v solana-test-validator &> /dev/null && [ -z "$SOLANA_TEST_VALIDATOR" ]; then
+    echo "solana-test-validator not found. Please install Solana CLI tools"
+    exit 1
+fi
+
+# Build the program unless a build was supplied
+if [ -z "$UPGRADE_MANAGER_SO" ] && [ ! -f target/deploy/upgrade_manager.so ]; then
+    echo "Building Anchor program..."
+    anchor build
+fi
+
+echo "Applying migrations..."
+for migration in migrations/*.sql; do
+    psql "$E2E_DATABASE_URL" -v ON_ERROR_STOP=1 -q -f "$migration"
+done
+
+echo "Running end-to-end tests..."
+cd backend
+cargo test --test database_test -- --ignored
+cargo test --test e2e -- --ignored --test-threads=1
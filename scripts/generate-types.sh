@@ -0,0 +1,34 @@
+#!/bin/bash
+
+# Generate client types from the backend's API structs
+#
+# Writes target/schema/api.schema.json (JSON Schema), api.ts (TypeScript,
+# needs Node) and, when datamodel-code-generator is installed, models.py
+# (pydantic). .github/workflows/api-types.yml publishes them as a build
+# artifact.
+
+set -e
+
+cd "$(dirname "$0")/.."
+
+OUT_DIR="${1:-target/schema}"
+
+cargo run -q --manifest-path backend/Cargo.toml --features schema --bin schema -- "$OUT_DIR"
+
+npx --yes json-schema-to-typescript@15 \
+    --input "$OUT_DIR/api.schema.json" \
+    --output "$OUT_DIR/api.ts" \
+    --unreachableDefinitions \
+    --additionalProperties false
+echo "$OUT_DIR/api.ts"
+
+if command -v datamodel-codegen &> /dev/null; then
+    datamodel-codegen \
+        --input "$OUT_DIR/api.schema.json" \
+        --input-file-type jsonschema \
+        --output-model-type pydantic_v2.BaseModel \
+        --output "$OUT_DIR/models.py"
+    echo "$OUT_DIR/models.py"
+else
+    echo "datamodel-codegen not found, skipping Python models (pip install datamodel-code-generator)"
+fi
@@ -1,38 +0,0 @@
-use goquant_upgrade_service::*;
-
-#[tokio::test]
-async fn test_proposal_flow() {
-    // Test complete proposal flow:
-    // 1. Create proposal
-    // 2. Approve proposal
-    // 3. Wait for timelock
-    // 4. Execute upgrade
-    
-    // This is a placeholder - in real implementation would test against testnet
-    assert!(true);
-}
-
-#[tokio::test]
-async fn test_multisig_approval() {
-    // Test multisig approval mechanism
-    assert!(true);
-}
-
-#[tokio::test]
-async fn test_timelock_enforcement() {
-    // Test that upgrades cannot be executed before timelock expires
-    assert!(true);
-}
-
-#[tokio::test]
-async fn test_migration_process() {
-    // Test account migration process
-    assert!(true);
-}
-
-#[tokio::test]
-async fn test_rollback_mechanism() {
-    // Test rollback functionality
-    assert!(true);
-}
-